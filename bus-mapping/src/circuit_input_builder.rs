@@ -18,6 +18,7 @@ use crate::{
     operation::{self, CallContextField, Operation, RWCounter, StartOp, StorageOp, RW},
     rpc::GethClient,
     state_db::{self, CodeDB, StateDB},
+    wasm::gas::{find_mischarged_step, WasmGasModel, DEFAULT_WASM_GAS_MODEL},
 };
 pub use access::{Access, AccessSet, AccessValue, CodeSource};
 pub use block::{Block, BlockContext};
@@ -25,7 +26,7 @@ pub use call::{Call, CallContext, CallKind};
 use core::fmt::Debug;
 use eth_types::{
     self,
-    evm_types::OpcodeId,
+    evm_types::{OpcodeId, WASM_MAX_STACK_DEPTH},
     geth_types,
     sign_types::{pk_bytes_le, pk_bytes_swap_endianness, SignData},
     Address, GethExecStep, GethExecTrace, ToBigEndian, ToWord, Word, H256, U256,
@@ -87,6 +88,12 @@ pub struct CircuitsParams {
     /// calculated, so the same circuit will not be able to prove different
     /// witnesses.
     pub max_keccak_rows: usize,
+    /// Max number of operands on the WASM operand stack, the traced stack
+    /// overflows are checked against it in the circuit.
+    pub max_wasm_stack_depth: usize,
+    /// Step cost model of WASM execution, the traced cost of every WASM
+    /// instruction must match it.
+    pub wasm_gas_model: &'static dyn WasmGasModel,
 }
 
 impl Default for CircuitsParams {
@@ -106,6 +113,8 @@ impl Default for CircuitsParams {
             max_evm_rows: 0,
             max_keccak_rows: 0,
             max_rlp_rows: 1000,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         }
     }
 }
@@ -453,6 +462,15 @@ impl<'a> CircuitInputBuilder {
         geth_trace: &GethExecTrace,
         is_last_tx: bool,
     ) -> Result<(), Error> {
+        if let Some(step) = find_mischarged_step(
+            self.block.circuits_params.wasm_gas_model,
+            &geth_trace.struct_logs,
+        ) {
+            return Err(Error::UnexpectedExecStepError(
+                "wasm step cost differs from the gas model",
+                Box::new(step.clone()),
+            ));
+        }
         let mut tx = self.new_tx(eth_tx, !geth_trace.failed)?;
 
         // Sanity check for transaction L1 fee.
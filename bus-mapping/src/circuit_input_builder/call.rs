@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use super::CodeSource;
 use crate::{exec_trace::OperationRef, Error};
 use eth_types::{
@@ -143,6 +145,8 @@ pub struct CallContext {
     pub memory: Memory,
     /// return data buffer
     pub return_data: Vec<u8>,
+    /// indexes of the data segments dropped by `data.drop`
+    pub dropped_data_segments: HashSet<u64>,
 }
 
 impl CallContext {
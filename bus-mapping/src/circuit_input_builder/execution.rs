@@ -57,6 +57,8 @@ pub struct ExecStep {
     pub max_stack_height: u32,
     ///
     pub num_locals: u32,
+    /// Immediate params of the opcode
+    pub params: Vec<u64>,
 }
 
 impl ExecStep {
@@ -87,6 +89,7 @@ impl ExecStep {
             function_index: 0,
             max_stack_height: 0,
             num_locals: 0,
+            params: step.params.clone(),
         }
     }
 
@@ -125,6 +128,7 @@ impl Default for ExecStep {
             function_index: 0,
             num_locals: 0,
             max_stack_height: 0,
+            params: Vec::new(),
         }
     }
 }
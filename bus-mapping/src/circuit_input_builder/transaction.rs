@@ -176,6 +176,7 @@ impl TransactionContext {
             call_data,
             memory: Memory::default(),
             return_data: vec![],
+            dropped_data_segments: Default::default(),
         });
     }
 
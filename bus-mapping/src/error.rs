@@ -6,8 +6,10 @@ use ethers_providers::ProviderError;
 use std::error::Error as StdError;
 
 use crate::geth_errors::{
-    GETH_ERR_GAS_UINT_OVERFLOW, GETH_ERR_OUT_OF_GAS, GETH_ERR_STACK_OVERFLOW,
-    GETH_ERR_STACK_UNDERFLOW,
+    GETH_ERR_GAS_UINT_OVERFLOW, GETH_ERR_INDIRECT_CALL_TYPE_MISMATCH,
+    GETH_ERR_INTEGER_DIVIDE_BY_ZERO, GETH_ERR_INTEGER_OVERFLOW, GETH_ERR_MEMORY_OUT_OF_BOUNDS,
+    GETH_ERR_OUT_OF_GAS, GETH_ERR_STACK_OVERFLOW, GETH_ERR_STACK_UNDERFLOW,
+    GETH_ERR_UNDEFINED_ELEMENT,
 };
 
 /// Error type for any BusMapping related failure.
@@ -173,6 +175,15 @@ pub enum ExecError {
     PrecompileFailed,
     /// For CREATE, CREATE2
     NonceUintOverflow(NonceUintOverflowError),
+    /// For wasm loads and stores accessing bytes past the current memory size
+    MemoryOutOfBounds,
+    /// For wasm div and rem with a zero divisor
+    DivisionByZero,
+    /// For wasm signed div of the minimal integer by -1
+    IntegerOverflow,
+    /// For wasm call_indirect of an undefined table element or of a function whose type differs
+    /// from the type immediate
+    CallIndirect,
 }
 
 // TODO: Move to impl block.
@@ -209,6 +220,16 @@ pub(crate) fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
         ExecError::StackOverflow
     } else if error.starts_with(GETH_ERR_STACK_UNDERFLOW) {
         ExecError::StackUnderflow
+    } else if error.starts_with(GETH_ERR_MEMORY_OUT_OF_BOUNDS) {
+        ExecError::MemoryOutOfBounds
+    } else if error.starts_with(GETH_ERR_INTEGER_DIVIDE_BY_ZERO) {
+        ExecError::DivisionByZero
+    } else if error.starts_with(GETH_ERR_INTEGER_OVERFLOW) {
+        ExecError::IntegerOverflow
+    } else if error.starts_with(GETH_ERR_UNDEFINED_ELEMENT)
+        || error.starts_with(GETH_ERR_INDIRECT_CALL_TYPE_MISMATCH)
+    {
+        ExecError::CallIndirect
     } else {
         panic!("Unknown GethExecStep.error: {}", error);
     }
@@ -6,3 +6,13 @@ pub const GETH_ERR_STACK_UNDERFLOW: &str = "stack underflow";
 pub const GETH_ERR_OUT_OF_GAS: &str = "out of gas";
 /// Geth error message for gas uint64 overflow
 pub const GETH_ERR_GAS_UINT_OVERFLOW: &str = "gas uint64 overflow";
+/// Wasm trap message for out of bounds memory access
+pub const GETH_ERR_MEMORY_OUT_OF_BOUNDS: &str = "out of bounds memory access";
+/// Wasm trap message for integer division (or remainder) by zero
+pub const GETH_ERR_INTEGER_DIVIDE_BY_ZERO: &str = "integer divide by zero";
+/// Wasm trap message for signed integer division overflow
+pub const GETH_ERR_INTEGER_OVERFLOW: &str = "integer overflow";
+/// Wasm trap message for a `call_indirect` of an element past the table elements
+pub const GETH_ERR_UNDEFINED_ELEMENT: &str = "undefined element";
+/// Wasm trap message for a `call_indirect` of a function of another type than the immediate
+pub const GETH_ERR_INDIRECT_CALL_TYPE_MISMATCH: &str = "indirect call type mismatch";
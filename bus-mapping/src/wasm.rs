@@ -1,5 +1,6 @@
 //! Evm types needed for parsing instruction sets as well
 
+pub mod gas;
 pub(crate) mod opcodes;
 
 pub use eth_types::evm_types::opcode_ids::OpcodeId;
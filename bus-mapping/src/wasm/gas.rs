@@ -0,0 +1,156 @@
+//! Step cost model of WASM execution.
+
+use eth_types::{evm_types::OpcodeId, GethExecStep};
+use std::fmt::Debug;
+
+/// Cost charged for every WASM opcode by the default (flat) cost model, the tracer running the
+/// txs doesn't charge WASM instructions.
+pub const WASM_FLAT_OPCODE_COST: u64 = 0;
+
+/// Cost model of the tracer running the txs.
+pub const DEFAULT_WASM_GAS_MODEL: FlatGasModel = FlatGasModel {
+    cost: WASM_FLAT_OPCODE_COST,
+};
+
+/// Per-opcode step cost model of WASM execution. It has to match the costs charged by the tracer
+/// running the txs: the traced costs are only checked against it.
+pub trait WasmGasModel: Debug + Sync {
+    /// Gas charged for executing a single `opcode` step.
+    fn opcode_cost(&self, opcode: OpcodeId) -> u64;
+}
+
+/// Charges the same `cost` for every opcode.
+#[derive(Clone, Copy, Debug)]
+pub struct FlatGasModel {
+    pub cost: u64,
+}
+
+impl Default for FlatGasModel {
+    fn default() -> Self {
+        DEFAULT_WASM_GAS_MODEL
+    }
+}
+
+impl WasmGasModel for FlatGasModel {
+    fn opcode_cost(&self, _opcode: OpcodeId) -> u64 {
+        self.cost
+    }
+}
+
+/// Returns true if `opcode` is a WASM instruction charged by the step cost model, host calls of
+/// EVM opcodes only pay their EVM gas.
+pub fn is_wasm_instruction(opcode: OpcodeId) -> bool {
+    !opcode.is_evm_call()
+}
+
+/// Returns the first executed WASM instruction of the `steps` whose traced cost differs from its
+/// `model` cost.
+pub fn find_mischarged_step<'a>(
+    model: &dyn WasmGasModel,
+    steps: &'a [GethExecStep],
+) -> Option<&'a GethExecStep> {
+    steps.iter().find(|step| {
+        step.error.is_none()
+            && is_wasm_instruction(step.op)
+            && step.gas_cost.0 != model.opcode_cost(step.op)
+    })
+}
+
+#[cfg(test)]
+mod gas_tests {
+    use eth_types::{
+        evm_types::{Gas, GasCost, Memory, OpcodeId, ProgramCounter, Stack, Storage},
+        GethExecStep,
+    };
+
+    use crate::wasm::gas::{
+        find_mischarged_step, FlatGasModel, WasmGasModel, DEFAULT_WASM_GAS_MODEL,
+        WASM_FLAT_OPCODE_COST,
+    };
+
+    const FUNC_BODY: [OpcodeId; 5] = [
+        OpcodeId::I32Const,
+        OpcodeId::I32Const,
+        OpcodeId::I32Add,
+        OpcodeId::Drop,
+        OpcodeId::End,
+    ];
+
+    #[derive(Debug)]
+    struct ArithmeticHeavyGasModel;
+
+    impl WasmGasModel for ArithmeticHeavyGasModel {
+        fn opcode_cost(&self, opcode: OpcodeId) -> u64 {
+            match opcode {
+                OpcodeId::I32Add => 10,
+                _ => 1,
+            }
+        }
+    }
+
+    /// root call steps of `opcodes` traced with the `model` costs
+    fn traced_steps(
+        opcodes: impl IntoIterator<Item = OpcodeId>,
+        model: &dyn WasmGasModel,
+    ) -> Vec<GethExecStep> {
+        opcodes
+            .into_iter()
+            .map(|op| GethExecStep {
+                pc: ProgramCounter(0),
+                op_family: None,
+                params: vec![],
+                op,
+                gas: Gas(100),
+                refund: Gas(0),
+                gas_cost: GasCost(model.opcode_cost(op)),
+                depth: 1,
+                error: None,
+                stack: Stack::new(),
+                storage: Storage(Default::default()),
+                memory: vec![],
+                global_memory: Memory::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flat_model_is_the_default() {
+        let model = FlatGasModel::default();
+        assert_eq!(model.opcode_cost(OpcodeId::I32Add), WASM_FLAT_OPCODE_COST);
+        assert_eq!(model.opcode_cost(OpcodeId::End), WASM_FLAT_OPCODE_COST);
+    }
+
+    #[test]
+    fn steps_charged_by_the_model_are_accepted() {
+        let steps = traced_steps(FUNC_BODY, &DEFAULT_WASM_GAS_MODEL);
+        assert!(find_mischarged_step(&DEFAULT_WASM_GAS_MODEL, &steps).is_none());
+    }
+
+    #[test]
+    fn step_charged_another_cost_is_found() {
+        let mut steps = traced_steps(FUNC_BODY, &DEFAULT_WASM_GAS_MODEL);
+        steps[2].gas_cost = GasCost(WASM_FLAT_OPCODE_COST + 1);
+        let step = find_mischarged_step(&DEFAULT_WASM_GAS_MODEL, &steps);
+        assert_eq!(step.map(|step| step.op), Some(OpcodeId::I32Add));
+    }
+
+    #[test]
+    fn host_calls_and_failed_steps_are_not_checked() {
+        let mut steps = traced_steps(
+            [OpcodeId::I32Const, OpcodeId::ADDRESS, OpcodeId::Drop],
+            &DEFAULT_WASM_GAS_MODEL,
+        );
+        steps[1].gas_cost = GasCost::QUICK;
+        steps[2].gas_cost = GasCost(WASM_FLAT_OPCODE_COST + 1);
+        steps[2].error = Some("out of gas".to_string());
+        assert!(find_mischarged_step(&DEFAULT_WASM_GAS_MODEL, &steps).is_none());
+    }
+
+    #[test]
+    fn cost_model_is_pluggable() {
+        let steps = traced_steps(FUNC_BODY, &ArithmeticHeavyGasModel);
+        assert!(find_mischarged_step(&ArithmeticHeavyGasModel, &steps).is_none());
+        let step = find_mischarged_step(&DEFAULT_WASM_GAS_MODEL, &steps);
+        assert_eq!(step.map(|step| step.op), Some(OpcodeId::I32Const));
+    }
+}
@@ -14,6 +14,7 @@ use callvalue::Callvalue;
 use codecopy::Codecopy;
 use codesize::Codesize;
 use error_invalid_jump::InvalidJump;
+use error_memory_out_of_bounds::ErrorMemoryOutOfBounds;
 use error_oog_call::OOGCall;
 use error_oog_log::ErrorOOGLog;
 use error_oog_sload_sstore::OOGSloadSstore;
@@ -36,7 +37,12 @@ use stop::Stop;
 use wasm_break::WasmBreakOpcode;
 use wasm_call::WasmCallOpcode;
 use wasm_global::WasmGlobalOpcode;
+use wasm_load::WasmLoadOpcode;
 use wasm_local::WasmLocalOpcode;
+use wasm_memory_op::WasmMemoryOpcode;
+use wasm_memory_init::WasmMemoryInitOpcode;
+use wasm_memory_bulk::WasmMemoryBulkOpcode;
+use wasm_store::WasmStoreOpcode;
 
 use crate::{
     circuit_input_builder::{CircuitInputStateRef, ExecStep},
@@ -102,6 +108,7 @@ mod error_codestore;
 mod error_contract_address_collision;
 mod error_invalid_creation_code;
 mod error_invalid_jump;
+mod error_memory_out_of_bounds;
 mod error_oog_account_access;
 mod error_oog_call;
 mod error_oog_dynamic_memory;
@@ -119,7 +126,12 @@ pub use callop::tests::PrecompileCallArgs;
 
 mod wasm_call;
 mod wasm_global;
+mod wasm_load;
 mod wasm_local;
+mod wasm_memory_op;
+mod wasm_memory_init;
+mod wasm_memory_bulk;
+mod wasm_store;
 mod wasm_break;
 
 /// Generic opcode trait which defines the logic of the
@@ -201,10 +213,10 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         // OpcodeId::I64Store8 => Dummy::gen_associated_ops,
         // OpcodeId::I64Store16 => Dummy::gen_associated_ops,
         // OpcodeId::I64Store32 => Dummy::gen_associated_ops,
-        // OpcodeId::CurrentMemory => Dummy::gen_associated_ops,
-        // OpcodeId::GrowMemory => Dummy::gen_associated_ops,
         OpcodeId::I32Const |
-        OpcodeId::I64Const => StackOnlyOpcode::<0, 1>::gen_associated_ops,
+        OpcodeId::I64Const |
+        OpcodeId::F32Const |
+        OpcodeId::F64Const => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         // WASM binary opcodes
 
         OpcodeId::I32Eq |
@@ -264,7 +276,17 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::I64Rotl |
         OpcodeId::I64Rotr => StackOnlyOpcode::<2, 1>::gen_associated_ops,
 
-        // WASM load store like opcodes (like unary).
+        // WASM float binary opcodes
+        OpcodeId::F32Add |
+        OpcodeId::F32Sub |
+        OpcodeId::F32Mul |
+        OpcodeId::F32Div |
+        OpcodeId::F64Add |
+        OpcodeId::F64Sub |
+        OpcodeId::F64Mul |
+        OpcodeId::F64Div => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+
+        // WASM load opcodes
         OpcodeId::I32Load |
         OpcodeId::I32Load8S |
         OpcodeId::I32Load8U |
@@ -276,12 +298,17 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::I64Load16S |
         OpcodeId::I64Load16U |
         OpcodeId::I64Load32S |
-        OpcodeId::I64Load32U => StackOnlyOpcode::<1, 1>::gen_associated_ops,
+        OpcodeId::I64Load32U => WasmLoadOpcode::gen_associated_ops,
 
         // WASM unary opcodes
         OpcodeId::I64ExtendUI32 |
         OpcodeId::I64ExtendSI32 |
         OpcodeId::I32WrapI64 |
+        OpcodeId::I32Extend8S |
+        OpcodeId::I32Extend16S |
+        OpcodeId::I64Extend8S |
+        OpcodeId::I64Extend16S |
+        OpcodeId::I64Extend32S |
         OpcodeId::I32Ctz |
         OpcodeId::I64Ctz |
         OpcodeId::I32Clz |
@@ -296,6 +323,15 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SetLocal |
         OpcodeId::GetLocal |
         OpcodeId::TeeLocal => WasmLocalOpcode::gen_associated_ops,
+        // WASM memory opcodes
+        OpcodeId::CurrentMemory |
+        OpcodeId::GrowMemory => WasmMemoryOpcode::gen_associated_ops,
+        // WASM passive data segment opcodes
+        OpcodeId::MemoryInit |
+        OpcodeId::DataDrop => WasmMemoryInitOpcode::gen_associated_ops,
+        // WASM bulk memory opcodes
+        OpcodeId::MemoryCopy |
+        OpcodeId::MemoryFill => WasmMemoryBulkOpcode::gen_associated_ops,
         // call opcodes
         OpcodeId::Call |
         OpcodeId::CallIndirect => WasmCallOpcode::gen_associated_ops,
@@ -306,16 +342,17 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::BrTable => WasmBreakOpcode::gen_associated_ops,
 
         // WASM select like opcodes.
-        OpcodeId::Select => StackOnlyOpcode::<3, 1>::gen_associated_ops,
+        OpcodeId::Select |
+        OpcodeId::SelectT => StackOnlyOpcode::<3, 1>::gen_associated_ops,
 
-        // WASM store like ops.
+        // WASM store opcodes
         OpcodeId::I32Store |
         OpcodeId::I32Store8 |
         OpcodeId::I32Store16 |
         OpcodeId::I64Store |
         OpcodeId::I64Store8 |
         OpcodeId::I64Store16 |
-        OpcodeId::I64Store32 => StackOnlyOpcode::<2, 0>::gen_associated_ops,
+        OpcodeId::I64Store32 => WasmStoreOpcode::gen_associated_ops,
 
         // WASM test opcodes
         OpcodeId::I32Eqz | OpcodeId::I64Eqz => StackOnlyOpcode::<1, 1>::gen_associated_ops,
@@ -460,6 +497,11 @@ fn fn_gen_error_state_associated_ops(
             Some(Create::<true>::gen_associated_ops)
         }
         ExecError::InvalidCreationCode => Some(ErrorCreationCode::gen_associated_ops),
+        ExecError::MemoryOutOfBounds => Some(ErrorMemoryOutOfBounds::gen_associated_ops),
+        ExecError::DivisionByZero | ExecError::IntegerOverflow => {
+            Some(StackOnlyOpcode::<2, 0, true>::gen_associated_ops)
+        }
+        ExecError::CallIndirect => Some(StackOnlyOpcode::<1, 0, true>::gen_associated_ops),
         // more future errors place here
         _ => {
             evm_unimplemented!("TODO: error state {:?} not implemented", error);
@@ -0,0 +1,71 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::{Opcode, OpcodeId},
+    Error,
+};
+use eth_types::{GethExecStep, StackWord};
+
+use super::wasm_memory_init::dropped_data_segment_global_index;
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorMemoryOutOfBounds;
+
+impl Opcode for ErrorMemoryOutOfBounds {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::MemoryOutOfBounds);
+
+        // stores pop the value and then the address, loads pop only the address, bulk memory
+        // opcodes and `memory.init` pop all of their three operands
+        let is_store = matches!(
+            geth_step.op,
+            OpcodeId::I32Store
+                | OpcodeId::I32Store8
+                | OpcodeId::I32Store16
+                | OpcodeId::I64Store
+                | OpcodeId::I64Store8
+                | OpcodeId::I64Store16
+                | OpcodeId::I64Store32
+        );
+        let is_bulk = matches!(
+            geth_step.op,
+            OpcodeId::MemoryCopy | OpcodeId::MemoryFill | OpcodeId::MemoryInit
+        );
+        let stack_reads = if is_bulk {
+            3
+        } else if is_store {
+            2
+        } else {
+            1
+        };
+        for i in 0..stack_reads {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+        // `memory.init` also reads whether its segment was dropped
+        if geth_step.op == OpcodeId::MemoryInit {
+            let data_index = geth_step.params.first().copied().unwrap_or_default();
+            let is_dropped = state
+                .call_ctx()?
+                .dropped_data_segments
+                .contains(&data_index);
+            state.global_read(
+                &mut exec_step,
+                dropped_data_segment_global_index(data_index)?,
+                StackWord::from(is_dropped as u64),
+            )?;
+        }
+
+        // `IsSuccess` call context operation is added in handle_return
+        state.handle_return(&mut exec_step, geth_steps, true)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -1,8 +1,9 @@
-use eth_types::{GethExecStep};
+use eth_types::{GethExecStep, ToU256};
 use eth_types::evm_types::OpcodeId;
 
 use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
 use crate::Error;
+use crate::operation::CallContextField;
 
 use super::Opcode;
 
@@ -16,21 +17,33 @@ impl Opcode for WasmBreakOpcode {
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
         let current_step = &geth_steps[0];
-        let _next_step = &geth_steps[1];
+        let next_step = &geth_steps[1];
 
-        let exec_step = state.new_step(current_step)?;
+        let mut exec_step = state.new_step(current_step)?;
 
-        match current_step.op {
-            OpcodeId::Return => {
-            }
-            OpcodeId::Br => {
-            }
+        let is_taken = match current_step.op {
+            OpcodeId::Return | OpcodeId::Br | OpcodeId::BrTable => true,
             OpcodeId::BrIf => {
-            }
-            OpcodeId::BrTable => {
+                // the condition is popped whether the branch is taken or not
+                let condition = current_step.stack.nth_last(0)?;
+                state.stack_read(
+                    &mut exec_step,
+                    current_step.stack.nth_last_filled(0),
+                    condition,
+                )?;
+                !condition.is_zero()
             }
             _ => unreachable!("not supported opcode: {:?}", current_step.op)
         };
+        // the branch target, not recorded if `br_if` falls through
+        if is_taken {
+            state.call_context_write(
+                &mut exec_step,
+                state.call()?.call_id,
+                CallContextField::ProgramCounter,
+                next_step.pc.0.to_u256(),
+            );
+        }
 
         Ok(vec![exec_step])
     }
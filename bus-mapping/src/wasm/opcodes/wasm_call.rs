@@ -39,7 +39,20 @@ impl Opcode for WasmCallOpcode {
                 );
             }
             OpcodeId::CallIndirect => {
-
+                // the callee is resolved from the table elements of the module, so only the
+                // popped element index and the return pc are recorded
+                state.stack_read(
+                    &mut exec_step,
+                    current_step.stack.nth_last_filled(0),
+                    current_step.stack.nth_last(0)?,
+                )?;
+                let pc = next_step.pc;
+                state.call_context_write(
+                    &mut exec_step,
+                    state.call()?.call_id,
+                    CallContextField::ProgramCounter,
+                    pc.0.to_u256(),
+                );
             }
             _ => unreachable!("not supported opcode: {:?}", current_step.op)
         };
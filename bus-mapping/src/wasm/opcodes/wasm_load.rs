@@ -0,0 +1,52 @@
+use eth_types::evm_types::{MemoryAddress, OpcodeId};
+use eth_types::GethExecStep;
+
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::Error;
+
+use super::Opcode;
+
+/// Returns the amount of bytes read from memory by the load opcode.
+pub(crate) fn wasm_load_width(op: OpcodeId) -> usize {
+    match op {
+        OpcodeId::I32Load8S | OpcodeId::I32Load8U | OpcodeId::I64Load8S | OpcodeId::I64Load8U => 1,
+        OpcodeId::I32Load16S | OpcodeId::I32Load16U | OpcodeId::I64Load16S | OpcodeId::I64Load16U => 2,
+        OpcodeId::I32Load | OpcodeId::I64Load32S | OpcodeId::I64Load32U => 4,
+        OpcodeId::I64Load => 8,
+        _ => unreachable!("not supported opcode: {:?}", op),
+    }
+}
+
+///
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WasmLoadOpcode;
+
+impl Opcode for WasmLoadOpcode {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let current_step = &geth_steps[0];
+        let next_step = &geth_steps[1];
+
+        let mut exec_step = state.new_step(current_step)?;
+
+        // static offset immediate of the memarg
+        let mem_offset = current_step.params.first().copied().unwrap_or_default();
+
+        let address = current_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(0), address)?;
+        let effective_address = MemoryAddress(address.as_u64() as usize + mem_offset as usize);
+
+        // loaded bytes are the lowest bytes of the result (sign extension only affects the upper ones)
+        let value = next_step.stack.nth_last(0)?;
+        let bytes = value.as_u64().to_le_bytes();
+        for (i, byte) in bytes.iter().take(wasm_load_width(current_step.op)).enumerate() {
+            state.memory_read(&mut exec_step, effective_address.map(|a| a + i), *byte)?;
+        }
+
+        state.stack_write(&mut exec_step, next_step.stack.nth_last_filled(0), value)?;
+
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,123 @@
+use eth_types::evm_types::{MemoryAddress, OpcodeId};
+use eth_types::GethExecStep;
+
+use crate::circuit_input_builder::{CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash};
+use crate::operation::{MemoryOp, RW};
+use crate::Error;
+
+use super::Opcode;
+
+/// Returns true if `memory.copy` has to go from the last byte to the first one, i.e. the
+/// destination range starts inside the source range (a forward copy would read bytes it has
+/// already overwritten).
+pub(crate) fn is_backward_copy(dst: u64, src: u64, length: u64) -> bool {
+    src < dst && dst < src + length
+}
+
+/// Reads `bytes` from the memory of `src_id` at `src` and writes them to the memory of `dst_id`
+/// at `dst`, one byte after the other, and records it as a memory to memory copy event.
+fn push_memory_copy(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    (src_id, src): (usize, u64),
+    (dst_id, dst): (usize, u64),
+    bytes: &[u8],
+) {
+    let rw_counter_start = state.block_ctx.rwc;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let i = i as u64;
+        let (src_addr, dst_addr) = (MemoryAddress((src + i) as usize), MemoryAddress((dst + i) as usize));
+        state.push_op(exec_step, RW::READ, MemoryOp::new(src_id, src_addr, byte));
+        state.push_op(exec_step, RW::WRITE, MemoryOp::new(dst_id, dst_addr, byte));
+    }
+    let copy_event = CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(src_id),
+        src_addr: src,
+        src_addr_end: src + bytes.len() as u64,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(dst_id),
+        dst_addr: dst,
+        log_id: None,
+        rw_counter_start,
+        bytes: bytes.iter().map(|&byte| (byte, false)).collect(),
+    };
+    state.push_copy(exec_step, copy_event);
+}
+
+/// Handles `memory.copy` and `memory.fill`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WasmMemoryBulkOpcode;
+
+impl Opcode for WasmMemoryBulkOpcode {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let current_step = &geth_steps[0];
+        let next_step = &geth_steps[1];
+
+        let mut exec_step = state.new_step(current_step)?;
+
+        let length = current_step.stack.nth_last(0)?;
+        let src_or_value = current_step.stack.nth_last(1)?;
+        let dst = current_step.stack.nth_last(2)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(0), length)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(1), src_or_value)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(2), dst)?;
+        let (length, dst) = (length.as_u64(), dst.as_u64());
+        let call_id = state.call()?.call_id;
+
+        match current_step.op {
+            OpcodeId::MemoryCopy if length > 0 => {
+                let src = src_or_value.as_u64();
+                // copied bytes are what the destination holds after the step
+                let bytes = next_step.global_memory.read_chunk(
+                    MemoryAddress(dst as usize),
+                    MemoryAddress(length as usize),
+                );
+                if is_backward_copy(dst, src, length) {
+                    // The copy circuit only walks the addresses up, so the source goes through a
+                    // scratch memory first. It is keyed by the step's rw counter, which is never
+                    // the id of a call since this step doesn't begin one.
+                    let scratch_id = exec_step.rwc.0;
+                    let scratch = (scratch_id, src);
+                    push_memory_copy(state, &mut exec_step, (call_id, src), scratch, &bytes);
+                    push_memory_copy(state, &mut exec_step, scratch, (call_id, dst), &bytes);
+                } else {
+                    push_memory_copy(state, &mut exec_step, (call_id, src), (call_id, dst), &bytes);
+                }
+            }
+            OpcodeId::MemoryFill if length > 0 => {
+                // only the lowest byte of the i32 value is written, the first one directly and the
+                // others by copying every byte to the next address
+                let byte = src_or_value.as_u64() as u8;
+                state.memory_write(&mut exec_step, MemoryAddress(dst as usize), byte)?;
+                if length > 1 {
+                    let bytes = vec![byte; (length - 1) as usize];
+                    let (src, dst) = ((call_id, dst), (call_id, dst + 1));
+                    push_memory_copy(state, &mut exec_step, src, dst, &bytes);
+                }
+            }
+            OpcodeId::MemoryCopy | OpcodeId::MemoryFill => {}
+            _ => unreachable!("not supported opcode: {:?}", current_step.op)
+        };
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod wasm_memory_bulk_tests {
+    use super::is_backward_copy;
+
+    #[test]
+    fn backward_copy_only_for_overlapping_higher_destination() {
+        assert!(!is_backward_copy(0, 10, 5));
+        assert!(!is_backward_copy(10, 0, 5));
+        assert!(!is_backward_copy(10, 5, 5));
+        assert!(!is_backward_copy(5, 8, 10));
+        assert!(is_backward_copy(8, 5, 10));
+        assert!(!is_backward_copy(8, 5, 0));
+    }
+}
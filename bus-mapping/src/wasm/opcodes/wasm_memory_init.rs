@@ -0,0 +1,188 @@
+use eth_types::{Bytecode, GethExecStep, StackWord};
+use eth_types::evm_types::{OpcodeId, WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX};
+
+use crate::circuit_input_builder::{CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash};
+use crate::Error;
+
+use super::Opcode;
+
+/// Id of the data section in the wasm binary.
+const WASM_DATA_SECTION_ID: u8 = 11;
+/// Magic and version prefix of the wasm binary.
+const WASM_PREAMBLE_LEN: usize = 8;
+
+fn read_leb(code: &[u8], offset: &mut usize) -> Result<u64, Error> {
+    let mut result = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *code.get(*offset)
+            .ok_or(Error::InternalError("wasm binary is truncated"))?;
+        *offset += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return Err(Error::InternalError("malformed leb128 in wasm binary"));
+        }
+    }
+}
+
+/// skips `i32.const <s32> end` of an active segment
+fn skip_const_expr(code: &[u8], offset: &mut usize) -> Result<(), Error> {
+    *offset += 1;
+    read_leb(code, offset)?;
+    *offset += 1;
+    Ok(())
+}
+
+/// Returns (offset of the first byte in the binary, length) of the data segment `data_index`, the
+/// length of an active segment is 0 as it is dropped once the module is instantiated.
+pub(crate) fn wasm_data_segment_span(code: &[u8], data_index: u64) -> Result<(u64, u64), Error> {
+    let mut offset = WASM_PREAMBLE_LEN;
+    while offset < code.len() {
+        let section_id = code[offset];
+        offset += 1;
+        let section_len = read_leb(code, &mut offset)? as usize;
+        if section_id != WASM_DATA_SECTION_ID {
+            offset += section_len;
+            continue;
+        }
+        let count = read_leb(code, &mut offset)?;
+        for index in 0..count {
+            let is_passive = code.get(offset).copied() == Some(1);
+            match code.get(offset).copied() {
+                Some(0) => {
+                    offset += 1;
+                    skip_const_expr(code, &mut offset)?;
+                }
+                Some(1) => offset += 1,
+                Some(2) => {
+                    offset += 1;
+                    read_leb(code, &mut offset)?;
+                    skip_const_expr(code, &mut offset)?;
+                }
+                _ => return Err(Error::InternalError("unknown data segment type")),
+            }
+            let len = read_leb(code, &mut offset)?;
+            if index == data_index {
+                return Ok((offset as u64, if is_passive { len } else { 0 }));
+            }
+            offset += len as usize;
+        }
+        break;
+    }
+    Err(Error::InternalError("data segment not found"))
+}
+
+/// Global index the dropped flag of the data segment `data_index` is kept at in the rw table.
+pub(crate) fn dropped_data_segment_global_index(data_index: u64) -> Result<u32, Error> {
+    u32::try_from(data_index)
+        .ok()
+        .and_then(|data_index| WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX.checked_add(data_index))
+        .ok_or(Error::InternalError("data index is out of range"))
+}
+
+/// Handles `memory.init` (copies a slice of a passive data segment into the linear memory) and
+/// `data.drop`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WasmMemoryInitOpcode;
+
+impl Opcode for WasmMemoryInitOpcode {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let current_step = &geth_steps[0];
+
+        let mut exec_step = state.new_step(current_step)?;
+        let data_index = current_step.params.first().copied().unwrap_or_default();
+
+        match current_step.op {
+            OpcodeId::MemoryInit => {
+                let length = current_step.stack.nth_last(0)?;
+                let src_offset = current_step.stack.nth_last(1)?;
+                let dst_offset = current_step.stack.nth_last(2)?;
+                state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(0), length)?;
+                state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(1), src_offset)?;
+                state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(2), dst_offset)?;
+
+                // a dropped segment behaves as an empty one
+                let is_dropped = state.call_ctx()?.dropped_data_segments.contains(&data_index);
+                state.global_read(
+                    &mut exec_step,
+                    dropped_data_segment_global_index(data_index)?,
+                    StackWord::from(is_dropped as u64),
+                )?;
+
+                let code_hash = state.call()?.code_hash;
+                let code = state.code(code_hash)?;
+                let (segment_offset, mut segment_len) = wasm_data_segment_span(&code, data_index)?;
+                if is_dropped {
+                    segment_len = 0;
+                }
+
+                let rw_counter_start = state.block_ctx.rwc;
+                let bytecode: Bytecode = code.into();
+                let src_addr = segment_offset + src_offset.as_u64();
+                let src_addr_end = segment_offset + segment_len;
+                let dst_addr = dst_offset.as_u64();
+                let copy_steps = state.gen_copy_steps_for_bytecode(
+                    &mut exec_step,
+                    &bytecode,
+                    src_addr,
+                    dst_addr,
+                    src_addr_end,
+                    length.as_u64(),
+                )?;
+                let copy_event = CopyEvent {
+                    src_type: CopyDataType::Bytecode,
+                    src_id: NumberOrHash::Hash(code_hash),
+                    src_addr,
+                    src_addr_end,
+                    dst_type: CopyDataType::Memory,
+                    dst_id: NumberOrHash::Number(state.call()?.call_id),
+                    dst_addr,
+                    log_id: None,
+                    rw_counter_start,
+                    bytes: copy_steps,
+                };
+                state.push_copy(&mut exec_step, copy_event);
+            }
+            OpcodeId::DataDrop => {
+                state.global_write(
+                    &mut exec_step,
+                    dropped_data_segment_global_index(data_index)?,
+                    StackWord::one(),
+                )?;
+                state.call_ctx_mut()?.dropped_data_segments.insert(data_index);
+            }
+            _ => unreachable!("not supported opcode: {:?}", current_step.op)
+        };
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod wasm_memory_init_tests {
+    use eth_types::{bytecode, bytecode::WasmBinaryBytecode, Bytecode};
+
+    use super::wasm_data_segment_span;
+
+    #[test]
+    fn data_segment_span_found() {
+        let mut code = bytecode! {
+            Drop
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        code.add_passive_data(vec![4, 5]);
+        let wasm_bin = code.wasm_binary();
+        let (offset, len) = wasm_data_segment_span(&wasm_bin, 1).unwrap();
+        assert_eq!(len, 2);
+        assert_eq!(&wasm_bin[offset as usize..(offset + len) as usize], &[4, 5]);
+        assert!(wasm_data_segment_span(&wasm_bin, 2).is_err());
+        assert!(wasm_data_segment_span(&Bytecode::default().wasm_binary(), 0).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use eth_types::evm_types::OpcodeId;
+use eth_types::{GethExecStep, StackWord, Word};
+
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::operation::CallContextField;
+use crate::Error;
+
+use super::Opcode;
+
+/// Result pushed by `memory.grow` when the memory can't be grown.
+const MEMORY_GROW_FAILURE: u64 = 0xffffffff;
+/// WebAssembly page size in bytes.
+const WASM_PAGE_SIZE: u64 = 0x10000;
+
+///
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WasmMemoryOpcode;
+
+impl Opcode for WasmMemoryOpcode {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let current_step = &geth_steps[0];
+        let next_step = &geth_steps[1];
+
+        let mut exec_step = state.new_step(current_step)?;
+
+        match current_step.op {
+            OpcodeId::CurrentMemory => {
+                let pages = next_step.stack.nth_last(0)?;
+                state.stack_write(&mut exec_step, next_step.stack.nth_last_filled(0), pages)?;
+            },
+            OpcodeId::GrowMemory => {
+                let delta = current_step.stack.nth_last(0)?;
+                state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(0), delta)?;
+                let result = next_step.stack.nth_last(0)?;
+                let pages = if result == StackWord::from(MEMORY_GROW_FAILURE) {
+                    (exec_step.memory_size as u64 + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE
+                } else {
+                    result.as_u64() + delta.as_u64()
+                };
+                // the memory metadata row keeps the page count after the grow
+                state.call_context_write(
+                    &mut exec_step,
+                    state.call()?.call_id,
+                    CallContextField::MemorySize,
+                    Word::from(pages),
+                );
+                state.stack_write(&mut exec_step, next_step.stack.nth_last_filled(0), result)?;
+            },
+            _ => unreachable!("not supported opcode: {:?}", current_step.op)
+        };
+
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,50 @@
+use eth_types::evm_types::{MemoryAddress, OpcodeId};
+use eth_types::GethExecStep;
+
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::Error;
+
+use super::Opcode;
+
+/// Returns the amount of bytes written to memory by the store opcode.
+pub(crate) fn wasm_store_width(op: OpcodeId) -> usize {
+    match op {
+        OpcodeId::I32Store8 | OpcodeId::I64Store8 => 1,
+        OpcodeId::I32Store16 | OpcodeId::I64Store16 => 2,
+        OpcodeId::I32Store | OpcodeId::I64Store32 => 4,
+        OpcodeId::I64Store => 8,
+        _ => unreachable!("not supported opcode: {:?}", op),
+    }
+}
+
+///
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WasmStoreOpcode;
+
+impl Opcode for WasmStoreOpcode {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let current_step = &geth_steps[0];
+
+        let mut exec_step = state.new_step(current_step)?;
+
+        // static offset immediate of the memarg
+        let mem_offset = current_step.params.first().copied().unwrap_or_default();
+
+        let value = current_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(0), value)?;
+        let address = current_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, current_step.stack.nth_last_filled(1), address)?;
+        let effective_address = MemoryAddress(address.as_u64() as usize + mem_offset as usize);
+
+        // stored value is truncated to the width of the opcode
+        let bytes = value.as_u64().to_le_bytes();
+        for (i, byte) in bytes.iter().take(wasm_store_width(current_step.op)).enumerate() {
+            state.memory_write(&mut exec_step, effective_address.map(|a| a + i), *byte)?;
+        }
+
+        Ok(vec![exec_step])
+    }
+}
@@ -32,6 +32,10 @@ pub mod copy_circuit;
 #[cfg(feature = "benches")]
 pub mod exp_circuit;
 
+#[cfg(test)]
+#[cfg(feature = "benches")]
+pub mod wasm_circuit;
+
 #[cfg(test)]
 #[cfg(feature = "benches")]
 pub mod constants;
@@ -3,8 +3,12 @@
 #[cfg(test)]
 mod tests {
     use ark_std::{end_timer, start_timer};
-    use bus_mapping::circuit_input_builder::{BuilderClient, CircuitsParams};
+    use bus_mapping::{
+        circuit_input_builder::{BuilderClient, CircuitsParams},
+        wasm::gas::DEFAULT_WASM_GAS_MODEL,
+    };
     use env_logger::Env;
+    use eth_types::evm_types::WASM_MAX_STACK_DEPTH;
     use halo2_proofs::{
         halo2curves::bn256::{Bn256, Fr, G1Affine},
         plonk::{create_proof, keygen_pk, keygen_vk, verify_proof},
@@ -55,6 +59,8 @@ mod tests {
             max_exp_steps: 100_000,
             max_evm_rows: 4_000_000,
             max_rlp_rows: 4_000_000,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         };
         let cli = BuilderClient::new(cli, params).await.unwrap();
         let (builder, _) = cli.gen_inputs(block_num).await.unwrap();
@@ -0,0 +1,187 @@
+//! Wasm circuit benchmarks
+
+#[cfg(test)]
+mod tests {
+    use ark_std::{end_timer, start_timer};
+    use env_logger::Env;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use std::{cell::RefCell, env::var, rc::Rc};
+    use zkevm_circuits::wasm_circuit::{
+        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        circuit::{WasmChip, WasmConfig},
+        standalone::WasmCircuit,
+        types::SharedState,
+    };
+
+    /// Peak allocation tracking, it replaces the allocator of the whole test binary so it is
+    /// only built with the `peak-alloc` feature.
+    #[cfg(feature = "peak-alloc")]
+    mod peak_alloc {
+        use std::{
+            alloc::{GlobalAlloc, Layout, System},
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        /// System allocator keeping track of the peak of the allocated bytes
+        struct PeakAlloc;
+
+        static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+        static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+        unsafe impl GlobalAlloc for PeakAlloc {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let ptr = System.alloc(layout);
+                if !ptr.is_null() {
+                    let allocated =
+                        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+                    PEAK.fetch_max(allocated, Ordering::SeqCst);
+                }
+                ptr
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout);
+                ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+            }
+        }
+
+        #[global_allocator]
+        static GLOBAL: PeakAlloc = PeakAlloc;
+
+        /// Runs `f` and returns the peak of the bytes allocated on top of the ones allocated
+        /// before.
+        pub(super) fn peak_allocated_by(f: impl FnOnce()) -> usize {
+            let allocated_before = ALLOCATED.load(Ordering::SeqCst);
+            PEAK.store(allocated_before, Ordering::SeqCst);
+            f();
+            PEAK.load(Ordering::SeqCst) - allocated_before
+        }
+    }
+
+    /// Assigns every bytecode step by step: `assign_prefix`, `assign_section` for each section
+    /// and `assign_dynamic_indexes`, all of them into the same region.
+    #[derive(Default)]
+    struct WasmSectionsCircuit {
+        wbs: Vec<WasmBytecode>,
+    }
+
+    impl Circuit<Fr> for WasmSectionsCircuit {
+        type Config = WasmConfig<Fr>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let shared_state = Rc::new(RefCell::new(SharedState::default()));
+            let wb_table = Rc::new(WasmBytecodeTable::construct(cs, true));
+
+            WasmChip::<Fr>::configure(cs, wb_table, shared_state)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut wasm_chip = WasmChip::construct(config);
+
+            wasm_chip
+                .load_once(&mut layouter)
+                .map_err(|_| Error::Synthesis)?;
+            layouter.assign_region(
+                || "wasm_chip region",
+                |mut region| {
+                    wasm_chip.config.shared_state.borrow_mut().reset();
+                    let mut assign_delta = 0;
+                    for wb in &self.wbs {
+                        wasm_chip
+                            .load(&mut region, wb, assign_delta)
+                            .map_err(|_| Error::Synthesis)?;
+                        wasm_chip
+                            .assign_prefix(&mut region, wb, 0, assign_delta)
+                            .map_err(|_| Error::Synthesis)?;
+                        let section_spans = wasm_chip
+                            .section_spans(&mut region, wb, assign_delta)
+                            .map_err(|_| Error::Synthesis)?;
+                        for section_span in section_spans {
+                            wasm_chip
+                                .assign_section(&mut region, wb, &section_span, assign_delta)
+                                .map_err(|_| Error::Synthesis)?;
+                        }
+                        assign_delta = wasm_chip
+                            .assign_dynamic_indexes(&mut region, wb, assign_delta)
+                            .map_err(|_| Error::Synthesis)?;
+                    }
+
+                    Ok(())
+                },
+            )?;
+
+            Ok(())
+        }
+    }
+
+    /// module with `funcs_count` functions, each one adding and multiplying its params
+    fn module_wat(funcs_count: usize) -> String {
+        let func = "(func (param i32 i32) (result i32) local.get 0 local.get 1 i32.add \
+                    local.get 0 i32.mul)";
+        format!("(module (memory 1) {})", vec![func; funcs_count].join(" "))
+    }
+
+    /// Runs the MockProver on `circuit`, reports the peak allocated bytes with the `peak-alloc`
+    /// feature.
+    fn run_mock_prover(name: &str, degree: u32, circuit: &impl Circuit<Fr>) {
+        let start = start_timer!(|| format!("{} with degree = {}", name, degree));
+        let run = || {
+            MockProver::run(degree, circuit, vec![]).unwrap();
+        };
+        #[cfg(feature = "peak-alloc")]
+        log::info!(
+            "{} peak allocated bytes: {}",
+            name,
+            peak_alloc::peak_allocated_by(run)
+        );
+        #[cfg(not(feature = "peak-alloc"))]
+        run();
+        end_timer!(start);
+    }
+
+    /// Both paths assign a bytecode into a single region (the gates read rows across section
+    /// boundaries), the section by section one only lets the synthesizer run code between the
+    /// sections.
+    #[cfg_attr(not(feature = "benches"), ignore)]
+    #[test]
+    fn bench_wasm_circuit_assign() {
+        env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+        let funcs_count: usize = var("WASM_FUNCS")
+            .unwrap_or_else(|_| "512".to_string())
+            .parse()
+            .expect("Cannot parse WASM_FUNCS env var as usize");
+
+        // Unique string used by bench results module for parsing the result
+        const BENCHMARK_ID: &str = "Wasm Circuit";
+
+        let wb = WasmBytecode::from_wat(&module_wat(funcs_count)).unwrap();
+        let rows = WasmChip::<Fr>::assign_auto_dry_run(&wb.bytes).unwrap();
+        let degree = (rows + 256).next_power_of_two().trailing_zeros().max(17);
+
+        run_mock_prover(
+            &format!("{} assign_auto", BENCHMARK_ID),
+            degree,
+            &WasmCircuit::<Fr>::new(vec![wb.clone()]),
+        );
+        run_mock_prover(
+            &format!("{} assign_section", BENCHMARK_ID),
+            degree,
+            &WasmSectionsCircuit { wbs: vec![wb] },
+        );
+    }
+}
@@ -7,7 +7,7 @@ use std::collections::BTreeMap;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-use wasm_encoder::{CodeSection, ConstExpr, DataSection, Encode, Function, FunctionSection, GlobalSection, GlobalType, Instruction, MemArg, TypeSection, ValType};
+use wasm_encoder::{CodeSection, ConstExpr, DataCountSection, DataSection, ElementSection, Elements, Encode, Function, FunctionSection, GlobalSection, GlobalType, Instruction, MemArg, TableSection, TableType, TypeSection, ValType};
 use wasm_encoder::BlockType::Empty;
 
 /// Error type for Bytecode related failures
@@ -114,9 +114,13 @@ pub struct Bytecode {
     functions: FunctionSection,
     codes: CodeSection,
     main_locals: Vec<(u32, ValType)>,
+    main_type_index: u32,
     evm_table: HashMap<EvmCall, usize>,
     num_opcodes: usize,
     markers: HashMap<String, usize>,
+    memory_maximum: Option<u64>,
+    table_elements: Vec<u32>,
+    passive_data: Vec<Vec<u8>>,
 }
 
 impl From<Bytecode> for Bytes {
@@ -163,7 +167,7 @@ impl WasmBinaryBytecode for Bytecode {
         let mut memories = MemorySection::new();
         memories.memory(MemoryType {
             minimum: 1,
-            maximum: None,
+            maximum: self.memory_maximum,
             memory64: false,
             shared: false,
         });
@@ -173,7 +177,7 @@ impl WasmBinaryBytecode for Bytecode {
         exports.export("memory", ExportKind::Memory, 0);
         // Encode the main function
         let mut functions = self.functions.clone();
-        functions.function(0);
+        functions.function(self.main_type_index);
         let mut codes = self.codes.clone();
         let mut f = Function::new(self.main_locals.clone());
         f.raw(self.code());
@@ -183,6 +187,15 @@ impl WasmBinaryBytecode for Bytecode {
         module.section(&self.types);
         module.section(&imports);
         module.section(&functions);
+        if self.table_elements.len() > 0 {
+            let mut tables = TableSection::new();
+            tables.table(TableType {
+                element_type: ValType::FuncRef,
+                minimum: self.table_elements.len() as u32,
+                maximum: None,
+            });
+            module.section(&tables);
+        }
         module.section(&memories);
         if self.variables.len() > 0 {
             let mut global_section = GlobalSection::new();
@@ -197,6 +210,21 @@ impl WasmBinaryBytecode for Bytecode {
             module.section(&global_section);
         }
         module.section(&exports);
+        if self.table_elements.len() > 0 {
+            let mut elements = ElementSection::new();
+            elements.active(
+                None,
+                &ConstExpr::i32_const(0),
+                ValType::FuncRef,
+                Elements::Functions(&self.table_elements),
+            );
+            module.section(&elements);
+        }
+        // global data segment goes after the passive ones
+        let data_count = self.passive_data.len() as u32 + (self.global_data.1.len() > 0) as u32;
+        if self.passive_data.len() > 0 {
+            module.section(&DataCountSection { count: data_count });
+        }
         module.section(&codes);
         // if we have global data section then put it into final binary
         let mut sections = self.section_descriptors.clone();
@@ -211,9 +239,14 @@ impl WasmBinaryBytecode for Bytecode {
                 // _ => unreachable!("unknown section: {:?}", section)
             }
         }
-        if self.global_data.1.len() > 0 {
+        if data_count > 0 {
             let mut data_section = DataSection::new();
-            data_section.active(0, &ConstExpr::i32_const(self.global_data.0 as i32), self.global_data.1.clone());
+            for data in &self.passive_data {
+                data_section.passive(data.clone());
+            }
+            if self.global_data.1.len() > 0 {
+                data_section.active(0, &ConstExpr::i32_const(self.global_data.0 as i32), self.global_data.1.clone());
+            }
             module.section(&data_section);
         }
         let wasm_bytes = module.finish();
@@ -233,9 +266,13 @@ impl Default for Bytecode {
             functions: Default::default(),
             codes: Default::default(),
             main_locals: Default::default(),
+            main_type_index: 0,
             evm_table: Default::default(),
             num_opcodes: 0,
             markers: Default::default(),
+            memory_maximum: None,
+            table_elements: vec![],
+            passive_data: vec![],
         };
         res.ensure_function_type(vec![], vec![]);
         res
@@ -271,6 +308,12 @@ impl Bytecode {
         self
     }
 
+    /// Declare the results of the main function (it returns nothing by default)
+    pub fn with_main_results(&mut self, results: Vec<ValType>) -> &mut Self {
+        self.main_type_index = self.ensure_function_type(vec![], results);
+        self
+    }
+
     #[deprecated(note = "Use `fill_default_global_data` instead")]
     pub fn with_global_data(&mut self, memory_index: u32, memory_offset: u32, data: Vec<u8>) -> &mut Self {
         self.section_descriptors.push(SectionDescriptor::Data {
@@ -281,6 +324,24 @@ impl Bytecode {
         self
     }
 
+    /// Declare an upper bound (in pages) for the module memory
+    pub fn with_memory_maximum(&mut self, maximum: u64) -> &mut Self {
+        self.memory_maximum = Some(maximum);
+        self
+    }
+
+    /// Put functions into the table 0 starting from slot 0 (for `call_indirect`)
+    pub fn with_table_elements(&mut self, func_indexes: Vec<u32>) -> &mut Self {
+        self.table_elements.extend(&func_indexes);
+        self
+    }
+
+    /// Add a passive data segment (for `memory.init`), returns its data index
+    pub fn add_passive_data(&mut self, data: Vec<u8>) -> u32 {
+        self.passive_data.push(data);
+        self.passive_data.len() as u32 - 1
+    }
+
     pub fn with_global_variable(&mut self, global_variable: GlobalVariable) {
         self.variables.push(global_variable);
     }
@@ -471,8 +532,10 @@ impl Bytecode {
             OpcodeId::I64Store32 => Instruction::I64Store32,
 */
 
-            //OpcodeId::GrowMemory => Instruction::MemoryGrow,
-            //OpcodeId::CurrentMemory => Instruction::MemorySize,
+            OpcodeId::GrowMemory => Instruction::MemoryGrow(0),
+            OpcodeId::CurrentMemory => Instruction::MemorySize(0),
+            OpcodeId::MemoryCopy => Instruction::MemoryCopy { src_mem: 0, dst_mem: 0 },
+            OpcodeId::MemoryFill => Instruction::MemoryFill(0),
 
             OpcodeId::I32Add => Instruction::I32Add,
             OpcodeId::I64Add => Instruction::I64Add,
@@ -494,11 +557,24 @@ impl Bytecode {
             OpcodeId::I64Clz => Instruction::I64Clz,
             OpcodeId::I32Popcnt => Instruction::I32Popcnt,
             OpcodeId::I64Popcnt => Instruction::I64Popcnt,
+            OpcodeId::F32Add => Instruction::F32Add,
+            OpcodeId::F32Sub => Instruction::F32Sub,
+            OpcodeId::F32Mul => Instruction::F32Mul,
+            OpcodeId::F32Div => Instruction::F32Div,
+            OpcodeId::F64Add => Instruction::F64Add,
+            OpcodeId::F64Sub => Instruction::F64Sub,
+            OpcodeId::F64Mul => Instruction::F64Mul,
+            OpcodeId::F64Div => Instruction::F64Div,
             OpcodeId::I32Eqz => Instruction::I32Eqz,
             OpcodeId::I64Eqz => Instruction::I64Eqz,
             OpcodeId::I32WrapI64 => Instruction::I32WrapI64,
             OpcodeId::I64ExtendSI32 => Instruction::I64ExtendI32S,
             OpcodeId::I64ExtendUI32 => Instruction::I64ExtendI32U,
+            OpcodeId::I32Extend8S => Instruction::I32Extend8S,
+            OpcodeId::I32Extend16S => Instruction::I32Extend16S,
+            OpcodeId::I64Extend8S => Instruction::I64Extend8S,
+            OpcodeId::I64Extend16S => Instruction::I64Extend16S,
+            OpcodeId::I64Extend32S => Instruction::I64Extend32S,
             OpcodeId::End => Instruction::End,
             OpcodeId::Unreachable => Instruction::Unreachable,
             OpcodeId::Drop => Instruction::Drop,
@@ -581,14 +657,29 @@ impl Bytecode {
         let op = match op {
             OpcodeId::I32Const => Instruction::I32Const(val as i32),
             OpcodeId::I64Const => Instruction::I64Const(val as i64),
+            // float constants are passed as raw IEEE-754 bits
+            OpcodeId::F32Const => Instruction::F32Const(f32::from_bits(val as u32)),
+            OpcodeId::F64Const => Instruction::F64Const(f64::from_bits(val as u64)),
             OpcodeId::GetGlobal => Instruction::GlobalGet(val as u32),
             OpcodeId::SetGlobal => Instruction::GlobalSet(val as u32),
             OpcodeId::GetLocal => Instruction::LocalGet(val as u32),
             OpcodeId::SetLocal => Instruction::LocalSet(val as u32),
             OpcodeId::TeeLocal => Instruction::LocalTee(val as u32),
             OpcodeId::Call => Instruction::Call(val as u32),
+            // type index of the callee, the table is always 0
+            OpcodeId::CallIndirect => Instruction::CallIndirect { ty: val as u32, table: 0 },
+            OpcodeId::MemoryInit => Instruction::MemoryInit { mem: 0, data_index: val as u32 },
+            OpcodeId::DataDrop => Instruction::DataDrop(val as u32),
             OpcodeId::Br => Instruction::Br(val as u32),
             OpcodeId::BrIf => Instruction::BrIf(val as u32),
+            // typed select takes the value type byte as its immediate
+            OpcodeId::SelectT => Instruction::TypedSelect(match val as u8 {
+                0x7f => ValType::I32,
+                0x7e => ValType::I64,
+                0x7d => ValType::F32,
+                0x7c => ValType::F64,
+                _ => unreachable!("not supported value type: {:#x}", val),
+            }),
             _ => {
                 unreachable!("not supported opcode: {:?} ({})", op, op.as_u8())
             }
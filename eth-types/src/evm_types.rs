@@ -71,6 +71,12 @@ pub const MAX_EXPANDED_MEMORY_ADDRESS: u64 = 0x1FFFFFFFE0;
 pub const MAX_REFUND_QUOTIENT_OF_GAS_USED: usize = 5;
 /// Gas stipend when CALL or CALLCODE is attached with value.
 pub const GAS_STIPEND_CALL_WITH_VALUE: u64 = 2300;
+/// Default max depth of the WASM operand stack.
+pub const WASM_MAX_STACK_DEPTH: usize = 1024;
+/// Global index the dropped flag of the data segment 0 is kept at in the rw table, the flag of
+/// the data segment `i` is at `WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX + i`, past the module
+/// globals.
+pub const WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX: u32 = 1 << 31;
 
 #[cfg(feature = "shanghai")]
 mod gas_create {
@@ -8,6 +8,20 @@ use std::{fmt, matches, str::FromStr};
 use itertools::Itertools;
 use strum_macros::EnumIter;
 
+/// Prefix byte of the wasm bulk memory instructions, followed by a leb128 sub-opcode.
+pub const WASM_BULK_MEMORY_PREFIX: u8 = 0xfc;
+
+/// WASM opcodes pushing one operand without popping any.
+pub const WASM_PUSH_OPCODES: [OpcodeId; 7] = [
+    OpcodeId::I32Const,
+    OpcodeId::I64Const,
+    OpcodeId::F32Const,
+    OpcodeId::F64Const,
+    OpcodeId::GetLocal,
+    OpcodeId::GetGlobal,
+    OpcodeId::CurrentMemory,
+];
+
 /// Opcode enum. One-to-one corresponding to an `u8` value.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Hash, EnumIter, PartialOrd, Ord)]
 pub enum OpcodeId {
@@ -79,6 +93,7 @@ pub enum OpcodeId {
     CallIndirect,
     Drop,
     Select,
+    SelectT,
     GetLocal,
     SetLocal,
     TeeLocal,
@@ -109,6 +124,10 @@ pub enum OpcodeId {
     I64Store32,
     CurrentMemory,
     GrowMemory,
+    MemoryInit,
+    DataDrop,
+    MemoryCopy,
+    MemoryFill,
     I32Const,
     I64Const,
     F32Const,
@@ -236,6 +255,11 @@ pub enum OpcodeId {
     I64ReinterpretF64,
     F32ReinterpretI32,
     F64ReinterpretI64,
+    I32Extend8S,
+    I32Extend16S,
+    I64Extend8S,
+    I64Extend16S,
+    I64Extend32S,
 
     // TODO: "remove these opcodes in the future they don't work"
     STOP,
@@ -455,6 +479,7 @@ impl OpcodeId {
             OpcodeId::CallIndirect => 0x11,
             OpcodeId::Drop => 0x1a,
             OpcodeId::Select => 0x1b,
+            OpcodeId::SelectT => 0x1c,
             OpcodeId::GetLocal => 0x20,
             OpcodeId::SetLocal => 0x21,
             OpcodeId::TeeLocal => 0x22,
@@ -485,6 +510,11 @@ impl OpcodeId {
             OpcodeId::I64Store32 => 0x3e,
             OpcodeId::CurrentMemory => 0x3f,
             OpcodeId::GrowMemory => 0x40,
+            // `0xfc 0x08..=0x0b` in the binary, remapped to free single byte codes
+            OpcodeId::MemoryInit => 0xf4,
+            OpcodeId::DataDrop => 0xf5,
+            OpcodeId::MemoryCopy => 0xf6,
+            OpcodeId::MemoryFill => 0xf7,
             OpcodeId::I32Const => 0x41,
             OpcodeId::I64Const => 0x42,
             OpcodeId::F32Const => 0x43,
@@ -612,52 +642,57 @@ impl OpcodeId {
             OpcodeId::I64ReinterpretF64 => 0xbd,
             OpcodeId::F32ReinterpretI32 => 0xbe,
             OpcodeId::F64ReinterpretI64 => 0xbf,
-            // EVM opcode family
-            OpcodeId::CALLDATALOAD => 0xc1,
-            OpcodeId::CALLDATASIZE => 0xc2,
-            OpcodeId::CALLDATACOPY => 0xc3,
-            OpcodeId::CODESIZE => 0xc4,
-            OpcodeId::CODECOPY => 0xc5,
-            OpcodeId::PC => 0xc6,
-            OpcodeId::MSIZE => 0xc7,
-            OpcodeId::RETURN => 0xc9,
-            OpcodeId::REVERT => 0xca,
-            OpcodeId::SHA3 => 0xcb,
-            OpcodeId::ADDRESS => 0xcc,
-            OpcodeId::BALANCE => 0xcd,
-            OpcodeId::ORIGIN => 0xce,
-            OpcodeId::CALLER => 0xcf,
-            OpcodeId::CALLVALUE => 0xd0,
-            OpcodeId::GASPRICE => 0xd1,
-            OpcodeId::EXTCODESIZE => 0xd2,
-            OpcodeId::EXTCODECOPY => 0xd3,
-            OpcodeId::EXTCODEHASH => 0xd4,
-            OpcodeId::RETURNDATASIZE => 0xd5,
-            OpcodeId::RETURNDATACOPY => 0xd6,
-            OpcodeId::BLOCKHASH => 0xd7,
-            OpcodeId::COINBASE => 0xd8,
-            OpcodeId::TIMESTAMP => 0xd9,
-            OpcodeId::NUMBER => 0xda,
-            OpcodeId::DIFFICULTY => 0xdb,
-            OpcodeId::GASLIMIT => 0xdc,
-            OpcodeId::CHAINID => 0xde,
-            OpcodeId::SELFBALANCE => 0xdf,
-            OpcodeId::BASEFEE => 0xe0,
-            OpcodeId::SLOAD => 0xe1,
-            OpcodeId::SSTORE => 0xe2,
-            OpcodeId::GAS => 0xe3,
-            OpcodeId::LOG0 => 0xe4,
-            OpcodeId::LOG1 => 0xe5,
-            OpcodeId::LOG2 => 0xe6,
-            OpcodeId::LOG3 => 0xe7,
-            OpcodeId::LOG4 => 0xe8,
-            OpcodeId::CREATE => 0xe9,
-            OpcodeId::CREATE2 => 0xe0,
-            OpcodeId::CALL => 0xea,
-            OpcodeId::CALLCODE => 0xeb,
-            OpcodeId::DELEGATECALL => 0xec,
-            OpcodeId::STATICCALL => 0xed,
-            OpcodeId::SELFDESTRUCT => 0xef,
+            OpcodeId::I32Extend8S => 0xc0,
+            OpcodeId::I32Extend16S => 0xc1,
+            OpcodeId::I64Extend8S => 0xc2,
+            OpcodeId::I64Extend16S => 0xc3,
+            OpcodeId::I64Extend32S => 0xc4,
+            // EVM opcode family, right after the wasm opcodes
+            OpcodeId::CALLDATALOAD => 0xc5,
+            OpcodeId::CALLDATASIZE => 0xc6,
+            OpcodeId::CALLDATACOPY => 0xc7,
+            OpcodeId::CODESIZE => 0xc8,
+            OpcodeId::CODECOPY => 0xc9,
+            OpcodeId::PC => 0xca,
+            OpcodeId::MSIZE => 0xcb,
+            OpcodeId::RETURN => 0xcd,
+            OpcodeId::REVERT => 0xce,
+            OpcodeId::SHA3 => 0xcf,
+            OpcodeId::ADDRESS => 0xd0,
+            OpcodeId::BALANCE => 0xd1,
+            OpcodeId::ORIGIN => 0xd2,
+            OpcodeId::CALLER => 0xd3,
+            OpcodeId::CALLVALUE => 0xd4,
+            OpcodeId::GASPRICE => 0xd5,
+            OpcodeId::EXTCODESIZE => 0xd6,
+            OpcodeId::EXTCODECOPY => 0xd7,
+            OpcodeId::EXTCODEHASH => 0xd8,
+            OpcodeId::RETURNDATASIZE => 0xd9,
+            OpcodeId::RETURNDATACOPY => 0xda,
+            OpcodeId::BLOCKHASH => 0xdb,
+            OpcodeId::COINBASE => 0xdc,
+            OpcodeId::TIMESTAMP => 0xdd,
+            OpcodeId::NUMBER => 0xde,
+            OpcodeId::DIFFICULTY => 0xdf,
+            OpcodeId::GASLIMIT => 0xe0,
+            OpcodeId::CHAINID => 0xe2,
+            OpcodeId::SELFBALANCE => 0xe3,
+            OpcodeId::BASEFEE => 0xe4,
+            OpcodeId::SLOAD => 0xe5,
+            OpcodeId::SSTORE => 0xe6,
+            OpcodeId::GAS => 0xe7,
+            OpcodeId::LOG0 => 0xe8,
+            OpcodeId::LOG1 => 0xe9,
+            OpcodeId::LOG2 => 0xea,
+            OpcodeId::LOG3 => 0xeb,
+            OpcodeId::LOG4 => 0xec,
+            OpcodeId::CREATE => 0xed,
+            OpcodeId::CREATE2 => 0xee,
+            OpcodeId::CALL => 0xef,
+            OpcodeId::CALLCODE => 0xf0,
+            OpcodeId::DELEGATECALL => 0xf1,
+            OpcodeId::STATICCALL => 0xf2,
+            OpcodeId::SELFDESTRUCT => 0xf3,
             _ => 0x00,
         }
     }
@@ -900,6 +935,43 @@ impl OpcodeId {
             acc
         })
     }
+
+    /// Returns the opcode a single byte encodes in a wasm binary, `None` for bytes that are not
+    /// wasm opcodes there (the EVM opcode family and the bulk memory trace codes).
+    pub fn from_wasm_byte(opcode: u8) -> Option<Self> {
+        if opcode > Self::I64Extend32S.as_u8() {
+            return None;
+        }
+        match Self::from(opcode) {
+            Self::INVALID(_) => None,
+            op => Some(op),
+        }
+    }
+
+    /// Returns the bulk memory opcode `prefix sub_opcode` encodes in a wasm binary.
+    pub fn from_wasm_prefixed(prefix: u8, sub_opcode: u32) -> Option<Self> {
+        if prefix != WASM_BULK_MEMORY_PREFIX {
+            return None;
+        }
+        match sub_opcode {
+            0x08 => Some(Self::MemoryInit),
+            0x09 => Some(Self::DataDrop),
+            0x0a => Some(Self::MemoryCopy),
+            0x0b => Some(Self::MemoryFill),
+            _ => None,
+        }
+    }
+
+    /// Returns the sub-opcode following `WASM_BULK_MEMORY_PREFIX` for the bulk memory opcodes.
+    pub fn wasm_sub_opcode(&self) -> Option<u32> {
+        match self {
+            Self::MemoryInit => Some(0x08),
+            Self::DataDrop => Some(0x09),
+            Self::MemoryCopy => Some(0x0a),
+            Self::MemoryFill => Some(0x0b),
+            _ => None,
+        }
+    }
 }
 
 impl From<u8> for OpcodeId {
@@ -920,6 +992,7 @@ impl From<u8> for OpcodeId {
             0x11 => OpcodeId::CallIndirect,
             0x1a => OpcodeId::Drop,
             0x1b => OpcodeId::Select,
+            0x1c => OpcodeId::SelectT,
             0x20 => OpcodeId::GetLocal,
             0x21 => OpcodeId::SetLocal,
             0x22 => OpcodeId::TeeLocal,
@@ -950,6 +1023,10 @@ impl From<u8> for OpcodeId {
             0x3e => OpcodeId::I64Store32,
             0x3f => OpcodeId::CurrentMemory,
             0x40 => OpcodeId::GrowMemory,
+            0xf4 => OpcodeId::MemoryInit,
+            0xf5 => OpcodeId::DataDrop,
+            0xf6 => OpcodeId::MemoryCopy,
+            0xf7 => OpcodeId::MemoryFill,
             0x41 => OpcodeId::I32Const,
             0x42 => OpcodeId::I64Const,
             0x43 => OpcodeId::F32Const,
@@ -1077,53 +1154,58 @@ impl From<u8> for OpcodeId {
             0xbd => OpcodeId::I64ReinterpretF64,
             0xbe => OpcodeId::F32ReinterpretI32,
             0xbf => OpcodeId::F64ReinterpretI64,
-            // EVM opcode family
-            0xc1 => OpcodeId::CALLDATALOAD,
-            0xc2 => OpcodeId::CALLDATASIZE,
-            0xc3 => OpcodeId::CALLDATACOPY,
-            0xc4 => OpcodeId::CODESIZE,
-            0xc5 => OpcodeId::CODECOPY,
-            0xc6 => OpcodeId::PC,
-            0xc7 => OpcodeId::MSIZE,
-            0xc9 => OpcodeId::RETURN,
-            0xca => OpcodeId::REVERT,
-            0xcb => OpcodeId::SHA3,
-            0xcc => OpcodeId::ADDRESS,
-            0xcd => OpcodeId::BALANCE,
-            0xce => OpcodeId::ORIGIN,
-            0xcf => OpcodeId::CALLER,
-            0xd0 => OpcodeId::CALLVALUE,
-            0xd1 => OpcodeId::GASPRICE,
-            0xd2 => OpcodeId::EXTCODESIZE,
-            0xd3 => OpcodeId::EXTCODECOPY,
-            0xd4 => OpcodeId::EXTCODEHASH,
-            0xd5 => OpcodeId::RETURNDATASIZE,
-            0xd6 => OpcodeId::RETURNDATACOPY,
-            0xd7 => OpcodeId::BLOCKHASH,
-            0xd8 => OpcodeId::COINBASE,
-            0xd9 => OpcodeId::TIMESTAMP,
-            0xda => OpcodeId::NUMBER,
-            0xdb => OpcodeId::DIFFICULTY,
-            0xdc => OpcodeId::GASLIMIT,
-            0xde => OpcodeId::CHAINID,
-            0xdf => OpcodeId::SELFBALANCE,
-            0xe0 => OpcodeId::BASEFEE,
-            0xe1 => OpcodeId::SLOAD,
-            0xe2 => OpcodeId::SSTORE,
-            0xe3 => OpcodeId::GAS,
-            0xe4 => OpcodeId::LOG0,
-            0xe5 => OpcodeId::LOG1,
-            0xe6 => OpcodeId::LOG2,
-            0xe7 => OpcodeId::LOG3,
-            0xe8 => OpcodeId::LOG4,
-            0xe9 => OpcodeId::CREATE,
-            0xea => OpcodeId::CREATE2,
-            0xeb => OpcodeId::CALL,
-            0xec => OpcodeId::CALLCODE,
-            0xed => OpcodeId::DELEGATECALL,
-            0xee => OpcodeId::STATICCALL,
+            0xc0 => OpcodeId::I32Extend8S,
+            0xc1 => OpcodeId::I32Extend16S,
+            0xc2 => OpcodeId::I64Extend8S,
+            0xc3 => OpcodeId::I64Extend16S,
+            0xc4 => OpcodeId::I64Extend32S,
+            // EVM opcode family, right after the wasm opcodes
+            0xc5 => OpcodeId::CALLDATALOAD,
+            0xc6 => OpcodeId::CALLDATASIZE,
+            0xc7 => OpcodeId::CALLDATACOPY,
+            0xc8 => OpcodeId::CODESIZE,
+            0xc9 => OpcodeId::CODECOPY,
+            0xca => OpcodeId::PC,
+            0xcb => OpcodeId::MSIZE,
+            0xcd => OpcodeId::RETURN,
+            0xce => OpcodeId::REVERT,
+            0xcf => OpcodeId::SHA3,
+            0xd0 => OpcodeId::ADDRESS,
+            0xd1 => OpcodeId::BALANCE,
+            0xd2 => OpcodeId::ORIGIN,
+            0xd3 => OpcodeId::CALLER,
+            0xd4 => OpcodeId::CALLVALUE,
+            0xd5 => OpcodeId::GASPRICE,
+            0xd6 => OpcodeId::EXTCODESIZE,
+            0xd7 => OpcodeId::EXTCODECOPY,
+            0xd8 => OpcodeId::EXTCODEHASH,
+            0xd9 => OpcodeId::RETURNDATASIZE,
+            0xda => OpcodeId::RETURNDATACOPY,
+            0xdb => OpcodeId::BLOCKHASH,
+            0xdc => OpcodeId::COINBASE,
+            0xdd => OpcodeId::TIMESTAMP,
+            0xde => OpcodeId::NUMBER,
+            0xdf => OpcodeId::DIFFICULTY,
+            0xe0 => OpcodeId::GASLIMIT,
+            0xe2 => OpcodeId::CHAINID,
+            0xe3 => OpcodeId::SELFBALANCE,
+            0xe4 => OpcodeId::BASEFEE,
+            0xe5 => OpcodeId::SLOAD,
+            0xe6 => OpcodeId::SSTORE,
+            0xe7 => OpcodeId::GAS,
+            0xe8 => OpcodeId::LOG0,
+            0xe9 => OpcodeId::LOG1,
+            0xea => OpcodeId::LOG2,
+            0xeb => OpcodeId::LOG3,
+            0xec => OpcodeId::LOG4,
+            0xed => OpcodeId::CREATE,
+            0xee => OpcodeId::CREATE2,
+            0xef => OpcodeId::CALL,
+            0xf0 => OpcodeId::CALLCODE,
+            0xf1 => OpcodeId::DELEGATECALL,
+            0xf2 => OpcodeId::STATICCALL,
             #[cfg(not(feature = "scroll"))]
-            0xef => OpcodeId::SELFDESTRUCT,
+            0xf3 => OpcodeId::SELFDESTRUCT,
             // invalid opcode
             _ => OpcodeId::INVALID(value)
         }
@@ -1154,6 +1236,7 @@ impl FromStr for OpcodeId {
             "call_indirect" => OpcodeId::CallIndirect,
             "drop" => OpcodeId::Drop,
             "select" => OpcodeId::Select,
+            "select_t" => OpcodeId::SelectT,
             "get_local" => OpcodeId::GetLocal,
             "set_local" => OpcodeId::SetLocal,
             "tee_local" => OpcodeId::TeeLocal,
@@ -1184,6 +1267,10 @@ impl FromStr for OpcodeId {
             "i64_store32" => OpcodeId::I64Store32,
             "current_memory" => OpcodeId::CurrentMemory,
             "grow_memory" => OpcodeId::GrowMemory,
+            "memory_init" => OpcodeId::MemoryInit,
+            "data_drop" => OpcodeId::DataDrop,
+            "memory_copy" => OpcodeId::MemoryCopy,
+            "memory_fill" => OpcodeId::MemoryFill,
             "i32_const" => OpcodeId::I32Const,
             "i64_const" => OpcodeId::I64Const,
             "f32_const" => OpcodeId::F32Const,
@@ -1311,6 +1398,11 @@ impl FromStr for OpcodeId {
             "i64_reinterpret_f64" => OpcodeId::I64ReinterpretF64,
             "f32_reinterpret_i32" => OpcodeId::F32ReinterpretI32,
             "f64_reinterpret_i64" => OpcodeId::F64ReinterpretI64,
+            "i32_extend8_s" => OpcodeId::I32Extend8S,
+            "i32_extend16_s" => OpcodeId::I32Extend16S,
+            "i64_extend8_s" => OpcodeId::I64Extend8S,
+            "i64_extend16_s" => OpcodeId::I64Extend16S,
+            "i64_extend32_s" => OpcodeId::I64Extend32S,
             // special WASM opcodes
             "evm_stop" => OpcodeId::STOP,
             "evm_return" => OpcodeId::RETURN,
@@ -1439,4 +1531,36 @@ mod opcode_ids_tests {
         assert_eq!(OpcodeId::LOG2.data_len(), 0);
         assert_eq!(OpcodeId::CALLCODE.data_len(), 0);
     }
+
+    #[test]
+    fn byte_round_trip() {
+        for opcode in OpcodeId::valid_opcodes() {
+            assert_eq!(OpcodeId::from(opcode.as_u8()), opcode, "{:?}", opcode);
+        }
+        assert_eq!(OpcodeId::from(0xc1), OpcodeId::I32Extend16S);
+        assert_eq!(OpcodeId::from(0xc4), OpcodeId::I64Extend32S);
+        assert_eq!(OpcodeId::CREATE2.as_u8(), 0xee);
+    }
+
+    #[test]
+    fn wasm_binary_decoding() {
+        assert_eq!(OpcodeId::from_wasm_byte(0x6a), Some(OpcodeId::I32Add));
+        assert_eq!(OpcodeId::from_wasm_byte(0xc4), Some(OpcodeId::I64Extend32S));
+        assert_eq!(OpcodeId::from_wasm_byte(OpcodeId::CALL.as_u8()), None);
+        assert_eq!(OpcodeId::from_wasm_byte(OpcodeId::MemoryInit.as_u8()), None);
+        for opcode in [
+            OpcodeId::MemoryInit,
+            OpcodeId::DataDrop,
+            OpcodeId::MemoryCopy,
+            OpcodeId::MemoryFill,
+        ] {
+            let sub_opcode = opcode.wasm_sub_opcode().unwrap();
+            assert_eq!(
+                OpcodeId::from_wasm_prefixed(WASM_BULK_MEMORY_PREFIX, sub_opcode),
+                Some(opcode)
+            );
+        }
+        assert_eq!(OpcodeId::from_wasm_prefixed(WASM_BULK_MEMORY_PREFIX, 0x0c), None);
+        assert_eq!(OpcodeId::from_wasm_prefixed(0xfd, 0x08), None);
+    }
 }
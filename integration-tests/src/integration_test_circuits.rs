@@ -2,8 +2,9 @@ use crate::{get_client, GenDataOutput};
 use bus_mapping::{
     circuit_input_builder::{BuilderClient, CircuitInputBuilder, CircuitsParams},
     mock::BlockData,
+    wasm::gas::DEFAULT_WASM_GAS_MODEL,
 };
-use eth_types::geth_types::GethData;
+use eth_types::{evm_types::WASM_MAX_STACK_DEPTH, geth_types::GethData};
 use halo2_proofs::{
     dev::{CellValue, MockProver},
     halo2curves::bn256::{Bn256, Fr, G1Affine},
@@ -76,6 +77,8 @@ const CIRCUITS_PARAMS: CircuitsParams = CircuitsParams {
     max_exp_steps: MAX_EXP_STEPS,
     max_keccak_rows: MAX_KECCAK_ROWS,
     max_rlp_rows: MAX_RLP_ROWS,
+    max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+    wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
 };
 
 const EVM_CIRCUIT_DEGREE: u32 = 18;
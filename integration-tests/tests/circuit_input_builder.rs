@@ -1,8 +1,12 @@
 #![cfg(feature = "circuit_input_builder")]
 
-use bus_mapping::circuit_input_builder::{
-    build_state_code_db, get_state_accesses, BuilderClient, CircuitsParams,
+use bus_mapping::{
+    circuit_input_builder::{
+        build_state_code_db, get_state_accesses, BuilderClient, CircuitsParams,
+    },
+    wasm::gas::DEFAULT_WASM_GAS_MODEL,
 };
+use eth_types::evm_types::WASM_MAX_STACK_DEPTH;
 use integration_tests::{get_client, log_init, GenDataOutput};
 use lazy_static::lazy_static;
 use log::trace;
@@ -27,6 +31,8 @@ async fn test_circuit_input_builder_block(block_num: u64) {
             max_exp_steps: 1000,
             max_keccak_rows: 0,
             max_rlp_rows: 4200,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         },
     )
     .await
@@ -1,7 +1,9 @@
 use bus_mapping::{
     circuit_input_builder::{keccak_inputs, BuilderClient, CircuitsParams},
+    wasm::gas::DEFAULT_WASM_GAS_MODEL,
     Error::JSONRpcError,
 };
+use eth_types::evm_types::WASM_MAX_STACK_DEPTH;
 use halo2_proofs::{
     circuit::Value,
     dev::{MockProver, VerifyFailure},
@@ -33,6 +35,8 @@ const CIRCUITS_PARAMS: CircuitsParams = CircuitsParams {
     max_exp_steps: 1000,
     max_evm_rows: 0,
     max_rlp_rows: 33000,
+    max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+    wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
 };
 
 #[tokio::test]
@@ -56,6 +60,8 @@ async fn test_mock_prove_tx() {
         max_exp_steps: 5000,
         max_evm_rows: 0,
         max_rlp_rows: 42000,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
 
     let cli = BuilderClient::new(cli, params).await.unwrap();
@@ -123,6 +129,8 @@ async fn test_circuit_all_block() {
             max_exp_steps: 100_000,
             max_evm_rows: 0,
             max_rlp_rows: 2_070_000,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         };
         let cli = BuilderClient::new(cli, params).await.unwrap();
         let builder = cli.gen_inputs(block_num).await;
@@ -3,8 +3,12 @@ use crate::config::TestSuite;
 use bus_mapping::{
     circuit_input_builder::{CircuitInputBuilder, CircuitsParams},
     mock::BlockData,
+    wasm::gas::DEFAULT_WASM_GAS_MODEL,
+};
+use eth_types::{
+    evm_types::WASM_MAX_STACK_DEPTH, geth_types, geth_types::TxType, Address, Bytes, GethExecTrace,
+    U256, U64,
 };
-use eth_types::{geth_types, geth_types::TxType, Address, Bytes, GethExecTrace, U256, U64};
 use ethers_core::{
     k256::ecdsa::SigningKey,
     types::{transaction::eip2718::TypedTransaction, TransactionRequest},
@@ -332,6 +336,8 @@ pub fn run_test(
             max_keccak_rows: 0,
             max_inner_blocks: 64,
             max_rlp_rows: 6000,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         };
         let block_data = BlockData::new_from_geth_data_with_params(geth_data, circuits_params);
 
@@ -360,6 +366,8 @@ pub fn run_test(
             max_keccak_rows: 0,
             max_inner_blocks: 64,
             max_rlp_rows: 512,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         };
         let (k, circuit, instance, _builder) =
             SuperCircuit::<Fr, MAX_TXS, MAX_CALLDATA, 64, 0x100>::build(geth_data, circuits_params)
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zkevm_circuits::wasm_circuit::fuzz::fuzz_assign_auto;
+
+fuzz_target!(|bytes: &[u8]| {
+    fuzz_assign_auto(bytes);
+});
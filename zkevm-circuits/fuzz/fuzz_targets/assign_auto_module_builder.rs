@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zkevm_circuits::wasm_circuit::fuzz::{fuzz_assign_auto, WasmModuleBuilder};
+
+fuzz_target!(|builder: WasmModuleBuilder| {
+    fuzz_assign_auto(&builder.build());
+});
@@ -27,11 +27,13 @@ pub use self::EvmCircuit as TestEvmCircuit;
 
 pub use crate::witness;
 use crate::{
-    evm_circuit::param::{MAX_STEP_HEIGHT, STEP_STATE_HEIGHT},
+    evm_circuit::param::{WasmExecutionParams, MAX_STEP_HEIGHT, STEP_STATE_HEIGHT},
     table::{
-        BlockTable, BytecodeTable, CopyTable, ExpTable, KeccakTable, LookupTable, RwTable, TxTable,
+        BlockTable, BytecodeTable, CopyTable, ExpTable, FloatTable, KeccakTable, LookupTable,
+        RwTable, TxTable, WasmMetadataTable,
     },
     util::{SubCircuit, SubCircuitConfig},
+    wasm_circuit::gas::WasmGasModel,
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
@@ -55,6 +57,8 @@ pub struct EvmCircuitConfig<F> {
     copy_table: CopyTable,
     keccak_table: KeccakTable,
     exp_table: ExpTable,
+    float_table: FloatTable,
+    wasm_metadata_table: WasmMetadataTable,
 }
 
 /// Circuit configuration arguments
@@ -75,6 +79,10 @@ pub struct EvmCircuitConfigArgs<F: Field> {
     pub keccak_table: KeccakTable,
     /// ExpTable
     pub exp_table: ExpTable,
+    /// FloatTable
+    pub float_table: FloatTable,
+    /// Parameters of the proven WASM execution
+    pub wasm_params: WasmExecutionParams,
 }
 
 /// Circuit exported cells after synthesis, used for subcircuit
@@ -100,10 +108,16 @@ impl<F: Field> SubCircuitConfig<F> for EvmCircuitConfig<F> {
             copy_table,
             keccak_table,
             exp_table,
+            float_table,
+            wasm_params,
         }: Self::ConfigArgs,
     ) -> Self {
         let fixed_table = [(); 4].map(|_| meta.fixed_column());
         let byte_table = [(); 1].map(|_| meta.fixed_column());
+        // The wasm metadata table is owned (and loaded) by the EVM circuit itself,
+        // `WasmChip::configure_metadata_table` binds its rows to the module sections once the wasm
+        // circuit is part of the super circuit.
+        let wasm_metadata_table = WasmMetadataTable::construct(meta);
         let execution = Box::new(ExecutionConfig::configure(
             meta,
             challenges,
@@ -116,6 +130,9 @@ impl<F: Field> SubCircuitConfig<F> for EvmCircuitConfig<F> {
             &copy_table,
             &keccak_table,
             &exp_table,
+            &float_table,
+            &wasm_metadata_table,
+            wasm_params,
         ));
 
         meta.annotate_lookup_any_column(byte_table[0], || "byte_range");
@@ -129,6 +146,8 @@ impl<F: Field> SubCircuitConfig<F> for EvmCircuitConfig<F> {
         copy_table.annotate_columns(meta);
         keccak_table.annotate_columns(meta);
         exp_table.annotate_columns(meta);
+        float_table.annotate_columns(meta);
+        wasm_metadata_table.annotate_columns(meta);
 
         Self {
             fixed_table,
@@ -141,6 +160,8 @@ impl<F: Field> SubCircuitConfig<F> for EvmCircuitConfig<F> {
             copy_table,
             keccak_table,
             exp_table,
+            float_table,
+            wasm_metadata_table,
         }
     }
 }
@@ -151,12 +172,17 @@ impl<F: Field> EvmCircuitConfig<F> {
         &self,
         layouter: &mut impl Layouter<F>,
         fixed_table_tags: Vec<FixedTableTag>,
+        wasm_gas_model: &'static dyn WasmGasModel,
     ) -> Result<(), Error> {
         layouter.assign_region(
             || "fixed table",
             |mut region| {
                 for (offset, row) in std::iter::once([F::zero(); 4])
-                    .chain(fixed_table_tags.iter().flat_map(|tag| tag.build()))
+                    .chain(
+                        fixed_table_tags
+                            .iter()
+                            .flat_map(|tag| tag.build(wasm_gas_model)),
+                    )
                     .enumerate()
                 {
                     for (column, value) in self.fixed_table.iter().zip_eq(row) {
@@ -280,7 +306,7 @@ impl<F: Field> SubCircuit<F> for EvmCircuit<F> {
             Self::get_num_rows_required_no_padding(block);
         let num_rows_required_for_fixed_table: usize = detect_fixed_table_tags(block)
             .iter()
-            .map(|tag| tag.build::<F>().count())
+            .map(|tag| tag.build::<F>(block.circuits_params.wasm_gas_model).count())
             .sum();
         (
             num_rows_required_for_execution_steps,
@@ -303,8 +329,15 @@ impl<F: Field> SubCircuit<F> for EvmCircuit<F> {
     ) -> Result<(), Error> {
         let block = self.block.as_ref().unwrap();
 
-        config.load_fixed_table(layouter, self.fixed_table_tags.clone())?;
+        config.load_fixed_table(
+            layouter,
+            self.fixed_table_tags.clone(),
+            block.circuits_params.wasm_gas_model,
+        )?;
         config.load_byte_table(layouter)?;
+        config
+            .wasm_metadata_table
+            .dev_load(layouter, block, challenges)?;
         let export = config.execution.assign_block(layouter, block, challenges)?;
         self.exports.borrow_mut().replace(export);
         Ok(())
@@ -321,6 +354,10 @@ pub(crate) fn detect_fixed_table_tags<F: Field>(block: &Block<F>) -> Vec<FixedTa
                     | Some(OpcodeId::OR)
                     | Some(OpcodeId::XOR)
                     | Some(OpcodeId::NOT)
+                    | Some(OpcodeId::I32Rotl)
+                    | Some(OpcodeId::I32Rotr)
+                    | Some(OpcodeId::I64Rotl)
+                    | Some(OpcodeId::I64Rotr)
             )
         })
     });
@@ -398,15 +435,12 @@ use crate::util::Challenges;
 #[cfg(feature = "onephase")]
 use crate::util::MockChallenges as Challenges;
 
-impl<F: Field> Circuit<F> for EvmCircuit<F> {
-    type Config = (EvmCircuitConfig<F>, Challenges);
-    type FloorPlanner = SimpleFloorPlanner;
-
-    fn without_witnesses(&self) -> Self {
-        Self::default()
-    }
-
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+impl<F: Field> EvmCircuit<F> {
+    /// Same as `Circuit::configure` with the given params of the proven WASM execution
+    pub fn configure_with_wasm_params(
+        meta: &mut ConstraintSystem<F>,
+        wasm_params: WasmExecutionParams,
+    ) -> (EvmCircuitConfig<F>, Challenges) {
         let challenges = Challenges::construct(meta);
         let challenges_expr = challenges.exprs(meta);
         let rw_table = RwTable::construct(meta);
@@ -417,6 +451,7 @@ impl<F: Field> Circuit<F> for EvmCircuit<F> {
         let copy_table = CopyTable::construct(meta, q_copy_table);
         let keccak_table = KeccakTable::construct(meta);
         let exp_table = ExpTable::construct(meta);
+        let float_table = FloatTable::construct(meta);
         (
             EvmCircuitConfig::new(
                 meta,
@@ -429,11 +464,26 @@ impl<F: Field> Circuit<F> for EvmCircuit<F> {
                     copy_table,
                     keccak_table,
                     exp_table,
+                    float_table,
+                    wasm_params,
                 },
             ),
             challenges,
         )
     }
+}
+
+impl<F: Field> Circuit<F> for EvmCircuit<F> {
+    type Config = (EvmCircuitConfig<F>, Challenges);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::configure_with_wasm_params(meta, WasmExecutionParams::default())
+    }
 
     fn synthesize(
         &self,
@@ -473,11 +523,55 @@ impl<F: Field> Circuit<F> for EvmCircuit<F> {
             .keccak_table
             .dev_load(&mut layouter, &block.sha3_inputs, &challenges)?;
         config.exp_table.dev_load(&mut layouter, block)?;
+        config.float_table.dev_load(&mut layouter, block)?;
 
         self.synthesize_sub(&config, &challenges, &mut layouter)
     }
 }
 
+/// `EvmCircuit` configured with `WasmExecutionParams` other than the default ones. The params are
+/// const generics since `Circuit::configure` has no access to the circuit.
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Default)]
+pub struct EvmCircuitWithWasmParams<
+    F: Field,
+    const MAX_STACK_DEPTH: usize,
+    const IS_LOOKUP_INPUT_DETERMINISTIC: bool,
+>(pub EvmCircuit<F>);
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<F: Field, const MAX_STACK_DEPTH: usize, const IS_LOOKUP_INPUT_DETERMINISTIC: bool>
+    EvmCircuitWithWasmParams<F, MAX_STACK_DEPTH, IS_LOOKUP_INPUT_DETERMINISTIC>
+{
+    /// Params the circuit is configured with
+    pub fn wasm_params() -> WasmExecutionParams {
+        WasmExecutionParams {
+            max_stack_depth: MAX_STACK_DEPTH,
+            is_lookup_input_deterministic: IS_LOOKUP_INPUT_DETERMINISTIC,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl<F: Field, const MAX_STACK_DEPTH: usize, const IS_LOOKUP_INPUT_DETERMINISTIC: bool> Circuit<F>
+    for EvmCircuitWithWasmParams<F, MAX_STACK_DEPTH, IS_LOOKUP_INPUT_DETERMINISTIC>
+{
+    type Config = (EvmCircuitConfig<F>, Challenges);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        EvmCircuit::configure_with_wasm_params(meta, Self::wasm_params())
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
 #[cfg(test)]
 mod evm_circuit_stats {
     use crate::{
@@ -654,7 +748,11 @@ mod evm_circuit_stats {
             keccak_table,
             LOOKUP_CONFIG[6].1,
             exp_table,
-            LOOKUP_CONFIG[7].1
+            LOOKUP_CONFIG[7].1,
+            float_table,
+            LOOKUP_CONFIG[8].1,
+            wasm_metadata_table,
+            LOOKUP_CONFIG[9].1
         );
     }
 
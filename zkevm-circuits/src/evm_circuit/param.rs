@@ -1,5 +1,8 @@
 use super::table::Table;
-use crate::evm_circuit::{step::ExecutionState, EvmCircuit};
+use crate::{
+    evm_circuit::{step::ExecutionState, EvmCircuit},
+    util::Challenges,
+};
 use halo2_proofs::{
     halo2curves::bn256::Fr,
     plonk::{Circuit, ConstraintSystem},
@@ -37,7 +40,9 @@ pub(crate) const EVM_LOOKUP_COLS: usize = FIXED_TABLE_LOOKUPS
     + BLOCK_TABLE_LOOKUPS
     + COPY_TABLE_LOOKUPS
     + KECCAK_TABLE_LOOKUPS
-    + EXP_TABLE_LOOKUPS;
+    + EXP_TABLE_LOOKUPS
+    + FLOAT_TABLE_LOOKUPS
+    + WASM_METADATA_TABLE_LOOKUPS;
 
 /// Lookups done per row.
 pub(crate) const LOOKUP_CONFIG: &[(Table, usize)] = &[
@@ -49,6 +54,8 @@ pub(crate) const LOOKUP_CONFIG: &[(Table, usize)] = &[
     (Table::Copy, COPY_TABLE_LOOKUPS),
     (Table::Keccak, KECCAK_TABLE_LOOKUPS),
     (Table::Exp, EXP_TABLE_LOOKUPS),
+    (Table::Float, FLOAT_TABLE_LOOKUPS),
+    (Table::WasmMetadata, WASM_METADATA_TABLE_LOOKUPS),
 ];
 
 /// Fixed Table lookups done in EVMCircuit
@@ -75,6 +82,12 @@ pub const KECCAK_TABLE_LOOKUPS: usize = 1;
 /// Exp Table lookups done in EVMCircuit
 pub const EXP_TABLE_LOOKUPS: usize = 1;
 
+/// Float Table lookups done in EVMCircuit
+pub const FLOAT_TABLE_LOOKUPS: usize = 1;
+
+/// Wasm Metadata Table lookups done in EVMCircuit
+pub const WASM_METADATA_TABLE_LOOKUPS: usize = 1;
+
 /// Maximum number of bytes that an integer can fit in field without wrapping
 /// around.
 pub(crate) const MAX_N_BYTES_INTEGER: usize = 31;
@@ -96,6 +109,57 @@ pub(crate) const N_BYTES_MEMORY_WORD_SIZE: usize = 4;
 pub(crate) const PAGE_SIZE: usize = 0x10000;
 pub(crate) const STACK_CAPACITY: usize = 1024;
 
+pub use eth_types::evm_types::{opcode_ids::WASM_PUSH_OPCODES, WASM_MAX_STACK_DEPTH};
+
+/// Lookup RLC randomness used instead of the `lookup_input` challenge when
+/// `WasmExecutionParams::is_lookup_input_deterministic` is set.
+pub const DETERMINISTIC_LOOKUP_INPUT: u64 = 0x100;
+
+/// Parameters of the WASM execution proven by the EVM circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WasmExecutionParams {
+    /// Max number of operands on the stack, a push on a full stack traps to `WASM_ErrorStack`.
+    pub max_stack_depth: usize,
+    /// RLCs the lookups with `DETERMINISTIC_LOOKUP_INPUT` instead of the Fiat-Shamir
+    /// `lookup_input` challenge, so the lookup values are the same on every run. Debug only:
+    /// the randomness is known before the witness is committed, so a prover can craft colliding
+    /// RLCs and the lookups are NOT sound.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub is_lookup_input_deterministic: bool,
+}
+
+impl Default for WasmExecutionParams {
+    fn default() -> Self {
+        Self {
+            max_stack_depth: WASM_MAX_STACK_DEPTH,
+            #[cfg(any(test, feature = "test-utils"))]
+            is_lookup_input_deterministic: false,
+        }
+    }
+}
+
+impl WasmExecutionParams {
+    /// Stack pointer of a full operand stack (the stack grows down from `STACK_CAPACITY`).
+    pub fn full_stack_pointer(&self) -> usize {
+        STACK_CAPACITY.saturating_sub(self.max_stack_depth)
+    }
+
+    /// Replaces the `lookup_input` challenge with `DETERMINISTIC_LOOKUP_INPUT` (as built by
+    /// `deterministic`) if the deterministic lookup mode is set.
+    #[allow(unused_variables)]
+    pub fn lookup_challenges<T: Clone>(
+        &self,
+        challenges: Challenges<T>,
+        deterministic: impl FnOnce(u64) -> T,
+    ) -> Challenges<T> {
+        #[cfg(any(test, feature = "test-utils"))]
+        if self.is_lookup_input_deterministic {
+            return challenges.with_lookup_input(deterministic(DETERMINISTIC_LOOKUP_INPUT));
+        }
+        challenges
+    }
+}
+
 // Number of bytes that will be used of prorgam counter. Although the maximum
 // size of execution bytecode could be at most 128kB due to the size limit of a
 // transaction, which could be covered by 3 bytes, we still support program
@@ -120,3 +184,45 @@ fn get_step_height_map() -> HashMap<ExecutionState, usize> {
     circuit.0.execution.height_map
     // Default::default()
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        evm_circuit::param::{
+            WasmExecutionParams, DETERMINISTIC_LOOKUP_INPUT, WASM_MAX_STACK_DEPTH,
+        },
+        test_util::CircuitTestBuilder,
+        util::Challenges,
+    };
+    use eth_types::bytecode;
+    use mock::TestContext;
+
+    #[test]
+    fn deterministic_lookup_input_replaces_only_the_lookup_challenge() {
+        let challenges = Challenges::mock(1u64, 2, 3);
+        let params = WasmExecutionParams::default();
+        let same = params.lookup_challenges(challenges, |v| v);
+        assert_eq!(same.indexed(), [&1, &2, &3]);
+
+        let params = WasmExecutionParams {
+            is_lookup_input_deterministic: true,
+            ..Default::default()
+        };
+        let replaced = params.lookup_challenges(challenges, |v| v);
+        assert_eq!(replaced.indexed(), [&1, &2, &DETERMINISTIC_LOOKUP_INPUT]);
+    }
+
+    #[test]
+    fn deterministic_lookup_input_circuit_is_satisfied() {
+        let code = bytecode! {
+            I32Const[1]
+            I32Const[2]
+            Drop
+            Drop
+        };
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        )
+        .run_evm_with_wasm_params::<WASM_MAX_STACK_DEPTH, true>();
+    }
+}
@@ -1,7 +1,10 @@
 use super::util::{CachedRegion, CellManager, CellType};
 use crate::{
     evm_circuit::{
-        param::{EXECUTION_STATE_HEIGHT_MAP, MAX_STEP_HEIGHT, STEP_STATE_HEIGHT, STEP_WIDTH},
+        param::{
+            EXECUTION_STATE_HEIGHT_MAP, MAX_STEP_HEIGHT, STEP_STATE_HEIGHT, STEP_WIDTH,
+            WASM_PUSH_OPCODES,
+        },
         util::Cell,
         witness::{Block, Call, ExecStep},
     },
@@ -47,18 +50,32 @@ pub enum ExecutionState {
     WASM_BIN,
     WASM_BREAK,
     WASM_CALL,
+    WASM_CALL_INDIRECT,
     WASM_CONST,
     WASM_CONVERSION,
+    WASM_DATA_DROP,
     WASM_DROP,
     WASM_END,
+    WASM_FLOAT,
     WASM_GLOBAL,
     WASM_LOAD,
     WASM_LOCAL,
+    WASM_MEMORY_BULK,
+    WASM_MEMORY_INIT,
+    WASM_MEMORY_OP,
     WASM_REL,
     WASM_SELECT,
+    WASM_SHIFT,
     WASM_STORE,
     WASM_TEST,
     WASM_UNARY,
+    WASM_ErrorMemoryOutOfBounds,
+    WASM_ErrorMemoryBulkOutOfBounds,
+    WASM_ErrorMemoryInitOutOfBounds,
+    WASM_ErrorDivByZero,
+    WASM_ErrorCallIndirect,
+    WASM_ErrorStack,
+    WASM_ErrorOutOfGas,
     // Opcode successful cases
     STOP,
     ADD_SUB, // ADD, SUB
@@ -225,6 +242,13 @@ impl ExecutionState {
                 | Self::ErrorOutOfGasSloadSstore
                 | Self::ErrorOutOfGasCREATE
                 | Self::ErrorOutOfGasSELFDESTRUCT
+                | Self::WASM_ErrorMemoryOutOfBounds
+                | Self::WASM_ErrorMemoryBulkOutOfBounds
+                | Self::WASM_ErrorMemoryInitOutOfBounds
+                | Self::WASM_ErrorDivByZero
+                | Self::WASM_ErrorCallIndirect
+                | Self::WASM_ErrorStack
+                | Self::WASM_ErrorOutOfGas
         )
     }
 
@@ -262,6 +286,12 @@ impl ExecutionState {
                 OpcodeId::I64RemS,
                 OpcodeId::I32RemU,
                 OpcodeId::I64RemU,
+                OpcodeId::I32And,
+                OpcodeId::I64And,
+                OpcodeId::I32Or,
+                OpcodeId::I64Or,
+                OpcodeId::I32Xor,
+                OpcodeId::I64Xor,
             ],
             Self::WASM_BREAK => vec![
                 OpcodeId::Return,
@@ -272,14 +302,22 @@ impl ExecutionState {
             Self::WASM_CONST => vec![
                 OpcodeId::I32Const,
                 OpcodeId::I64Const,
+                OpcodeId::F32Const,
+                OpcodeId::F64Const,
             ],
             Self::WASM_CALL => vec![
                 OpcodeId::Call,
+            ],
+            Self::WASM_CALL_INDIRECT => vec![
                 OpcodeId::CallIndirect,
             ],
             Self::WASM_DROP => vec![
                 OpcodeId::Drop,
             ],
+            Self::WASM_SELECT => vec![
+                OpcodeId::Select,
+                OpcodeId::SelectT,
+            ],
             Self::WASM_TEST => vec![
                 OpcodeId::I32Eqz,
                 OpcodeId::I64Eqz,
@@ -298,21 +336,78 @@ impl ExecutionState {
                 OpcodeId::I32Popcnt,
                 OpcodeId::I64Popcnt,
             ],
+            Self::WASM_SHIFT => vec![
+                OpcodeId::I32Shl,
+                OpcodeId::I64Shl,
+                OpcodeId::I32ShrS,
+                OpcodeId::I64ShrS,
+                OpcodeId::I32ShrU,
+                OpcodeId::I64ShrU,
+                OpcodeId::I32Rotl,
+                OpcodeId::I64Rotl,
+                OpcodeId::I32Rotr,
+                OpcodeId::I64Rotr,
+            ],
             Self::WASM_CONVERSION => vec![
                 OpcodeId::I32WrapI64,
                 OpcodeId::I64ExtendUI32,
                 OpcodeId::I64ExtendSI32,
+                OpcodeId::I32Extend8S,
+                OpcodeId::I32Extend16S,
+                OpcodeId::I64Extend8S,
+                OpcodeId::I64Extend16S,
+                OpcodeId::I64Extend32S,
             ],
             Self::WASM_GLOBAL => vec![
                 OpcodeId::GetGlobal,
                 OpcodeId::SetGlobal,
             ],
+            Self::WASM_MEMORY_OP => vec![
+                OpcodeId::CurrentMemory,
+                OpcodeId::GrowMemory,
+            ],
+            Self::WASM_MEMORY_INIT => vec![OpcodeId::MemoryInit],
+            Self::WASM_MEMORY_BULK => vec![OpcodeId::MemoryCopy, OpcodeId::MemoryFill],
+            Self::WASM_DATA_DROP => vec![OpcodeId::DataDrop],
+            Self::WASM_LOAD => vec![
+                OpcodeId::I32Load,
+                OpcodeId::I32Load8S,
+                OpcodeId::I32Load8U,
+                OpcodeId::I32Load16S,
+                OpcodeId::I32Load16U,
+                OpcodeId::I64Load,
+                OpcodeId::I64Load8S,
+                OpcodeId::I64Load8U,
+                OpcodeId::I64Load16S,
+                OpcodeId::I64Load16U,
+                OpcodeId::I64Load32S,
+                OpcodeId::I64Load32U,
+            ],
+            Self::WASM_STORE => vec![
+                OpcodeId::I32Store,
+                OpcodeId::I32Store8,
+                OpcodeId::I32Store16,
+                OpcodeId::I64Store,
+                OpcodeId::I64Store8,
+                OpcodeId::I64Store16,
+                OpcodeId::I64Store32,
+            ],
             Self::WASM_LOCAL => vec![
                 OpcodeId::GetLocal,
                 OpcodeId::SetLocal,
                 OpcodeId::TeeLocal,
             ],
             Self::WASM_END => vec![OpcodeId::End],
+            Self::WASM_FLOAT => vec![
+                OpcodeId::F32Add,
+                OpcodeId::F32Sub,
+                OpcodeId::F32Mul,
+                OpcodeId::F32Div,
+                OpcodeId::F64Add,
+                OpcodeId::F64Sub,
+                OpcodeId::F64Mul,
+                OpcodeId::F64Div,
+            ],
             // EVM opcodes
             Self::STOP => vec![OpcodeId::STOP],
             Self::MUL_DIV_MOD => vec![OpcodeId::MUL, OpcodeId::DIV, OpcodeId::MOD],
@@ -382,6 +477,45 @@ impl ExecutionState {
             Self::RETURN_REVERT => vec![OpcodeId::RETURN, OpcodeId::REVERT],
             Self::SELFDESTRUCT => vec![OpcodeId::SELFDESTRUCT],
             Self::ErrorInvalidOpcode => OpcodeId::invalid_opcodes(),
+            Self::WASM_ErrorMemoryOutOfBounds => vec![
+                OpcodeId::I32Load,
+                OpcodeId::I64Load,
+                OpcodeId::I32Load8S,
+                OpcodeId::I32Load8U,
+                OpcodeId::I32Load16S,
+                OpcodeId::I32Load16U,
+                OpcodeId::I64Load8S,
+                OpcodeId::I64Load8U,
+                OpcodeId::I64Load16S,
+                OpcodeId::I64Load16U,
+                OpcodeId::I64Load32S,
+                OpcodeId::I64Load32U,
+                OpcodeId::I32Store,
+                OpcodeId::I64Store,
+                OpcodeId::I32Store8,
+                OpcodeId::I32Store16,
+                OpcodeId::I64Store8,
+                OpcodeId::I64Store16,
+                OpcodeId::I64Store32,
+            ],
+            Self::WASM_ErrorDivByZero => vec![
+                OpcodeId::I32DivS,
+                OpcodeId::I64DivS,
+                OpcodeId::I32DivU,
+                OpcodeId::I64DivU,
+                OpcodeId::I32RemS,
+                OpcodeId::I64RemS,
+                OpcodeId::I32RemU,
+                OpcodeId::I64RemU,
+            ],
+            Self::WASM_ErrorCallIndirect => vec![OpcodeId::CallIndirect],
+            Self::WASM_ErrorStack => {
+                iter::once(OpcodeId::Drop).chain(WASM_PUSH_OPCODES).collect()
+            }
+            Self::WASM_ErrorMemoryBulkOutOfBounds => {
+                vec![OpcodeId::MemoryCopy, OpcodeId::MemoryFill]
+            }
+            Self::WASM_ErrorMemoryInitOutOfBounds => vec![OpcodeId::MemoryInit],
             _ => vec![],
         }
             .into_iter()
@@ -399,6 +533,149 @@ impl ExecutionState {
     }
 }
 
+/// Maps a single byte wasm opcode, as it appears in the binary, to the execution state of the
+/// gadget proving it, `None` if there is no gadget for the opcode. It is the mapping the witness
+/// steps are assigned with; the byte decoding is `OpcodeId::from_wasm_byte`, shared with
+/// bus-mapping.
+pub fn wasm_opcode_to_execution_state(opcode: u8) -> Option<ExecutionState> {
+    OpcodeId::from_wasm_byte(opcode).and_then(wasm_opcode_id_to_execution_state)
+}
+
+/// Same as `wasm_opcode_to_execution_state` for the prefixed (`0xfc sub_opcode`) bulk memory
+/// opcodes.
+pub fn wasm_prefixed_opcode_to_execution_state(
+    prefix: u8,
+    sub_opcode: u32,
+) -> Option<ExecutionState> {
+    OpcodeId::from_wasm_prefixed(prefix, sub_opcode).and_then(wasm_opcode_id_to_execution_state)
+}
+
+pub(crate) fn wasm_opcode_id_to_execution_state(opcode: OpcodeId) -> Option<ExecutionState> {
+    match opcode {
+        OpcodeId::I32Add |
+        OpcodeId::I64Add |
+        OpcodeId::I32Sub |
+        OpcodeId::I64Sub |
+        OpcodeId::I32Mul |
+        OpcodeId::I64Mul |
+        OpcodeId::I32DivS |
+        OpcodeId::I64DivS |
+        OpcodeId::I32DivU |
+        OpcodeId::I64DivU |
+        OpcodeId::I32RemS |
+        OpcodeId::I64RemS |
+        OpcodeId::I32RemU |
+        OpcodeId::I64RemU |
+        OpcodeId::I32And |
+        OpcodeId::I64And |
+        OpcodeId::I32Or |
+        OpcodeId::I64Or |
+        OpcodeId::I32Xor |
+        OpcodeId::I64Xor => Some(ExecutionState::WASM_BIN),
+
+        OpcodeId::I32Const |
+        OpcodeId::I64Const |
+        OpcodeId::F32Const |
+        OpcodeId::F64Const => Some(ExecutionState::WASM_CONST),
+
+        OpcodeId::Drop => Some(ExecutionState::WASM_DROP),
+
+        OpcodeId::I32Ctz |
+        OpcodeId::I64Ctz |
+        OpcodeId::I32Clz |
+        OpcodeId::I64Clz |
+        OpcodeId::I32Popcnt |
+        OpcodeId::I64Popcnt => Some(ExecutionState::WASM_UNARY),
+
+        OpcodeId::I32Eqz |
+        OpcodeId::I64Eqz => Some(ExecutionState::WASM_TEST),
+
+        OpcodeId::I32Shl |
+        OpcodeId::I64Shl |
+        OpcodeId::I32ShrS |
+        OpcodeId::I64ShrS |
+        OpcodeId::I32ShrU |
+        OpcodeId::I64ShrU |
+        OpcodeId::I32Rotl |
+        OpcodeId::I64Rotl |
+        OpcodeId::I32Rotr |
+        OpcodeId::I64Rotr => Some(ExecutionState::WASM_SHIFT),
+
+        OpcodeId::I32WrapI64 |
+        OpcodeId::I64ExtendSI32 |
+        OpcodeId::I64ExtendUI32 |
+        OpcodeId::I32Extend8S |
+        OpcodeId::I32Extend16S |
+        OpcodeId::I64Extend8S |
+        OpcodeId::I64Extend16S |
+        OpcodeId::I64Extend32S => Some(ExecutionState::WASM_CONVERSION),
+
+        OpcodeId::GetGlobal |
+        OpcodeId::SetGlobal => Some(ExecutionState::WASM_GLOBAL),
+
+        OpcodeId::GetLocal |
+        OpcodeId::SetLocal |
+        OpcodeId::TeeLocal => Some(ExecutionState::WASM_LOCAL),
+
+        OpcodeId::CurrentMemory |
+        OpcodeId::GrowMemory => Some(ExecutionState::WASM_MEMORY_OP),
+        OpcodeId::MemoryInit => Some(ExecutionState::WASM_MEMORY_INIT),
+        OpcodeId::DataDrop => Some(ExecutionState::WASM_DATA_DROP),
+        OpcodeId::MemoryCopy |
+        OpcodeId::MemoryFill => Some(ExecutionState::WASM_MEMORY_BULK),
+
+        OpcodeId::I32Load |
+        OpcodeId::I32Load8S |
+        OpcodeId::I32Load8U |
+        OpcodeId::I32Load16S |
+        OpcodeId::I32Load16U |
+        OpcodeId::I64Load |
+        OpcodeId::I64Load8S |
+        OpcodeId::I64Load8U |
+        OpcodeId::I64Load16S |
+        OpcodeId::I64Load16U |
+        OpcodeId::I64Load32S |
+        OpcodeId::I64Load32U => Some(ExecutionState::WASM_LOAD),
+
+        OpcodeId::I32Store |
+        OpcodeId::I32Store8 |
+        OpcodeId::I32Store16 |
+        OpcodeId::I64Store |
+        OpcodeId::I64Store8 |
+        OpcodeId::I64Store16 |
+        OpcodeId::I64Store32 => Some(ExecutionState::WASM_STORE),
+
+        OpcodeId::Call => Some(ExecutionState::WASM_CALL),
+        OpcodeId::CallIndirect => Some(ExecutionState::WASM_CALL_INDIRECT),
+
+        OpcodeId::Return |
+        OpcodeId::Br |
+        OpcodeId::BrIf |
+        OpcodeId::BrTable => Some(ExecutionState::WASM_BREAK),
+
+        OpcodeId::End => Some(ExecutionState::WASM_END),
+
+        OpcodeId::F32Add |
+        OpcodeId::F32Sub |
+        OpcodeId::F32Mul |
+        OpcodeId::F32Div |
+        OpcodeId::F64Add |
+        OpcodeId::F64Sub |
+        OpcodeId::F64Mul |
+        OpcodeId::F64Div => Some(ExecutionState::WASM_FLOAT),
+
+        OpcodeId::Select | OpcodeId::SelectT => Some(ExecutionState::WASM_SELECT),
+
+        OpcodeId::I32GtU | OpcodeId::I32GeU | OpcodeId::I32LtU | OpcodeId::I32LeU |
+        OpcodeId::I32Eq | OpcodeId::I32Ne | OpcodeId::I32GtS | OpcodeId::I32GeS | OpcodeId::I32LtS |
+        OpcodeId::I32LeS | OpcodeId::I64GtU | OpcodeId::I64GeU | OpcodeId::I64LtU | OpcodeId::I64LeU |
+        OpcodeId::I64Eq | OpcodeId::I64Ne | OpcodeId::I64GtS | OpcodeId::I64GeS | OpcodeId::I64LtS |
+        OpcodeId::I64LeS => Some(ExecutionState::WASM_REL),
+
+        _ => None,
+    }
+}
+
 /// Enum of Responsible opcode mapping to execution state.
 #[derive(Debug)]
 pub(crate) enum ResponsibleOp {
@@ -684,3 +961,48 @@ impl<F: FieldExt> Step<F> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::evm_types::opcode_ids::WASM_BULK_MEMORY_PREFIX;
+
+    #[test]
+    fn wasm_opcodes_map_to_their_gadgets() {
+        let wasm_states = ExecutionState::iter().filter(|state| {
+            format!("{:?}", state).starts_with("WASM_") && !state.halts_in_exception()
+        });
+        for state in wasm_states {
+            let opcodes = state.responsible_opcodes();
+            assert!(!opcodes.is_empty(), "{:?} has no responsible opcodes", state);
+            for op in opcodes {
+                let mapped_state = match op.opcode().wasm_sub_opcode() {
+                    Some(sub_opcode) => wasm_prefixed_opcode_to_execution_state(
+                        WASM_BULK_MEMORY_PREFIX,
+                        sub_opcode,
+                    ),
+                    None => wasm_opcode_to_execution_state(op.opcode().as_u8()),
+                };
+                assert_eq!(mapped_state, Some(state), "{:?}", op.opcode());
+            }
+        }
+        // the single byte trace codes of the bulk memory opcodes are not wasm opcodes
+        for opcode in [OpcodeId::MemoryInit, OpcodeId::MemoryCopy, OpcodeId::MemoryFill] {
+            assert_eq!(wasm_opcode_to_execution_state(opcode.as_u8()), None);
+        }
+        // and every mapped opcode is one the gadget is responsible for
+        for opcode in 0..=u8::MAX {
+            if let Some(state) = wasm_opcode_to_execution_state(opcode) {
+                assert!(
+                    state
+                        .responsible_opcodes()
+                        .iter()
+                        .any(|op| op.opcode().as_u8() == opcode),
+                    "{:#x} is not a responsible opcode of {:?}",
+                    opcode,
+                    state
+                );
+            }
+        }
+    }
+}
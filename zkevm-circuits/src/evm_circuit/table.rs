@@ -2,6 +2,7 @@ pub use crate::table::TxContextFieldTag;
 use crate::{
     evm_circuit::step::{ExecutionState, ResponsibleOp},
     impl_expr,
+    wasm_circuit::gas::{is_wasm_instruction, WasmGasModel},
 };
 use bus_mapping::{evm::OpcodeId, precompile::PrecompileCalls};
 use eth_types::Field;
@@ -36,11 +37,15 @@ pub enum FixedTableTag {
     Clz,
     ClzFilter,
     CzOut,
+    WasmOpcodeCost,
 }
 impl_expr!(FixedTableTag);
 
 impl FixedTableTag {
-    pub fn build<F: Field>(&self) -> Box<dyn Iterator<Item = [F; 4]>> {
+    pub fn build<F: Field>(
+        &self,
+        wasm_gas_model: &'static dyn WasmGasModel,
+    ) -> Box<dyn Iterator<Item = [F; 4]>> {
         let tag = F::from(*self as u64);
         match self {
             Self::Zero => Box::new((0..1).map(move |_| [tag, F::zero(), F::zero(), F::zero()])),
@@ -197,6 +202,18 @@ impl FixedTableTag {
                      out
                 })])
             })),
+            Self::WasmOpcodeCost => Box::new(
+                OpcodeId::iter()
+                    .filter(move |opcode| is_wasm_instruction(*opcode))
+                    .map(move |opcode| {
+                        [
+                            tag,
+                            F::from(opcode.as_u64()),
+                            F::from(wasm_gas_model.opcode_cost(opcode)),
+                            F::zero(),
+                        ]
+                    }),
+            ),
         }
     }
 }
@@ -211,6 +228,8 @@ pub(crate) enum Table {
     Copy,
     Keccak,
     Exp,
+    Float,
+    WasmMetadata,
 }
 
 #[derive(Clone, Debug)]
@@ -360,6 +379,20 @@ pub(crate) enum Lookup<F> {
         exponent_lo_hi: [Expression<F>; 2],
         exponentiation_lo_hi: [Expression<F>; 2],
     },
+    /// Lookup to float table.
+    FloatTable {
+        op: Expression<F>,
+        lhs: Expression<F>,
+        rhs: Expression<F>,
+        res: Expression<F>,
+    },
+    /// Lookup to wasm metadata table.
+    WasmMetadataTable {
+        code_hash: Expression<F>,
+        tag: Expression<F>,
+        index: Expression<F>,
+        value: Expression<F>,
+    },
     /// Conditional lookup enabled by the first element.
     Conditional(Expression<F>, Box<Lookup<F>>),
 }
@@ -379,6 +412,8 @@ impl<F: Field> Lookup<F> {
             Self::CopyTable { .. } => Table::Copy,
             Self::KeccakTable { .. } => Table::Keccak,
             Self::ExpTable { .. } => Table::Exp,
+            Self::FloatTable { .. } => Table::Float,
+            Self::WasmMetadataTable { .. } => Table::WasmMetadata,
             Self::Conditional(_, lookup) => lookup.table(),
         }
     }
@@ -501,6 +536,25 @@ impl<F: Field> Lookup<F> {
                 exponentiation_lo_hi[0].clone(),
                 exponentiation_lo_hi[1].clone(),
             ],
+            Self::FloatTable { op, lhs, rhs, res } => vec![
+                1.expr(), // q_enable
+                op.clone(),
+                lhs.clone(),
+                rhs.clone(),
+                res.clone(),
+            ],
+            Self::WasmMetadataTable {
+                code_hash,
+                tag,
+                index,
+                value,
+            } => vec![
+                1.expr(), // q_enable
+                code_hash.clone(),
+                tag.clone(),
+                index.clone(),
+                value.clone(),
+            ],
             Self::Conditional(condition, lookup) => lookup
                 .input_exprs()
                 .into_iter()
@@ -1,8 +1,13 @@
 #![allow(unused_imports)]
 pub use super::EvmCircuit;
-use crate::evm_circuit::{detect_fixed_table_tags, witness::Block};
+use crate::{
+    evm_circuit::{detect_fixed_table_tags, step::ExecutionState, witness::Block},
+    table::RwTableTag,
+    test_util::CircuitTestBuilder,
+};
 
-use eth_types::{Field, Word};
+use eth_types::{Bytecode, Field, StackWord, Word};
+use mock::TestContext;
 use rand::{
     distributions::uniform::{SampleRange, SampleUniform},
     random, thread_rng, Rng,
@@ -34,3 +39,36 @@ impl<F: Field> EvmCircuit<F> {
         EvmCircuit::<F>::new_dev(block, fixed_table_tags)
     }
 }
+
+/// runs the bytecode and checks the value pushed by every `execution_state` step, in order
+pub(crate) fn run_test_with_results(
+    execution_state: ExecutionState,
+    bytecode: Bytecode,
+    expected: Vec<u64>,
+) {
+    CircuitTestBuilder::new_from_test_ctx(
+        TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+    )
+    .block_modifier(Box::new(move |block| {
+        let results = block.txs[0]
+            .steps
+            .iter()
+            .filter(|step| step.execution_state == execution_state)
+            .map(|step| {
+                step.rw_indices
+                    .iter()
+                    .map(|idx| &block.rws[*idx])
+                    .filter(|rw| rw.tag() == RwTableTag::Stack && rw.is_write())
+                    .last()
+                    .expect("the step pushes no result")
+                    .stack_value()
+            })
+            .collect::<Vec<_>>();
+        let expected = expected
+            .iter()
+            .map(|v| StackWord::from(*v))
+            .collect::<Vec<_>>();
+        assert_eq!(results, expected);
+    }))
+    .run()
+}
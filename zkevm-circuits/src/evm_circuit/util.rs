@@ -31,6 +31,7 @@ pub(crate) mod math_gadget;
 pub(crate) mod memory_gadget;
 pub(crate) mod precompile_gadget;
 pub(crate) mod host_return_gadget;
+pub(crate) mod u64_cell;
 
 pub use gadgets::util::{and, not, or, select, sum};
 
@@ -42,6 +42,8 @@ use crate::evm_circuit::util::RandomLinearCombination;
 pub(crate) struct SameContextGadget<F> {
     opcode: Cell<F>,
     sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
+    /// Traced step cost of the WASM instruction, charged instead of the `gas_left` transition.
+    wasm_gas_cost: Option<Cell<F>>,
 }
 
 impl<F: Field> SameContextGadget<F> {
@@ -72,6 +74,27 @@ impl<F: Field> SameContextGadget<F> {
         Self {
             opcode,
             sufficient_gas_left,
+            wasm_gas_cost: None,
+        }
+    }
+
+    /// Same as [`SameContextGadget::construct`] for a WASM instruction, the step is charged its
+    /// traced cost, which has to be the cost of the opcode in the WASM step cost model.
+    pub(crate) fn construct_wasm(
+        cb: &mut EVMConstraintBuilder<F>,
+        opcode: Cell<F>,
+        step_state_transition: StepStateTransition<F>,
+    ) -> Self {
+        let gas_cost = cb.query_cell();
+        cb.wasm_opcode_cost_lookup(opcode.expr(), gas_cost.expr());
+        let step_state_transition = StepStateTransition {
+            gas_left: Delta(-gas_cost.expr()),
+            ..step_state_transition
+        };
+
+        Self {
+            wasm_gas_cost: Some(gas_cost),
+            ..Self::construct(cb, opcode, step_state_transition)
         }
     }
 
@@ -84,6 +107,9 @@ impl<F: Field> SameContextGadget<F> {
         let opcode = step.opcode.unwrap();
         self.opcode
             .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        if let Some(gas_cost) = &self.wasm_gas_cost {
+            gas_cost.assign(region, offset, Value::known(F::from(step.gas_cost)))?;
+        }
 
         self.sufficient_gas_left
             .assign(region, offset, F::from(step.gas_left - step.gas_cost))?;
@@ -1,6 +1,6 @@
 use crate::{
     evm_circuit::{
-        param::STACK_CAPACITY,
+        param::{WasmExecutionParams, STACK_CAPACITY},
         step::{ExecutionState, Step},
         table::{FixedTableTag, Lookup, RwValues},
         util::{Cell, RandomLinearCombination, Word},
@@ -306,6 +306,7 @@ pub(crate) struct EVMConstraintBuilder<'a, F> {
     constraints_location: ConstraintLocation,
     stored_expressions: Vec<StoredExpression<F>>,
     pub(crate) max_inner_degree: (&'static str, usize),
+    pub(crate) wasm_params: WasmExecutionParams,
 }
 
 impl<'a, F: Field> ConstrainBuilderCommon<F> for EVMConstraintBuilder<'a, F> {
@@ -349,6 +350,7 @@ impl<'a, F: Field> EVMConstraintBuilder<'a, F> {
             constraints_location: ConstraintLocation::Step,
             stored_expressions: Vec::new(),
             max_inner_degree: ("", 0),
+            wasm_params: WasmExecutionParams::default(),
         }
     }
 
@@ -635,6 +637,17 @@ impl<'a, F: Field> EVMConstraintBuilder<'a, F> {
         );
     }
 
+    // wasm step cost
+    pub(crate) fn wasm_opcode_cost_lookup(&mut self, opcode: Expression<F>, gas: Expression<F>) {
+        self.add_lookup(
+            "wasm opcode cost",
+            Lookup::Fixed {
+                tag: FixedTableTag::WasmOpcodeCost.expr(),
+                values: [opcode, gas, 0.expr()],
+            },
+        );
+    }
+
     // Opcode
 
     pub(crate) fn opcode_lookup(&mut self, opcode: Expression<F>, is_code: Expression<F>) {
@@ -1453,6 +1466,38 @@ impl<'a, F: Field> EVMConstraintBuilder<'a, F> {
         );
     }
 
+    // Float Table
+
+    pub(crate) fn float_table_lookup(
+        &mut self,
+        op: Expression<F>,
+        lhs: Expression<F>,
+        rhs: Expression<F>,
+        res: Expression<F>,
+    ) {
+        self.add_lookup("float lookup", Lookup::FloatTable { op, lhs, rhs, res });
+    }
+
+    // Wasm Metadata Table
+
+    /// Looks up a property of the module being executed, see `WasmMetadataTag`
+    pub(crate) fn wasm_metadata_lookup(
+        &mut self,
+        tag: Expression<F>,
+        index: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.add_lookup(
+            "wasm metadata lookup",
+            Lookup::WasmMetadataTable {
+                code_hash: self.curr.state.code_hash.expr(),
+                tag,
+                index,
+                value,
+            },
+        );
+    }
+
     // Keccak Table
 
     pub(crate) fn keccak_table_lookup(
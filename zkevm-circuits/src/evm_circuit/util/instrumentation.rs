@@ -103,6 +103,12 @@ impl Instrument {
                     CellType::Lookup(Table::Exp) => {
                         report.exp_table = data_entry;
                     }
+                    CellType::Lookup(Table::Float) => {
+                        report.float_table = data_entry;
+                    }
+                    CellType::Lookup(Table::WasmMetadata) => {
+                        report.wasm_metadata_table = data_entry;
+                    }
                 }
             }
             report_collection.push(report);
@@ -129,6 +135,8 @@ pub(crate) struct ExecStateReport {
     pub(crate) copy_table: StateReportRow,
     pub(crate) keccak_table: StateReportRow,
     pub(crate) exp_table: StateReportRow,
+    pub(crate) float_table: StateReportRow,
+    pub(crate) wasm_metadata_table: StateReportRow,
 }
 
 impl From<ExecutionState> for ExecStateReport {
@@ -14,6 +14,7 @@ use crate::{
         Advice, Column, Fixed,
     },
     table::LookupTable,
+    wasm_circuit::gas::DEFAULT_WASM_GAS_MODEL,
 };
 
 #[cfg(not(feature = "onephase"))]
@@ -243,7 +244,7 @@ impl<F: Field, G: MathGadgetContainer<F>> Circuit<F> for UnitTestMathGadgetBaseC
                                         | FixedTableTag::Range1024
                                 )
                             })
-                            .flat_map(|tag| tag.build()),
+                            .flat_map(|tag| tag.build(&DEFAULT_WASM_GAS_MODEL)),
                     )
                     .enumerate()
                 {
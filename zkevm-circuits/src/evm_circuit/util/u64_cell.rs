@@ -0,0 +1,133 @@
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{Error, Expression},
+};
+
+use crate::{
+    evm_circuit::util::{
+        constraint_builder::EVMConstraintBuilder, from_bytes, pow_of_two_expr, CachedRegion, Cell,
+    },
+    util::Expr,
+};
+
+/// 64-bit value kept as two 32-bit limbs (`lo`, `hi`). Every limb is composed from 4 byte cells,
+/// so the value is range checked to `[0, 2^64)` by the byte lookup.
+#[derive(Clone, Debug)]
+pub(crate) struct U64Cell<F> {
+    /// little-endian bytes of the value
+    bytes: [Cell<F>; 8],
+}
+
+impl<F: Field> U64Cell<F> {
+    pub(crate) fn construct(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        Self {
+            bytes: cb.query_bytes(),
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> &[Cell<F>; 8] {
+        &self.bytes
+    }
+
+    /// low 32 bits of the value
+    pub(crate) fn lo(&self) -> Expression<F> {
+        from_bytes::expr(&self.bytes[..4])
+    }
+
+    /// high 32 bits of the value
+    pub(crate) fn hi(&self) -> Expression<F> {
+        from_bytes::expr(&self.bytes[4..])
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        value: u64,
+    ) -> Result<(), Error> {
+        for (byte, cell) in value.to_le_bytes().iter().zip(self.bytes.iter()) {
+            cell.assign(region, offset, Value::known(F::from(*byte as u64)))?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> Expr<F> for U64Cell<F> {
+    fn expr(&self) -> Expression<F> {
+        self.lo() + self.hi() * pow_of_two_expr(32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_types::{Field, ToScalar, Word};
+    use halo2_proofs::{circuit::Value, halo2curves::bn256::Fr, plonk::Error};
+
+    use crate::{
+        evm_circuit::util::{
+            constraint_builder::{ConstrainBuilderCommon, EVMConstraintBuilder},
+            math_gadget::test_util::*,
+            u64_cell::U64Cell,
+            CachedRegion, Cell,
+        },
+        util::Expr,
+    };
+
+    #[derive(Clone)]
+    /// U64CellTestContainer: require(a == u64_cell), the cell gets the low 64 bits of `a`
+    struct U64CellTestContainer<F> {
+        u64_cell: U64Cell<F>,
+        a: Cell<F>,
+    }
+
+    impl<F: Field> MathGadgetContainer<F> for U64CellTestContainer<F> {
+        fn configure_gadget_container(cb: &mut EVMConstraintBuilder<F>) -> Self {
+            let a = cb.query_cell();
+            let u64_cell = U64Cell::construct(cb);
+            cb.require_equal("a is the u64 cell value", a.expr(), u64_cell.expr());
+            U64CellTestContainer { u64_cell, a }
+        }
+
+        fn assign_gadget_container(
+            &self,
+            witnesses: &[Word],
+            region: &mut CachedRegion<'_, '_, F>,
+        ) -> Result<(), Error> {
+            let a = witnesses[0];
+            let offset = 0;
+
+            self.a
+                .assign(region, offset, Value::known(a.to_scalar().unwrap()))?;
+            self.u64_cell.assign(region, offset, a.low_u64())?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_u64_cell_in_range() {
+        try_test!(U64CellTestContainer<Fr>, vec![Word::from(0)], true);
+        try_test!(
+            U64CellTestContainer<Fr>,
+            vec![Word::from(0xffff_ffffu64)],
+            true
+        );
+        try_test!(U64CellTestContainer<Fr>, vec![Word::from(1u64 << 32)], true);
+        try_test!(U64CellTestContainer<Fr>, vec![Word::from(u64::MAX)], true);
+    }
+
+    #[test]
+    fn test_u64_cell_above_2_pow_64_rejected() {
+        try_test!(
+            U64CellTestContainer<Fr>,
+            vec![Word::from(u64::MAX) + 1],
+            false
+        );
+        try_test!(
+            U64CellTestContainer<Fr>,
+            vec![(Word::from(1) << 64) + 0x1234],
+            false
+        );
+    }
+}
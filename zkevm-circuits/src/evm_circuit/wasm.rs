@@ -1,14 +1,17 @@
 use super::{
     param::{
         BLOCK_TABLE_LOOKUPS, BYTECODE_TABLE_LOOKUPS, COPY_TABLE_LOOKUPS, EXP_TABLE_LOOKUPS,
-        FIXED_TABLE_LOOKUPS, KECCAK_TABLE_LOOKUPS, N_BYTE_LOOKUPS, N_COPY_COLUMNS,
+        FIXED_TABLE_LOOKUPS, FLOAT_TABLE_LOOKUPS, KECCAK_TABLE_LOOKUPS, N_BYTE_LOOKUPS, N_COPY_COLUMNS,
         N_PHASE1_COLUMNS, RW_TABLE_LOOKUPS, TX_TABLE_LOOKUPS,
+        WASM_METADATA_TABLE_LOOKUPS,
     },
     util::{instrumentation::Instrument, CachedRegion, CellManager, StoredExpression},
 };
 use crate::{
     evm_circuit::{
-        param::{EVM_LOOKUP_COLS, MAX_STEP_HEIGHT, N_PHASE2_COLUMNS, STEP_WIDTH},
+        param::{
+            WasmExecutionParams, EVM_LOOKUP_COLS, MAX_STEP_HEIGHT, N_PHASE2_COLUMNS, STEP_WIDTH,
+        },
         step::{ExecutionState, Step},
         table::Table,
         util::{
@@ -57,16 +60,23 @@ mod common_block_ctx;
 mod common_dummy;
 mod common_end_block;
 mod common_end_tx;
+mod error_call_indirect;
+mod error_div_by_zero;
 mod error_invalid_jump;
 mod error_invalid_opcode;
+mod error_memory_bulk_out_of_bounds;
+mod error_memory_init_out_of_bounds;
+mod error_memory_out_of_bounds;
 mod error_oog_call;
 mod error_oog_constant;
 mod error_oog_exp;
 mod error_oog_log;
 mod error_oog_sload_sstore;
 mod error_oog_static_memory;
+mod error_oog_wasm;
 mod error_return_data_oo_bound;
 mod error_stack;
+mod error_stack_underflow;
 mod error_write_protection;
 mod evm_address;
 mod evm_balance;
@@ -100,16 +110,23 @@ mod evm_stop;
 mod wasm_bin;
 mod wasm_break;
 mod wasm_call;
+mod wasm_call_indirect;
 mod wasm_const;
 mod wasm_conversion;
+mod wasm_data_drop;
 mod wasm_drop;
 mod wasm_end;
+mod wasm_float;
 mod wasm_global;
-// mod wasm_load;
+mod wasm_load;
 mod wasm_local;
+mod wasm_memory_bulk;
+mod wasm_memory_init;
+mod wasm_memory_op;
 mod wasm_rel;
 mod wasm_select;
-// mod wasm_store;
+mod wasm_shift;
+mod wasm_store;
 mod wasm_test;
 mod wasm_unary;
 
@@ -118,16 +135,23 @@ use common_begin_tx::CommonBeginTxGadget;
 use common_dummy::CommonDummyGadget;
 use common_end_block::CommonEndBlockGadget;
 use common_end_tx::CommonEndTxGadget;
+use error_call_indirect::ErrorCallIndirectGadget;
+use error_div_by_zero::ErrorDivByZeroGadget;
 use error_invalid_jump::ErrorInvalidJumpGadget;
 use error_invalid_opcode::ErrorInvalidOpcodeGadget;
+use error_memory_bulk_out_of_bounds::ErrorMemoryBulkOutOfBoundsGadget;
+use error_memory_init_out_of_bounds::ErrorMemoryInitOutOfBoundsGadget;
+use error_memory_out_of_bounds::ErrorMemoryOutOfBoundsGadget;
 use error_oog_call::ErrorOOGCallGadget;
 use error_oog_constant::ErrorOOGConstantGadget;
 use error_oog_exp::ErrorOOGExpGadget;
 use error_oog_log::ErrorOOGLogGadget;
 use error_oog_sload_sstore::ErrorOOGSloadSstoreGadget;
 // use error_oog_static_memory::ErrorOOGStaticMemoryGadget;
+use error_oog_wasm::ErrorOOGWasmGadget;
 use error_return_data_oo_bound::ErrorReturnDataOutOfBoundGadget;
 use error_stack::ErrorStackGadget;
+use error_stack_underflow::ErrorStackUnderflowGadget;
 use error_write_protection::ErrorWriteProtectionGadget;
 use evm_address::EvmAddressGadget;
 use evm_balance::EvmBalanceGadget;
@@ -161,16 +185,23 @@ use evm_stop::EvmStopGadget;
 use wasm_bin::WasmBinGadget;
 use wasm_break::WasmBreakGadget;
 use wasm_call::WasmCallGadget;
+use wasm_call_indirect::WasmCallIndirectGadget;
 use wasm_const::WasmConstGadget;
 use wasm_conversion::WasmConversionGadget;
+use wasm_data_drop::WasmDataDropGadget;
 use wasm_drop::WasmDropGadget;
 use wasm_end::WasmEndGadget;
+use wasm_float::WasmFloatGadget;
 use wasm_global::WasmGlobalGadget;
-// use wasm_load::WasmLoadGadget;
+use wasm_load::WasmLoadGadget;
 use wasm_local::WasmLocalGadget;
+use wasm_memory_bulk::WasmMemoryBulkGadget;
+use wasm_memory_init::WasmMemoryInitGadget;
+use wasm_memory_op::WasmMemoryOpGadget;
 use wasm_rel::WasmRelGadget;
 use wasm_select::WasmSelectGadget;
-// use wasm_store::WasmStoreGadget;
+use wasm_shift::WasmShiftGadget;
+use wasm_store::WasmStoreGadget;
 use wasm_test::WasmTestGadget;
 use wasm_unary::WasmUnaryGadget;
 use crate::evm_circuit::EvmCircuitExports;
@@ -228,6 +259,7 @@ pub(crate) struct ExecutionConfig<F> {
     pub(crate) height_map: HashMap<ExecutionState, usize>,
     stored_expressions_map: HashMap<ExecutionState, Vec<StoredExpression<F>>>,
     instrument: Instrument,
+    wasm_params: WasmExecutionParams,
 
     // Common Gadgets
     common_begin_tx: Box<CommonBeginTxGadget<F>>,
@@ -245,6 +277,8 @@ pub(crate) struct ExecutionConfig<F> {
     error_oog_sload_sstore: Box<ErrorOOGSloadSstoreGadget<F>>,
     error_oog_static_memory_gadget: Box<CommonDummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasStaticMemoryExpansion }>>,
     error_stack: Box<ErrorStackGadget<F>>,
+    error_stack_underflow: Box<ErrorStackUnderflowGadget<F>>,
+    error_oog_wasm: Box<ErrorOOGWasmGadget<F>>,
     error_write_protection: Box<ErrorWriteProtectionGadget<F>>,
     error_oog_dynamic_memory_gadget: Box<CommonDummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasDynamicMemoryExpansion }>>,
     error_oog_log: Box<ErrorOOGLogGadget<F>>,
@@ -254,8 +288,13 @@ pub(crate) struct ExecutionConfig<F> {
     error_code_store: Box<ErrorCodeStoreGadget<F>>,
     #[cfg(not(feature = "scroll"))]
     error_oog_self_destruct: Box<CommonDummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSELFDESTRUCT }>>,
+    error_call_indirect: Box<ErrorCallIndirectGadget<F>>,
+    error_div_by_zero: Box<ErrorDivByZeroGadget<F>>,
     error_invalid_jump: Box<ErrorInvalidJumpGadget<F>>,
     error_invalid_opcode: Box<ErrorInvalidOpcodeGadget<F>>,
+    error_memory_out_of_bounds: Box<ErrorMemoryOutOfBoundsGadget<F>>,
+    error_memory_bulk_out_of_bounds: Box<ErrorMemoryBulkOutOfBoundsGadget<F>>,
+    error_memory_init_out_of_bounds: Box<ErrorMemoryInitOutOfBoundsGadget<F>>,
     error_invalid_creation_code: Box<ErrorInvalidCreationCodeGadget<F>>,
     error_precompile_failed: Box<ErrorPrecompileFailedGadget<F>>,
     error_return_data_out_of_bound: Box<ErrorReturnDataOutOfBoundGadget<F>>,
@@ -295,16 +334,23 @@ pub(crate) struct ExecutionConfig<F> {
     wasm_bin: Box<WasmBinGadget<F>>,
     wasm_break: Box<WasmBreakGadget<F>>,
     wasm_call: Box<WasmCallGadget<F>>,
+    wasm_call_indirect: Box<WasmCallIndirectGadget<F>>,
     wasm_const: Box<WasmConstGadget<F>>,
     wasm_conversion: Box<WasmConversionGadget<F>>,
+    wasm_data_drop: Box<WasmDataDropGadget<F>>,
     wasm_drop: Box<WasmDropGadget<F>>,
     wasm_end: Box<WasmEndGadget<F>>,
+    wasm_float: Box<WasmFloatGadget<F>>,
     wasm_global: Box<WasmGlobalGadget<F>>,
-    // wasm_load: Box<WasmLoadGadget<F>>,
+    wasm_load: Box<WasmLoadGadget<F>>,
     wasm_local: Box<WasmLocalGadget<F>>,
+    wasm_memory_bulk: Box<WasmMemoryBulkGadget<F>>,
+    wasm_memory_init: Box<WasmMemoryInitGadget<F>>,
+    wasm_memory_op: Box<WasmMemoryOpGadget<F>>,
     wasm_rel: Box<WasmRelGadget<F>>,
     wasm_select: Box<WasmSelectGadget<F>>,
-    // wasm_store: Box<WasmStoreGadget<F>>,
+    wasm_shift: Box<WasmShiftGadget<F>>,
+    wasm_store: Box<WasmStoreGadget<F>>,
     wasm_test: Box<WasmTestGadget<F>>,
     wasm_unary: Box<WasmUnaryGadget<F>>,
 }
@@ -324,7 +370,11 @@ impl<F: Field> ExecutionConfig<F> {
         copy_table: &dyn LookupTable<F>,
         keccak_table: &dyn LookupTable<F>,
         exp_table: &dyn LookupTable<F>,
+        float_table: &dyn LookupTable<F>,
+        wasm_metadata_table: &dyn LookupTable<F>,
+        wasm_params: WasmExecutionParams,
     ) -> Self {
+        let challenges = wasm_params.lookup_challenges(challenges, |v| v.expr());
         let mut instrument = Instrument::default();
         let q_usable = meta.complex_selector();
         let q_step = meta.advice_column();
@@ -466,6 +516,7 @@ impl<F: Field> ExecutionConfig<F> {
                         &mut height_map,
                         &mut stored_expressions_map,
                         &mut instrument,
+                        wasm_params,
                     ))
                 })()
             };
@@ -492,6 +543,8 @@ impl<F: Field> ExecutionConfig<F> {
             error_oog_constant: configure_gadget!(),
             error_oog_static_memory_gadget: configure_gadget!(),
             error_stack: configure_gadget!(),
+            error_stack_underflow: configure_gadget!(),
+            error_oog_wasm: configure_gadget!(),
             error_oog_dynamic_memory_gadget: configure_gadget!(),
             error_oog_log: configure_gadget!(),
             error_oog_sload_sstore: configure_gadget!(),
@@ -504,8 +557,13 @@ impl<F: Field> ExecutionConfig<F> {
             #[cfg(not(feature = "scroll"))]
             error_oog_self_destruct: configure_gadget!(),
             error_code_store: configure_gadget!(),
+            error_call_indirect: configure_gadget!(),
+            error_div_by_zero: configure_gadget!(),
             error_invalid_jump: configure_gadget!(),
             error_invalid_opcode: configure_gadget!(),
+            error_memory_out_of_bounds: configure_gadget!(),
+            error_memory_bulk_out_of_bounds: configure_gadget!(),
+            error_memory_init_out_of_bounds: configure_gadget!(),
             error_write_protection: configure_gadget!(),
             error_invalid_creation_code: configure_gadget!(),
             error_return_data_out_of_bound: configure_gadget!(),
@@ -542,16 +600,23 @@ impl<F: Field> ExecutionConfig<F> {
             wasm_bin: configure_gadget!(),
             wasm_break: configure_gadget!(),
             wasm_call: configure_gadget!(),
+            wasm_call_indirect: configure_gadget!(),
             wasm_const: configure_gadget!(),
             wasm_conversion: configure_gadget!(),
+            wasm_data_drop: configure_gadget!(),
             wasm_drop: configure_gadget!(),
             wasm_end: configure_gadget!(),
+            wasm_float: configure_gadget!(),
             wasm_global: configure_gadget!(),
-            // wasm_load: configure_gadget!(),
+            wasm_load: configure_gadget!(),
             wasm_local: configure_gadget!(),
+            wasm_memory_bulk: configure_gadget!(),
+            wasm_memory_init: configure_gadget!(),
+            wasm_memory_op: configure_gadget!(),
             wasm_rel: configure_gadget!(),
             wasm_select: configure_gadget!(),
-            // wasm_store: configure_gadget!(),
+            wasm_shift: configure_gadget!(),
+            wasm_store: configure_gadget!(),
             wasm_test: configure_gadget!(),
             wasm_unary: configure_gadget!(),
 
@@ -560,6 +625,7 @@ impl<F: Field> ExecutionConfig<F> {
             height_map,
             stored_expressions_map,
             instrument,
+            wasm_params,
         };
 
         Self::configure_lookup(
@@ -573,6 +639,8 @@ impl<F: Field> ExecutionConfig<F> {
             copy_table,
             keccak_table,
             exp_table,
+            float_table,
+            wasm_metadata_table,
             &challenges,
             &cell_manager,
         );
@@ -597,6 +665,7 @@ impl<F: Field> ExecutionConfig<F> {
         height_map: &mut HashMap<ExecutionState, usize>,
         stored_expressions_map: &mut HashMap<ExecutionState, Vec<StoredExpression<F>>>,
         instrument: &mut Instrument,
+        wasm_params: WasmExecutionParams,
     ) -> G {
         // Configure the gadget with the max height first so we can find out the actual
         // height
@@ -608,6 +677,7 @@ impl<F: Field> ExecutionConfig<F> {
                 challenges,
                 G::EXECUTION_STATE,
             );
+            cb.wasm_params = wasm_params;
             G::configure(&mut cb);
             let (_, _, height) = cb.build();
             height
@@ -621,6 +691,7 @@ impl<F: Field> ExecutionConfig<F> {
             challenges,
             G::EXECUTION_STATE,
         );
+        cb.wasm_params = wasm_params;
 
         let gadget = G::configure(&mut cb);
 
@@ -828,6 +899,8 @@ impl<F: Field> ExecutionConfig<F> {
         copy_table: &dyn LookupTable<F>,
         keccak_table: &dyn LookupTable<F>,
         exp_table: &dyn LookupTable<F>,
+        float_table: &dyn LookupTable<F>,
+        wasm_metadata_table: &dyn LookupTable<F>,
         challenges: &Challenges<Expression<F>>,
         cell_manager: &CellManager<F>,
     ) {
@@ -844,6 +917,8 @@ impl<F: Field> ExecutionConfig<F> {
                         Table::Copy => copy_table,
                         Table::Keccak => keccak_table,
                         Table::Exp => exp_table,
+                        Table::Float => float_table,
+                        Table::WasmMetadata => wasm_metadata_table,
                     }
                     .table_exprs(meta);
                     vec![(
@@ -927,6 +1002,9 @@ impl<F: Field> ExecutionConfig<F> {
         block: &Block<F>,
         challenges: &Challenges<Value<F>>,
     ) -> Result<EvmCircuitExports<Assigned<F>>, Error> {
+        let challenges = &self
+            .wasm_params
+            .lookup_challenges(challenges.clone(), |v| Value::known(F::from(v)));
         let mut is_first_time = true;
 
         layouter.assign_region(
@@ -1143,6 +1221,8 @@ impl<F: Field> ExecutionConfig<F> {
             ("EVM_lookup_copy", COPY_TABLE_LOOKUPS),
             ("EVM_lookup_keccak", KECCAK_TABLE_LOOKUPS),
             ("EVM_lookup_exp", EXP_TABLE_LOOKUPS),
+            ("EVM_lookup_float", FLOAT_TABLE_LOOKUPS),
+            ("EVM_lookup_wasm_metadata", WASM_METADATA_TABLE_LOOKUPS),
             ("EVM_adv_phase2", N_PHASE2_COLUMNS),
             ("EVM_copy", N_COPY_COLUMNS),
             ("EVM_lookup_byte", N_BYTE_LOOKUPS),
@@ -1294,12 +1374,21 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::WASM_DROP => assign_exec_step!(self.wasm_drop),
             ExecutionState::WASM_GLOBAL => assign_exec_step!(self.wasm_global),
             ExecutionState::WASM_LOCAL => assign_exec_step!(self.wasm_local),
+            ExecutionState::WASM_MEMORY_OP => assign_exec_step!(self.wasm_memory_op),
+            ExecutionState::WASM_MEMORY_INIT => assign_exec_step!(self.wasm_memory_init),
+            ExecutionState::WASM_MEMORY_BULK => assign_exec_step!(self.wasm_memory_bulk),
+            ExecutionState::WASM_DATA_DROP => assign_exec_step!(self.wasm_data_drop),
             ExecutionState::WASM_UNARY => assign_exec_step!(self.wasm_unary),
+            ExecutionState::WASM_SHIFT => assign_exec_step!(self.wasm_shift),
             ExecutionState::WASM_CONVERSION => assign_exec_step!(self.wasm_conversion),
             ExecutionState::WASM_REL => assign_exec_step!(self.wasm_rel),
             ExecutionState::WASM_END => assign_exec_step!(self.wasm_end),
+            ExecutionState::WASM_FLOAT => assign_exec_step!(self.wasm_float),
             ExecutionState::WASM_BREAK => assign_exec_step!(self.wasm_break),
             ExecutionState::WASM_CALL => assign_exec_step!(self.wasm_call),
+            ExecutionState::WASM_CALL_INDIRECT => assign_exec_step!(self.wasm_call_indirect),
+            ExecutionState::WASM_LOAD => assign_exec_step!(self.wasm_load),
+            ExecutionState::WASM_STORE => assign_exec_step!(self.wasm_store),
             // opcode
             ExecutionState::SHA3 => assign_exec_step!(self.evm_keccak256),
             ExecutionState::ADDRESS => assign_exec_step!(self.evm_address),
@@ -1395,6 +1484,27 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::ErrorInvalidOpcode => {
                 assign_exec_step!(self.error_invalid_opcode)
             }
+            ExecutionState::WASM_ErrorMemoryOutOfBounds => {
+                assign_exec_step!(self.error_memory_out_of_bounds)
+            }
+            ExecutionState::WASM_ErrorMemoryBulkOutOfBounds => {
+                assign_exec_step!(self.error_memory_bulk_out_of_bounds)
+            }
+            ExecutionState::WASM_ErrorMemoryInitOutOfBounds => {
+                assign_exec_step!(self.error_memory_init_out_of_bounds)
+            }
+            ExecutionState::WASM_ErrorDivByZero => {
+                assign_exec_step!(self.error_div_by_zero)
+            }
+            ExecutionState::WASM_ErrorCallIndirect => {
+                assign_exec_step!(self.error_call_indirect)
+            }
+            ExecutionState::WASM_ErrorStack => {
+                assign_exec_step!(self.error_stack_underflow)
+            }
+            ExecutionState::WASM_ErrorOutOfGas => {
+                assign_exec_step!(self.error_oog_wasm)
+            }
             ExecutionState::ErrorWriteProtection => {
                 assign_exec_step!(self.error_write_protection)
             }
@@ -0,0 +1,217 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::{IsEqualGadget, LtGadget},
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::WasmMetadataTag,
+    util::Expr,
+    wasm_circuit::host_interface::helpers::{extract_func_type_indexes, extract_table_elem_func_indexes},
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for `call_indirect` trapping because the popped element index is past the table
+/// elements or the function it holds has another type than the type immediate.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorCallIndirectGadget<F> {
+    opcode: Cell<F>,
+    elem_index: Cell<F>,
+    type_index: Cell<F>,
+    elems_count: Cell<F>,
+    /// Function stored at `elem_index` and its declared type index, zero if the element is
+    /// undefined.
+    func_index: Cell<F>,
+    func_type_index: Cell<F>,
+    is_defined: LtGadget<F, 8>,
+    is_same_type: IsEqualGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorCallIndirectGadget<F> {
+    const NAME: &'static str = "WASM_ErrorCallIndirect";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorCallIndirect;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let elem_index = cb.alloc_u64();
+        let type_index = cb.query_cell();
+        let elems_count = cb.query_cell();
+        let func_index = cb.query_cell();
+        let func_type_index = cb.query_cell();
+
+        cb.require_equal(
+            "call_indirect_error: opcode is call_indirect",
+            opcode.expr(),
+            OpcodeId::CallIndirect.expr(),
+        );
+
+        cb.stack_pop(elem_index.expr());
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::CallIndirectTypeIndex.expr(),
+            cb.curr.state.program_counter.expr(),
+            type_index.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::TableElemFuncsCount.expr(),
+            0.expr(),
+            elems_count.expr(),
+        );
+        let is_defined = LtGadget::construct(cb, elem_index.expr(), elems_count.expr());
+        cb.condition(is_defined.expr(), |cb| {
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::TableElemFunc.expr(),
+                elem_index.expr(),
+                func_index.expr(),
+            );
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::FuncTypeIndex.expr(),
+                func_index.expr(),
+                func_type_index.expr(),
+            );
+        });
+        let is_same_type = IsEqualGadget::construct(cb, func_type_index.expr(), type_index.expr());
+        cb.require_zero(
+            "call_indirect_error: the element is undefined or its type differs",
+            is_defined.expr() * is_same_type.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 3.expr());
+
+        Self {
+            opcode,
+            elem_index,
+            type_index,
+            elems_count,
+            func_index,
+            func_type_index,
+            is_defined,
+            is_same_type,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let elem_index = block.rws[step.rw_indices[0]].stack_value();
+        self.elem_index.assign(region, offset, Value::known(elem_index.to_scalar().unwrap()))?;
+        let type_index = *step.params.first().expect("call_indirect type index immediate is missing");
+        self.type_index.assign(region, offset, Value::known(F::from(type_index)))?;
+
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let func_indexes = extract_table_elem_func_indexes(&bytecode.bytes)
+            .expect("could not parse the element section");
+        let elems_count = func_indexes.len() as u64;
+        self.elems_count.assign(region, offset, Value::known(F::from(elems_count)))?;
+        self.is_defined.assign(
+            region,
+            offset,
+            F::from(elem_index.as_u64()),
+            F::from(elems_count),
+        )?;
+
+        let (func_index, func_type_index) = match func_indexes.get(elem_index.as_usize()) {
+            Some(&func_index) => {
+                let func_type_index = *extract_func_type_indexes(&bytecode.bytes)
+                    .expect("could not parse the function types")
+                    .get(func_index as usize)
+                    .expect("call_indirect callee is undefined");
+                (func_index, func_type_index)
+            }
+            None => (0, 0),
+        };
+        self.func_index.assign(region, offset, Value::known(F::from(func_index)))?;
+        self.func_type_index.assign(region, offset, Value::known(F::from(func_type_index)))?;
+        self.is_same_type.assign(
+            region,
+            offset,
+            F::from(func_type_index),
+            F::from(type_index),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 3)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wasm_encoder::ValType;
+
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder};
+
+    /// runs the bytecode and checks that it ends with the `call_indirect` trap
+    fn run_trap_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorCallIndirect);
+            assert!(has_error_step);
+        })).run()
+    }
+
+    #[test]
+    fn test_call_indirect_undefined_element_traps() {
+        let mut code = bytecode! {
+            I32Const[1]
+            CallIndirect[0]
+        };
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        code.with_table_elements(vec![0]);
+        run_trap_test(code);
+    }
+
+    #[test]
+    fn test_call_indirect_type_mismatch_traps() {
+        // the element holds a `[i32; 2] -> [i32]` function, the immediate is the type 0
+        // `[] -> []`
+        let mut code = bytecode! {
+            I32Const[0]
+            CallIndirect[0]
+        };
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        code.new_function(vec![ValType::I32; 2], vec![ValType::I32; 1], bytecode! {
+            GetLocal[0]
+            GetLocal[1]
+            I32Add
+        }, vec![]);
+        code.with_table_elements(vec![1]);
+        run_trap_test(code);
+    }
+}
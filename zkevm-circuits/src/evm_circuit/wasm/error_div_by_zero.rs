@@ -0,0 +1,223 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::{IsEqualGadget, IsZeroGadget},
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for the integer division traps: div/rem by zero and the signed `MIN / -1` overflow.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorDivByZeroGadget<F> {
+    opcode: Cell<F>,
+    lhs: Cell<F>,
+    rhs: Cell<F>,
+    is_div_u: Cell<F>,
+    is_rem_u: Cell<F>,
+    is_div_s: Cell<F>,
+    is_rem_s: Cell<F>,
+    is_64bits: Cell<F>,
+    rhs_is_zero: IsZeroGadget<F>,
+    lhs_is_min: IsEqualGadget<F>,
+    rhs_is_minus_one: IsEqualGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorDivByZeroGadget<F> {
+    const NAME: &'static str = "WASM_ErrorDivByZero";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorDivByZero;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let lhs = cb.alloc_u64();
+        let rhs = cb.alloc_u64();
+
+        let is_div_u = cb.alloc_bit_value();
+        let is_rem_u = cb.alloc_bit_value();
+        let is_div_s = cb.alloc_bit_value();
+        let is_rem_s = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+
+        for (name, bit) in [
+            ("div_by_zero: is_div_u is bool", &is_div_u),
+            ("div_by_zero: is_rem_u is bool", &is_rem_u),
+            ("div_by_zero: is_div_s is bool", &is_div_s),
+            ("div_by_zero: is_rem_s is bool", &is_rem_s),
+            ("div_by_zero: is_64bits is bool", &is_64bits),
+        ] {
+            cb.require_boolean(name, bit.expr());
+        }
+        cb.require_equal(
+            "div_by_zero: selector",
+            is_div_u.expr() + is_rem_u.expr() + is_div_s.expr() + is_rem_s.expr(),
+            1.expr(),
+        );
+
+        // Selectors are bound to the opcode, so the overflow trap can't be claimed for a rem_s.
+        let select_op = |op32: OpcodeId, op64: OpcodeId| {
+            op32.expr() + (op64.expr() - op32.expr()) * is_64bits.expr()
+        };
+        cb.require_equal(
+            "div_by_zero: opcode matches selectors",
+            opcode.expr(),
+            is_div_u.expr() * select_op(OpcodeId::I32DivU, OpcodeId::I64DivU) +
+                is_rem_u.expr() * select_op(OpcodeId::I32RemU, OpcodeId::I64RemU) +
+                is_div_s.expr() * select_op(OpcodeId::I32DivS, OpcodeId::I64DivS) +
+                is_rem_s.expr() * select_op(OpcodeId::I32RemS, OpcodeId::I64RemS),
+        );
+
+        cb.stack_pop(rhs.expr());
+        cb.stack_pop(lhs.expr());
+
+        let int_min = 0x80000000_u64.expr() + (0x80000000_00000000_u64 - 0x80000000_u64).expr() * is_64bits.expr();
+        let minus_one = 0xffffffff_u64.expr() + (u64::MAX - 0xffffffff_u64).expr() * is_64bits.expr();
+        let rhs_is_zero = IsZeroGadget::construct(cb, rhs.expr());
+        let lhs_is_min = IsEqualGadget::construct(cb, lhs.expr(), int_min);
+        let rhs_is_minus_one = IsEqualGadget::construct(cb, rhs.expr(), minus_one);
+
+        // Both traps can't happen at once since `-1` is not zero.
+        cb.require_equal(
+            "div_by_zero: division traps",
+            rhs_is_zero.expr() + is_div_s.expr() * lhs_is_min.expr() * rhs_is_minus_one.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 4.expr());
+
+        Self {
+            opcode,
+            lhs,
+            rhs,
+            is_div_u,
+            is_rem_u,
+            is_div_s,
+            is_rem_s,
+            is_64bits,
+            rhs_is_zero,
+            lhs_is_min,
+            rhs_is_minus_one,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let (selector, is_64bits) = match opcode {
+            OpcodeId::I32DivU => (&self.is_div_u, false),
+            OpcodeId::I64DivU => (&self.is_div_u, true),
+            OpcodeId::I32RemU => (&self.is_rem_u, false),
+            OpcodeId::I64RemU => (&self.is_rem_u, true),
+            OpcodeId::I32DivS => (&self.is_div_s, false),
+            OpcodeId::I64DivS => (&self.is_div_s, true),
+            OpcodeId::I32RemS => (&self.is_rem_s, false),
+            OpcodeId::I64RemS => (&self.is_rem_s, true),
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
+
+        let [rhs, lhs] = [step.rw_indices[0], step.rw_indices[1]]
+            .map(|idx| block.rws[idx].stack_value());
+        self.lhs.assign(region, offset, Value::known(lhs.to_scalar().unwrap()))?;
+        self.rhs.assign(region, offset, Value::known(rhs.to_scalar().unwrap()))?;
+
+        let (int_min, minus_one) = if is_64bits {
+            (i64::MIN as u64, u64::MAX)
+        } else {
+            (i32::MIN as u32 as u64, u32::MAX as u64)
+        };
+        self.rhs_is_zero.assign(region, offset, F::from(rhs.as_u64()))?;
+        self.lhs_is_min.assign(region, offset, F::from(lhs.as_u64()), F::from(int_min))?;
+        self.rhs_is_minus_one.assign(region, offset, F::from(rhs.as_u64()), F::from(minus_one))?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 4)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder};
+
+    /// runs the bytecode and checks that it ends with the division trap
+    fn run_trap_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorDivByZero);
+            assert!(has_error_step);
+        })).run()
+    }
+
+    #[test]
+    fn test_i32_div_u_by_zero() {
+        run_trap_test(bytecode! {
+            I32Const[7]
+            I32Const[0]
+            I32DivU
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_i64_rem_s_by_zero() {
+        run_trap_test(bytecode! {
+            I64Const[7]
+            I64Const[0]
+            I64RemS
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_i32_div_s_overflow() {
+        run_trap_test(bytecode! {
+            I32Const[0x80000000]
+            I32Const[0xffffffff]
+            I32DivS
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_i64_div_s_overflow() {
+        run_trap_test(bytecode! {
+            I64Const[0x8000000000000000]
+            I64Const[0xffffffffffffffff]
+            I64DivS
+            Drop
+        });
+    }
+}
@@ -78,7 +78,7 @@ mod test {
             vec![0x0e],
             vec![0x4f],
             vec![0xa5],
-            vec![0xf6],
+            vec![0xf8],
             vec![0xfe],
             // Multiple invalid opcodes
             vec![0x5c, 0x5e, 0x5f],
@@ -0,0 +1,185 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::PAGE_SIZE,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::LtGadget,
+            or,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for `memory.copy` and `memory.fill` trapping because the destination (or the copy
+/// source) range goes past the current memory size.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorMemoryBulkOutOfBoundsGadget<F> {
+    opcode: Cell<F>,
+    is_copy: Cell<F>,
+    length: Cell<F>,
+    src_or_value: Cell<F>,
+    dst: Cell<F>,
+    dst_out_of_bounds: LtGadget<F, 8>,
+    src_out_of_bounds: LtGadget<F, 8>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorMemoryBulkOutOfBoundsGadget<F> {
+    const NAME: &'static str = "WASM_ErrorMemoryBulkOutOfBounds";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorMemoryBulkOutOfBounds;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let is_copy = cb.alloc_bit_value();
+        let length = cb.alloc_u64();
+        let src_or_value = cb.alloc_u64();
+        let dst = cb.alloc_u64();
+
+        cb.require_boolean("memory_bulk_oob: is_copy is bool", is_copy.expr());
+        cb.require_equal(
+            "memory_bulk_oob: opcode matches is_copy",
+            opcode.expr(),
+            is_copy.expr() * OpcodeId::MemoryCopy.expr() +
+                (1.expr() - is_copy.expr()) * OpcodeId::MemoryFill.expr(),
+        );
+
+        cb.stack_pop(length.expr());
+        cb.stack_pop(src_or_value.expr());
+        cb.stack_pop(dst.expr());
+
+        // The step traps iff the destination or (for copies) the source range ends past the
+        // current memory size.
+        let memory_size = cb.curr.state.memory_word_size.expr() * PAGE_SIZE.expr();
+        let dst_out_of_bounds = LtGadget::construct(cb, memory_size.clone(), dst.expr() + length.expr());
+        let src_out_of_bounds = LtGadget::construct(cb, memory_size, src_or_value.expr() + length.expr());
+        cb.require_equal(
+            "memory_bulk_oob: an accessed range goes past the memory size",
+            or::expr([dst_out_of_bounds.expr(), is_copy.expr() * src_out_of_bounds.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 5.expr());
+
+        Self {
+            opcode,
+            is_copy,
+            length,
+            src_or_value,
+            dst,
+            dst_out_of_bounds,
+            src_out_of_bounds,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        let is_copy = opcode == OpcodeId::MemoryCopy;
+        self.is_copy.assign(region, offset, Value::known(F::from(is_copy as u64)))?;
+
+        let [length, src_or_value, dst] =
+            [0, 1, 2].map(|i| block.rws[step.rw_indices[i]].stack_value());
+        self.length.assign(region, offset, Value::known(length.to_scalar().unwrap()))?;
+        self.src_or_value.assign(region, offset, Value::known(src_or_value.to_scalar().unwrap()))?;
+        self.dst.assign(region, offset, Value::known(dst.to_scalar().unwrap()))?;
+
+        let memory_size = step.memory_word_size() * PAGE_SIZE as u64;
+        self.dst_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(memory_size),
+            F::from(dst.as_u64() + length.as_u64()),
+        )?;
+        self.src_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(memory_size),
+            F::from(src_or_value.as_u64() + length.as_u64()),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 5)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::{evm_circuit::{param::PAGE_SIZE, step::ExecutionState}, test_util::CircuitTestBuilder};
+
+    fn run_test(code: Bytecode, is_oob: bool) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(move |block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorMemoryBulkOutOfBounds);
+            assert_eq!(has_error_step, is_oob);
+        })).run()
+    }
+
+    fn run_copy_test(dst: u64, src: u64, length: u64, is_oob: bool) {
+        run_test(bytecode! {
+            I32Const[dst]
+            I32Const[src]
+            I32Const[length]
+            MemoryCopy
+        }, is_oob);
+    }
+
+    fn run_fill_test(dst: u64, length: u64, is_oob: bool) {
+        run_test(bytecode! {
+            I32Const[dst]
+            I32Const[0xff]
+            I32Const[length]
+            MemoryFill
+        }, is_oob);
+    }
+
+    #[test]
+    fn test_copy_up_to_the_last_byte() {
+        run_copy_test(PAGE_SIZE as u64 - 4, 0, 4, false);
+        run_copy_test(0, PAGE_SIZE as u64 - 4, 4, false);
+    }
+
+    #[test]
+    fn test_copy_destination_past_memory_traps() {
+        run_copy_test(PAGE_SIZE as u64 - 3, 0, 4, true);
+    }
+
+    #[test]
+    fn test_copy_source_past_memory_traps() {
+        run_copy_test(0, PAGE_SIZE as u64 - 3, 4, true);
+    }
+
+    #[test]
+    fn test_fill_past_memory_traps() {
+        run_fill_test(PAGE_SIZE as u64 - 4, 4, false);
+        run_fill_test(PAGE_SIZE as u64 - 3, 4, true);
+    }
+}
@@ -0,0 +1,218 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX, Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::PAGE_SIZE,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::LtGadget,
+            or,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::WasmMetadataTag,
+    util::Expr,
+    wasm_circuit::host_interface::helpers::extract_data_segments,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for `memory.init` trapping because the source range goes past its data segment (a
+/// dropped segment is empty) or the destination range past the current memory size.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorMemoryInitOutOfBoundsGadget<F> {
+    opcode: Cell<F>,
+    length: Cell<F>,
+    src_offset: Cell<F>,
+    dst_offset: Cell<F>,
+    data_index: Cell<F>,
+    is_dropped: Cell<F>,
+    segment_len: Cell<F>,
+    src_out_of_range: LtGadget<F, 8>,
+    dst_out_of_bounds: LtGadget<F, 8>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorMemoryInitOutOfBoundsGadget<F> {
+    const NAME: &'static str = "WASM_ErrorMemoryInitOutOfBounds";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorMemoryInitOutOfBounds;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let length = cb.alloc_u64();
+        let src_offset = cb.alloc_u64();
+        let dst_offset = cb.alloc_u64();
+        let data_index = cb.query_cell();
+        let is_dropped = cb.query_bool();
+        let segment_len = cb.query_cell();
+
+        cb.require_equal(
+            "memory_init_oob: opcode is memory.init",
+            opcode.expr(),
+            OpcodeId::MemoryInit.expr(),
+        );
+
+        cb.stack_pop(length.expr());
+        cb.stack_pop(src_offset.expr());
+        cb.stack_pop(dst_offset.expr());
+
+        cb.global_read(
+            (WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX as u64).expr() + data_index.expr(),
+            is_dropped.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::DataSegmentLen.expr(),
+            data_index.expr(),
+            segment_len.expr(),
+        );
+
+        // The step traps iff the source range ends past the bytes left in the segment or the
+        // destination range past the current memory size.
+        let available_len = (1.expr() - is_dropped.expr()) * segment_len.expr();
+        let src_out_of_range = LtGadget::construct(cb, available_len, src_offset.expr() + length.expr());
+        let memory_size = cb.curr.state.memory_word_size.expr() * PAGE_SIZE.expr();
+        let dst_out_of_bounds = LtGadget::construct(cb, memory_size, dst_offset.expr() + length.expr());
+        cb.require_equal(
+            "memory_init_oob: an accessed range goes past the segment or the memory size",
+            or::expr([src_out_of_range.expr(), dst_out_of_bounds.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 6.expr());
+
+        Self {
+            opcode,
+            length,
+            src_offset,
+            dst_offset,
+            data_index,
+            is_dropped,
+            segment_len,
+            src_out_of_range,
+            dst_out_of_bounds,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [length, src_offset, dst_offset] =
+            [0, 1, 2].map(|i| block.rws[step.rw_indices[i]].stack_value());
+        self.length.assign(region, offset, Value::known(length.to_scalar().unwrap()))?;
+        self.src_offset.assign(region, offset, Value::known(src_offset.to_scalar().unwrap()))?;
+        self.dst_offset.assign(region, offset, Value::known(dst_offset.to_scalar().unwrap()))?;
+
+        let data_index = *step.params.first().expect("memory.init data index immediate is missing");
+        self.data_index.assign(region, offset, Value::known(F::from(data_index)))?;
+        let (is_dropped, _) = block.rws[step.rw_indices[3]].global_value();
+        self.is_dropped.assign(region, offset, Value::known(F::from(is_dropped.as_u64())))?;
+
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let segment = extract_data_segments(&bytecode.bytes)
+            .expect("could not parse the data section")
+            .get(data_index as usize)
+            .cloned()
+            .expect("memory.init data segment is undefined");
+        // active segments are dropped once the module is instantiated
+        let segment_len = if segment.is_passive { segment.len as u64 } else { 0 };
+        self.segment_len.assign(region, offset, Value::known(F::from(segment_len)))?;
+
+        let available_len = if is_dropped.is_zero() { segment_len } else { 0 };
+        self.src_out_of_range.assign(
+            region,
+            offset,
+            F::from(available_len),
+            F::from(src_offset.as_u64() + length.as_u64()),
+        )?;
+        self.dst_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(step.memory_word_size() * PAGE_SIZE as u64),
+            F::from(dst_offset.as_u64() + length.as_u64()),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 6)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::{evm_circuit::{param::PAGE_SIZE, step::ExecutionState}, test_util::CircuitTestBuilder};
+
+    fn run_test(code: Bytecode, is_oob: bool) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(move |block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorMemoryInitOutOfBounds);
+            assert_eq!(has_error_step, is_oob);
+        })).run()
+    }
+
+    fn run_init_test(dst: u64, src: u64, length: u64, is_oob: bool) {
+        let mut code = bytecode! {
+            I32Const[dst]
+            I32Const[src]
+            I32Const[length]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code, is_oob);
+    }
+
+    #[test]
+    fn test_init_up_to_the_last_byte() {
+        run_init_test(0, 1, 2, false);
+        run_init_test(PAGE_SIZE as u64 - 3, 0, 3, false);
+    }
+
+    #[test]
+    fn test_init_source_past_segment_traps() {
+        run_init_test(0, 2, 2, true);
+    }
+
+    #[test]
+    fn test_init_destination_past_memory_traps() {
+        run_init_test(PAGE_SIZE as u64 - 2, 0, 3, true);
+    }
+
+    #[test]
+    fn test_init_dropped_segment_traps() {
+        let mut code = bytecode! {
+            DataDrop[0]
+            I32Const[0]
+            I32Const[0]
+            I32Const[1]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code, true);
+    }
+}
@@ -0,0 +1,307 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::PAGE_SIZE,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            from_bytes,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::LtGadget,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+const LOAD_OPCODES: [OpcodeId; 12] = [
+    OpcodeId::I32Load,
+    OpcodeId::I32Load8S,
+    OpcodeId::I32Load8U,
+    OpcodeId::I32Load16S,
+    OpcodeId::I32Load16U,
+    OpcodeId::I64Load,
+    OpcodeId::I64Load8S,
+    OpcodeId::I64Load8U,
+    OpcodeId::I64Load16S,
+    OpcodeId::I64Load16U,
+    OpcodeId::I64Load32S,
+    OpcodeId::I64Load32U,
+];
+
+const STORE_OPCODES: [OpcodeId; 7] = [
+    OpcodeId::I32Store,
+    OpcodeId::I32Store8,
+    OpcodeId::I32Store16,
+    OpcodeId::I64Store,
+    OpcodeId::I64Store8,
+    OpcodeId::I64Store16,
+    OpcodeId::I64Store32,
+];
+
+/// Returns the amount of bytes accessed by the load or store opcode.
+fn access_width(opcode: OpcodeId) -> u64 {
+    match opcode {
+        OpcodeId::I32Load8S | OpcodeId::I32Load8U | OpcodeId::I64Load8S | OpcodeId::I64Load8U |
+        OpcodeId::I32Store8 | OpcodeId::I64Store8 => 1,
+        OpcodeId::I32Load16S | OpcodeId::I32Load16U | OpcodeId::I64Load16S | OpcodeId::I64Load16U |
+        OpcodeId::I32Store16 | OpcodeId::I64Store16 => 2,
+        OpcodeId::I32Load | OpcodeId::I64Load32S | OpcodeId::I64Load32U |
+        OpcodeId::I32Store | OpcodeId::I64Store32 => 4,
+        OpcodeId::I64Load | OpcodeId::I64Store => 8,
+        _ => unreachable!("not supported opcode: {:?}", opcode),
+    }
+}
+
+/// Gadget for loads and stores trapping because the accessed bytes go past the current memory
+/// size (`page_count * PAGE_SIZE`).
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorMemoryOutOfBoundsGadget<F> {
+    opcode: Cell<F>,
+    is_store: Cell<F>,
+    value: Cell<F>,
+    /// Popped address, wasm32 addresses fit into 4 bytes.
+    address: [Cell<F>; 4],
+    /// Static offset immediate of the memarg.
+    mem_offset: [Cell<F>; 4],
+    /// Address of the first accessed byte, `address + mem_offset`.
+    effective_address: Cell<F>,
+    is_width1: Cell<F>,
+    is_width2: Cell<F>,
+    is_width4: Cell<F>,
+    is_width8: Cell<F>,
+    memory_size_lt_end: LtGadget<F, 8>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorMemoryOutOfBoundsGadget<F> {
+    const NAME: &'static str = "WASM_ErrorMemoryOutOfBounds";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorMemoryOutOfBounds;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let is_store = cb.alloc_bit_value();
+        let value = cb.alloc_u64();
+        let address: [Cell<F>; 4] = cb.query_bytes();
+        let mem_offset: [Cell<F>; 4] = cb.query_bytes();
+        let effective_address = cb.alloc_u64();
+
+        let is_width1 = cb.alloc_bit_value();
+        let is_width2 = cb.alloc_bit_value();
+        let is_width4 = cb.alloc_bit_value();
+        let is_width8 = cb.alloc_bit_value();
+
+        for (name, bit) in [
+            ("memory_oob: is_store is bool", &is_store),
+            ("memory_oob: is_width1 is bool", &is_width1),
+            ("memory_oob: is_width2 is bool", &is_width2),
+            ("memory_oob: is_width4 is bool", &is_width4),
+            ("memory_oob: is_width8 is bool", &is_width8),
+        ] {
+            cb.require_boolean(name, bit.expr());
+        }
+        cb.require_equal(
+            "memory_oob: width selector",
+            is_width1.expr() + is_width2.expr() + is_width4.expr() + is_width8.expr(),
+            1.expr(),
+        );
+
+        // Selectors must agree with the opcode, otherwise the width could be faked to trap.
+        let opcodes_of = |width: u64, stores: bool| -> Vec<_> {
+            let opcodes = if stores { STORE_OPCODES.to_vec() } else { LOAD_OPCODES.to_vec() };
+            opcodes.into_iter()
+                .filter(|opcode| access_width(*opcode) == width)
+                .map(|opcode| opcode.expr())
+                .collect()
+        };
+        for (width, selector) in [(1, &is_width1), (2, &is_width2), (4, &is_width4), (8, &is_width8)] {
+            cb.condition(selector.expr() * is_store.expr(), |cb| {
+                cb.require_in_set("memory_oob: store opcode matches width", opcode.expr(), opcodes_of(width, true));
+            });
+            cb.condition(selector.expr() * (1.expr() - is_store.expr()), |cb| {
+                cb.require_in_set("memory_oob: load opcode matches width", opcode.expr(), opcodes_of(width, false));
+            });
+        }
+
+        // Stores pop the value first and then the address, loads pop only the address.
+        cb.condition(is_store.expr(), |cb| {
+            cb.stack_pop(value.expr());
+        });
+        cb.require_zero("memory_oob: no value for loads", value.expr() * (1.expr() - is_store.expr()));
+        cb.stack_pop(from_bytes::expr(&address));
+
+        // Both parts are byte decomposed, so the sum can't wrap and fake an access past the memory.
+        cb.require_equal(
+            "memory_oob: effective address is the popped address plus the memarg offset",
+            effective_address.expr(),
+            from_bytes::expr(&address) + from_bytes::expr(&mem_offset),
+        );
+
+        // The access traps iff its last byte is past the current memory size.
+        let width = is_width1.expr() + 2.expr() * is_width2.expr() + 4.expr() * is_width4.expr() +
+            8.expr() * is_width8.expr();
+        let memory_size = cb.curr.state.memory_word_size.expr() * PAGE_SIZE.expr();
+        let memory_size_lt_end = LtGadget::construct(
+            cb,
+            memory_size,
+            effective_address.expr() + width,
+        );
+        cb.require_equal("memory_oob: access goes past the memory size", memory_size_lt_end.expr(), 1.expr());
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 3.expr() + is_store.expr());
+
+        Self {
+            opcode,
+            is_store,
+            value,
+            address,
+            mem_offset,
+            effective_address,
+            is_width1,
+            is_width2,
+            is_width4,
+            is_width8,
+            memory_size_lt_end,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let is_store = STORE_OPCODES.contains(&opcode);
+        self.is_store.assign(region, offset, Value::known(F::from(is_store as u64)))?;
+
+        let address = if is_store {
+            let [value, address] = [step.rw_indices[0], step.rw_indices[1]]
+                .map(|idx| block.rws[idx].stack_value());
+            self.value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
+            address
+        } else {
+            block.rws[step.rw_indices[0]].stack_value()
+        };
+        let address = address.as_u64();
+        for (cell, byte) in self.address.iter().zip((address as u32).to_le_bytes()) {
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+
+        let mem_offset = *step.params.first().expect("memarg offset immediate is missing");
+        for (cell, byte) in self.mem_offset.iter().zip((mem_offset as u32).to_le_bytes()) {
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+        let effective_address = address + mem_offset;
+        self.effective_address.assign(region, offset, Value::known(F::from(effective_address)))?;
+
+        let width = access_width(opcode);
+        let selector = match width {
+            1 => &self.is_width1,
+            2 => &self.is_width2,
+            4 => &self.is_width4,
+            _ => &self.is_width8,
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+
+        let memory_size = step.memory_word_size() * PAGE_SIZE as u64;
+        self.memory_size_lt_end.assign(
+            region,
+            offset,
+            F::from(memory_size),
+            F::from(effective_address + width),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 3 + is_store as usize)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode_internal, Bytecode};
+    use eth_types::evm_types::OpcodeId;
+    use mock::TestContext;
+
+    use crate::{evm_circuit::{param::PAGE_SIZE, step::ExecutionState}, test_util::CircuitTestBuilder};
+
+    fn store8_code(address: u64, mem_offset: u64) -> Bytecode {
+        let mut code = Bytecode::default();
+        code.write_postfix(OpcodeId::I32Const, address as i128);
+        code.write_postfix(OpcodeId::I32Const, 0x7f);
+        bytecode_internal! {code,
+            .write_memarg(OpcodeId::I32Store8, mem_offset, 0, 0)
+        }
+        code
+    }
+
+    /// stores a single byte at `address + mem_offset` of the one page memory and checks whether
+    /// the store traps
+    fn run_store8_test(address: u64, mem_offset: u64, is_oob: bool) {
+        let code = store8_code(address, mem_offset);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(move |block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorMemoryOutOfBounds);
+            assert_eq!(has_error_step, is_oob);
+        })).run()
+    }
+
+    #[test]
+    fn test_store_at_last_valid_byte() {
+        run_store8_test(PAGE_SIZE as u64 - 1, 0, false);
+    }
+
+    #[test]
+    fn test_store_at_last_valid_byte_with_offset() {
+        run_store8_test(PAGE_SIZE as u64 - 0x11, 0x10, false);
+    }
+
+    #[test]
+    fn test_store_one_byte_past_memory_traps() {
+        run_store8_test(PAGE_SIZE as u64, 0, true);
+    }
+
+    #[test]
+    fn test_store_one_byte_past_memory_with_offset_traps() {
+        run_store8_test(PAGE_SIZE as u64 - 0x10, 0x10, true);
+    }
+
+    #[test]
+    fn test_store_in_bounds_address_with_dropped_offset_fails() {
+        // the popped address alone is in bounds, so the trap only holds with the memarg offset
+        let code = store8_code(PAGE_SIZE as u64 - 0x10, 0x10);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            for step in block.txs[0].steps.iter_mut() {
+                if step.execution_state == ExecutionState::WASM_ErrorMemoryOutOfBounds {
+                    step.params[0] = 0;
+                }
+            }
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
+}
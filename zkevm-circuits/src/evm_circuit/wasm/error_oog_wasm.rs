@@ -0,0 +1,234 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use eth_types::Field;
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::LtGadget,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for WASM opcodes executed with less gas left than their step cost.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGWasmGadget<F> {
+    opcode: Cell<F>,
+    gas_required: Cell<F>,
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGWasmGadget<F> {
+    const NAME: &'static str = "WASM_ErrorOutOfGas";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorOutOfGas;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let gas_required = cb.query_cell();
+
+        cb.wasm_opcode_cost_lookup(opcode.expr(), gas_required.expr());
+
+        let insufficient_gas = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            gas_required.expr(),
+        );
+        cb.require_equal(
+            "oog_wasm: gas left is less than gas required",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 2.expr());
+
+        Self {
+            opcode,
+            gas_required,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let gas_required = block.circuits_params.wasm_gas_model.opcode_cost(opcode);
+        self.gas_required.assign(region, offset, Value::known(F::from(gas_required)))?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(gas_required),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 2)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bus_mapping::circuit_input_builder::CircuitsParams;
+    use eth_types::{
+        bytecode,
+        evm_types::{Gas, GasCost, OpcodeId},
+        Bytecode, GethExecTrace,
+    };
+    use mock::{test_ctx::helpers::account_0_code_account_1_no_code, TestContext};
+
+    use crate::{
+        evm_circuit::step::ExecutionState,
+        test_util::CircuitTestBuilder,
+        wasm_circuit::gas::{is_wasm_instruction, FlatGasModel, WasmGasModel},
+    };
+
+    /// charges one gas per step
+    static UNIT_GAS_MODEL: FlatGasModel = FlatGasModel { cost: 1 };
+
+    #[derive(Debug)]
+    struct ArithmeticHeavyGasModel;
+
+    impl WasmGasModel for ArithmeticHeavyGasModel {
+        fn opcode_cost(&self, opcode: OpcodeId) -> u64 {
+            match opcode {
+                OpcodeId::I32Add => 10,
+                _ => 1,
+            }
+        }
+    }
+
+    /// rewrites the `trace` of a tracer charging nothing for WASM instructions as a VM charging
+    /// the `model` costs would have traced it, the first step short of gas runs out of gas
+    fn charge_gas_model(trace: &mut GethExecTrace, model: &dyn WasmGasModel) {
+        let mut charged = 0;
+        for index in 0..trace.struct_logs.len() {
+            let step = &mut trace.struct_logs[index];
+            let cost = if is_wasm_instruction(step.op) {
+                model.opcode_cost(step.op)
+            } else {
+                0
+            };
+            step.gas = Gas(step.gas.0 - charged);
+            if step.gas.0 < step.gas_cost.0 + cost {
+                step.error = Some("out of gas".to_string());
+                trace.struct_logs.truncate(index + 1);
+                trace.failed = true;
+                trace.return_value.clear();
+                return;
+            }
+            step.gas_cost = GasCost(step.gas_cost.0 + cost);
+            charged += cost;
+        }
+        trace.gas = Gas(trace.gas.0 + charged);
+    }
+
+    /// runs the bytecode with `gas` on top of the intrinsic tx gas under the `model` and checks
+    /// whether it ends with the out of gas error
+    fn run_oog_test(bytecode: Bytecode, model: &'static dyn WasmGasModel, gas: u64, is_oog: bool) {
+        let mut ctx = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(bytecode),
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .gas((GasCost::TX.as_u64() + gas).into());
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+        charge_gas_model(&mut ctx.geth_traces[0], model);
+        CircuitTestBuilder::new_from_test_ctx(ctx)
+            .params(CircuitsParams {
+                wasm_gas_model: model,
+                ..Default::default()
+            })
+            .block_modifier(Box::new(move |block| {
+                let has_error_step = block.txs[0]
+                    .steps
+                    .iter()
+                    .any(|step| step.execution_state == ExecutionState::WASM_ErrorOutOfGas);
+                assert_eq!(has_error_step, is_oog);
+            }))
+            .run()
+    }
+
+    #[test]
+    fn test_oog_mid_function() {
+        // gas is exhausted on `i32.add`
+        run_oog_test(
+            bytecode! {
+                I32Const[1]
+                I32Const[2]
+                I32Add
+                Drop
+            },
+            &UNIT_GAS_MODEL,
+            2,
+            true,
+        );
+    }
+
+    #[test]
+    fn test_oog_on_first_step() {
+        run_oog_test(
+            bytecode! {
+                I32Const[1]
+                Drop
+            },
+            &UNIT_GAS_MODEL,
+            0,
+            true,
+        );
+    }
+
+    #[test]
+    fn test_every_step_is_charged() {
+        // `i32.const`, `drop` and the final `end` use up all the gas
+        run_oog_test(
+            bytecode! {
+                I32Const[1]
+                Drop
+            },
+            &UNIT_GAS_MODEL,
+            3,
+            false,
+        );
+    }
+
+    #[test]
+    fn test_non_flat_gas_model() {
+        let code = bytecode! {
+            I32Const[1]
+            I32Const[2]
+            I32Add
+            Drop
+        };
+        // `i32.add` costs 10, the other four steps 1 each
+        run_oog_test(code.clone(), &ArithmeticHeavyGasModel, 14, false);
+        run_oog_test(code, &ArithmeticHeavyGasModel, 13, true);
+    }
+}
@@ -0,0 +1,257 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::Field;
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{STACK_CAPACITY, WASM_PUSH_OPCODES},
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstrainBuilderCommon,
+            math_gadget::LtGadget,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for popping ops executed on an empty operand stack (only `drop` for now) and pushing
+/// ops executed on a full one.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorStackUnderflowGadget<F> {
+    opcode: Cell<F>,
+    is_overflow: Cell<F>,
+    /// The stack is empty once the stack pointer reaches the frame base (`STACK_CAPACITY`).
+    is_stack_not_empty: LtGadget<F, 2>,
+    /// The stack is full once it holds `max_stack_depth` operands.
+    is_stack_not_full: LtGadget<F, 2>,
+    full_stack_pointer: usize,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorStackUnderflowGadget<F> {
+    const NAME: &'static str = "WASM_ErrorStack";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_ErrorStack;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let is_overflow = cb.alloc_bit_value();
+
+        cb.require_boolean("stack_error: is_overflow is bool", is_overflow.expr());
+
+        let is_stack_not_empty = LtGadget::construct(
+            cb,
+            cb.curr.state.stack_pointer.expr(),
+            STACK_CAPACITY.expr(),
+        );
+        let full_stack_pointer = cb.wasm_params.full_stack_pointer();
+        let is_stack_not_full = LtGadget::construct(
+            cb,
+            full_stack_pointer.expr(),
+            cb.curr.state.stack_pointer.expr(),
+        );
+
+        cb.condition(1.expr() - is_overflow.expr(), |cb| {
+            cb.require_equal(
+                "stack_underflow: only drop is handled",
+                opcode.expr(),
+                OpcodeId::Drop.expr(),
+            );
+            cb.require_zero(
+                "stack_underflow: the operand stack is empty",
+                is_stack_not_empty.expr(),
+            );
+        });
+
+        cb.condition(is_overflow.expr(), |cb| {
+            cb.require_in_set(
+                "stack_overflow: only pushes are handled",
+                opcode.expr(),
+                WASM_PUSH_OPCODES.iter().map(|opcode| opcode.expr()).collect(),
+            );
+            cb.require_zero(
+                "stack_overflow: the operand stack is full",
+                is_stack_not_full.expr(),
+            );
+        });
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr(), 2.expr());
+
+        Self {
+            opcode,
+            is_overflow,
+            is_stack_not_empty,
+            is_stack_not_full,
+            full_stack_pointer,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode.assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        let is_overflow = WASM_PUSH_OPCODES.contains(&opcode);
+        self.is_overflow.assign(region, offset, Value::known(F::from(is_overflow as u64)))?;
+
+        self.is_stack_not_empty.assign(
+            region,
+            offset,
+            F::from(step.stack_pointer as u64),
+            F::from(STACK_CAPACITY as u64),
+        )?;
+        self.is_stack_not_full.assign(
+            region,
+            offset,
+            F::from(self.full_stack_pointer as u64),
+            F::from(step.stack_pointer as u64),
+        )?;
+
+        self.common_error_gadget.assign(region, offset, block, call, step, 2)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bus_mapping::circuit_input_builder::CircuitsParams;
+    use eth_types::{bytecode, Bytecode, GethExecTrace};
+    use mock::TestContext;
+
+    use crate::{
+        evm_circuit::{
+            param::{WasmExecutionParams, WASM_PUSH_OPCODES},
+            step::ExecutionState,
+        },
+        test_util::CircuitTestBuilder,
+    };
+
+    /// rewrites the `trace` as a VM with `max_stack_depth` operand slots would have traced it, the
+    /// first push on a full stack overflows
+    fn trap_stack_overflow(trace: &mut GethExecTrace, max_stack_depth: usize) {
+        let index = trace
+            .struct_logs
+            .iter()
+            .position(|step| {
+                WASM_PUSH_OPCODES.contains(&step.op) && step.stack.0.len() >= max_stack_depth
+            })
+            .expect("a push must overflow the stack");
+        trace.struct_logs.truncate(index + 1);
+        trace.struct_logs[index].error = Some("stack limit reached".to_string());
+        trace.failed = true;
+        trace.return_value.clear();
+    }
+
+    /// the bytecode traced by a VM with 4 operand slots, the fifth push overflows
+    fn overflowing_ctx() -> TestContext<2, 1> {
+        let code = bytecode! {
+            I32Const[1]
+            I32Const[2]
+            I32Const[3]
+            I32Const[4]
+            I32Const[5]
+            I32Const[6]
+            I32Const[7]
+            I32Const[8]
+        };
+        let mut ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap();
+        trap_stack_overflow(&mut ctx.geth_traces[0], 4);
+        ctx
+    }
+
+    /// runs the bytecode and checks that it ends with the stack underflow
+    fn run_underflow_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let has_error_step = block.txs[0].steps.iter()
+                .any(|step| step.execution_state == ExecutionState::WASM_ErrorStack);
+            assert!(has_error_step);
+        })).run()
+    }
+
+    #[test]
+    fn test_drop_on_empty_stack() {
+        run_underflow_test(bytecode! {
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_drop_after_stack_is_drained() {
+        run_underflow_test(bytecode! {
+            I32Const[1]
+            Drop
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_const_run_overflows_small_capacity_circuit() {
+        const MAX_STACK_DEPTH: usize = 4;
+        CircuitTestBuilder::new_from_test_ctx(overflowing_ctx())
+            .params(CircuitsParams {
+                max_wasm_stack_depth: MAX_STACK_DEPTH,
+                ..Default::default()
+            })
+            .block_modifier(Box::new(|block| {
+                let steps = &block.txs[0].steps;
+                let error_index = steps
+                    .iter()
+                    .position(|step| step.execution_state == ExecutionState::WASM_ErrorStack)
+                    .expect("the fifth push must overflow the stack");
+                let consts_count = steps[..error_index]
+                    .iter()
+                    .filter(|step| step.execution_state == ExecutionState::WASM_CONST)
+                    .count();
+                assert_eq!(consts_count, MAX_STACK_DEPTH);
+            }))
+            .run_evm_with_wasm_params::<MAX_STACK_DEPTH, false>();
+    }
+
+    #[test]
+    fn test_overflow_below_the_circuit_capacity_is_rejected() {
+        const MAX_STACK_DEPTH: usize = 8;
+        CircuitTestBuilder::new_from_test_ctx(overflowing_ctx())
+            .params(CircuitsParams {
+                max_wasm_stack_depth: MAX_STACK_DEPTH,
+                ..Default::default()
+            })
+            .evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+                assert!(prover
+                    .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                    .is_err());
+            }))
+            .run_evm_with_wasm_params::<MAX_STACK_DEPTH, false>();
+    }
+
+    #[test]
+    fn test_default_capacity_is_the_stack_frame() {
+        let params = WasmExecutionParams::default();
+        assert_eq!(params.full_stack_pointer(), 0);
+        assert_eq!(
+            WasmExecutionParams {
+                max_stack_depth: 4,
+                ..Default::default()
+            }
+            .full_stack_pointer(),
+            1020
+        );
+    }
+}
@@ -2,33 +2,36 @@ use halo2_proofs::circuit::Value;
 use halo2_proofs::plonk::{Error, Expression};
 
 use bus_mapping::evm::OpcodeId;
-use eth_types::{Field, ToScalar};
+use eth_types::Field;
 use std::ops::Neg;
 
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
+        table::{FixedTableTag, Lookup},
         step::ExecutionState,
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
             constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::{IsEqualGadget, IsZeroGadget},
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
 use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::u64_cell::U64Cell;
 use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 
 #[derive(Clone, Debug)]
 pub(crate) struct WasmBinGadget<F> {
     same_context: SameContextGadget<F>,
-    lhs: Cell<F>,
+    lhs: U64Cell<F>,
     lhs_neg: Cell<F>,
-    rhs: Cell<F>,
+    rhs: U64Cell<F>,
     rhs_neg: Cell<F>,
-    res: Cell<F>,
+    res: U64Cell<F>,
     res_neg: Cell<F>,
     is_add: Cell<F>,
     is_sub: Cell<F>,
@@ -37,9 +40,18 @@ pub(crate) struct WasmBinGadget<F> {
     is_rem_u: Cell<F>,
     is_div_s: Cell<F>,
     is_rem_s: Cell<F>,
+    is_and: Cell<F>,
+    is_or: Cell<F>,
+    is_xor: Cell<F>,
     div_rem_s_is_lhs_pos: Cell<F>,
     div_rem_s_is_rhs_pos: Cell<F>,
-    aux1: Cell<F>,
+    /// Trap conditions of div/rem, steps hitting them go to `WASM_ErrorDivByZero` instead.
+    rhs_is_zero: IsZeroGadget<F>,
+    lhs_is_min: IsEqualGadget<F>,
+    rhs_is_minus_one: IsEqualGadget<F>,
+    /// Carry of add/sub, high half of mul and quotient of div, range checked to 64 bits so the
+    /// split of the mul product into `aux1` and `res` is unique.
+    aux1: U64Cell<F>,
     aux1_neg: Cell<F>,
     aux2: Cell<F>,
     aux2_neg: Cell<F>,
@@ -56,11 +68,11 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
 
-        let lhs = cb.query_cell();
+        let lhs = U64Cell::construct(cb);
         let lhs_neg = cb.query_cell();
-        let rhs = cb.query_cell();
+        let rhs = U64Cell::construct(cb);
         let rhs_neg = cb.query_cell();
-        let res = cb.query_cell();
+        let res = U64Cell::construct(cb);
         let res_neg = cb.query_cell();
 
         let is_add = cb.alloc_bit_value();
@@ -70,11 +82,14 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
         let is_rem_u = cb.alloc_bit_value();
         let is_div_s = cb.alloc_bit_value();
         let is_rem_s = cb.alloc_bit_value();
+        let is_and = cb.alloc_bit_value();
+        let is_or = cb.alloc_bit_value();
+        let is_xor = cb.alloc_bit_value();
 
         let div_rem_s_is_lhs_pos = cb.alloc_bit_value();
         let div_rem_s_is_rhs_pos = cb.alloc_bit_value();
 
-        let aux1 = cb.alloc_u64_on_u8();
+        let aux1 = U64Cell::construct(cb);
         let aux1_neg = cb.alloc_u64_on_u8();
         let aux2 = cb.alloc_u64_on_u8();
         let aux2_neg = cb.alloc_u64_on_u8();
@@ -101,13 +116,40 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
         // If `alloc_bit_value` does the job. If not, then fraud is possible.
         cb.require_equal(
             "binop: selector",
-            is_add.expr() + is_sub.expr() + is_mul.expr() + is_div_u.expr() + is_rem_u.expr() + is_div_s.expr() + is_rem_s.expr(),
+            is_add.expr() + is_sub.expr() + is_mul.expr() + is_div_u.expr() + is_rem_u.expr() + is_div_s.expr() + is_rem_s.expr() +
+                is_and.expr() + is_or.expr() + is_xor.expr(),
             1.expr(),
         );
 
         let modulus = Expression::Constant(F::from(1u64 << 32usize)) +
             Expression::Constant(F::from((u32::MAX as u64) << 32usize)) * is_64bits.expr();
 
+        cb.require_zero(
+            "binop: i32 result has no high limbs",
+            res.hi() * (1.expr() - is_64bits.expr()),
+        );
+
+        // Bitwise ops are checked byte by byte against the fixed bitwise tables (the operands and
+        // the result are already range checked by `U64Cell`).
+        let is_bitwise = is_and.expr() + is_or.expr() + is_xor.expr();
+        cb.require_zeros(
+            "binop: bitwise i32 operands have no high limbs",
+            [lhs.hi(), rhs.hi()].into_iter()
+                .map(|hi| hi * is_bitwise.clone() * (1.expr() - is_64bits.expr()))
+                .collect(),
+        );
+        cb.condition(is_bitwise.clone(), |cb| {
+            let tag = FixedTableTag::BitwiseAnd.expr() * is_and.expr() +
+                FixedTableTag::BitwiseOr.expr() * is_or.expr() +
+                FixedTableTag::BitwiseXor.expr() * is_xor.expr();
+            for i in 0..8 {
+                cb.add_lookup("binop: bitwise op on limbs", Lookup::Fixed {
+                    tag: tag.clone(),
+                    values: [lhs.bytes()[i].expr(), rhs.bytes()[i].expr(), res.bytes()[i].expr()],
+                });
+            }
+        });
+
         cb.require_zero(
             "binop: add constraint",
             (lhs.expr() + rhs.expr() - res.expr() - aux1.expr() * modulus.clone()) * is_add.expr(),
@@ -118,11 +160,29 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             (rhs.expr() + res.expr() - lhs.expr() - aux1.expr() * modulus.clone()) * is_sub.expr(),
         );
 
+        // The product of two 64-bit operands is below 2^128 and can't wrap the field, its high half
+        // is at most 2^64 - 2, so the range checked u64 `aux1` is wide enough to hold it.
         cb.require_zero(
             "binop: mul constraint",
             (lhs.expr() * rhs.expr() - aux1.expr() * modulus.clone() - res.expr()) * is_mul.expr(),
         );
 
+        // Division by zero traps for every div/rem, `MIN / -1` overflows and traps for div_s only
+        // (rem_s gives zero), so such steps can't be proven as a successful binop.
+        let rhs_is_zero = IsZeroGadget::construct(cb, rhs.expr());
+        cb.require_zero(
+            "binop: div/rem by zero traps",
+            (is_div_u.expr() + is_rem_u.expr() + is_div_s.expr() + is_rem_s.expr()) * rhs_is_zero.expr(),
+        );
+        let int_min = 0x80000000_u64.expr() + (0x80000000_00000000_u64 - 0x80000000_u64).expr() * is_64bits.expr();
+        let minus_one = 0xffffffff_u64.expr() + (u64::MAX - 0xffffffff_u64).expr() * is_64bits.expr();
+        let lhs_is_min = IsEqualGadget::construct(cb, lhs.expr(), int_min);
+        let rhs_is_minus_one = IsEqualGadget::construct(cb, rhs.expr(), minus_one);
+        cb.require_zero(
+            "binop: signed div overflow traps",
+            is_div_s.expr() * lhs_is_min.expr() * rhs_is_minus_one.expr(),
+        );
+
         cb.require_zeros("div_u/rem_u constraints", vec![
             (lhs.expr() - rhs.expr() * aux1.expr() - aux2.expr()) * (is_rem_u.expr() + is_div_u.expr()),
             (aux2.expr() + aux3.expr() + 1.expr() - rhs.expr()) * (is_rem_u.expr() + is_div_u.expr()),
@@ -198,10 +258,9 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             rw_counter: Delta(3.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(1.expr()),
-            gas_left: Delta(-OpcodeId::I32Add.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
@@ -218,8 +277,14 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             is_rem_u,
             is_div_s,
             is_rem_s,
+            is_and,
+            is_or,
+            is_xor,
             div_rem_s_is_lhs_pos,
             div_rem_s_is_rhs_pos,
+            rhs_is_zero,
+            lhs_is_min,
+            rhs_is_minus_one,
             aux1,
             aux1_neg,
             aux2,
@@ -246,9 +311,9 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
         let [rhs, lhs, res] = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
             .map(|idx| block.rws[idx].stack_value());
 
-        self.lhs.assign(region, offset, Value::known(lhs.to_scalar().unwrap()))?;
-        self.rhs.assign(region, offset, Value::known(rhs.to_scalar().unwrap()))?;
-        self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
+        self.lhs.assign(region, offset, lhs.as_u64())?;
+        self.rhs.assign(region, offset, rhs.as_u64())?;
+        self.res.assign(region, offset, res.as_u64())?;
 
         let selector = match opcode {
             OpcodeId::I32Add | OpcodeId::I64Add => &self.is_add,
@@ -258,6 +323,9 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             OpcodeId::I32DivU | OpcodeId::I64DivU => &self.is_div_u,
             OpcodeId::I32RemU | OpcodeId::I64RemU => &self.is_rem_u,
             OpcodeId::I32RemS | OpcodeId::I64RemS => &self.is_rem_s,
+            OpcodeId::I32And | OpcodeId::I64And => &self.is_and,
+            OpcodeId::I32Or | OpcodeId::I64Or => &self.is_or,
+            OpcodeId::I32Xor | OpcodeId::I64Xor => &self.is_xor,
             _ => unreachable!("not supported opcode: {:?}", opcode),
         };
         selector.assign(region, offset, Value::known(F::one()))?;
@@ -294,6 +362,7 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             OpcodeId::I64Mul => {
                 let (res2, overflow) = (lhs.as_u64() as u128).overflowing_mul(rhs.as_u64() as u128);
                 debug_assert!(!overflow, "overflow here is not possible");
+                debug_assert!(res2 >> 64 <= u64::MAX as u128 - 1);
                 aux1 = (res2 >> 64) as u64;
             }
             OpcodeId::I32DivU | OpcodeId::I32RemU => {
@@ -308,23 +377,29 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             }
             OpcodeId::I32DivS | OpcodeId::I32RemS => {
                 // TODO: check and correct to fix possible problems with conversion.
-                aux1 = ((lhs.as_u32() as i32 / rhs.as_u32() as i32) as u32) as u64;
-                aux2 = ((lhs.as_u32() as i32 % rhs.as_u32() as i32) as u32) as u64;
-                aux3 = ((rhs.as_u32() as i32 - lhs.as_u32() as i32 % rhs.as_u32() as i32 - 1) as u32) as u64;
+                // `rem_s(MIN, -1)` is zero, so wrapping ops are used to not panic on it.
+                aux1 = ((lhs.as_u32() as i32).wrapping_div(rhs.as_u32() as i32) as u32) as u64;
+                aux2 = ((lhs.as_u32() as i32).wrapping_rem(rhs.as_u32() as i32) as u32) as u64;
+                aux3 = ((rhs.as_u32() as i32 - (lhs.as_u32() as i32).wrapping_rem(rhs.as_u32() as i32) - 1) as u32) as u64;
                 div_rem_s_is_lhs_pos = (lhs.as_u32() <= i32::MAX as u32) as u64;
                 div_rem_s_is_rhs_pos = (rhs.as_u32() <= i32::MAX as u32) as u64;
             }
             OpcodeId::I64DivS | OpcodeId::I64RemS => {
                 // TODO: check and correct to fix possible problems with conversion.
-                aux1 = (lhs.as_u64() as i64 / rhs.as_u64() as i64) as u64;
-                aux2 = (lhs.as_u64() as i64 % rhs.as_u64() as i64) as u64;
-                aux3 = (rhs.as_u64() as i64 - lhs.as_u64() as i64 % rhs.as_u64() as i64 - 1) as u64;
+                aux1 = (lhs.as_u64() as i64).wrapping_div(rhs.as_u64() as i64) as u64;
+                aux2 = (lhs.as_u64() as i64).wrapping_rem(rhs.as_u64() as i64) as u64;
+                aux3 = (rhs.as_u64() as i64 - (lhs.as_u64() as i64).wrapping_rem(rhs.as_u64() as i64) - 1) as u64;
                 div_rem_s_is_lhs_pos = (lhs.as_u64() <= i64::MAX as u64) as u64;
                 div_rem_s_is_rhs_pos = (rhs.as_u64() <= i64::MAX as u64) as u64;
             }
+            OpcodeId::I32And | OpcodeId::I64And |
+            OpcodeId::I32Or | OpcodeId::I64Or |
+            OpcodeId::I32Xor | OpcodeId::I64Xor => {
+                aux1 = 0;
+            }
             _ => unreachable!("not supported opcode: {:?}", opcode),
         };
-        self.aux1.assign(region, offset, Value::known(F::from(aux1)))?;
+        self.aux1.assign(region, offset, aux1)?;
         self.aux2.assign(region, offset, Value::known(F::from(aux2)))?;
         self.aux3.assign(region, offset, Value::known(F::from(aux3)))?;
         self.div_rem_s_is_lhs_pos.assign(region, offset, Value::known(F::from(div_rem_s_is_lhs_pos)))?;
@@ -337,10 +412,22 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
             OpcodeId::I64DivS |
             OpcodeId::I64DivU |
             OpcodeId::I64RemS |
-            OpcodeId::I64RemU
+            OpcodeId::I64RemU |
+            OpcodeId::I64And |
+            OpcodeId::I64Or |
+            OpcodeId::I64Xor
         );
         self.is_64bits.assign(region, offset, Value::known(F::from(is_64bit as u64)))?;
 
+        let (int_min, minus_one) = if is_64bit {
+            (i64::MIN as u64, u64::MAX)
+        } else {
+            (i32::MIN as u32 as u64, u32::MAX as u64)
+        };
+        self.rhs_is_zero.assign(region, offset, F::from(rhs.as_u64()))?;
+        self.lhs_is_min.assign(region, offset, F::from(lhs.as_u64()), F::from(int_min))?;
+        self.rhs_is_minus_one.assign(region, offset, F::from(rhs.as_u64()), F::from(minus_one))?;
+
         let mut rhs_neg = 0u64;
         let mut lhs_neg = 0u64;
         let mut res_neg = 0u64;
@@ -377,10 +464,17 @@ impl<F: Field> ExecutionGadget<F> for WasmBinGadget<F> {
 
 #[cfg(test)]
 mod test {
-    use eth_types::{bytecode, Bytecode};
+    use bus_mapping::evm::OpcodeId;
+    use eth_types::{bytecode, Bytecode, StackWord};
     use mock::TestContext;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{
+        evm_circuit::{step::ExecutionState, test::run_test_with_results},
+        table::RwTableTag,
+        test_util::CircuitTestBuilder,
+    };
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -388,6 +482,25 @@ mod test {
         ).run()
     }
 
+    /// runs every bitwise op on random (seeded) inputs and compares with the native result
+    fn run_bitwise_test(const_op: OpcodeId, ops: [OpcodeId; 3], mask: u64) {
+        let mut rng = ChaCha20Rng::seed_from_u64(mask);
+        let mut code = Bytecode::default();
+        let mut expected = vec![];
+        for _ in 0..4 {
+            let lhs = rng.gen::<u64>() & mask;
+            let rhs = rng.gen::<u64>() & mask;
+            for (op, res) in ops.into_iter().zip([lhs & rhs, lhs | rhs, lhs ^ rhs]) {
+                code.write_postfix(const_op, lhs as i128)
+                    .write_postfix(const_op, rhs as i128)
+                    .write_op(op)
+                    .write_op(OpcodeId::Drop);
+                expected.push(res);
+            }
+        }
+        run_test_with_results(ExecutionState::WASM_BIN, code, expected);
+    }
+
     #[test]
     fn test_i32_add() {
         run_test(bytecode! {
@@ -484,6 +597,42 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_i64_mul_max_operands() {
+        run_test_with_results(ExecutionState::WASM_BIN, bytecode! {
+            I64Const[18446744073709551615]
+            I64Const[18446744073709551615]
+            I64Mul
+            Drop
+        }, vec![u64::MAX.wrapping_mul(u64::MAX)]);
+    }
+
+    #[test]
+    fn test_i32_mul_unwrapped_result_fails() {
+        let bytecode = bytecode! {
+            I32Const[0x10003]
+            I32Const[0x10005]
+            I32Mul
+            Drop
+        };
+        CircuitTestBuilder::<2, 1>::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            // the wrapped product is replaced with the full one, leaving nothing for `aux1`
+            for rw in block.rws.0.get_mut(&RwTableTag::Stack).unwrap().iter_mut() {
+                if let crate::witness::Rw::Stack { value, .. } = rw {
+                    if *value == StackWord::from(0x8000fu64) {
+                        *value = StackWord::from(0x1_0008_000fu64);
+                    }
+                }
+            }
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run();
+    }
+
     #[test]
     fn test_i32_64_rem() {
         run_test(bytecode! {
@@ -599,4 +748,54 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn test_i32_bitwise() {
+        run_test_with_results(ExecutionState::WASM_BIN, bytecode! {
+            I32Const[0xf0f0ff00]
+            I32Const[0x0ff0f0f0]
+            I32And
+            Drop
+            I32Const[0xf0f0ff00]
+            I32Const[0x0ff0f0f0]
+            I32Or
+            Drop
+            I32Const[0xf0f0ff00]
+            I32Const[0x0ff0f0f0]
+            I32Xor
+            Drop
+        }, vec![0x00f0f000, 0xfff0fff0, 0xff000ff0]);
+    }
+
+    #[test]
+    fn test_rem_s_min_by_minus_one_is_zero() {
+        run_test_with_results(ExecutionState::WASM_BIN, bytecode! {
+            I32Const[0x80000000]
+            I32Const[0xffffffff]
+            I32RemS
+            Drop
+            I64Const[0x8000000000000000]
+            I64Const[0xffffffffffffffff]
+            I64RemS
+            Drop
+        }, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_i32_bitwise_random() {
+        run_bitwise_test(
+            OpcodeId::I32Const,
+            [OpcodeId::I32And, OpcodeId::I32Or, OpcodeId::I32Xor],
+            u32::MAX as u64,
+        );
+    }
+
+    #[test]
+    fn test_i64_bitwise_random() {
+        run_bitwise_test(
+            OpcodeId::I64Const,
+            [OpcodeId::I64And, OpcodeId::I64Or, OpcodeId::I64Xor],
+            u64::MAX,
+        );
+    }
 }
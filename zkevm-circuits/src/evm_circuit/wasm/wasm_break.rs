@@ -1,7 +1,8 @@
+use halo2_proofs::circuit::Value;
 use halo2_proofs::plonk::Error;
 
 use bus_mapping::evm::OpcodeId;
-use eth_types::Field;
+use eth_types::{Field, ToScalar};
 
 use crate::{
     evm_circuit::{
@@ -11,9 +12,12 @@ use crate::{
             CachedRegion,
             common_gadget::SameContextGadget,
             constraint_builder::{StepStateTransition, Transition::To, Transition::Delta},
+            math_gadget::{IsEqualGadget, IsZeroGadget},
+            select,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
+    table::{CallContextFieldTag, WasmMetadataTag},
     util::Expr,
 };
 use crate::evm_circuit::util::Cell;
@@ -22,7 +26,12 @@ use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 #[derive(Clone, Debug)]
 pub(crate) struct WasmBreakGadget<F> {
     same_context: SameContextGadget<F>,
+    /// Program counter of the branch target.
     program_counter: Cell<F>,
+    is_br_if: IsEqualGadget<F>,
+    /// Condition popped by `br_if`, the branch is taken only if it is nonzero.
+    condition: Cell<F>,
+    condition_is_zero: IsZeroGadget<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmBreakGadget<F> {
@@ -31,22 +40,55 @@ impl<F: Field> ExecutionGadget<F> for WasmBreakGadget<F> {
     const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_BREAK;
 
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
         let program_counter = cb.query_cell();
+        let condition = cb.query_cell();
+
+        let is_br_if = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::BrIf.expr());
+        // `br_if` always pops its condition, even if the branch is not taken
+        cb.condition(is_br_if.expr(), |cb| {
+            cb.stack_pop(condition.expr());
+        });
+        let condition_is_zero = IsZeroGadget::construct(cb, condition.expr());
+        let is_taken = 1.expr() - is_br_if.expr() * condition_is_zero.expr();
+
+        // the branch target is recorded only when the branch is taken, otherwise the control
+        // falls through to the next instruction. The target is the one the code section has for
+        // the branch, a `br`/`br_if` leaving the func body is traced as a return
+        let branch_program_counter = cb.curr.state.program_counter.expr();
+        cb.condition(is_taken.clone(), |cb| {
+            cb.call_context_lookup(
+                1.expr(),
+                None,
+                CallContextFieldTag::ProgramCounter,
+                program_counter.expr(),
+            );
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::BranchTarget.expr(),
+                branch_program_counter.clone(),
+                program_counter.expr(),
+            );
+        });
 
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(2.expr()),
-            program_counter: To(program_counter.expr()),
-            stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::Call.constant_gas_cost().expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: To(select::expr(
+                is_taken,
+                program_counter.expr(),
+                branch_program_counter + 1.expr(),
+            )),
+            stack_pointer: Delta(cb.stack_pointer_offset()),
             ..Default::default()
         };
 
-        let opcode = cb.query_cell();
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
             program_counter,
+            is_br_if,
+            condition,
+            condition_is_zero,
         }
     }
 
@@ -54,24 +96,57 @@ impl<F: Field> ExecutionGadget<F> for WasmBreakGadget<F> {
         &self,
         region: &mut CachedRegion<'_, '_, F>,
         offset: usize,
-        _block: &Block<F>,
+        block: &Block<F>,
         _: &Transaction,
         _call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
 
+        let opcode = step.opcode.unwrap();
+        self.is_br_if.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()),
+            F::from(OpcodeId::BrIf.as_u64()),
+        )?;
+
+        let mut rw_indices = step.rw_indices.iter();
+        let condition = if opcode == OpcodeId::BrIf {
+            let condition = block.rws[*rw_indices.next().unwrap()].stack_value();
+            condition.to_scalar().unwrap()
+        } else {
+            F::zero()
+        };
+        self.condition.assign(region, offset, Value::known(condition))?;
+        self.condition_is_zero.assign(region, offset, condition)?;
+
+        // the branch target is written only if the branch is taken
+        if let Some(idx) = rw_indices.next() {
+            let program_counter = block.rws[*idx].call_context_value();
+            self.program_counter.assign(
+                region,
+                offset,
+                Value::known(F::from(program_counter.low_u64())),
+            )?;
+        }
+
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
+    use halo2_proofs::halo2curves::bn256::Fr;
     use wasm_encoder::ValType;
     use eth_types::{bytecode, Bytecode};
     use mock::test_ctx::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{
+        evm_circuit::{step::ExecutionState, witness::Block},
+        test_util::CircuitTestBuilder,
+        witness::Rw,
+    };
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -79,6 +154,55 @@ mod test {
         ).run()
     }
 
+    fn br_if_code(condition: i32) -> Bytecode {
+        bytecode! {
+            Block
+                I32Const[condition]
+                BrIf[0]
+                I32Const[100]
+                Drop
+            End
+        }
+    }
+
+    fn run_br_if_test(condition: i32, check: fn(&Block<Fr>)) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(br_if_code(condition)).unwrap(),
+        ).block_modifier(Box::new(move |block| check(block))).run()
+    }
+
+    /// replaces the target of the taken `br_if` with `forge(br_if pc, target)` and moves the steps
+    /// after the branch along, so the branch target is the only thing going wrong
+    fn run_forged_br_if_target_test(forge: fn(u64, u64) -> u64) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(br_if_code(7)).unwrap(),
+        )
+        .block_modifier(Box::new(move |block| {
+            let steps = &mut block.txs[0].steps;
+            let br_if_step = steps
+                .iter()
+                .position(|step| step.execution_state == ExecutionState::WASM_BREAK)
+                .unwrap();
+            let target = steps[br_if_step + 1].program_counter;
+            let forged_target = forge(steps[br_if_step].program_counter, target);
+            assert_ne!(forged_target, target);
+            for step in steps[br_if_step + 1..].iter_mut() {
+                step.program_counter = step.program_counter + forged_target - target;
+            }
+            let (tag, index) = *steps[br_if_step].rw_indices.last().unwrap();
+            match &mut block.rws.0.get_mut(&tag).unwrap()[index] {
+                Rw::CallContext { value, .. } => *value = forged_target.into(),
+                rw => unreachable!("{:?}", rw),
+            }
+        }))
+        .evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        }))
+        .run();
+    }
+
     // #[test]
     // fn test_wasm_locals_encoding() {
     //     let mut code = bytecode! {
@@ -198,4 +322,49 @@ mod test {
         };
         run_test(code);
     }
+
+    #[test]
+    fn test_wasm_br_if_taken_jumps_to_block_end() {
+        run_br_if_test(7, |block| {
+            let steps = &block.txs[0].steps;
+            let br_if_step = steps.iter()
+                .position(|step| step.execution_state == ExecutionState::WASM_BREAK)
+                .unwrap();
+            let (br_if, next) = (&steps[br_if_step], &steps[br_if_step + 1]);
+            // the condition is popped and the pc is written to the call context
+            assert_eq!(br_if.rw_indices.len(), 2);
+            assert_eq!(next.stack_pointer, br_if.stack_pointer + 1);
+            assert_ne!(next.program_counter, br_if.program_counter + 1);
+            // the rest of the block is skipped
+            assert!(!steps.iter().any(|step| step.execution_state == ExecutionState::WASM_DROP));
+        })
+    }
+
+    #[test]
+    fn test_wasm_br_if_not_taken_falls_through() {
+        run_br_if_test(0, |block| {
+            let steps = &block.txs[0].steps;
+            let br_if_step = steps.iter()
+                .position(|step| step.execution_state == ExecutionState::WASM_BREAK)
+                .unwrap();
+            let (br_if, next) = (&steps[br_if_step], &steps[br_if_step + 1]);
+            // only the condition is popped
+            assert_eq!(br_if.rw_indices.len(), 1);
+            assert_eq!(next.stack_pointer, br_if.stack_pointer + 1);
+            assert_eq!(next.program_counter, br_if.program_counter + 1);
+            assert_eq!(next.execution_state, ExecutionState::WASM_CONST);
+            assert!(steps.iter().any(|step| step.execution_state == ExecutionState::WASM_DROP));
+        })
+    }
+
+    #[test]
+    fn test_wasm_br_if_forged_target_fails() {
+        run_forged_br_if_target_test(|_, target| target + 1)
+    }
+
+    #[test]
+    fn test_wasm_br_if_forged_fallthrough_fails() {
+        // the branch is taken but lands on the instruction right after it
+        run_forged_br_if_target_test(|br_if_pc, _| br_if_pc + 1)
+    }
 }
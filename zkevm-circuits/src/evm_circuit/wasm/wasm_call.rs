@@ -1,21 +1,24 @@
 use halo2_proofs::circuit::Value;
 use halo2_proofs::plonk::Error;
 
-use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
 
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
+        param::STACK_CAPACITY,
         step::ExecutionState,
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta, Transition::To},
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta, Transition::To},
+            math_gadget::LtGadget,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
+    table::{CallContextFieldTag, WasmMetadataTag},
     util::Expr,
+    wasm_circuit::host_interface::helpers::{extract_func_type_indexes, extract_func_types},
 };
 use crate::evm_circuit::util::Cell;
 use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
@@ -25,6 +28,15 @@ pub(crate) struct WasmCallGadget<F> {
     same_context: SameContextGadget<F>,
     program_counter: Cell<F>,
     function_index: Cell<F>,
+    /// Declared type index of the callee and the param/result counts of that type, all read from
+    /// the wasm metadata table.
+    func_type_index: Cell<F>,
+    params_count: Cell<F>,
+    results_count: Cell<F>,
+    /// The operand stack holds fewer values than the callee takes as params.
+    is_params_underflow: LtGadget<F, 2>,
+    /// The results the callee leaves in place of its params don't fit on the operand stack.
+    is_results_overflow: LtGadget<F, 2>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmCallGadget<F> {
@@ -35,35 +47,86 @@ impl<F: Field> ExecutionGadget<F> for WasmCallGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let function_index = cb.query_cell();
         let program_counter = cb.query_cell();
+        let func_type_index = cb.query_cell();
+        let params_count = cb.query_cell();
+        let results_count = cb.query_cell();
 
-        // cb.call_context_lookup(
-        //     1.expr(),
-        //     None,
-        //     CallContextFieldTag::InternalFunctionId,
-        //     function_index.expr(),
-        // );
-        // cb.call_context_lookup(
-        //     1.expr(),
-        //     None,
-        //     CallContextFieldTag::ProgramCounter,
-        //     program_counter.expr(),
-        // );
+        cb.call_context_lookup(
+            1.expr(),
+            None,
+            CallContextFieldTag::InternalFunctionId,
+            function_index.expr(),
+        );
+        cb.call_context_lookup(
+            1.expr(),
+            None,
+            CallContextFieldTag::ProgramCounter,
+            program_counter.expr(),
+        );
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::CallFuncIndex.expr(),
+            cb.curr.state.program_counter.expr(),
+            function_index.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::FuncTypeIndex.expr(),
+            function_index.expr(),
+            func_type_index.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::TypeParamsCount.expr(),
+            func_type_index.expr(),
+            params_count.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::TypeResultsCount.expr(),
+            func_type_index.expr(),
+            results_count.expr(),
+        );
+
+        // The params stay on the operand stack as the first locals of the callee, so the call
+        // itself doesn't move the stack pointer whatever the arity, but the stack must hold all
+        // the params. They are replaced by the results when the callee returns, which moves the
+        // stack pointer by `params_count - results_count`, so the results must fit too.
+        let is_params_underflow = LtGadget::construct(
+            cb,
+            STACK_CAPACITY.expr() - cb.curr.state.stack_pointer.expr(),
+            params_count.expr(),
+        );
+        cb.require_zero(
+            "call: the operand stack holds the callee params",
+            is_params_underflow.expr(),
+        );
+        let is_results_overflow = LtGadget::construct(
+            cb,
+            cb.curr.state.stack_pointer.expr() + params_count.expr(),
+            results_count.expr(),
+        );
+        cb.require_zero(
+            "call: the operand stack has room for the callee results",
+            is_results_overflow.expr(),
+        );
 
         let step_state_transition = StepStateTransition {
             rw_counter: Delta(2.expr()),
             program_counter: To(program_counter.expr()),
             stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::Call.constant_gas_cost().expr()),
             ..Default::default()
         };
 
         let opcode = cb.query_cell();
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
             program_counter,
             function_index,
+            func_type_index,
+            params_count,
+            results_count,
+            is_params_underflow,
+            is_results_overflow,
         }
     }
 
@@ -73,7 +136,7 @@ impl<F: Field> ExecutionGadget<F> for WasmCallGadget<F> {
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
-        _call: &Call,
+        call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
@@ -83,6 +146,37 @@ impl<F: Field> ExecutionGadget<F> for WasmCallGadget<F> {
         let program_counter = block.rws[step.rw_indices[1]].call_context_value();
         self.program_counter.assign(region, offset, Value::known(F::from(program_counter.low_u64())))?;
 
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let func_type_index = *extract_func_type_indexes(&bytecode.bytes)
+            .expect("could not parse the function types")
+            .get(function_index.as_usize())
+            .expect("call callee is undefined");
+        let func_type = extract_func_types(&bytecode.bytes)
+            .expect("could not parse the type section")
+            .get(func_type_index as usize)
+            .cloned()
+            .expect("call callee type is undefined");
+        let params_count = func_type.params.len() as u64;
+        let results_count = func_type.results.len() as u64;
+        self.func_type_index.assign(region, offset, Value::known(F::from(func_type_index)))?;
+        self.params_count.assign(region, offset, Value::known(F::from(params_count)))?;
+        self.results_count.assign(region, offset, Value::known(F::from(results_count)))?;
+        self.is_params_underflow.assign(
+            region,
+            offset,
+            F::from((STACK_CAPACITY - step.stack_pointer) as u64),
+            F::from(params_count),
+        )?;
+        self.is_results_overflow.assign(
+            region,
+            offset,
+            F::from(step.stack_pointer as u64 + params_count),
+            F::from(results_count),
+        )?;
+
         Ok(())
     }
 }
@@ -94,7 +188,7 @@ mod test {
     use eth_types::{bytecode, Bytecode};
     use mock::test_ctx::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder, witness::Rw};
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -132,4 +226,65 @@ mod test {
         }, vec![(1, ValType::I32)]);
         run_test(code);
     }
+
+    #[test]
+    fn test_function_with_two_params_and_one_result() {
+        let mut code = bytecode! {
+            I32Const[100]
+            I32Const[20]
+            Call[0]
+            Drop
+        };
+        code.new_function(vec![ValType::I32; 2], vec![ValType::I32; 1], bytecode! {
+            GetLocal[0]
+            GetLocal[1]
+            I32Add
+            Return
+        }, vec![]);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let steps = &block.txs[0].steps;
+            let call_step = steps.iter()
+                .find(|step| step.execution_state == ExecutionState::WASM_CALL)
+                .unwrap();
+            // the `drop` of the caller runs right after the callee returns
+            let drop_step = steps.iter()
+                .rev()
+                .find(|step| step.execution_state == ExecutionState::WASM_DROP)
+                .unwrap();
+            // 2 params are popped and 1 result is pushed, the stack shrinks by 1
+            assert_eq!(drop_step.stack_pointer, call_step.stack_pointer + 1);
+        })).run()
+    }
+
+    #[test]
+    fn test_forged_function_index_fails() {
+        // both functions share the type, so only the function index bound to the `call` is wrong
+        let mut code = bytecode! {
+            Call[0]
+        };
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let call_step = block.txs[0].steps.iter()
+                .find(|step| step.execution_state == ExecutionState::WASM_CALL)
+                .unwrap();
+            let (tag, index) = call_step.rw_indices[0];
+            match &mut block.rws.0.get_mut(&tag).unwrap()[index] {
+                Rw::CallContext { value, .. } => *value = 1.into(),
+                rw => unreachable!("{:?}", rw),
+            }
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
 }
@@ -0,0 +1,187 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::{Delta, To}},
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{CallContextFieldTag, WasmMetadataTag},
+    util::Expr,
+    wasm_circuit::host_interface::helpers::{extract_func_type_indexes, extract_table_elem_func_indexes},
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmCallIndirectGadget<F> {
+    same_context: SameContextGadget<F>,
+    elem_index: Cell<F>,
+    /// Type index immediate of the `call_indirect`.
+    type_index: Cell<F>,
+    /// Function stored at `elem_index` of the table and its declared type index, both read from
+    /// the wasm metadata table.
+    func_index: Cell<F>,
+    func_type_index: Cell<F>,
+    program_counter: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmCallIndirectGadget<F> {
+    const NAME: &'static str = "WASM_CALL_INDIRECT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_CALL_INDIRECT;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let elem_index = cb.alloc_u64();
+        let type_index = cb.query_cell();
+        let func_index = cb.query_cell();
+        let func_type_index = cb.query_cell();
+        let program_counter = cb.query_cell();
+
+        cb.stack_pop(elem_index.expr());
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::CallIndirectTypeIndex.expr(),
+            cb.curr.state.program_counter.expr(),
+            type_index.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::TableElemFunc.expr(),
+            elem_index.expr(),
+            func_index.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::FuncTypeIndex.expr(),
+            func_index.expr(),
+            func_type_index.expr(),
+        );
+        // an undefined element or a type mismatch is handled by `ErrorCallIndirectGadget`
+        cb.require_equal(
+            "call_indirect: callee type matches the type immediate",
+            func_type_index.expr(),
+            type_index.expr(),
+        );
+
+        cb.call_context_lookup(
+            1.expr(),
+            None,
+            CallContextFieldTag::ProgramCounter,
+            program_counter.expr(),
+        );
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(2.expr()),
+            program_counter: To(program_counter.expr()),
+            stack_pointer: Delta(1.expr()),
+            ..Default::default()
+        };
+
+        let opcode = cb.query_cell();
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            elem_index,
+            type_index,
+            func_index,
+            func_type_index,
+            program_counter,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let elem_index = block.rws[step.rw_indices[0]].stack_value();
+        self.elem_index.assign(region, offset, Value::known(elem_index.to_scalar().unwrap()))?;
+        let program_counter = block.rws[step.rw_indices[1]].call_context_value();
+        self.program_counter.assign(region, offset, Value::known(F::from(program_counter.low_u64())))?;
+
+        let type_index = *step.params.first().expect("call_indirect type index immediate is missing");
+        self.type_index.assign(region, offset, Value::known(F::from(type_index)))?;
+
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let func_index = *extract_table_elem_func_indexes(&bytecode.bytes)
+            .expect("could not parse the element section")
+            .get(elem_index.as_usize())
+            .expect("call_indirect element is undefined");
+        let func_type_index = *extract_func_type_indexes(&bytecode.bytes)
+            .expect("could not parse the function types")
+            .get(func_index as usize)
+            .expect("call_indirect callee is undefined");
+        self.func_index.assign(region, offset, Value::known(F::from(func_index)))?;
+        self.func_type_index.assign(region, offset, Value::known(F::from(func_type_index)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wasm_encoder::ValType;
+
+    use eth_types::{bytecode, Bytecode};
+    use mock::test_ctx::TestContext;
+
+    use crate::test_util::CircuitTestBuilder;
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    #[test]
+    fn test_call_indirect_without_params() {
+        // the type 0 `[] -> []` is always declared first
+        let mut code = bytecode! {
+            I32Const[0]
+            CallIndirect[0]
+        };
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        code.with_table_elements(vec![0]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_call_indirect_picks_table_element() {
+        let mut code = bytecode! {
+            I32Const[100]
+            I32Const[20]
+            I32Const[1]
+            CallIndirect[1]
+            Drop
+        };
+        code.new_function(vec![], vec![], bytecode! {
+            Return
+        }, vec![]);
+        code.new_function(vec![ValType::I32; 2], vec![ValType::I32; 1], bytecode! {
+            GetLocal[0]
+            GetLocal[1]
+            I32Add
+        }, vec![]);
+        code.with_table_elements(vec![0, 1]);
+        run_test(code);
+    }
+}
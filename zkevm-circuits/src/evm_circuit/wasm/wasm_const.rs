@@ -1,5 +1,5 @@
 use halo2_proofs::circuit::Value;
-use halo2_proofs::plonk::Error;
+use halo2_proofs::plonk::{Error, Expression};
 
 use eth_types::{evm_types::OpcodeId, Field, ToScalar};
 
@@ -10,7 +10,8 @@ use crate::{
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta},
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::LtGadget,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -23,6 +24,12 @@ use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 pub(crate) struct WasmConstGadget<F> {
     same_context: SameContextGadget<F>,
     value: Cell<F>,
+    value_bytes: [Cell<F>; 8],
+    is_64bits: Cell<F>,
+    is_float: Cell<F>,
+    /// Pushing on a full operand stack (see `WasmExecutionParams`) goes to `WASM_ErrorStack`.
+    is_stack_not_full: LtGadget<F, 2>,
+    full_stack_pointer: usize,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmConstGadget<F> {
@@ -33,6 +40,39 @@ impl<F: Field> ExecutionGadget<F> for WasmConstGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
         let value = cb.query_cell();
+        let value_bytes: [Cell<F>; 8] = cb.query_bytes();
+        let is_64bits = cb.alloc_bit_value();
+        let is_float = cb.alloc_bit_value();
+
+        cb.require_boolean("const: is_64bits is bool", is_64bits.expr());
+        cb.require_boolean("const: is_float is bool", is_float.expr());
+
+        // Opcodes are laid out as i32.const, i64.const, f32.const, f64.const.
+        cb.require_equal(
+            "const: width is constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::I32Const.expr() + is_64bits.expr() + 2.expr() * is_float.expr(),
+        );
+
+        // Range check of the value against 2^32 or 2^64
+        cb.require_equal(
+            "const: value bytes",
+            value.expr(),
+            value_bytes.iter().enumerate().fold(0.expr(), |acc, (i, byte)| {
+                acc + byte.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
+            }),
+        );
+        for byte in value_bytes.iter().skip(4) {
+            cb.require_zero("const: high bytes of 32 bits value are zero", byte.expr() * (1.expr() - is_64bits.expr()));
+        }
+
+        let full_stack_pointer = cb.wasm_params.full_stack_pointer();
+        let is_stack_not_full = LtGadget::construct(
+            cb,
+            full_stack_pointer.expr(),
+            cb.curr.state.stack_pointer.expr(),
+        );
+        cb.require_equal("const: the operand stack is not full", is_stack_not_full.expr(), 1.expr());
 
         // Push the value on the stack
         cb.stack_push(value.expr());
@@ -42,14 +82,18 @@ impl<F: Field> ExecutionGadget<F> for WasmConstGadget<F> {
             rw_counter: Delta(1.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta((-1).expr()),
-            gas_left: Delta(-OpcodeId::I32Const.constant_gas_cost().expr()),
             ..Default::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
             value,
+            value_bytes,
+            is_64bits,
+            is_float,
+            is_stack_not_full,
+            full_stack_pointer,
         }
     }
 
@@ -67,6 +111,21 @@ impl<F: Field> ExecutionGadget<F> for WasmConstGadget<F> {
         let value = block.rws[step.rw_indices[0]].stack_value();
         self.value
             .assign(region, offset, Value::<F>::known(value.to_scalar().unwrap()))?;
+        for (cell, byte) in self.value_bytes.iter().zip(value.as_u64().to_le_bytes()) {
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+
+        let opcode = step.opcode.unwrap();
+        let is_64bits = matches!(opcode, OpcodeId::I64Const | OpcodeId::F64Const);
+        let is_float = matches!(opcode, OpcodeId::F32Const | OpcodeId::F64Const);
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
+        self.is_float.assign(region, offset, Value::known(F::from(is_float as u64)))?;
+        self.is_stack_not_full.assign(
+            region,
+            offset,
+            F::from(self.full_stack_pointer as u64),
+            F::from(step.stack_pointer as u64),
+        )?;
 
         Ok(())
     }
@@ -74,10 +133,10 @@ impl<F: Field> ExecutionGadget<F> for WasmConstGadget<F> {
 
 #[cfg(test)]
 mod test {
-    use eth_types::{bytecode, Bytecode};
+    use eth_types::{bytecode, Bytecode, StackWord};
     use mock::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{table::RwTableTag, test_util::CircuitTestBuilder};
 
     fn test_ok(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -92,4 +151,39 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn push_gadget_i64_above_32_bits() {
+        test_ok(bytecode! {
+            I64Const[0x1_0000_0000i64]
+            Drop
+        });
+    }
+
+    #[test]
+    fn push_gadget_i32_above_32_bits_fails() {
+        let bytecode = bytecode! {
+            I32Const[0x12345]
+            Drop
+        };
+        let ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap();
+        CircuitTestBuilder::<2, 1>::new_from_test_ctx(ctx)
+            .block_modifier(Box::new(|block| {
+                // `i32.const` can't encode such a value, so the pushed (and dropped) stack
+                // value is replaced with one that doesn't fit into 32 bits.
+                for rw in block.rws.0.get_mut(&RwTableTag::Stack).unwrap().iter_mut() {
+                    if let crate::witness::Rw::Stack { value, .. } = rw {
+                        if *value == StackWord::from(0x12345) {
+                            *value = StackWord::from(0x1_0000_0000u64);
+                        }
+                    }
+                }
+            }))
+            .evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+                assert!(prover
+                    .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                    .is_err())
+            }))
+            .run();
+    }
 }
@@ -12,7 +12,7 @@ use crate::{
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta},
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -27,10 +27,16 @@ pub(crate) struct WasmConversionGadget<F> {
     value: Cell<F>,
     value_limbs: [Cell<F>; 8],
     res: Cell<F>,
+    value_neg: Cell<F>,
     is_value_pos: Cell<F>,
     is_i32_wrap_i64: Cell<F>,
     is_i64_extend_i32_u: Cell<F>,
     is_i64_extend_i32_s: Cell<F>,
+    is_i32_extend8_s: Cell<F>,
+    is_i32_extend16_s: Cell<F>,
+    is_i64_extend8_s: Cell<F>,
+    is_i64_extend16_s: Cell<F>,
+    is_i64_extend32_s: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmConversionGadget<F> {
@@ -43,11 +49,26 @@ impl<F: Field> ExecutionGadget<F> for WasmConversionGadget<F> {
         let value_limbs = [cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(),
                            cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64()];
         let res = cb.alloc_u64();
+        let value_neg = cb.alloc_u64();
 
         let is_value_pos = cb.alloc_bit_value();
         let is_i32_wrap_i64 = cb.alloc_bit_value();
         let is_i64_extend_i32_u = cb.alloc_bit_value();
         let is_i64_extend_i32_s = cb.alloc_bit_value();
+        let is_i32_extend8_s = cb.alloc_bit_value();
+        let is_i32_extend16_s = cb.alloc_bit_value();
+        let is_i64_extend8_s = cb.alloc_bit_value();
+        let is_i64_extend16_s = cb.alloc_bit_value();
+        let is_i64_extend32_s = cb.alloc_bit_value();
+
+        // (selector, bytes of the argument to extend, bytes of the result)
+        let sign_extensions = [
+            (&is_i32_extend8_s, 1, 4),
+            (&is_i32_extend16_s, 2, 4),
+            (&is_i64_extend8_s, 1, 8),
+            (&is_i64_extend16_s, 2, 8),
+            (&is_i64_extend32_s, 4, 8),
+        ];
 
         cb.stack_pop(value.expr());
         cb.stack_push(res.expr());
@@ -63,9 +84,48 @@ impl<F: Field> ExecutionGadget<F> for WasmConversionGadget<F> {
               is_i32_wrap_i64.expr()
             + is_i64_extend_i32_u.expr()
             + is_i64_extend_i32_s.expr()
+            + is_i32_extend8_s.expr()
+            + is_i32_extend16_s.expr()
+            + is_i64_extend8_s.expr()
+            + is_i64_extend16_s.expr()
+            + is_i64_extend32_s.expr()
             - 1.expr()
         ]);
 
+        let opcode = cb.query_cell();
+
+        cb.require_equal(
+            "op_conversion: selectors are constrained from the opcode",
+            opcode.expr(),
+              is_i32_wrap_i64.expr() * OpcodeId::I32WrapI64.expr()
+            + is_i64_extend_i32_u.expr() * OpcodeId::I64ExtendUI32.expr()
+            + is_i64_extend_i32_s.expr() * OpcodeId::I64ExtendSI32.expr()
+            + is_i32_extend8_s.expr() * OpcodeId::I32Extend8S.expr()
+            + is_i32_extend16_s.expr() * OpcodeId::I32Extend16S.expr()
+            + is_i64_extend8_s.expr() * OpcodeId::I64Extend8S.expr()
+            + is_i64_extend16_s.expr() * OpcodeId::I64Extend16S.expr()
+            + is_i64_extend32_s.expr() * OpcodeId::I64Extend32S.expr(),
+        );
+
+        // Sign of i32 argument is the top bit of its fourth limb, sign-extension ops take the
+        // top bit of the last limb they extend from.
+        let is_signed = sign_extensions.iter()
+            .fold(is_i64_extend_i32_s.expr(), |acc, (selector, _, _)| acc + selector.expr());
+        let sign_limb = sign_extensions.iter()
+            .fold(value_limbs[3].expr() * is_i64_extend_i32_s.expr(), |acc, (selector, bytes, _)| {
+                acc + value_limbs[bytes - 1].expr() * selector.expr()
+            });
+        cb.condition(is_signed, |cb| {
+            cb.add_lookup("op_conversion: sign of argument", Lookup::Fixed {
+                tag: FixedTableTag::SignByte.expr(),
+                values: [
+                    sign_limb,
+                    (1.expr() - is_value_pos.expr()) * 0xff.expr(),
+                    0.expr(),
+                ],
+            });
+        });
+
         cb.require_zeros(
             "op_conversion: argument from limbs",
             vec![{
@@ -100,35 +160,66 @@ impl<F: Field> ExecutionGadget<F> for WasmConversionGadget<F> {
                 let cond = || is_i64_extend_i32_u.expr() + is_i64_extend_i32_s.expr();
                 let pos_cond = || is_i64_extend_i32_u.expr() + is_i64_extend_i32_s.expr() * is_value_pos.expr();
                 let neg_cond = || is_i64_extend_i32_s.expr() * (1.expr() - is_value_pos.expr());
+                // Same conversion as in `wasm_bin.rs`: negative i32 is turned to its absolute value
+                // and then back to negative i64.
+                let conv_32 = |x| 0xffffffff_u64.expr() - x + 1.expr();
+                let conv_64 = |x| 0xffffffff_ffffffff_u64.expr() - x + 1.expr();
                 vec![
                     check * cond(),
                     ( value.expr() - res.expr() ) * pos_cond(),
-                    ( value.expr() + 0xffffffff_00000000_u64.expr() - res.expr() ) * neg_cond(),
+                    ( value.expr() - conv_32(value_neg.expr()) ) * neg_cond(),
+                    ( res.expr() - conv_64(value_neg.expr()) ) * neg_cond(),
                 ]
             },
         );
 
-        let opcode = cb.query_cell();
+        cb.require_zeros(
+            "op_conversion: result case of sign-extension",
+            {
+                // Only the low `bytes` limbs of the argument are extended, the rest is ignored.
+                // Negative argument is turned to its absolute value and then back to negative
+                // value of the result width.
+                let conv = |bytes: usize, x| (u64::MAX >> (64 - bytes * 8)).expr() - x + 1.expr();
+                let mut constraints = vec![];
+                for (selector, bytes, res_bytes) in sign_extensions.iter() {
+                    let mut low = value_limbs[0].expr();
+                    for i in 1..*bytes {
+                        low = low + value_limbs[i].expr() * (1_u64 << i*8).expr();
+                    }
+                    let pos_cond = || selector.expr() * is_value_pos.expr();
+                    let neg_cond = || selector.expr() * (1.expr() - is_value_pos.expr());
+                    constraints.push(( low.clone() - res.expr() ) * pos_cond());
+                    constraints.push(( low - conv(*bytes, value_neg.expr()) ) * neg_cond());
+                    constraints.push(( res.expr() - conv(*res_bytes, value_neg.expr()) ) * neg_cond());
+                }
+                constraints
+            },
+        );
 
         // State transition
         let step_state_transition = StepStateTransition {
             rw_counter: Delta(2.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::I32WrapI64.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
             value,
             value_limbs,
             res,
+            value_neg,
             is_value_pos,
             is_i32_wrap_i64,
             is_i64_extend_i32_u,
             is_i64_extend_i32_s,
+            is_i32_extend8_s,
+            is_i32_extend16_s,
+            is_i64_extend8_s,
+            is_i64_extend16_s,
+            is_i64_extend32_s,
         }
     }
 
@@ -166,8 +257,33 @@ impl<F: Field> ExecutionGadget<F> for WasmConversionGadget<F> {
             OpcodeId::I64ExtendSI32 => {
                 let is_value_pos = (value.as_u32() <= i32::MAX as u32) as u64;
                 self.is_value_pos.assign(region, offset, Value::<F>::known(F::from(is_value_pos)))?;
+                if is_value_pos == 0 {
+                    let value_neg = (value.as_u32() as i32).unsigned_abs() as u64;
+                    self.value_neg.assign(region, offset, Value::<F>::known(F::from(value_neg)))?;
+                }
                 self.is_i64_extend_i32_s.assign(region, offset, Value::known(true.to_scalar().unwrap()))?;
             }
+            OpcodeId::I32Extend8S |
+            OpcodeId::I32Extend16S |
+            OpcodeId::I64Extend8S |
+            OpcodeId::I64Extend16S |
+            OpcodeId::I64Extend32S => {
+                let (selector, bytes) = match opcode {
+                    OpcodeId::I32Extend8S => (&self.is_i32_extend8_s, 1),
+                    OpcodeId::I32Extend16S => (&self.is_i32_extend16_s, 2),
+                    OpcodeId::I64Extend8S => (&self.is_i64_extend8_s, 1),
+                    OpcodeId::I64Extend16S => (&self.is_i64_extend16_s, 2),
+                    _ => (&self.is_i64_extend32_s, 4),
+                };
+                let low = value.0[0] & (u64::MAX >> (64 - bytes * 8));
+                let is_value_pos = (low >> (bytes * 8 - 1) == 0) as u64;
+                self.is_value_pos.assign(region, offset, Value::<F>::known(F::from(is_value_pos)))?;
+                if is_value_pos == 0 {
+                    let value_neg = (1_u64 << (bytes * 8)) - low;
+                    self.value_neg.assign(region, offset, Value::<F>::known(F::from(value_neg)))?;
+                }
+                selector.assign(region, offset, Value::known(true.to_scalar().unwrap()))?;
+            }
             _ => unreachable!("not supported opcode: {:?}", opcode),
         };
  
@@ -180,7 +296,10 @@ mod test {
     use eth_types::{bytecode, Bytecode};
     use mock::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{
+        evm_circuit::{step::ExecutionState, test::run_test_with_results},
+        test_util::CircuitTestBuilder,
+    };
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -232,4 +351,67 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn test_i64_extend_s_i32_all_ones() {
+        run_test_with_results(ExecutionState::WASM_CONVERSION, bytecode! {
+            I32Const[0xffffffff]
+            I64ExtendSI32
+            Drop
+        }, vec![0xffffffff_ffffffff]);
+    }
+
+    #[test]
+    fn test_i32_extend8_s() {
+        run_test_with_results(ExecutionState::WASM_CONVERSION, bytecode! {
+            I32Const[0xff]
+            I32Extend8S
+            Drop
+            I32Const[0x7f]
+            I32Extend8S
+            Drop
+            I32Const[0x1280]
+            I32Extend8S
+            Drop
+        }, vec![0xffffffff, 0x7f, 0xffffff80]);
+    }
+
+    #[test]
+    fn test_i32_extend16_s() {
+        run_test_with_results(ExecutionState::WASM_CONVERSION, bytecode! {
+            I32Const[0x8000]
+            I32Extend16S
+            Drop
+            I32Const[0x12347fff]
+            I32Extend16S
+            Drop
+        }, vec![0xffff8000, 0x7fff]);
+    }
+
+    #[test]
+    fn test_i64_extend_s() {
+        run_test_with_results(ExecutionState::WASM_CONVERSION, bytecode! {
+            I64Const[0xff]
+            I64Extend8S
+            Drop
+            I64Const[0xffff_0000_0000_7fff]
+            I64Extend16S
+            Drop
+            I64Const[0x1_8000_0000]
+            I64Extend32S
+            Drop
+        }, vec![0xffffffff_ffffffff, 0x7fff, 0xffffffff_80000000]);
+    }
+
+    #[test]
+    fn test_i64_extend_s_i32_min() {
+        run_test_with_results(ExecutionState::WASM_CONVERSION, bytecode! {
+            I32Const[0x80000000]
+            I64ExtendSI32
+            Drop
+            I32Const[0x7fffffff]
+            I64ExtendSI32
+            Drop
+        }, vec![0xffffffff_80000000, 0x7fffffff]);
+    }
 }
@@ -0,0 +1,140 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use eth_types::{evm_types::WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX, Field};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{StepStateTransition, Transition::Delta},
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::WasmMetadataTag,
+    util::Expr,
+    wasm_circuit::host_interface::helpers::extract_data_segments,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmDataDropGadget<F> {
+    same_context: SameContextGadget<F>,
+    /// Data index immediate of the `data.drop`.
+    data_index: Cell<F>,
+    /// Length of the segment, only read to check that the segment exists.
+    segment_len: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmDataDropGadget<F> {
+    const NAME: &'static str = "WASM_DATA_DROP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_DATA_DROP;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let data_index = cb.query_cell();
+        let segment_len = cb.query_cell();
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::DataSegmentLen.expr(),
+            data_index.expr(),
+            segment_len.expr(),
+        );
+        // following `memory.init` of the segment read the flag and see an empty segment
+        cb.global_write(
+            (WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX as u64).expr() + data_index.expr(),
+            1.expr(),
+        );
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(0.expr()),
+            ..StepStateTransition::default()
+        };
+
+        let opcode = cb.query_cell();
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            data_index,
+            segment_len,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let data_index = *step.params.first().expect("data.drop data index immediate is missing");
+        self.data_index.assign(region, offset, Value::known(F::from(data_index)))?;
+
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let segment = extract_data_segments(&bytecode.bytes)
+            .expect("could not parse the data section")
+            .get(data_index as usize)
+            .cloned()
+            .expect("data.drop data segment is undefined");
+        // active segments are dropped once the module is instantiated
+        let segment_len = if segment.is_passive { segment.len as u64 } else { 0 };
+        self.segment_len.assign(region, offset, Value::known(F::from(segment_len)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::test_ctx::TestContext;
+
+    use crate::test_util::CircuitTestBuilder;
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    #[test]
+    fn test_data_drop() {
+        let mut code = bytecode! {
+            DataDrop[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_memory_init_after_data_drop() {
+        // the dropped segment is empty, so only an empty range can be copied out of it
+        let mut code = bytecode! {
+            I32Const[0]
+            I32Const[1]
+            I32Const[2]
+            MemoryInit[0]
+            DataDrop[0]
+            I32Const[0]
+            I32Const[0]
+            I32Const[0]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code);
+    }
+}
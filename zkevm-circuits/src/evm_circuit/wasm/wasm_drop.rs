@@ -1,17 +1,18 @@
 use halo2_proofs::circuit::Value;
 use halo2_proofs::plonk::Error;
 
-use bus_mapping::evm::OpcodeId;
 use eth_types::{Field, ToScalar};
 
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
+        param::STACK_CAPACITY,
         step::ExecutionState,
         util::{
             CachedRegion,
             Cell,
-            common_gadget::SameContextGadget, constraint_builder::{StepStateTransition, Transition::Delta},
+            common_gadget::SameContextGadget, constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::LtGadget,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -23,6 +24,7 @@ use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 pub(crate) struct WasmDropGadget<F> {
     same_context: SameContextGadget<F>,
     phase2_value: Cell<F>,
+    is_stack_not_empty: LtGadget<F, 2>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmDropGadget<F> {
@@ -33,6 +35,18 @@ impl<F: Field> ExecutionGadget<F> for WasmDropGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let phase2_value = cb.query_cell_phase2();
 
+        // Drop on an empty stack (stack pointer at the frame base) goes to `WASM_ErrorStack`.
+        let is_stack_not_empty = LtGadget::construct(
+            cb,
+            cb.curr.state.stack_pointer.expr(),
+            STACK_CAPACITY.expr(),
+        );
+        cb.require_equal(
+            "drop: the operand stack is not empty",
+            is_stack_not_empty.expr(),
+            1.expr(),
+        );
+
         // Pop the value from the stack
         cb.stack_pop(phase2_value.expr());
 
@@ -41,15 +55,15 @@ impl<F: Field> ExecutionGadget<F> for WasmDropGadget<F> {
             rw_counter: Delta(1.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(1.expr()),
-            gas_left: Delta(-OpcodeId::POP.constant_gas_cost().expr()),
             ..Default::default()
         };
         let opcode = cb.query_cell();
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
             phase2_value,
+            is_stack_not_empty,
         }
     }
 
@@ -66,6 +80,12 @@ impl<F: Field> ExecutionGadget<F> for WasmDropGadget<F> {
 
         let value = block.rws[step.rw_indices[0]].stack_value();
         self.phase2_value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
+        self.is_stack_not_empty.assign(
+            region,
+            offset,
+            F::from(step.stack_pointer as u64),
+            F::from(STACK_CAPACITY as u64),
+        )?;
 
         Ok(())
     }
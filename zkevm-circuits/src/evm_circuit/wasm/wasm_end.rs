@@ -1,6 +1,7 @@
 use crate::{
     evm_circuit::{
         execution::ExecutionGadget,
+        param::STACK_CAPACITY,
         step::ExecutionState,
         util::{
             constraint_builder::{
@@ -12,12 +13,22 @@ use crate::{
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
-    table::CallContextFieldTag,
+    table::{CallContextFieldTag, WasmMetadataTag},
     util::Expr,
+    wasm_circuit::{
+        consts::WASM_MAIN_EXPORT_NAME,
+        host_interface::helpers::{
+            extract_func_exports, extract_func_type_indexes, extract_func_types,
+        },
+        sections::export::body::circuit::export_name_key,
+    },
 };
 use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
-use halo2_proofs::{circuit::Value, plonk::Error};
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{Error, Expression},
+};
 use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 
 #[derive(Clone, Debug)]
@@ -25,6 +36,11 @@ pub(crate) struct WasmEndGadget<F> {
     code_length: Cell<F>,
     is_out_of_range: IsZeroGadget<F>,
     opcode: Cell<F>,
+    gas_cost: Cell<F>,
+    /// Function exported as `main` the root call runs, its type index and declared result count.
+    main_func_index: Cell<F>,
+    main_type_index: Cell<F>,
+    results_count: Cell<F>,
     // restore_context: RestoreContextGadget<F>,
 }
 
@@ -52,6 +68,8 @@ impl<F: Field> ExecutionGadget<F> for WasmEndGadget<F> {
             opcode.expr(),
             OpcodeId::End.expr(),
         );
+        let gas_cost = cb.query_cell();
+        cb.wasm_opcode_cost_lookup(opcode.expr(), gas_cost.expr());
 
         // Call ends with STOP must be successful
         cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 1.expr());
@@ -63,12 +81,38 @@ impl<F: Field> ExecutionGadget<F> for WasmEndGadget<F> {
             is_to_end_tx,
         );
 
+        let main_func_index = cb.query_cell();
+        let main_type_index = cb.query_cell();
+        let results_count = cb.query_cell();
+
         // When it's a root call
         cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::ExportFuncIndex.expr(),
+                Expression::Constant(export_name_key(WASM_MAIN_EXPORT_NAME)),
+                main_func_index.expr(),
+            );
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::FuncTypeIndex.expr(),
+                main_func_index.expr(),
+                main_type_index.expr(),
+            );
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::TypeResultsCount.expr(),
+                main_type_index.expr(),
+                results_count.expr(),
+            );
+            // The function returns exactly the values left on the operand stack
+            cb.require_equal(
+                "end: the operand stack holds the function results",
+                STACK_CAPACITY.expr() - cb.curr.state.stack_pointer.expr(),
+                results_count.expr(),
+            );
             // Do step state transition
             cb.require_step_state_transition(StepStateTransition {
                 call_id: Same,
                 rw_counter: Delta(1.expr()),
+                gas_left: Delta(-gas_cost.expr()),
                 ..StepStateTransition::any()
             });
         });
@@ -90,6 +134,10 @@ impl<F: Field> ExecutionGadget<F> for WasmEndGadget<F> {
             code_length,
             is_out_of_range,
             opcode,
+            gas_cost,
+            main_func_index,
+            main_type_index,
+            results_count,
             // restore_context,
         }
     }
@@ -122,6 +170,36 @@ impl<F: Field> ExecutionGadget<F> for WasmEndGadget<F> {
         let opcode = step.opcode.unwrap();
         self.opcode
             .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        self.gas_cost
+            .assign(region, offset, Value::known(F::from(step.gas_cost)))?;
+
+        if call.is_root {
+            let main_func_index = extract_func_exports(&code.bytes)
+                .expect("could not parse the export section")
+                .into_iter()
+                .find(|(name, _)| name.as_bytes() == WASM_MAIN_EXPORT_NAME)
+                .map(|(_, func_index)| func_index)
+                .expect("the module doesn't export main");
+            let main_type_index = *extract_func_type_indexes(&code.bytes)
+                .expect("could not parse the function types")
+                .get(main_func_index as usize)
+                .expect("main is undefined");
+            let results_count = extract_func_types(&code.bytes)
+                .expect("could not parse the type section")
+                .get(main_type_index as usize)
+                .expect("main type is undefined")
+                .results
+                .len();
+            self.main_func_index
+                .assign(region, offset, Value::known(F::from(main_func_index)))?;
+            self.main_type_index
+                .assign(region, offset, Value::known(F::from(main_type_index)))?;
+            self.results_count.assign(
+                region,
+                offset,
+                Value::known(F::from(results_count as u64)),
+            )?;
+        }
 
         // if !call.is_root {
         //     self.restore_context
@@ -136,6 +214,7 @@ impl<F: Field> ExecutionGadget<F> for WasmEndGadget<F> {
 mod test {
     use crate::test_util::CircuitTestBuilder;
     use eth_types::{bytecode, Bytecode};
+    use wasm_encoder::ValType;
 
     use mock::TestContext;
 
@@ -152,4 +231,41 @@ mod test {
         };
         run_test(code);
     }
+
+    #[test]
+    fn test_end_with_result() {
+        let mut code = bytecode! {
+            I32Const[1]
+        };
+        code.with_main_results(vec![ValType::I32]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_end_without_declared_result_fails() {
+        // a main leaving a value it doesn't declare doesn't validate, so its `[] -> [i32]` type is
+        // turned into `[i32] -> []` once traced
+        let mut code = bytecode! {
+            I32Const[1]
+        };
+        code.with_main_results(vec![ValType::I32]);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let main_type_bytes = [0x60, 0x00, 0x01, 0x7f];
+            let (bytecode, main_type_offset) = block.bytecodes.values_mut()
+                .find_map(|bytecode| {
+                    let offset = bytecode.bytes.windows(main_type_bytes.len())
+                        .position(|w| w == main_type_bytes)?;
+                    Some((bytecode, offset))
+                })
+                .unwrap();
+            bytecode.bytes[main_type_offset + 1..main_type_offset + 4]
+                .copy_from_slice(&[0x01, 0x7f, 0x00]);
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
 }
@@ -0,0 +1,265 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::FloatOpTag,
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmFloatGadget<F> {
+    same_context: SameContextGadget<F>,
+    lhs: Cell<F>,
+    rhs: Cell<F>,
+    res: Cell<F>,
+    is_add: Cell<F>,
+    is_sub: Cell<F>,
+    is_mul: Cell<F>,
+    is_div: Cell<F>,
+    is_64bits: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmFloatGadget<F> {
+    const NAME: &'static str = "WASM_FLOAT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_FLOAT;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let lhs = cb.alloc_u64();
+        let rhs = cb.alloc_u64();
+        let res = cb.alloc_u64();
+
+        let is_add = cb.alloc_bit_value();
+        let is_sub = cb.alloc_bit_value();
+        let is_mul = cb.alloc_bit_value();
+        let is_div = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+
+        cb.stack_pop(rhs.expr());
+        cb.stack_pop(lhs.expr());
+        cb.stack_push(res.expr());
+
+        cb.require_equal(
+            "float_binop: selector",
+            is_add.expr() + is_sub.expr() + is_mul.expr() + is_div.expr(),
+            1.expr(),
+        );
+        cb.require_boolean("float_binop: is_64bits is bool", is_64bits.expr());
+
+        // Tags of the f32 operations go first, so the f64 tag is shifted by the amount of them.
+        let op = is_add.expr() * FloatOpTag::F32Add.expr() +
+            is_sub.expr() * FloatOpTag::F32Sub.expr() +
+            is_mul.expr() * FloatOpTag::F32Mul.expr() +
+            is_div.expr() * FloatOpTag::F32Div.expr() +
+            is_64bits.expr() * (FloatOpTag::F64Add.expr() - FloatOpTag::F32Add.expr());
+
+        // IEEE-754 results (rounding, NaN propagation, signed zeros) are provided by the float table.
+        cb.float_table_lookup(op, lhs.expr(), rhs.expr(), res.expr());
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            ..StepStateTransition::default()
+        };
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            lhs,
+            rhs,
+            res,
+            is_add,
+            is_sub,
+            is_mul,
+            is_div,
+            is_64bits,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let opcode = step.opcode.unwrap();
+
+        let [rhs, lhs, res] = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
+            .map(|idx| block.rws[idx].stack_value());
+
+        self.lhs.assign(region, offset, Value::known(lhs.to_scalar().unwrap()))?;
+        self.rhs.assign(region, offset, Value::known(rhs.to_scalar().unwrap()))?;
+        self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
+
+        let selector = match opcode {
+            OpcodeId::F32Add | OpcodeId::F64Add => &self.is_add,
+            OpcodeId::F32Sub | OpcodeId::F64Sub => &self.is_sub,
+            OpcodeId::F32Mul | OpcodeId::F64Mul => &self.is_mul,
+            OpcodeId::F32Div | OpcodeId::F64Div => &self.is_div,
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+
+        let is_64bits = matches!(opcode,
+            OpcodeId::F64Add |
+            OpcodeId::F64Sub |
+            OpcodeId::F64Mul |
+            OpcodeId::F64Div
+        );
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::test_util::CircuitTestBuilder;
+
+    const F32_ONE: u32 = 0x3f800000;
+    const F32_POS_ZERO: u32 = 0x00000000;
+    const F32_NEG_ZERO: u32 = 0x80000000;
+    const F32_NEG_INF: u32 = 0xff800000;
+    const F32_NAN: u32 = 0x7fc00000;
+    const F64_ONE: u64 = 0x3ff0000000000000;
+    const F64_POS_ZERO: u64 = 0x0000000000000000;
+    const F64_NEG_ZERO: u64 = 0x8000000000000000;
+    const F64_NAN: u64 = 0x7ff8000000000000;
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    #[test]
+    fn test_f32_arithmetic() {
+        run_test(bytecode! {
+            F32Const[0x40400000] // 3.0
+            F32Const[0x3fc00000] // 1.5
+            F32Add
+            F32Const[0x3f000000] // 0.5
+            F32Sub
+            F32Const[0x40000000] // 2.0
+            F32Mul
+            F32Const[0x40400000] // 3.0
+            F32Div
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_f64_arithmetic() {
+        run_test(bytecode! {
+            F64Const[0x4008000000000000u64] // 3.0
+            F64Const[0x3ff8000000000000u64] // 1.5
+            F64Add
+            F64Const[0x3fe0000000000000u64] // 0.5
+            F64Sub
+            F64Const[0x4000000000000000u64] // 2.0
+            F64Mul
+            F64Const[0x4008000000000000u64] // 3.0
+            F64Div
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_f32_nan_propagation() {
+        run_test(bytecode! {
+            F32Const[F32_NAN]
+            F32Const[F32_ONE]
+            F32Add
+            Drop
+            F32Const[F32_ONE]
+            F32Const[F32_NAN]
+            F32Mul
+            Drop
+            // 0 / 0 produces NaN
+            F32Const[F32_POS_ZERO]
+            F32Const[F32_POS_ZERO]
+            F32Div
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_f64_nan_propagation() {
+        run_test(bytecode! {
+            F64Const[F64_NAN]
+            F64Const[F64_ONE]
+            F64Sub
+            Drop
+            F64Const[F64_POS_ZERO]
+            F64Const[F64_POS_ZERO]
+            F64Div
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_f32_signed_zero() {
+        run_test(bytecode! {
+            // -0 + +0 = +0
+            F32Const[F32_NEG_ZERO]
+            F32Const[F32_POS_ZERO]
+            F32Add
+            Drop
+            // -0 + -0 = -0
+            F32Const[F32_NEG_ZERO]
+            F32Const[F32_NEG_ZERO]
+            F32Add
+            Drop
+            // +0 * -1 = -0
+            F32Const[F32_POS_ZERO]
+            F32Const[0xbf800000u32]
+            F32Mul
+            Drop
+            // 1 / -inf = -0
+            F32Const[F32_ONE]
+            F32Const[F32_NEG_INF]
+            F32Div
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_f64_signed_zero() {
+        run_test(bytecode! {
+            F64Const[F64_NEG_ZERO]
+            F64Const[F64_POS_ZERO]
+            F64Sub
+            Drop
+            F64Const[F64_NEG_ZERO]
+            F64Const[F64_NEG_ZERO]
+            F64Add
+            Drop
+        });
+    }
+}
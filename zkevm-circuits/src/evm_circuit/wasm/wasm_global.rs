@@ -12,10 +12,13 @@ use crate::{
             CachedRegion,
             common_gadget::SameContextGadget,
             constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::LtGadget,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
+    table::WasmMetadataTag,
     util::Expr,
+    wasm_circuit::{host_interface::helpers::extract_global_mutabilities, types::Mutability},
 };
 use crate::evm_circuit::util::Cell;
 use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
@@ -23,10 +26,15 @@ use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 #[derive(Clone, Debug)]
 pub(crate) struct WasmGlobalGadget<F> {
     same_context: SameContextGadget<F>,
-    is_get_global: Cell<F>,
-    is_set_global: Cell<F>,
+    is_get: Cell<F>,
+    is_set: Cell<F>,
     index: Cell<F>,
     value: Cell<F>,
+    /// Mutability flag of the global (as declared by the global section or the global import).
+    is_mutable: Cell<F>,
+    /// `global.get` on a full operand stack (see `WasmExecutionParams`) goes to `WASM_ErrorStack`.
+    is_stack_not_full: LtGadget<F, 2>,
+    full_stack_pointer: usize,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmGlobalGadget<F> {
@@ -37,46 +45,74 @@ impl<F: Field> ExecutionGadget<F> for WasmGlobalGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
 
-        let is_get_global = cb.query_cell();
-        let is_set_global = cb.query_cell();
+        let is_get = cb.alloc_bit_value();
+        let is_set = cb.alloc_bit_value();
 
+        cb.require_boolean("op_global: is_get is bool", is_get.expr());
+        cb.require_boolean("op_global: is_set is bool", is_set.expr());
         cb.require_equal(
             "op_global: selector",
-            is_get_global.expr() + is_set_global.expr(),
+            is_get.expr() + is_set.expr(),
             1.expr(),
         );
+        cb.require_equal(
+            "op_global: opcode matches selector",
+            opcode.expr(),
+            is_get.expr() * OpcodeId::GetGlobal.expr() + is_set.expr() * OpcodeId::SetGlobal.expr(),
+        );
 
         let index = cb.query_cell();
         let value = cb.query_cell();
+        let is_mutable = cb.query_cell();
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::GlobalMutability.expr(),
+            index.expr(),
+            is_mutable.expr(),
+        );
+        cb.require_zero(
+            "op_global: only mutable globals are written",
+            is_set.expr() * (1.expr() - is_mutable.expr()),
+        );
 
-        cb.condition(is_set_global.expr(), |cb| {
+        cb.condition(is_set.expr(), |cb| {
             cb.stack_pop(value.expr());
             cb.global_write(index.expr(), value.expr());
         });
 
-        cb.condition(is_get_global.expr(), |cb| {
+        let full_stack_pointer = cb.wasm_params.full_stack_pointer();
+        let is_stack_not_full = LtGadget::construct(
+            cb,
+            full_stack_pointer.expr(),
+            cb.curr.state.stack_pointer.expr(),
+        );
+
+        cb.condition(is_get.expr(), |cb| {
+            cb.require_equal("op_global: the operand stack is not full", is_stack_not_full.expr(), 1.expr());
             cb.global_read(index.expr(), value.expr());
             cb.stack_push(value.expr());
         });
 
-        let sp = is_get_global.expr() * (-1).expr() + is_set_global.expr() * (1).expr();
+        let sp = is_get.expr() * (-1).expr() + is_set.expr() * (1).expr();
 
         let step_state_transition = StepStateTransition {
             rw_counter: Delta(2.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(sp),
-            gas_left: Delta(-OpcodeId::GetGlobal.constant_gas_cost().expr()),
             ..Default::default()
         };
 
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
-            is_set_global,
-            is_get_global,
+            is_set,
+            is_get,
             index,
             value,
+            is_mutable,
+            is_stack_not_full,
+            full_stack_pointer,
         }
     }
 
@@ -86,20 +122,20 @@ impl<F: Field> ExecutionGadget<F> for WasmGlobalGadget<F> {
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
-        _call: &Call,
+        call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
 
         match step.opcode.unwrap() {
             OpcodeId::SetGlobal => {
-                self.is_set_global.assign(region, offset, Value::known(F::one()))?;
+                self.is_set.assign(region, offset, Value::known(F::one()))?;
                 let (value, index) = block.rws[step.rw_indices[1]].global_value();
                 self.value.assign(region, offset, Value::<F>::known(value.to_scalar().unwrap()))?;
                 self.index.assign(region, offset, Value::<F>::known(index.to_scalar().unwrap()))?;
             },
             OpcodeId::GetGlobal => {
-                self.is_get_global.assign(region, offset, Value::known(F::one()))?;
+                self.is_get.assign(region, offset, Value::known(F::one()))?;
                 let (value, index) = block.rws[step.rw_indices[0]].global_value();
                 self.value.assign(region, offset, Value::<F>::known(value.to_scalar().unwrap()))?;
                 self.index.assign(region, offset, Value::<F>::known(index.to_scalar().unwrap()))?;
@@ -107,6 +143,24 @@ impl<F: Field> ExecutionGadget<F> for WasmGlobalGadget<F> {
             _ => unreachable!("not supported opcode: {:?}", step.opcode),
         };
 
+        let index = *step.params.first().expect("global index immediate is missing");
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let is_mutable = *extract_global_mutabilities(&bytecode.bytes)
+            .expect("could not parse the global mutabilities")
+            .get(index as usize)
+            .expect("global is undefined")
+            == Mutability::Var;
+        self.is_mutable.assign(region, offset, Value::known(F::from(is_mutable as u64)))?;
+        self.is_stack_not_full.assign(
+            region,
+            offset,
+            F::from(self.full_stack_pointer as u64),
+            F::from(step.stack_pointer as u64),
+        )?;
+
         Ok(())
     }
 }
@@ -150,4 +204,43 @@ mod test {
         // code.with_global_variable(GlobalVariable::default_i32(0, 0));
         // run_test(code);
     }
+
+    #[test]
+    fn test_global_set_mutable() {
+        let mut code = bytecode! {
+            I32Const[0x7f]
+            SetGlobal[0]
+        };
+        code.with_global_variable(GlobalVariable::default_i32(0, 0));
+        run_test(code);
+    }
+
+    #[test]
+    fn test_global_set_const_fails() {
+        // a module writing a const global doesn't validate, so the global is declared `var` and
+        // turned into a const one once traced
+        let mut code = bytecode! {
+            I32Const[0x7f]
+            SetGlobal[0]
+        };
+        code.with_global_variable(GlobalVariable::default_i32(0, 0));
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            // `var i32` initialized by `i32.const 0`
+            let global_bytes = [0x7f, 0x01, 0x41, 0x00, 0x0b];
+            let (bytecode, global_offset) = block.bytecodes.values_mut()
+                .find_map(|bytecode| {
+                    let offset = bytecode.bytes.windows(global_bytes.len())
+                        .position(|w| w == global_bytes)?;
+                    Some((bytecode, offset))
+                })
+                .unwrap();
+            bytecode.bytes[global_offset + 1] = 0x00;
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
 }
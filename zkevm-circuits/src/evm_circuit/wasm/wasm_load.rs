@@ -18,44 +18,24 @@ use crate::{
     util::Expr,
 };
 use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 
 #[derive(Clone, Debug)]
 pub(crate) struct WasmLoadGadget<F> {
     same_context: SameContextGadget<F>,
-
-    opcode_load_offset: Cell<F>,
-
-    load_start_block_index: Cell<F>,
-    load_start_block_inner_offset: Cell<F>,
-    load_start_block_inner_offset_helper: Cell<F>,
-
-    load_end_block_index: Cell<F>,
-    load_end_block_inner_offset: Cell<F>,
-    load_end_block_inner_offset_helper: Cell<F>,
-
-    load_value1: Cell<F>,
-    load_value2: Cell<F>,
-
-    mask_bits: [Cell<F>; 16],
-    offset_modulus: Cell<F>,
+    address: Cell<F>,
+    mem_offset: Cell<F>,
+    bytes: [Cell<F>; 8],
     res: Cell<F>,
-    value_in_heap: Cell<F>,
-    load_base: Cell<F>,
-
-    vtype: Cell<F>,
-    is_one_byte: Cell<F>,
-    is_two_bytes: Cell<F>,
-    is_four_bytes: Cell<F>,
-    is_eight_bytes: Cell<F>,
-    is_sign: Cell<F>,
-    is_i64: Cell<F>,
-
-    highest_u4: [Cell<F>; 4],
-
-    //lookup_offset_len_bits: OffsetLenBitsTableLookupCell,
-    //lookup_pow: PowTableLookupCell,
-
-    address_within_allocated_pages_helper: Cell<F>,
+    res_neg: Cell<F>,
+    sign_bit: Cell<F>,
+    top_byte_rest: Cell<F>,
+    is_load8: Cell<F>,
+    is_load16: Cell<F>,
+    is_load32: Cell<F>,
+    is_load64: Cell<F>,
+    is_signed: Cell<F>,
+    is_64bits: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmLoadGadget<F> {
@@ -63,215 +43,123 @@ impl<F: Field> ExecutionGadget<F> for WasmLoadGadget<F> {
 
     const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_LOAD;
 
-    fn configure(cb: &mut ConstrainBuilderCommon<F>) -> Self {
-
-        let opcode_load_offset = cb.alloc_common_range_value();
-
-        let load_start_block_index = cb.alloc_common_range_value();
-        let load_start_block_inner_offset = cb.alloc_common_range_value();
-        let load_start_block_inner_offset_helper = cb.alloc_common_range_value();
-
-        let load_end_block_index = cb.alloc_common_range_value();
-        let load_end_block_inner_offset = cb.alloc_common_range_value();
-        let load_end_block_inner_offset_helper = cb.alloc_common_range_value();
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
 
-        let load_value1 = cb.alloc_u64_on_u8();
-        let load_value2 = cb.alloc_u64_on_u8();
-        let offset_modulus = cb.alloc_u64_on_u8();
+        let address = cb.alloc_u64();
+        let mem_offset = cb.alloc_u64();
+        let bytes: [Cell<F>; 8] = cb.query_bytes();
         let res = cb.alloc_u64();
-        let value_in_heap = cb.alloc_u64();
-        let load_base = cb.alloc_u64();
-
-        let mask_bits = [0; 16].map(|_| cb.alloc_bit_value());
-        let is_one_byte = cb.alloc_bit_value();
-        let is_two_bytes = cb.alloc_bit_value();
-        let is_four_bytes = cb.alloc_bit_value();
-        let is_eight_bytes = cb.alloc_bit_value();
-        let is_sign = cb.alloc_bit_value();
-        let is_i64 = cb.alloc_bit_value();
-        let vtype = cb.alloc_common_range_value();
-
-        let highest_u4 = [0; 4].map(|_| cb.alloc_bit_value());
-
-        //let lookup_offset_len_bits = common.alloc_offset_len_bits_table_lookup();
-        //let lookup_pow = common.alloc_pow_table_lookup();
-
-        let current_memory_page_size = cb.allocated_memory_pages_cell();
-        let address_within_allocated_pages_helper = cb.alloc_common_range_value();
-
-        cb.stack_pop(raw_address.expr());
-        cb.stack_pop(block_value1.expr());
-        cb.stack_pop(block_value2.expr());
-        cb.stack_push(value.expr());
-
-        cb.require_zeros("op_load: start end offset <= 7", vec![
-            load_start_block_inner_offset.expr()
-                + load_start_block_inner_offset_helper.expr()
-                - 7.expr(),
-            load_end_block_inner_offset.expr()
-                + load_end_block_inner_offset_helper.expr()
-                - 7.expr(),
-        ]);
+        let res_neg = cb.alloc_u64();
+
+        let sign_bit = cb.alloc_bit_value();
+        let top_byte_rest = cb.alloc_common_range_value();
+
+        let is_load8 = cb.alloc_bit_value();
+        let is_load16 = cb.alloc_bit_value();
+        let is_load32 = cb.alloc_bit_value();
+        let is_load64 = cb.alloc_bit_value();
+        let is_signed = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+
+        for (name, bit) in [
+            ("load: sign_bit is bool", &sign_bit),
+            ("load: is_load8 is bool", &is_load8),
+            ("load: is_load16 is bool", &is_load16),
+            ("load: is_load32 is bool", &is_load32),
+            ("load: is_load64 is bool", &is_load64),
+            ("load: is_signed is bool", &is_signed),
+            ("load: is_64bits is bool", &is_64bits),
+        ] {
+            cb.require_boolean(name, bit.expr());
+        }
 
-        cb.require_zeros("op_load: start end equation, start_index * 8 + start_offset + len = stop_index * 8 + stop_offset + 1", {
-            let len = 1.expr()
-                + is_two_bytes.expr() * 1.expr()
-                + is_four_bytes.expr() * 3.expr()
-                + is_eight_bytes.expr() * 7.expr();
-            vec![
-                load_start_block_index.expr() * 8.expr()
-                    + load_start_block_inner_offset.expr()
-                    + len
-                    - 1.expr()
-                    - load_end_block_index.expr() * 8.expr()
-                    - load_end_block_inner_offset.expr(),
-            ]
-        });
+        cb.require_equal(
+            "load: selector",
+            is_load8.expr() + is_load16.expr() + is_load32.expr() + is_load64.expr(),
+            1.expr(),
+        );
+        // `i64.load` always operates on 64 bits, `i32.load` and `i64.load` are never signed.
+        cb.require_zero("load: i64.load is 64 bits", is_load64.expr() * (1.expr() - is_64bits.expr()));
+        cb.require_zero("load: i64.load is unsigned", is_load64.expr() * is_signed.expr());
+        cb.require_zero(
+            "load: i32.load is unsigned",
+            is_load32.expr() * (1.expr() - is_64bits.expr()) * is_signed.expr(),
+        );
 
-        cb.require_zeros("op_load: start load_base", vec![
-            load_base.expr() + opcode_load_offset.expr()
-                - load_start_block_index.expr() * 8.expr()
-                - load_start_block_inner_offset.expr(),
-        ]);
+        cb.stack_pop(address.expr());
 
-        cb.require_zeros("op_load: length", vec![
-            is_one_byte.expr()
-                + is_two_bytes.expr()
-                + is_four_bytes.expr()
-                + is_eight_bytes.expr()
-                - 1.expr(),
-        ]);
+        // Amount of bytes read is 1, 2, 4 or 8, so the byte `i` is read if width is greater than `i`.
+        let is_byte_read = |i: usize| -> Expression<F> {
+            match i {
+                0 => 1.expr(),
+                1 => 1.expr() - is_load8.expr(),
+                2 | 3 => is_load32.expr() + is_load64.expr(),
+                _ => is_load64.expr(),
+            }
+        };
+        let effective_address = address.expr() + mem_offset.expr();
+        for (i, byte) in bytes.iter().enumerate() {
+            cb.condition(is_byte_read(i), |cb| {
+                cb.memory_lookup(0.expr(), effective_address.clone() + i.expr(), byte.expr(), None);
+            });
+            cb.require_zero("load: unread byte is zero", byte.expr() * (1.expr() - is_byte_read(i)));
+        }
 
-        cb.require_zeros("op_load: mask_bits offset len", {
-            let len = 1.expr()
-                + is_two_bytes.expr() * 1.expr()
-                + is_four_bytes.expr() * 3.expr()
-                + is_eight_bytes.expr() * 7.expr();
-            let (_, bits_encode) = mask_bits
-                .map(|c| c.expr(meta))
-                .into_iter()
-                .enumerate()
-                .reduce(|(_, acc), (i, e)| (i, acc + e * (1u64 << i).expr()))
-                .unwrap();
-            vec![
-                lookup_offset_len_bits.expr()
-                    - offset_len_bits_encode_expr(
-                        load_start_block_inner_offset.expr(),
-                        len,
-                        bits_encode,
-                    ),
-            ]
+        let value = bytes.iter().enumerate().fold(0.expr(), |acc, (i, byte)| {
+            acc + byte.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
         });
 
-        cb.require_zeros("op_load: pow table lookup", vec![
-            lookup_pow.expr(meta)
-                - pow_table_encode(
-                    offset_modulus.expr(),
-                    load_start_block_inner_offset.expr() * 8.expr(),
-                ),
+        // The most significant loaded byte is split into the sign bit and the remaining 7 bits.
+        let top_byte = is_load8.expr() * bytes[0].expr() +
+            is_load16.expr() * bytes[1].expr() +
+            is_load32.expr() * bytes[3].expr() +
+            is_load64.expr() * bytes[7].expr();
+        cb.require_equal("load: top byte decomposition", top_byte, sign_bit.expr() * 128.expr() + top_byte_rest.expr());
+        cb.range_lookup(top_byte_rest.expr(), 128);
+
+        let modulus = Expression::Constant(F::from(1u64 << 32usize)) +
+            Expression::Constant(F::from((u32::MAX as u64) << 32usize)) * is_64bits.expr();
+        let width_modulus = is_load8.expr() * Expression::Constant(F::from(1u64 << 8usize)) +
+            is_load16.expr() * Expression::Constant(F::from(1u64 << 16usize)) +
+            is_load32.expr() * Expression::Constant(F::from(1u64 << 32usize)) +
+            is_load64.expr() * Expression::Constant(F::from_u128(1u128 << 64usize));
+
+        // If sign extension is required then `res_neg` keeps the absolute value of the loaded number,
+        // so `res` is the same negative number represented in the target bit width.
+        let is_extended = is_signed.expr() * sign_bit.expr();
+        cb.require_zeros("load: sign extension", vec![
+            (res_neg.expr() + value.clone() - width_modulus) * is_extended.clone(),
+            (res.expr() + res_neg.expr() - modulus) * is_extended.clone(),
+            (res.expr() - value) * (1.expr() - is_extended.clone()),
+            res_neg.expr() * (1.expr() - is_extended),
         ]);
 
-        /*constraint_builder.push(
-            "op_load value_in_heap",
-            Box::new(move |meta| {
-                let mut acc = value_in_heap.expr(meta) * offset_modulus.expr(meta);
-                for i in 0..8 {
-                    acc = acc
-                        - load_value1.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8))))
-                            * mask_bits[i as usize].expr(meta);
-                    acc = acc
-                        - load_value2.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8 + 64))))
-                            * mask_bits[i as usize + 8].expr(meta);
-                }
-                vec![acc]
-            }),
-        );*/
-
-        /*constraint_builder.push(
-            "op_load value: value = padding + value_in_heap",
-            Box::new(move |meta| {
-                let mut acc = is_one_byte.expr(meta) * value_in_heap.u4_expr(meta, 1)
-                    + is_two_bytes.expr(meta) * value_in_heap.u4_expr(meta, 3)
-                    + is_four_bytes.expr(meta) * value_in_heap.u4_expr(meta, 7)
-                    + is_eight_bytes.expr(meta) * value_in_heap.u4_expr(meta, 15);
-                for i in 0..4 {
-                    acc = acc - highest_u4[i].expr(meta) * constant_from!(1u64 << 3 - i as u64)
-                }
-                let padding = is_one_byte.expr(meta) * constant_from!(0xffffff00)
-                    + is_two_bytes.expr(meta) * constant_from!(0xffff0000)
-                    + (constant_from!(1) - is_eight_bytes.expr(meta))
-                        * is_i64.expr(meta)
-                        * constant_from!(0xffffffff00000000);
-                vec![
-                    res.expr(meta)
-                        - value_in_heap.expr(meta)
-                        - highest_u4[0].expr(meta) * is_sign.expr(meta) * padding,
-                    acc,
-                ]
-            }),
-        );*/
-
-        cb.require_zeros("op_load: is_i64 = 1 when vtype = 2", vec![
-            is_i64.expr() + 1.expr() - vtype.expr()
-        ]);
-
-        cb.require_zeros("op_load: allocated address", {
-            let len = 1.expr()
-                + is_two_bytes.expr(meta) * 1.expr()
-                + is_four_bytes.expr(meta) * 3.expr()
-                + is_eight_bytes.expr(meta) * 7.expr();
-            vec![
-                load_base.expr()
-                    + opcode_load_offset.expr()
-                    + len
-                    + address_within_allocated_pages_helper.expr()
-                    - current_memory_page_size.expr() * WASM_PAGE_SIZE.expr(),
-            ]
-        });
+        cb.stack_push(res.expr());
 
-        let opcode = cb.query_cell();
-
-        // State transition
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(2.expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
-            // TODO: Change opcode.
-            gas_left: Delta(-OpcodeId::I32Eqz.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
-            opcode_load_offset,
-            load_start_block_index,
-            load_start_block_inner_offset,
-            load_start_block_inner_offset_helper,
-            load_end_block_index,
-            load_end_block_inner_offset,
-            load_end_block_inner_offset_helper,
-            load_value1,
-            load_value2,
-            mask_bits,
-            offset_modulus,
-            load_base,
+            address,
+            mem_offset,
+            bytes,
             res,
-            value_in_heap,
-            is_one_byte,
-            is_two_bytes,
-            is_four_bytes,
-            is_eight_bytes,
-            is_sign,
-            is_i64,
-            highest_u4,
-            vtype,
-            //lookup_stack_write,
-            //lookup_offset_len_bits,
-            lookup_pow,
-            address_within_allocated_pages_helper,
+            res_neg,
+            sign_bit,
+            top_byte_rest,
+            is_load8,
+            is_load16,
+            is_load32,
+            is_load64,
+            is_signed,
+            is_64bits,
         }
     }
 
@@ -281,38 +169,62 @@ impl<F: Field> ExecutionGadget<F> for WasmLoadGadget<F> {
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
-        _call: &Call,
+        _: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
 
         let opcode = step.opcode.unwrap();
 
-        let [rhs, lhs, value] = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
-            .map(|idx| block.rws[idx].stack_value());
+        let (selector, width, is_signed, is_64bits) = match opcode {
+            OpcodeId::I32Load8U => (&self.is_load8, 1, false, false),
+            OpcodeId::I32Load8S => (&self.is_load8, 1, true, false),
+            OpcodeId::I32Load16U => (&self.is_load16, 2, false, false),
+            OpcodeId::I32Load16S => (&self.is_load16, 2, true, false),
+            OpcodeId::I32Load => (&self.is_load32, 4, false, false),
+            OpcodeId::I64Load8U => (&self.is_load8, 1, false, true),
+            OpcodeId::I64Load8S => (&self.is_load8, 1, true, true),
+            OpcodeId::I64Load16U => (&self.is_load16, 2, false, true),
+            OpcodeId::I64Load16S => (&self.is_load16, 2, true, true),
+            OpcodeId::I64Load32U => (&self.is_load32, 4, false, true),
+            OpcodeId::I64Load32S => (&self.is_load32, 4, true, true),
+            OpcodeId::I64Load => (&self.is_load64, 8, false, true),
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+        self.is_signed.assign(region, offset, Value::known(F::from(is_signed as u64)))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
 
-/*
-        self.value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
-        self.value_inv.assign(region, offset, Value::known(F::from(value.as_u64()).invert().unwrap_or(F::zero())))?;
+        let address = block.rws[step.rw_indices[0]].stack_value();
+        let res = block.rws[step.rw_indices[1 + width]].stack_value();
+        self.address.assign(region, offset, Value::known(address.to_scalar().unwrap()))?;
         self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
 
-        match opcode {
-            OpcodeId::I64Eqz => {
-                let zero_or_one = (value.as_u64() == 0) as u64;
-                self.res.assign(region, offset, Value::known(F::from(zero_or_one)))?;
-            }
-            OpcodeId::I32Eqz => {
-                let zero_or_one = (value.as_u32() == 0) as u64;
-                self.res.assign(region, offset, Value::known(F::from(zero_or_one)))?;
-            }
-            _ => unreachable!("not supported opcode: {:?}", opcode),
+        let effective_address = block.rws[step.rw_indices[1]].memory_address();
+        self.mem_offset.assign(region, offset, Value::known(F::from(effective_address - address.as_u64())))?;
+
+        let mut value = 0u64;
+        for (i, cell) in self.bytes.iter().enumerate() {
+            let byte = if i < width {
+                block.rws[step.rw_indices[1 + i]].memory_value()
+            } else {
+                0
+            };
+            value |= (byte as u64) << (8 * i);
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+
+        let top_byte = (value >> (8 * (width - 1))) as u8;
+        let sign_bit = top_byte >> 7;
+        self.sign_bit.assign(region, offset, Value::known(F::from(sign_bit as u64)))?;
+        self.top_byte_rest.assign(region, offset, Value::known(F::from((top_byte & 0x7f) as u64)))?;
+
+        let res_neg = if is_signed && sign_bit == 1 {
+            (1u64 << (8 * width)) - value
+        } else {
+            0
         };
- 
-        let is_i64 = matches!(opcode,
-            OpcodeId::I64Eqz
-        );
-        self.is_i64.assign(region, offset, Value::known(F::from(is_i64 as u64)))?;
-*/
+        self.res_neg.assign(region, offset, Value::known(F::from(res_neg)))?;
 
         Ok(())
     }
@@ -320,7 +232,8 @@ impl<F: Field> ExecutionGadget<F> for WasmLoadGadget<F> {
 
 #[cfg(test)]
 mod test {
-    use eth_types::{bytecode, Bytecode};
+    use eth_types::{bytecode_internal, Bytecode};
+    use eth_types::evm_types::OpcodeId;
     use mock::TestContext;
 
     use crate::test_util::CircuitTestBuilder;
@@ -331,29 +244,51 @@ mod test {
         ).run()
     }
 
-/*
-    #[test]
-    fn test_i32_eqz() {
-        run_test(bytecode! {
-            I32Const[0]
-            I32Eqz
+    fn run_load_test(data: Vec<u8>, op: OpcodeId, mem_offset: u64) {
+        let mut code = Bytecode::default();
+        let data_offset = code.fill_default_global_data(data);
+        bytecode_internal! {code,
+            I32Const[data_offset]
+            .write_memarg(op, mem_offset, 0, 0)
             Drop
-            I32Const[1]
-            I32Eqz
-            Drop
-        });
+        }
+        run_test(code);
     }
 
     #[test]
-    fn test_i64_eqz() {
-        run_test(bytecode! {
-            I64Const[0]
-            I64Eqz
-            Drop
-            I64Const[1]
-            I64Eqz
-            Drop
-        });
+    fn test_i32_load() {
+        run_load_test(vec![0x01, 0x02, 0x03, 0x04], OpcodeId::I32Load, 0);
+    }
+
+    #[test]
+    fn test_i32_load_with_offset() {
+        run_load_test(vec![0x00, 0x00, 0x01, 0x02, 0x03, 0x04], OpcodeId::I32Load, 2);
+    }
+
+    #[test]
+    fn test_i32_load8() {
+        run_load_test(vec![0xff], OpcodeId::I32Load8U, 0);
+        run_load_test(vec![0xff], OpcodeId::I32Load8S, 0);
+    }
+
+    #[test]
+    fn test_i32_load16() {
+        run_load_test(vec![0x01, 0x80], OpcodeId::I32Load16U, 0);
+        run_load_test(vec![0x01, 0x80], OpcodeId::I32Load16S, 0);
+    }
+
+    #[test]
+    fn test_i64_load() {
+        run_load_test(vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x88], OpcodeId::I64Load, 0);
+    }
+
+    #[test]
+    fn test_i64_load_extended() {
+        run_load_test(vec![0x80], OpcodeId::I64Load8U, 0);
+        run_load_test(vec![0x80], OpcodeId::I64Load8S, 0);
+        run_load_test(vec![0x00, 0x80], OpcodeId::I64Load16U, 0);
+        run_load_test(vec![0x00, 0x80], OpcodeId::I64Load16S, 0);
+        run_load_test(vec![0x00, 0x00, 0x00, 0x80], OpcodeId::I64Load32U, 0);
+        run_load_test(vec![0x00, 0x00, 0x00, 0x80], OpcodeId::I64Load32S, 0);
     }
-*/
 }
@@ -12,6 +12,7 @@ use crate::{
             CachedRegion,
             common_gadget::SameContextGadget,
             constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::LtGadget,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -28,6 +29,9 @@ pub(crate) struct WasmLocalGadget<F> {
     is_tee_local: Cell<F>,
     index: Cell<F>,
     value: Cell<F>,
+    /// `local.get` on a full operand stack (see `WasmExecutionParams`) goes to `WASM_ErrorStack`.
+    is_stack_not_full: LtGadget<F, 2>,
+    full_stack_pointer: usize,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
@@ -38,9 +42,9 @@ impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
 
-        let is_get_local = cb.query_cell();
-        let is_set_local = cb.query_cell();
-        let is_tee_local = cb.query_cell();
+        let is_get_local = cb.alloc_bit_value();
+        let is_set_local = cb.alloc_bit_value();
+        let is_tee_local = cb.alloc_bit_value();
 
         let index = cb.query_cell();
         let value = cb.query_cell();
@@ -50,17 +54,41 @@ impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
             is_get_local.expr() + is_set_local.expr() + is_tee_local.expr(),
             1.expr(),
         );
+        for (name, selector) in [
+            ("op_local: is_get_local is bool", &is_get_local),
+            ("op_local: is_set_local is bool", &is_set_local),
+            ("op_local: is_tee_local is bool", &is_tee_local),
+        ] {
+            cb.require_boolean(name, selector.expr());
+        }
+        cb.require_equal(
+            "op_local: selectors are bound to the opcode",
+            opcode.expr(),
+            is_get_local.expr() * OpcodeId::GetLocal.expr() +
+                is_set_local.expr() * OpcodeId::SetLocal.expr() +
+                is_tee_local.expr() * OpcodeId::TeeLocal.expr(),
+        );
 
         cb.condition(is_set_local.expr(), |cb| {
             cb.stack_pop(value.expr());
             cb.stack_lookup(1.expr(), cb.stack_pointer_offset() + index.expr(), value.expr());
         });
 
+        let full_stack_pointer = cb.wasm_params.full_stack_pointer();
+        let is_stack_not_full = LtGadget::construct(
+            cb,
+            full_stack_pointer.expr(),
+            cb.curr.state.stack_pointer.expr(),
+        );
+
         cb.condition(is_get_local.expr(), |cb| {
+            cb.require_equal("op_local: the operand stack is not full", is_stack_not_full.expr(), 1.expr());
             cb.stack_lookup(0.expr(), cb.stack_pointer_offset() + index.expr(), value.expr());
             cb.stack_push(value.expr());
         });
 
+        // Tee writes the local and keeps the value on the stack, so the value is popped and pushed
+        // back at the same stack slot.
         cb.condition(is_tee_local.expr(), |cb| {
             cb.stack_pop(value.expr());
             cb.stack_lookup(1.expr(), cb.stack_pointer_offset() + index.expr() - 1.expr(), value.expr());
@@ -68,15 +96,13 @@ impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
         });
 
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(2.expr()),
+            rw_counter: Delta(2.expr() + is_tee_local.expr()),
             program_counter: Delta(1.expr()),
-            stack_pointer: Delta((-1).expr()),
-            // stack_pointer: Delta(is_tee_local.expr() * 2.expr() + (1.expr() - is_tee_local.expr()) * 1.expr()),
-            gas_left: Delta(-OpcodeId::GetLocal.constant_gas_cost().expr()),
+            stack_pointer: Delta(is_set_local.expr() - is_get_local.expr()),
             ..Default::default()
         };
 
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
@@ -85,6 +111,8 @@ impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
             is_tee_local,
             index,
             value,
+            is_stack_not_full,
+            full_stack_pointer,
         }
     }
 
@@ -120,6 +148,12 @@ impl<F: Field> ExecutionGadget<F> for WasmLocalGadget<F> {
             }
             _ => unreachable!("not supported opcode: {:?}", step.opcode),
         };
+        self.is_stack_not_full.assign(
+            region,
+            offset,
+            F::from(self.full_stack_pointer as u64),
+            F::from(step.stack_pointer as u64),
+        )?;
 
         Ok(())
     }
@@ -132,7 +166,7 @@ mod test {
     use eth_types::{bytecode, Bytecode};
     use mock::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder};
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -140,6 +174,20 @@ mod test {
         ).run()
     }
 
+    /// checks the stack depth change made by every local op of the bytecode
+    fn run_test_with_depth_deltas(bytecode: Bytecode, expected: Vec<i64>) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(move |block| {
+            let steps = &block.txs[0].steps;
+            let deltas = steps.iter().zip(steps.iter().skip(1))
+                .filter(|(step, _)| step.execution_state == ExecutionState::WASM_LOCAL)
+                .map(|(step, next)| step.stack_pointer as i64 - next.stack_pointer as i64)
+                .collect::<Vec<_>>();
+            assert_eq!(deltas, expected);
+        })).run()
+    }
+
     #[test]
     fn test_get_local() {
         let mut code = bytecode! {
@@ -183,6 +231,19 @@ mod test {
         run_test(code);
     }
 
+    #[test]
+    fn test_tee_local_keeps_stack_depth() {
+        let mut code = bytecode! {
+            I32Const[123]
+            TeeLocal[0]
+            SetLocal[1]
+            GetLocal[1]
+            Drop
+        };
+        code.with_main_locals(vec![(2, ValType::I32)]);
+        run_test_with_depth_deltas(code, vec![0, -1, 1]);
+    }
+
     #[test]
     fn test_different_locals() {
         let mut code = bytecode! {
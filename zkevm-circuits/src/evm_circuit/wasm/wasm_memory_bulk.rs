@@ -0,0 +1,335 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{Error, Expression};
+
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::PAGE_SIZE,
+        step::ExecutionState,
+        util::{
+            and,
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::{IsEqualGadget, IsZeroGadget, LtGadget},
+            not,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Gadget for `memory.copy` and `memory.fill`.
+#[derive(Clone, Debug)]
+pub(crate) struct WasmMemoryBulkGadget<F> {
+    same_context: SameContextGadget<F>,
+    is_copy: Cell<F>,
+    length: Cell<F>,
+    /// Source address of `memory.copy` or the value of `memory.fill`.
+    src_or_value: Cell<F>,
+    dst: Cell<F>,
+    /// Lowest byte of the `memory.fill` value, the only one written.
+    fill_byte: Cell<F>,
+    fill_value_high: Cell<F>,
+    dst_out_of_bounds: LtGadget<F, 8>,
+    src_out_of_bounds: LtGadget<F, 8>,
+    /// `src < dst < src + length`, the copy goes from the last byte to the first one.
+    src_lt_dst: LtGadget<F, 8>,
+    dst_lt_src_end: LtGadget<F, 8>,
+    length_is_zero: IsZeroGadget<F>,
+    length_is_one: IsEqualGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmMemoryBulkGadget<F> {
+    const NAME: &'static str = "WASM_MEMORY_BULK";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_MEMORY_BULK;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let is_copy = cb.alloc_bit_value();
+        let length = cb.alloc_u64();
+        let src_or_value = cb.alloc_u64();
+        let dst = cb.alloc_u64();
+        let fill_byte = cb.query_byte();
+        let fill_value_high = cb.alloc_u64();
+
+        cb.require_boolean("op_memory_bulk: is_copy is bool", is_copy.expr());
+        cb.require_equal(
+            "op_memory_bulk: opcode matches is_copy",
+            opcode.expr(),
+            is_copy.expr() * OpcodeId::MemoryCopy.expr() +
+                (1.expr() - is_copy.expr()) * OpcodeId::MemoryFill.expr(),
+        );
+
+        cb.stack_pop(length.expr());
+        cb.stack_pop(src_or_value.expr());
+        cb.stack_pop(dst.expr());
+
+        // Out of bounds accesses trap to `WASM_ErrorMemoryBulkOutOfBounds`.
+        let memory_size = cb.curr.state.memory_word_size.expr() * PAGE_SIZE.expr();
+        let dst_out_of_bounds = LtGadget::construct(cb, memory_size.clone(), dst.expr() + length.expr());
+        cb.require_zero("op_memory_bulk: destination is in bounds", dst_out_of_bounds.expr());
+        let src_out_of_bounds = LtGadget::construct(cb, memory_size, src_or_value.expr() + length.expr());
+        cb.require_zero(
+            "op_memory_bulk: copy source is in bounds",
+            is_copy.expr() * src_out_of_bounds.expr(),
+        );
+
+        cb.condition(1.expr() - is_copy.expr(), |cb| {
+            cb.require_equal(
+                "op_memory_bulk: fill value decomposition",
+                src_or_value.expr(),
+                fill_value_high.expr() * 256.expr() + fill_byte.expr(),
+            );
+        });
+
+        // Every copied byte is a memory read followed by a memory write in the copy table. The
+        // copy circuit walks the addresses up, so a backward copy goes through a scratch memory
+        // keyed by the step's rw counter (never the id of a call).
+        let src_lt_dst = LtGadget::construct(cb, src_or_value.expr(), dst.expr());
+        let dst_lt_src_end = LtGadget::construct(cb, dst.expr(), src_or_value.expr() + length.expr());
+        let length_is_zero = IsZeroGadget::construct(cb, length.expr());
+        let length_is_one = IsEqualGadget::construct(cb, length.expr(), 1.expr());
+        let is_backward = src_lt_dst.expr() * dst_lt_src_end.expr();
+        let call_id = cb.curr.state.call_id.expr();
+        let scratch_id = cb.curr.state.rw_counter.expr();
+        let memory_copy_lookup = |cb: &mut EVMConstraintBuilder<F>,
+                                  (src_id, src): (Expression<F>, Expression<F>),
+                                  (dst_id, dst): (Expression<F>, Expression<F>),
+                                  length: Expression<F>| {
+            cb.copy_table_lookup(
+                src_id,
+                CopyDataType::Memory.expr(),
+                dst_id,
+                CopyDataType::Memory.expr(),
+                src.clone(),
+                src + length.clone(),
+                dst,
+                length.clone(),
+                0.expr(),
+                2.expr() * length,
+            );
+        };
+        let is_forward = and::expr([
+            is_copy.expr(),
+            not::expr(is_backward.clone()),
+            not::expr(length_is_zero.expr()),
+        ]);
+        cb.condition(is_forward, |cb| {
+            memory_copy_lookup(
+                cb,
+                (call_id.clone(), src_or_value.expr()),
+                (call_id.clone(), dst.expr()),
+                length.expr(),
+            );
+        });
+        cb.condition(is_copy.expr() * is_backward, |cb| {
+            memory_copy_lookup(
+                cb,
+                (call_id.clone(), src_or_value.expr()),
+                (scratch_id.clone(), src_or_value.expr()),
+                length.expr(),
+            );
+            memory_copy_lookup(
+                cb,
+                (scratch_id, src_or_value.expr()),
+                (call_id.clone(), dst.expr()),
+                length.expr(),
+            );
+        });
+        // A fill writes its first byte and then copies every byte to the next address.
+        let is_fill = and::expr([1.expr() - is_copy.expr(), not::expr(length_is_zero.expr())]);
+        cb.condition(is_fill, |cb| {
+            cb.memory_lookup(1.expr(), dst.expr(), fill_byte.expr(), None);
+            cb.condition(not::expr(length_is_one.expr()), |cb| {
+                memory_copy_lookup(
+                    cb,
+                    (call_id.clone(), dst.expr()),
+                    (call_id, dst.expr() + 1.expr()),
+                    length.expr() - 1.expr(),
+                );
+            });
+        });
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(3.expr()),
+            ..StepStateTransition::default()
+        };
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            is_copy,
+            length,
+            src_or_value,
+            dst,
+            fill_byte,
+            fill_value_high,
+            dst_out_of_bounds,
+            src_out_of_bounds,
+            src_lt_dst,
+            dst_lt_src_end,
+            length_is_zero,
+            length_is_one,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let is_copy = step.opcode.unwrap() == OpcodeId::MemoryCopy;
+        self.is_copy.assign(region, offset, Value::known(F::from(is_copy as u64)))?;
+
+        let [length, src_or_value, dst] =
+            [0, 1, 2].map(|i| block.rws[step.rw_indices[i]].stack_value());
+        self.length.assign(region, offset, Value::known(length.to_scalar().unwrap()))?;
+        self.src_or_value.assign(region, offset, Value::known(src_or_value.to_scalar().unwrap()))?;
+        self.dst.assign(region, offset, Value::known(dst.to_scalar().unwrap()))?;
+        let (length, src_or_value, dst) = (length.as_u64(), src_or_value.as_u64(), dst.as_u64());
+
+        let memory_size = step.memory_word_size() * PAGE_SIZE as u64;
+        self.dst_out_of_bounds.assign(region, offset, F::from(memory_size), F::from(dst + length))?;
+        self.src_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(memory_size),
+            F::from(src_or_value + length),
+        )?;
+        self.src_lt_dst.assign(region, offset, F::from(src_or_value), F::from(dst))?;
+        self.dst_lt_src_end.assign(region, offset, F::from(dst), F::from(src_or_value + length))?;
+        self.length_is_zero.assign(region, offset, F::from(length))?;
+        self.length_is_one.assign(region, offset, F::from(length), F::one())?;
+
+        if !is_copy {
+            self.fill_byte.assign(region, offset, Value::known(F::from(src_or_value & 0xff)))?;
+            self.fill_value_high.assign(region, offset, Value::known(F::from(src_or_value >> 8)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, bytecode_internal, Bytecode};
+    use eth_types::evm_types::OpcodeId;
+    use mock::TestContext;
+
+    use crate::{evm_circuit::{step::ExecutionState, witness::Rw}, test_util::CircuitTestBuilder};
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    /// copies `length` bytes of `[1, 2, .., 16]` (placed at the data offset) from `src` to `dst`
+    /// (both relative to the data offset) and loads the destination back
+    fn run_copy_test(dst: u32, src: u32, length: u32) {
+        let mut code = Bytecode::default();
+        let data_offset = code.fill_default_global_data((1..=16).collect());
+        bytecode_internal! {code,
+            I32Const[data_offset + dst]
+            I32Const[data_offset + src]
+            I32Const[length]
+            MemoryCopy
+            I32Const[data_offset + dst]
+            .write_memarg(OpcodeId::I64Load, 0, 0, 0)
+            Drop
+        }
+        run_test(code);
+    }
+
+    #[test]
+    fn test_memory_copy_disjoint() {
+        run_copy_test(8, 0, 8);
+    }
+
+    #[test]
+    fn test_memory_copy_overlapping_to_lower_address() {
+        run_copy_test(0, 3, 10);
+    }
+
+    #[test]
+    fn test_memory_copy_overlapping_to_higher_address() {
+        run_copy_test(3, 0, 10);
+    }
+
+    #[test]
+    fn test_memory_copy_in_place() {
+        run_copy_test(4, 4, 8);
+    }
+
+    #[test]
+    fn test_memory_copy_zero_length() {
+        run_copy_test(0, 8, 0);
+    }
+
+    /// fills `length` bytes at the address 100 with `0x1ab` and loads them back
+    fn fill_code(length: u32) -> Bytecode {
+        let mut code = bytecode! {
+            I32Const[100]
+            I32Const[0x1ab]
+            I32Const[length]
+            MemoryFill
+            I32Const[100]
+        };
+        bytecode_internal! {code,
+            .write_memarg(OpcodeId::I64Load, 0, 0, 0)
+            Drop
+        }
+        code
+    }
+
+    #[test]
+    fn test_memory_fill() {
+        run_test(fill_code(8));
+    }
+
+    #[test]
+    fn test_memory_fill_single_byte() {
+        run_test(fill_code(1));
+    }
+
+    #[test]
+    fn test_memory_fill_zero_length() {
+        run_test(fill_code(0));
+    }
+
+    #[test]
+    fn test_memory_fill_forged_byte_fails() {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(fill_code(8)).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let fill_step = block.txs[0].steps.iter()
+                .find(|step| step.execution_state == ExecutionState::WASM_MEMORY_BULK)
+                .unwrap();
+            // the first byte written after the three stack pops
+            let (tag, index) = fill_step.rw_indices[3];
+            match &mut block.rws.0.get_mut(&tag).unwrap()[index] {
+                Rw::Memory { byte, .. } => *byte = 0xac,
+                rw => unreachable!("{:?}", rw),
+            }
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
+}
@@ -0,0 +1,286 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{evm_types::WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX, Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_U64, PAGE_SIZE},
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            math_gadget::{IsZeroGadget, LtGadget},
+            not,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::WasmMetadataTag,
+    util::Expr,
+    wasm_circuit::host_interface::helpers::extract_data_segments,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmMemoryInitGadget<F> {
+    same_context: SameContextGadget<F>,
+    length: Cell<F>,
+    src_offset: Cell<F>,
+    dst_offset: Cell<F>,
+    /// Data index immediate of the `memory.init`.
+    data_index: Cell<F>,
+    /// The segment was dropped by a `data.drop` before, it is empty then.
+    is_dropped: Cell<F>,
+    /// Offset of the first byte of the segment in the bytecode and its length, both read from
+    /// the wasm metadata table.
+    segment_offset: Cell<F>,
+    segment_len: Cell<F>,
+    length_is_zero: IsZeroGadget<F>,
+    /// `segment_len < src_offset + length`, the source range must fit into the segment.
+    src_out_of_range: LtGadget<F, N_BYTES_U64>,
+    /// `memory_size < dst_offset + length`, the destination range must fit into the memory.
+    dst_out_of_bounds: LtGadget<F, N_BYTES_U64>,
+    copy_rwc_inc: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmMemoryInitGadget<F> {
+    const NAME: &'static str = "WASM_MEMORY_INIT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_MEMORY_INIT;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let length = cb.alloc_u64();
+        let src_offset = cb.alloc_u64();
+        let dst_offset = cb.alloc_u64();
+        let data_index = cb.query_cell();
+        let is_dropped = cb.query_bool();
+        let segment_offset = cb.query_cell();
+        let segment_len = cb.query_cell();
+        let copy_rwc_inc = cb.query_cell();
+
+        cb.stack_pop(length.expr());
+        cb.stack_pop(src_offset.expr());
+        cb.stack_pop(dst_offset.expr());
+
+        // `data.drop` writes the flag, it reads as 0 until then
+        cb.global_read(
+            (WASM_DROPPED_DATA_SEGMENT_GLOBAL_INDEX as u64).expr() + data_index.expr(),
+            is_dropped.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::DataSegmentLen.expr(),
+            data_index.expr(),
+            segment_len.expr(),
+        );
+        // a dropped segment behaves as an empty one, an out of range slice is handled by
+        // `ErrorMemoryInitOutOfBoundsGadget`
+        let available_len = (1.expr() - is_dropped.expr()) * segment_len.expr();
+        let src_out_of_range = LtGadget::construct(
+            cb,
+            available_len.clone(),
+            src_offset.expr() + length.expr(),
+        );
+        cb.require_zero(
+            "memory.init: source range is within the segment",
+            src_out_of_range.expr(),
+        );
+        let memory_size = cb.curr.state.memory_word_size.expr() * PAGE_SIZE.expr();
+        let dst_out_of_bounds = LtGadget::construct(cb, memory_size, dst_offset.expr() + length.expr());
+        cb.require_zero(
+            "memory.init: destination range is within the memory",
+            dst_out_of_bounds.expr(),
+        );
+
+        let length_is_zero = IsZeroGadget::construct(cb, length.expr());
+        cb.condition(not::expr(length_is_zero.expr()), |cb| {
+            // the segment isn't empty here, so it has bytes in the bytecode
+            cb.wasm_metadata_lookup(
+                WasmMetadataTag::DataSegmentOffset.expr(),
+                data_index.expr(),
+                segment_offset.expr(),
+            );
+            cb.copy_table_lookup(
+                cb.curr.state.code_hash.expr(),
+                CopyDataType::Bytecode.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                segment_offset.expr() + src_offset.expr(),
+                segment_offset.expr() + available_len,
+                dst_offset.expr(),
+                length.expr(),
+                0.expr(),
+                copy_rwc_inc.expr(),
+            );
+        });
+        cb.condition(length_is_zero.expr(), |cb| {
+            cb.require_zero(
+                "memory.init: if no bytes to copy, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+        });
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(3.expr()),
+            ..StepStateTransition::default()
+        };
+
+        let opcode = cb.query_cell();
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            length,
+            src_offset,
+            dst_offset,
+            data_index,
+            is_dropped,
+            segment_offset,
+            segment_len,
+            length_is_zero,
+            src_out_of_range,
+            dst_out_of_bounds,
+            copy_rwc_inc,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [length, src_offset, dst_offset] =
+            [0, 1, 2].map(|i| block.rws[step.rw_indices[i]].stack_value());
+        self.length.assign(region, offset, Value::known(length.to_scalar().unwrap()))?;
+        self.src_offset.assign(region, offset, Value::known(src_offset.to_scalar().unwrap()))?;
+        self.dst_offset.assign(region, offset, Value::known(dst_offset.to_scalar().unwrap()))?;
+
+        let data_index = *step.params.first().expect("memory.init data index immediate is missing");
+        self.data_index.assign(region, offset, Value::known(F::from(data_index)))?;
+        let (is_dropped, _) = block.rws[step.rw_indices[3]].global_value();
+        self.is_dropped.assign(region, offset, Value::known(F::from(is_dropped.as_u64())))?;
+
+        let bytecode = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let segment = extract_data_segments(&bytecode.bytes)
+            .expect("could not parse the data section")
+            .get(data_index as usize)
+            .cloned()
+            .expect("memory.init data segment is undefined");
+        // active segments are dropped once the module is instantiated
+        let segment_len = if segment.is_passive { segment.len as u64 } else { 0 };
+        self.segment_offset.assign(region, offset, Value::known(F::from(segment.bytes_offset as u64)))?;
+        self.segment_len.assign(region, offset, Value::known(F::from(segment_len)))?;
+
+        let available_len = if is_dropped.is_zero() { segment_len } else { 0 };
+        self.src_out_of_range.assign(
+            region,
+            offset,
+            F::from(available_len),
+            F::from(src_offset.as_u64() + length.as_u64()),
+        )?;
+        self.dst_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(step.memory_word_size() * PAGE_SIZE as u64),
+            F::from(dst_offset.as_u64() + length.as_u64()),
+        )?;
+        self.length_is_zero.assign(region, offset, F::from(length.as_u64()))?;
+        // every copied byte is a memory write
+        self.copy_rwc_inc.assign(region, offset, Value::known(F::from(length.as_u64())))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::test_ctx::TestContext;
+
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder, witness::Rw};
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    #[test]
+    fn test_memory_init_whole_segment() {
+        let mut code = bytecode! {
+            I32Const[0]
+            I32Const[0]
+            I32Const[3]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_memory_init_segment_slice() {
+        let mut code = bytecode! {
+            I32Const[100]
+            I32Const[1]
+            I32Const[2]
+            MemoryInit[1]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        code.add_passive_data(vec![4, 5, 6, 7]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_memory_init_empty_range() {
+        let mut code = bytecode! {
+            I32Const[0]
+            I32Const[3]
+            I32Const[0]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        run_test(code);
+    }
+
+    #[test]
+    fn test_memory_init_forged_dropped_flag_fails() {
+        // the segment reads as dropped, so the copied range is past its bytes
+        let mut code = bytecode! {
+            I32Const[0]
+            I32Const[0]
+            I32Const[3]
+            MemoryInit[0]
+        };
+        code.add_passive_data(vec![1, 2, 3]);
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+        ).block_modifier(Box::new(|block| {
+            let init_step = block.txs[0].steps.iter()
+                .find(|step| step.execution_state == ExecutionState::WASM_MEMORY_INIT)
+                .unwrap();
+            let (tag, index) = init_step.rw_indices[3];
+            match &mut block.rws.0.get_mut(&tag).unwrap()[index] {
+                Rw::Global { value, .. } => *value = 1.into(),
+                rw => unreachable!("{:?}", rw),
+            }
+        })).evm_checks(Box::new(|prover, gate_rows, lookup_rows| {
+            assert!(prover
+                .verify_at_rows_par(gate_rows.iter().cloned(), lookup_rows.iter().cloned())
+                .is_err())
+        })).run()
+    }
+}
@@ -0,0 +1,284 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::Error;
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::{Delta, To}},
+            math_gadget::LtGadget,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{CallContextFieldTag, WasmMetadataTag},
+    util::Expr,
+    wasm_circuit::{
+        consts::WASM_MEMORY_MAX_PAGES, host_interface::helpers::extract_memory_limits,
+    },
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+/// Result pushed by `memory.grow` when the memory can't be grown.
+const MEMORY_GROW_FAILURE: u64 = 0xffffffff;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmMemoryOpGadget<F> {
+    same_context: SameContextGadget<F>,
+    is_grow: Cell<F>,
+    delta: Cell<F>,
+    res: Cell<F>,
+    is_success: Cell<F>,
+    /// Limits of the first memory declared by the memory section of the module, looked up from
+    /// the wasm metadata table, `max_pages` is `WASM_MEMORY_MAX_PAGES` if no max is declared.
+    min_pages: Cell<F>,
+    max_pages: Cell<F>,
+    pages_lt_min: LtGadget<F, 8>,
+    max_lt_new_pages: LtGadget<F, 8>,
+    /// `memory.size` on a full operand stack (see `WasmExecutionParams`) goes to
+    /// `WASM_ErrorStack`.
+    is_stack_not_full: LtGadget<F, 2>,
+    full_stack_pointer: usize,
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmMemoryOpGadget<F> {
+    const NAME: &'static str = "WASM_MEMORY_OP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_MEMORY_OP;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let is_grow = cb.alloc_bit_value();
+        let delta = cb.alloc_u64();
+        let res = cb.alloc_u64();
+        let is_success = cb.alloc_bit_value();
+        let min_pages = cb.alloc_u64();
+        let max_pages = cb.alloc_u64();
+
+        let opcode = cb.query_cell();
+
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::MemoryMinPages.expr(),
+            0.expr(),
+            min_pages.expr(),
+        );
+        cb.wasm_metadata_lookup(
+            WasmMetadataTag::MemoryMaxPages.expr(),
+            0.expr(),
+            max_pages.expr(),
+        );
+
+        cb.require_boolean("op_memory: is_grow is bool", is_grow.expr());
+        cb.require_boolean("op_memory: is_success is bool", is_success.expr());
+
+        // Opcodes are laid out as memory.size, memory.grow.
+        cb.require_equal(
+            "op_memory: is_grow is constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::CurrentMemory.expr() + is_grow.expr(),
+        );
+
+        let pages = cb.curr.state.memory_word_size.expr();
+        let new_pages = pages.clone() + is_success.expr() * delta.expr();
+
+        let pages_lt_min = LtGadget::construct(cb, pages.clone(), min_pages.expr());
+        cb.require_zero(
+            "op_memory: page count is not below the declared min",
+            pages_lt_min.expr(),
+        );
+        let max_lt_new_pages =
+            LtGadget::construct(cb, max_pages.expr(), pages.clone() + delta.expr());
+
+        let full_stack_pointer = cb.wasm_params.full_stack_pointer();
+        let is_stack_not_full = LtGadget::construct(
+            cb,
+            full_stack_pointer.expr(),
+            cb.curr.state.stack_pointer.expr(),
+        );
+
+        cb.condition(1.expr() - is_grow.expr(), |cb| {
+            cb.require_equal(
+                "op_memory: the operand stack is not full for memory.size",
+                is_stack_not_full.expr(),
+                1.expr(),
+            );
+            cb.require_zero("op_memory: delta is zero for memory.size", delta.expr());
+            cb.require_zero("op_memory: memory.size always succeeds", is_success.expr());
+            cb.stack_push(pages.clone());
+        });
+
+        cb.condition(is_grow.expr(), |cb| {
+            // memory.grow fails iff the new page count goes over the declared max
+            cb.require_equal(
+                "op_memory: is_success is set when the declared max is respected",
+                is_success.expr(),
+                1.expr() - max_lt_new_pages.expr(),
+            );
+            cb.require_equal(
+                "op_memory: memory.grow pushes the old page count or -1",
+                res.expr(),
+                is_success.expr() * pages.clone()
+                    + (1.expr() - is_success.expr()) * MEMORY_GROW_FAILURE.expr(),
+            );
+            cb.stack_pop(delta.expr());
+            cb.call_context_lookup(
+                1.expr(),
+                None,
+                CallContextFieldTag::MemorySize,
+                new_pages.clone(),
+            );
+            cb.stack_push(res.expr());
+        });
+
+        // State transition
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(1.expr() + 2.expr() * is_grow.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(is_grow.expr() - 1.expr()),
+            memory_word_size: To(new_pages),
+            ..StepStateTransition::default()
+        };
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            is_grow,
+            delta,
+            res,
+            is_success,
+            min_pages,
+            max_pages,
+            pages_lt_min,
+            max_lt_new_pages,
+            is_stack_not_full,
+            full_stack_pointer,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let opcode = step.opcode.unwrap();
+        let pages = step.memory_word_size();
+
+        // a module without memory has no metadata rows, the lookups fail then
+        let (min_pages, max_pages) = block
+            .bytecodes
+            .get(&call.code_hash)
+            .and_then(|bytecode| extract_memory_limits(&bytecode.bytes).ok().flatten())
+            .map(|(min, max)| (min, max.unwrap_or(WASM_MEMORY_MAX_PAGES)))
+            .unwrap_or((0, WASM_MEMORY_MAX_PAGES));
+        self.min_pages.assign(region, offset, Value::known(F::from(min_pages)))?;
+        self.max_pages.assign(region, offset, Value::known(F::from(max_pages)))?;
+
+        let (is_grow, delta, is_success) = match opcode {
+            OpcodeId::CurrentMemory => (false, 0, false),
+            OpcodeId::GrowMemory => {
+                let [delta, res] = [step.rw_indices[0], step.rw_indices[2]]
+                    .map(|idx| block.rws[idx].stack_value());
+                self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
+                (true, delta.as_u64(), res.as_u64() != MEMORY_GROW_FAILURE)
+            }
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        self.is_grow.assign(region, offset, Value::known(F::from(is_grow as u64)))?;
+        self.delta.assign(region, offset, Value::known(F::from(delta)))?;
+        self.is_success.assign(region, offset, Value::known(F::from(is_success as u64)))?;
+
+        self.pages_lt_min.assign(region, offset, F::from(pages), F::from(min_pages))?;
+        self.max_lt_new_pages.assign(region, offset, F::from(max_pages), F::from(pages + delta))?;
+        self.is_stack_not_full.assign(
+            region,
+            offset,
+            F::from(self.full_stack_pointer as u64),
+            F::from(step.stack_pointer as u64),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, Bytecode};
+    use mock::TestContext;
+
+    use crate::{
+        evm_circuit::{step::ExecutionState, test::run_test_with_results},
+        test_util::CircuitTestBuilder,
+    };
+
+    fn run_test(bytecode: Bytecode) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).run()
+    }
+
+    #[test]
+    fn test_memory_size() {
+        run_test(bytecode! {
+            CurrentMemory
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_memory_grow() {
+        run_test_with_results(ExecutionState::WASM_MEMORY_OP, bytecode! {
+            I32Const[2]
+            GrowMemory
+            Drop
+            CurrentMemory
+            Drop
+        }, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_memory_grow_by_zero() {
+        run_test_with_results(ExecutionState::WASM_MEMORY_OP, bytecode! {
+            I32Const[0]
+            GrowMemory
+            Drop
+        }, vec![1]);
+    }
+
+    #[test]
+    fn test_memory_grow_up_to_declared_max() {
+        let mut code = bytecode! {
+            I32Const[1]
+            GrowMemory
+            Drop
+            I32Const[1]
+            GrowMemory
+            Drop
+        };
+        code.with_memory_maximum(2);
+        run_test_with_results(ExecutionState::WASM_MEMORY_OP, code, vec![1, 0xffffffff]);
+    }
+
+    #[test]
+    fn test_memory_grow_over_declared_max_fails() {
+        let mut code = bytecode! {
+            I32Const[5]
+            GrowMemory
+            Drop
+            CurrentMemory
+            Drop
+        };
+        code.with_memory_maximum(3);
+        run_test_with_results(ExecutionState::WASM_MEMORY_OP, code, vec![0xffffffff, 1]);
+    }
+}
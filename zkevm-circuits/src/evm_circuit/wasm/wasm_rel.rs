@@ -229,10 +229,9 @@ impl<F: Field> ExecutionGadget<F> for WasmRelGadget<F> {
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
             // TODO: Change opcode.
-            gas_left: Delta(-OpcodeId::I32Eqz.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
@@ -1,4 +1,4 @@
-use halo2_proofs::plonk::{Error};
+use halo2_proofs::plonk::{Error, Expression};
 
 use bus_mapping::evm::OpcodeId;
 use eth_types::{Field};
@@ -10,7 +10,7 @@ use crate::{
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta},
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -28,6 +28,12 @@ pub(crate) struct WasmSelectGadget<F> {
     val2: Cell<F>,
     res: Cell<F>,
     vtype: Cell<F>,
+    is_typed_select: Cell<F>,
+    is_64bits: Cell<F>,
+    is_int: Cell<F>,
+    val1_bytes: [Cell<F>; 8],
+    val2_bytes: [Cell<F>; 8],
+    cond_bytes: [Cell<F>; 4],
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
@@ -42,6 +48,12 @@ impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
         let val2 = cb.alloc_u64();
         let res = cb.alloc_u64();
         let vtype = cb.alloc_common_range_value();
+        let is_typed_select = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+        let is_int = cb.alloc_bit_value();
+        let val1_bytes: [Cell<F>; 8] = cb.query_bytes();
+        let val2_bytes: [Cell<F>; 8] = cb.query_bytes();
+        let cond_bytes: [Cell<F>; 4] = cb.query_bytes();
 
         cb.stack_pop(cond.expr());
         cb.stack_pop(val2.expr());
@@ -59,15 +71,70 @@ impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
 
         let opcode = cb.query_cell();
 
+        cb.require_boolean("op_select: is_typed_select is bool", is_typed_select.expr());
+        cb.require_boolean("op_select: is_64bits is bool", is_64bits.expr());
+        cb.require_boolean("op_select: is_int is bool", is_int.expr());
+
+        // Opcodes are laid out as select, select t.
+        cb.require_equal(
+            "op_select: is_typed_select is constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::Select.expr() + is_typed_select.expr(),
+        );
+
+        // Value types are laid out as f64 (0x7c), f32, i64, i32 (0x7f).
+        cb.condition(is_typed_select.expr(), |cb| {
+            cb.require_equal(
+                "op_select: vtype is constrained from the width and kind",
+                vtype.expr(),
+                0x7c.expr() + (1.expr() - is_64bits.expr()) + 2.expr() * is_int.expr(),
+            );
+        });
+        // Untyped select puts no restriction on the operands width.
+        cb.condition(1.expr() - is_typed_select.expr(), |cb| {
+            cb.require_zero("op_select: vtype is zero for untyped select", vtype.expr());
+            cb.require_zero("op_select: is_int is zero for untyped select", is_int.expr());
+            cb.require_equal(
+                "op_select: is_64bits is set for untyped select",
+                is_64bits.expr(),
+                1.expr(),
+            );
+        });
+
+        // The condition is always i32, whatever the width of the operands is.
+        cb.require_equal(
+            "op_select: cond bytes",
+            cond.expr(),
+            cond_bytes.iter().enumerate().fold(0.expr(), |acc, (i, byte)| {
+                acc + byte.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
+            }),
+        );
+
+        // The selected operand is pushed as is, so i64 operands keep their high bytes.
+        for (val, val_bytes) in [(&val1, &val1_bytes), (&val2, &val2_bytes)] {
+            cb.require_equal(
+                "op_select: operand bytes",
+                val.expr(),
+                val_bytes.iter().enumerate().fold(0.expr(), |acc, (i, byte)| {
+                    acc + byte.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
+                }),
+            );
+            for byte in val_bytes.iter().skip(4) {
+                cb.require_zero(
+                    "op_select: high bytes of 32 bits operands are zero",
+                    byte.expr() * (1.expr() - is_64bits.expr()),
+                );
+            }
+        }
+
         // State transition
         let step_state_transition = StepStateTransition {
             rw_counter: Delta(4.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::Select.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
@@ -77,6 +144,12 @@ impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
             val2,
             res,
             vtype,
+            is_typed_select,
+            is_64bits,
+            is_int,
+            val1_bytes,
+            val2_bytes,
+            cond_bytes,
         }
     }
 
@@ -105,6 +178,27 @@ impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
         self.val1.assign(region, offset, Value::known(val1.to_scalar().unwrap()))?;
         self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
 
+        for (cells, val) in [(&self.val1_bytes, val1), (&self.val2_bytes, val2)] {
+            for (cell, byte) in cells.iter().zip(val.as_u64().to_le_bytes()) {
+                cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+            }
+        }
+        for (cell, byte) in self.cond_bytes.iter().zip(cond.as_u32().to_le_bytes()) {
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+
+        let (is_typed_select, vtype) = match opcode {
+            OpcodeId::Select => (false, 0),
+            OpcodeId::SelectT => (true, step.params[0]),
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        let is_64bits = !is_typed_select || matches!(vtype, 0x7e | 0x7c);
+        let is_int = is_typed_select && matches!(vtype, 0x7f | 0x7e);
+        self.is_typed_select.assign(region, offset, Value::known(F::from(is_typed_select as u64)))?;
+        self.vtype.assign(region, offset, Value::known(F::from(vtype)))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
+        self.is_int.assign(region, offset, Value::known(F::from(is_int as u64)))?;
+
 /*
         self.value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
         self.value_inv.assign(region, offset, Value::known(F::from(value.as_u64()).invert().unwrap_or(F::zero())))?;
@@ -134,10 +228,10 @@ impl<F: Field> ExecutionGadget<F> for WasmSelectGadget<F> {
 
 #[cfg(test)]
 mod test {
-    use eth_types::{bytecode, Bytecode};
+    use eth_types::{bytecode, Bytecode, StackWord};
     use mock::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder, witness::Rw};
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -145,6 +239,26 @@ mod test {
         ).run()
     }
 
+    /// checks that select pushes `expected` into the slot of the first operand, so the
+    /// non-selected operand (and the condition) are dropped from the stack
+    fn run_test_with_result(bytecode: Bytecode, expected: u64) {
+        CircuitTestBuilder::new_from_test_ctx(
+            TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+        ).block_modifier(Box::new(move |block| {
+            let step = block.txs[0].steps.iter()
+                .find(|step| step.execution_state == ExecutionState::WASM_SELECT)
+                .expect("select step");
+            let stack_pointer = |rw: &Rw| match rw {
+                Rw::Stack { stack_pointer, .. } => *stack_pointer,
+                _ => unreachable!("not a stack rw: {:?}", rw),
+            };
+            let val1 = &block.rws[step.rw_indices[2]];
+            let res = &block.rws[step.rw_indices[3]];
+            assert_eq!(res.stack_value(), StackWord::from(expected));
+            assert_eq!(stack_pointer(res), stack_pointer(val1));
+        })).run()
+    }
+
     #[test]
     fn test_select_i32() {
         run_test(bytecode! {
@@ -166,4 +280,68 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn test_select_typed_i32() {
+        run_test(bytecode! {
+            I32Const[1]
+            I32Const[2]
+            I32Const[0]
+            SelectT[0x7f]
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_select_typed_i64() {
+        run_test(bytecode! {
+            I64Const[0x1_0000_0000i64]
+            I64Const[2]
+            I32Const[1]
+            SelectT[0x7e]
+            Drop
+        });
+    }
+
+    #[test]
+    fn test_select_i64_keeps_high_bits() {
+        // operands only differ in their high 32 bits
+        let (val1, val2) = (0x1_0000_0002u64, 0x2_0000_0002u64);
+        for (cond, expected) in [(0, val2), (1, val1)] {
+            run_test_with_result(bytecode! {
+                I64Const[val1 as i64]
+                I64Const[val2 as i64]
+                I32Const[cond]
+                Select
+                Drop
+            }, expected);
+            run_test_with_result(bytecode! {
+                I64Const[val1 as i64]
+                I64Const[val2 as i64]
+                I32Const[cond]
+                SelectT[0x7e]
+                Drop
+            }, expected);
+        }
+    }
+
+    #[test]
+    fn test_select_drops_non_selected_operand() {
+        for (cond, expected) in [(0, 2), (1, 1)] {
+            run_test_with_result(bytecode! {
+                I32Const[1]
+                I32Const[2]
+                I32Const[cond]
+                Select
+                Drop
+            }, expected);
+            run_test_with_result(bytecode! {
+                I32Const[1]
+                I32Const[2]
+                I32Const[cond]
+                SelectT[0x7f]
+                Drop
+            }, expected);
+        }
+    }
 }
@@ -0,0 +1,580 @@
+use halo2_proofs::circuit::Value;
+use halo2_proofs::plonk::{Error, Expression};
+
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToScalar};
+
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        table::{FixedTableTag, Lookup},
+        step::ExecutionState,
+        util::{
+            CachedRegion,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
+            from_bytes,
+            math_gadget::IsZeroGadget,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
+
+#[derive(Clone, Debug)]
+pub(crate) struct WasmShiftGadget<F> {
+    same_context: SameContextGadget<F>,
+    lhs: Cell<F>,
+    lhs_limbs: [Cell<F>; 8],
+    rhs: Cell<F>,
+    res: Cell<F>,
+    is_shl: Cell<F>,
+    is_shr_s: Cell<F>,
+    is_shr_u: Cell<F>,
+    is_rotl: Cell<F>,
+    is_rotr: Cell<F>,
+    is_64bits: Cell<F>,
+    is_lhs_neg: Cell<F>,
+    /// Shift amount masked by the width, `rhs = rhs_hi * width + shift`.
+    shift: Cell<F>,
+    rhs_hi: [Cell<F>; 8],
+    is_shift_zero: IsZeroGadget<F>,
+    /// `pow = 2^shift` and `rev_pow = 2^(width - shift)`, except `rev_pow = 0` for a zero shift
+    /// since `2^width` doesn't fit into the 8 bytes of `rev_pow` for i64.
+    pow: Cell<F>,
+    rev_pow: [Cell<F>; 8],
+    /// Left ops: `lhs * pow = aux_hi * modulus + aux_lo`, `aux_hi` holds the shifted-out bits.
+    /// Right ops: `lhs = aux_hi * pow + aux_lo`, `aux_lo` holds the shifted-out bits.
+    aux_hi: [Cell<F>; 8],
+    aux_lo: [Cell<F>; 8],
+    /// `aux_lo + aux_lo_rem + 1` is the bound of `aux_lo`.
+    aux_lo_rem: [Cell<F>; 8],
+    /// Rotates only: byte limbs of the bits kept in place (`x << n` for rotl, `x >> n` for rotr),
+    /// of the bits wrapped around the width and of the result, `res_limbs = kept | wrapped`.
+    rot_kept_limbs: [Cell<F>; 8],
+    rot_wrapped_limbs: [Cell<F>; 8],
+    res_limbs: [Cell<F>; 8],
+}
+
+impl<F: Field> ExecutionGadget<F> for WasmShiftGadget<F> {
+    const NAME: &'static str = "WASM_SHIFT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_SHIFT;
+
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let lhs = cb.alloc_u64_on_u8();
+        let lhs_limbs = [cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(),
+                         cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64(), cb.alloc_u64()];
+        let rhs = cb.alloc_u64_on_u8();
+        let res = cb.alloc_u64_on_u8();
+
+        let is_shl = cb.alloc_bit_value();
+        let is_shr_s = cb.alloc_bit_value();
+        let is_shr_u = cb.alloc_bit_value();
+        let is_rotl = cb.alloc_bit_value();
+        let is_rotr = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+        let is_32bits = || 1.expr() - is_64bits.expr();
+        let is_lhs_neg = cb.alloc_bit_value();
+
+        let shift = cb.alloc_common_range_value();
+        let pow = cb.alloc_u64_on_u8();
+        // Byte cells, otherwise the decompositions below have solutions wrapping the field.
+        let rhs_hi: [Cell<F>; 8] = cb.query_bytes();
+        let rev_pow: [Cell<F>; 8] = cb.query_bytes();
+        let aux_hi: [Cell<F>; 8] = cb.query_bytes();
+        let aux_lo: [Cell<F>; 8] = cb.query_bytes();
+        let aux_lo_rem: [Cell<F>; 8] = cb.query_bytes();
+        let rhs_hi_expr = || from_bytes::expr(&rhs_hi);
+        let rev_pow_expr = || from_bytes::expr(&rev_pow);
+        let aux_hi_expr = || from_bytes::expr(&aux_hi);
+        let aux_lo_expr = || from_bytes::expr(&aux_lo);
+        let aux_lo_rem_expr = || from_bytes::expr(&aux_lo_rem);
+        let rot_kept_limbs = [(); 8].map(|_| cb.query_cell());
+        let rot_wrapped_limbs = [(); 8].map(|_| cb.query_cell());
+        let res_limbs = [(); 8].map(|_| cb.query_cell());
+
+        cb.stack_pop(rhs.expr());
+        cb.stack_pop(lhs.expr());
+        cb.stack_push(res.expr());
+
+        for cell in [&is_shl, &is_shr_s, &is_shr_u, &is_rotl, &is_rotr, &is_64bits, &is_lhs_neg] {
+            cb.require_boolean("op_shift: flag is bool", cell.expr());
+        }
+        cb.require_equal(
+            "op_shift: selector",
+            is_shl.expr() + is_shr_s.expr() + is_shr_u.expr() + is_rotl.expr() + is_rotr.expr(),
+            1.expr(),
+        );
+
+        // Opcodes are laid out as shl, shr_s, shr_u, rotl, rotr for both i32 and i64.
+        cb.require_equal(
+            "op_shift: selectors are constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::I32Shl.expr()
+                + is_shr_s.expr()
+                + 2.expr() * is_shr_u.expr()
+                + 3.expr() * is_rotl.expr()
+                + 4.expr() * is_rotr.expr()
+                + (OpcodeId::I64Shl.as_u64() - OpcodeId::I32Shl.as_u64()).expr() * is_64bits.expr(),
+        );
+
+        let width = 32.expr() + 32.expr() * is_64bits.expr();
+        let modulus = Expression::Constant(F::from(1u64 << 32usize)) +
+            Expression::Constant(F::from((u32::MAX as u64) << 32usize)) * is_64bits.expr();
+
+        // Shift amount is taken modulo the width.
+        cb.require_equal(
+            "op_shift: shift is rhs modulo width",
+            rhs.expr(),
+            rhs_hi_expr() * width + shift.expr(),
+        );
+        cb.add_lookup("op_shift: shift is below 64", Lookup::Fixed {
+            tag: FixedTableTag::Range64.expr(),
+            values: [shift.expr(), 0.expr(), 0.expr()],
+        });
+        cb.condition(is_32bits(), |cb| {
+            cb.add_lookup("op_shift: shift is below 32", Lookup::Fixed {
+                tag: FixedTableTag::Range32.expr(),
+                values: [shift.expr(), 0.expr(), 0.expr()],
+            });
+        });
+        cb.add_lookup("op_shift: pow is 2^shift", Lookup::Fixed {
+            tag: FixedTableTag::Pow2.expr(),
+            values: [shift.expr(), pow.expr(), 0.expr()],
+        });
+        let is_shift_zero = IsZeroGadget::construct(cb, shift.expr());
+        cb.condition(1.expr() - is_shift_zero.expr(), |cb| {
+            cb.require_equal(
+                "op_shift: rev_pow is 2^(width - shift)",
+                pow.expr() * rev_pow_expr(),
+                modulus.clone(),
+            );
+        });
+        cb.condition(is_shift_zero.expr(), |cb| {
+            cb.require_zero("op_shift: rev_pow is zero for a zero shift", rev_pow_expr());
+        });
+
+        cb.require_zeros(
+            "op_shift: argument from limbs",
+            vec![{
+                let mut out = lhs_limbs[0].expr();
+                for i in 1..8 {
+                  out = out + lhs_limbs[i].expr() * (1_u64 << i*8).expr();
+                }
+                out - lhs.expr()
+            }],
+        );
+        for i in 0..4 {
+            cb.add_lookup("op_shift: Using Range256x2 fixed table", Lookup::Fixed {
+                tag: FixedTableTag::Range256x2.expr(),
+                values: [lhs_limbs[i*2].expr(), lhs_limbs[i*2+1].expr(), 0.expr()],
+            });
+        }
+        cb.require_zeros(
+            "op_shift: i32 argument has no high limbs",
+            (4..8).map(|i| lhs_limbs[i].expr() * is_32bits()).collect(),
+        );
+
+        let is_left = || is_shl.expr() + is_rotl.expr();
+        let is_right = || is_shr_s.expr() + is_shr_u.expr() + is_rotr.expr();
+
+        cb.require_zeros("op_shift: left shift decomposition", vec![
+            (lhs.expr() * pow.expr() - aux_hi_expr() * modulus.clone() - aux_lo_expr()) * is_left(),
+            (aux_lo_expr() + aux_lo_rem_expr() + 1.expr() - modulus.clone()) * is_left(),
+        ]);
+        cb.require_zeros("op_shift: right shift decomposition", vec![
+            (lhs.expr() - aux_hi_expr() * pow.expr() - aux_lo_expr()) * is_right(),
+            (aux_lo_expr() + aux_lo_rem_expr() + 1.expr() - pow.expr()) * is_right(),
+        ]);
+
+        // Sign of the argument is the top bit of its top limb, needed by shr_s only.
+        cb.condition(is_shr_s.expr(), |cb| {
+            cb.add_lookup("op_shift: sign of argument", Lookup::Fixed {
+                tag: FixedTableTag::SignByte.expr(),
+                values: [
+                    lhs_limbs[7].expr() * is_64bits.expr() + lhs_limbs[3].expr() * is_32bits(),
+                    is_lhs_neg.expr() * 0xff.expr(),
+                    0.expr(),
+                ],
+            });
+        });
+
+        cb.require_zeros("op_shift: result", vec![
+            (res.expr() - aux_lo_expr()) * is_shl.expr(),
+            (res.expr() - aux_hi_expr()) * is_shr_u.expr(),
+            // Negative argument gets its top `shift` bits set.
+            // Nothing is filled for a zero shift, where `rev_pow` is zero instead of the modulus.
+            (res.expr() - aux_hi_expr() - is_lhs_neg.expr() * (1.expr() - is_shift_zero.expr())
+                * (modulus.clone() - rev_pow_expr())) * is_shr_s.expr(),
+        ]);
+
+        // Rotates: rotl(x, n) = (x << n) | (x >> (w - n)) and rotr(x, n) = (x >> n) | (x << (w - n))
+        // with n = shift, the two parts are OR-ed limb by limb through the bitwise table.
+        let is_rotate = is_rotl.expr() + is_rotr.expr();
+        let from_limbs = |limbs: &[Cell<F>; 8]| {
+            limbs.iter().enumerate().fold(0.expr(), |acc, (i, limb)| {
+                acc + limb.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
+            })
+        };
+        let rot_kept = aux_lo_expr() * is_rotl.expr() + aux_hi_expr() * is_rotr.expr();
+        let rot_wrapped =
+            aux_hi_expr() * is_rotl.expr() + aux_lo_expr() * rev_pow_expr() * is_rotr.expr();
+        cb.require_zeros("op_shift: rotate decomposition", vec![
+            (from_limbs(&rot_kept_limbs) - rot_kept) * is_rotate.clone(),
+            (from_limbs(&rot_wrapped_limbs) - rot_wrapped) * is_rotate.clone(),
+            (from_limbs(&res_limbs) - res.expr()) * is_rotate.clone(),
+        ]);
+        cb.condition(is_rotate, |cb| {
+            for i in 0..8 {
+                cb.add_lookup("op_shift: rotate result limb is kept | wrapped", Lookup::Fixed {
+                    tag: FixedTableTag::BitwiseOr.expr(),
+                    values: [
+                        rot_kept_limbs[i].expr(),
+                        rot_wrapped_limbs[i].expr(),
+                        res_limbs[i].expr(),
+                    ],
+                });
+            }
+        });
+
+        // State transition
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            ..StepStateTransition::default()
+        };
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            lhs,
+            lhs_limbs,
+            rhs,
+            res,
+            is_shl,
+            is_shr_s,
+            is_shr_u,
+            is_rotl,
+            is_rotr,
+            is_64bits,
+            is_lhs_neg,
+            shift,
+            rhs_hi,
+            is_shift_zero,
+            pow,
+            rev_pow,
+            aux_hi,
+            aux_lo,
+            aux_lo_rem,
+            rot_kept_limbs,
+            rot_wrapped_limbs,
+            res_limbs,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let opcode = step.opcode.unwrap();
+
+        let [rhs, lhs, res] = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]]
+            .map(|idx| block.rws[idx].stack_value());
+
+        self.lhs.assign(region, offset, Value::known(lhs.to_scalar().unwrap()))?;
+        self.rhs.assign(region, offset, Value::known(rhs.to_scalar().unwrap()))?;
+        self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
+
+        let (selector, is_64bits) = match opcode {
+            OpcodeId::I32Shl => (&self.is_shl, false),
+            OpcodeId::I32ShrS => (&self.is_shr_s, false),
+            OpcodeId::I32ShrU => (&self.is_shr_u, false),
+            OpcodeId::I32Rotl => (&self.is_rotl, false),
+            OpcodeId::I32Rotr => (&self.is_rotr, false),
+            OpcodeId::I64Shl => (&self.is_shl, true),
+            OpcodeId::I64ShrS => (&self.is_shr_s, true),
+            OpcodeId::I64ShrU => (&self.is_shr_u, true),
+            OpcodeId::I64Rotl => (&self.is_rotl, true),
+            OpcodeId::I64Rotr => (&self.is_rotr, true),
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
+
+        let lhs = lhs.as_u64();
+        let rhs = rhs.as_u64();
+        let width: u64 = if is_64bits { 64 } else { 32 };
+        let modulus = 1u128 << width;
+        let shift = rhs % width;
+        let pow = 1u128 << shift;
+        let rev_pow = modulus / pow;
+
+        for idx in 0..8 {
+            let limb = (lhs >> (idx * 8)) & 0xff;
+            self.lhs_limbs[idx].assign(region, offset, Value::<F>::known(F::from(limb)))?;
+        }
+        let is_lhs_neg = (lhs >> (width - 1)) & 1;
+        self.is_lhs_neg.assign(region, offset, Value::known(F::from(is_lhs_neg)))?;
+
+        let assign_bytes = |
+            region: &mut CachedRegion<'_, '_, F>,
+            cells: &[Cell<F>; 8],
+            value: u128,
+        | {
+            for (cell, byte) in cells.iter().zip((value as u64).to_le_bytes()) {
+                cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+            }
+            Ok::<(), Error>(())
+        };
+
+        self.shift.assign(region, offset, Value::known(F::from(shift)))?;
+        assign_bytes(region, &self.rhs_hi, (rhs / width) as u128)?;
+        self.is_shift_zero.assign(region, offset, F::from(shift))?;
+        self.pow.assign(region, offset, Value::known(F::from_u128(pow)))?;
+        assign_bytes(region, &self.rev_pow, if shift == 0 { 0 } else { rev_pow })?;
+
+        let (aux_hi, aux_lo, aux_lo_bound) = match opcode {
+            OpcodeId::I32Shl | OpcodeId::I32Rotl | OpcodeId::I64Shl | OpcodeId::I64Rotl => {
+                let shifted = lhs as u128 * pow;
+                (shifted / modulus, shifted % modulus, modulus)
+            }
+            _ => (lhs as u128 / pow, lhs as u128 % pow, pow),
+        };
+        assign_bytes(region, &self.aux_hi, aux_hi)?;
+        assign_bytes(region, &self.aux_lo, aux_lo)?;
+        assign_bytes(region, &self.aux_lo_rem, aux_lo_bound - aux_lo - 1)?;
+
+        let rot_parts = match opcode {
+            OpcodeId::I32Rotl | OpcodeId::I64Rotl => Some((aux_lo, aux_hi)),
+            OpcodeId::I32Rotr | OpcodeId::I64Rotr => Some((aux_hi, aux_lo * rev_pow)),
+            _ => None,
+        };
+        if let Some((kept, wrapped)) = rot_parts {
+            let res = res.as_u64();
+            for idx in 0..8 {
+                for (cells, value) in [
+                    (&self.rot_kept_limbs, kept as u64),
+                    (&self.rot_wrapped_limbs, wrapped as u64),
+                    (&self.res_limbs, res),
+                ] {
+                    let limb = (value >> (idx * 8)) & 0xff;
+                    cells[idx].assign(region, offset, Value::known(F::from(limb)))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use eth_types::{bytecode, evm_types::OpcodeId, Bytecode};
+
+    use crate::evm_circuit::{step::ExecutionState, test::run_test_with_results};
+
+    #[test]
+    fn test_i32_shift_by_zero() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I32Const[0x12345678]
+            I32Const[0]
+            I32Shl
+            Drop
+            I32Const[0x12345678]
+            I32Const[0]
+            I32ShrU
+            Drop
+            I32Const[0x80000000]
+            I32Const[0]
+            I32ShrS
+            Drop
+            I32Const[0x12345678]
+            I32Const[0]
+            I32Rotl
+            Drop
+            I32Const[0x12345678]
+            I32Const[0]
+            I32Rotr
+            Drop
+        }, vec![0x12345678, 0x12345678, 0x80000000, 0x12345678, 0x12345678]);
+    }
+
+    #[test]
+    fn test_i32_shift_by_width() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I32Const[0x12345678]
+            I32Const[32]
+            I32Shl
+            Drop
+            I32Const[0x12345678]
+            I32Const[33]
+            I32ShrU
+            Drop
+            I32Const[0x12345678]
+            I32Const[36]
+            I32Rotl
+            Drop
+        }, vec![0x12345678, 0x091a2b3c, 0x23456781]);
+    }
+
+    #[test]
+    fn test_i32_shift() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I32Const[0xf0000001]
+            I32Const[4]
+            I32Shl
+            Drop
+            I32Const[0xf0000001]
+            I32Const[4]
+            I32ShrU
+            Drop
+            I32Const[0xf0000001]
+            I32Const[4]
+            I32Rotr
+            Drop
+        }, vec![0x00000010, 0x0f000000, 0x1f000000]);
+    }
+
+    #[test]
+    fn test_i32_shr_s_negative() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I32Const[0x80000000]
+            I32Const[4]
+            I32ShrS
+            Drop
+            I32Const[0xffffffff]
+            I32Const[31]
+            I32ShrS
+            Drop
+            I32Const[0x70000000]
+            I32Const[4]
+            I32ShrS
+            Drop
+        }, vec![0xf8000000, 0xffffffff, 0x07000000]);
+    }
+
+    #[test]
+    fn test_i64_shift() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I64Const[0x0123456789abcdef]
+            I64Const[64]
+            I64Shl
+            Drop
+            I64Const[0x0123456789abcdef]
+            I64Const[8]
+            I64Shl
+            Drop
+            I64Const[0x0123456789abcdef]
+            I64Const[8]
+            I64ShrU
+            Drop
+            I64Const[0x0123456789abcdef]
+            I64Const[8]
+            I64Rotl
+            Drop
+            I64Const[0x0123456789abcdef]
+            I64Const[8]
+            I64Rotr
+            Drop
+        }, vec![
+            0x0123456789abcdef,
+            0x23456789abcdef00,
+            0x000123456789abcd,
+            0x23456789abcdef01,
+            0xef0123456789abcd,
+        ]);
+    }
+
+    #[test]
+    fn test_i64_shift_by_zero() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I64Const[0x8123456789abcdef]
+            I64Const[0]
+            I64Shl
+            Drop
+            I64Const[0x8123456789abcdef]
+            I64Const[64]
+            I64ShrU
+            Drop
+            I64Const[0x8123456789abcdef]
+            I64Const[128]
+            I64ShrS
+            Drop
+        }, vec![0x8123456789abcdef, 0x8123456789abcdef, 0x8123456789abcdef]);
+    }
+
+    #[test]
+    fn test_i64_shr_s_negative() {
+        run_test_with_results(ExecutionState::WASM_SHIFT, bytecode! {
+            I64Const[0x8000000000000000]
+            I64Const[8]
+            I64ShrS
+            Drop
+            I64Const[0x8000000000000000]
+            I64Const[0]
+            I64ShrS
+            Drop
+        }, vec![0xff80000000000000, 0x8000000000000000]);
+    }
+
+    /// rotates `value` (truncated to the width of `rotl`/`rotr`) by each of `amounts` both ways
+    fn rotate_bytecode(
+        value: u64,
+        amounts: &[u64],
+        const_op: OpcodeId,
+        [rotl, rotr]: [OpcodeId; 2],
+    ) -> Bytecode {
+        let mut code = Bytecode::default();
+        for amount in amounts {
+            for op in [rotl, rotr] {
+                code.write_postfix(const_op, value as i128)
+                    .write_postfix(const_op, *amount as i128)
+                    .write_op(op)
+                    .write_op(OpcodeId::Drop);
+            }
+        }
+        code
+    }
+
+    #[test]
+    fn test_i32_rotate_matches_std() {
+        let value = 0x81234567u32;
+        let amounts = [0, 1, 4, 8, 31, 32, 33];
+        let expected = amounts.iter()
+            .flat_map(|n| [value.rotate_left(*n as u32), value.rotate_right(*n as u32)])
+            .map(|v| v as u64)
+            .collect();
+        run_test_with_results(
+            ExecutionState::WASM_SHIFT,
+            rotate_bytecode(value as u64, &amounts, OpcodeId::I32Const, [OpcodeId::I32Rotl, OpcodeId::I32Rotr]),
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_i64_rotate_matches_std() {
+        let value = 0x8123456789abcdefu64;
+        let amounts = [0, 1, 8, 36, 63, 64, 65];
+        let expected = amounts.iter()
+            .flat_map(|n| [value.rotate_left(*n as u32), value.rotate_right(*n as u32)])
+            .collect();
+        run_test_with_results(
+            ExecutionState::WASM_SHIFT,
+            rotate_bytecode(value, &amounts, OpcodeId::I64Const, [OpcodeId::I64Rotl, OpcodeId::I64Rotr]),
+            expected,
+        );
+    }
+}
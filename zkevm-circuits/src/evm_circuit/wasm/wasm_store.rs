@@ -18,42 +18,22 @@ use crate::{
     util::Expr,
 };
 use crate::evm_circuit::util::Cell;
+use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 
 #[derive(Clone, Debug)]
 pub(crate) struct WasmStoreGadget<F> {
     same_context: SameContextGadget<F>,
-
-    opcode_store_offset: Cell<F>,
-
-    store_start_block_index: Cell<F>,
-    store_start_block_inner_offset: Cell<F>,
-    store_start_block_inner_offset_helper: Cell<F>,
-
-    store_end_block_index: Cell<F>,
-    store_end_block_inner_offset: Cell<F>,
-    store_end_block_inner_offset_helper: Cell<F>,
-
-    load_value1: Cell<F>,
-    load_value2: Cell<F>,
-    store_value1: Cell<F>,
-    store_value2: Cell<F>,
-
-    mask_bits: [Cell<F>; 16],
-    offset_modulus: Cell<F>,
-    store_raw_value: Cell<F>,
-    store_base: Cell<F>,
-    store_wrapped_value: Cell<F>,
-
-    vtype: Cell<F>,
-    is_one_byte: Cell<F>,
-    is_two_bytes: Cell<F>,
-    is_four_bytes: Cell<F>,
-    is_eight_bytes: Cell<F>,
-
-    //lookup_offset_len_bits: OffsetLenBitsTableLookupCell,
-    //lookup_pow: PowTableLookupCell,
-
-    address_within_allocated_pages_helper: Cell<F>,
+    address: Cell<F>,
+    mem_offset: Cell<F>,
+    value: Cell<F>,
+    value_truncated: Cell<F>,
+    value_high: Cell<F>,
+    bytes: [Cell<F>; 8],
+    is_store8: Cell<F>,
+    is_store16: Cell<F>,
+    is_store32: Cell<F>,
+    is_store64: Cell<F>,
+    is_64bits: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for WasmStoreGadget<F> {
@@ -61,239 +41,95 @@ impl<F: Field> ExecutionGadget<F> for WasmStoreGadget<F> {
 
     const EXECUTION_STATE: ExecutionState = ExecutionState::WASM_STORE;
 
-    fn configure(cb: &mut ConstrainBuilderCommon<F>) -> Self {
-        let opcode_store_offset = cb.alloc_common_range_value();
-
-        let store_start_block_index = cb.alloc_common_range_value();
-        let store_start_block_inner_offset = cb.alloc_common_range_value();
-        let store_start_block_inner_offset_helper = cb.alloc_common_range_value();
-
-        let store_end_block_index = cb.alloc_common_range_value();
-        let store_end_block_inner_offset = cb.alloc_common_range_value();
-        let store_end_block_inner_offset_helper = cb.alloc_common_range_value();
-
-        let load_value1 = cb.alloc_u64_on_u8();
-        let load_value2 = cb.alloc_u64_on_u8();
-        let store_value1 = cb.alloc_u64_on_u8();
-        let store_value2 = cb.alloc_u64_on_u8();
-        let offset_modulus = cb.alloc_u64();
-        let store_raw_value = cb.alloc_u64();
-        let store_base = cb.alloc_u64();
-
-        let store_wrapped_value = cb.alloc_unlimited_value();
-
-        let mask_bits = [0; 16].map(|_| cb.alloc_bit_value());
-        let is_one_byte = cb.alloc_bit_value();
-        let is_two_bytes = cb.alloc_bit_value();
-        let is_four_bytes = cb.alloc_bit_value();
-        let is_eight_bytes = cb.alloc_bit_value();
-        let vtype = cb.alloc_common_range_value();
+    fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
 
-        let lookup_offset_len_bits = cb.alloc_offset_len_bits_table_lookup();
-        let lookup_pow = cb.alloc_pow_table_lookup();
+        let address = cb.alloc_u64();
+        let mem_offset = cb.alloc_u64();
+        let value = cb.alloc_u64();
+        let value_truncated = cb.alloc_u64();
+        let bytes: [Cell<F>; 8] = cb.query_bytes();
+
+        let is_store8 = cb.alloc_bit_value();
+        let is_store16 = cb.alloc_bit_value();
+        let is_store32 = cb.alloc_bit_value();
+        let is_store64 = cb.alloc_bit_value();
+        let is_64bits = cb.alloc_bit_value();
+
+        for (name, bit) in [
+            ("store: is_store8 is bool", &is_store8),
+            ("store: is_store16 is bool", &is_store16),
+            ("store: is_store32 is bool", &is_store32),
+            ("store: is_store64 is bool", &is_store64),
+            ("store: is_64bits is bool", &is_64bits),
+        ] {
+            cb.require_boolean(name, bit.expr());
+        }
 
-        let current_memory_page_size = cb.allocated_memory_pages_cell();
-        let address_within_allocated_pages_helper = cb.alloc_common_range_value();
+        cb.require_equal(
+            "store: selector",
+            is_store8.expr() + is_store16.expr() + is_store32.expr() + is_store64.expr(),
+            1.expr(),
+        );
+        cb.require_zero("store: i64.store is 64 bits", is_store64.expr() * (1.expr() - is_64bits.expr()));
 
         cb.stack_pop(value.expr());
-        cb.stack_pop(raw_address.expr());
-        cb.stack_pop(pre_block_value.expr());
-        cb.stack_push(update_block_value1.expr());
-
-        cb.require_zeros("op_store: start end offset range", vec![
-            store_start_block_inner_offset.expr()
-                + store_start_block_inner_offset_helper.expr()
-                - 7.expr(),
-            store_end_block_inner_offset.expr()
-                + store_end_block_inner_offset_helper.expr()
-                - 7.expr(),
-        ]);
-
-        cb.require_zeros("op_store: start end equation", {
-            let len = 1.expr()
-                + is_two_bytes.expr() * 1.expr()
-                + is_four_bytes.expr() * 3.expr()
-                + is_eight_bytes.expr() * 7.expr();
-            vec![
-                store_start_block_index.expr() * 8.expr()
-                    + store_start_block_inner_offset.expr()
-                    + len
-                    - 1.expr()
-                    - store_end_block_index.expr() * 8.expr()
-                    - store_end_block_inner_offset.expr(),
-            ]
-        });
-
-        cb.require_zeros("op_store: start store_base", vec![
-            store_base.expr() + opcode_store_offset.expr()
-                - store_start_block_index.expr() * 8.expr()
-                - store_start_block_inner_offset.expr(),
-        ]);
-
-        cb.require_zeros("op_store: length", vec![
-            is_one_byte.expr()
-                + is_two_bytes.expr()
-                + is_four_bytes.expr()
-                + is_eight_bytes.expr()
-                - 1.expr(),
-        ]);
-
-        cb.require_zeros("op_store: mask_bits offset len", {
-            let len = 1.expr()
-                + is_two_bytes.expr() * 1.expr()
-                + is_four_bytes.expr() * 3.expr()
-                + is_eight_bytes.expr() * 7.expr();
-            let (_, bits_encode) = mask_bits
-                .map(|c| c.expr())
-                .into_iter()
-                .enumerate()
-                .reduce(|(_, acc), (i, e)| (i, acc + e * (1u64 << i).expr()))
-                .unwrap();
-            vec![
-                lookup_offset_len_bits.expr()
-                    - offset_len_bits_encode_expr(
-                        store_start_block_inner_offset.expr(),
-                        len,
-                        bits_encode,
-                    ),
-            ]
-        });
-
-        cb.require_zeros("op_store: pow table lookup", vec![
-            lookup_pow.expr()
-                - pow_table_encode(
-                    offset_modulus.expr(),
-                    store_start_block_inner_offset.expr() * 8.expr(),
-                ),
-        ]);
-
-        /*constraint_builder.push(
-            "op_store wrap value",
-            Box::new(move |meta| {
-                let has_two_bytes =
-                    is_two_bytes.expr(meta) + is_four_bytes.expr(meta) + is_eight_bytes.expr(meta);
-                let has_four_bytes = is_four_bytes.expr(meta) + is_eight_bytes.expr(meta);
-                let has_eight_bytes = is_eight_bytes.expr(meta);
-                let byte_value = (0..8)
-                    .map(|i| {
-                        store_raw_value.u4_expr(meta, i * 2) * constant_from!(1u64 << (8 * i))
-                            + store_raw_value.u4_expr(meta, i * 2 + 1)
-                                * constant_from!(1u64 << (8 * i + 4))
-                    })
-                    .collect::<Vec<_>>();
-                vec![
-                    byte_value[0].clone()
-                        + byte_value[1].clone() * has_two_bytes
-                        + (byte_value[2].clone() + byte_value[3].clone()) * has_four_bytes
-                        + (byte_value[4].clone()
-                            + byte_value[5].clone()
-                            + byte_value[6].clone()
-                            + byte_value[7].clone())
-                            * has_eight_bytes
-                        - store_wrapped_value.expr(meta),
-                ]
-            }),
-        );*/
-
-        /*constraint_builder.push(
-            "op_store write value",
-            Box::new(move |meta| {
-                let mut acc = store_wrapped_value.expr(meta) * offset_modulus.expr(meta);
-
-                for i in 0..8 {
-                    acc = acc
-                        - store_value1.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8))))
-                            * mask_bits[i as usize].expr(meta);
-
-                    acc = acc
-                        - store_value2.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8 + 64))))
-                            * mask_bits[i as usize + 8].expr(meta);
-                }
-
-                vec![acc]
-            }),
-        );*/
-
-        /*constraint_builder.push(
-            "op_store unchanged value",
-            Box::new(move |meta| {
-                let mut acc = constant_from!(0);
-
-                for i in 0..8 {
-                    acc = acc
-                        + load_value1.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8))))
-                            * (constant_from!(1) - mask_bits[i as usize].expr(meta))
-                        - store_value1.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8))))
-                            * (constant_from!(1) - mask_bits[i as usize].expr(meta));
-
-                    acc = acc
-                        + load_value2.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8 + 64))))
-                            * (constant_from!(1) - mask_bits[i as usize + 8].expr(meta))
-                        - store_value2.u8_expr(meta, i)
-                            * constant!(bn_to_field(&(BigUint::from(1u64) << (i * 8 + 64))))
-                            * (constant_from!(1) - mask_bits[i as usize + 8].expr(meta));
-                }
-
-                vec![acc]
-            }),
-        );*/
+        cb.stack_pop(address.expr());
+
+        // Amount of bytes written is 1, 2, 4 or 8, so the byte `i` is written if width is greater than `i`.
+        let is_byte_written = |i: usize| -> Expression<F> {
+            match i {
+                0 => 1.expr(),
+                1 => 1.expr() - is_store8.expr(),
+                2 | 3 => is_store32.expr() + is_store64.expr(),
+                _ => is_store64.expr(),
+            }
+        };
+        let effective_address = address.expr() + mem_offset.expr();
+        for (i, byte) in bytes.iter().enumerate() {
+            cb.condition(is_byte_written(i), |cb| {
+                cb.memory_lookup(1.expr(), effective_address.clone() + i.expr(), byte.expr(), None);
+            });
+            cb.require_zero("store: unwritten byte is zero", byte.expr() * (1.expr() - is_byte_written(i)));
+        }
 
-        cb.require_zeros("op_store: allocated address", {
-            let len = 1.expr()
-                + is_two_bytes.expr() * 1.expr()
-                + is_four_bytes.expr() * 3.expr()
-                + is_eight_bytes.expr() * 7.expr();
-            vec![
-                (store_base.expr()
-                    + opcode_store_offset.expr()
-                    + len
-                    + address_within_allocated_pages_helper.expr()
-                    - current_memory_page_size.expr() * WASM_PAGE_SIZE.expr()),
-            ]
+        // The stored bytes are the lowest bytes of the value, the rest of the value is dropped.
+        let truncated = bytes.iter().enumerate().fold(0.expr(), |acc, (i, byte)| {
+            acc + byte.expr() * Expression::Constant(F::from_u128(1u128 << (8 * i)))
         });
+        cb.require_equal("store: truncated value", value_truncated.expr(), truncated);
+        let width_modulus = is_store8.expr() * Expression::Constant(F::from(1u64 << 8usize)) +
+            is_store16.expr() * Expression::Constant(F::from(1u64 << 16usize)) +
+            is_store32.expr() * Expression::Constant(F::from(1u64 << 32usize)) +
+            is_store64.expr() * Expression::Constant(F::from_u128(1u128 << 64usize));
+        let value_high = cb.alloc_u64();
+        cb.require_equal(
+            "store: value decomposition",
+            value.expr(),
+            value_high.expr() * width_modulus + value_truncated.expr(),
+        );
 
-        let opcode = cb.query_cell();
-
-        // State transition
         let step_state_transition = StepStateTransition {
-            rw_counter: Delta(4.expr()),
+            rw_counter: Delta(cb.rw_counter_offset()),
             program_counter: Delta(1.expr()),
-            stack_pointer: Delta(0.expr()),
-            // TODO: change op.
-            gas_left: Delta(-OpcodeId::I32Eqz.constant_gas_cost().expr()),
+            stack_pointer: Delta(2.expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
-            opcode_store_offset,
-            store_start_block_index,
-            store_start_block_inner_offset,
-            store_start_block_inner_offset_helper,
-            store_end_block_index,
-            store_end_block_inner_offset,
-            store_end_block_inner_offset_helper,
-            store_value1,
-            store_value2,
-            mask_bits,
-            offset_modulus,
-            store_base,
-            store_raw_value,
-            store_wrapped_value,
-            is_one_byte,
-            is_two_bytes,
-            is_four_bytes,
-            is_eight_bytes,
-            vtype,
-            load_value1,
-            load_value2,
-            address_within_allocated_pages_helper,
+            address,
+            mem_offset,
+            value,
+            value_truncated,
+            value_high,
+            bytes,
+            is_store8,
+            is_store16,
+            is_store32,
+            is_store64,
+            is_64bits,
         }
     }
 
@@ -303,44 +139,47 @@ impl<F: Field> ExecutionGadget<F> for WasmStoreGadget<F> {
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
-        _call: &Call,
+        _: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         self.same_context.assign_exec_step(region, offset, step)?;
 
         let opcode = step.opcode.unwrap();
 
-        cb.stack_pop(value.expr());
-        cb.stack_pop(raw_address.expr());
-        cb.stack_pop(pre_block_value.expr());
-        cb.stack_push(update_block_value1.expr());
+        let (selector, width, is_64bits) = match opcode {
+            OpcodeId::I32Store8 => (&self.is_store8, 1, false),
+            OpcodeId::I32Store16 => (&self.is_store16, 2, false),
+            OpcodeId::I32Store => (&self.is_store32, 4, false),
+            OpcodeId::I64Store8 => (&self.is_store8, 1, true),
+            OpcodeId::I64Store16 => (&self.is_store16, 2, true),
+            OpcodeId::I64Store32 => (&self.is_store32, 4, true),
+            OpcodeId::I64Store => (&self.is_store64, 8, true),
+            _ => unreachable!("not supported opcode: {:?}", opcode),
+        };
+        selector.assign(region, offset, Value::known(F::one()))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
 
-        let [value, raw_address, pre_block_value, update_block_value1] =
-            [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2], step.rw_indices[3]]
+        let [value, address] = [step.rw_indices[0], step.rw_indices[1]]
             .map(|idx| block.rws[idx].stack_value());
-
-/*
         self.value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
-        self.value_inv.assign(region, offset, Value::known(F::from(value.as_u64()).invert().unwrap_or(F::zero())))?;
-        self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
-
-        match opcode {
-            OpcodeId::I64Eqz => {
-                let zero_or_one = (value.as_u64() == 0) as u64;
-                self.res.assign(region, offset, Value::known(F::from(zero_or_one)))?;
-            }
-            OpcodeId::I32Eqz => {
-                let zero_or_one = (value.as_u32() == 0) as u64;
-                self.res.assign(region, offset, Value::known(F::from(zero_or_one)))?;
-            }
-            _ => unreachable!("not supported opcode: {:?}", opcode),
-        };
- 
-        let is_i64 = matches!(opcode,
-            OpcodeId::I64Eqz
-        );
-        self.is_i64.assign(region, offset, Value::known(F::from(is_i64 as u64)))?;
-*/
+        self.address.assign(region, offset, Value::known(address.to_scalar().unwrap()))?;
+
+        let effective_address = block.rws[step.rw_indices[2]].memory_address();
+        self.mem_offset.assign(region, offset, Value::known(F::from(effective_address - address.as_u64())))?;
+
+        let mut value_truncated = 0u64;
+        for (i, cell) in self.bytes.iter().enumerate() {
+            let byte = if i < width {
+                block.rws[step.rw_indices[2 + i]].memory_value()
+            } else {
+                0
+            };
+            value_truncated |= (byte as u64) << (8 * i);
+            cell.assign(region, offset, Value::known(F::from(byte as u64)))?;
+        }
+        self.value_truncated.assign(region, offset, Value::known(F::from(value_truncated)))?;
+        let value_high = if width < 8 { value.as_u64() >> (8 * width) } else { 0 };
+        self.value_high.assign(region, offset, Value::known(F::from(value_high)))?;
 
         Ok(())
     }
@@ -348,7 +187,8 @@ impl<F: Field> ExecutionGadget<F> for WasmStoreGadget<F> {
 
 #[cfg(test)]
 mod test {
-    use eth_types::{bytecode, Bytecode};
+    use eth_types::{bytecode_internal, Bytecode};
+    use eth_types::evm_types::OpcodeId;
     use mock::TestContext;
 
     use crate::test_util::CircuitTestBuilder;
@@ -359,29 +199,49 @@ mod test {
         ).run()
     }
 
-/*
-    #[test]
-    fn test_i32_eqz() {
-        run_test(bytecode! {
-            I32Const[0]
-            I32Eqz
-            Drop
-            I32Const[1]
-            I32Eqz
+    fn run_store_load_test(value: i128, store_op: OpcodeId, load_op: OpcodeId) {
+        let mut code = Bytecode::default();
+        let data_offset = code.alloc_default_global_data(8);
+        let value_op = match store_op {
+            OpcodeId::I32Store | OpcodeId::I32Store8 | OpcodeId::I32Store16 => OpcodeId::I32Const,
+            _ => OpcodeId::I64Const,
+        };
+        code.write_postfix(OpcodeId::I32Const, data_offset as i128);
+        code.write_postfix(value_op, value);
+        bytecode_internal! {code,
+            .write_memarg(store_op, 0, 0, 0)
+            I32Const[data_offset]
+            .write_memarg(load_op, 0, 0, 0)
             Drop
-        });
+        }
+        run_test(code);
     }
 
     #[test]
-    fn test_i64_eqz() {
-        run_test(bytecode! {
-            I64Const[0]
-            I64Eqz
-            Drop
-            I64Const[1]
-            I64Eqz
-            Drop
-        });
+    fn test_i32_store() {
+        run_store_load_test(0x01020304, OpcodeId::I32Store, OpcodeId::I32Load);
+    }
+
+    #[test]
+    fn test_i32_store8_truncates() {
+        run_store_load_test(0x1ff, OpcodeId::I32Store8, OpcodeId::I32Load8U);
+        run_store_load_test(0x1ff, OpcodeId::I32Store8, OpcodeId::I32Load);
+    }
+
+    #[test]
+    fn test_i32_store16_truncates() {
+        run_store_load_test(0x18001, OpcodeId::I32Store16, OpcodeId::I32Load16S);
+    }
+
+    #[test]
+    fn test_i64_store() {
+        run_store_load_test(0x0102030405060708, OpcodeId::I64Store, OpcodeId::I64Load);
+    }
+
+    #[test]
+    fn test_i64_store_truncates() {
+        run_store_load_test(0x0102030405060708, OpcodeId::I64Store8, OpcodeId::I64Load8U);
+        run_store_load_test(0x0102030405060708, OpcodeId::I64Store16, OpcodeId::I64Load16U);
+        run_store_load_test(0x0102030485060708, OpcodeId::I64Store32, OpcodeId::I64Load32S);
     }
-*/
 }
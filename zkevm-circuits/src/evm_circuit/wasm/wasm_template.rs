@@ -2,7 +2,6 @@ use std::convert::TryInto;
 
 use halo2_proofs::plonk::Error;
 
-use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
 
 use crate::{
@@ -37,12 +36,11 @@ impl<F: Field> ExecutionGadget<F> for WasmUnaryGadget<F> {
             rw_counter: Delta(22.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta((-1).expr()),
-            gas_left: Delta(-OpcodeId::ADDRESS.constant_gas_cost().expr()),
             ..Default::default()
         };
 
         let opcode = cb.query_cell();
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
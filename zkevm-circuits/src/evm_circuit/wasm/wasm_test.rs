@@ -11,7 +11,8 @@ use crate::{
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta},
+            math_gadget::IsZeroGadget,
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -23,8 +24,8 @@ use crate::evm_circuit::util::constraint_builder::EVMConstraintBuilder;
 #[derive(Clone, Debug)]
 pub(crate) struct WasmTestGadget<F> {
     same_context: SameContextGadget<F>,
-    is_i64: Cell<F>,
-    value_inv: Cell<F>,
+    is_64bits: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
     value: Cell<F>,
     res: Cell<F>,
 }
@@ -37,33 +38,37 @@ impl<F: Field> ExecutionGadget<F> for WasmTestGadget<F> {
     fn configure(cb: &mut EVMConstraintBuilder<F>) -> Self {
         let value = cb.alloc_u64();
         let res = cb.alloc_bit_value();
-        let is_i64 = cb.alloc_bit_value();
-        let value_inv = cb.alloc_unlimited_value();
+        let is_64bits = cb.alloc_bit_value();
 
         cb.stack_pop(value.expr());
         cb.stack_push(res.expr());
 
-        cb.require_zeros("op_test: constraints", vec![
-            res.expr() * value.expr(),
-            value.expr() * value_inv.expr() - 1.expr() + res.expr(),
-        ]);
+        // The whole operand is tested, so i64 values with zero low word are not zero.
+        let value_is_zero = IsZeroGadget::construct(cb, value.expr());
+        cb.require_equal("op_test: result", res.expr(), value_is_zero.expr());
 
         let opcode = cb.query_cell();
 
+        cb.require_equal(
+            "op_test: is_64bits is constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::I32Eqz.expr()
+                + (OpcodeId::I64Eqz.as_u64() - OpcodeId::I32Eqz.as_u64()).expr() * is_64bits.expr(),
+        );
+
         // State transition
         let step_state_transition = StepStateTransition {
             rw_counter: Delta(2.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::I32Eqz.constant_gas_cost().expr()),
             ..StepStateTransition::default()
         };
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
-            is_i64,
-            value_inv,
+            is_64bits,
+            value_is_zero,
             value,
             res,
         }
@@ -86,7 +91,7 @@ impl<F: Field> ExecutionGadget<F> for WasmTestGadget<F> {
             .map(|idx| block.rws[idx].stack_value());
 
         self.value.assign(region, offset, Value::known(value.to_scalar().unwrap()))?;
-        self.value_inv.assign(region, offset, Value::known(F::from(value.as_u64()).invert().unwrap_or(F::zero())))?;
+        self.value_is_zero.assign(region, offset, F::from(value.as_u64()))?;
         self.res.assign(region, offset, Value::known(res.to_scalar().unwrap()))?;
 
         match opcode {
@@ -101,10 +106,10 @@ impl<F: Field> ExecutionGadget<F> for WasmTestGadget<F> {
             _ => unreachable!("not supported opcode: {:?}", opcode),
         };
  
-        let is_i64 = matches!(opcode,
+        let is_64bits = matches!(opcode,
             OpcodeId::I64Eqz
         );
-        self.is_i64.assign(region, offset, Value::known(F::from(is_i64 as u64)))?;
+        self.is_64bits.assign(region, offset, Value::known(F::from(is_64bits as u64)))?;
 
         Ok(())
     }
@@ -115,7 +120,10 @@ mod test {
     use eth_types::{bytecode, Bytecode};
     use mock::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{
+        evm_circuit::{step::ExecutionState, test::run_test_with_results},
+        test_util::CircuitTestBuilder,
+    };
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -146,4 +154,16 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn test_i64_eqz_tests_high_word() {
+        run_test_with_results(ExecutionState::WASM_TEST, bytecode! {
+            I64Const[0x1_0000_0000]
+            I64Eqz
+            Drop
+            I64Const[0]
+            I64Eqz
+            Drop
+        }, vec![0, 1]);
+    }
 }
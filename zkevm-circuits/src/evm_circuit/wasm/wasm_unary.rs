@@ -12,7 +12,7 @@ use crate::{
         util::{
             CachedRegion,
             common_gadget::SameContextGadget,
-            constraint_builder::{StepStateTransition, Transition::Delta},
+            constraint_builder::{ConstrainBuilderCommon, StepStateTransition, Transition::Delta},
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -98,6 +98,23 @@ impl<F: Field> ExecutionGadget<F> for WasmUnaryGadget<F> {
             is_ctz.expr() + is_clz.expr() + is_popcnt.expr() - 1.expr(),
         );
 
+        let opcode = cb.query_cell();
+
+        // Opcodes are laid out as clz, ctz, popcnt for both i32 and i64.
+        cb.require_equal(
+            "op_unary: selectors are constrained from the opcode",
+            opcode.expr(),
+            OpcodeId::I32Clz.expr()
+                + is_ctz.expr()
+                + 2.expr() * is_popcnt.expr()
+                + (OpcodeId::I64Clz.as_u64() - OpcodeId::I32Clz.as_u64()).expr() * is_64bits.expr(),
+        );
+
+        cb.require_zeros(
+            "op_unary: i32 argument has no high limbs",
+            (4..8).map(|i| arg_limbs[i].expr() * is_32bits()).collect(),
+        );
+
         cb.require_zeros(
             "op_unary: argument from limbs",
             vec![{
@@ -121,12 +138,10 @@ impl<F: Field> ExecutionGadget<F> for WasmUnaryGadget<F> {
             rw_counter: Delta(2.expr()),
             program_counter: Delta(1.expr()),
             stack_pointer: Delta(0.expr()),
-            gas_left: Delta(-OpcodeId::I32Ctz.constant_gas_cost().expr()),
             ..Default::default()
         };
 
-        let opcode = cb.query_cell();
-        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+        let same_context = SameContextGadget::construct_wasm(cb, opcode, step_state_transition);
 
         Self {
             same_context,
@@ -201,7 +216,10 @@ mod test {
     use eth_types::{bytecode, Bytecode};
     use mock::test_ctx::TestContext;
 
-    use crate::test_util::CircuitTestBuilder;
+    use crate::{
+        evm_circuit::{step::ExecutionState, test::run_test_with_results},
+        test_util::CircuitTestBuilder,
+    };
 
     fn run_test(bytecode: Bytecode) {
         CircuitTestBuilder::new_from_test_ctx(
@@ -292,4 +310,34 @@ mod test {
             Drop
         });
     }
+
+    #[test]
+    fn test_zero_input_is_full_width() {
+        run_test_with_results(ExecutionState::WASM_UNARY, bytecode! {
+            I32Const[0x00000000]
+            I32Clz
+            Drop
+            I32Const[0x00000000]
+            I32Ctz
+            Drop
+            I64Const[0x0000000000000000]
+            I64Clz
+            Drop
+            I64Const[0x0000000000000000]
+            I64Ctz
+            Drop
+        }, vec![32, 32, 64, 64]);
+    }
+
+    #[test]
+    fn test_all_ones_popcnt_is_full_width() {
+        run_test_with_results(ExecutionState::WASM_UNARY, bytecode! {
+            I32Const[0xffffffff]
+            I32Popcnt
+            Drop
+            I64Const[0xffffffffffffffff]
+            I64Popcnt
+            Drop
+        }, vec![32, 64]);
+    }
 }
@@ -0,0 +1,1450 @@
+//! Floating-point verification circuit.
+//!
+//! Every row of the FloatTable is checked to hold the IEEE-754 (round to nearest, ties to even)
+//! result of the operation applied to its operands. The operands and the result are decomposed
+//! into sign, exponent and fraction. NaN, infinity and zero operands are handled by the special
+//! cases of each operation, which fix the class (and the sign) of the result. Otherwise the exact
+//! value is rebuilt as an integer `a * 2^shift / divisor` (scaled by the exponent of the claimed
+//! result) and the significand of the result is proven to be its correctly rounded quotient,
+//! with the remainders range checked in limbs.
+
+#[cfg(any(feature = "test", test, feature = "test-circuits"))]
+mod dev;
+pub(crate) mod param;
+#[cfg(any(feature = "test", test))]
+mod test;
+#[cfg(any(feature = "test", test, feature = "test-circuits"))]
+pub use dev::FloatCircuit as TestFloatCircuit;
+
+use crate::{
+    evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::{FloatOpTag, FloatTable},
+    util::{Challenges, SubCircuit, SubCircuitConfig},
+    witness,
+};
+use eth_types::{Field, ToScalar, U256};
+use gadgets::{
+    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    util::{not, or, pow_of_two, select, xor, Expr},
+};
+use halo2_proofs::{
+    circuit::{Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use param::*;
+use std::marker::PhantomData;
+
+/// Parameters of an IEEE-754 binary format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FloatFormat {
+    /// Number of bits of the fraction (trailing significand)
+    pub(crate) frac_bits: u64,
+    /// Number of bits of the biased exponent
+    pub(crate) exp_bits: u64,
+}
+
+impl FloatFormat {
+    pub(crate) const F32: Self = Self {
+        frac_bits: 23,
+        exp_bits: 8,
+    };
+    pub(crate) const F64: Self = Self {
+        frac_bits: 52,
+        exp_bits: 11,
+    };
+
+    fn bias(&self) -> u64 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    fn exp_max(&self) -> u64 {
+        (1 << self.exp_bits) - 1
+    }
+
+    fn sign_shift(&self) -> u64 {
+        1 << (self.frac_bits + self.exp_bits)
+    }
+
+    /// Returns the constant of the format selected by `is_f64`.
+    fn expr<F: Field>(is_f64: &Expression<F>, constant: impl Fn(Self) -> u64) -> Expression<F> {
+        let (f32, f64) = (F::from(constant(Self::F32)), F::from(constant(Self::F64)));
+        Expression::Constant(f32) + is_f64.clone() * Expression::Constant(f64 - f32)
+    }
+}
+
+/// The arithmetic of a floating-point operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FloatOpKind {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl FloatOpKind {
+    fn from_tag(tag: FloatOpTag) -> (Self, FloatFormat) {
+        match tag {
+            FloatOpTag::F32Add => (Self::Add, FloatFormat::F32),
+            FloatOpTag::F32Sub => (Self::Sub, FloatFormat::F32),
+            FloatOpTag::F32Mul => (Self::Mul, FloatFormat::F32),
+            FloatOpTag::F32Div => (Self::Div, FloatFormat::F32),
+            FloatOpTag::F64Add => (Self::Add, FloatFormat::F64),
+            FloatOpTag::F64Sub => (Self::Sub, FloatFormat::F64),
+            FloatOpTag::F64Mul => (Self::Mul, FloatFormat::F64),
+            FloatOpTag::F64Div => (Self::Div, FloatFormat::F64),
+        }
+    }
+
+    /// Returns the shift applied to the exact value and the largest shift of the rounding step.
+    /// Above the largest shift the exact value is below half of the smallest subnormal, so it
+    /// always rounds to zero.
+    fn rounding_shifts(&self, format: FloatFormat) -> (u64, u64) {
+        let p = format.frac_bits;
+        match self {
+            Self::Add | Self::Sub => (p + 1, 3 * p + 7),
+            Self::Mul => (0, 2 * p + 3),
+            Self::Div => (p + 2, p + 4),
+        }
+    }
+}
+
+/// The fields of the bits of a floating-point value.
+#[derive(Clone, Copy, Debug)]
+struct FloatBits {
+    sign: bool,
+    exp: u64,
+    frac: u64,
+    format: FloatFormat,
+}
+
+impl FloatBits {
+    fn decode(bits: u64, format: FloatFormat) -> Self {
+        Self {
+            sign: (bits >> (format.frac_bits + format.exp_bits)) & 1 == 1,
+            exp: (bits >> format.frac_bits) & format.exp_max(),
+            frac: bits & ((1 << format.frac_bits) - 1),
+            format,
+        }
+    }
+
+    fn significand(&self) -> u64 {
+        self.frac + ((self.exp != 0) as u64) * (1 << self.format.frac_bits)
+    }
+
+    /// Returns the biased exponent of the significand, the subnormals share the one of the
+    /// smallest normals.
+    fn exponent(&self) -> u64 {
+        self.exp.max(1)
+    }
+
+    fn is_nan(&self) -> bool {
+        self.exp == self.format.exp_max() && self.frac != 0
+    }
+
+    fn is_inf(&self) -> bool {
+        self.exp == self.format.exp_max() && self.frac == 0
+    }
+
+    fn is_zero(&self) -> bool {
+        self.exp == 0 && self.frac == 0
+    }
+}
+
+fn signed<F: Field>(value: i64) -> F {
+    if value < 0 {
+        -F::from(value.unsigned_abs())
+    } else {
+        F::from(value as u64)
+    }
+}
+
+fn word<F: Field>(value: U256) -> F {
+    value.to_scalar().unwrap()
+}
+
+/// Little-endian limbs of `LIMB_BITS` bits, each limb is looked up in the range table.
+#[derive(Clone, Debug)]
+pub struct FloatLimbsConfig {
+    limbs: Vec<Column<Advice>>,
+}
+
+impl FloatLimbsConfig {
+    fn configure<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        num_limbs: usize,
+        range_table: Column<Fixed>,
+    ) -> Self {
+        let limbs = (0..num_limbs)
+            .map(|_| meta.advice_column())
+            .collect::<Vec<_>>();
+        for &limb in limbs.iter() {
+            meta.lookup_any("float limb in range", |meta| {
+                vec![(
+                    meta.query_advice(limb, Rotation::cur()),
+                    meta.query_fixed(range_table, Rotation::cur()),
+                )]
+            });
+        }
+        Self { limbs }
+    }
+
+    fn limb<F: Field>(&self, meta: &mut VirtualCells<'_, F>, idx: usize) -> Expression<F> {
+        meta.query_advice(self.limbs[idx], Rotation::cur())
+    }
+
+    fn expr<F: Field>(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        (0..self.limbs.len()).rev().fold(0.expr(), |acc, idx| {
+            acc * Expression::Constant(pow_of_two(LIMB_BITS)) + self.limb(meta, idx)
+        })
+    }
+
+    /// Assigns the lowest limbs of the value, a value out of their range can't be rebuilt from
+    /// them.
+    fn assign<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: F,
+    ) -> Result<(), Error> {
+        let bytes = value.to_repr();
+        for (idx, &limb) in self.limbs.iter().enumerate() {
+            let limb_value = bytes[2 * idx] as u64 + ((bytes[2 * idx + 1] as u64) << 8);
+            region.assign_advice(
+                || format!("float limb {}", idx),
+                limb,
+                offset,
+                || Value::known(F::from(limb_value)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Expressions of a decomposed floating-point value.
+struct FloatOperandExprs<F> {
+    sign: Expression<F>,
+    exp_zero: Expression<F>,
+    frac_zero: Expression<F>,
+    significand: Expression<F>,
+    /// Biased exponent of the significand
+    exponent: Expression<F>,
+    is_nan: Expression<F>,
+    is_inf: Expression<F>,
+    is_zero: Expression<F>,
+}
+
+/// Decomposition of the bits of an operand (or of the result) of a FloatTable row.
+#[derive(Clone, Debug)]
+pub struct FloatOperandConfig<F> {
+    sign: Column<Advice>,
+    exp: Column<Advice>,
+    frac_limbs: FloatLimbsConfig,
+    significand: Column<Advice>,
+    is_nan: Column<Advice>,
+    is_inf: Column<Advice>,
+    is_zero: Column<Advice>,
+    exp_zero: IsZeroConfig<F>,
+    exp_max: IsZeroConfig<F>,
+    frac_zero: IsZeroConfig<F>,
+}
+
+impl<F: Field> FloatOperandConfig<F> {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: Column<Fixed>,
+        is_f64: Column<Advice>,
+        bits: Column<Advice>,
+        range_table: Column<Fixed>,
+    ) -> Self {
+        let sign = meta.advice_column();
+        let exp = meta.advice_column();
+        let frac_limbs = FloatLimbsConfig::configure(meta, SIGNIFICAND_LIMBS, range_table);
+        let significand = meta.advice_column();
+        let is_nan = meta.advice_column();
+        let is_inf = meta.advice_column();
+        let is_zero = meta.advice_column();
+
+        let exp_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(exp, Rotation::cur()),
+            meta.advice_column(),
+        );
+        let exp_max = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| {
+                let is_f64 = meta.query_advice(is_f64, Rotation::cur());
+                meta.query_advice(exp, Rotation::cur())
+                    - FloatFormat::expr(&is_f64, |format| format.exp_max())
+            },
+            meta.advice_column(),
+        );
+        let frac_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| frac_limbs.expr(meta),
+            meta.advice_column(),
+        );
+
+        // The exponent fits the exponent bits of the format: exp < 2^16 and
+        // exp * 2^(16 - exp_bits) < 2^16.
+        meta.lookup_any("float exp in range", |meta| {
+            vec![(
+                meta.query_advice(exp, Rotation::cur()),
+                meta.query_fixed(range_table, Rotation::cur()),
+            )]
+        });
+        meta.lookup_any("float exp fits the format", |meta| {
+            let is_f64 = meta.query_advice(is_f64, Rotation::cur());
+            vec![(
+                meta.query_advice(exp, Rotation::cur())
+                    * FloatFormat::expr(&is_f64, |format| {
+                        1 << (LIMB_BITS as u64 - format.exp_bits)
+                    }),
+                meta.query_fixed(range_table, Rotation::cur()),
+            )]
+        });
+        // Same for the top limb of the fraction, the limbs above it are zero.
+        meta.lookup_any("float frac fits the format", |meta| {
+            let is_f64 = meta.query_advice(is_f64, Rotation::cur());
+            let [top_f32, top_f64] = [FloatFormat::F32, FloatFormat::F64].map(|format| {
+                let idx = format.frac_bits as usize / LIMB_BITS;
+                frac_limbs.limb(meta, idx)
+                    * Expression::Constant(pow_of_two(
+                        LIMB_BITS - format.frac_bits as usize % LIMB_BITS,
+                    ))
+            });
+            vec![(
+                select::expr(is_f64, top_f64, top_f32),
+                meta.query_fixed(range_table, Rotation::cur()),
+            )]
+        });
+
+        let config = Self {
+            sign,
+            exp,
+            frac_limbs,
+            significand,
+            is_nan,
+            is_inf,
+            is_zero,
+            exp_zero,
+            exp_max,
+            frac_zero,
+        };
+
+        meta.create_gate("float operand decomposition", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_f64 = meta.query_advice(is_f64, Rotation::cur());
+            let bits = meta.query_advice(bits, Rotation::cur());
+            let exp = meta.query_advice(exp, Rotation::cur());
+            let exp_max = config.exp_max.expr();
+            let operand = config.exprs(meta);
+
+            cb.require_boolean("sign is bool", operand.sign.clone());
+            cb.require_equal(
+                "bits == sign * 2^(bits - 1) + exp * 2^frac_bits + frac",
+                bits,
+                operand.sign.clone() * FloatFormat::expr(&is_f64, |format| format.sign_shift())
+                    + exp * FloatFormat::expr(&is_f64, |format| 1 << format.frac_bits)
+                    + config.frac_limbs.expr(meta),
+            );
+            let top_f32 = FloatFormat::F32.frac_bits as usize / LIMB_BITS;
+            cb.condition(not::expr(is_f64.clone()), |cb| {
+                for idx in top_f32 + 1..SIGNIFICAND_LIMBS {
+                    cb.require_zero(
+                        "f32 frac limbs above the top one are zero",
+                        config.frac_limbs.limb(meta, idx),
+                    );
+                }
+            });
+            cb.require_equal(
+                "significand == frac + 2^frac_bits for normals",
+                meta.query_advice(config.significand, Rotation::cur()),
+                config.frac_limbs.expr(meta)
+                    + not::expr(operand.exp_zero.clone())
+                        * FloatFormat::expr(&is_f64, |format| 1 << format.frac_bits),
+            );
+            cb.require_equal(
+                "is_nan == exp is max and frac != 0",
+                operand.is_nan,
+                exp_max.clone() * not::expr(operand.frac_zero.clone()),
+            );
+            cb.require_equal(
+                "is_inf == exp is max and frac == 0",
+                operand.is_inf,
+                exp_max * operand.frac_zero.clone(),
+            );
+            cb.require_equal(
+                "is_zero == exp == 0 and frac == 0",
+                operand.is_zero,
+                operand.exp_zero * operand.frac_zero,
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        config
+    }
+
+    fn exprs(&self, meta: &mut VirtualCells<'_, F>) -> FloatOperandExprs<F> {
+        let exp_zero = self.exp_zero.expr();
+        FloatOperandExprs {
+            sign: meta.query_advice(self.sign, Rotation::cur()),
+            exp_zero: exp_zero.clone(),
+            frac_zero: self.frac_zero.expr(),
+            significand: meta.query_advice(self.significand, Rotation::cur()),
+            exponent: meta.query_advice(self.exp, Rotation::cur()) + exp_zero,
+            is_nan: meta.query_advice(self.is_nan, Rotation::cur()),
+            is_inf: meta.query_advice(self.is_inf, Rotation::cur()),
+            is_zero: meta.query_advice(self.is_zero, Rotation::cur()),
+        }
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: &FloatBits,
+    ) -> Result<(), Error> {
+        for (name, column, cell_value) in [
+            ("sign", self.sign, value.sign as u64),
+            ("exp", self.exp, value.exp),
+            ("significand", self.significand, value.significand()),
+            ("is_nan", self.is_nan, value.is_nan() as u64),
+            ("is_inf", self.is_inf, value.is_inf() as u64),
+            ("is_zero", self.is_zero, value.is_zero() as u64),
+        ] {
+            region.assign_advice(
+                || format!("float operand {}", name),
+                column,
+                offset,
+                || Value::known(F::from(cell_value)),
+            )?;
+        }
+        self.frac_limbs
+            .assign(region, offset, F::from(value.frac))?;
+        IsZeroChip::construct(self.exp_zero.clone()).assign(
+            region,
+            offset,
+            Value::known(F::from(value.exp)),
+        )?;
+        IsZeroChip::construct(self.exp_max.clone()).assign(
+            region,
+            offset,
+            Value::known(F::from(value.exp) - F::from(value.format.exp_max())),
+        )?;
+        IsZeroChip::construct(self.frac_zero.clone()).assign(
+            region,
+            offset,
+            Value::known(F::from(value.frac)),
+        )?;
+        Ok(())
+    }
+}
+
+/// Normalization of the significand of a multiplication or division operand, shifted into
+/// `[2^frac_bits, 2^(frac_bits + 1))`.
+#[derive(Clone, Debug)]
+pub struct FloatNormalizeConfig {
+    shift: Column<Advice>,
+    pow2_shift: Column<Advice>,
+    significand: Column<Advice>,
+    lower_limbs: FloatLimbsConfig,
+    upper_limbs: FloatLimbsConfig,
+}
+
+impl FloatNormalizeConfig {
+    fn configure<F: Field>(meta: &mut ConstraintSystem<F>, range_table: Column<Fixed>) -> Self {
+        Self {
+            shift: meta.advice_column(),
+            pow2_shift: meta.advice_column(),
+            significand: meta.advice_column(),
+            lower_limbs: FloatLimbsConfig::configure(meta, SIGNIFICAND_LIMBS, range_table),
+            upper_limbs: FloatLimbsConfig::configure(meta, SIGNIFICAND_LIMBS, range_table),
+        }
+    }
+
+    fn assign<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Option<(&FloatBits, u64)>,
+    ) -> Result<(), Error> {
+        let (shift, significand, lower, upper) = match value {
+            Some((value, shift)) => {
+                let one = 1u64 << value.format.frac_bits;
+                let significand = value.significand() << shift;
+                (
+                    shift,
+                    significand,
+                    F::from(significand) - F::from(one),
+                    F::from(2 * one - 1) - F::from(significand),
+                )
+            }
+            None => (0, 0, F::zero(), F::zero()),
+        };
+        region.assign_advice(
+            || "float normalization shift",
+            self.shift,
+            offset,
+            || Value::known(F::from(shift)),
+        )?;
+        region.assign_advice(
+            || "float normalization pow2_shift",
+            self.pow2_shift,
+            offset,
+            || {
+                Value::known(if value.is_some() {
+                    pow_of_two(shift as usize)
+                } else {
+                    F::zero()
+                })
+            },
+        )?;
+        region.assign_advice(
+            || "float normalized significand",
+            self.significand,
+            offset,
+            || Value::known(F::from(significand)),
+        )?;
+        self.lower_limbs.assign(region, offset, lower)?;
+        self.upper_limbs.assign(region, offset, upper)
+    }
+}
+
+/// Layout for the Float circuit.
+#[derive(Clone, Debug)]
+pub struct FloatCircuitConfig<F> {
+    /// Whether the row holds an operation.
+    pub q_enable: Column<Fixed>,
+    /// The Float circuit's table.
+    pub float_table: FloatTable,
+    /// Fixed table of the values in `[0, 2^LIMB_BITS)`.
+    range_table: Column<Fixed>,
+    /// Fixed table of the (enabled, exponent, 2^exponent) rows.
+    pow2_table: [Column<Fixed>; 3],
+    /// Flags of the op tag, the tag is `1 + is_sub + 2 * is_mul + 3 * is_div + 4 * is_f64`.
+    is_sub: Column<Advice>,
+    is_mul: Column<Advice>,
+    is_div: Column<Advice>,
+    is_f64: Column<Advice>,
+    /// Decomposition of lhs, rhs and res.
+    operands: [FloatOperandConfig<F>; 3],
+    res_exp_one: IsZeroConfig<F>,
+    /// Sign of rhs, flipped by a subtraction.
+    rhs_sign: Column<Advice>,
+    /// Special cases that fix the class of the result.
+    is_nan_result: Column<Advice>,
+    is_inf_result: Column<Advice>,
+    is_zero_result: Column<Advice>,
+    /// Normalized significands of the multiplication and division operands.
+    normalizers: [FloatNormalizeConfig; 2],
+    /// Alignment of the addition operands, the operand with the higher exponent goes first.
+    swap: Column<Advice>,
+    is_shift_clamped: Column<Advice>,
+    shift: Column<Advice>,
+    pow2_shift: Column<Advice>,
+    exponent_diff_limbs: FloatLimbsConfig,
+    shift_clamp_limbs: FloatLimbsConfig,
+    /// Magnitude of the exact aligned sum, of the product or of the dividend.
+    sum: Column<Advice>,
+    sum_limbs: FloatLimbsConfig,
+    sum_zero: IsZeroConfig<F>,
+    /// Rounding of `sum * 2^shift / (divisor * 2^v)` into the significand of the result.
+    is_rounded: Column<Advice>,
+    v: Column<Advice>,
+    is_v_low: Column<Advice>,
+    is_v_high: Column<Advice>,
+    pow2_v: Column<Advice>,
+    divisor: Column<Advice>,
+    round_up: Column<Advice>,
+    quotient_parity: Column<Advice>,
+    is_binade_boundary: Column<Advice>,
+    v_clamp_limbs: FloatLimbsConfig,
+    half_quotient_limbs: FloatLimbsConfig,
+    remainder_limbs: FloatLimbsConfig,
+    remainder_gap_limbs: FloatLimbsConfig,
+    round_up_limbs: FloatLimbsConfig,
+    boundary_limbs: FloatLimbsConfig,
+}
+
+impl<F: Field> SubCircuitConfig<F> for FloatCircuitConfig<F> {
+    type ConfigArgs = FloatTable;
+
+    /// Return a new FloatCircuitConfig
+    fn new(meta: &mut ConstraintSystem<F>, float_table: Self::ConfigArgs) -> Self {
+        let q_enable = meta.fixed_column();
+        let range_table = meta.fixed_column();
+        let pow2_table = [(); 3].map(|_| meta.fixed_column());
+        let is_sub = meta.advice_column();
+        let is_mul = meta.advice_column();
+        let is_div = meta.advice_column();
+        let is_f64 = meta.advice_column();
+
+        let operands = [float_table.lhs, float_table.rhs, float_table.res]
+            .map(|bits| FloatOperandConfig::configure(meta, q_enable, is_f64, bits, range_table));
+        let res_exp = operands[2].exp;
+        let res_exp_one = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(res_exp, Rotation::cur()) - 1.expr(),
+            meta.advice_column(),
+        );
+        let rhs_sign = meta.advice_column();
+        let is_nan_result = meta.advice_column();
+        let is_inf_result = meta.advice_column();
+        let is_zero_result = meta.advice_column();
+
+        let normalizers = [(); 2].map(|_| FloatNormalizeConfig::configure(meta, range_table));
+
+        let swap = meta.advice_column();
+        let is_shift_clamped = meta.advice_column();
+        let shift = meta.advice_column();
+        let pow2_shift = meta.advice_column();
+        let exponent_diff_limbs = FloatLimbsConfig::configure(meta, 1, range_table);
+        let shift_clamp_limbs = FloatLimbsConfig::configure(meta, 1, range_table);
+
+        let sum = meta.advice_column();
+        let sum_limbs = FloatLimbsConfig::configure(meta, SUM_LIMBS, range_table);
+        let sum_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(sum, Rotation::cur()),
+            meta.advice_column(),
+        );
+
+        let is_rounded = meta.advice_column();
+        let v = meta.advice_column();
+        let is_v_low = meta.advice_column();
+        let is_v_high = meta.advice_column();
+        let pow2_v = meta.advice_column();
+        let divisor = meta.advice_column();
+        let round_up = meta.advice_column();
+        let quotient_parity = meta.advice_column();
+        let is_binade_boundary = meta.advice_column();
+        let v_clamp_limbs = FloatLimbsConfig::configure(meta, 1, range_table);
+        let half_quotient_limbs = FloatLimbsConfig::configure(meta, SIGNIFICAND_LIMBS, range_table);
+        let remainder_limbs = FloatLimbsConfig::configure(meta, REMAINDER_LIMBS, range_table);
+        let remainder_gap_limbs = FloatLimbsConfig::configure(meta, REMAINDER_LIMBS, range_table);
+        let round_up_limbs = FloatLimbsConfig::configure(meta, REMAINDER_LIMBS, range_table);
+        let boundary_limbs = FloatLimbsConfig::configure(meta, REMAINDER_LIMBS, range_table);
+
+        let config = Self {
+            q_enable,
+            float_table,
+            range_table,
+            pow2_table,
+            is_sub,
+            is_mul,
+            is_div,
+            is_f64,
+            operands,
+            res_exp_one,
+            rhs_sign,
+            is_nan_result,
+            is_inf_result,
+            is_zero_result,
+            normalizers,
+            swap,
+            is_shift_clamped,
+            shift,
+            pow2_shift,
+            exponent_diff_limbs,
+            shift_clamp_limbs,
+            sum,
+            sum_limbs,
+            sum_zero,
+            is_rounded,
+            v,
+            is_v_low,
+            is_v_high,
+            pow2_v,
+            divisor,
+            round_up,
+            quotient_parity,
+            is_binade_boundary,
+            v_clamp_limbs,
+            half_quotient_limbs,
+            remainder_limbs,
+            remainder_gap_limbs,
+            round_up_limbs,
+            boundary_limbs,
+        };
+
+        meta.create_gate("float operation", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_sub = meta.query_advice(is_sub, Rotation::cur());
+            let is_mul = meta.query_advice(is_mul, Rotation::cur());
+            let is_div = meta.query_advice(is_div, Rotation::cur());
+            let is_f64 = meta.query_advice(is_f64, Rotation::cur());
+            let is_add_or_sub = not::expr(is_mul.clone() + is_div.clone());
+
+            for (name, flag) in [
+                ("is_sub is bool", is_sub.clone()),
+                ("is_mul is bool", is_mul.clone()),
+                ("is_div is bool", is_div.clone()),
+                ("is_f64 is bool", is_f64.clone()),
+                (
+                    "at most one of sub, mul and div",
+                    is_add_or_sub.clone() - is_sub.clone(),
+                ),
+            ] {
+                cb.require_boolean(name, flag);
+            }
+            cb.require_equal(
+                "op == 1 + is_sub + 2 * is_mul + 3 * is_div + 4 * is_f64",
+                meta.query_advice(float_table.op, Rotation::cur()),
+                1.expr()
+                    + is_sub.clone()
+                    + 2.expr() * is_mul.clone()
+                    + 3.expr() * is_div.clone()
+                    + 4.expr() * is_f64.clone(),
+            );
+
+            let [x, y, r] = config.operands.clone().map(|operand| operand.exprs(meta));
+            let frac_bits = FloatFormat::expr(&is_f64, |format| format.frac_bits);
+            let bias = FloatFormat::expr(&is_f64, |format| format.bias());
+            let one = FloatFormat::expr(&is_f64, |format| 1 << format.frac_bits);
+
+            // Special cases
+            let rhs_sign = meta.query_advice(rhs_sign, Rotation::cur());
+            cb.require_equal(
+                "rhs_sign == rhs sign flipped by a subtraction",
+                rhs_sign.clone(),
+                xor::expr(y.sign.clone(), is_sub),
+            );
+            let sign_xor = xor::expr(x.sign.clone(), y.sign.clone());
+            let nan_add = or::expr([
+                x.is_nan.clone(),
+                y.is_nan.clone(),
+                x.is_inf.clone() * y.is_inf.clone() * xor::expr(x.sign.clone(), rhs_sign.clone()),
+            ]);
+            let nan_mul = or::expr([
+                x.is_nan.clone(),
+                y.is_nan.clone(),
+                x.is_inf.clone() * y.is_zero.clone() + x.is_zero.clone() * y.is_inf.clone(),
+            ]);
+            let nan_div = or::expr([
+                x.is_nan.clone(),
+                y.is_nan.clone(),
+                x.is_zero.clone() * y.is_zero.clone() + x.is_inf.clone() * y.is_inf.clone(),
+            ]);
+            let is_nan_result = meta.query_advice(is_nan_result, Rotation::cur());
+            let is_inf_result = meta.query_advice(is_inf_result, Rotation::cur());
+            let is_zero_result = meta.query_advice(is_zero_result, Rotation::cur());
+            cb.require_equal(
+                "NaN result of NaN operands, inf - inf, 0 * inf, 0 / 0 and inf / inf",
+                is_nan_result.clone(),
+                is_add_or_sub.clone() * nan_add
+                    + is_mul.clone() * nan_mul
+                    + is_div.clone() * nan_div,
+            );
+            cb.require_equal(
+                "infinite result of infinite operands and of division by zero",
+                is_inf_result.clone(),
+                not::expr(is_nan_result.clone())
+                    * select::expr(
+                        is_div.clone(),
+                        or::expr([x.is_inf.clone(), y.is_zero.clone()]),
+                        or::expr([x.is_inf.clone(), y.is_inf.clone()]),
+                    ),
+            );
+            cb.require_equal(
+                "zero result of multiplication by zero and division of zero or by inf",
+                is_zero_result.clone(),
+                not::expr(is_nan_result.clone())
+                    * not::expr(is_inf_result.clone())
+                    * (is_mul.clone() * or::expr([x.is_zero.clone(), y.is_zero.clone()])
+                        + is_div.clone() * or::expr([x.is_zero.clone(), y.is_inf.clone()])),
+            );
+            let is_finite =
+                1.expr() - is_nan_result.clone() - is_inf_result.clone() - is_zero_result.clone();
+            cb.condition(is_nan_result, |cb| {
+                cb.require_equal("res is NaN", r.is_nan.clone(), 1.expr());
+            });
+            cb.condition(is_inf_result, |cb| {
+                cb.require_equal("res is inf", r.is_inf.clone(), 1.expr());
+                cb.require_equal(
+                    "res sign of the infinite operand or product",
+                    r.sign.clone(),
+                    select::expr(
+                        is_add_or_sub.clone(),
+                        select::expr(x.is_inf.clone(), x.sign.clone(), rhs_sign.clone()),
+                        sign_xor.clone(),
+                    ),
+                );
+            });
+            cb.condition(is_zero_result, |cb| {
+                cb.require_equal("res is zero", r.is_zero.clone(), 1.expr());
+                cb.require_equal("res sign of the product", r.sign.clone(), sign_xor.clone());
+            });
+            cb.condition(is_finite.clone(), |cb| {
+                cb.require_zero("res is not NaN", r.is_nan.clone());
+            });
+            cb.condition(is_finite.clone() * not::expr(is_add_or_sub.clone()), |cb| {
+                cb.require_equal("res sign of the product", r.sign.clone(), sign_xor);
+            });
+
+            // Normalization of the multiplication and division operands
+            let [(x_significand, x_exponent), (y_significand, y_exponent)] =
+                [(&config.normalizers[0], &x), (&config.normalizers[1], &y)].map(
+                    |(normalizer, operand)| {
+                        let shift = meta.query_advice(normalizer.shift, Rotation::cur());
+                        let pow2_shift = meta.query_advice(normalizer.pow2_shift, Rotation::cur());
+                        let significand =
+                            meta.query_advice(normalizer.significand, Rotation::cur());
+                        let lower = normalizer.lower_limbs.expr(meta);
+                        let upper = normalizer.upper_limbs.expr(meta);
+                        cb.condition(is_finite.clone() * not::expr(is_add_or_sub.clone()), |cb| {
+                            cb.require_equal(
+                                "normalized significand == significand * 2^shift",
+                                significand.clone(),
+                                operand.significand.clone() * pow2_shift,
+                            );
+                            cb.require_equal(
+                                "normalized significand >= 2^frac_bits",
+                                significand.clone() - one.clone(),
+                                lower,
+                            );
+                            cb.require_equal(
+                                "normalized significand < 2^(frac_bits + 1)",
+                                2.expr() * one.clone() - 1.expr() - significand.clone(),
+                                upper,
+                            );
+                        });
+                        (significand, operand.exponent.clone() - shift)
+                    },
+                );
+
+            // Alignment of the addition operands
+            let swap = meta.query_advice(swap, Rotation::cur());
+            let is_shift_clamped = meta.query_advice(is_shift_clamped, Rotation::cur());
+            let shift = meta.query_advice(shift, Rotation::cur());
+            let pow2_shift = meta.query_advice(pow2_shift, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            cb.require_boolean("swap is bool", swap.clone());
+            cb.require_boolean("is_shift_clamped is bool", is_shift_clamped.clone());
+            let hi_significand =
+                select::expr(swap.clone(), y.significand.clone(), x.significand.clone());
+            let lo_significand =
+                x.significand.clone() + y.significand.clone() - hi_significand.clone();
+            let hi_exponent = select::expr(swap.clone(), y.exponent.clone(), x.exponent.clone());
+            let lo_exponent = x.exponent.clone() + y.exponent.clone() - hi_exponent.clone();
+            let hi_sign = select::expr(swap, rhs_sign.clone(), x.sign.clone());
+            let lo_sign = x.sign.clone() + rhs_sign.clone() - hi_sign.clone();
+            let exponent_diff = hi_exponent.clone() - lo_exponent;
+            // A lower operand shifted by more than frac_bits + 3 is below a quarter of the ulp
+            // of the higher one, so it rounds as if it was shifted by exactly that much.
+            let max_shift = frac_bits.clone() + 3.expr();
+            cb.condition(is_finite.clone() * is_add_or_sub.clone(), |cb| {
+                cb.require_equal(
+                    "exponent of the higher operand >= exponent of the lower one",
+                    exponent_diff.clone(),
+                    config.exponent_diff_limbs.expr(meta),
+                );
+                cb.require_equal(
+                    "shift == min(exponent difference, frac_bits + 3)",
+                    shift.clone(),
+                    select::expr(
+                        is_shift_clamped.clone(),
+                        max_shift.clone(),
+                        exponent_diff.clone(),
+                    ),
+                );
+                cb.require_equal(
+                    "shift clamped iff exponent difference >= frac_bits + 3",
+                    select::expr(
+                        is_shift_clamped.clone(),
+                        exponent_diff.clone() - max_shift.clone(),
+                        max_shift.clone() - exponent_diff.clone(),
+                    ),
+                    config.shift_clamp_limbs.expr(meta),
+                );
+                cb.require_equal(
+                    "aligned sum == (1 - 2 * res sign) * sum",
+                    (1.expr() - 2.expr() * hi_sign) * hi_significand * pow2_shift.clone()
+                        + (1.expr() - 2.expr() * lo_sign) * lo_significand,
+                    (1.expr() - 2.expr() * r.sign.clone()) * sum.clone(),
+                );
+                cb.require_equal("sum >= 0", sum.clone(), config.sum_limbs.expr(meta));
+            });
+            cb.condition(
+                is_finite.clone() * is_add_or_sub.clone() * config.sum_zero.expr(),
+                |cb| {
+                    cb.require_equal("exact zero sum", r.is_zero.clone(), 1.expr());
+                    cb.require_equal(
+                        "exact zero sum is negative only for negative operands",
+                        r.sign.clone(),
+                        x.sign.clone() * rhs_sign,
+                    );
+                },
+            );
+            cb.condition(is_finite.clone() * is_mul.clone(), |cb| {
+                cb.require_equal(
+                    "sum == product of the significands",
+                    sum.clone(),
+                    x_significand.clone() * y_significand.clone(),
+                );
+            });
+            cb.condition(is_finite.clone() * is_div.clone(), |cb| {
+                cb.require_equal("sum == dividend significand", sum.clone(), x_significand);
+            });
+
+            // Rounding
+            let is_rounded = meta.query_advice(is_rounded, Rotation::cur());
+            cb.require_equal(
+                "rounding of the finite non-zero exact values",
+                is_rounded.clone(),
+                is_finite * not::expr(config.sum_zero.expr()),
+            );
+            let v = meta.query_advice(v, Rotation::cur());
+            let is_v_low = meta.query_advice(is_v_low, Rotation::cur());
+            let is_v_high = meta.query_advice(is_v_high, Rotation::cur());
+            let pow2_v = meta.query_advice(pow2_v, Rotation::cur());
+            let divisor = meta.query_advice(divisor, Rotation::cur());
+            let round_up = meta.query_advice(round_up, Rotation::cur());
+            let quotient_parity = meta.query_advice(quotient_parity, Rotation::cur());
+            let is_binade_boundary = meta.query_advice(is_binade_boundary, Rotation::cur());
+            let remainder = config.remainder_limbs.expr(meta);
+            for (name, flag) in [
+                ("is_v_low is bool", is_v_low.clone()),
+                ("is_v_high is bool", is_v_high.clone()),
+                (
+                    "v is not clamped both ways",
+                    is_v_low.clone() + is_v_high.clone(),
+                ),
+                ("round_up is bool", round_up.clone()),
+                ("quotient_parity is bool", quotient_parity.clone()),
+            ] {
+                cb.require_boolean(name, flag);
+            }
+            cb.require_equal(
+                "the result is the lowest normal of its binade, above the lowest two binades",
+                is_binade_boundary.clone(),
+                r.frac_zero.clone()
+                    * not::expr(r.exp_zero.clone())
+                    * not::expr(config.res_exp_one.expr()),
+            );
+
+            // The exact value is `sum * 2^(exponent - bias - frac_bits)` (divided by the
+            // normalized divisor significand), `v` is the shift that scales it to the units of the
+            // claimed result significand.
+            let unbias = bias + frac_bits.clone();
+            let exponent = select::expr(
+                is_add_or_sub.clone(),
+                hi_exponent - unbias.clone() - shift,
+                select::expr(
+                    is_mul.clone(),
+                    x_exponent.clone() + y_exponent.clone() - 2.expr() * unbias.clone(),
+                    x_exponent - y_exponent,
+                ),
+            );
+            let sum_shift = is_add_or_sub.clone() * (frac_bits.clone() + 1.expr())
+                + is_div.clone() * (frac_bits.clone() + 2.expr());
+            let pow2_sum_shift = is_add_or_sub.clone()
+                * FloatFormat::expr(&is_f64, |format| 1 << (format.frac_bits + 1))
+                + is_div.clone() * FloatFormat::expr(&is_f64, |format| 1 << (format.frac_bits + 2))
+                + is_mul.clone();
+            let max_v = is_add_or_sub * (3.expr() * frac_bits.clone() + 7.expr())
+                + is_mul * (2.expr() * frac_bits.clone() + 3.expr())
+                + is_div.clone() * (frac_bits + 4.expr());
+            let v_raw = r.exponent.clone() - unbias - exponent + sum_shift;
+            let is_v_exact = 1.expr() - is_v_low.clone() - is_v_high.clone();
+            let shifted_sum = sum * pow2_sum_shift;
+            cb.condition(is_rounded.clone(), |cb| {
+                cb.require_equal(
+                    "v == clamp(v_raw, 0, max_v)",
+                    v.clone(),
+                    is_v_exact.clone() * v_raw.clone() + is_v_high.clone() * max_v.clone(),
+                );
+                cb.require_equal(
+                    "v clamped iff v_raw is out of [0, max_v]",
+                    is_v_exact.clone() * (max_v.clone() - v.clone())
+                        + is_v_low.clone() * (-v_raw.clone() - 1.expr())
+                        + is_v_high.clone() * (v_raw.clone() - max_v.clone() - 1.expr()),
+                    config.v_clamp_limbs.expr(meta),
+                );
+                cb.require_equal(
+                    "divisor == normalized divisor significand * 2^v",
+                    divisor.clone(),
+                    select::expr(is_div, y_significand, 1.expr()) * pow2_v.clone(),
+                );
+                // An infinite result is either way above the largest normal (the exact value
+                // needs a left shift), or rounds up from the largest normal:
+                // shifted_sum / divisor >= 2^frac_bits - 1/4.
+                // The lowest normal of a binade is below the lowest value that rounds into it
+                // from the binade below when the quotient rounds up:
+                // remainder / divisor >= 3/4.
+                cb.require_equal(
+                    "overflow and binade boundary",
+                    r.is_inf.clone()
+                        * not::expr(is_v_low.clone())
+                        * (4.expr() * shifted_sum.clone()
+                            - (4.expr() * one.clone() - 1.expr()) * divisor.clone())
+                        + not::expr(r.is_inf.clone())
+                            * is_binade_boundary
+                            * round_up.clone()
+                            * (4.expr() * remainder.clone() - 3.expr() * divisor.clone()),
+                    config.boundary_limbs.expr(meta),
+                );
+            });
+            cb.condition(is_rounded * not::expr(r.is_inf), |cb| {
+                cb.require_zero("a finite result needs no left shift", is_v_low);
+                let quotient = r.significand - round_up.clone();
+                cb.require_equal(
+                    "shifted_sum == (res significand - round_up) * divisor + remainder",
+                    shifted_sum,
+                    quotient.clone() * divisor.clone() + remainder.clone(),
+                );
+                cb.require_equal(
+                    "quotient parity",
+                    quotient,
+                    2.expr() * config.half_quotient_limbs.expr(meta) + quotient_parity.clone(),
+                );
+                cb.require_equal(
+                    "remainder < divisor",
+                    divisor.clone() - 1.expr() - remainder.clone(),
+                    config.remainder_gap_limbs.expr(meta),
+                );
+                // Round to nearest, ties to even: up iff 2 * remainder + parity > divisor
+                let diff = 2.expr() * remainder - divisor + quotient_parity;
+                cb.require_equal(
+                    "round_up iff above half, or at half with an odd quotient",
+                    select::expr(round_up, diff.clone() - 1.expr(), -diff),
+                    config.round_up_limbs.expr(meta),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        let pow2_lookup = |meta: &mut ConstraintSystem<F>,
+                           name: &'static str,
+                           condition: &dyn Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+                           exponent: Column<Advice>,
+                           value: Column<Advice>| {
+            meta.lookup_any(name, |meta| {
+                let condition = meta.query_fixed(q_enable, Rotation::cur()) * condition(meta);
+                vec![
+                    (
+                        condition.clone(),
+                        meta.query_fixed(pow2_table[0], Rotation::cur()),
+                    ),
+                    (
+                        condition.clone() * meta.query_advice(exponent, Rotation::cur()),
+                        meta.query_fixed(pow2_table[1], Rotation::cur()),
+                    ),
+                    (
+                        condition * meta.query_advice(value, Rotation::cur()),
+                        meta.query_fixed(pow2_table[2], Rotation::cur()),
+                    ),
+                ]
+            });
+        };
+        let is_finite = move |meta: &mut VirtualCells<'_, F>| {
+            1.expr()
+                - meta.query_advice(is_nan_result, Rotation::cur())
+                - meta.query_advice(is_inf_result, Rotation::cur())
+                - meta.query_advice(is_zero_result, Rotation::cur())
+        };
+        let is_mul_or_div = move |meta: &mut VirtualCells<'_, F>| {
+            meta.query_advice(is_mul, Rotation::cur()) + meta.query_advice(is_div, Rotation::cur())
+        };
+        for normalizer in config.normalizers.iter() {
+            pow2_lookup(
+                meta,
+                "float normalization pow2",
+                &|meta| is_finite(meta) * is_mul_or_div(meta),
+                normalizer.shift,
+                normalizer.pow2_shift,
+            );
+        }
+        pow2_lookup(
+            meta,
+            "float alignment pow2",
+            &|meta| is_finite(meta) * not::expr(is_mul_or_div(meta)),
+            shift,
+            pow2_shift,
+        );
+        pow2_lookup(
+            meta,
+            "float rounding pow2",
+            &|meta| meta.query_advice(is_rounded, Rotation::cur()),
+            v,
+            pow2_v,
+        );
+
+        config
+    }
+}
+
+impl<F: Field> FloatCircuitConfig<F> {
+    /// Assign the floating-point operations, the range table and the pow2 table
+    pub fn assign_float_ops(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        float_ops: &[(FloatOpTag, u64, u64, u64)],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "float circuit",
+            |mut region| {
+                self.float_table.assign(&mut region, 0, [F::zero(); 4])?;
+                for (idx, &(tag, lhs, rhs, res)) in float_ops.iter().enumerate() {
+                    self.assign_float_op(&mut region, idx + 1, tag, lhs, rhs, res)?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "float range table",
+            |mut region| {
+                for idx in 0..RANGE_TABLE_ROWS {
+                    region.assign_fixed(
+                        || "float range table",
+                        self.range_table,
+                        idx,
+                        || Value::known(F::from(idx as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "float pow2 table",
+            |mut region| {
+                for exponent in 0..=MAX_POW2_EXPONENT {
+                    let row = [F::one(), F::from(exponent), pow_of_two(exponent as usize)];
+                    for (column, value) in self.pow2_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "float pow2 table",
+                            *column,
+                            exponent as usize,
+                            || Value::known(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_float_op(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        tag: FloatOpTag,
+        lhs: u64,
+        rhs: u64,
+        res: u64,
+    ) -> Result<(), Error> {
+        let assign = |region: &mut Region<'_, F>, name: &str, column: Column<Advice>, value: F| {
+            region
+                .assign_advice(
+                    || format!("float {}", name),
+                    column,
+                    offset,
+                    || Value::known(value),
+                )
+                .map(|_| ())
+        };
+        let flag = |value: bool| F::from(value as u64);
+
+        region.assign_fixed(
+            || "float q_enable",
+            self.q_enable,
+            offset,
+            || Value::known(F::one()),
+        )?;
+        self.float_table
+            .assign(region, offset, [tag as u64, lhs, rhs, res].map(F::from))?;
+
+        let (kind, format) = FloatOpKind::from_tag(tag);
+        let (is_sub, is_mul, is_div) = (
+            kind == FloatOpKind::Sub,
+            kind == FloatOpKind::Mul,
+            kind == FloatOpKind::Div,
+        );
+        let is_add_or_sub = !is_mul && !is_div;
+        assign(region, "is_sub", self.is_sub, flag(is_sub))?;
+        assign(region, "is_mul", self.is_mul, flag(is_mul))?;
+        assign(region, "is_div", self.is_div, flag(is_div))?;
+        assign(
+            region,
+            "is_f64",
+            self.is_f64,
+            flag(format == FloatFormat::F64),
+        )?;
+
+        let [x, y, r] = [lhs, rhs, res].map(|bits| FloatBits::decode(bits, format));
+        for (operand, value) in self.operands.iter().zip([&x, &y, &r]) {
+            operand.assign(region, offset, value)?;
+        }
+        IsZeroChip::construct(self.res_exp_one.clone()).assign(
+            region,
+            offset,
+            Value::known(F::from(r.exp) - F::one()),
+        )?;
+
+        // Special cases
+        let rhs_sign = y.sign ^ is_sub;
+        let is_nan_result = x.is_nan()
+            || y.is_nan()
+            || match kind {
+                FloatOpKind::Add | FloatOpKind::Sub => {
+                    x.is_inf() && y.is_inf() && x.sign != rhs_sign
+                }
+                FloatOpKind::Mul => (x.is_inf() && y.is_zero()) || (x.is_zero() && y.is_inf()),
+                FloatOpKind::Div => (x.is_zero() && y.is_zero()) || (x.is_inf() && y.is_inf()),
+            };
+        let is_inf_result =
+            !is_nan_result && (x.is_inf() || if is_div { y.is_zero() } else { y.is_inf() });
+        let is_zero_result = !is_nan_result
+            && !is_inf_result
+            && match kind {
+                FloatOpKind::Add | FloatOpKind::Sub => false,
+                FloatOpKind::Mul => x.is_zero() || y.is_zero(),
+                FloatOpKind::Div => x.is_zero() || y.is_inf(),
+            };
+        let is_finite = !is_nan_result && !is_inf_result && !is_zero_result;
+        assign(region, "rhs_sign", self.rhs_sign, flag(rhs_sign))?;
+        assign(
+            region,
+            "is_nan_result",
+            self.is_nan_result,
+            flag(is_nan_result),
+        )?;
+        assign(
+            region,
+            "is_inf_result",
+            self.is_inf_result,
+            flag(is_inf_result),
+        )?;
+        assign(
+            region,
+            "is_zero_result",
+            self.is_zero_result,
+            flag(is_zero_result),
+        )?;
+
+        // Normalization of the multiplication and division operands
+        let normalize = |value: &FloatBits| {
+            format.frac_bits + 1 - (64 - value.significand().leading_zeros() as u64)
+        };
+        let [x_shift, y_shift] = [&x, &y].map(|value| {
+            if is_finite && !is_add_or_sub {
+                Some(normalize(value))
+            } else {
+                None
+            }
+        });
+        for (normalizer, (value, shift)) in
+            self.normalizers.iter().zip([(&x, x_shift), (&y, y_shift)])
+        {
+            normalizer.assign(region, offset, shift.map(|shift| (value, shift)))?;
+        }
+        let normalized = |value: &FloatBits, shift: Option<u64>| {
+            (
+                value.significand() << shift.unwrap_or_default(),
+                value.exponent() as i64 - shift.unwrap_or_default() as i64,
+            )
+        };
+        let ((x_significand, x_exponent), (y_significand, y_exponent)) =
+            (normalized(&x, x_shift), normalized(&y, y_shift));
+
+        // Alignment of the addition operands
+        let bias = format.bias() as i64;
+        let frac_bits = format.frac_bits as i64;
+        let unbias = bias + frac_bits;
+        let max_shift = format.frac_bits + 3;
+        let (sum, exponent) = if is_finite && is_add_or_sub {
+            let swap = y.exponent() > x.exponent();
+            let ((hi, hi_sign), (lo, lo_sign)) = if swap {
+                ((&y, rhs_sign), (&x, x.sign))
+            } else {
+                ((&x, x.sign), (&y, rhs_sign))
+            };
+            let exponent_diff = hi.exponent() - lo.exponent();
+            let is_shift_clamped = exponent_diff > max_shift;
+            let shift = exponent_diff.min(max_shift);
+            let signed_significand =
+                |significand: u64, sign: bool| (1 - 2 * sign as i128) * significand as i128;
+            let aligned_sum = signed_significand(hi.significand(), hi_sign) * (1i128 << shift)
+                + signed_significand(lo.significand(), lo_sign);
+            assign(region, "swap", self.swap, flag(swap))?;
+            assign(
+                region,
+                "is_shift_clamped",
+                self.is_shift_clamped,
+                flag(is_shift_clamped),
+            )?;
+            assign(region, "shift", self.shift, F::from(shift))?;
+            assign(
+                region,
+                "pow2_shift",
+                self.pow2_shift,
+                pow_of_two(shift as usize),
+            )?;
+            self.exponent_diff_limbs
+                .assign(region, offset, F::from(exponent_diff))?;
+            self.shift_clamp_limbs.assign(
+                region,
+                offset,
+                signed(if is_shift_clamped {
+                    exponent_diff as i64 - max_shift as i64
+                } else {
+                    max_shift as i64 - exponent_diff as i64
+                }),
+            )?;
+            let sum = aligned_sum.unsigned_abs();
+            self.sum_limbs.assign(region, offset, F::from_u128(sum))?;
+            (sum, hi.exponent() as i64 - unbias - shift as i64)
+        } else if is_finite && is_mul {
+            (
+                x_significand as u128 * y_significand as u128,
+                x_exponent + y_exponent - 2 * unbias,
+            )
+        } else if is_finite && is_div {
+            (x_significand as u128, x_exponent - y_exponent)
+        } else {
+            (0, 0)
+        };
+        assign(region, "sum", self.sum, F::from_u128(sum))?;
+        IsZeroChip::construct(self.sum_zero.clone()).assign(
+            region,
+            offset,
+            Value::known(F::from_u128(sum)),
+        )?;
+
+        // Rounding
+        let is_binade_boundary = r.frac == 0 && r.exp >= 2;
+        assign(
+            region,
+            "is_binade_boundary",
+            self.is_binade_boundary,
+            flag(is_binade_boundary),
+        )?;
+        let is_rounded = is_finite && sum != 0;
+        assign(region, "is_rounded", self.is_rounded, flag(is_rounded))?;
+        if !is_rounded {
+            return Ok(());
+        }
+        let (sum_shift, max_v) = kind.rounding_shifts(format);
+        let v_raw = r.exponent() as i64 - unbias - exponent + sum_shift as i64;
+        let (is_v_low, is_v_high) = (v_raw < 0, v_raw > max_v as i64);
+        let v = v_raw.clamp(0, max_v as i64) as u64;
+        let v_clamp = if is_v_low {
+            -v_raw - 1
+        } else if is_v_high {
+            v_raw - max_v as i64 - 1
+        } else {
+            max_v as i64 - v_raw
+        };
+        let divisor = U256::from(if is_div { y_significand } else { 1 }) << v as usize;
+        let shifted_sum = U256::from(sum) << sum_shift as usize;
+        assign(region, "v", self.v, F::from(v))?;
+        assign(region, "is_v_low", self.is_v_low, flag(is_v_low))?;
+        assign(region, "is_v_high", self.is_v_high, flag(is_v_high))?;
+        assign(region, "pow2_v", self.pow2_v, pow_of_two(v as usize))?;
+        assign(region, "divisor", self.divisor, word(divisor))?;
+        self.v_clamp_limbs.assign(region, offset, signed(v_clamp))?;
+
+        let one = F::from(1u64 << format.frac_bits);
+        if r.is_inf() {
+            let overflow = if is_v_low {
+                F::zero()
+            } else {
+                F::from(4) * word::<F>(shifted_sum) - (F::from(4) * one - F::one()) * word(divisor)
+            };
+            return self.boundary_limbs.assign(region, offset, overflow);
+        }
+        let (quotient, remainder) = shifted_sum.div_mod(divisor);
+        let quotient_parity = quotient.bit(0);
+        let twice_remainder = remainder + remainder;
+        let round_up = twice_remainder > divisor || (twice_remainder == divisor && quotient_parity);
+        let diff = F::from(2) * word::<F>(remainder) - word::<F>(divisor) + flag(quotient_parity);
+        assign(region, "round_up", self.round_up, flag(round_up))?;
+        assign(
+            region,
+            "quotient_parity",
+            self.quotient_parity,
+            flag(quotient_parity),
+        )?;
+        self.half_quotient_limbs
+            .assign(region, offset, word(quotient >> 1))?;
+        self.remainder_limbs
+            .assign(region, offset, word(remainder))?;
+        self.remainder_gap_limbs
+            .assign(region, offset, word(divisor - remainder - U256::one()))?;
+        self.round_up_limbs.assign(
+            region,
+            offset,
+            if round_up { diff - F::one() } else { -diff },
+        )?;
+        self.boundary_limbs.assign(
+            region,
+            offset,
+            if is_binade_boundary && round_up {
+                F::from(4) * word::<F>(remainder) - F::from(3) * word::<F>(divisor)
+            } else {
+                F::zero()
+            },
+        )
+    }
+
+    fn min_num_rows(float_ops: &[(FloatOpTag, u64, u64, u64)]) -> usize {
+        float_ops.len() + 1
+    }
+}
+
+/// FloatCircuit
+#[derive(Default, Clone, Debug)]
+pub struct FloatCircuit<F> {
+    /// The (op, lhs, rhs, res) floating-point operations
+    pub float_ops: Vec<(FloatOpTag, u64, u64, u64)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> FloatCircuit<F> {
+    /// Return a new FloatCircuit
+    pub fn new(float_ops: Vec<(FloatOpTag, u64, u64, u64)>) -> Self {
+        Self {
+            float_ops,
+            _marker: PhantomData::default(),
+        }
+    }
+}
+
+impl<F: Field> SubCircuit<F> for FloatCircuit<F> {
+    type Config = FloatCircuitConfig<F>;
+
+    fn unusable_rows() -> usize {
+        // No column queried at more than 1 distinct rotation, so returns 6 as
+        // minimum unusable rows.
+        6
+    }
+
+    fn new_from_block(block: &witness::Block<F>) -> Self {
+        Self::new(FloatTable::float_ops(block))
+    }
+
+    /// Return the minimum number of rows required to prove the block
+    fn min_num_rows_block(block: &witness::Block<F>) -> (usize, usize) {
+        let num_rows = Self::Config::min_num_rows(&FloatTable::float_ops(block));
+        (num_rows, num_rows.max(RANGE_TABLE_ROWS))
+    }
+
+    /// Make the assignments to the FloatCircuit
+    fn synthesize_sub(
+        &self,
+        config: &Self::Config,
+        _challenges: &Challenges<Value<F>>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.assign_float_ops(layouter, &self.float_ops)
+    }
+}
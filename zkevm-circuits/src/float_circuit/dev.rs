@@ -0,0 +1,36 @@
+pub use super::FloatCircuit;
+
+use crate::{
+    float_circuit::FloatCircuitConfig,
+    table::FloatTable,
+    util::{Challenges, SubCircuit, SubCircuitConfig},
+};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+impl<F: Field> Circuit<F> for FloatCircuit<F> {
+    type Config = (FloatCircuitConfig<F>, Challenges);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let float_table = FloatTable::construct(meta);
+        let challenges = Challenges::construct(meta);
+        (FloatCircuitConfig::new(meta, float_table), challenges)
+    }
+
+    fn synthesize(
+        &self,
+        (config, challenges): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let challenges = challenges.values(&layouter);
+        self.synthesize_sub(&config, &challenges, &mut layouter)
+    }
+}
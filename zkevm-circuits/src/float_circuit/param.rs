@@ -0,0 +1,15 @@
+/// The number of bits of a limb, every limb is looked up in a range table of `1 << LIMB_BITS`
+/// rows.
+pub(crate) const LIMB_BITS: usize = 16usize;
+/// The number of rows of the range table.
+pub(crate) const RANGE_TABLE_ROWS: usize = 1usize << LIMB_BITS;
+/// The largest exponent of the power of two table. It's the largest shift of the rounding step,
+/// reached by the f64 additions (`3 * 52 + 7`).
+pub(crate) const MAX_POW2_EXPONENT: u64 = 163u64;
+/// The number of limbs of a (normalized) significand, the fraction of a f64 has 52 bits.
+pub(crate) const SIGNIFICAND_LIMBS: usize = 4usize;
+/// The number of limbs of the exact magnitude of an aligned sum, it's below `2^(2 * 52 + 5)`.
+pub(crate) const SUM_LIMBS: usize = 7usize;
+/// The number of limbs of the rounding remainders, they're below the divisor of the rounding step
+/// shifted by 2 bits, so below `2^(3 * 52 + 9)`.
+pub(crate) const REMAINDER_LIMBS: usize = 11usize;
@@ -0,0 +1,189 @@
+use crate::{
+    float_circuit::FloatCircuit,
+    table::FloatOpTag,
+    util::{unusable_rows, SubCircuit},
+};
+use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+use strum::IntoEnumIterator;
+
+const K: u32 = 17;
+
+#[test]
+fn float_circuit_unusable_rows() {
+    assert_eq!(
+        FloatCircuit::<Fr>::unusable_rows(),
+        unusable_rows::<Fr, FloatCircuit::<Fr>>(),
+    )
+}
+
+fn float_op(op: FloatOpTag, lhs: u64, rhs: u64) -> (FloatOpTag, u64, u64, u64) {
+    (op, lhs, rhs, op.eval(lhs, rhs))
+}
+
+fn f32_op(op: FloatOpTag, lhs: f32, rhs: f32) -> (FloatOpTag, u64, u64, u64) {
+    float_op(op, lhs.to_bits() as u64, rhs.to_bits() as u64)
+}
+
+fn f64_op(op: FloatOpTag, lhs: f64, rhs: f64) -> (FloatOpTag, u64, u64, u64) {
+    float_op(op, lhs.to_bits(), rhs.to_bits())
+}
+
+fn test_float_circuit(float_ops: Vec<(FloatOpTag, u64, u64, u64)>, is_ok: bool) {
+    let circuit = FloatCircuit::<Fr>::new(float_ops);
+    let prover = MockProver::<Fr>::run(K, &circuit, vec![]).unwrap();
+    if is_ok {
+        prover.assert_satisfied_par();
+    } else {
+        assert!(prover.verify_par().is_err());
+    }
+}
+
+#[test]
+fn float_circuit_f64_ok() {
+    use FloatOpTag::*;
+    let min_subnormal = f64::from_bits(1);
+    test_float_circuit(
+        vec![
+            f64_op(F64Add, 1.5, 2.25),
+            f64_op(F64Add, 0.1, 0.2),
+            f64_op(F64Add, 1.0, 2f64.powi(-53)),
+            f64_op(F64Add, 1.0 + f64::EPSILON, 2f64.powi(-53)),
+            f64_op(F64Sub, 1.0, 2f64.powi(-60)),
+            f64_op(F64Sub, 1.0, 2f64.powi(-54)),
+            f64_op(F64Sub, 3.5, 3.5),
+            f64_op(F64Add, -0.0, -0.0),
+            f64_op(F64Sub, -0.0, 0.0),
+            f64_op(F64Add, f64::MAX, f64::MAX),
+            f64_op(F64Sub, -f64::MAX, 2f64.powi(970)),
+            f64_op(F64Add, f64::INFINITY, 1.0),
+            f64_op(F64Sub, f64::INFINITY, f64::INFINITY),
+            f64_op(F64Add, f64::NAN, 1.0),
+            f64_op(F64Add, min_subnormal, min_subnormal),
+            f64_op(F64Sub, f64::MIN_POSITIVE, min_subnormal),
+            f64_op(F64Mul, 1.5, -2.5),
+            f64_op(F64Mul, 0.1, 0.3),
+            f64_op(F64Mul, f64::MAX, 2.0),
+            f64_op(F64Mul, f64::MAX, 1.0),
+            f64_op(F64Mul, f64::MIN_POSITIVE, 0.5),
+            f64_op(F64Mul, min_subnormal, 0.5),
+            f64_op(F64Mul, min_subnormal, min_subnormal),
+            f64_op(F64Mul, min_subnormal, 2f64.powi(1000)),
+            f64_op(F64Mul, -0.0, 5.0),
+            f64_op(F64Mul, f64::INFINITY, 0.0),
+            f64_op(F64Div, 1.0, 3.0),
+            f64_op(F64Div, 1.0, 0.0),
+            f64_op(F64Div, -1.0, 0.0),
+            f64_op(F64Div, 0.0, 0.0),
+            f64_op(F64Div, f64::INFINITY, f64::INFINITY),
+            f64_op(F64Div, 1.0, f64::NEG_INFINITY),
+            f64_op(F64Div, 3.0 * min_subnormal, 2.0),
+            f64_op(F64Div, f64::MAX, min_subnormal),
+            f64_op(F64Div, min_subnormal, f64::MAX),
+            f64_op(F64Div, min_subnormal, 3.0 * min_subnormal),
+        ],
+        true,
+    );
+}
+
+#[test]
+fn float_circuit_f32_ok() {
+    use FloatOpTag::*;
+    let min_subnormal = f32::from_bits(1);
+    test_float_circuit(
+        vec![
+            f32_op(F32Add, 1.5, 2.25),
+            f32_op(F32Add, 0.1, 0.2),
+            f32_op(F32Add, 1.0, 2f32.powi(-24)),
+            f32_op(F32Sub, 1.0, 2f32.powi(-40)),
+            f32_op(F32Sub, -2.0, -2.0),
+            f32_op(F32Add, f32::MAX, f32::MAX),
+            f32_op(F32Sub, f32::NEG_INFINITY, f32::INFINITY),
+            f32_op(F32Sub, f32::INFINITY, f32::INFINITY),
+            f32_op(F32Add, min_subnormal, f32::MIN_POSITIVE),
+            f32_op(F32Mul, 0.1, 0.3),
+            f32_op(F32Mul, f32::MAX, -2.0),
+            f32_op(F32Mul, min_subnormal, 0.5),
+            f32_op(F32Mul, 0.0, f32::NEG_INFINITY),
+            f32_op(F32Div, 1.0, 3.0),
+            f32_op(F32Div, -0.0, 0.0),
+            f32_op(F32Div, 2.0, -0.0),
+            f32_op(F32Div, f32::MAX, min_subnormal),
+            f32_op(F32Div, min_subnormal, f32::MAX),
+            f32_op(F32Div, f32::NAN, 1.0),
+        ],
+        true,
+    );
+}
+
+#[test]
+fn float_circuit_random_ok() {
+    // xorshift64, the bits cover every class of values of both formats
+    let mut state = 0x2545f4914f6cdd1du64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let float_ops = FloatOpTag::iter()
+        .flat_map(|op| std::iter::repeat(op).take(16))
+        .map(|op| {
+            let (lhs, rhs) = if (op as u64) < FloatOpTag::F64Add as u64 {
+                (next() as u32 as u64, next() as u32 as u64)
+            } else {
+                (next(), next())
+            };
+            float_op(op, lhs, rhs)
+        })
+        .collect();
+    test_float_circuit(float_ops, true);
+}
+
+#[test]
+fn float_circuit_wrong_result_fails() {
+    use FloatOpTag::*;
+    for (op, lhs, rhs, res) in [
+        f64_op(F64Add, 1.0, 2.0),
+        f64_op(F64Add, 0.1, 0.2),
+        f64_op(F64Sub, 1.0, 2f64.powi(-54)),
+        f64_op(F64Mul, 0.1, 0.3),
+        f64_op(F64Div, 1.0, 3.0),
+        f32_op(F32Div, 1.0, 3.0),
+    ] {
+        // one ulp above and below, and the negated result
+        for wrong_res in [res + 1, res - 1, res ^ (1 << 63)] {
+            test_float_circuit(vec![(op, lhs, rhs, wrong_res)], false);
+        }
+    }
+    for (op, lhs, rhs, wrong_res) in [
+        // the tie rounds to even
+        (
+            F64Add,
+            1f64.to_bits(),
+            2f64.powi(-53).to_bits(),
+            (1.0 + f64::EPSILON).to_bits(),
+        ),
+        // overflows to inf
+        (
+            F64Mul,
+            f64::MAX.to_bits(),
+            2f64.to_bits(),
+            f64::MAX.to_bits(),
+        ),
+        // 1 / 0 is inf
+        (F64Div, 1f64.to_bits(), 0f64.to_bits(), f64::NAN.to_bits()),
+        // x - x is +0
+        (F64Sub, 1f64.to_bits(), 1f64.to_bits(), (-0f64).to_bits()),
+        // the smallest subnormal halved rounds to zero
+        (F64Mul, 1, 0.5f64.to_bits(), 1),
+        // f32 results have no upper bits
+        (
+            F32Add,
+            1f32.to_bits() as u64,
+            1f32.to_bits() as u64,
+            2f32.to_bits() as u64 | 1 << 32,
+        ),
+    ] {
+        test_float_circuit(vec![(op, lhs, rhs, wrong_res)], false);
+    }
+}
@@ -31,6 +31,7 @@ pub mod bytecode_circuit;
 pub mod copy_circuit;
 pub mod evm_circuit;
 pub mod exp_circuit;
+pub mod float_circuit;
 pub mod keccak_circuit;
 pub mod mpt_circuit;
 pub mod pi_circuit;
@@ -1,7 +1,8 @@
 #![allow(unused_imports)]
 pub use super::*;
 use crate::super_circuit::{test::block_1tx, SuperCircuit};
-use bus_mapping::circuit_input_builder::CircuitsParams;
+use bus_mapping::{circuit_input_builder::CircuitsParams, wasm::gas::DEFAULT_WASM_GAS_MODEL};
+use eth_types::evm_types::WASM_MAX_STACK_DEPTH;
 use halo2_proofs::{
     circuit::Value,
     dev::MockProver,
@@ -32,6 +33,8 @@ fn test_root_circuit() {
             max_bytecode: 512,
             max_evm_rows: 0,
             max_keccak_rows: 0,
+            max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+            wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
         };
         let (k, circuit, instance, _) =
             SuperCircuit::<_, MAX_TXS, MAX_CALLDATA, TEST_MOCK_RANDOMNESS>::build(
@@ -62,8 +62,12 @@ use crate::bytecode_circuit::circuit::BytecodeCircuitConfig;
 use crate::{
     bytecode_circuit::circuit::{BytecodeCircuit, BytecodeCircuitConfigArgs},
     copy_circuit::{CopyCircuit, CopyCircuitConfig, CopyCircuitConfigArgs},
-    evm_circuit::{EvmCircuit, EvmCircuitConfig, EvmCircuitConfigArgs},
+    evm_circuit::{
+        param::{WasmExecutionParams, WASM_MAX_STACK_DEPTH},
+        EvmCircuit, EvmCircuitConfig, EvmCircuitConfigArgs,
+    },
     exp_circuit::{ExpCircuit, ExpCircuitConfig},
+    float_circuit::{FloatCircuit, FloatCircuitConfig},
     keccak_circuit::{KeccakCircuit, KeccakCircuitConfig, KeccakCircuitConfigArgs},
     poseidon_circuit::{PoseidonCircuit, PoseidonCircuitConfig, PoseidonCircuitConfigArgs},
     tx_circuit::{TxCircuit, TxCircuitConfig, TxCircuitConfigArgs},
@@ -82,8 +86,8 @@ use crate::util::MockChallenges as Challenges;
 use crate::{
     state_circuit::{StateCircuit, StateCircuitConfig, StateCircuitConfigArgs},
     table::{
-        BlockTable, BytecodeTable, CopyTable, ExpTable, KeccakTable, MptTable, PoseidonTable,
-        RlpFsmRlpTable as RlpTable, RwTable, TxTable,
+        BlockTable, BytecodeTable, CopyTable, ExpTable, FloatTable, KeccakTable, MptTable,
+        PoseidonTable, RlpFsmRlpTable as RlpTable, RwTable, TxTable,
     },
 };
 
@@ -126,6 +130,7 @@ pub struct SuperCircuitConfig<F: Field> {
     poseidon_circuit: PoseidonCircuitConfig<F>,
     pi_circuit: PiCircuitConfig<F>,
     exp_circuit: ExpCircuitConfig<F>,
+    float_circuit: FloatCircuitConfig<F>,
     rlp_circuit: RlpCircuitConfig<F>,
     /// Mpt Circuit
     #[cfg(feature = "zktrie")]
@@ -142,6 +147,8 @@ pub struct SuperCircuitConfigArgs<F: Field> {
     pub max_inner_blocks: usize,
     /// Mock randomness
     pub mock_randomness: u64,
+    /// Params of the proven WASM execution
+    pub wasm_params: WasmExecutionParams,
     /// Challenges
     pub challenges: crate::util::Challenges<Expression<F>>,
 }
@@ -157,6 +164,7 @@ impl<F: Field> SubCircuitConfig<F> for SuperCircuitConfig<F> {
             max_calldata,
             max_inner_blocks,
             mock_randomness: _mock_randomness,
+            wasm_params,
             challenges,
         }: Self::ConfigArgs,
     ) -> Self {
@@ -183,6 +191,8 @@ impl<F: Field> SubCircuitConfig<F> for SuperCircuitConfig<F> {
         log_circuit_info(meta, "copy table");
         let exp_table = ExpTable::construct(meta);
         log_circuit_info(meta, "exp table");
+        let float_table = FloatTable::construct(meta);
+        log_circuit_info(meta, "float table");
         let rlp_table = RlpTable::construct(meta);
         log_circuit_info(meta, "rlp table");
         let keccak_table = KeccakTable::construct(meta);
@@ -298,6 +308,9 @@ impl<F: Field> SubCircuitConfig<F> for SuperCircuitConfig<F> {
         let exp_circuit = ExpCircuitConfig::new(meta, exp_table);
         log_circuit_info(meta, "exp circuit");
 
+        let float_circuit = FloatCircuitConfig::new(meta, float_table);
+        log_circuit_info(meta, "float circuit");
+
         let evm_circuit = EvmCircuitConfig::new(
             meta,
             EvmCircuitConfigArgs {
@@ -309,6 +322,8 @@ impl<F: Field> SubCircuitConfig<F> for SuperCircuitConfig<F> {
                 copy_table,
                 keccak_table,
                 exp_table,
+                float_table,
+                wasm_params,
             },
         );
         log_circuit_info(meta, "evm circuit");
@@ -334,6 +349,7 @@ impl<F: Field> SubCircuitConfig<F> for SuperCircuitConfig<F> {
             rlp_circuit,
             tx_circuit,
             exp_circuit,
+            float_circuit,
             #[cfg(feature = "zktrie")]
             mpt_circuit,
         }
@@ -348,6 +364,7 @@ pub struct SuperCircuit<
     const MAX_CALLDATA: usize,
     const MAX_INNER_BLOCKS: usize,
     const MOCK_RANDOMNESS: u64,
+    const MAX_WASM_STACK_DEPTH: usize = WASM_MAX_STACK_DEPTH,
 > {
     /// EVM Circuit
     pub evm_circuit: EvmCircuit<F>,
@@ -363,6 +380,8 @@ pub struct SuperCircuit<
     pub copy_circuit: CopyCircuit<F>,
     /// Exp Circuit
     pub exp_circuit: ExpCircuit<F>,
+    /// Float Circuit
+    pub float_circuit: FloatCircuit<F>,
     /// Keccak Circuit
     pub keccak_circuit: KeccakCircuit<F>,
     /// Poseidon hash Circuit
@@ -380,7 +399,9 @@ impl<
         const MAX_CALLDATA: usize,
         const MAX_INNER_BLOCKS: usize,
         const MOCK_RANDOMNESS: u64,
-    > SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS>
+        const MAX_WASM_STACK_DEPTH: usize,
+    >
+    SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS, MAX_WASM_STACK_DEPTH>
 {
     /// Return the number of rows required to verify a given block
     pub fn get_num_rows_required(block: &Block<F>) -> usize {
@@ -400,6 +421,7 @@ impl<
         let tx = TxCircuit::min_num_rows_block(block);
         let rlp = RlpCircuit::min_num_rows_block(block);
         let exp = ExpCircuit::min_num_rows_block(block);
+        let float = FloatCircuit::min_num_rows_block(block);
         let pi = PiCircuit::min_num_rows_block(block);
         let poseidon = (0, 0); //PoseidonCircuit::min_num_rows_block(block);
         #[cfg(feature = "zktrie")]
@@ -414,6 +436,7 @@ impl<
             tx,
             rlp,
             exp,
+            float,
             pi,
             poseidon,
             #[cfg(feature = "zktrie")]
@@ -439,7 +462,16 @@ impl<
         const MAX_CALLDATA: usize,
         const MAX_INNER_BLOCKS: usize,
         const MOCK_RANDOMNESS: u64,
-    > SubCircuit<F> for SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS>
+        const MAX_WASM_STACK_DEPTH: usize,
+    > SubCircuit<F>
+    for SuperCircuit<
+        F,
+        MAX_TXS,
+        MAX_CALLDATA,
+        MAX_INNER_BLOCKS,
+        MOCK_RANDOMNESS,
+        MAX_WASM_STACK_DEPTH,
+    >
 {
     type Config = SuperCircuitConfig<F>;
 
@@ -452,6 +484,7 @@ impl<
             BytecodeCircuit::<F>::unusable_rows(),
             CopyCircuit::<F>::unusable_rows(),
             ExpCircuit::<F>::unusable_rows(),
+            FloatCircuit::<F>::unusable_rows(),
             KeccakCircuit::<F>::unusable_rows(),
         ])
         .unwrap()
@@ -465,12 +498,20 @@ impl<
         let bytecode_circuit = BytecodeCircuit::new_from_block(block);
         let copy_circuit = CopyCircuit::new_from_block_no_external(block);
         let exp_circuit = ExpCircuit::new_from_block(block);
+        let float_circuit = FloatCircuit::new_from_block(block);
         let keccak_circuit = KeccakCircuit::new_from_block(block);
         let poseidon_circuit = PoseidonCircuit::new_from_block(block);
         let rlp_circuit = RlpCircuit::new_from_block(block);
         #[cfg(feature = "zktrie")]
         let mpt_circuit = MptCircuit::new_from_block(block);
-        SuperCircuit::<_, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS> {
+        SuperCircuit::<
+            _,
+            MAX_TXS,
+            MAX_CALLDATA,
+            MAX_INNER_BLOCKS,
+            MOCK_RANDOMNESS,
+            MAX_WASM_STACK_DEPTH,
+        > {
             evm_circuit,
             state_circuit,
             tx_circuit,
@@ -478,6 +519,7 @@ impl<
             bytecode_circuit,
             copy_circuit,
             exp_circuit,
+            float_circuit,
             keccak_circuit,
             poseidon_circuit,
             rlp_circuit,
@@ -496,6 +538,7 @@ impl<
         instance.extend_from_slice(&self.copy_circuit.instance());
         instance.extend_from_slice(&self.state_circuit.instance());
         instance.extend_from_slice(&self.exp_circuit.instance());
+        instance.extend_from_slice(&self.float_circuit.instance());
         instance.extend_from_slice(&self.evm_circuit.instance());
 
         instance
@@ -531,6 +574,8 @@ impl<
             .synthesize_sub(&config.copy_circuit, challenges, layouter)?;
         self.exp_circuit
             .synthesize_sub(&config.exp_circuit, challenges, layouter)?;
+        self.float_circuit
+            .synthesize_sub(&config.float_circuit, challenges, layouter)?;
         self.evm_circuit
             .synthesize_sub(&config.evm_circuit, challenges, layouter)?;
 
@@ -562,7 +607,16 @@ impl<
         const MAX_CALLDATA: usize,
         const MAX_INNER_BLOCKS: usize,
         const MOCK_RANDOMNESS: u64,
-    > Circuit<F> for SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS>
+        const MAX_WASM_STACK_DEPTH: usize,
+    > Circuit<F>
+    for SuperCircuit<
+        F,
+        MAX_TXS,
+        MAX_CALLDATA,
+        MAX_INNER_BLOCKS,
+        MOCK_RANDOMNESS,
+        MAX_WASM_STACK_DEPTH,
+    >
 {
     type Config = (SuperCircuitConfig<F>, Challenges);
     type FloorPlanner = SimpleFloorPlanner;
@@ -582,6 +636,10 @@ impl<
                     max_calldata: MAX_CALLDATA,
                     max_inner_blocks: MAX_INNER_BLOCKS,
                     mock_randomness: MOCK_RANDOMNESS,
+                    wasm_params: WasmExecutionParams {
+                        max_stack_depth: MAX_WASM_STACK_DEPTH,
+                        ..Default::default()
+                    },
                     challenges: challenge_exprs,
                 },
             ),
@@ -630,7 +688,16 @@ impl<
         const MAX_CALLDATA: usize,
         const MAX_INNER_BLOCKS: usize,
         const MOCK_RANDOMNESS: u64,
-    > CircuitExt<F> for SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS>
+        const MAX_WASM_STACK_DEPTH: usize,
+    > CircuitExt<F>
+    for SuperCircuit<
+        F,
+        MAX_TXS,
+        MAX_CALLDATA,
+        MAX_INNER_BLOCKS,
+        MOCK_RANDOMNESS,
+        MAX_WASM_STACK_DEPTH,
+    >
 {
     fn num_instance(&self) -> Vec<usize> {
         self.instances().iter().map(|l| l.len()).collect_vec()
@@ -647,7 +714,9 @@ impl<
         const MAX_CALLDATA: usize,
         const MAX_INNER_BLOCKS: usize,
         const MOCK_RANDOMNESS: u64,
-    > SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS>
+        const MAX_WASM_STACK_DEPTH: usize,
+    >
+    SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, MOCK_RANDOMNESS, MAX_WASM_STACK_DEPTH>
 {
     /// From the witness data, generate a SuperCircuit instance with all of the
     /// sub-circuits filled with their corresponding witnesses.
@@ -683,6 +752,10 @@ impl<
         block.randomness = F::from(MOCK_RANDOMNESS);
         assert_eq!(block.circuits_params.max_txs, MAX_TXS);
         assert_eq!(block.circuits_params.max_calldata, MAX_CALLDATA);
+        assert_eq!(
+            block.circuits_params.max_wasm_stack_depth,
+            MAX_WASM_STACK_DEPTH
+        );
         Self::build_from_witness_block(block)
     }
     /// ..
@@ -698,8 +771,14 @@ impl<
         let k = log2_ceil(Self::unusable_rows() + rows_needed);
         log::debug!("super circuit needs k = {}", k);
 
-        let circuit =
-            SuperCircuit::<_, MAX_TXS, MAX_CALLDATA,MAX_INNER_BLOCKS,  MOCK_RANDOMNESS>::new_from_block(&block);
+        let circuit = SuperCircuit::<
+            _,
+            MAX_TXS,
+            MAX_CALLDATA,
+            MAX_INNER_BLOCKS,
+            MOCK_RANDOMNESS,
+            MAX_WASM_STACK_DEPTH,
+        >::new_from_block(&block);
 
         let instance = circuit.instance();
         Ok((k, circuit, instance))
@@ -1,5 +1,5 @@
 pub use super::*;
-use bus_mapping::evm::OpcodeId;
+use bus_mapping::{evm::OpcodeId, wasm::gas::DEFAULT_WASM_GAS_MODEL};
 use ethers_signers::{LocalWallet, Signer};
 use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
 use log::error;
@@ -8,7 +8,9 @@ use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use std::{collections::HashMap, env::set_var};
 
-use eth_types::{address, bytecode, geth_types::GethData, Bytecode, Word};
+use eth_types::{
+    address, bytecode, evm_types::WASM_MAX_STACK_DEPTH, geth_types::GethData, Bytecode, Word,
+};
 
 #[test]
 fn super_circuit_degree() {
@@ -207,6 +209,8 @@ fn serial_test_super_circuit_1tx_1max_tx() {
         max_keccak_rows: 0,
         max_inner_blocks: MAX_INNER_BLOCKS,
         max_rlp_rows: 500,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
     test_super_circuit::<MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, TEST_MOCK_RANDOMNESS>(
         block,
@@ -236,6 +240,8 @@ fn serial_test_super_circuit_1tx_deploy_2max_tx() {
         max_exp_steps: 256,
         max_evm_rows: 0,
         max_rlp_rows: 500,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
     test_super_circuit::<MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, TEST_MOCK_RANDOMNESS>(
         block,
@@ -263,6 +269,8 @@ fn serial_test_super_circuit_1tx_2max_tx() {
         max_keccak_rows: 0,
         max_inner_blocks: MAX_INNER_BLOCKS,
         max_rlp_rows: 500,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
     test_super_circuit::<MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, TEST_MOCK_RANDOMNESS>(
         block,
@@ -292,6 +300,8 @@ fn serial_test_super_circuit_2tx_4max_tx() {
         max_exp_steps: 256,
         max_evm_rows: 0,
         max_rlp_rows: 800,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
     test_super_circuit::<MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, TEST_MOCK_RANDOMNESS>(
         block,
@@ -319,6 +329,8 @@ fn serial_test_super_circuit_2tx_2max_tx() {
         max_keccak_rows: 0,
         max_inner_blocks: MAX_INNER_BLOCKS,
         max_rlp_rows: 500,
+        max_wasm_stack_depth: WASM_MAX_STACK_DEPTH,
+        wasm_gas_model: &DEFAULT_WASM_GAS_MODEL,
     };
     test_super_circuit::<MAX_TXS, MAX_CALLDATA, MAX_INNER_BLOCKS, TEST_MOCK_RANDOMNESS>(
         block,
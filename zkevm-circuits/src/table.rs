@@ -16,7 +16,10 @@ use halo2_proofs::{
 use itertools::Itertools;
 use strum_macros::{EnumCount, EnumIter};
 
-use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, ExpEvent};
+use bus_mapping::{
+    circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, ExpEvent},
+    evm::OpcodeId,
+};
 use eth_types::{Field, ToLittleEndian, ToScalar, ToWord, Word, U256};
 use gadgets::{
     binary_number::{BinaryNumberChip, BinaryNumberConfig},
@@ -30,6 +33,16 @@ use crate::{
     exp_circuit::param::{OFFSET_INCREMENT, ROWS_PER_STEP},
     impl_expr,
     util::{build_tx_log_address, Challenges},
+    wasm_circuit::{
+        consts::WASM_MEMORY_MAX_PAGES,
+        host_interface::helpers::{
+            extract_branch_targets, extract_control_instruction_args, extract_data_segments,
+            extract_func_exports, extract_func_type_indexes, extract_func_types,
+            extract_global_mutabilities, extract_memory_limits, extract_table_elem_func_indexes,
+        },
+        sections::export::body::circuit::export_name_key_u64,
+        types::{ControlInstruction, Mutability},
+    },
     witness::{
         Block, BlockContext, BlockContexts, Bytecode, MptUpdateRow, MptUpdates, RlpFsmWitnessGen,
         Rw, RwMap, RwRow, Transaction,
@@ -1062,11 +1075,7 @@ impl PoseidonTable {
         inputs: impl IntoIterator<Item = &'a Vec<u8>> + Clone,
         assign_delta: usize,
     ) -> Result<(), Error> {
-        use crate::bytecode_circuit::bytecode_unroller::{
-            unroll_to_hash_input_default, HASHBLOCK_BYTES_IN_FIELD,
-        };
         use bus_mapping::state_db::CodeDB;
-        use mpt_zktrie::hash::HASHABLE_DOMAIN_SPEC;
 
         // layouter.assign_region(
         //     || "poseidon table",
@@ -1106,65 +1115,543 @@ impl PoseidonTable {
         offset += 1;
 
         for input in inputs.clone() {
-            let mut control_len = input.len();
-            let mut first_row = true;
-            let ref_hash = Value::known(
-                CodeDB::hash(input.as_slice())
-                    .to_word()
-                    .to_scalar()
-                    .unwrap(),
-            );
-            for row in unroll_to_hash_input_default::<F>(input.iter().copied()) {
-                assert_ne!(
-                    control_len,
-                    0,
-                    "must have enough len left (original size {})",
-                    input.len()
-                );
-                let block_size = HASHBLOCK_BYTES_IN_FIELD * row.len();
-                let control_len_as_flag = F::from_u128(HASHABLE_DOMAIN_SPEC * control_len as u128);
-
+            for row in Self::code_hash_rows::<F>(input) {
                 region.assign_fixed(
                     || format!("poseidon table row {}", offset),
                     self.q_enable,
                     offset,
                     || Value::known(F::one()),
                 )?;
-                for (column, value) in poseidon_table_columns.iter().zip_eq(
-                    once(ref_hash)
-                        .chain(row.map(Value::known))
-                        .chain(once(Value::known(control_len_as_flag)))
-                        .chain(once(Value::known(if first_row {
-                            F::one()
-                        } else {
-                            F::zero()
-                        }))),
-                ) {
+                for (column, value) in poseidon_table_columns.iter().zip_eq(row) {
                     region.assign_advice(
                         || format!("poseidon table row {}", offset),
                         *column,
                         offset,
-                        || value,
+                        || Value::known(value),
                     )?;
                 }
-                first_row = false;
                 offset += 1;
-                control_len = if control_len > block_size {
-                    control_len - block_size
-                } else {
-                    0
-                };
             }
-            assert_eq!(
+        }
+        Ok(())
+        // },
+        // )
+    }
+
+    /// Rows `[hash_id, input0, input1, control, heading_mark]` the poseidon circuit lays out
+    /// when hashing `input` as a code hash.
+    pub(crate) fn code_hash_rows<F: Field>(input: &[u8]) -> Vec<[F; 5]> {
+        use crate::bytecode_circuit::bytecode_unroller::{
+            unroll_to_hash_input_default, HASHBLOCK_BYTES_IN_FIELD,
+        };
+        use bus_mapping::state_db::CodeDB;
+        use mpt_zktrie::hash::HASHABLE_DOMAIN_SPEC;
+
+        let mut rows = vec![];
+        let mut control_len = input.len();
+        let mut first_row = true;
+        let ref_hash: F = CodeDB::hash(input).to_word().to_scalar().unwrap();
+        for row in unroll_to_hash_input_default::<F>(input.iter().copied()) {
+            assert_ne!(
                 control_len,
                 0,
-                "should have exhaust all bytes (original size {})",
+                "must have enough len left (original size {})",
                 input.len()
             );
+            let block_size = HASHBLOCK_BYTES_IN_FIELD * row.len();
+            let control_len_as_flag = F::from_u128(HASHABLE_DOMAIN_SPEC * control_len as u128);
+            let [input0, input1] = row;
+            rows.push([
+                ref_hash,
+                input0,
+                input1,
+                control_len_as_flag,
+                if first_row { F::one() } else { F::zero() },
+            ]);
+            first_row = false;
+            control_len = if control_len > block_size {
+                control_len - block_size
+            } else {
+                0
+            };
+        }
+        assert_eq!(
+            control_len,
+            0,
+            "should have exhaust all bytes (original size {})",
+            input.len()
+        );
+        rows
+    }
+
+    /// Loads the code hash rows of `inputs` for a table built by `construct`, i.e. with the layout
+    /// of the poseidon circuit: an all-zero row followed by the hash rows of every input.
+    ///
+    /// Unlike `dev_load2` there is no extra row for the nil code hash, and `hash_id` is a second
+    /// phase column, so only the rows the poseidon circuit itself would produce are assigned.
+    pub fn load_code_hashes_with_region<'a, F: Field>(
+        &self,
+        region: &mut Region<F>,
+        inputs: impl IntoIterator<Item = &'a Vec<u8>>,
+        assign_delta: usize,
+    ) -> Result<(), Error> {
+        self.assign(
+            region,
+            assign_delta,
+            [Value::known(F::zero()); 5].as_slice(),
+        )?;
+        let mut offset = assign_delta + 1;
+        for input in inputs {
+            for row in Self::code_hash_rows::<F>(input) {
+                self.assign(region, offset, row.map(Value::known).as_slice())?;
+                offset += 1;
+            }
         }
         Ok(())
-        // },
-        // )
+    }
+}
+
+/// Tag to identify the floating-point operation in a FloatTable row
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum FloatOpTag {
+    /// f32.add
+    F32Add = 1,
+    /// f32.sub
+    F32Sub,
+    /// f32.mul
+    F32Mul,
+    /// f32.div
+    F32Div,
+    /// f64.add
+    F64Add,
+    /// f64.sub
+    F64Sub,
+    /// f64.mul
+    F64Mul,
+    /// f64.div
+    F64Div,
+}
+impl_expr!(FloatOpTag);
+
+impl FloatOpTag {
+    /// Returns the tag of the floating-point opcode, if any
+    pub fn from_opcode(opcode: OpcodeId) -> Option<Self> {
+        match opcode {
+            OpcodeId::F32Add => Some(Self::F32Add),
+            OpcodeId::F32Sub => Some(Self::F32Sub),
+            OpcodeId::F32Mul => Some(Self::F32Mul),
+            OpcodeId::F32Div => Some(Self::F32Div),
+            OpcodeId::F64Add => Some(Self::F64Add),
+            OpcodeId::F64Sub => Some(Self::F64Sub),
+            OpcodeId::F64Mul => Some(Self::F64Mul),
+            OpcodeId::F64Div => Some(Self::F64Div),
+            _ => None,
+        }
+    }
+
+    /// Returns the bits of the IEEE-754 (round to nearest, ties to even) result of the
+    /// operation applied to the operand bits
+    pub fn eval(&self, lhs: u64, rhs: u64) -> u64 {
+        let (lhs32, rhs32) = (f32::from_bits(lhs as u32), f32::from_bits(rhs as u32));
+        let (lhs64, rhs64) = (f64::from_bits(lhs), f64::from_bits(rhs));
+        match self {
+            Self::F32Add => (lhs32 + rhs32).to_bits() as u64,
+            Self::F32Sub => (lhs32 - rhs32).to_bits() as u64,
+            Self::F32Mul => (lhs32 * rhs32).to_bits() as u64,
+            Self::F32Div => (lhs32 / rhs32).to_bits() as u64,
+            Self::F64Add => (lhs64 + rhs64).to_bits(),
+            Self::F64Sub => (lhs64 - rhs64).to_bits(),
+            Self::F64Mul => (lhs64 * rhs64).to_bits(),
+            Self::F64Div => (lhs64 / rhs64).to_bits(),
+        }
+    }
+}
+
+/// The Float table, contains the results of the floating-point operations executed in the block,
+/// keyed on (op, lhs_bits, rhs_bits).
+/// the 4 cols represent [op, lhs, rhs, res]
+#[derive(Clone, Copy, Debug)]
+pub struct FloatTable {
+    /// Is Enabled
+    pub q_enable: Column<Fixed>,
+    /// Operation tag
+    pub op: Column<Advice>,
+    /// Bits of the left operand
+    pub lhs: Column<Advice>,
+    /// Bits of the right operand
+    pub rhs: Column<Advice>,
+    /// Bits of the result
+    pub res: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for FloatTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![
+            self.q_enable.into(),
+            self.op.into(),
+            self.lhs.into(),
+            self.rhs.into(),
+            self.res.into(),
+        ]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        vec![
+            String::from("q_enable"),
+            String::from("op"),
+            String::from("lhs"),
+            String::from("rhs"),
+            String::from("res"),
+        ]
+    }
+}
+
+impl FloatTable {
+    /// Construct a new FloatTable
+    pub(crate) fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            op: meta.advice_column(),
+            lhs: meta.advice_column(),
+            rhs: meta.advice_column(),
+            res: meta.advice_column(),
+        }
+    }
+
+    pub(crate) fn assign<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 4],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || "assign float table row value",
+            self.q_enable,
+            offset,
+            || Value::known(F::one()),
+        )?;
+        let float_table_columns = <FloatTable as LookupTable<F>>::advice_columns(self);
+        for (column, value) in float_table_columns.iter().zip_eq(row) {
+            region.assign_advice(
+                || "assign float table row value",
+                *column,
+                offset,
+                || Value::known(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the (op, lhs, rhs, res) floating-point operations of the block, the result is
+    /// computed natively from the operands and constrained by the float circuit
+    pub fn float_ops<F: Field>(block: &Block<F>) -> Vec<(FloatOpTag, u64, u64, u64)> {
+        block
+            .txs
+            .iter()
+            .flat_map(|tx| tx.steps.iter())
+            .filter_map(|step| {
+                let op = FloatOpTag::from_opcode(step.opcode?)?;
+                let [rhs, lhs] = [step.rw_indices[0], step.rw_indices[1]]
+                    .map(|idx| block.rws[idx].stack_value().as_u64());
+                Some((op, lhs, rhs, op.eval(lhs, rhs)))
+            })
+            .collect()
+    }
+
+    /// Returns the (op, lhs, rhs, res) rows of all the floating-point operations of the block
+    pub fn assignments<F: Field>(block: &Block<F>) -> Vec<[F; 4]> {
+        Self::float_ops(block)
+            .into_iter()
+            .map(|(op, lhs, rhs, res)| [op as u64, lhs, rhs, res].map(F::from))
+            .collect()
+    }
+
+    /// Assign witness data from a block to the float table
+    pub fn dev_load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "float table",
+            |mut region| {
+                self.assign(&mut region, 0, [F::zero(); 4])?;
+                for (offset, row) in Self::assignments(block).into_iter().enumerate() {
+                    self.assign(&mut region, offset + 1, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Tag to identify the module property held by a WasmMetadataTable row
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum WasmMetadataTag {
+    /// Min pages of a memory declared by the memory section
+    MemoryMinPages = 1,
+    /// Max pages of a memory declared by the memory section, `WASM_MEMORY_MAX_PAGES` if the
+    /// memory declares no max
+    MemoryMaxPages,
+    /// Program counter a taken `br`/`br_if` leaving a block jumps to, indexed by the program
+    /// counter of the branch (see `extract_branch_targets`)
+    BranchTarget,
+    /// Type index immediate of a `call_indirect`, indexed by its program counter (see
+    /// `extract_control_instruction_args`)
+    CallIndirectTypeIndex,
+    /// Function index of a table element, indexed by the element index (the element segments
+    /// concatenated in declaration order)
+    TableElemFunc,
+    /// Count of the table elements, indexed by 0. Modules without element section have no row
+    TableElemFuncsCount,
+    /// Type index of a function, indexed by the function index (imported functions come first)
+    FuncTypeIndex,
+    /// Function index immediate of a `call`, indexed by its program counter
+    CallFuncIndex,
+    /// Params count of a function type, indexed by the type index
+    TypeParamsCount,
+    /// Results count of a function type, indexed by the type index
+    TypeResultsCount,
+    /// Length of a data segment, indexed by the data index. Active segments are dropped once the
+    /// module is instantiated, so their length is 0
+    DataSegmentLen,
+    /// Offset of the first byte of a non-empty data segment in the bytecode, indexed by the data
+    /// index
+    DataSegmentOffset,
+    /// Mutability of a global, 1 if it is `var`, indexed by the global index (imported globals
+    /// come first)
+    GlobalMutability,
+    /// Index of an exported function, indexed by the key of the export name (see
+    /// `export_name_key`)
+    ExportFuncIndex,
+}
+impl_expr!(WasmMetadataTag);
+
+/// The WASM metadata table, contains the properties of the modules executed in the block the
+/// EVM circuit can't read from the bytecode itself (memory limits, etc.), keyed on
+/// (code_hash, tag, index). Every row is bound to the section of the module it comes from by
+/// `WasmChip::configure_metadata_table`.
+/// the 4 cols represent [code_hash, tag, index, value]
+#[derive(Clone, Copy, Debug)]
+pub struct WasmMetadataTable {
+    /// Is Enabled
+    pub q_enable: Column<Fixed>,
+    /// Code hash of the module
+    pub code_hash: Column<Advice>,
+    /// Tag of the property
+    pub tag: Column<Advice>,
+    /// Index of the item the property belongs to, e.g. the index of the memory in the memory
+    /// section
+    pub index: Column<Advice>,
+    /// Value of the property
+    pub value: Column<Advice>,
+}
+
+impl<F: Field> LookupTable<F> for WasmMetadataTable {
+    fn columns(&self) -> Vec<Column<Any>> {
+        vec![
+            self.q_enable.into(),
+            self.code_hash.into(),
+            self.tag.into(),
+            self.index.into(),
+            self.value.into(),
+        ]
+    }
+
+    fn annotations(&self) -> Vec<String> {
+        vec![
+            String::from("q_enable"),
+            String::from("code_hash"),
+            String::from("tag"),
+            String::from("index"),
+            String::from("value"),
+        ]
+    }
+}
+
+impl WasmMetadataTable {
+    /// Construct a new WasmMetadataTable
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            code_hash: meta.advice_column(),
+            tag: meta.advice_column(),
+            index: meta.advice_column(),
+            value: meta.advice_column(),
+        }
+    }
+
+    pub(crate) fn assign<F: Field>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [Value<F>; 4],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || "assign wasm metadata table row value",
+            self.q_enable,
+            offset,
+            || Value::known(F::one()),
+        )?;
+        let wasm_metadata_table_columns =
+            <WasmMetadataTable as LookupTable<F>>::advice_columns(self);
+        for (column, value) in wasm_metadata_table_columns.iter().zip_eq(row) {
+            region.assign_advice(
+                || "assign wasm metadata table row value",
+                *column,
+                offset,
+                || value,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the (tag, index, value) rows of a module, bytes that don't parse as a module have
+    /// no rows
+    pub fn module_rows(bytes: &[u8]) -> Vec<(WasmMetadataTag, u64, u64)> {
+        let mut rows = vec![];
+        if let Ok(Some((min_pages, max_pages))) = extract_memory_limits(bytes) {
+            rows.push((WasmMetadataTag::MemoryMinPages, 0, min_pages));
+            rows.push((
+                WasmMetadataTag::MemoryMaxPages,
+                0,
+                max_pages.unwrap_or(WASM_MEMORY_MAX_PAGES),
+            ));
+        }
+        if let Ok(branch_targets) = extract_branch_targets(bytes) {
+            rows.extend(
+                branch_targets
+                    .into_iter()
+                    .map(|(pc, target)| (WasmMetadataTag::BranchTarget, pc, target)),
+            );
+        }
+        if let Ok(func_indexes) = extract_control_instruction_args(bytes, ControlInstruction::Call)
+        {
+            rows.extend(
+                func_indexes
+                    .into_iter()
+                    .map(|(pc, func_index)| (WasmMetadataTag::CallFuncIndex, pc, func_index)),
+            );
+        }
+        if let Ok(type_indexes) =
+            extract_control_instruction_args(bytes, ControlInstruction::CallIndirect)
+        {
+            rows.extend(
+                type_indexes.into_iter().map(|(pc, type_index)| {
+                    (WasmMetadataTag::CallIndirectTypeIndex, pc, type_index)
+                }),
+            );
+        }
+        if let Ok(func_indexes) = extract_table_elem_func_indexes(bytes) {
+            if !func_indexes.is_empty() {
+                rows.push((
+                    WasmMetadataTag::TableElemFuncsCount,
+                    0,
+                    func_indexes.len() as u64,
+                ));
+            }
+            rows.extend(
+                func_indexes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, func_index)| (WasmMetadataTag::TableElemFunc, i as u64, func_index)),
+            );
+        }
+        if let Ok(type_indexes) = extract_func_type_indexes(bytes) {
+            rows.extend(
+                type_indexes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, type_index)| (WasmMetadataTag::FuncTypeIndex, i as u64, type_index)),
+            );
+        }
+        if let Ok(func_types) = extract_func_types(bytes) {
+            for (i, func_type) in func_types.iter().enumerate() {
+                rows.push((
+                    WasmMetadataTag::TypeParamsCount,
+                    i as u64,
+                    func_type.params.len() as u64,
+                ));
+                rows.push((
+                    WasmMetadataTag::TypeResultsCount,
+                    i as u64,
+                    func_type.results.len() as u64,
+                ));
+            }
+        }
+        if let Ok(segments) = extract_data_segments(bytes) {
+            for (i, segment) in segments.iter().enumerate() {
+                let len = if segment.is_passive { segment.len } else { 0 };
+                rows.push((WasmMetadataTag::DataSegmentLen, i as u64, len as u64));
+                if segment.len > 0 {
+                    rows.push((
+                        WasmMetadataTag::DataSegmentOffset,
+                        i as u64,
+                        segment.bytes_offset as u64,
+                    ));
+                }
+            }
+        }
+        if let Ok(mutabilities) = extract_global_mutabilities(bytes) {
+            rows.extend(mutabilities.into_iter().enumerate().map(|(i, mutability)| {
+                (
+                    WasmMetadataTag::GlobalMutability,
+                    i as u64,
+                    (mutability == Mutability::Var) as u64,
+                )
+            }));
+        }
+        // the EVM circuit only looks up short names (`main`), the keys of the long ones don't fit
+        if let Ok(func_exports) = extract_func_exports(bytes) {
+            rows.extend(func_exports.into_iter().filter_map(|(name, func_index)| {
+                let name_key = export_name_key_u64(name.as_bytes())?;
+                Some((WasmMetadataTag::ExportFuncIndex, name_key, func_index))
+            }));
+        }
+        rows
+    }
+
+    /// Returns the (code_hash, tag, index, value) rows of all the modules of the block
+    pub fn assignments<F: Field>(
+        block: &Block<F>,
+        challenges: &Challenges<Value<F>>,
+    ) -> Vec<[Value<F>; 4]> {
+        block
+            .bytecodes
+            .values()
+            .flat_map(|bytecode| {
+                let code_hash = bytecode.hash_value(challenges);
+                Self::module_rows(&bytecode.bytes)
+                    .into_iter()
+                    .map(move |(tag, index, value)| {
+                        [
+                            code_hash,
+                            Value::known(F::from(tag as u64)),
+                            Value::known(F::from(index)),
+                            Value::known(F::from(value)),
+                        ]
+                    })
+            })
+            .collect()
+    }
+
+    /// Assign witness data from a block to the wasm metadata table
+    pub fn dev_load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "wasm metadata table",
+            |mut region| {
+                self.assign(&mut region, 0, [Value::known(F::zero()); 4])?;
+                for (offset, row) in Self::assignments(block, challenges).into_iter().enumerate() {
+                    self.assign(&mut region, offset + 1, row)?;
+                }
+                Ok(())
+            },
+        )
     }
 }
 
@@ -186,18 +186,20 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
 
 impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
 
-    /// TODO
-    pub fn must_fail_run(self) {
+    /// Converts the [`TestContext`] if any into a [`Block`] and applies the block_modifiers,
+    /// returns the params the block is built with too
+    fn build_block(&mut self) -> (CircuitsParams, Block<Fr>) {
         let params = if let Some(block) = self.block.as_ref() {
             block.circuits_params
         } else {
             self.circuits_params.unwrap_or_default()
         };
+        log::debug!("params in CircuitTestBuilder: {:?}", params);
 
-        let block: Block<Fr> = if self.block.is_some() {
-            self.block.unwrap()
-        } else if self.test_ctx.is_some() {
-            let block: GethData = self.test_ctx.unwrap().into();
+        let block: Block<Fr> = if let Some(block) = self.block.take() {
+            block
+        } else if let Some(test_ctx) = self.test_ctx.take() {
+            let block: GethData = test_ctx.into();
             let mut builder = BlockData::new_from_geth_data_with_params(block.clone(), params)
                 .new_circuit_input_builder();
             builder
@@ -207,7 +209,7 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
             let mut block =
                 crate::witness::block_convert(&builder.block, &builder.code_db).unwrap();
 
-            for modifier_fn in self.block_modifiers {
+            for modifier_fn in &self.block_modifiers {
                 modifier_fn.as_ref()(&mut block);
             }
             block
@@ -215,6 +217,39 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
             panic!("No attribute to build a block was passed to the CircuitTestBuilder")
         };
 
+        (params, block)
+    }
+
+    /// Runs the checks of the EVM circuit only, the circuit is configured with the
+    /// `WasmExecutionParams` of [`crate::evm_circuit::EvmCircuitWithWasmParams`]
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn run_evm_with_wasm_params<
+        const MAX_STACK_DEPTH: usize,
+        const IS_LOOKUP_INPUT_DETERMINISTIC: bool,
+    >(
+        mut self,
+    ) {
+        let (params, block) = self.build_block();
+        assert_eq!(
+            params.max_wasm_stack_depth, MAX_STACK_DEPTH,
+            "the block must be built with the stack depth the circuit is configured with"
+        );
+        let k = block.get_test_degree();
+        let (active_gate_rows, active_lookup_rows) = EvmCircuit::<Fr>::get_active_rows(&block);
+
+        let circuit =
+            crate::evm_circuit::EvmCircuitWithWasmParams::<Fr, MAX_STACK_DEPTH, IS_LOOKUP_INPUT_DETERMINISTIC>(
+                EvmCircuit::get_test_cicuit_from_block(block),
+            );
+        let prover = MockProver::<Fr>::run(k, &circuit, vec![]).unwrap();
+
+        self.evm_checks.as_ref()(prover, &active_gate_rows, &active_lookup_rows)
+    }
+
+    /// TODO
+    pub fn must_fail_run(mut self) {
+        let (params, block) = self.build_block();
+
         let mut error_appear = false;
 
         // Run evm circuit test
@@ -257,34 +292,8 @@ impl<const NACC: usize, const NTX: usize> CircuitTestBuilder<NACC, NTX> {
     /// Triggers the `CircuitTestBuilder` to convert the [`TestContext`] if any,
     /// into a [`Block`] and apply the default or provided block_modifiers or
     /// circuit checks to the provers generated for the State and EVM circuits.
-    pub fn run(self) {
-        let params = if let Some(block) = self.block.as_ref() {
-            block.circuits_params
-        } else {
-            self.circuits_params.unwrap_or_default()
-        };
-        log::debug!("params in CircuitTestBuilder: {:?}", params);
-
-        let block: Block<Fr> = if self.block.is_some() {
-            self.block.unwrap()
-        } else if self.test_ctx.is_some() {
-            let block: GethData = self.test_ctx.unwrap().into();
-            let mut builder = BlockData::new_from_geth_data_with_params(block.clone(), params)
-                .new_circuit_input_builder();
-            builder
-                .handle_block(&block.eth_block, &block.geth_traces)
-                .unwrap();
-            // Build a witness block from trace result.
-            let mut block =
-                crate::witness::block_convert(&builder.block, &builder.code_db).unwrap();
-
-            for modifier_fn in self.block_modifiers {
-                modifier_fn.as_ref()(&mut block);
-            }
-            block
-        } else {
-            panic!("No attribute to build a block was passed to the CircuitTestBuilder")
-        };
+    pub fn run(mut self) {
+        let (params, block) = self.build_block();
 
         const NUM_BLINDING_ROWS: usize = 64;
         // Run evm circuit test
@@ -150,6 +150,14 @@ impl<T: Clone> Challenges<T> {
         [&self.evm_word, &self.keccak_input, &self.lookup_input]
     }
 
+    /// Returns the challenges with `lookup_input` replaced
+    pub fn with_lookup_input(self, lookup_input: T) -> Self {
+        Self {
+            lookup_input,
+            ..self
+        }
+    }
+
     /// ..
     pub fn mock(evm_word: T, keccak_input: T, lookup_input: T) -> Self {
         Self {
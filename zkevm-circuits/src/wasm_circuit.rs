@@ -14,4 +14,13 @@ pub mod sections;
 pub mod error;
 pub mod utf8;
 pub mod types;
+pub mod host_interface;
+pub mod instrumentation;
+pub mod parser;
+pub mod standalone;
+pub mod gas;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
 mod tests_helpers;
+#[cfg(any(feature = "test", test))]
+pub mod test_helpers;
@@ -1,2 +1,3 @@
 pub mod bytecode_table;
 pub mod bytecode;
+pub mod code_hash;
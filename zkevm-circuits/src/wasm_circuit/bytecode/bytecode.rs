@@ -3,6 +3,8 @@ use halo2_proofs::circuit::Value;
 use bus_mapping::state_db::CodeDB;
 use eth_types::{Field, ToScalar, ToWord, Word};
 
+use crate::wasm_circuit::error::Error;
+
 #[derive(Clone, Debug)]
 pub struct WasmBytecode {
     pub(crate) bytes: Vec<u8>,
@@ -18,6 +20,14 @@ impl WasmBytecode {
         }
     }
 
+    /// Construct from the WAT text format, the code hash is computed the same way as for raw bytes
+    #[cfg(any(test, feature = "wat"))]
+    pub fn from_wat(src: &str) -> Result<Self, Error> {
+        let bytes = wabt::wat2wasm(src)
+            .map_err(|e| Error::FatalInvalidArgumentValue(format!("invalid wat: {:?}", e)))?;
+        Ok(Self::new(bytes))
+    }
+
     /// Assignments for bytecode table
     pub fn table_assignments<F: Field>(&self) -> Vec<[Value<F>; 3]> {
         let n = 1 + self.bytes.len();
@@ -36,6 +46,14 @@ impl WasmBytecode {
     pub fn get(&self, idx: usize) -> Option<&u8> {
         self.bytes.get(idx)
     }
+
+    /// get byte value, fails with `UnexpectedEndOfBytes` if `offset` is past the last byte
+    pub fn byte_at(&self, offset: usize) -> Result<u8, Error> {
+        self.bytes
+            .get(offset)
+            .copied()
+            .ok_or(Error::UnexpectedEndOfBytes(offset))
+    }
 }
 
 impl From<&eth_types::bytecode::Bytecode> for WasmBytecode {
@@ -0,0 +1,444 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Chip, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use mpt_zktrie::hash::HASHABLE_DOMAIN_SPEC;
+
+use eth_types::Field;
+use gadgets::{
+    is_zero::{IsZeroChip, IsZeroInstruction},
+    util::{and, not, or, Expr},
+};
+
+use crate::{
+    bytecode_circuit::bytecode_unroller::HASHBLOCK_BYTES_IN_FIELD,
+    evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::PoseidonTable,
+    wasm_circuit::{
+        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        common::{annotate_region_columns, WasmColumnAnnotationAwareChip},
+        error::{remap_error, remap_error_to_assign_at, Error},
+        types::AssignDeltaType,
+    },
+};
+
+/// Binds the `code_hash` of the bytecode table to the poseidon table: the bytes of a bytecode are
+/// packed big endian into fields of `HASHBLOCK_BYTES_IN_FIELD` bytes, two fields per poseidon row
+/// (see `PoseidonTable::code_hash_rows`), and every field is looked up in the poseidon table
+/// together with the code hash, the control (bytes left from the poseidon row on) and the
+/// heading mark of its row.
+#[derive(Debug, Clone)]
+pub struct WasmCodeHashConfig<F: Field> {
+    /// byte rows of a bytecode
+    pub q_enable: Column<Fixed>,
+    pub q_first: Column<Fixed>,
+    pub q_last: Column<Fixed>,
+    /// position of the byte inside its field, `0..HASHBLOCK_BYTES_IN_FIELD`
+    pub byte_in_field_index: Column<Advice>,
+    /// position of the field inside its poseidon row, `0..PoseidonTable::INPUT_WIDTH`
+    pub field_index: Column<Advice>,
+    /// field value accumulated up to the byte, padded with zero bytes
+    pub field_input: Column<Advice>,
+    /// multiplier of the byte inside its field
+    pub padding_shift: Column<Advice>,
+    /// bytes left from the start of the poseidon row of the byte
+    pub control_len: Column<Advice>,
+    /// 1 if the byte belongs to the first poseidon row of the bytecode
+    pub heading_mark: Column<Advice>,
+    /// `byte_in_field_index` is the last position of the field
+    pub(crate) is_field_border_chip: IsZeroChip<F>,
+
+    _marker: PhantomData<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WasmCodeHashChip<F: Field> {
+    pub config: WasmCodeHashConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmCodeHashChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "code_hash",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                (
+                    "byte_in_field_index",
+                    self.config.byte_in_field_index.into(),
+                ),
+                ("field_index", self.config.field_index.into()),
+                ("field_input", self.config.field_input.into()),
+                ("padding_shift", self.config.padding_shift.into()),
+                ("control_len", self.config.control_len.into()),
+                ("heading_mark", self.config.heading_mark.into()),
+            ],
+        );
+    }
+}
+
+impl<F: Field> WasmCodeHashChip<F> {
+    pub fn construct(config: WasmCodeHashConfig<F>) -> Self {
+        let instance = Self {
+            config,
+            _marker: PhantomData,
+        };
+        instance
+    }
+
+    /// multiplier of the byte at `byte_in_field_index` of a field
+    fn padding_shift(byte_in_field_index: usize) -> F {
+        F::from(256).pow_vartime([(HASHBLOCK_BYTES_IN_FIELD - 1 - byte_in_field_index) as u64])
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        wb_table: &WasmBytecodeTable,
+        poseidon_table: &PoseidonTable,
+    ) -> WasmCodeHashConfig<F> {
+        debug_assert_eq!(PoseidonTable::INPUT_WIDTH, 2);
+
+        let q_enable = cs.fixed_column();
+        let q_first = cs.fixed_column();
+        let q_last = cs.fixed_column();
+        let byte_in_field_index = cs.advice_column();
+        let field_index = cs.advice_column();
+        let field_input = cs.advice_column();
+        let padding_shift = cs.advice_column();
+        let control_len = cs.advice_column();
+        let heading_mark = cs.advice_column();
+
+        let value_inv = cs.advice_column();
+        let is_field_border_config = IsZeroChip::configure(
+            cs,
+            |vc| vc.query_fixed(q_enable, Rotation::cur()),
+            |vc| {
+                vc.query_advice(byte_in_field_index, Rotation::cur())
+                    - (HASHBLOCK_BYTES_IN_FIELD - 1).expr()
+            },
+            value_inv,
+        );
+        let is_field_border_chip = IsZeroChip::construct(is_field_border_config);
+
+        cs.create_gate("code hash gate", |vc| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+            let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
+            let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+            let byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
+            let code_hash_expr = vc.query_advice(wb_table.code_hash, Rotation::cur());
+            let byte_in_field_index_expr = vc.query_advice(byte_in_field_index, Rotation::cur());
+            let field_index_expr = vc.query_advice(field_index, Rotation::cur());
+            let field_input_expr = vc.query_advice(field_input, Rotation::cur());
+            let padding_shift_expr = vc.query_advice(padding_shift, Rotation::cur());
+            let control_len_expr = vc.query_advice(control_len, Rotation::cur());
+            let heading_mark_expr = vc.query_advice(heading_mark, Rotation::cur());
+            let field_shift_expr = Expression::Constant(Self::padding_shift(0));
+
+            cb.require_boolean("q_first is bool", q_first_expr.clone());
+            cb.require_boolean("q_last is bool", q_last_expr.clone());
+            cb.require_boolean("field_index is bool", field_index_expr.clone());
+
+            cb.condition(q_first_expr.clone(), |cb| {
+                cb.require_zero(
+                    "q_first => byte_in_field_index=0",
+                    byte_in_field_index_expr.clone(),
+                );
+                cb.require_zero("q_first => field_index=0", field_index_expr.clone());
+                cb.require_equal(
+                    "q_first => padding_shift=field_shift",
+                    padding_shift_expr.clone(),
+                    field_shift_expr.clone(),
+                );
+                cb.require_equal(
+                    "q_first => field_input=byte_val*padding_shift",
+                    field_input_expr.clone(),
+                    byte_val_expr.clone() * padding_shift_expr.clone(),
+                );
+                cb.require_equal(
+                    "q_first => heading_mark=1",
+                    heading_mark_expr.clone(),
+                    1.expr(),
+                );
+            });
+
+            cb.condition(not::expr(q_first_expr.clone()), |cb| {
+                let code_hash_prev_expr = vc.query_advice(wb_table.code_hash, Rotation::prev());
+                let byte_in_field_index_prev_expr =
+                    vc.query_advice(byte_in_field_index, Rotation::prev());
+                let field_index_prev_expr = vc.query_advice(field_index, Rotation::prev());
+                let field_input_prev_expr = vc.query_advice(field_input, Rotation::prev());
+                let padding_shift_prev_expr = vc.query_advice(padding_shift, Rotation::prev());
+                let control_len_prev_expr = vc.query_advice(control_len, Rotation::prev());
+                let heading_mark_prev_expr = vc.query_advice(heading_mark, Rotation::prev());
+                // the is_zero expression of the chip is bound to the current row, rebuild it for
+                // the prev one
+                let value_inv_prev_expr =
+                    vc.query_advice(is_field_border_chip.config().value_inv, Rotation::prev());
+                let is_field_border_prev_expr = 1.expr()
+                    - (byte_in_field_index_prev_expr.clone()
+                        - (HASHBLOCK_BYTES_IN_FIELD - 1).expr())
+                        * value_inv_prev_expr;
+
+                cb.require_equal(
+                    "not_q_first => code_hash=prev.code_hash",
+                    code_hash_expr.clone(),
+                    code_hash_prev_expr,
+                );
+                cb.condition(is_field_border_prev_expr.clone(), |cb| {
+                    cb.require_zero(
+                        "prev.is_field_border => byte_in_field_index=0",
+                        byte_in_field_index_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "prev.is_field_border => field_index flips",
+                        field_index_expr.clone(),
+                        1.expr() - field_index_prev_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "prev.is_field_border => padding_shift=field_shift",
+                        padding_shift_expr.clone(),
+                        field_shift_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "prev.is_field_border => field_input=byte_val*padding_shift",
+                        field_input_expr.clone(),
+                        byte_val_expr.clone() * padding_shift_expr.clone(),
+                    );
+                    // the last field of a poseidon row was completed, the next row starts
+                    cb.require_equal(
+                        "prev.is_field_border => control_len decreased by the bytes of a row",
+                        control_len_expr.clone(),
+                        control_len_prev_expr.clone()
+                            - field_index_prev_expr.clone()
+                                * (HASHBLOCK_BYTES_IN_FIELD * PoseidonTable::INPUT_WIDTH).expr(),
+                    );
+                    cb.require_equal(
+                        "prev.is_field_border => heading_mark is cleared on a new row",
+                        heading_mark_expr.clone(),
+                        heading_mark_prev_expr.clone() * not::expr(field_index_prev_expr.clone()),
+                    );
+                });
+                cb.condition(not::expr(is_field_border_prev_expr), |cb| {
+                    cb.require_equal(
+                        "!prev.is_field_border => byte_in_field_index=prev.byte_in_field_index+1",
+                        byte_in_field_index_expr.clone(),
+                        byte_in_field_index_prev_expr + 1.expr(),
+                    );
+                    cb.require_equal(
+                        "!prev.is_field_border => field_index=prev.field_index",
+                        field_index_expr.clone(),
+                        field_index_prev_expr,
+                    );
+                    cb.require_equal(
+                        "!prev.is_field_border => padding_shift*256=prev.padding_shift",
+                        padding_shift_expr.clone() * 256.expr(),
+                        padding_shift_prev_expr,
+                    );
+                    cb.require_equal(
+                        "!prev.is_field_border => field_input grows by byte_val*padding_shift",
+                        field_input_expr.clone(),
+                        field_input_prev_expr + byte_val_expr.clone() * padding_shift_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "!prev.is_field_border => control_len=prev.control_len",
+                        control_len_expr.clone(),
+                        control_len_prev_expr,
+                    );
+                    cb.require_equal(
+                        "!prev.is_field_border => heading_mark=prev.heading_mark",
+                        heading_mark_expr.clone(),
+                        heading_mark_prev_expr,
+                    );
+                });
+            });
+
+            cb.condition(q_last_expr.clone(), |cb| {
+                cb.require_equal(
+                    "q_last => control_len is the count of the bytes of the last row",
+                    control_len_expr.clone(),
+                    field_index_expr.clone() * HASHBLOCK_BYTES_IN_FIELD.expr()
+                        + byte_in_field_index_expr.clone()
+                        + 1.expr(),
+                );
+            });
+
+            cb.gate(q_enable_expr)
+        });
+
+        let domain_spec_factor = Expression::Constant(F::from_u128(HASHABLE_DOMAIN_SPEC));
+        let lookup_args = |vc: &mut VirtualCells<F>, field_input_expr: Expression<F>| {
+            [
+                1.expr(),
+                vc.query_advice(wb_table.code_hash, Rotation::cur()),
+                field_input_expr,
+                vc.query_advice(control_len, Rotation::cur()) * domain_spec_factor.clone(),
+                vc.query_advice(heading_mark, Rotation::cur()),
+            ]
+        };
+        let poseidon_table_exprs = |vc: &mut VirtualCells<F>, input: Column<Advice>| {
+            [
+                vc.query_fixed(poseidon_table.q_enable, Rotation::cur()),
+                vc.query_advice(poseidon_table.hash_id, Rotation::cur()),
+                vc.query_advice(input, Rotation::cur()),
+                vc.query_advice(poseidon_table.control, Rotation::cur()),
+                vc.query_advice(poseidon_table.heading_mark, Rotation::cur()),
+            ]
+        };
+
+        for (field_idx, input) in [poseidon_table.input0, poseidon_table.input1]
+            .into_iter()
+            .enumerate()
+        {
+            cs.lookup_any(
+                "code hash field is a poseidon input of the code hash",
+                |vc| {
+                    let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+                    let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                    let field_index_expr = vc.query_advice(field_index, Rotation::cur());
+                    let field_input_expr = vc.query_advice(field_input, Rotation::cur());
+                    let is_field_border_expr = is_field_border_chip.config().expr();
+                    let is_field_idx_expr = if field_idx == 0 {
+                        not::expr(field_index_expr)
+                    } else {
+                        field_index_expr
+                    };
+
+                    let cond = and::expr([
+                        q_enable_expr,
+                        or::expr([is_field_border_expr, q_last_expr]),
+                        is_field_idx_expr,
+                    ]);
+
+                    lookup_args(vc, field_input_expr)
+                        .into_iter()
+                        .zip(poseidon_table_exprs(vc, input))
+                        .map(|(arg, table)| (cond.clone() * arg, table))
+                        .collect()
+                },
+            );
+        }
+        cs.lookup_any(
+            "code hash last row ending in the first field has zero second input",
+            |vc| {
+                let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let field_index_expr = vc.query_advice(field_index, Rotation::cur());
+
+                let cond = and::expr([q_enable_expr, q_last_expr, not::expr(field_index_expr)]);
+
+                lookup_args(vc, 0.expr())
+                    .into_iter()
+                    .zip(poseidon_table_exprs(vc, poseidon_table.input1))
+                    .map(|(arg, table)| (cond.clone() * arg, table))
+                    .collect()
+            },
+        );
+
+        WasmCodeHashConfig {
+            q_enable,
+            q_first,
+            q_last,
+            byte_in_field_index,
+            field_index,
+            field_input,
+            padding_shift,
+            control_len,
+            heading_mark,
+            is_field_border_chip,
+            _marker: PhantomData,
+        }
+    }
+
+    /// assigns the packing of the bytes of `wb`, the rows are the ones `WasmBytecodeTable::load`
+    /// assigns the bytes to, starting at `assign_delta`
+    pub fn assign_auto(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(), Error> {
+        let row_len = HASHBLOCK_BYTES_IN_FIELD * PoseidonTable::INPUT_WIDTH;
+        let mut field_input = F::zero();
+        for (wb_offset, &byte_val) in wb.bytes.iter().enumerate() {
+            let assign_offset = assign_delta + wb_offset;
+            let byte_in_field_index = wb_offset % HASHBLOCK_BYTES_IN_FIELD;
+            let field_index = wb_offset % row_len / HASHBLOCK_BYTES_IN_FIELD;
+            let control_len = wb.bytes.len() - wb_offset / row_len * row_len;
+            let heading_mark = wb_offset < row_len;
+            let padding_shift = Self::padding_shift(byte_in_field_index);
+            if byte_in_field_index == 0 {
+                field_input = F::zero();
+            }
+            field_input += F::from(byte_val as u64) * padding_shift;
+
+            for (column, is_set) in [
+                (self.config.q_enable, true),
+                (self.config.q_first, wb_offset == 0),
+                (self.config.q_last, wb_offset == wb.bytes.len() - 1),
+            ] {
+                region
+                    .assign_fixed(
+                        || format!("assign code hash markup at {}", assign_offset),
+                        column,
+                        assign_offset,
+                        || Value::known(F::from(is_set as u64)),
+                    )
+                    .map_err(remap_error_to_assign_at(assign_offset))?;
+            }
+            for (name, column, value) in [
+                (
+                    "byte_in_field_index",
+                    self.config.byte_in_field_index,
+                    F::from(byte_in_field_index as u64),
+                ),
+                (
+                    "field_index",
+                    self.config.field_index,
+                    F::from(field_index as u64),
+                ),
+                ("field_input", self.config.field_input, field_input),
+                ("padding_shift", self.config.padding_shift, padding_shift),
+                (
+                    "control_len",
+                    self.config.control_len,
+                    F::from(control_len as u64),
+                ),
+                (
+                    "heading_mark",
+                    self.config.heading_mark,
+                    F::from(heading_mark as u64),
+                ),
+            ] {
+                region
+                    .assign_advice(
+                        || format!("assign '{}' val {:?} at {}", name, value, assign_offset),
+                        column,
+                        assign_offset,
+                        || Value::known(value),
+                    )
+                    .map_err(remap_error_to_assign_at(assign_offset))?;
+            }
+            self.config
+                .is_field_border_chip
+                .assign(
+                    region,
+                    assign_offset,
+                    Value::known(
+                        F::from(byte_in_field_index as u64)
+                            - F::from(HASHBLOCK_BYTES_IN_FIELD as u64 - 1),
+                    ),
+                )
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        }
+
+        Ok(())
+    }
+}
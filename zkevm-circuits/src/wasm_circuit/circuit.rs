@@ -1,8 +1,11 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Chip, Layouter, Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    circuit::{
+        layouter::{RegionLayouter, RegionShape},
+        Chip, Layouter, Region, Value,
+    },
+    plonk::{Advice, Column, ConstraintSystem, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
@@ -16,31 +19,41 @@ use gadgets::{
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
-    table::PoseidonTable,
+    table::{PoseidonTable, WasmMetadataTable},
     wasm_circuit::{
-        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        bytecode::{
+            bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable, code_hash::WasmCodeHashChip,
+        },
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            digit_char_to_number, wasm_compute_section_len, WasmAssignAwareChip,
-            WasmBytecodeNumberAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmBytecodeNumberAwareChip,
+            WasmColumnAnnotationAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
             WasmLenPrefixedBytesSpanAwareChip, WasmMarkupLeb128SectionAwareChip,
-            WasmSharedStateAwareChip,
+            WasmSectionSummaryAwareChip, WasmSharedStateAwareChip,
         },
         consts::{
             SECTION_ID_DEFAULT, WASM_MAGIC_PREFIX, WASM_MAGIC_PREFIX_LEN,
             WASM_MAGIC_PREFIX_START_INDEX, WASM_SECTIONS_START_INDEX, WASM_SECTION_ID_MAX,
-            WASM_VERSION_PREFIX, WASM_VERSION_PREFIX_END_INDEX, WASM_VERSION_PREFIX_LEN,
+            WASM_VERSION, WASM_VERSION_PREFIX_END_INDEX, WASM_VERSION_PREFIX_LEN,
             WASM_VERSION_PREFIX_START_INDEX,
         },
         error::{
-            error_index_out_of_bounds, is_recoverable_error, remap_error_to_assign_at,
-            remap_error_to_compute_value_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, is_recoverable_error, remap_error, remap_error_to_assign_at,
+            remap_error_to_compute_value_at, require_leb_params_at, Error,
         },
-        leb128::{circuit::LEB128Chip, helpers::leb128_compute_last_byte_offset},
+        instrumentation::WasmInstrument,
+        leb128::{
+            circuit::LEB128Chip,
+            consts::LEB128_MAX_BYTES_COUNT,
+            helpers::{leb128_compute_last_byte_offset, leb128_compute_sn},
+        },
+        parser::{parse_sections, parse_version, DecodedSection, SectionSpan},
         sections::{
             code::body::circuit::WasmCodeSectionBodyChip,
             consts::LebParams,
+            custom::body::circuit::WasmCustomSectionBodyChip,
             data::body::circuit::WasmDataSectionBodyChip,
+            data_count::body::circuit::WasmDataCountSectionBodyChip,
             element::body::circuit::WasmElementSectionBodyChip,
             export::body::circuit::WasmExportSectionBodyChip,
             function::body::circuit::WasmFunctionSectionBodyChip,
@@ -59,15 +72,30 @@ use crate::{
                 types::{LookupArgsParams, Tag},
             },
             fixed_range::config::RangeTableConfig,
+            section_rank::config::SectionRankTableConfig,
         },
         types::{
-            AssignDeltaType, AssignType, AssignValueType, ControlInstruction, ErrorCode,
-            ExportDescType, ImportDescType, NewOffsetType, NewWbOffsetType, OffsetType,
-            SharedState, WasmSection,
+            AssignDeltaType, AssignType, AssignValueType, ColumnStats, ControlInstruction,
+            ErrorCode, ExportDescType, ImportDescType, LimitType, MemSegmentType, NewOffsetType,
+            NewWbOffsetType, SectionParseSummary, SharedState, VariableInstruction, WasmSection,
         },
         utf8::circuit::UTF8Chip,
     },
 };
+#[cfg(any(test, feature = "test-utils"))]
+use crate::{
+    util::log2_ceil,
+    wasm_circuit::{error::ValidateError, standalone::WasmCircuit},
+};
+#[cfg(any(test, feature = "test-utils"))]
+use halo2_proofs::dev::MockProver;
+
+/// smallest `k` the range and poseidon tables of the standalone circuit fit into
+#[cfg(any(test, feature = "test-utils"))]
+const VALIDATE_MIN_K: u32 = 9;
+/// rows kept free on top of the assigned ones (blinding rows, zero row)
+#[cfg(any(test, feature = "test-utils"))]
+const VALIDATE_SPARE_ROWS: usize = 64;
 
 pub struct WasmSectionConfig<F: Field> {
     _marker: PhantomData<F>,
@@ -87,10 +115,20 @@ pub struct WasmConfig<F: Field> {
     is_section_id: Column<Fixed>,
     is_section_len: Column<Fixed>,
     is_section_body: Column<Fixed>,
+    /// body bytes of a section whose body chip is not configured (`structure_only_enabled`)
+    is_opaque_section_body: Column<Fixed>,
+    /// funcs count of an opaque code section body
+    is_opaque_funcs_count: Column<Fixed>,
+    /// items count of an opaque data section body
+    is_opaque_data_items_count: Column<Fixed>,
 
     section_id: Column<Advice>,
+    /// id of the last non-custom section up to the row, custom sections may appear anywhere so
+    /// they keep the id of the previous one
+    ordered_section_id: Column<Advice>,
+    is_custom_section_chip: IsZeroChip<F>,
 
-    leb128_chip: Rc<LEB128Chip<F>>,
+    pub leb128_chip: Rc<LEB128Chip<F>>,
     utf8_chip: Rc<UTF8Chip<F>>,
     wasm_type_section_item_chip: Rc<WasmTypeSectionItemChip<F>>,
     wasm_type_section_body_chip: Rc<WasmTypeSectionBodyChip<F>>,
@@ -98,22 +136,38 @@ pub struct WasmConfig<F: Field> {
     wasm_function_section_body_chip: Rc<WasmFunctionSectionBodyChip<F>>,
     wasm_memory_section_body_chip: Rc<WasmMemorySectionBodyChip<F>>,
     wasm_export_section_body_chip: Rc<WasmExportSectionBodyChip<F>>,
-    wasm_data_section_body_chip: Rc<WasmDataSectionBodyChip<F>>,
+    /// `None` if `structure_only_enabled`
+    wasm_data_section_body_chip: Option<Rc<WasmDataSectionBodyChip<F>>>,
+    wasm_data_count_section_body_chip: Rc<WasmDataCountSectionBodyChip<F>>,
     wasm_global_section_body_chip: Rc<WasmGlobalSectionBodyChip<F>>,
-    wasm_code_section_body_chip: Rc<WasmCodeSectionBodyChip<F>>,
+    /// `None` if `structure_only_enabled`
+    wasm_code_section_body_chip: Option<Rc<WasmCodeSectionBodyChip<F>>>,
     wasm_start_section_body_chip: Rc<WasmStartSectionBodyChip<F>>,
     wasm_table_section_body_chip: Rc<WasmTableSectionBodyChip<F>>,
     wasm_element_section_body_chip: Rc<WasmElementSectionBodyChip<F>>,
+    wasm_custom_section_body_chip: Rc<WasmCustomSectionBodyChip<F>>,
     section_id_lt_chip: LtChip<F, 1>,
     dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
     magic_prefix_count: usize,
     index_at_magic_prefix: Vec<IsZeroChip<F>>,
     poseidon_table: PoseidonTable,
+    /// binds the code hash of the bytecode table to its bytes hashed in `poseidon_table`
+    code_hash_chip: Rc<WasmCodeHashChip<F>>,
     range_table_config_0_256: RangeTableConfig<F, 0, 256>,
     section_id_range_table_config: RangeTableConfig<F, 0, { WASM_SECTION_ID_MAX + 1 }>,
+    section_rank_table_config: SectionRankTableConfig<F>,
     range_table_config_0_128: Rc<RangeTableConfig<F, 0, 128>>,
+    /// 16 bit range (memory offsets, page counts), loaded only if
+    /// `SharedState::range_table_0_65536_enabled` is set
+    pub range_table_config_0_65536: Rc<RangeTableConfig<F, 0, 65536>>,
 
     func_count: Column<Advice>,
+    /// globals of the global index space seen so far (imported ones first)
+    pub global_count: Column<Advice>,
+    /// memories of the memory index space seen so far (imported ones first)
+    pub memory_count: Column<Advice>,
+    /// tables of the table index space seen so far (imported ones first)
+    pub table_count: Column<Advice>,
     block_depth_level: Column<Advice>,
     body_byte_rev_index_l1: Column<Advice>,
     body_byte_rev_index_l2: Column<Advice>,
@@ -122,14 +176,26 @@ pub struct WasmConfig<F: Field> {
 
     error_code: Column<Advice>,
 
+    column_stats: ColumnStats,
+
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> WasmConfig<F> {}
+impl<F: Field> WasmConfig<F> {
+    /// fixed/advice columns each subchip (leb128, utf8, sections, poseidon, range tables)
+    /// contributes, handy to tune `k` and to see the cost of the optional parts
+    pub fn column_stats(&self) -> ColumnStats {
+        self.column_stats.clone()
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct WasmChip<F: Field> {
     pub config: WasmConfig<F>,
+    instrument: WasmInstrument,
+    decoded_sections: Vec<DecodedSection>,
+    /// an assignment step of the current bytecode failed and its rows got the error code
+    is_error_processed: bool,
     _marker: PhantomData<F>,
 }
 
@@ -175,10 +241,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -253,7 +320,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmChip<F> {
                             || Value::known(F::from(assign_value)),
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
-                    let p = leb_params.unwrap();
+                    let p = require_leb_params_at(leb_params, assign_offset)?;
                     self.config
                         .leb128_chip
                         .assign(region, assign_offset, q_enable, p)?;
@@ -273,6 +340,48 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::IsOpaqueSectionBody => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_opaque_section_body' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_opaque_section_body,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsOpaqueFuncsCount | AssignType::IsOpaqueDataItemsCount => {
+                    let (column, name) = if *assign_type == AssignType::IsOpaqueFuncsCount {
+                        (self.config.is_opaque_funcs_count, "is_opaque_funcs_count")
+                    } else {
+                        (
+                            self.config.is_opaque_data_items_count,
+                            "is_opaque_data_items_count",
+                        )
+                    };
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign '{}' val {} at {}",
+                                    name, assign_value, assign_offset
+                                )
+                            },
+                            column,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                    let p = require_leb_params_at(leb_params, assign_offset)?;
+                    self.config
+                        .leb128_chip
+                        .assign(region, assign_offset, q_enable, p)?;
+                }
                 AssignType::BodyByteRevIndexL1 => {
                     region
                         .assign_advice(
@@ -315,8 +424,11 @@ impl<F: Field> WasmChip<F> {
         wb: &WasmBytecode,
         assign_delta: AssignDeltaType,
     ) -> Result<NewOffsetType, Error> {
-        let mut new_assign_offset = 0;
-        new_assign_offset = self.config.wb_table.load(region, wb, assign_delta).unwrap();
+        let new_assign_offset = self
+            .config
+            .wb_table
+            .load(region, wb, assign_delta)
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
 
         let assign_delta = assign_delta
             + if self.config.wb_table.zero_row_enabled {
@@ -324,20 +436,56 @@ impl<F: Field> WasmChip<F> {
             } else {
                 0
             };
+        if self
+            .config
+            .shared_state
+            .borrow()
+            .production_poseidon_table_enabled
+        {
+            self.config
+                .poseidon_table
+                .load_code_hashes_with_region(region, &[wb.bytes.clone()], assign_delta)
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        } else {
+            self.config
+                .poseidon_table
+                .dev_load2(region, &[wb.bytes.clone()], assign_delta)
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        }
         self.config
-            .poseidon_table
-            .dev_load2(region, &[wb.bytes.clone()], assign_delta)
-            .unwrap();
+            .code_hash_chip
+            .assign_auto(region, wb, assign_delta)?;
 
         Ok(new_assign_offset)
     }
     pub fn load_once(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        self.config.range_table_config_0_256.load(layouter).unwrap();
+        self.config
+            .range_table_config_0_256
+            .load(layouter)
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
         self.config
             .section_id_range_table_config
             .load(layouter)
-            .unwrap();
-        self.config.range_table_config_0_128.load(layouter).unwrap();
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        self.config
+            .section_rank_table_config
+            .load(layouter)
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        self.config
+            .range_table_config_0_128
+            .load(layouter)
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        if self
+            .config
+            .shared_state
+            .borrow()
+            .range_table_0_65536_enabled
+        {
+            self.config
+                .range_table_config_0_65536
+                .load(layouter)
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        }
 
         Ok(())
     }
@@ -348,6 +496,8 @@ impl<F: Field> WasmChip<F> {
         shared_state: Rc<RefCell<SharedState>>,
     ) -> WasmConfig<F> {
         let magic_prefix_count = WASM_MAGIC_PREFIX_LEN + WASM_VERSION_PREFIX_LEN;
+        let structure_only = shared_state.borrow().structure_only_enabled;
+        let mut column_stats = ColumnStats::new(cs);
 
         let bytecode_number = cs.advice_column();
 
@@ -357,9 +507,16 @@ impl<F: Field> WasmChip<F> {
         let is_section_id = cs.fixed_column();
         let is_section_len = cs.fixed_column();
         let is_section_body = cs.fixed_column();
+        let is_opaque_section_body = cs.fixed_column();
+        let is_opaque_funcs_count = cs.fixed_column();
+        let is_opaque_data_items_count = cs.fixed_column();
 
         let section_id = cs.advice_column();
+        let ordered_section_id = cs.advice_column();
         let func_count = cs.advice_column();
+        let global_count = cs.advice_column();
+        let memory_count = cs.advice_column();
+        let table_count = cs.advice_column();
         let block_depth_level = cs.advice_column();
         let body_byte_rev_index_l1 = cs.advice_column();
         let body_byte_rev_index_l2 = cs.advice_column();
@@ -367,21 +524,41 @@ impl<F: Field> WasmChip<F> {
         let body_item_rev_count_l2 = cs.advice_column();
 
         let error_code = cs.advice_column();
+        column_stats.record("wasm chip", cs);
 
         let range_table_config_0_256 = RangeTableConfig::configure(cs);
         let section_id_range_table_config = RangeTableConfig::configure(cs);
+        let section_rank_table_config = SectionRankTableConfig::configure(cs);
         let range_table_config_0_128 = Rc::new(RangeTableConfig::configure(cs));
-        let poseidon_table = PoseidonTable::dev_construct(cs);
+        let range_table_config_0_65536 = Rc::new(RangeTableConfig::configure(cs));
+        column_stats.record("range tables", cs);
+        let poseidon_table = if shared_state.borrow().production_poseidon_table_enabled {
+            PoseidonTable::construct(cs)
+        } else {
+            PoseidonTable::dev_construct(cs)
+        };
+        column_stats.record("poseidon table", cs);
+        let code_hash_config = WasmCodeHashChip::configure(cs, &wb_table, &poseidon_table);
+        let code_hash_chip = Rc::new(WasmCodeHashChip::construct(code_hash_config));
+        column_stats.record("code hash", cs);
 
-        let leb128_config = LEB128Chip::configure(cs, &wb_table.value);
+        let leb128_config = LEB128Chip::configure(
+            cs,
+            &wb_table.value,
+            LEB128_MAX_BYTES_COUNT,
+            Some(&range_table_config_0_256),
+        );
         let mut leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
+        column_stats.record("leb128", cs);
 
         let utf8_config =
             UTF8Chip::configure(cs, range_table_config_0_128.clone(), &wb_table.value);
         let mut utf8_chip = Rc::new(UTF8Chip::construct(utf8_config));
+        column_stats.record("utf8", cs);
 
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
+        column_stats.record("dynamic indexes", cs);
 
         let config = WasmTypeSectionItemChip::configure(
             cs,
@@ -405,6 +582,7 @@ impl<F: Field> WasmChip<F> {
             error_code,
         );
         let wasm_type_section_body_chip = Rc::new(WasmTypeSectionBodyChip::construct(config));
+        column_stats.record("type section", cs);
 
         let config = WasmImportSectionBodyChip::configure(
             cs,
@@ -413,24 +591,29 @@ impl<F: Field> WasmChip<F> {
             utf8_chip.clone(),
             dynamic_indexes_chip.clone(),
             func_count,
+            global_count,
             shared_state.clone(),
             body_byte_rev_index_l2,
             body_item_rev_count_l1,
             error_code,
         );
         let wasm_import_section_body_chip = Rc::new(WasmImportSectionBodyChip::construct(config));
+        column_stats.record("import section", cs);
 
         let config = WasmFunctionSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
             body_item_rev_count_l1,
             error_code,
+            bytecode_number,
         );
         let wasm_function_section_body_chip =
             Rc::new(WasmFunctionSectionBodyChip::construct(config));
+        column_stats.record("function section", cs);
 
         let config = WasmMemorySectionBodyChip::configure(
             cs,
@@ -441,14 +624,16 @@ impl<F: Field> WasmChip<F> {
             shared_state.clone(),
             body_item_rev_count_l1,
             error_code,
-            bytecode_number,
         );
         let wasm_memory_section_body_chip = Rc::new(WasmMemorySectionBodyChip::construct(config));
+        column_stats.record("memory section", cs);
 
         let config = WasmExportSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            utf8_chip.clone(),
+            &range_table_config_0_256,
             func_count,
             shared_state.clone(),
             body_byte_rev_index_l2,
@@ -456,20 +641,40 @@ impl<F: Field> WasmChip<F> {
             error_code,
         );
         let wasm_export_section_body_chip = Rc::new(WasmExportSectionBodyChip::construct(config));
+        column_stats.record("export section", cs);
+
+        let wasm_data_section_body_chip = if structure_only {
+            None
+        } else {
+            let config = WasmDataSectionBodyChip::configure(
+                cs,
+                wb_table.clone(),
+                leb128_chip.clone(),
+                dynamic_indexes_chip.clone(),
+                func_count,
+                shared_state.clone(),
+                body_byte_rev_index_l2,
+                body_item_rev_count_l1,
+                error_code,
+                bytecode_number,
+            );
+            Some(Rc::new(WasmDataSectionBodyChip::construct(config)))
+        };
+        column_stats.record("data section", cs);
 
-        let config = WasmDataSectionBodyChip::configure(
+        let config = WasmDataCountSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
             dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
-            body_byte_rev_index_l2,
-            body_item_rev_count_l1,
             error_code,
             bytecode_number,
         );
-        let wasm_data_section_body_chip = Rc::new(WasmDataSectionBodyChip::construct(config));
+        let wasm_data_count_section_body_chip =
+            Rc::new(WasmDataCountSectionBodyChip::construct(config));
+        column_stats.record("data count section", cs);
 
         let config = WasmGlobalSectionBodyChip::configure(
             cs,
@@ -480,44 +685,99 @@ impl<F: Field> WasmChip<F> {
             shared_state.clone(),
             body_item_rev_count_l1,
             error_code,
-            bytecode_number,
         );
         let wasm_global_section_body_chip = Rc::new(WasmGlobalSectionBodyChip::construct(config));
+        column_stats.record("global section", cs);
+
+        let wasm_code_section_body_chip = if structure_only {
+            None
+        } else {
+            let config = WasmCodeSectionBodyChip::configure(
+                cs,
+                wb_table.clone(),
+                leb128_chip.clone(),
+                dynamic_indexes_chip.clone(),
+                func_count,
+                shared_state.clone(),
+                body_byte_rev_index_l2,
+                body_item_rev_count_l1,
+                error_code,
+                bytecode_number,
+            );
+            // the type of a func body is the typeidx the function section declares for it and
+            // its params are the first locals of the body
+            let lookup_cond = |vc: &mut VirtualCells<'_, F>| {
+                Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    config.q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                ) * vc.query_fixed(config.is_func_body_len, Rotation::cur())
+            };
+            wasm_function_section_body_chip.lookup_type_index(
+                "code section func body type index is declared by the function section",
+                cs,
+                |vc| {
+                    (
+                        lookup_cond(vc),
+                        vc.query_advice(wb_table.code_hash, Rotation::cur()),
+                        vc.query_advice(config.body_item_rev_count, Rotation::cur()),
+                        vc.query_advice(config.func_type_index, Rotation::cur()),
+                    )
+                },
+            );
+            wasm_type_section_body_chip.lookup_params_count(
+                "code section func body params count is declared by the type section",
+                cs,
+                |vc| {
+                    (
+                        lookup_cond(vc),
+                        vc.query_advice(wb_table.code_hash, Rotation::cur()),
+                        vc.query_advice(config.func_type_index, Rotation::cur()),
+                        vc.query_advice(config.params_count, Rotation::cur()),
+                    )
+                },
+            );
+            Some(Rc::new(WasmCodeSectionBodyChip::construct(config)))
+        };
+        column_stats.record("code section", cs);
 
-        let config = WasmCodeSectionBodyChip::configure(
+        let config = WasmStartSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
-            dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
-            body_byte_rev_index_l2,
-            body_item_rev_count_l1,
             error_code,
-            bytecode_number,
         );
-        let wasm_code_section_body_chip = Rc::new(WasmCodeSectionBodyChip::construct(config));
+        let wasm_start_section_body_chip = Rc::new(WasmStartSectionBodyChip::construct(config));
+        column_stats.record("start section", cs);
 
-        let config = WasmStartSectionBodyChip::configure(
+        let config = WasmElementSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
+            body_item_rev_count_l1,
             error_code,
         );
-        let wasm_start_section_body_chip = Rc::new(WasmStartSectionBodyChip::construct(config));
+        let wasm_element_section_body_chip = Rc::new(WasmElementSectionBodyChip::construct(config));
+        column_stats.record("element section", cs);
 
-        let config = WasmElementSectionBodyChip::configure(
+        let config = WasmCustomSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            utf8_chip.clone(),
             func_count,
             shared_state.clone(),
-            body_item_rev_count_l1,
+            body_byte_rev_index_l2,
             error_code,
         );
-        let wasm_element_section_body_chip = Rc::new(WasmElementSectionBodyChip::construct(config));
+        let wasm_custom_section_body_chip = Rc::new(WasmCustomSectionBodyChip::construct(config));
+        column_stats.record("custom section", cs);
 
         let config = WasmTableSectionBodyChip::configure(
             cs,
@@ -529,6 +789,7 @@ impl<F: Field> WasmChip<F> {
             shared_state.clone(),
         );
         let wasm_table_section_body_chip = Rc::new(WasmTableSectionBodyChip::construct(config));
+        column_stats.record("table section", cs);
 
         let mut index_at_magic_prefix: Vec<IsZeroChip<F>> = Vec::new();
         for index in 0..magic_prefix_count {
@@ -554,24 +815,36 @@ impl<F: Field> WasmChip<F> {
             |vc| vc.query_fixed(is_section_body, Rotation::cur()),
             body_byte_rev_index_l1,
             |vc| {
-                let not_q_last_expr = not::expr(vc.query_fixed(q_last, Rotation::cur()));
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let not_q_last_expr = not::expr(q_last_expr.clone());
                 let is_section_len_expr = vc.query_fixed(is_section_len, Rotation::cur());
                 let is_section_body_next_expr = vc.query_fixed(is_section_body, Rotation::next());
+                let is_section_id_next_expr = vc.query_fixed(is_section_id, Rotation::next());
 
+                // an empty section body goes from the len straight to the next section (or q_last)
                 and::expr([
-                    not_q_last_expr,
                     is_section_len_expr,
-                    is_section_body_next_expr,
+                    or::expr([
+                        q_last_expr,
+                        and::expr([
+                            not_q_last_expr,
+                            or::expr([is_section_body_next_expr, is_section_id_next_expr]),
+                        ]),
+                    ]),
                 ])
             },
             |vc| {
                 let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let is_section_len_expr = vc.query_fixed(is_section_len, Rotation::cur());
                 let is_section_body_expr = vc.query_fixed(is_section_body, Rotation::cur());
                 let is_section_id_next_expr = vc.query_fixed(is_section_id, Rotation::next());
 
                 or::expr([
                     q_last_expr,
-                    and::expr([is_section_body_expr, is_section_id_next_expr]),
+                    and::expr([
+                        or::expr([is_section_len_expr, is_section_body_expr]),
+                        is_section_id_next_expr,
+                    ]),
                 ])
             },
         );
@@ -620,38 +893,20 @@ impl<F: Field> WasmChip<F> {
                 ]
             });
         }
-        for index in WASM_VERSION_PREFIX_START_INDEX
-            ..WASM_VERSION_PREFIX_START_INDEX + WASM_VERSION_PREFIX_LEN
-        {
-            let version_val = if index == WASM_VERSION_PREFIX_START_INDEX {
-                1
-            } else {
-                0
-            };
-            cs.lookup_any("byte_val[index]=version_val[index]", |vc| {
-                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
-                    vc,
-                    q_enable,
-                    &shared_state.borrow(),
-                    error_code,
-                );
-                let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
-                let not_q_first_expr = not::expr(q_first_expr.clone());
-                let q_enable_expr = q_enable_expr * not_q_first_expr;
 
-                let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
-                let byte_index_expr = vc.query_advice(wb_table.index, Rotation::cur());
-                let byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
-                vec![
-                    (
-                        q_enable_expr.clone() * bytecode_number_expr.clone(),
-                        bytecode_number_expr,
-                    ),
-                    (q_enable_expr.clone() * index.expr(), byte_index_expr),
-                    (q_enable_expr.clone() * version_val.expr(), byte_val_expr),
-                ]
-            });
-        }
+        let value_inv = cs.advice_column();
+        let is_custom_section_config = IsZeroChip::configure(
+            cs,
+            |vc| {
+                and::expr([
+                    vc.query_fixed(q_enable, Rotation::cur()),
+                    not::expr(vc.query_fixed(q_first, Rotation::cur())),
+                ])
+            },
+            |vc| vc.query_advice(section_id, Rotation::cur()),
+            value_inv,
+        );
+        let is_custom_section_chip = IsZeroChip::construct(is_custom_section_config);
 
         let section_id_lt_chip_config = LtChip::configure(
             cs,
@@ -667,8 +922,8 @@ impl<F: Field> WasmChip<F> {
 
                 and::expr([not_q_first_expr.clone(), q_enable_expr.clone()])
             },
-            |vc| vc.query_advice(section_id, Rotation::prev()),
-            |vc| vc.query_advice(section_id, Rotation::cur()),
+            |vc| vc.query_advice(ordered_section_id, Rotation::prev()),
+            |vc| vc.query_advice(ordered_section_id, Rotation::cur()),
         );
         let section_id_lt_chip = LtChip::construct(section_id_lt_chip_config);
 
@@ -691,6 +946,10 @@ impl<F: Field> WasmChip<F> {
             let is_section_id_expr = vc.query_fixed(is_section_id, Rotation::cur());
             let is_section_len_expr = vc.query_fixed(is_section_len, Rotation::cur());
             let is_section_body_expr = vc.query_fixed(is_section_body, Rotation::cur());
+            let is_opaque_section_body_expr = vc.query_fixed(is_opaque_section_body, Rotation::cur());
+            let is_opaque_funcs_count_expr = vc.query_fixed(is_opaque_funcs_count, Rotation::cur());
+            let is_opaque_data_items_count_expr =
+                vc.query_fixed(is_opaque_data_items_count, Rotation::cur());
 
             let index_val_expr = vc.query_advice(wb_table.index, Rotation::cur());
             let byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
@@ -701,18 +960,35 @@ impl<F: Field> WasmChip<F> {
             let byte_index_next_expr = vc.query_advice(wb_table.index, Rotation::next());
 
             let section_id_expr = vc.query_advice(section_id, Rotation::cur());
-            let section_id_prev_expr = vc.query_advice(section_id, Rotation::prev());
 
             let leb128_is_last_byte_expr =
                 vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur());
 
-            let wb_table_code_hash = vc.query_advice(wb_table.code_hash, Rotation::cur());
-            let poseidon_table_hash_id = vc.query_advice(poseidon_table.hash_id, Rotation::cur());
-
             cb.require_boolean("q_enable is boolean", q_enable_expr.clone());
             cb.require_boolean("is_section_id is boolean", is_section_id_expr.clone());
             cb.require_boolean("is_section_len is boolean", is_section_len_expr.clone());
             cb.require_boolean("is_section_body is boolean", is_section_body_expr.clone());
+            cb.require_boolean(
+                "is_opaque_section_body is boolean",
+                is_opaque_section_body_expr.clone(),
+            );
+            cb.require_boolean(
+                "is_opaque_funcs_count is boolean",
+                is_opaque_funcs_count_expr.clone(),
+            );
+            cb.require_boolean(
+                "is_opaque_data_items_count is boolean",
+                is_opaque_data_items_count_expr.clone(),
+            );
+            cb.require_zero(
+                "opaque items count => is_opaque_section_body",
+                (is_opaque_funcs_count_expr.clone() + is_opaque_data_items_count_expr.clone())
+                    * not::expr(is_opaque_section_body_expr.clone()),
+            );
+            cb.require_zero(
+                "is_opaque_section_body => is_section_body",
+                is_opaque_section_body_expr.clone() * not::expr(is_section_body_expr.clone()),
+            );
 
             cb.condition(
                 q_first_expr.clone(),
@@ -805,18 +1081,20 @@ impl<F: Field> WasmChip<F> {
                         wasm_export_section_body_chip.config.q_enable,
                         Rotation::cur(),
                     )
+                        + wasm_data_section_body_chip.as_ref().map_or(0.expr(), |chip| {
+                            vc.query_fixed(chip.config.q_enable, Rotation::cur())
+                        })
                         + vc.query_fixed(
-                        wasm_data_section_body_chip.config.q_enable,
+                        wasm_data_count_section_body_chip.config.q_enable,
                         Rotation::cur(),
                     )
                         + vc.query_fixed(
                         wasm_global_section_body_chip.config.q_enable,
                         Rotation::cur(),
                     )
-                        + vc.query_fixed(
-                        wasm_code_section_body_chip.config.q_enable,
-                        Rotation::cur(),
-                    )
+                        + wasm_code_section_body_chip.as_ref().map_or(0.expr(), |chip| {
+                            vc.query_fixed(chip.config.q_enable, Rotation::cur())
+                        })
                         + vc.query_fixed(
                         wasm_start_section_body_chip.config.q_enable,
                         Rotation::cur(),
@@ -829,6 +1107,11 @@ impl<F: Field> WasmChip<F> {
                         wasm_element_section_body_chip.config.q_enable,
                         Rotation::cur(),
                     )
+                        + vc.query_fixed(
+                        wasm_custom_section_body_chip.config.q_enable,
+                        Rotation::cur(),
+                    )
+                        + is_opaque_section_body_expr.clone()
                         + is_section_id_expr.clone()
                         + is_section_len_expr.clone(),
                     1.expr(),
@@ -849,11 +1132,19 @@ impl<F: Field> WasmChip<F> {
                     .config
                     .value_equals(ImportDescType::Typeidx, Rotation::cur())(vc),
             ]);
-            let wasm_code_section_q_first_expr =
-                vc.query_fixed(wasm_code_section_body_chip.config.q_first, Rotation::cur());
+            let wasm_code_section_q_first_expr = wasm_code_section_body_chip
+                .as_ref()
+                .map_or(0.expr(), |chip| {
+                    vc.query_fixed(chip.config.q_first, Rotation::cur())
+                });
+            let opaque_funcs_count_first_byte_expr = and::expr([
+                is_opaque_funcs_count_expr.clone(),
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+            ]);
             let not_func_count_inc_expr = and::expr([
                 not::expr(importdesc_type_is_typeidx_expr.clone()),
                 not::expr(wasm_code_section_q_first_expr.clone()),
+                not::expr(opaque_funcs_count_first_byte_expr.clone()),
             ]);
             cb.condition(
                 and::expr([not_q_first_expr.clone(), not_func_count_inc_expr.clone()]),
@@ -874,19 +1165,185 @@ impl<F: Field> WasmChip<F> {
                     func_count_expr.clone(),
                 );
             });
-            cb.condition(wasm_code_section_q_first_expr.clone(), |cb| {
+            if let Some(wasm_code_section_body_chip) = &wasm_code_section_body_chip {
+                cb.condition(wasm_code_section_q_first_expr.clone(), |cb| {
+                    let func_count_prev_expr = vc.query_advice(func_count, Rotation::prev());
+                    let wasm_code_section_leb128_sn_expr = vc.query_advice(
+                        wasm_code_section_body_chip.config.leb128_chip.config.sn,
+                        Rotation::cur(),
+                    );
+                    cb.require_equal(
+                        "wasm_code_section_q_first => func_count grew by specific number",
+                        func_count_prev_expr.clone() + wasm_code_section_leb128_sn_expr.clone(),
+                        func_count_expr.clone(),
+                    );
+                });
+            }
+            cb.condition(opaque_funcs_count_first_byte_expr.clone(), |cb| {
                 let func_count_prev_expr = vc.query_advice(func_count, Rotation::prev());
-                let wasm_code_section_leb128_sn_expr = vc.query_advice(
-                    wasm_code_section_body_chip.config.leb128_chip.config.sn,
+                let leb128_sn_expr = vc.query_advice(leb128_chip.config.sn, Rotation::cur());
+                cb.require_equal(
+                    "opaque funcs count first byte => func_count grew by funcs count",
+                    func_count_prev_expr.clone() + leb128_sn_expr.clone(),
+                    func_count_expr.clone(),
+                );
+            });
+            // global_count constraints
+            let global_count_expr = vc.query_advice(global_count, Rotation::cur());
+            cb.condition(q_first_expr.clone(), |cb| {
+                cb.require_zero("q_first => global_count=0", global_count_expr.clone());
+            });
+            let importdesc_type_is_global_type_expr = and::expr([
+                vc.query_fixed(
+                    wasm_import_section_body_chip.config.is_importdesc_type,
+                    Rotation::cur(),
+                ),
+                wasm_import_section_body_chip
+                    .config
+                    .importdesc_type_chip
+                    .config
+                    .value_equals(ImportDescType::GlobalType, Rotation::cur())(vc),
+            ]);
+            let global_items_count_first_byte_expr = and::expr([
+                vc.query_fixed(
+                    wasm_global_section_body_chip.config.is_items_count,
                     Rotation::cur(),
+                ),
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+            ]);
+            let not_global_count_inc_expr = and::expr([
+                not::expr(importdesc_type_is_global_type_expr.clone()),
+                not::expr(global_items_count_first_byte_expr.clone()),
+            ]);
+            cb.condition(
+                and::expr([not_q_first_expr.clone(), not_global_count_inc_expr.clone()]),
+                |cb| {
+                    let global_count_prev_expr = vc.query_advice(global_count, Rotation::prev());
+                    cb.require_equal(
+                        "not_q_first && not_global_count_inc => prev.global_count=global_count",
+                        global_count_prev_expr.clone(),
+                        global_count_expr.clone(),
+                    );
+                },
+            );
+            cb.condition(importdesc_type_is_global_type_expr.clone(), |cb| {
+                let global_count_prev_expr = vc.query_advice(global_count, Rotation::prev());
+                cb.require_equal(
+                    "importdesc_type_is_global_type => global_count increased by 1",
+                    global_count_prev_expr.clone() + 1.expr(),
+                    global_count_expr.clone(),
                 );
+            });
+            cb.condition(global_items_count_first_byte_expr.clone(), |cb| {
+                let global_count_prev_expr = vc.query_advice(global_count, Rotation::prev());
+                let leb128_sn_expr = vc.query_advice(leb128_chip.config.sn, Rotation::cur());
                 cb.require_equal(
-                    "wasm_code_section_q_first => func_count grew by specific number",
-                    func_count_prev_expr.clone() + wasm_code_section_leb128_sn_expr.clone(),
-                    func_count_expr.clone(),
+                    "global section items count first byte => global_count grew by items count",
+                    global_count_prev_expr.clone() + leb128_sn_expr.clone(),
+                    global_count_expr.clone(),
+                );
+            });
+            // the global section numbers its globals after the imported ones
+            cb.condition(
+                vc.query_fixed(
+                    wasm_global_section_body_chip.config.is_items_count,
+                    Rotation::cur(),
+                ),
+                |cb| {
+                    let leb128_sn_expr = vc.query_advice(leb128_chip.config.sn, Rotation::cur());
+                    let global_index_expr = vc.query_advice(
+                        wasm_global_section_body_chip.config.global_index,
+                        Rotation::cur(),
+                    );
+                    cb.require_equal(
+                        "global section items count => global_index=global_count-items count",
+                        global_index_expr,
+                        global_count_expr.clone() - leb128_sn_expr,
+                    );
+                },
+            );
+
+            // memory_count constraints
+            let memory_count_expr = vc.query_advice(memory_count, Rotation::cur());
+            let memory_count_prev_expr = vc.query_advice(memory_count, Rotation::prev());
+            cb.condition(q_first_expr.clone(), |cb| {
+                cb.require_zero("q_first => memory_count=0", memory_count_expr.clone());
+            });
+            let importdesc_type_is_mem_type_expr = and::expr([
+                vc.query_fixed(
+                    wasm_import_section_body_chip.config.is_importdesc_type,
+                    Rotation::cur(),
+                ),
+                wasm_import_section_body_chip
+                    .config
+                    .importdesc_type_chip
+                    .config
+                    .value_equals(ImportDescType::MemType, Rotation::cur())(vc),
+            ]);
+            let memory_items_count_first_byte_expr = and::expr([
+                vc.query_fixed(
+                    wasm_memory_section_body_chip.config.is_items_count,
+                    Rotation::cur(),
+                ),
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+            ]);
+            cb.condition(not_q_first_expr.clone(), |cb| {
+                cb.require_equal(
+                    "!q_first => memory_count=prev.memory_count+memory imports+memory section items count",
+                    memory_count_expr.clone(),
+                    memory_count_prev_expr
+                        + importdesc_type_is_mem_type_expr
+                        + memory_items_count_first_byte_expr
+                            * vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                );
+            });
+
+            // table_count constraints, the table section body holds a single table
+            let table_count_expr = vc.query_advice(table_count, Rotation::cur());
+            let table_count_prev_expr = vc.query_advice(table_count, Rotation::prev());
+            cb.condition(q_first_expr.clone(), |cb| {
+                cb.require_zero("q_first => table_count=0", table_count_expr.clone());
+            });
+            let importdesc_type_is_table_type_expr = and::expr([
+                vc.query_fixed(
+                    wasm_import_section_body_chip.config.is_importdesc_type,
+                    Rotation::cur(),
+                ),
+                wasm_import_section_body_chip
+                    .config
+                    .importdesc_type_chip
+                    .config
+                    .value_equals(ImportDescType::TableType, Rotation::cur())(vc),
+            ]);
+            let is_table_reference_type_expr = vc.query_fixed(
+                wasm_table_section_body_chip.config.is_reference_type,
+                Rotation::cur(),
+            );
+            cb.condition(not_q_first_expr.clone(), |cb| {
+                cb.require_equal(
+                    "!q_first => table_count=prev.table_count+table imports+table section tables",
+                    table_count_expr.clone(),
+                    table_count_prev_expr
+                        + importdesc_type_is_table_type_expr
+                        + is_table_reference_type_expr,
                 );
             });
 
+            // version bytes are range checked, so the little-endian word pins every one of them
+            cb.condition(
+                index_at_magic_prefix[WASM_VERSION_PREFIX_END_INDEX].config().expr(),
+                |cb| {
+                    let version_expr = (0..WASM_VERSION_PREFIX_LEN).fold(0.expr(), |acc, idx| {
+                        acc * 256.expr()
+                            + vc.query_advice(wb_table.value, Rotation(-(idx as i32)))
+                    });
+                    cb.require_equal(
+                        "bytecode[4..8] as little-endian word = WASM_VERSION",
+                        version_expr,
+                        (WASM_VERSION as u64).expr(),
+                    )
+                }
+            );
             // wasm section layout check
             cb.condition(
                 index_at_magic_prefix[WASM_VERSION_PREFIX_END_INDEX].config().expr(),
@@ -911,22 +1368,22 @@ impl<F: Field> WasmChip<F> {
             configure_transition_check(
                 &mut cb,
                 vc,
-                "check next: is_section_len+ -> is_section_body+",
+                "check next: is_section_len+ -> is_section_body* -> is_section_id{1} || q_last",
                 and::expr([not_q_last_expr.clone(), is_section_len_expr.clone()]),
                 true,
-                &[is_section_len, is_section_body],
+                &[is_section_len, is_section_body, is_section_id],
             );
             configure_transition_check(
                 &mut cb,
                 vc,
-                "check next (last leb byte): is_section_len+ -> is_section_body+",
+                "check next (last leb byte): is_section_len+ -> is_section_body* -> is_section_id{1}",
                 and::expr([
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_section_len_expr.clone(),
                 ]),
                 true,
-                &[is_section_body],
+                &[is_section_body, is_section_id],
             );
             configure_transition_check(
                 &mut cb,
@@ -962,25 +1419,45 @@ impl<F: Field> WasmChip<F> {
                 );
             }
 
-            cb.condition(not_q_first_expr.clone(), |cb| {
+            let ordered_section_id_expr = vc.query_advice(ordered_section_id, Rotation::cur());
+            let ordered_section_id_prev_expr = vc.query_advice(ordered_section_id, Rotation::prev());
+            let is_custom_section_expr = is_custom_section_chip.config().expr();
+            cb.condition(q_first_expr.clone(), |cb| {
                 cb.require_zero(
-                    "prev.section_id <= cur.section_id",
-                    (section_id_lt_chip.config().is_lt(vc, None) - 1.expr())
-                        * (section_id_expr.clone() - section_id_prev_expr.clone()),
+                    "q_first => ordered_section_id=0",
+                    ordered_section_id_expr.clone(),
                 );
             });
-
-            // code_hash check
-            // TODO refactor
-            cb.require_zero(
-                "code hashes match",
-                index_at_magic_prefix[2].config().expr()
-                    * (wb_table_code_hash.clone() - poseidon_table_hash_id.clone()),
-            );
-
-            cb.gate(q_enable_expr)
-        });
-
+            cb.condition(and::expr([not_q_first_expr.clone(), is_custom_section_expr.clone()]), |cb| {
+                cb.require_equal(
+                    "custom section (or magic prefix) => ordered_section_id=prev.ordered_section_id",
+                    ordered_section_id_expr.clone(),
+                    ordered_section_id_prev_expr.clone(),
+                );
+            });
+            // !custom section => ordered_section_id=rank(section_id), see the section rank lookup
+            // non-custom sections can't repeat
+            cb.condition(
+                and::expr([is_section_id_expr.clone(), not::expr(is_custom_section_expr.clone())]),
+                |cb| {
+                    cb.require_equal(
+                        "is_section_id && !custom section => prev.ordered_section_id < cur.ordered_section_id",
+                        section_id_lt_chip.config().is_lt(vc, None),
+                        1.expr(),
+                    );
+                },
+            );
+            cb.condition(not_q_first_expr.clone(), |cb| {
+                cb.require_zero(
+                    "prev.ordered_section_id <= cur.ordered_section_id",
+                    (section_id_lt_chip.config().is_lt(vc, None) - 1.expr())
+                        * (ordered_section_id_expr.clone() - ordered_section_id_prev_expr.clone()),
+                );
+            });
+
+            cb.gate(q_enable_expr)
+        });
+
         cs.lookup("section_id is a valid number", |vc| {
             let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
                 vc,
@@ -997,6 +1474,98 @@ impl<F: Field> WasmChip<F> {
             )]
         });
 
+        section_rank_table_config.lookup(
+            "!custom section => ordered_section_id is the rank of section_id",
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let not_custom_section_expr = not::expr(is_custom_section_chip.config().expr());
+
+                (
+                    q_enable_expr * not_custom_section_expr,
+                    vc.query_advice(section_id, Rotation::cur()),
+                    vc.query_advice(ordered_section_id, Rotation::cur()),
+                )
+            },
+        );
+
+        // Several index crosschecks below query `leb128_chip.config.sn` at `Rotation::next()`: the
+        // marker byte (importdesc type, exportdesc type, call opcode) sits on the current row and
+        // the index leb128 is expected to start right on the next one. A desync would silently feed
+        // a wrong index into the lookup, so the coupling is enforced explicitly.
+        cs.create_gate("index marker is followed by leb128 first byte", |vc| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                vc,
+                q_enable,
+                &shared_state.borrow(),
+                error_code,
+            );
+
+            let leb128_q_enable_next_expr =
+                vc.query_fixed(leb128_chip.config.q_enable, Rotation::next());
+            let leb128_is_first_byte_next_expr =
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::next());
+
+            let is_import_typeidx_marker_expr = and::expr([
+                vc.query_fixed(
+                    wasm_import_section_body_chip.config.is_importdesc_type,
+                    Rotation::cur(),
+                ),
+                wasm_import_section_body_chip
+                    .config
+                    .importdesc_type_chip
+                    .config
+                    .value_equals(ImportDescType::Typeidx, Rotation::cur())(vc),
+            ]);
+            let is_exportdesc_type_marker_expr = vc.query_fixed(
+                wasm_export_section_body_chip.config.is_exportdesc_type,
+                Rotation::cur(),
+            );
+            let is_call_marker_expr =
+                wasm_code_section_body_chip
+                    .as_ref()
+                    .map_or(0.expr(), |chip| {
+                        and::expr([
+                            vc.query_fixed(chip.config.is_control_instruction, Rotation::cur()),
+                            chip.config
+                                .control_instruction_chip
+                                .config
+                                .value_equals(ControlInstruction::Call, Rotation::cur())(
+                                vc
+                            ),
+                        ])
+                    });
+
+            cb.condition(
+                or::expr([
+                    is_import_typeidx_marker_expr,
+                    is_exportdesc_type_marker_expr,
+                    is_call_marker_expr,
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "index marker byte => next.leb128_q_enable=1",
+                        leb128_q_enable_next_expr.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "index marker byte => next.leb128_is_first_byte=1",
+                        leb128_is_first_byte_next_expr.clone(),
+                        1.expr(),
+                    );
+                },
+            );
+
+            cb.gate(q_enable_expr)
+        });
+
         // start section crosschecks
         dynamic_indexes_chip.lookup_args("start section: func index refs are valid", cs, |vc| {
             let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
@@ -1172,10 +1741,15 @@ impl<F: Field> WasmChip<F> {
         });
         // func section crosschecks
         dynamic_indexes_chip.lookup_args("function section: funcidx refs are valid", cs, |vc| {
-            let cond = and::expr([vc.query_fixed(
-                wasm_function_section_body_chip.config.is_typeidx,
-                Rotation::cur(),
-            )]);
+            // is_typeidx marks the leb128 itself (there is no marker byte), so the index is taken
+            // from the current row at the first leb byte
+            let cond = and::expr([
+                vc.query_fixed(
+                    wasm_function_section_body_chip.config.is_typeidx,
+                    Rotation::cur(),
+                ),
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+            ]);
             let cond = cond
                 * Self::get_selector_expr_enriched_with_error_processing(
                     vc,
@@ -1188,37 +1762,167 @@ impl<F: Field> WasmChip<F> {
             LookupArgsParams {
                 cond,
                 bytecode_number: bytecode_number_expr,
-                index: vc.query_advice(leb128_chip.config.sn, Rotation::next()),
+                index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
                 tag: Tag::TypeIndex.expr(),
                 is_terminator: false.expr(),
             }
         });
         // data section crosschecks
-        dynamic_indexes_chip.lookup_args("data section: memidx refs are valid", cs, |vc| {
-            let cond = vc.query_fixed(
-                wasm_data_section_body_chip.config.is_memidx,
-                Rotation::cur(),
+        if let Some(wasm_data_section_body_chip) = &wasm_data_section_body_chip {
+            dynamic_indexes_chip.lookup_args("data section: memidx refs are valid", cs, |vc| {
+                // is_memidx marks the leb128 itself (there is no marker byte), so the index is
+                // taken from the current row at the first leb byte
+                let cond = and::expr([
+                    vc.query_fixed(
+                        wasm_data_section_body_chip.config.is_memidx,
+                        Rotation::cur(),
+                    ),
+                    vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+                ]);
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: bytecode_number_expr,
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::MemIndex.expr(),
+                    is_terminator: false.expr(),
+                }
+            });
+            dynamic_indexes_chip.lookup_args(
+                "data section: active segments have memory 0 to target",
+                cs,
+                |vc| {
+                    // the classic active segment has no explicit memidx, it implicitly targets
+                    // memory 0 (imported or declared by the memory section)
+                    let cond = and::expr([
+                        vc.query_fixed(
+                            wasm_data_section_body_chip.config.is_mem_segment_type,
+                            Rotation::cur(),
+                        ),
+                        wasm_data_section_body_chip
+                            .config
+                            .mem_segment_type_chip
+                            .config
+                            .value_equals(MemSegmentType::Active, Rotation::cur())(
+                            vc
+                        ),
+                    ]);
+                    let cond = cond
+                        * Self::get_selector_expr_enriched_with_error_processing(
+                            vc,
+                            q_enable,
+                            &shared_state.borrow(),
+                            error_code,
+                        );
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+
+                    LookupArgsParams {
+                        cond,
+                        bytecode_number: bytecode_number_expr,
+                        index: 0.expr(),
+                        tag: Tag::MemIndex.expr(),
+                        is_terminator: false.expr(),
+                    }
+                },
             );
-            let cond = cond
-                * Self::get_selector_expr_enriched_with_error_processing(
+            cs.lookup_any(
+                "data section: the offset opcode memory type is the memory 0 one",
+                |vc| {
+                    let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                    let cond = q_enable_expr.clone()
+                        * vc.query_fixed(
+                            wasm_data_section_body_chip
+                                .config
+                                .is_mem_segment_size_opcode,
+                            Rotation::cur(),
+                        );
+                    // the memory section limit type row, imported memories are never 64-bit ones
+                    let memory_limit_type_fields =
+                        &wasm_memory_section_body_chip.config.limit_type_fields;
+                    let is_memory_row_expr = q_enable_expr.clone()
+                        * vc.query_fixed(memory_limit_type_fields.is_limit_type, Rotation::cur());
+                    let is_imported_memory_row_expr = and::expr([
+                        q_enable_expr,
+                        vc.query_fixed(
+                            wasm_import_section_body_chip
+                                .config
+                                .limit_type_fields
+                                .is_limit_type,
+                            Rotation::cur(),
+                        ),
+                        vc.query_fixed(
+                            wasm_import_section_body_chip.config.is_importdesc_type,
+                            Rotation::prev(),
+                        ),
+                    ]);
+                    let limit_type_chip = &memory_limit_type_fields.limit_type_chip;
+                    let is_memory64_expr = limit_type_chip
+                        .config
+                        .value_equals(LimitType::MinOnly64, Rotation::cur())(
+                        vc
+                    ) + limit_type_chip
+                        .config
+                        .value_equals(LimitType::MinMax64, Rotation::cur())(
+                        vc
+                    );
+
+                    vec![
+                        (
+                            cond.clone() * bytecode_number_expr.clone(),
+                            (is_memory_row_expr.clone() + is_imported_memory_row_expr)
+                                * bytecode_number_expr,
+                        ),
+                        (
+                            cond * vc.query_advice(
+                                wasm_data_section_body_chip.config.is_memory64,
+                                Rotation::cur(),
+                            ),
+                            is_memory_row_expr * is_memory64_expr,
+                        ),
+                    ]
+                },
+            );
+        }
+        // code section crosschecks
+        dynamic_indexes_chip.lookup_args(
+            "code section has valid setup for func indexes",
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
                     vc,
                     q_enable,
                     &shared_state.borrow(),
                     error_code,
                 );
-            let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let cond = and::expr([q_last_expr, q_enable_expr]);
+                let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
 
-            LookupArgsParams {
-                cond,
-                bytecode_number: bytecode_number_expr,
-                index: vc.query_advice(leb128_chip.config.sn, Rotation::next()),
-                tag: Tag::MemIndex.expr(),
-                is_terminator: false.expr(),
-            }
-        });
-        // code section crosschecks
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: bytecode_number_expr,
+                    index: vc.query_advice(func_count, Rotation::cur()),
+                    tag: Tag::FuncIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
         dynamic_indexes_chip.lookup_args(
-            "code section has valid setup for func indexes",
+            "global section has valid setup for global indexes",
             cs,
             |vc| {
                 let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
@@ -1234,56 +1938,261 @@ impl<F: Field> WasmChip<F> {
                 LookupArgsParams {
                     cond,
                     bytecode_number: bytecode_number_expr,
-                    index: vc.query_advice(func_count, Rotation::cur()),
-                    tag: Tag::FuncIndex.expr(),
+                    index: vc.query_advice(global_count, Rotation::cur()),
+                    tag: Tag::GlobalIndex.expr(),
                     is_terminator: true.expr(),
                 }
             },
         );
-        dynamic_indexes_chip.lookup_args("code section: call opcode param is valid", cs, |vc| {
-            let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
-            let cond = and::expr([
-                vc.query_fixed(
-                    wasm_code_section_body_chip.config.is_control_instruction,
-                    Rotation::cur(),
-                ),
-                wasm_code_section_body_chip
-                    .config
-                    .control_instruction_chip
-                    .config
-                    .value_equals(ControlInstruction::Call, Rotation::cur())(vc),
-            ]);
-            let cond = cond
-                * Self::get_selector_expr_enriched_with_error_processing(
+        dynamic_indexes_chip.lookup_args(
+            "memory section has valid setup for mem indexes",
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
                     vc,
                     q_enable,
                     &shared_state.borrow(),
                     error_code,
                 );
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let cond = and::expr([q_last_expr, q_enable_expr]);
+                let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
 
-            LookupArgsParams {
-                cond,
-                bytecode_number: bytecode_number_expr,
-                index: vc.query_advice(leb128_chip.config.sn, Rotation::next()),
-                tag: Tag::FuncIndex.expr(),
-                is_terminator: false.expr(),
-            }
-        });
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: bytecode_number_expr,
+                    index: vc.query_advice(memory_count, Rotation::cur()),
+                    tag: Tag::MemIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+        dynamic_indexes_chip.lookup_args(
+            "table section has valid setup for table indexes",
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let cond = and::expr([q_last_expr, q_enable_expr]);
+                let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: bytecode_number_expr,
+                    index: vc.query_advice(table_count, Rotation::cur()),
+                    tag: Tag::TableIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+        if let Some(wasm_code_section_body_chip) = &wasm_code_section_body_chip {
+            dynamic_indexes_chip.lookup_args(
+                "code section: call opcode param is valid",
+                cs,
+                |vc| {
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                    let cond = and::expr([
+                        vc.query_fixed(
+                            wasm_code_section_body_chip.config.is_control_instruction,
+                            Rotation::cur(),
+                        ),
+                        wasm_code_section_body_chip
+                            .config
+                            .control_instruction_chip
+                            .config
+                            .value_equals(ControlInstruction::Call, Rotation::cur())(
+                            vc
+                        ),
+                    ]);
+                    let cond = cond
+                        * Self::get_selector_expr_enriched_with_error_processing(
+                            vc,
+                            q_enable,
+                            &shared_state.borrow(),
+                            error_code,
+                        );
+
+                    LookupArgsParams {
+                        cond,
+                        bytecode_number: bytecode_number_expr,
+                        index: vc.query_advice(leb128_chip.config.sn, Rotation::next()),
+                        tag: Tag::FuncIndex.expr(),
+                        is_terminator: false.expr(),
+                    }
+                },
+            );
+            dynamic_indexes_chip.lookup_args(
+                "code section: global.get/global.set opcode param is valid",
+                cs,
+                |vc| {
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                    let variable_instruction_chip =
+                        &wasm_code_section_body_chip.config.variable_instruction_chip;
+                    let cond = and::expr([
+                        vc.query_fixed(
+                            wasm_code_section_body_chip.config.is_variable_instruction,
+                            Rotation::cur(),
+                        ),
+                        or::expr([
+                            variable_instruction_chip
+                                .config
+                                .value_equals(VariableInstruction::GlobalGet, Rotation::cur())(
+                                vc
+                            ),
+                            variable_instruction_chip
+                                .config
+                                .value_equals(VariableInstruction::GlobalSet, Rotation::cur())(
+                                vc
+                            ),
+                        ]),
+                    ]);
+                    let cond = cond
+                        * Self::get_selector_expr_enriched_with_error_processing(
+                            vc,
+                            q_enable,
+                            &shared_state.borrow(),
+                            error_code,
+                        );
+
+                    LookupArgsParams {
+                        cond,
+                        bytecode_number: bytecode_number_expr,
+                        index: vc.query_advice(leb128_chip.config.sn, Rotation::next()),
+                        tag: Tag::GlobalIndex.expr(),
+                        is_terminator: false.expr(),
+                    }
+                },
+            );
+            dynamic_indexes_chip.lookup_args(
+                "code section: blocktype type index refs are valid",
+                cs,
+                |vc| {
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                    let cond = and::expr([
+                        vc.query_fixed(
+                            wasm_code_section_body_chip.config.is_blocktype_type_index,
+                            Rotation::cur(),
+                        ),
+                        vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+                    ]);
+                    let cond = cond
+                        * Self::get_selector_expr_enriched_with_error_processing(
+                            vc,
+                            q_enable,
+                            &shared_state.borrow(),
+                            error_code,
+                        );
+
+                    LookupArgsParams {
+                        cond,
+                        bytecode_number: bytecode_number_expr,
+                        index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                        tag: Tag::TypeIndex.expr(),
+                        is_terminator: false.expr(),
+                    }
+                },
+            );
+            dynamic_indexes_chip.lookup_args(
+                "code section: call_indirect type index refs are valid",
+                cs,
+                |vc| {
+                    let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
+                    let cond = and::expr([
+                        vc.query_fixed(
+                            wasm_code_section_body_chip
+                                .config
+                                .is_call_indirect_type_index,
+                            Rotation::cur(),
+                        ),
+                        vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur()),
+                    ]);
+                    let cond = cond
+                        * Self::get_selector_expr_enriched_with_error_processing(
+                            vc,
+                            q_enable,
+                            &shared_state.borrow(),
+                            error_code,
+                        );
+
+                    LookupArgsParams {
+                        cond,
+                        bytecode_number: bytecode_number_expr,
+                        index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                        tag: Tag::TypeIndex.expr(),
+                        is_terminator: false.expr(),
+                    }
+                },
+            );
+        }
+        // opaque (structure only) section bodies set up the indexes their body chips would
+        dynamic_indexes_chip.lookup_args(
+            "opaque code section has valid setup for func body indexes",
+            cs,
+            |vc| {
+                let cond = vc.query_fixed(is_opaque_funcs_count, Rotation::cur());
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::FuncBodyIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+        dynamic_indexes_chip.lookup_args(
+            "opaque data section has valid setup for data indexes",
+            cs,
+            |vc| {
+                let cond = vc.query_fixed(is_opaque_data_items_count, Rotation::cur());
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::DataIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+
+        column_stats.record("wasm chip", cs);
 
         let config = WasmConfig {
             _marker: PhantomData,
 
             bytecode_number,
             poseidon_table,
+            code_hash_chip,
             wb_table,
             q_enable,
             q_first,
             q_last,
             range_table_config_0_256,
             section_id_range_table_config,
+            section_rank_table_config,
             index_at_magic_prefix,
             magic_prefix_count,
             section_id,
+            ordered_section_id,
+            is_custom_section_chip,
             is_section_id,
             is_section_len,
             is_section_body,
@@ -1296,22 +2205,29 @@ impl<F: Field> WasmChip<F> {
             wasm_memory_section_body_chip,
             wasm_export_section_body_chip,
             wasm_data_section_body_chip,
+            wasm_data_count_section_body_chip,
             wasm_global_section_body_chip,
             wasm_code_section_body_chip,
             wasm_start_section_body_chip,
             wasm_table_section_body_chip,
             wasm_element_section_body_chip,
+            wasm_custom_section_body_chip,
             section_id_lt_chip,
             range_table_config_0_128,
+            range_table_config_0_65536,
             dynamic_indexes_chip,
             shared_state,
             func_count,
+            global_count,
+            memory_count,
+            table_count,
             block_depth_level,
             body_byte_rev_index_l1,
             body_byte_rev_index_l2,
             body_item_rev_count_l1,
             body_item_rev_count_l2,
             error_code,
+            column_stats,
         };
 
         config
@@ -1320,78 +2236,468 @@ impl<F: Field> WasmChip<F> {
     pub fn construct(config: WasmConfig<F>) -> Self {
         let instance = Self {
             config,
+            instrument: WasmInstrument::default(),
+            decoded_sections: vec![],
+            is_error_processed: false,
             _marker: PhantomData,
         };
         instance
     }
 
-    pub fn assign_auto(
-        &mut self,
-        region: &mut Region<F>,
-        wb: &WasmBytecode,
-        wb_offset: usize,
-        assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
-        let result = self.assign_auto_internal(region, wb, wb_offset, assign_delta);
-        let assign_delta = assign_delta
-            + if self.config.wb_table.zero_row_enabled {
-                1
-            } else {
-                0
-            };
+    /// Rows consumed by the section chips during the last `assign_auto`.
+    pub fn instrument(&self) -> &WasmInstrument {
+        &self.instrument
+    }
 
-        if let Err(e) = result {
-            return if is_recoverable_error(&e)
-                & self.config.shared_state.borrow().error_processing_enabled
-            {
-                debug!("detected recoverable error: {:?}", e);
-                match e {
-                    Error::IndexOutOfBoundsAt(offset) |
-                    Error::AssignAt(offset) |
-                    Error::ParseOpcodeFailedAt(offset) |
-                    Error::InvalidByteValueAt(offset) |
-                    Error::InvalidEnumValueAt(offset) |
-                    Error::ComputeValueAt(offset) => {
-                        debug!("recoverable error offset: {}", offset);
-                        self.shared_state().borrow_mut().error_code = ErrorCode::Error as u64;
-                        // cannot use offset received from error because of forward checks 
-                        // and also structure markups happen after return with error 
-                        for offset in 0..wb.bytes.len() {
-                            self.assign(region, wb, offset, assign_delta, &[AssignType::ErrorCode], ErrorCode::Error as u64, None)?;
-                        }
-                    }
+    /// Sections assigned during the last `assign_auto` in bytecode order.
+    pub fn decoded_sections(&self) -> &[DecodedSection] {
+        &self.decoded_sections
+    }
 
-                    Error::IndexOutOfBoundsSimple
-                    | Error::Leb128EncodeSigned
-                    | Error::Leb128EncodeUnsigned
-                    | Error::Leb128MaxBytes
-                    | Error::InvalidEnumValue
-                    | Error::ComputationFailed => {
-                        return Err(Error::FatalRecoverableButNotProcessed(
-                            "recoverable error without offset param must be converted inside circuit to sustain error processing mechanics".to_string()
-                        ))
+    /// binds every row of `metadata_table` to the module section it describes, the EVM circuit
+    /// reads from it the module properties it can't get from the bytecode table (memory limits,
+    /// branch targets, function types, table elements, etc.). Branch targets and `call` /
+    /// `call_indirect` immediates come from the code section body chip and data segments from the
+    /// data section body chip, there are none if `structure_only_enabled`
+    pub fn configure_metadata_table(
+        cs: &mut ConstraintSystem<F>,
+        config: &WasmConfig<F>,
+        metadata_table: &WasmMetadataTable,
+    ) {
+        cs.lookup_any(
+            "wasm metadata table rows come from the module sections",
+            |vc| {
+                let q_enable_expr = vc.query_fixed(metadata_table.q_enable, Rotation::cur());
+                let mut table_exprs = config
+                    .wasm_memory_section_body_chip
+                    .metadata_table_exprs(vc);
+                // the section chips describe disjoint rows, so their exprs add up
+                for section_exprs in [
+                    config.wasm_type_section_body_chip.metadata_table_exprs(vc),
+                    config
+                        .wasm_import_section_body_chip
+                        .metadata_table_exprs(vc),
+                    config
+                        .wasm_function_section_body_chip
+                        .metadata_table_exprs(vc),
+                    config
+                        .wasm_global_section_body_chip
+                        .metadata_table_exprs(vc),
+                    config
+                        .wasm_export_section_body_chip
+                        .metadata_table_exprs(vc),
+                    config
+                        .wasm_element_section_body_chip
+                        .metadata_table_exprs(vc),
+                ] {
+                    for (table_expr, section_expr) in table_exprs.iter_mut().zip(section_exprs) {
+                        *table_expr = table_expr.clone() + section_expr;
+                    }
+                }
+                if let Some(chip) = &config.wasm_code_section_body_chip {
+                    for (table_expr, code_section_expr) in
+                        table_exprs.iter_mut().zip(chip.metadata_table_exprs(vc))
+                    {
+                        *table_expr = table_expr.clone() + code_section_expr;
+                    }
+                }
+                if let Some(chip) = &config.wasm_data_section_body_chip {
+                    for (table_expr, data_section_expr) in
+                        table_exprs.iter_mut().zip(chip.metadata_table_exprs(vc))
+                    {
+                        *table_expr = table_expr.clone() + data_section_expr;
                     }
-
-                    _ => return Err(e)
                 }
 
-                Ok(wb.bytes.len() + assign_delta)
-            } else {
-                Err(e)
-            };
+                [
+                    metadata_table.code_hash,
+                    metadata_table.tag,
+                    metadata_table.index,
+                    metadata_table.value,
+                ]
+                .into_iter()
+                .zip(table_exprs)
+                .map(|(column, table_expr)| {
+                    (
+                        q_enable_expr.clone() * vc.query_advice(column, Rotation::cur()),
+                        table_expr,
+                    )
+                })
+                .collect()
+            },
+        );
+    }
+
+    /// names the columns of the chip and of every section chip, makes `MockProver` failure dumps
+    /// readable
+    pub fn annotate_columns(&self, region: &mut Region<F>) {
+        let config = &self.config;
+        annotate_region_columns(
+            region,
+            "wasm",
+            &[
+                ("q_enable", config.q_enable.into()),
+                ("q_first", config.q_first.into()),
+                ("q_last", config.q_last.into()),
+                ("is_section_id", config.is_section_id.into()),
+                ("is_section_len", config.is_section_len.into()),
+                ("is_section_body", config.is_section_body.into()),
+                (
+                    "is_opaque_section_body",
+                    config.is_opaque_section_body.into(),
+                ),
+                ("is_opaque_funcs_count", config.is_opaque_funcs_count.into()),
+                (
+                    "is_opaque_data_items_count",
+                    config.is_opaque_data_items_count.into(),
+                ),
+                ("bytecode_number", config.bytecode_number.into()),
+                ("section_id", config.section_id.into()),
+                ("ordered_section_id", config.ordered_section_id.into()),
+                ("func_count", config.func_count.into()),
+                ("global_count", config.global_count.into()),
+                ("memory_count", config.memory_count.into()),
+                ("table_count", config.table_count.into()),
+                ("block_depth_level", config.block_depth_level.into()),
+                (
+                    "body_byte_rev_index_l1",
+                    config.body_byte_rev_index_l1.into(),
+                ),
+                (
+                    "body_byte_rev_index_l2",
+                    config.body_byte_rev_index_l2.into(),
+                ),
+                (
+                    "body_item_rev_count_l1",
+                    config.body_item_rev_count_l1.into(),
+                ),
+                (
+                    "body_item_rev_count_l2",
+                    config.body_item_rev_count_l2.into(),
+                ),
+                ("error_code", config.error_code.into()),
+            ],
+        );
+        config.wasm_type_section_body_chip.annotate_columns(region);
+        config
+            .wasm_import_section_body_chip
+            .annotate_columns(region);
+        config
+            .wasm_function_section_body_chip
+            .annotate_columns(region);
+        config.wasm_table_section_body_chip.annotate_columns(region);
+        config
+            .wasm_memory_section_body_chip
+            .annotate_columns(region);
+        config
+            .wasm_global_section_body_chip
+            .annotate_columns(region);
+        config
+            .wasm_export_section_body_chip
+            .annotate_columns(region);
+        config.wasm_start_section_body_chip.annotate_columns(region);
+        config
+            .wasm_element_section_body_chip
+            .annotate_columns(region);
+        config
+            .wasm_data_count_section_body_chip
+            .annotate_columns(region);
+        config
+            .wasm_custom_section_body_chip
+            .annotate_columns(region);
+        if let Some(chip) = &config.wasm_code_section_body_chip {
+            chip.annotate_columns(region);
+        }
+        if let Some(chip) = &config.wasm_data_section_body_chip {
+            chip.annotate_columns(region);
+        }
+        config.code_hash_chip.annotate_columns(region);
+    }
+
+    /// assigns `wb` (bytecode rows and dynamic indexes), returns the new assign delta.
+    ///
+    /// runs `assign_prefix`, `assign_section` for each of `section_spans` and
+    /// `assign_dynamic_indexes`, see them for driving the assignment section by section
+    pub fn assign_auto(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        self.assign_prefix(region, wb, wb_offset, assign_delta)?;
+        for section_span in self.section_spans(region, wb, assign_delta)? {
+            self.assign_section(region, wb, &section_span, assign_delta)?;
+        }
+        self.assign_dynamic_indexes(region, wb, assign_delta)
+    }
+
+    /// a recoverable error of an assignment step marks every row of `wb` with the error code
+    /// (if error processing is enabled), the remaining steps of `wb` assign nothing then
+    fn process_assign_result(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        assign_delta: AssignDeltaType,
+        result: Result<(), Error>,
+    ) -> Result<(), Error> {
+        let e = match result {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if !(is_recoverable_error(&e) & self.config.shared_state.borrow().error_processing_enabled)
+        {
+            return Err(e);
+        }
+        debug!("detected recoverable error: {:?}", e);
+        let assign_delta = assign_delta + self.zero_row_shift();
+        match e {
+            Error::IndexOutOfBoundsAt(_) |
+            Error::AssignAt(_) |
+            Error::ParseOpcodeFailedAt(_) |
+            Error::InvalidByteValueAt(_) |
+            Error::InvalidEnumValueAt(_) |
+            Error::ComputeValueAt(_) |
+            Error::InvalidSectionId(_) |
+            Error::SectionLenMismatch |
+            Error::BlockLevelUnderflow |
+            Error::UnexpectedEndOfBytes(_) |
+            Error::DuplicateExportNameAt(_) |
+            Error::UnsupportedWasmVersion(_) |
+            Error::DuplicateSection(_) |
+            Error::FuncBodyLenExceedsModule(_) |
+            Error::SectionLenOverflow(_) |
+            Error::MissingRequiredSection(_) => {
+                self.shared_state().borrow_mut().error_code = ErrorCode::Error as u64;
+                // cannot use offset received from error because of forward checks 
+                // and also structure markups happen after return with error 
+                for offset in 0..wb.bytes.len() {
+                    self.assign(region, wb, offset, assign_delta, &[AssignType::ErrorCode], ErrorCode::Error as u64, None)?;
+                }
+                self.is_error_processed = true;
+                Ok(())
+            }
+
+            Error::IndexOutOfBoundsSimple
+            | Error::Leb128EncodeSigned
+            | Error::Leb128EncodeUnsigned
+            | Error::Leb128MaxBytes
+            | Error::InvalidEnumValue
+            | Error::ComputationFailed => {
+                Err(Error::FatalRecoverableButNotProcessed(
+                    "recoverable error without offset param must be converted inside circuit to sustain error processing mechanics".to_string()
+                ))
+            }
+
+            _ => Err(e)
         }
+    }
+
+    /// loads every bytecode of `wbs` into the bytecode table and assigns it right after the
+    /// previous one, so each bytecode gets its own `bytecode_number` (starting from the current
+    /// one of the shared state)
+    ///
+    /// returns new assign delta
+    pub fn assign_auto_bytecodes(
+        &mut self,
+        region: &mut Region<F>,
+        wbs: &[WasmBytecode],
+        assign_delta: AssignDeltaType,
+    ) -> Result<AssignDeltaType, Error> {
+        let mut assign_delta = assign_delta;
+        for wb in wbs {
+            self.load(region, wb, assign_delta)?;
+            assign_delta = self.assign_auto(region, wb, 0, assign_delta)?;
+        }
+
+        Ok(assign_delta)
+    }
+
+    /// runs `assign_auto` on `bytes` against a region that only records its shape, returns the
+    /// number of region rows the bytecode would occupy if it were the only one assigned (at
+    /// `assign_delta` 0, one more row is needed if the bytecode table has a zero row).
+    ///
+    /// both the bytecode rows and the dynamic indexes rows are accounted, which makes it possible
+    /// to size `k` before the circuit is built
+    pub fn assign_auto_dry_run(bytes: &[u8]) -> Result<usize, Error> {
+        let mut cs = ConstraintSystem::<F>::default();
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+        // no zero row, the errors report bytecode offsets
+        let wb_table = Rc::new(WasmBytecodeTable::construct(&mut cs, false));
+        let config = Self::configure(&mut cs, wb_table, shared_state);
+        let mut chip = Self::construct(config);
+        chip.config.shared_state.borrow_mut().reset();
+
+        let wb = WasmBytecode::new(bytes.to_vec());
+        let mut region_shape = RegionShape::new(0.into());
+        {
+            let mut region = Region::from(&mut region_shape as &mut dyn RegionLayouter<F>);
+            chip.assign_auto(&mut region, &wb, 0, 0)?;
+        }
+
+        Ok(region_shape.row_count())
+    }
+
+    /// checks whether `bytes` would prove: assigns the module with a freshly configured standalone
+    /// circuit (`k` is sized by `assign_auto_dry_run`) and verifies it with `MockProver`, no
+    /// proving key is involved.
+    ///
+    /// Fails with [ValidateError::Synthesis] if the circuit can't be synthesized at all and with
+    /// [ValidateError::Unsatisfied] if the synthesized circuit violates its constraints.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn validate(bytes: &[u8]) -> Result<(), ValidateError> {
+        let rows = Self::assign_auto_dry_run(bytes)?;
+        let k = log2_ceil(rows + VALIDATE_SPARE_ROWS).max(VALIDATE_MIN_K);
+        let circuit = WasmCircuit::<F>::new(vec![WasmBytecode::new(bytes.to_vec())]);
+        let prover = MockProver::run(k, &circuit, vec![])
+            .map_err(|e| Error::FatalMockProver(format!("{:?}", e)))?;
+        prover.verify().map_err(ValidateError::Unsatisfied)
+    }
+
+    /// rows the bytecode is shifted by if the bytecode table has a zero row
+    fn zero_row_shift(&self) -> usize {
+        if self.config.wb_table.zero_row_enabled {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn assign_global_count(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+        global_count: usize,
+    ) -> Result<(), Error> {
+        region
+            .assign_advice(
+                || {
+                    format!(
+                        "assign 'global_count' val {} at {}",
+                        global_count, assign_offset
+                    )
+                },
+                self.config.global_count,
+                assign_offset,
+                || Value::known(F::from(global_count as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        Ok(())
+    }
+
+    fn assign_memory_count(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+        memory_count: usize,
+    ) -> Result<(), Error> {
+        region
+            .assign_advice(
+                || {
+                    format!(
+                        "assign 'memory_count' val {} at {}",
+                        memory_count, assign_offset
+                    )
+                },
+                self.config.memory_count,
+                assign_offset,
+                || Value::known(F::from(memory_count as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        Ok(())
+    }
+
+    fn assign_table_count(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+        table_count: usize,
+    ) -> Result<(), Error> {
+        region
+            .assign_advice(
+                || {
+                    format!(
+                        "assign 'table_count' val {} at {}",
+                        table_count, assign_offset
+                    )
+                },
+                self.config.table_count,
+                assign_offset,
+                || Value::known(F::from(table_count as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        Ok(())
+    }
+
+    /// first step of the assignment of `wb`: `q_first`/`q_last`, the magic prefix and the
+    /// version. The state left by the previous bytecode is reset.
+    ///
+    /// the steps (`assign_prefix`, `assign_section` for each of `section_spans`,
+    /// `assign_dynamic_indexes`) let the caller observe the chip between the sections, e.g. read
+    /// `decoded_sections`/`instrument` of the sections assigned so far. All of them must get the
+    /// same region: the offsets are absolute within it and the gates read the previous and the
+    /// next rows across section boundaries, so a bytecode can't be split into several regions
+    pub fn assign_prefix(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(), Error> {
+        // the rows of the whole bytecode must be addressable, so the shifts below can't overflow
+        checked_assign_offset(
+            wb_offset + wb.bytes.len() + self.zero_row_shift(),
+            assign_delta,
+        )?;
+        self.annotate_columns(region);
+        // every bytecode starts from a clean state, so assigning the same bytecode twice
+        // accumulates nothing but the region layout
+        self.config.shared_state.borrow_mut().bytecode_state_reset();
+        self.instrument.reset();
+        self.decoded_sections.clear();
+        self.is_error_processed = false;
+        let result = self.assign_prefix_internal(region, wb, wb_offset, assign_delta);
+        self.process_assign_result(region, wb, assign_delta, result)
+    }
 
-        self.config.shared_state.borrow_mut().bytecode_number_inc();
-        return Ok(wb.bytes.len() + assign_delta);
+    /// spans of the sections of `wb` to pass to `assign_section` one by one in the order given.
+    /// Empty if an earlier step of `wb` failed with an error marked in the circuit
+    pub fn section_spans(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        assign_delta: AssignDeltaType,
+    ) -> Result<Vec<SectionSpan>, Error> {
+        if self.is_error_processed {
+            return Ok(vec![]);
+        }
+        // the parser reports bytecode offsets, they are moved to the assigned rows
+        let result = parse_sections(&wb.bytes).map_err(|e| match e {
+            Error::ComputeValueAt(offset) => match checked_assign_offset(offset, assign_delta) {
+                Ok(assign_offset) => Error::ComputeValueAt(assign_offset + self.zero_row_shift()),
+                Err(e) => e,
+            },
+            _ => e,
+        });
+        match result {
+            Ok(section_spans) => Ok(section_spans),
+            Err(e) => {
+                self.process_assign_result(region, wb, assign_delta, Err(e))?;
+                Ok(vec![])
+            }
+        }
     }
 
-    fn assign_auto_internal(
+    fn assign_prefix_internal(
         &mut self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<OffsetType, Error> {
+    ) -> Result<(), Error> {
         debug!("wb.bytes {:x?}", wb.bytes);
         self.assign(
             region,
@@ -1402,19 +2708,17 @@ impl<F: Field> WasmChip<F> {
             1,
             None,
         )?;
-        // TODO needed for multibytecode assignments, refactor
-        self.config.shared_state.borrow_mut().func_count = 0;
-        self.assign_func_count(region, wb_offset + assign_delta)?;
-        let assign_delta = assign_delta
-            + if self.config.wb_table.zero_row_enabled {
-                1
-            } else {
-                0
-            };
+        self.assign_func_count(region, checked_assign_offset(wb_offset, assign_delta)?)?;
+        let assign_delta = assign_delta + self.zero_row_shift();
+        let last_byte_offset = wb
+            .bytes
+            .len()
+            .checked_sub(1)
+            .ok_or(Error::IndexOutOfBoundsAt(wb_offset))?;
         self.assign(
             region,
             wb,
-            wb_offset + wb.bytes.len() - 1,
+            wb_offset + last_byte_offset,
             assign_delta,
             &[AssignType::QLast],
             1,
@@ -1422,11 +2726,15 @@ impl<F: Field> WasmChip<F> {
         )?;
 
         // check magic prefix and version
-        let assign_offset_start = wb_offset + assign_delta + WASM_MAGIC_PREFIX_START_INDEX;
+        let assign_offset_start =
+            checked_assign_offset(wb_offset + WASM_MAGIC_PREFIX_START_INDEX, assign_delta)?;
         for (idx, ch) in WASM_MAGIC_PREFIX.chars().enumerate() {
             let wb_offset = wb_offset + WASM_MAGIC_PREFIX_START_INDEX + idx;
-            let assign_offset = wb_offset + assign_delta;
+            let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
             self.assign_func_count(region, assign_offset)?;
+            self.assign_global_count(region, assign_offset, 0)?;
+            self.assign_memory_count(region, assign_offset, 0)?;
+            self.assign_table_count(region, assign_offset, 0)?;
             self.assign(region, &wb, wb_offset, assign_delta, &[], 1, None)?;
             let byte_val = *wb
                 .bytes
@@ -1436,227 +2744,584 @@ impl<F: Field> WasmChip<F> {
                 return Err(Error::InvalidByteValueAt(assign_offset_start));
             }
         }
-        let assign_offset_start = wb_offset + assign_delta + WASM_VERSION_PREFIX_START_INDEX;
-        for (idx, ch) in WASM_VERSION_PREFIX.chars().enumerate() {
+        let assign_offset_start =
+            checked_assign_offset(wb_offset + WASM_VERSION_PREFIX_START_INDEX, assign_delta)?;
+        for idx in 0..WASM_VERSION_PREFIX_LEN {
             let wb_offset = wb_offset + WASM_VERSION_PREFIX_START_INDEX + idx;
-            let assign_offset = wb_offset + assign_delta;
+            let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
             self.assign_func_count(region, assign_offset)?;
+            self.assign_global_count(region, assign_offset, 0)?;
+            self.assign_memory_count(region, assign_offset, 0)?;
+            self.assign_table_count(region, assign_offset, 0)?;
             self.assign(region, &wb, wb_offset, assign_delta, &[], 1, None)?;
-            let byte_val = *wb
-                .bytes
-                .get(wb_offset)
-                .ok_or(Error::IndexOutOfBoundsAt(assign_offset_start))?;
-            if byte_val != digit_char_to_number(&ch) {
-                return Err(Error::InvalidByteValueAt(assign_offset_start));
-            }
+        }
+        let version = parse_version(wb.bytes.get(wb_offset..).unwrap_or_default())
+            .map_err(remap_error(Error::IndexOutOfBoundsAt(assign_offset_start)))?;
+        if version != WASM_VERSION {
+            return Err(Error::UnsupportedWasmVersion(version));
         }
 
-        let mut wb_offset = WASM_SECTIONS_START_INDEX;
-        let mut section_id_prev: i64 = SECTION_ID_DEFAULT as i64;
-        while wb_offset < wb.bytes.len() {
-            let section_start_offset = wb_offset;
-            let section_len_start_offset = section_start_offset + 1;
-            let section_id = *wb
-                .get(wb_offset)
-                .ok_or(error_index_out_of_bounds(wb_offset + assign_delta))?
-                as u64;
-            wb_offset += 1;
-            let (section_len, section_len_leb_bytes_count) =
-                wasm_compute_section_len(&wb.bytes, wb_offset)
-                    .map_err(remap_error_to_compute_value_at(wb_offset + assign_delta))?;
-            wb_offset += section_len_leb_bytes_count as usize;
-            wb_offset += section_len;
-            let section_body_start_offset =
-                section_len_start_offset + section_len_leb_bytes_count as usize;
-            let section_len_end_offset = section_body_start_offset - 1;
-            let section_body_end_offset =
-                section_start_offset + section_len_leb_bytes_count as usize + section_len;
-            let section_end_offset = section_body_end_offset;
-
-            for wb_offset in section_start_offset..=section_end_offset {
-                if wb_offset == section_start_offset {
-                    let wasm_section: WasmSection = (section_id as i32).try_into().map_err(
-                        remap_error_to_invalid_enum_value_at(wb_offset + assign_delta),
-                    )?;
-                    debug!(
-                        "wasm_section {:?}(id={}) at offset {} (assign_offset {}) offset_end {} (assign_offset {}) section_len {} bytecode(hex) {:x?}",
-                        wasm_section,
-                        section_id,
-                        wb_offset,
-                        wb_offset+assign_delta,
-                        wb_offset+section_len-1,
-                        wb_offset+section_len-1+assign_delta,
-                        section_len,
-                        &wb.bytes[section_start_offset..=section_end_offset],
-                    );
-                    self.assign_func_count(region, wb_offset + assign_delta)?;
-
-                    let mut next_section_offset = 0;
-                    let section_body_offset = wb_offset + 1; // skip section_id
-                    let section_len_last_byte_offset =
-                        leb128_compute_last_byte_offset(&wb.bytes[..], section_body_offset)
-                            .map_err(remap_error_to_compute_value_at(
-                                section_body_offset + assign_delta,
-                            ))?;
-                    for offset in section_len_last_byte_offset..=section_body_end_offset {
-                        self.assign(
-                            region,
-                            &wb,
-                            offset,
+        Ok(())
+    }
+
+    /// assigns a single section (id, len and body) of `wb` into the region of `assign_prefix` and
+    /// returns the offset right after it. Sections must be assigned in bytecode order since the
+    /// state they share is carried by `SharedState`
+    pub fn assign_section(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        section_span: &SectionSpan,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        if !self.is_error_processed {
+            let result = self
+                .assign_section_internal(region, wb, section_span, assign_delta)
+                .map(|_| ());
+            self.process_assign_result(region, wb, assign_delta, result)?;
+        }
+
+        Ok(section_span.end_offset())
+    }
+
+    fn assign_section_internal(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        section_span: &SectionSpan,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        let assign_delta = assign_delta + self.zero_row_shift();
+        let section_start_offset = section_span.id_offset;
+        let section_len_start_offset = section_span.len_offset;
+        let section_id = section_span.section_id as u64;
+        let section_len = section_span.body_len;
+        let section_body_start_offset = section_span.body_offset;
+        let section_body_end_offset = section_span.end_offset() - 1;
+        let section_end_offset = section_body_end_offset;
+        let mut section_id_prev = self.config.shared_state.borrow().section_id_prev;
+        let mut global_count = self.config.shared_state.borrow().global_count;
+        let mut memory_count = self.config.shared_state.borrow().memory_count;
+        let mut table_count = self.config.shared_state.borrow().table_count;
+        let section_rank = WasmSection::try_from(section_id as i32)
+            .map_err(remap_error(Error::InvalidSectionId(section_id as u8)))?
+            .rank();
+        if section_id != WasmSection::Custom as u64 && section_rank == section_id_prev {
+            return Err(Error::DuplicateSection(section_id as u8));
+        }
+
+        for wb_offset in section_start_offset..=section_end_offset {
+            if wb_offset == section_start_offset {
+                let wasm_section: WasmSection = (section_id as i32)
+                    .try_into()
+                    .map_err(remap_error(Error::InvalidSectionId(section_id as u8)))?;
+                self.config
+                    .shared_state
+                    .borrow_mut()
+                    .section_present_set(section_id as u8);
+                debug!(
+                    "wasm_section {:?}(id={}) at offset {} (assign_offset {}) offset_end {} (assign_offset {}) section_len {} bytecode(hex) {:x?}",
+                    wasm_section,
+                    section_id,
+                    wb_offset,
+                    wb_offset+assign_delta,
+                    wb_offset+section_len-1,
+                    wb_offset+section_len-1+assign_delta,
+                    section_len,
+                    &wb.bytes[section_start_offset..=section_end_offset],
+                );
+                self.assign_func_count(region, checked_assign_offset(wb_offset, assign_delta)?)?;
+
+                let mut next_section_offset = 0;
+                // sections without a leading item count (custom, start and data count) and empty
+                // bodies are left to `DecodedSection::decode`
+                let mut section_summary: Option<SectionParseSummary> = None;
+                let section_body_offset = wb_offset + 1; // skip section_id
+                let section_len_last_byte_offset =
+                    leb128_compute_last_byte_offset(&wb.bytes[..], section_body_offset).map_err(
+                        remap_error_to_compute_value_at(checked_assign_offset(
+                            section_body_offset,
                             assign_delta,
-                            &[AssignType::BodyByteRevIndexL1],
-                            (section_body_end_offset - offset) as u64,
-                            None,
-                        )?;
+                        )?),
+                    )?;
+                for offset in section_len_last_byte_offset..=section_body_end_offset {
+                    self.assign(
+                        region,
+                        &wb,
+                        offset,
+                        assign_delta,
+                        &[AssignType::BodyByteRevIndexL1],
+                        (section_body_end_offset - offset) as u64,
+                        None,
+                    )?;
+                }
+                for offset in section_body_offset..=section_len_last_byte_offset {
+                    self.assign_func_count(region, checked_assign_offset(offset, assign_delta)?)?;
+                }
+                let section_body_offset = section_len_last_byte_offset + 1;
+                match wasm_section {
+                    // nothing to assign for an empty body, the len is followed by the next section
+                    _ if section_len == 0 && !matches!(wasm_section, WasmSection::Custom) => {
+                        next_section_offset = section_body_offset;
                     }
-                    for offset in section_body_offset..=section_len_last_byte_offset {
-                        self.assign_func_count(region, offset + assign_delta)?;
+                    WasmSection::Custom => {
+                        next_section_offset = self
+                            .config
+                            .wasm_custom_section_body_chip
+                            .assign_auto(
+                                region,
+                                wb,
+                                section_body_offset,
+                                assign_delta,
+                                section_body_end_offset + 1 - section_body_offset,
+                            )
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
                     }
-                    let section_body_offset = section_len_last_byte_offset + 1;
-                    match wasm_section {
-                        WasmSection::Type => {
-                            next_section_offset = self
-                                .config
-                                .wasm_type_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Import => {
-                            next_section_offset = self
-                                .config
-                                .wasm_import_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Function => {
-                            next_section_offset = self
-                                .config
-                                .wasm_function_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Table => {
-                            next_section_offset = self
-                                .config
-                                .wasm_table_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Memory => {
-                            next_section_offset = self
-                                .config
-                                .wasm_memory_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Global => {
-                            next_section_offset = self
-                                .config
-                                .wasm_global_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Export => {
-                            next_section_offset = self
-                                .config
-                                .wasm_export_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Start => {
-                            next_section_offset = self
-                                .config
-                                .wasm_start_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Element => {
-                            next_section_offset = self
-                                .config
-                                .wasm_element_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Code => {
-                            next_section_offset = self
-                                .config
-                                .wasm_code_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        WasmSection::Data => {
-                            next_section_offset = self
-                                .config
-                                .wasm_data_section_body_chip
-                                .assign_auto(region, wb, section_body_offset, assign_delta)
-                                .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                        }
-                        _ => {
-                            return Err(Error::FatalUnsupportedValue(format!(
-                                "unsupported section value '{:x?}'",
-                                wasm_section
-                            )))
-                        }
+                    WasmSection::Type => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_type_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Import => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_import_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Function => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_function_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Table => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_table_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Memory => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_memory_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Global => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_global_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Export => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_export_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Start => {
+                        next_section_offset = self
+                            .config
+                            .wasm_start_section_body_chip
+                            .assign_auto(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                    }
+                    WasmSection::Element => {
+                        let (offset, summary) = self
+                            .config
+                            .wasm_element_section_body_chip
+                            .assign_auto_with_summary(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Code => {
+                        self.config
+                            .shared_state
+                            .borrow_mut()
+                            .is_code_section_present = true;
+                        let (offset, summary) =
+                            match self.config.wasm_code_section_body_chip.clone() {
+                                Some(chip) => chip
+                                    .assign_auto_with_summary(
+                                        region,
+                                        wb,
+                                        section_body_offset,
+                                        assign_delta,
+                                    )
+                                    .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                        wb_offset,
+                                        assign_delta,
+                                    )?))?,
+                                None => self.assign_opaque_section_body(
+                                    region,
+                                    wb,
+                                    wasm_section,
+                                    section_body_offset,
+                                    section_body_end_offset,
+                                    assign_delta,
+                                )?,
+                            };
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::Data => {
+                        let (offset, summary) =
+                            match self.config.wasm_data_section_body_chip.clone() {
+                                Some(chip) => chip
+                                    .assign_auto_with_summary(
+                                        region,
+                                        wb,
+                                        section_body_offset,
+                                        assign_delta,
+                                    )
+                                    .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                        wb_offset,
+                                        assign_delta,
+                                    )?))?,
+                                None => self.assign_opaque_section_body(
+                                    region,
+                                    wb,
+                                    wasm_section,
+                                    section_body_offset,
+                                    section_body_end_offset,
+                                    assign_delta,
+                                )?,
+                            };
+                        next_section_offset = offset;
+                        section_summary = Some(summary);
+                    }
+                    WasmSection::DataCount => {
+                        next_section_offset = self
+                            .config
+                            .wasm_data_count_section_body_chip
+                            .assign_auto(region, wb, section_body_offset, assign_delta)
+                            .map_err(remap_error_to_assign_at(checked_assign_offset(
+                                wb_offset,
+                                assign_delta,
+                            )?))?;
+                    }
+                    _ => {
+                        return Err(Error::FatalUnsupportedValue(format!(
+                            "unsupported section value '{:x?}'",
+                            wasm_section
+                        )))
                     }
-                    debug!(
-                        "wasm_section {:?} section_body_offset {} after assign_auto next_section_offset {}",
-                        wasm_section,
-                        section_body_offset,
-                        next_section_offset,
-                    );
                 }
-                region
-                    .assign_advice(
-                        || format!("assign at {} section_id val {}", wb_offset, section_id),
-                        self.config.section_id,
-                        wb_offset + assign_delta,
-                        || Value::known(F::from(section_id)),
-                    )
-                    .map_err(remap_error_to_assign_at(wb_offset))?;
-                self.config
-                    .section_id_lt_chip
-                    .assign(
-                        region,
-                        wb_offset + assign_delta,
-                        F::from(section_id_prev as u64),
-                        F::from(section_id),
-                    )
-                    .map_err(remap_error_to_assign_at(wb_offset + assign_delta))?;
-                section_id_prev = section_id as i64;
+                debug!(
+                    "wasm_section {:?} section_body_offset {} after assign_auto next_section_offset {}",
+                    wasm_section,
+                    section_body_offset,
+                    next_section_offset,
+                );
+                if next_section_offset != section_body_end_offset + 1 {
+                    return Err(Error::SectionLenMismatch);
+                }
+                self.instrument
+                    .on_section_assigned(wasm_section, next_section_offset - section_body_offset);
+                let decoded_section = match section_summary {
+                    Some(summary) => {
+                        DecodedSection::from_summary(wasm_section, section_span, &summary)
+                    }
+                    None => DecodedSection::decode(&wb.bytes, wasm_section, section_span).map_err(
+                        remap_error_to_compute_value_at(checked_assign_offset(
+                            wb_offset,
+                            assign_delta,
+                        )?),
+                    )?,
+                };
+                self.decoded_sections.push(decoded_section);
+            }
+            if let Some(count) = self
+                .config
+                .shared_state
+                .borrow()
+                .global_count_changes
+                .get(&wb_offset)
+            {
+                global_count = *count;
+            }
+            self.assign_global_count(
+                region,
+                checked_assign_offset(wb_offset, assign_delta)?,
+                global_count,
+            )?;
+            if let Some(count) = self
+                .config
+                .shared_state
+                .borrow()
+                .memory_count_changes
+                .get(&wb_offset)
+            {
+                memory_count = *count;
             }
+            self.assign_memory_count(
+                region,
+                checked_assign_offset(wb_offset, assign_delta)?,
+                memory_count,
+            )?;
+            if let Some(count) = self
+                .config
+                .shared_state
+                .borrow()
+                .table_count_changes
+                .get(&wb_offset)
+            {
+                table_count = *count;
+            }
+            self.assign_table_count(
+                region,
+                checked_assign_offset(wb_offset, assign_delta)?,
+                table_count,
+            )?;
+            region
+                .assign_advice(
+                    || format!("assign at {} section_id val {}", wb_offset, section_id),
+                    self.config.section_id,
+                    checked_assign_offset(wb_offset, assign_delta)?,
+                    || Value::known(F::from(section_id)),
+                )
+                .map_err(remap_error_to_assign_at(wb_offset))?;
+            self.config
+                .is_custom_section_chip
+                .assign(
+                    region,
+                    checked_assign_offset(wb_offset, assign_delta)?,
+                    Value::known(F::from(section_id)),
+                )
+                .map_err(remap_error_to_assign_at(checked_assign_offset(
+                    wb_offset,
+                    assign_delta,
+                )?))?;
+            let ordered_section_id = if section_id == WasmSection::Custom as u64 {
+                section_id_prev
+            } else {
+                section_rank
+            };
+            region
+                .assign_advice(
+                    || {
+                        format!(
+                            "assign at {} ordered_section_id val {}",
+                            wb_offset, ordered_section_id
+                        )
+                    },
+                    self.config.ordered_section_id,
+                    checked_assign_offset(wb_offset, assign_delta)?,
+                    || Value::known(F::from(ordered_section_id)),
+                )
+                .map_err(remap_error_to_assign_at(wb_offset))?;
+            self.config
+                .section_id_lt_chip
+                .assign(
+                    region,
+                    checked_assign_offset(wb_offset, assign_delta)?,
+                    F::from(section_id_prev),
+                    F::from(ordered_section_id),
+                )
+                .map_err(remap_error_to_assign_at(checked_assign_offset(
+                    wb_offset,
+                    assign_delta,
+                )?))?;
+            section_id_prev = ordered_section_id;
+        }
 
+        self.assign(
+            region,
+            wb,
+            section_start_offset,
+            assign_delta,
+            &[AssignType::IsSectionId],
+            1,
+            None,
+        )?;
+
+        let (_section_len, _section_len_leb_len) = self.markup_leb_section(
+            region,
+            &wb,
+            section_len_start_offset,
+            assign_delta,
+            &[AssignType::IsSectionLen],
+        )?;
+
+        for i in 0..section_len {
+            let offset = section_body_start_offset + i;
             self.assign(
                 region,
                 wb,
-                section_start_offset,
+                offset,
                 assign_delta,
-                &[AssignType::IsSectionId],
+                &[AssignType::IsSectionBody],
                 1,
                 None,
             )?;
+        }
+        self.config.shared_state.borrow_mut().section_id_prev = section_id_prev;
+
+        Ok(section_span.end_offset())
+    }
 
-            let (_section_len, _section_len_leb_len) = self.markup_leb_section(
+    /// assigns the body of a code or data section whose body chip is not configured
+    /// (`structure_only_enabled`): only its items count is marked up, it bumps the func count
+    /// (code) and sets up the dynamic indexes the other sections refer to
+    fn assign_opaque_section_body(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wasm_section: WasmSection,
+        section_body_offset: usize,
+        section_body_end_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
+        let (items_count_assign_type, tag) = match wasm_section {
+            WasmSection::Code => (AssignType::IsOpaqueFuncsCount, Tag::FuncBodyIndex),
+            WasmSection::Data => (AssignType::IsOpaqueDataItemsCount, Tag::DataIndex),
+            _ => {
+                return Err(Error::FatalUnsupportedValue(format!(
+                    "section {:?} body can't be opaque",
+                    wasm_section
+                )))
+            }
+        };
+        let (items_count, _) = leb128_compute_sn(&wb.bytes, false, section_body_offset).map_err(
+            remap_error_to_compute_value_at(checked_assign_offset(
+                section_body_offset,
+                assign_delta,
+            )?),
+        )?;
+        if let WasmSection::Code = wasm_section {
+            self.config.shared_state.borrow_mut().func_count += items_count as usize;
+        }
+        self.markup_leb_section(
+            region,
+            wb,
+            section_body_offset,
+            assign_delta,
+            &[items_count_assign_type],
+        )?;
+        for offset in section_body_offset..=section_body_end_offset {
+            self.assign(
                 region,
-                &wb,
-                section_len_start_offset,
+                wb,
+                offset,
                 assign_delta,
-                &[AssignType::IsSectionLen],
+                &[AssignType::IsOpaqueSectionBody],
+                1,
+                None,
             )?;
+            self.assign_func_count(region, checked_assign_offset(offset, assign_delta)?)?;
+        }
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            items_count as usize,
+            tag,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
 
-            for i in 0..section_len {
-                let offset = section_body_start_offset + i;
-                self.assign(
-                    region,
-                    wb,
-                    offset,
-                    assign_delta,
-                    &[AssignType::IsSectionBody],
-                    1,
-                    None,
-                )?;
-            }
+        Ok((
+            section_body_end_offset + 1,
+            SectionParseSummary {
+                items_count,
+                byte_len: section_body_end_offset + 1 - section_body_offset,
+            },
+        ))
+    }
+
+    /// last step of the assignment of `wb`, called once after its last section: checks the
+    /// section dependencies and assigns the dynamic indexes collected by the sections. Returns
+    /// the new assign delta
+    pub fn assign_dynamic_indexes(
+        &mut self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        if !self.is_error_processed {
+            let result = self.assign_dynamic_indexes_internal(region, assign_delta);
+            self.process_assign_result(region, wb, assign_delta, result)?;
+        }
+        // a bytecode with an error marked in the circuit keeps its bytecode number
+        if !self.is_error_processed {
+            self.config.shared_state.borrow_mut().bytecode_number_inc();
         }
 
+        checked_assign_offset(wb.bytes.len(), assign_delta + self.zero_row_shift())
+    }
+
+    fn assign_dynamic_indexes_internal(
+        &mut self,
+        region: &mut Region<F>,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(), Error> {
+        self.config
+            .shared_state
+            .borrow()
+            .check_section_dependencies()?;
+        let assign_delta = assign_delta + self.zero_row_shift();
+        if !self.config.shared_state.borrow().is_code_section_present {
+            // no func bodies, the function section (if any) must declare no functions either
+            let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+                region,
+                self.config.shared_state.borrow().dynamic_indexes_offset,
+                assign_delta,
+                0,
+                Tag::FuncBodyIndex,
+            )?;
+            self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+        }
         let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
             region,
             self.config.shared_state.borrow().dynamic_indexes_offset,
@@ -1665,7 +3330,33 @@ impl<F: Field> WasmChip<F> {
             Tag::FuncIndex,
         )?;
         self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+        // imported globals come first in the global index space
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            self.config.shared_state.borrow().global_count,
+            Tag::GlobalIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+        // imported memories and tables come first in their index spaces as well
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            self.config.shared_state.borrow().memory_count,
+            Tag::MemIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            self.config.shared_state.borrow().table_count,
+            Tag::TableIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
 
-        Ok(wb_offset + assign_delta)
+        Ok(())
     }
 }
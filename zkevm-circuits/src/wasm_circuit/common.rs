@@ -2,18 +2,22 @@ use std::{cell::RefCell, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Chip, Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
+    plonk::{Advice, Any, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
 use num_traits::checked_pow;
+#[cfg(any(test, feature = "wat"))]
 use wabt::wat2wasm;
 use wasmbin::{
-    io::{DecodeError, Encode},
-    sections::Kind,
+    io::DecodeError,
     visit::{Visit, VisitError},
-    Module,
 };
+#[cfg(any(test, feature = "wat"))]
+use wasmbin::{io::Encode, sections::Kind, Module};
+
+#[cfg(any(test, feature = "wat"))]
+use crate::wasm_circuit::leb128::helpers::leb128_compute_last_byte_offset;
 
 use eth_types::Field;
 use gadgets::{
@@ -28,20 +32,18 @@ use crate::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         consts::MAX_LEB128_BYTES,
         error::{
-            error_index_out_of_bounds, remap_error_to_assign_at, remap_error_to_compute_value_at,
-            validate_wb_offset, Error,
+            checked_assign_offset, error_index_out_of_bounds, remap_error_to_assign_at,
+            remap_error_to_compute_value_at, validate_wb_offset, Error,
         },
         leb128::{
             circuit::LEB128Chip,
-            helpers::{
-                leb128_compute_last_byte_offset, leb128_compute_sn,
-                leb128_compute_sn_recovered_at_position,
-            },
+            consts::LEB128_MAX_BYTES_COUNT,
+            helpers::{leb128_compute_sn, leb128_compute_sn_recovered_at_position},
         },
         sections::consts::LebParams,
         types::{
             AssignDeltaType, AssignValueType, Leb128BytesCountType, Leb128LengthType, LimitType,
-            NewWbOffsetType, SectionLengthType, SharedState, Sn, WbOffsetType,
+            NewWbOffsetType, SectionLengthType, SectionParseSummary, SharedState, Sn, WbOffsetType,
         },
     },
 };
@@ -57,6 +59,40 @@ pub struct LimitTypeFields<F> {
     pub is_limit_type_ctx: Column<Fixed>,
 }
 
+impl<F: Field> LimitTypeFields<F> {
+    pub fn annotate_columns(&self, region: &mut Region<F>, prefix: &str) {
+        annotate_region_columns(
+            region,
+            prefix,
+            &[
+                ("is_limit_type", self.is_limit_type.into()),
+                ("is_limit_min", self.is_limit_min.into()),
+                ("is_limit_max", self.is_limit_max.into()),
+                ("is_limit_type_ctx", self.is_limit_type_ctx.into()),
+                ("limit_type", self.limit_type.into()),
+            ],
+        );
+    }
+}
+
+/// names the `columns` of a chip as `<prefix>_<name>` so `MockProver` failure dumps point at
+/// readable columns instead of column indexes
+pub fn annotate_region_columns<F: Field>(
+    region: &mut Region<F>,
+    prefix: &str,
+    columns: &[(&str, Column<Any>)],
+) {
+    for (name, column) in columns {
+        region.name_column(|| format!("{}_{}", prefix, name), *column);
+    }
+}
+
+pub trait WasmColumnAnnotationAwareChip<F: Field> {
+    /// names the columns owned by the chip (shared ones are named by `WasmChip`), see
+    /// [annotate_region_columns]
+    fn annotate_columns(&self, region: &mut Region<F>);
+}
+
 pub fn configure_constraints_for_q_first_and_q_last<F: Field>(
     cb: &mut BaseConstraintBuilder<F>,
     vc: &mut VirtualCells<F>,
@@ -149,6 +185,61 @@ pub fn configure_constraints_for_q_first_and_q_last<F: Field>(
     );
 }
 
+/// stable identifier of a transition check, it prefixes the gate label of the check so failures
+/// reported by `MockProver` map back to it
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransitionCheckId {
+    ExportItemsCount,
+    ExportItemsCountLastLebByte,
+    ExportNameLen,
+    ExportNameLenLastLebByte,
+    ExportName,
+    ExportDescType,
+    ExportDescVal,
+    ExportDescValLastLebByte,
+}
+
+/// same as [configure_transition_check] but the gate label is derived from the
+/// [TransitionCheckId] variant and the checked columns, which have to be named as their column
+/// annotations, e.g.
+/// `configure_transition_check_with_id!(cb, vc, ExportName, cond, next, [is_export_name,
+/// is_exportdesc_type])` is labeled `ExportName: check next -> is_export_name | is_exportdesc_type`
+macro_rules! configure_transition_check_with_id {
+    ($cb:expr, $vc:expr, $id:ident, $condition:expr, next, [$($column:ident),+ $(,)?] $(,)?) => {
+        $crate::wasm_circuit::common::configure_transition_check_with_id!(
+            @labeled $cb, $vc, $id, $condition, true, "next", [$($column),+]
+        )
+    };
+    ($cb:expr, $vc:expr, $id:ident, $condition:expr, prev, [$($column:ident),+ $(,)?] $(,)?) => {
+        $crate::wasm_circuit::common::configure_transition_check_with_id!(
+            @labeled $cb, $vc, $id, $condition, false, "prev", [$($column),+]
+        )
+    };
+    (
+        @labeled $cb:expr, $vc:expr, $id:ident, $condition:expr, $is_check_next:expr,
+        $direction:literal, [$first:ident $(, $rest:ident)*]
+    ) => {{
+        let _: $crate::wasm_circuit::common::TransitionCheckId =
+            $crate::wasm_circuit::common::TransitionCheckId::$id;
+        $crate::wasm_circuit::common::configure_transition_check(
+            $cb,
+            $vc,
+            concat!(
+                stringify!($id),
+                ": check ",
+                $direction,
+                " -> ",
+                stringify!($first)
+                $(, " | ", stringify!($rest))*
+            ),
+            $condition,
+            $is_check_next,
+            &[$first $(, $rest)*],
+        )
+    }};
+}
+pub(crate) use configure_transition_check_with_id;
+
 /// `is_check_next` is check next or prev
 pub fn configure_transition_check<F: Field>(
     cb: &mut BaseConstraintBuilder<F>,
@@ -322,9 +413,18 @@ pub trait WasmLimitTypeAwareChip<F: Field> {
             |vc| {
                 and::expr([
                     vc.query_fixed(q_enable, Rotation::cur()),
-                    limit_type_chip
-                        .config
-                        .value_equals(LimitType::MinMax, Rotation::cur())(vc),
+                    or::expr([
+                        limit_type_chip
+                            .config
+                            .value_equals(LimitType::MinMax, Rotation::cur())(
+                            vc
+                        ),
+                        limit_type_chip
+                            .config
+                            .value_equals(LimitType::MinMax64, Rotation::cur())(
+                            vc
+                        ),
+                    ]),
                     vc.query_fixed(is_limit_min, Rotation::prev()),
                     vc.query_fixed(is_limit_max, Rotation::cur()),
                 ])
@@ -728,7 +828,46 @@ pub trait WasmAssignAwareChip<F: Field> {
     ) -> Result<(), Error>;
 }
 
+pub trait WasmSectionSummaryAwareChip<F: Field> {
+    /// assigns the section body starting at `wb_offset`, returns the new offset together with
+    /// what was parsed out of the section body
+    fn assign_auto_with_summary(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error>;
+
+    /// same as `assign_auto_with_summary` without the summary
+    fn assign_auto(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        self.assign_auto_with_summary(region, wb, wb_offset, assign_delta)
+            .map(|(offset, _)| offset)
+    }
+}
+
 pub trait WasmMarkupLeb128SectionAwareChip<F: Field>: WasmAssignAwareChip<F> {
+    /// opt-in for minimal leb128 encoding checks of the section fields
+    fn leb128_require_minimal(&self) -> bool {
+        false
+    }
+
+    /// declared width (in bits) of the section fields, used by minimal encoding checks
+    fn leb128_bit_width(&self) -> usize {
+        64
+    }
+
+    /// max number of bytes a leb128 of the section may occupy
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT
+    }
+
     fn markup_leb_section(
         &self,
         region: &mut Region<F>,
@@ -738,9 +877,10 @@ pub trait WasmMarkupLeb128SectionAwareChip<F: Field>: WasmAssignAwareChip<F> {
         assign_types: &[Self::AssignType],
     ) -> Result<(Sn, Leb128LengthType), Error> {
         let is_signed = false;
-        let (sn, last_byte_offset) =
-            leb128_compute_sn(wb.bytes.as_slice(), is_signed, wb_offset)
-                .map_err(remap_error_to_compute_value_at(wb_offset + assign_delta))?;
+        let (sn, last_byte_offset) = leb128_compute_sn(wb.bytes.as_slice(), is_signed, wb_offset)
+            .map_err(remap_error_to_compute_value_at(
+            checked_assign_offset(wb_offset, assign_delta)?,
+        ))?;
         let mut sn_recovered_at_pos = 0;
         let last_byte_rel_offset = last_byte_offset - wb_offset;
         for byte_rel_offset in 0..=last_byte_rel_offset {
@@ -750,7 +890,7 @@ pub trait WasmMarkupLeb128SectionAwareChip<F: Field>: WasmAssignAwareChip<F> {
                 is_signed,
                 byte_rel_offset,
                 last_byte_rel_offset,
-                wb.bytes[wb_offset],
+                wb.byte_at(wb_offset)?,
             );
             let leb_params = Some(LebParams {
                 is_signed,
@@ -758,6 +898,10 @@ pub trait WasmMarkupLeb128SectionAwareChip<F: Field>: WasmAssignAwareChip<F> {
                 last_byte_rel_offset,
                 sn,
                 sn_recovered_at_pos,
+                byte_val: wb.byte_at(wb_offset)?,
+                is_minimal: self.leb128_require_minimal(),
+                bit_width: self.leb128_bit_width(),
+                max_bytes: self.leb128_max_bytes(),
             });
             self.assign(
                 region,
@@ -793,7 +937,7 @@ pub trait WasmBytesAwareChip<F: Field>: WasmAssignAwareChip<F> {
                 region,
                 wb,
                 offset,
-                offset + assign_delta,
+                checked_assign_offset(offset, assign_delta)?,
                 assign_types,
                 1,
                 None,
@@ -837,6 +981,9 @@ pub fn digit_char_to_number(ch: &char) -> u8 {
     *ch as u8 - 48
 }
 
+/// returns the section len starting at `len_start_index` and the count of its leb bytes.
+///
+/// fails with `SectionLenOverflow` if the section body would go past the end of `wb`
 pub fn wasm_compute_section_len(
     wb: &[u8],
     len_start_index: usize,
@@ -845,10 +992,13 @@ pub fn wasm_compute_section_len(
     let mut i = len_start_index;
     loop {
         let byte = wb.get(i).ok_or(Error::IndexOutOfBoundsSimple)?;
-        let mut byte_val: u32 = (byte & 0b1111111) as u32;
-        let pow = checked_pow(0b10000000, i - len_start_index).ok_or(Error::ComputationFailed)?;
-        byte_val = byte_val * pow;
-        section_len += byte_val as usize;
+        let byte_val = (byte & 0b1111111) as usize;
+        let pow: usize =
+            checked_pow(0b10000000, i - len_start_index).ok_or(Error::ComputationFailed)?;
+        section_len = byte_val
+            .checked_mul(pow)
+            .and_then(|byte_val| section_len.checked_add(byte_val))
+            .ok_or(Error::SectionLenOverflow(len_start_index))?;
         if byte & 0b10000000 == 0 {
             break;
         }
@@ -857,10 +1007,16 @@ pub fn wasm_compute_section_len(
             return Err(Error::Leb128MaxBytes);
         }
     }
+    let body_end_offset = (i + 1)
+        .checked_add(section_len)
+        .ok_or(Error::SectionLenOverflow(len_start_index))?;
+    if body_end_offset > wb.len() {
+        return Err(Error::SectionLenOverflow(len_start_index));
+    }
     Ok((section_len, (i - len_start_index + 1) as u8))
 }
 
-#[cfg(any(feature = "test", test))]
+#[cfg(any(test, feature = "wat"))]
 pub fn wat_extract_section_bytecode(path_to_file: &str, kind: Kind) -> Vec<u8> {
     let wat: Vec<u8> = std::fs::read(path_to_file).unwrap();
     let wasm_binary = wat2wasm(&wat.clone()).unwrap();
@@ -878,7 +1034,7 @@ pub fn wat_extract_section_bytecode(path_to_file: &str, kind: Kind) -> Vec<u8> {
     return bytes;
 }
 
-#[cfg(any(feature = "test", test))]
+#[cfg(any(test, feature = "wat"))]
 pub fn wat_extract_section_body_bytecode(path_to_file: &str, kind: Kind) -> Vec<u8> {
     let bytecode = &wat_extract_section_bytecode(path_to_file, kind)[..];
     if bytecode.len() <= 0 {
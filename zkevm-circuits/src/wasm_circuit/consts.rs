@@ -11,9 +11,15 @@ pub static WASM_VERSION_PREFIX_START_INDEX: usize = WASM_MAGIC_PREFIX_END_INDEX
 pub static WASM_VERSION_PREFIX_END_INDEX: usize =
     WASM_VERSION_PREFIX_START_INDEX + WASM_VERSION_PREFIX_LEN - 1;
 pub static WASM_SECTIONS_START_INDEX: usize = WASM_VERSION_PREFIX_END_INDEX + 1;
+/// the only supported value of the little-endian version word following the magic prefix
+pub static WASM_VERSION: u32 = 1;
 pub static WASM_BLOCK_END: u8 = 0xB;
 pub static WASM_BLOCKTYPE_DELIMITER: i32 = 0x40;
 pub const WASM_SECTION_ID_MAX: usize = WasmSection::DataCount as usize;
+/// pages a 32-bit memory can address, the max of a memory declared without one
+pub const WASM_MEMORY_MAX_PAGES: u64 = 0x10000;
+/// name of the function export the root call runs
+pub const WASM_MAIN_EXPORT_NAME: &[u8] = b"main";
 
 // TODO make it differ from custom section id (which is 0 too)
 pub const SECTION_ID_DEFAULT: i32 = 0;
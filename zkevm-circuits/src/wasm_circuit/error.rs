@@ -1,4 +1,10 @@
-use crate::wasm_circuit::{bytecode::bytecode::WasmBytecode, types::AssignOffsetType};
+use crate::wasm_circuit::{
+    bytecode::bytecode::WasmBytecode,
+    sections::consts::LebParams,
+    types::{AssignDeltaType, AssignOffsetType},
+};
+#[cfg(any(test, feature = "test-utils"))]
+use halo2_proofs::dev::VerifyFailure;
 use strum_macros::EnumIter;
 
 #[derive(Debug, Clone, EnumIter, PartialEq)]
@@ -9,6 +15,24 @@ pub enum Error {
     ParseOpcodeFailedAt(AssignOffsetType),
     InvalidEnumValueAt(AssignOffsetType),
     ComputeValueAt(AssignOffsetType),
+    InvalidSectionId(u8),
+    SectionLenMismatch,
+    BlockLevelUnderflow,
+    /// bytecode offset (not an assign offset) of the missing byte
+    UnexpectedEndOfBytes(usize),
+    /// bytecode offset of the first byte of the repeated export name
+    DuplicateExportNameAt(usize),
+    UnsupportedWasmVersion(u32),
+    /// id of the non-custom section which occurs more than once
+    DuplicateSection(u8),
+    /// bytecode offset of the func body len which goes past the end of the bytecode
+    FuncBodyLenExceedsModule(usize),
+    /// bytecode offset of the section len whose body goes past the end of the bytecode
+    SectionLenOverflow(usize),
+    /// id of the section required by another section of the bytecode but missing
+    MissingRequiredSection(u8),
+    /// bytecode offset whose assign offset (the offset plus the assign delta) overflows `usize`
+    OffsetOverflow(usize),
 
     InvalidEnumValue,
     IndexOutOfBoundsSimple,
@@ -31,8 +55,12 @@ pub enum Error {
 
     FatalRecoverableButNotProcessed(String),
 
+    /// `MockProver` failed to synthesize the circuit (debug representation of the prover error)
+    FatalMockProver(String),
+
     FatalUnknown(String),
 }
+
 pub fn is_recoverable_error(e: &Error) -> bool {
     return match e {
         Error::IndexOutOfBoundsAt(_)
@@ -41,6 +69,16 @@ pub fn is_recoverable_error(e: &Error) -> bool {
         | Error::InvalidByteValueAt(_)
         | Error::InvalidEnumValueAt(_)
         | Error::ComputeValueAt(_)
+        | Error::InvalidSectionId(_)
+        | Error::SectionLenMismatch
+        | Error::BlockLevelUnderflow
+        | Error::UnexpectedEndOfBytes(_)
+        | Error::DuplicateExportNameAt(_)
+        | Error::UnsupportedWasmVersion(_)
+        | Error::DuplicateSection(_)
+        | Error::FuncBodyLenExceedsModule(_)
+        | Error::SectionLenOverflow(_)
+        | Error::MissingRequiredSection(_)
         | Error::IndexOutOfBoundsSimple
         | Error::Leb128Encode
         | Error::Leb128EncodeSigned
@@ -64,12 +102,41 @@ pub fn is_fatal_error(e: &Error) -> bool {
         | Error::FatalLeb128ThresholdOverflow(_)
         | Error::FatalLeb128InvalidArgumentValue(_)
         | Error::FatalRecoverableButNotProcessed(_)
+        | Error::FatalMockProver(_)
         | Error::FatalUnknown(_) => true,
 
         _ => false,
     };
 }
 
+/// reason [WasmChip::validate](crate::wasm_circuit::circuit::WasmChip::validate) rejects a
+/// bytecode for
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Debug)]
+pub enum ValidateError {
+    /// the circuit can't be assigned or synthesized
+    Synthesis(Error),
+    /// constraints the synthesized circuit doesn't satisfy
+    Unsatisfied(Vec<VerifyFailure>),
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl From<Error> for ValidateError {
+    fn from(e: Error) -> Self {
+        ValidateError::Synthesis(e)
+    }
+}
+
+/// assign offset of the bytecode `offset` shifted by `assign_delta`
+pub fn checked_assign_offset(
+    offset: usize,
+    assign_delta: AssignDeltaType,
+) -> Result<AssignOffsetType, Error> {
+    offset
+        .checked_add(assign_delta)
+        .ok_or(Error::OffsetOverflow(offset))
+}
+
 pub fn error_index_out_of_bounds(assign_offset: usize) -> Error {
     Error::IndexOutOfBoundsAt(assign_offset)
 }
@@ -81,6 +148,17 @@ pub fn validate_wb_offset(wb: &WasmBytecode, assign_offset: usize) -> Result<(),
     Ok(())
 }
 
+/// leb params the leb128 assign types are assigned with. A missing one is a bug of the calling
+/// chip (not of the bytecode), so it is reported as a fatal error instead of a panic
+pub fn require_leb_params_at(
+    leb_params: Option<LebParams>,
+    assign_offset: usize,
+) -> Result<LebParams, Error> {
+    leb_params.ok_or_else(|| {
+        Error::FatalInvalidArgumentValue(format!("leb params are missing at {}", assign_offset))
+    })
+}
+
 pub fn remap_error_to_index_out_of_bounds_at<E>(assign_offset: usize) -> impl FnOnce(E) -> Error {
     move |_| Error::IndexOutOfBoundsAt(assign_offset)
 }
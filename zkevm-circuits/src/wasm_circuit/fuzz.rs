@@ -0,0 +1,150 @@
+use arbitrary::Arbitrary;
+use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+use crate::{
+    util::log2_ceil,
+    wasm_circuit::{
+        bytecode::bytecode::WasmBytecode,
+        circuit::WasmChip,
+        consts::{WASM_MAGIC_PREFIX, WASM_VERSION},
+        leb128::helpers::leb128_encode,
+        standalone::WasmCircuit,
+    },
+};
+
+/// smallest `k` the range and poseidon tables of the standalone circuit fit into
+const FUZZ_MIN_K: u32 = 9;
+/// rows kept free on top of the assigned ones (blinding rows, zero row)
+const FUZZ_SPARE_ROWS: usize = 64;
+
+/// Section of a generated module. The id is any byte and the declared len is random if set, so
+/// both can disagree with what the parser expects.
+#[derive(Arbitrary, Clone, Debug)]
+pub struct WasmSectionBuilder {
+    pub id: u8,
+    /// declared body len, the actual body len is used if `None`
+    pub len: Option<u32>,
+    pub body: Vec<u8>,
+}
+
+impl WasmSectionBuilder {
+    pub fn new(id: u8, body: Vec<u8>) -> Self {
+        Self {
+            id,
+            len: None,
+            body,
+        }
+    }
+}
+
+/// Generates structurally plausible (but possibly invalid) module bytes: a valid magic prefix
+/// followed by sections with random ids and LEB128 lens. Meant to feed `cargo fuzz` targets.
+#[derive(Arbitrary, Clone, Debug, Default)]
+pub struct WasmModuleBuilder {
+    /// `WASM_VERSION` is used if `None`
+    pub version: Option<u32>,
+    pub sections: Vec<WasmSectionBuilder>,
+}
+
+impl WasmModuleBuilder {
+    /// Corpus seed, a module with a single `() -> ()` func with an empty body.
+    pub fn minimal_valid() -> Self {
+        Self {
+            version: None,
+            sections: vec![
+                // type section: 1 type, func () -> ()
+                WasmSectionBuilder::new(1, vec![0x01, 0x60, 0x00, 0x00]),
+                // function section: 1 func of type 0
+                WasmSectionBuilder::new(3, vec![0x01, 0x00]),
+                // code section: 1 body of 2 bytes, no locals, end
+                WasmSectionBuilder::new(10, vec![0x01, 0x02, 0x00, 0x0b]),
+            ],
+        }
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = WASM_MAGIC_PREFIX.as_bytes().to_vec();
+        bytes.extend(self.version.unwrap_or(WASM_VERSION).to_le_bytes());
+        for section in &self.sections {
+            let len = section.len.unwrap_or(section.body.len() as u32);
+            bytes.push(section.id);
+            bytes.extend(leb128_encode(false, len as i128).unwrap());
+            bytes.extend(&section.body);
+        }
+        bytes
+    }
+
+    pub fn build_wasm_bytecode(&self) -> WasmBytecode {
+        WasmBytecode::new(self.build())
+    }
+}
+
+/// Assigns `bytes` with the standalone circuit. Invalid bytes are expected to end up with an
+/// `Err` (or an unsatisfied circuit), a panic is what fuzzing looks for.
+pub fn fuzz_assign_auto(bytes: &[u8]) {
+    let rows = WasmChip::<Fr>::assign_auto_dry_run(bytes).unwrap_or(bytes.len());
+    let k = log2_ceil(rows + FUZZ_SPARE_ROWS).max(FUZZ_MIN_K);
+    let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes.to_vec())]);
+    if let Ok(prover) = MockProver::run(k, &circuit, vec![]) {
+        let _ = prover.verify();
+    }
+}
+
+#[cfg(test)]
+mod fuzz_tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use wabt::wat2wasm;
+
+    use crate::wasm_circuit::{
+        consts::WASM_MAGIC_PREFIX,
+        fuzz::{fuzz_assign_auto, WasmModuleBuilder, WasmSectionBuilder},
+        standalone::WasmCircuit,
+    };
+
+    #[test]
+    fn minimal_valid_seed_is_accepted() {
+        let wb = WasmModuleBuilder::minimal_valid().build_wasm_bytecode();
+        assert_eq!(wb.bytes, wat2wasm("(module (func))").unwrap());
+        let circuit = WasmCircuit::<Fr>::new(vec![wb]);
+        MockProver::run(9, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+    }
+
+    #[test]
+    fn corpus_seed_is_the_minimal_valid_module() {
+        let seed = include_bytes!("../../fuzz/corpus/assign_auto/minimal_valid");
+        assert_eq!(WasmModuleBuilder::minimal_valid().build(), seed);
+    }
+
+    #[test]
+    fn declared_len_overrides_body_len() {
+        let builder = WasmModuleBuilder {
+            version: None,
+            sections: vec![WasmSectionBuilder {
+                id: 0xff,
+                len: Some(0x80),
+                body: vec![0x01],
+            }],
+        };
+        let bytes = builder.build();
+        assert!(bytes.starts_with(WASM_MAGIC_PREFIX.as_bytes()));
+        assert_eq!(bytes[8..], [0xff, 0x80, 0x01, 0x01]);
+        fuzz_assign_auto(&bytes);
+    }
+
+    #[test]
+    fn arbitrary_modules_do_not_panic() {
+        let data: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..8 {
+            match WasmModuleBuilder::arbitrary(&mut u) {
+                Ok(builder) => fuzz_assign_auto(&builder.build()),
+                Err(_) => break,
+            }
+        }
+    }
+}
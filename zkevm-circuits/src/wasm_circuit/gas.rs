@@ -0,0 +1,3 @@
+//! Step cost model of WASM execution, shared with the witness generation in bus-mapping.
+
+pub use bus_mapping::wasm::gas::*;
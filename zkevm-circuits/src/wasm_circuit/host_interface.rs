@@ -0,0 +1,4 @@
+#[cfg(any(feature = "test", test))]
+pub mod tests;
+pub mod helpers;
+pub mod types;
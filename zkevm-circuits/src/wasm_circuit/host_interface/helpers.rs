@@ -0,0 +1,773 @@
+use crate::wasm_circuit::{
+    consts::{WASM_BLOCKTYPE_DELIMITER, WASM_BLOCK_END},
+    error::{remap_error_to_invalid_enum_value_at, Error},
+    host_interface::types::{DataSegment, FuncType, ImportEntry, ImportKind},
+    leb128::helpers::leb128_compute_sn,
+    parser::parse_sections,
+    types::{
+        ControlInstruction, ExportDescType, ImportDescType, LimitType, MemSegmentType, Mutability,
+        NumType, NumericInstruction, ParametricInstruction, RefType, VariableInstruction,
+        WasmSection, CONTROL_INSTRUCTION_BLOCK, CONTROL_INSTRUCTION_WITH_LEB_ARG,
+        CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG, NUMERIC_INSTRUCTION_WITH_LEB_ARG,
+        VARIABLE_INSTRUCTION_WITH_LEB_ARG,
+    },
+};
+
+const FUNC_TYPE_PREFIX: u8 = 0x60;
+const CONST_EXPR_END: u8 = 0x0b;
+
+/// returns byte at `offset` and moves `offset` to the next byte
+fn read_byte(wb: &[u8], offset: &mut usize) -> Result<u8, Error> {
+    let byte = *wb.get(*offset).ok_or(Error::IndexOutOfBoundsAt(*offset))?;
+    *offset += 1;
+    Ok(byte)
+}
+
+/// returns unsigned leb128 value at `offset` and moves `offset` right after its last byte
+fn read_leb(wb: &[u8], offset: &mut usize) -> Result<u64, Error> {
+    let (sn, last_byte_offset) = leb128_compute_sn(wb, false, *offset)?;
+    *offset = last_byte_offset + 1;
+    Ok(sn)
+}
+
+/// empty vec for the `count` items starting at `offset`. `count` comes from the bytecode, so the
+/// capacity is capped by the bytes left (every item takes at least one byte)
+fn vec_for_items<T>(wb: &[u8], offset: usize, count: u64) -> Vec<T> {
+    let bytes_left = wb.len().saturating_sub(offset);
+    Vec::with_capacity(usize::try_from(count).map_or(bytes_left, |c| c.min(bytes_left)))
+}
+
+fn read_name(wb: &[u8], offset: &mut usize) -> Result<String, Error> {
+    let len = read_leb(wb, offset)? as usize;
+    let name_start = *offset;
+    let name_end = name_start
+        .checked_add(len)
+        .ok_or(Error::IndexOutOfBoundsAt(name_start))?;
+    let bytes = wb
+        .get(name_start..name_end)
+        .ok_or(Error::IndexOutOfBoundsAt(name_start))?;
+    *offset = name_end;
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::InvalidByteValueAt(name_start))
+}
+
+fn read_num_types(wb: &[u8], offset: &mut usize) -> Result<Vec<NumType>, Error> {
+    let count = read_leb(wb, offset)?;
+    let mut num_types = vec_for_items(wb, *offset, count);
+    for _ in 0..count {
+        let byte_offset = *offset;
+        let num_type: NumType = read_byte(wb, offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(byte_offset))?;
+        num_types.push(num_type);
+    }
+    Ok(num_types)
+}
+
+/// returns (min, max) of the limits at `offset`
+fn read_limits(wb: &[u8], offset: &mut usize) -> Result<(u64, Option<u64>), Error> {
+    let byte_offset = *offset;
+    let limit_type: LimitType = read_byte(wb, offset)?
+        .try_into()
+        .map_err(remap_error_to_invalid_enum_value_at(byte_offset))?;
+    read_limit_values(wb, offset, limit_type)
+}
+
+/// same as `read_limits` for the limits of a memory type, they can be 64-bit ones
+fn read_memory_limits(wb: &[u8], offset: &mut usize) -> Result<(u64, Option<u64>), Error> {
+    let byte_offset = *offset;
+    let limit_type = LimitType::try_from_memory(read_byte(wb, offset)?)
+        .map_err(remap_error_to_invalid_enum_value_at(byte_offset))?;
+    read_limit_values(wb, offset, limit_type)
+}
+
+/// returns (min, max) of the limits of `limit_type` at `offset`, right after the limit type
+fn read_limit_values(
+    wb: &[u8],
+    offset: &mut usize,
+    limit_type: LimitType,
+) -> Result<(u64, Option<u64>), Error> {
+    let min = read_leb(wb, offset)?;
+    let max = if limit_type.has_max() {
+        Some(read_leb(wb, offset)?)
+    } else {
+        None
+    };
+    Ok((min, max))
+}
+
+fn skip_limits(wb: &[u8], offset: &mut usize) -> Result<(), Error> {
+    read_limits(wb, offset)?;
+    Ok(())
+}
+
+/// skips a constant expression of a single numeric instruction with a signed leb128 arg and the
+/// `end` opcode
+fn skip_const_expr(wb: &[u8], offset: &mut usize) -> Result<(), Error> {
+    read_byte(wb, offset)?;
+    let (_, last_byte_offset) = leb128_compute_sn(wb, true, *offset)?;
+    *offset = last_byte_offset + 1;
+    let end_offset = *offset;
+    if read_byte(wb, offset)? != CONST_EXPR_END {
+        return Err(Error::InvalidByteValueAt(end_offset));
+    }
+    Ok(())
+}
+
+/// reads the instruction at `offset` the way the code section body chip marks it up and moves
+/// `offset` right after it. returns the opcode and its leb128 arg (0 if it has none)
+fn read_instruction(wb: &[u8], offset: &mut usize) -> Result<(u8, u64), Error> {
+    let opcode_offset = *offset;
+    let opcode = read_byte(wb, offset)?;
+    if opcode == WASM_BLOCK_END || ParametricInstruction::try_from(opcode).is_ok() {
+        return Ok((opcode, 0));
+    }
+    if let Ok(instruction) = NumericInstruction::try_from(opcode) {
+        if !NUMERIC_INSTRUCTION_WITH_LEB_ARG.contains(&instruction) {
+            return Ok((opcode, 0));
+        }
+        let (arg, last_byte_offset) = leb128_compute_sn(wb, true, *offset)?;
+        *offset = last_byte_offset + 1;
+        return Ok((opcode, arg));
+    }
+    if let Ok(instruction) = VariableInstruction::try_from(opcode) {
+        if !VARIABLE_INSTRUCTION_WITH_LEB_ARG.contains(&instruction) {
+            return Ok((opcode, 0));
+        }
+        return Ok((opcode, read_leb(wb, offset)?));
+    }
+    match ControlInstruction::try_from(opcode) {
+        Ok(instruction) if CONTROL_INSTRUCTION_WITH_LEB_ARG.contains(&instruction) => {
+            Ok((opcode, read_leb(wb, offset)?))
+        }
+        Ok(instruction) if CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG.contains(&instruction) => {
+            let type_index = read_leb(wb, offset)?;
+            let table_index_offset = *offset;
+            if read_byte(wb, offset)? != 0 {
+                return Err(Error::InvalidByteValueAt(table_index_offset));
+            }
+            Ok((opcode, type_index))
+        }
+        Ok(instruction) if CONTROL_INSTRUCTION_BLOCK.contains(&instruction) => {
+            let blocktype = *wb.get(*offset).ok_or(Error::IndexOutOfBoundsAt(*offset))?;
+            if blocktype == WASM_BLOCKTYPE_DELIMITER as u8
+                || NumType::try_from(blocktype).is_ok()
+                || RefType::try_from(blocktype).is_ok()
+            {
+                *offset += 1;
+            } else {
+                read_leb(wb, offset)?;
+            }
+            Ok((opcode, 0))
+        }
+        Ok(_) => Ok((opcode, 0)),
+        Err(_) => Err(Error::ParseOpcodeFailedAt(opcode_offset)),
+    }
+}
+
+fn parse_type_section_body(wb: &[u8], start: usize) -> Result<Vec<FuncType>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut func_types = vec_for_items(wb, offset, count);
+    for _ in 0..count {
+        let prefix_offset = offset;
+        if read_byte(wb, &mut offset)? != FUNC_TYPE_PREFIX {
+            return Err(Error::InvalidByteValueAt(prefix_offset));
+        }
+        let params = read_num_types(wb, &mut offset)?;
+        let results = read_num_types(wb, &mut offset)?;
+        func_types.push(FuncType { params, results });
+    }
+    Ok(func_types)
+}
+
+/// returns function and global imports, table and memory imports are skipped
+fn parse_import_section_body(
+    wb: &[u8],
+    start: usize,
+    func_types: &[FuncType],
+) -> Result<Vec<ImportEntry>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut imports = vec_for_items(wb, offset, count);
+    for _ in 0..count {
+        let module = read_name(wb, &mut offset)?;
+        let field = read_name(wb, &mut offset)?;
+        let desc_type_offset = offset;
+        let desc_type: ImportDescType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(desc_type_offset))?;
+        match desc_type {
+            ImportDescType::Typeidx => {
+                let typeidx_offset = offset;
+                let typeidx = read_leb(wb, &mut offset)? as usize;
+                let func_type = func_types
+                    .get(typeidx)
+                    .ok_or(Error::IndexOutOfBoundsAt(typeidx_offset))?;
+                imports.push(ImportEntry {
+                    module,
+                    field,
+                    kind: ImportKind::Func(func_type.clone()),
+                });
+            }
+            ImportDescType::TableType => {
+                let ref_type_offset = offset;
+                let _ref_type: RefType = read_byte(wb, &mut offset)?
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(ref_type_offset))?;
+                skip_limits(wb, &mut offset)?;
+            }
+            ImportDescType::MemType => {
+                skip_limits(wb, &mut offset)?;
+            }
+            ImportDescType::GlobalType => {
+                let val_type_offset = offset;
+                let val_type: NumType = read_byte(wb, &mut offset)?
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(val_type_offset))?;
+                let mutability_offset = offset;
+                let mutability: Mutability = read_byte(wb, &mut offset)?
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(mutability_offset))?;
+                imports.push(ImportEntry {
+                    module,
+                    field,
+                    kind: ImportKind::Global {
+                        val_type,
+                        mutability,
+                    },
+                });
+            }
+        }
+    }
+    Ok(imports)
+}
+
+/// returns type indexes of the function imports
+fn parse_import_section_func_type_indexes(wb: &[u8], start: usize) -> Result<Vec<u64>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut type_indexes = vec![];
+    for _ in 0..count {
+        read_name(wb, &mut offset)?;
+        read_name(wb, &mut offset)?;
+        let desc_type_offset = offset;
+        let desc_type: ImportDescType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(desc_type_offset))?;
+        match desc_type {
+            ImportDescType::Typeidx => type_indexes.push(read_leb(wb, &mut offset)?),
+            ImportDescType::TableType => {
+                read_byte(wb, &mut offset)?;
+                skip_limits(wb, &mut offset)?;
+            }
+            ImportDescType::MemType => skip_limits(wb, &mut offset)?,
+            ImportDescType::GlobalType => {
+                read_byte(wb, &mut offset)?;
+                read_byte(wb, &mut offset)?;
+            }
+        }
+    }
+    Ok(type_indexes)
+}
+
+/// returns function indexes of the element segments (kinds 0, 1, 2) in declaration order
+fn parse_element_section_func_indexes(wb: &[u8], start: usize) -> Result<Vec<u64>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut func_indexes = vec![];
+    for _ in 0..count {
+        let elem_type_offset = offset;
+        match read_byte(wb, &mut offset)? {
+            0 => skip_const_expr(wb, &mut offset)?,
+            1 => {
+                read_byte(wb, &mut offset)?;
+            }
+            2 => {
+                read_leb(wb, &mut offset)?;
+                skip_const_expr(wb, &mut offset)?;
+                read_byte(wb, &mut offset)?;
+            }
+            _ => return Err(Error::InvalidByteValueAt(elem_type_offset)),
+        }
+        let funcs_count = read_leb(wb, &mut offset)?;
+        for _ in 0..funcs_count {
+            func_indexes.push(read_leb(wb, &mut offset)?);
+        }
+    }
+    Ok(func_indexes)
+}
+
+/// returns desc types of all the imports in declaration order
+fn parse_import_section_desc_types(wb: &[u8], start: usize) -> Result<Vec<ImportDescType>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut desc_types = vec![];
+    for _ in 0..count {
+        read_name(wb, &mut offset)?;
+        read_name(wb, &mut offset)?;
+        let desc_type_offset = offset;
+        let desc_type: ImportDescType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(desc_type_offset))?;
+        match desc_type {
+            ImportDescType::Typeidx => {
+                read_leb(wb, &mut offset)?;
+            }
+            ImportDescType::TableType => {
+                read_byte(wb, &mut offset)?;
+                skip_limits(wb, &mut offset)?;
+            }
+            ImportDescType::MemType => skip_limits(wb, &mut offset)?,
+            ImportDescType::GlobalType => {
+                read_byte(wb, &mut offset)?;
+                read_byte(wb, &mut offset)?;
+            }
+        }
+        desc_types.push(desc_type);
+    }
+    Ok(desc_types)
+}
+
+/// returns mutabilities of the global imports
+fn parse_import_section_global_mutabilities(
+    wb: &[u8],
+    start: usize,
+) -> Result<Vec<Mutability>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut mutabilities = vec![];
+    for _ in 0..count {
+        read_name(wb, &mut offset)?;
+        read_name(wb, &mut offset)?;
+        let desc_type_offset = offset;
+        let desc_type: ImportDescType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(desc_type_offset))?;
+        match desc_type {
+            ImportDescType::Typeidx => {
+                read_leb(wb, &mut offset)?;
+            }
+            ImportDescType::TableType => {
+                read_byte(wb, &mut offset)?;
+                skip_limits(wb, &mut offset)?;
+            }
+            ImportDescType::MemType => skip_limits(wb, &mut offset)?,
+            ImportDescType::GlobalType => {
+                read_byte(wb, &mut offset)?;
+                let mutability_offset = offset;
+                let mutability: Mutability = read_byte(wb, &mut offset)?
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(mutability_offset))?;
+                mutabilities.push(mutability);
+            }
+        }
+    }
+    Ok(mutabilities)
+}
+
+fn parse_global_section_mutabilities(wb: &[u8], start: usize) -> Result<Vec<Mutability>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut mutabilities = vec_for_items(wb, offset, count);
+    for _ in 0..count {
+        read_byte(wb, &mut offset)?;
+        let mutability_offset = offset;
+        let mutability: Mutability = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(mutability_offset))?;
+        mutabilities.push(mutability);
+        skip_const_expr(wb, &mut offset)?;
+    }
+    Ok(mutabilities)
+}
+
+fn parse_data_section_segments(wb: &[u8], start: usize) -> Result<Vec<DataSegment>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut segments = vec_for_items(wb, offset, count);
+    for _ in 0..count {
+        let segment_type_offset = offset;
+        let segment_type: MemSegmentType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(segment_type_offset))?;
+        match segment_type {
+            MemSegmentType::Active => skip_const_expr(wb, &mut offset)?,
+            MemSegmentType::Passive => {}
+            MemSegmentType::ActiveVariadic => {
+                read_leb(wb, &mut offset)?;
+                skip_const_expr(wb, &mut offset)?;
+            }
+        }
+        let len = read_leb(wb, &mut offset)? as usize;
+        if offset.checked_add(len).map_or(true, |end| end > wb.len()) {
+            return Err(Error::IndexOutOfBoundsAt(offset));
+        }
+        segments.push(DataSegment {
+            is_passive: segment_type == MemSegmentType::Passive,
+            bytes_offset: offset,
+            len,
+        });
+        offset += len;
+    }
+    Ok(segments)
+}
+
+/// returns (name, function index) of the function exports in declaration order
+fn parse_export_section_func_indexes(wb: &[u8], start: usize) -> Result<Vec<(String, u64)>, Error> {
+    let mut offset = start;
+    let count = read_leb(wb, &mut offset)?;
+    let mut func_exports = vec_for_items(wb, offset, count);
+    for _ in 0..count {
+        let name = read_name(wb, &mut offset)?;
+        let desc_type_offset = offset;
+        let desc_type: ExportDescType = read_byte(wb, &mut offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(desc_type_offset))?;
+        let index = read_leb(wb, &mut offset)?;
+        if desc_type == ExportDescType::Funcidx {
+            func_exports.push((name, index));
+        }
+    }
+    Ok(func_exports)
+}
+
+/// extracts (name, function index) of the function exports of the module in declaration order
+pub fn extract_func_exports(wb: &[u8]) -> Result<Vec<(String, u64)>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Export {
+            return parse_export_section_func_indexes(wb, section_span.body_offset);
+        }
+    }
+    Ok(vec![])
+}
+
+/// extracts function and global imports (with resolved types) from the module bytecode
+pub fn extract_imports(wb: &[u8]) -> Result<Vec<ImportEntry>, Error> {
+    let mut func_types = vec![];
+    let mut imports = vec![];
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        match section {
+            WasmSection::Type => {
+                func_types = parse_type_section_body(wb, section_span.body_offset)?;
+            }
+            WasmSection::Import => {
+                imports = parse_import_section_body(wb, section_span.body_offset, &func_types)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(imports)
+}
+
+/// extracts desc types of all the imports of the module (funcs, tables, memories and globals) in
+/// declaration order
+pub fn extract_import_desc_types(wb: &[u8]) -> Result<Vec<ImportDescType>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Import {
+            return parse_import_section_desc_types(wb, section_span.body_offset);
+        }
+    }
+    Ok(vec![])
+}
+
+/// extracts limits (min, max) of the first memory declared by the memory section of the module.
+///
+/// returns `None` if the module has no memory section or the section is empty
+pub fn extract_memory_limits(wb: &[u8]) -> Result<Option<(u64, Option<u64>)>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Memory {
+            let mut body_offset = section_span.body_offset;
+            if read_leb(wb, &mut body_offset)? == 0 {
+                return Ok(None);
+            }
+            return Ok(Some(read_memory_limits(wb, &mut body_offset)?));
+        }
+    }
+    Ok(None)
+}
+
+/// extracts func types declared by the type section of the module, indexed by type index
+pub fn extract_func_types(wb: &[u8]) -> Result<Vec<FuncType>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Type {
+            return parse_type_section_body(wb, section_span.body_offset);
+        }
+    }
+    Ok(vec![])
+}
+
+/// extracts type indexes of all functions of the module, indexed by function index (imported
+/// functions come first)
+pub fn extract_func_type_indexes(wb: &[u8]) -> Result<Vec<u64>, Error> {
+    let mut type_indexes = vec![];
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        match section {
+            WasmSection::Import => {
+                type_indexes.extend(parse_import_section_func_type_indexes(
+                    wb,
+                    section_span.body_offset,
+                )?);
+            }
+            WasmSection::Function => {
+                let mut offset = section_span.body_offset;
+                let count = read_leb(wb, &mut offset)?;
+                for _ in 0..count {
+                    type_indexes.push(read_leb(wb, &mut offset)?);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(type_indexes)
+}
+
+/// counts functions of the module: the imported ones (`Typeidx` import descs) followed by the ones
+/// defined by the code section. It is the `func_count` the wasm circuit ends up with, so it can be
+/// used to pre-size the `dynamic_indexes` region before assignment.
+///
+/// the function section must declare as many functions as the code section defines
+pub fn count_functions(wb: &[u8]) -> Result<usize, Error> {
+    let mut imported_count = 0;
+    // (funcs count, bytecode offset of the count)
+    let mut declared = (0, 0);
+    let mut defined = (0, 0);
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        let mut offset = section_span.body_offset;
+        match section {
+            WasmSection::Import => {
+                imported_count = parse_import_section_desc_types(wb, offset)?
+                    .into_iter()
+                    .filter(|desc_type| *desc_type == ImportDescType::Typeidx)
+                    .count();
+            }
+            WasmSection::Function if section_span.body_len > 0 => {
+                declared = (read_leb(wb, &mut offset)?, section_span.body_offset);
+            }
+            WasmSection::Code if section_span.body_len > 0 => {
+                defined = (read_leb(wb, &mut offset)?, section_span.body_offset);
+            }
+            _ => {}
+        }
+    }
+    if declared.0 != defined.0 {
+        return Err(Error::InvalidByteValueAt(declared.1.max(defined.1)));
+    }
+    Ok(imported_count + defined.0 as usize)
+}
+
+/// extracts function indexes referenced by the element segments of the module.
+///
+/// the returned list is what `Tag::TableElemFuncIndex` indexes (segments in declaration order)
+pub fn extract_table_elem_func_indexes(wb: &[u8]) -> Result<Vec<u64>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Element {
+            return parse_element_section_func_indexes(wb, section_span.body_offset);
+        }
+    }
+    Ok(vec![])
+}
+
+/// extracts mutabilities of all globals of the module, indexed by global index (imported globals
+/// come first)
+pub fn extract_global_mutabilities(wb: &[u8]) -> Result<Vec<Mutability>, Error> {
+    let mut mutabilities = vec![];
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        match section {
+            WasmSection::Import => {
+                mutabilities.extend(parse_import_section_global_mutabilities(
+                    wb,
+                    section_span.body_offset,
+                )?);
+            }
+            WasmSection::Global => {
+                mutabilities.extend(parse_global_section_mutabilities(
+                    wb,
+                    section_span.body_offset,
+                )?);
+            }
+            _ => {}
+        }
+    }
+    Ok(mutabilities)
+}
+
+/// extracts data segments of the module indexed by data index (what `Tag::DataSegmentIndex`
+/// indexes, active segments included)
+pub fn extract_data_segments(wb: &[u8]) -> Result<Vec<DataSegment>, Error> {
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section == WasmSection::Data {
+            return parse_data_section_segments(wb, section_span.body_offset);
+        }
+    }
+    Ok(vec![])
+}
+
+/// extracts (program counter of the branch, program counter it jumps to) of every `br`/`br_if` of
+/// the code section leaving a block, the ones leaving the func body return instead.
+///
+/// program counters index the instructions of all func bodies in declaration order, every opcode
+/// is an instruction but `block`, `loop` and the `end` of a block (the `end` of a func body is
+/// its return). A branch leaving a `loop` jumps to the first instruction of the loop, one leaving
+/// a `block` or an `if` jumps to the instruction right after its `end`
+pub fn extract_branch_targets(wb: &[u8]) -> Result<Vec<(u64, u64)>, Error> {
+    let mut branch_targets = vec![];
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section != WasmSection::Code {
+            continue;
+        }
+        let mut offset = section_span.body_offset;
+        let mut pc = 0;
+        let funcs_count = read_leb(wb, &mut offset)?;
+        for _ in 0..funcs_count {
+            let func_body_len = read_leb(wb, &mut offset)?;
+            let func_body_end_offset = usize::try_from(func_body_len)
+                .ok()
+                .and_then(|len| offset.checked_add(len))
+                .ok_or(Error::IndexOutOfBoundsAt(offset))?;
+            let local_type_transitions_count = read_leb(wb, &mut offset)?;
+            for _ in 0..local_type_transitions_count {
+                read_leb(wb, &mut offset)?;
+                read_byte(wb, &mut offset)?;
+            }
+            // (is loop, pc of the first instruction of the block, `branch_targets` indexes of the
+            // branches waiting for the `end` of the block) of the blocks opened so far
+            let mut blocks: Vec<(bool, u64, Vec<usize>)> = vec![];
+            while offset < func_body_end_offset {
+                let (opcode, arg) = read_instruction(wb, &mut offset)?;
+                if opcode == WASM_BLOCK_END {
+                    match blocks.pop() {
+                        Some((_, _, branches)) => {
+                            for branch in branches {
+                                branch_targets[branch].1 = pc;
+                            }
+                        }
+                        None => pc += 1,
+                    }
+                    continue;
+                }
+                match ControlInstruction::try_from(opcode) {
+                    Ok(ControlInstruction::Block) => blocks.push((false, pc, vec![])),
+                    Ok(ControlInstruction::Loop) => blocks.push((true, pc, vec![])),
+                    Ok(ControlInstruction::If) => {
+                        pc += 1;
+                        blocks.push((false, pc, vec![]));
+                    }
+                    Ok(ControlInstruction::Br | ControlInstruction::BrIf) => {
+                        if arg < blocks.len() as u64 {
+                            let block_index = blocks.len() - 1 - arg as usize;
+                            let (is_loop, start_pc, branches) = &mut blocks[block_index];
+                            if !*is_loop {
+                                branches.push(branch_targets.len());
+                            }
+                            branch_targets.push((pc, *start_pc));
+                        }
+                        pc += 1;
+                    }
+                    _ => pc += 1,
+                }
+            }
+        }
+    }
+    Ok(branch_targets)
+}
+
+/// extracts (program counter, argument) of every `instruction` of the code section (the function
+/// index of a `call`, the type index of a `call_indirect`, etc.), see `extract_branch_targets` for
+/// the program counters
+pub fn extract_control_instruction_args(
+    wb: &[u8],
+    instruction: ControlInstruction,
+) -> Result<Vec<(u64, u64)>, Error> {
+    let mut args = vec![];
+    for section_span in parse_sections(wb)? {
+        let section: WasmSection = (section_span.section_id as i32)
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(section_span.id_offset))?;
+        if section != WasmSection::Code {
+            continue;
+        }
+        let mut offset = section_span.body_offset;
+        let mut pc = 0;
+        let funcs_count = read_leb(wb, &mut offset)?;
+        for _ in 0..funcs_count {
+            let func_body_len = read_leb(wb, &mut offset)?;
+            let func_body_end_offset = usize::try_from(func_body_len)
+                .ok()
+                .and_then(|len| offset.checked_add(len))
+                .ok_or(Error::IndexOutOfBoundsAt(offset))?;
+            let local_type_transitions_count = read_leb(wb, &mut offset)?;
+            for _ in 0..local_type_transitions_count {
+                read_leb(wb, &mut offset)?;
+                read_byte(wb, &mut offset)?;
+            }
+            // blocks opened so far, the `end` of a block is no instruction
+            let mut block_level = 0;
+            while offset < func_body_end_offset {
+                let (opcode, arg) = read_instruction(wb, &mut offset)?;
+                match ControlInstruction::try_from(opcode) {
+                    _ if opcode == WASM_BLOCK_END && block_level > 0 => block_level -= 1,
+                    Ok(ControlInstruction::Block | ControlInstruction::Loop) => block_level += 1,
+                    Ok(opcode) => {
+                        if opcode == ControlInstruction::If {
+                            block_level += 1;
+                        }
+                        if opcode == instruction {
+                            args.push((pc, arg));
+                        }
+                        pc += 1;
+                    }
+                    _ => pc += 1,
+                }
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// checks that every function/global import of the module is offered by the host interface with
+/// exactly the same type.
+///
+/// returns the list of unsatisfied imports (empty if the module can be linked against the host)
+pub fn check_imports_satisfied(
+    wb: &[u8],
+    host_interface: &[ImportEntry],
+) -> Result<Vec<ImportEntry>, Error> {
+    let unsatisfied = extract_imports(wb)?
+        .into_iter()
+        .filter(|import| !host_interface.contains(import))
+        .collect();
+    Ok(unsatisfied)
+}
@@ -0,0 +1,306 @@
+#[cfg(test)]
+mod host_interface_tests {
+    use wabt::wat2wasm;
+
+    use crate::wasm_circuit::{
+        host_interface::{
+            helpers::{
+                check_imports_satisfied, count_functions, extract_branch_targets,
+                extract_data_segments, extract_func_type_indexes, extract_func_types,
+                extract_global_mutabilities, extract_import_desc_types, extract_imports,
+                extract_memory_limits, extract_table_elem_func_indexes,
+            },
+            types::{DataSegment, FuncType, ImportEntry, ImportKind},
+        },
+        types::{ImportDescType, Mutability, NumType},
+    };
+
+    fn read_wasm(path: &str) -> Vec<u8> {
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        wat2wasm(data).unwrap()
+    }
+
+    fn func(params: &[NumType], results: &[NumType]) -> ImportKind {
+        ImportKind::Func(FuncType {
+            params: params.to_vec(),
+            results: results.to_vec(),
+        })
+    }
+
+    fn file1_host_interface() -> Vec<ImportEntry> {
+        vec![
+            ImportEntry::new("env", "_evm_address", func(&[NumType::I32], &[])),
+            ImportEntry::new(
+                "env",
+                "_evm_balance",
+                func(&[NumType::I32, NumType::I32], &[]),
+            ),
+            ImportEntry::new(
+                "env",
+                "_evm_some_long_name_func_some_long_name_func_some_long_name_func_some_long_name_func_some_long_name_func_some_long_name_func_some_long_name_func_some_long_name_func",
+                func(&[NumType::I64, NumType::I32], &[NumType::I32]),
+            ),
+        ]
+    }
+
+    #[test]
+    pub fn file1_extract_imports_ok() {
+        let bytes = read_wasm("./test_files/cc1.wat");
+        let imports = extract_imports(&bytes).unwrap();
+        assert_eq!(imports, file1_host_interface());
+    }
+
+    #[test]
+    pub fn file1_all_imports_satisfied_ok() {
+        let bytes = read_wasm("./test_files/cc1.wat");
+        let mut host_interface = file1_host_interface();
+        host_interface.push(ImportEntry::new("env", "_evm_unused", func(&[], &[])));
+        let unsatisfied = check_imports_satisfied(&bytes, &host_interface).unwrap();
+        assert!(unsatisfied.is_empty());
+    }
+
+    #[test]
+    pub fn file1_missing_and_mismatched_imports_reported() {
+        let bytes = read_wasm("./test_files/cc1.wat");
+        let host_interface = vec![
+            ImportEntry::new("env", "_evm_address", func(&[NumType::I32], &[])),
+            ImportEntry::new("env", "_evm_balance", func(&[NumType::I32], &[])),
+        ];
+        let unsatisfied = check_imports_satisfied(&bytes, &host_interface).unwrap();
+        let expected = file1_host_interface()[1..].to_vec();
+        assert_eq!(unsatisfied, expected);
+    }
+
+    #[test]
+    pub fn file2_global_mutability_mismatch_reported() {
+        let bytes = read_wasm("./test_files/cc2.wat");
+        let host_interface = vec![
+            ImportEntry::new(
+                "js",
+                "global",
+                ImportKind::Global {
+                    val_type: NumType::I32,
+                    mutability: Mutability::Var,
+                },
+            ),
+            ImportEntry::new(
+                "test",
+                "global-i32",
+                ImportKind::Global {
+                    val_type: NumType::I32,
+                    mutability: Mutability::Var,
+                },
+            ),
+        ];
+        let unsatisfied = check_imports_satisfied(&bytes, &host_interface).unwrap();
+        assert_eq!(
+            unsatisfied,
+            vec![ImportEntry::new(
+                "test",
+                "global-i32",
+                ImportKind::Global {
+                    val_type: NumType::I32,
+                    mutability: Mutability::Const,
+                },
+            )]
+        );
+    }
+
+    #[test]
+    pub fn memory_limits_extracted() {
+        let bytes = wat2wasm("(module (memory 2 5))").unwrap();
+        assert_eq!(extract_memory_limits(&bytes).unwrap(), Some((2, Some(5))));
+        let bytes = wat2wasm("(module (memory 1))").unwrap();
+        assert_eq!(extract_memory_limits(&bytes).unwrap(), Some((1, None)));
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(extract_memory_limits(&bytes).unwrap(), None);
+    }
+
+    #[test]
+    pub fn func_type_indexes_and_table_elems_extracted() {
+        let bytes = wat2wasm(
+            r#"(module
+                (type $a (func))
+                (type $b (func (param i32)))
+                (import "env" "f" (func (type $b)))
+                (table 3 funcref)
+                (func (type $a))
+                (func (type $b))
+                (elem (i32.const 0) 2 0)
+                (elem (i32.const 2) 1)
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(extract_func_type_indexes(&bytes).unwrap(), vec![1, 0, 1]);
+        assert_eq!(
+            extract_table_elem_func_indexes(&bytes).unwrap(),
+            vec![2, 0, 1]
+        );
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(
+            extract_table_elem_func_indexes(&bytes).unwrap(),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    pub fn data_segments_extracted() {
+        let bytes = wat2wasm(
+            r#"(module
+                (memory 1)
+                (data (i32.const 16) "abc")
+                (data "hello")
+            )"#,
+        )
+        .unwrap();
+        let segments = extract_data_segments(&bytes).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert!(!segments[0].is_passive);
+        assert_eq!(segments[0].len, 3);
+        assert_eq!(
+            &bytes[segments[0].bytes_offset..segments[0].bytes_offset + 3],
+            b"abc"
+        );
+        assert_eq!(
+            segments[1],
+            DataSegment {
+                is_passive: true,
+                bytes_offset: segments[1].bytes_offset,
+                len: 5,
+            }
+        );
+        assert_eq!(
+            &bytes[segments[1].bytes_offset..segments[1].bytes_offset + 5],
+            b"hello"
+        );
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(extract_data_segments(&bytes).unwrap(), vec![]);
+    }
+
+    #[test]
+    pub fn global_mutabilities_extracted() {
+        let bytes = wat2wasm(
+            r#"(module
+                (import "env" "g" (global (mut i64)))
+                (import "env" "f" (func))
+                (global i32 (i32.const 1))
+                (global (mut i32) (i32.const -1))
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            extract_global_mutabilities(&bytes).unwrap(),
+            vec![Mutability::Var, Mutability::Const, Mutability::Var]
+        );
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(extract_global_mutabilities(&bytes).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn branch_targets_extracted() {
+        let bytes = wat2wasm(
+            r#"(module
+                (func
+                    block
+                        i32.const 1
+                        br_if 0
+                        loop
+                            i32.const 0
+                            br_if 0
+                            br 1
+                        end
+                    end
+                    i32.const 2
+                    drop
+                    br 0
+                )
+                (func (param i32)
+                    local.get 0
+                    if
+                        br 0
+                    end
+                )
+            )"#,
+        )
+        .unwrap();
+        // the `br 0` of the first func body returns, the program counters of the second one
+        // start after the `end` of the first one
+        assert_eq!(
+            extract_branch_targets(&bytes).unwrap(),
+            vec![(1, 5), (3, 2), (4, 5), (11, 12)]
+        );
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(extract_branch_targets(&bytes).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn import_desc_types() {
+        let bytes = read_wasm("./test_files/cc3.wat");
+        let desc_types = extract_import_desc_types(&bytes).unwrap();
+        assert_eq!(
+            desc_types[..2],
+            [ImportDescType::MemType, ImportDescType::TableType]
+        );
+        assert!(desc_types[2..]
+            .iter()
+            .all(|desc_type| *desc_type == ImportDescType::Typeidx));
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(extract_import_desc_types(&bytes).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn functions_counted() {
+        let bytes = wat2wasm(
+            r#"(module
+                (import "env" "f" (func))
+                (import "env" "g" (global i32))
+                (import "env" "memory" (memory 1))
+                (import "env" "h" (func (param i32)))
+                (func)
+                (func (result i32) i32.const 0)
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(count_functions(&bytes).unwrap(), 4);
+        let bytes = read_wasm("./test_files/cc3.wat");
+        assert_eq!(
+            count_functions(&bytes).unwrap(),
+            extract_func_type_indexes(&bytes).unwrap().len()
+        );
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(count_functions(&bytes).unwrap(), 0);
+    }
+
+    #[test]
+    fn functions_count_mismatch_reported() {
+        // function section declares 2 funcs of type 0, code section defines 1 empty body
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic prefix, version
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section
+            0x03, 0x03, 0x02, 0x00, 0x00, // function section
+            0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b, // code section
+        ];
+        assert!(count_functions(&bytes).is_err());
+    }
+
+    #[test]
+    fn huge_type_count_rejected() {
+        // type section declares u32::MAX types but has no bytes for them
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic prefix, version
+            0x01, 0x05, 0xff, 0xff, 0xff, 0xff, 0x0f, // type section
+        ];
+        assert!(extract_func_types(&bytes).is_err());
+    }
+
+    #[test]
+    fn import_name_len_overflow_rejected() {
+        // the module name len is u64::MAX
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic prefix, version
+            0x02, 0x0b, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x01, // import section
+        ];
+        assert!(extract_imports(&bytes).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::wasm_circuit::types::{Mutability, NumType};
+
+/// https://webassembly.github.io/spec/core/binary/types.html#function-types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuncType {
+    pub params: Vec<NumType>,
+    pub results: Vec<NumType>,
+}
+
+/// kind of import a host is able to satisfy (only function and global imports are checked)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportKind {
+    Func(FuncType),
+    Global {
+        val_type: NumType,
+        mutability: Mutability,
+    },
+}
+
+/// single (module, field, type) import entry
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportEntry {
+    pub module: String,
+    pub field: String,
+    pub kind: ImportKind,
+}
+
+impl ImportEntry {
+    pub fn new(module: &str, field: &str, kind: ImportKind) -> Self {
+        Self {
+            module: module.to_string(),
+            field: field.to_string(),
+            kind,
+        }
+    }
+}
+
+/// data segment of the module, `bytes_offset` is the offset of its first byte in the bytecode
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataSegment {
+    pub is_passive: bool,
+    pub bytes_offset: usize,
+    pub len: usize,
+}
@@ -0,0 +1,38 @@
+use crate::wasm_circuit::types::WasmSection;
+
+/// WasmInstrument captures row usage of the section chips while a bytecode gets assigned.
+#[derive(Clone, Debug, Default)]
+pub struct WasmInstrument {
+    // sections in bytecode order -> rows consumed by the section body chip
+    sections: Vec<(WasmSection, usize)>,
+}
+
+impl WasmInstrument {
+    /// Records rows consumed by `assign_auto` of the section body chip.
+    pub(crate) fn on_section_assigned(&mut self, section: WasmSection, rows: usize) {
+        self.sections.push((section, rows));
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.sections.clear();
+    }
+
+    /// Rows consumed by each section body in bytecode order.
+    pub fn sections(&self) -> &[(WasmSection, usize)] {
+        &self.sections
+    }
+
+    /// Rows consumed by all bodies of the `section` (custom sections may repeat).
+    pub fn rows_of(&self, section: WasmSection) -> usize {
+        self.sections
+            .iter()
+            .filter(|(s, _)| *s as u8 == section as u8)
+            .map(|(_, rows)| rows)
+            .sum()
+    }
+
+    /// Rows consumed by all section bodies.
+    pub fn total_rows(&self) -> usize {
+        self.sections.iter().map(|(_, rows)| rows).sum()
+    }
+}
@@ -14,7 +14,9 @@ use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
     wasm_circuit::{
         error::{remap_error_to_assign_at, Error},
+        leb128::consts::LEB128_MAX_BYTES_COUNT_32_BITS,
         sections::consts::LebParams,
+        tables::fixed_range::config::RangeTableConfig,
     },
 };
 
@@ -25,10 +27,16 @@ pub struct LEB128Config<F> {
     pub is_first_byte: Column<Fixed>,
     pub is_last_byte: Column<Fixed>,
     pub is_byte_has_cb: Column<Fixed>,
+    pub is_minimal: Column<Fixed>,
+    pub last_byte_high_bits_mul: Column<Fixed>,
+    pub byte_index: Column<Fixed>,
+    pub max_bytes: Column<Fixed>,
 
     pub byte_mul: Column<Advice>,
     pub sn: Column<Advice>,
     pub sn_recovered: Column<Advice>,
+    pub last_byte_inv: Column<Advice>,
+    pub max_byte_index_diff_inv: Column<Advice>,
 
     _marker: PhantomData<F>,
 }
@@ -50,16 +58,92 @@ impl<F: Field> LEB128Chip<F> {
         instance
     }
 
-    pub fn configure(cs: &mut ConstraintSystem<F>, bytes: &Column<Advice>) -> LEB128Config<F> {
+    /// `require_minimal` enables minimal encoding checks for unsigned values marked with
+    /// `is_minimal`: the last byte is nonzero (except for a single-byte zero) and the bits unused
+    /// by the declared width are zero (checked against the provided byte range table)
+    ///
+    /// `max_bytes` is the upper bound for the per-row `max_bytes` value which limits the number of
+    /// bytes a single leb128 may occupy (5 for 32-bit fields, up to 10 for 64-bit fields)
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        bytes: &Column<Advice>,
+        max_bytes: usize,
+        require_minimal: Option<&RangeTableConfig<F, 0, 256>>,
+    ) -> LEB128Config<F> {
         let q_enable = cs.fixed_column();
         let is_signed = cs.fixed_column();
         let is_first_byte = cs.fixed_column();
         let is_last_byte = cs.fixed_column();
         let is_byte_has_cb = cs.fixed_column();
+        let is_minimal = cs.fixed_column();
+        let last_byte_high_bits_mul = cs.fixed_column();
+        let byte_index = cs.fixed_column();
+        let max_bytes_col = cs.fixed_column();
 
         let byte_mul = cs.advice_column();
         let sn = cs.advice_column();
         let sn_recovered = cs.advice_column();
+        let last_byte_inv = cs.advice_column();
+        let max_byte_index_diff_inv = cs.advice_column();
+
+        if let Some(range_table_config_0_256) = require_minimal {
+            cs.create_gate("LEB128 minimal encoding gate", |vc| {
+                let mut cb = BaseConstraintBuilder::default();
+
+                let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+                let is_signed_expr = vc.query_fixed(is_signed, Rotation::cur());
+                let is_first_byte_expr = vc.query_fixed(is_first_byte, Rotation::cur());
+                let is_last_byte_expr = vc.query_fixed(is_last_byte, Rotation::cur());
+                let is_minimal_expr = vc.query_fixed(is_minimal, Rotation::cur());
+                let last_byte_inv_expr = vc.query_advice(last_byte_inv, Rotation::cur());
+                let byte_val_expr = vc.query_advice(*bytes, Rotation::cur());
+
+                cb.require_boolean("is_minimal is bool", is_minimal_expr.clone());
+                cb.condition(not::expr(is_first_byte_expr.clone()), |cb| {
+                    let is_minimal_prev_expr = vc.query_fixed(is_minimal, Rotation::prev());
+                    cb.require_equal(
+                        "is_minimal consistent",
+                        is_minimal_prev_expr,
+                        is_minimal_expr.clone(),
+                    );
+                });
+                cb.condition(
+                    and::expr([
+                        is_minimal_expr.clone(),
+                        not::expr(is_signed_expr.clone()),
+                        is_last_byte_expr.clone(),
+                        not::expr(is_first_byte_expr.clone()),
+                    ]),
+                    |cb| {
+                        cb.require_equal(
+                            "minimal encoding => multibyte leb128 last byte is nonzero",
+                            byte_val_expr.clone() * last_byte_inv_expr.clone(),
+                            1.expr(),
+                        );
+                    },
+                );
+
+                cb.gate(q_enable_expr)
+            });
+            cs.lookup(
+                "LEB128 minimal encoding: unused high bits of last byte are zero",
+                |vc| {
+                    let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+                    let is_signed_expr = vc.query_fixed(is_signed, Rotation::cur());
+                    let last_byte_high_bits_mul_expr =
+                        vc.query_fixed(last_byte_high_bits_mul, Rotation::cur());
+                    let byte_val_expr = vc.query_advice(*bytes, Rotation::cur());
+
+                    vec![(
+                        q_enable_expr
+                            * not::expr(is_signed_expr)
+                            * last_byte_high_bits_mul_expr
+                            * byte_val_expr,
+                        range_table_config_0_256.value,
+                    )]
+                },
+            );
+        }
 
         cs.create_gate("LEB128 gate", |vc| {
             let mut cb = BaseConstraintBuilder::default();
@@ -132,6 +216,53 @@ impl<F: Field> LEB128Chip<F> {
                 cb.require_zero("byte_has_cb is 0 on last_byte", is_byte_has_cb_expr.clone());
             });
 
+            let byte_index_expr = vc.query_fixed(byte_index, Rotation::cur());
+            let max_bytes_expr = vc.query_fixed(max_bytes_col, Rotation::cur());
+            let max_byte_index_diff_inv_expr =
+                vc.query_advice(max_byte_index_diff_inv, Rotation::cur());
+            cb.condition(is_first_byte_expr.clone(), |cb| {
+                cb.require_zero("byte_index=0 at first byte", byte_index_expr.clone());
+                cb.require_in_set(
+                    "max_bytes is in the allowed set",
+                    max_bytes_expr.clone(),
+                    vec![LEB128_MAX_BYTES_COUNT_32_BITS.expr(), max_bytes.expr()],
+                );
+            });
+            cb.condition(
+                and::expr([
+                    not::expr(is_first_byte_expr.clone()),
+                    is_consider_byte_expr.clone(),
+                ]),
+                |cb| {
+                    let byte_index_prev_expr = vc.query_fixed(byte_index, Rotation::prev());
+                    let max_bytes_prev_expr = vc.query_fixed(max_bytes_col, Rotation::prev());
+                    cb.require_equal(
+                        "byte_index grows by 1",
+                        byte_index_prev_expr + 1.expr(),
+                        byte_index_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "max_bytes consistent",
+                        max_bytes_prev_expr,
+                        max_bytes_expr.clone(),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([
+                    not::expr(is_last_byte_expr.clone()),
+                    is_consider_byte_expr.clone(),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "not is_last_byte => byte_index < max_bytes-1",
+                        (max_bytes_expr.clone() - 1.expr() - byte_index_expr.clone())
+                            * max_byte_index_diff_inv_expr.clone(),
+                        1.expr(),
+                    );
+                },
+            );
+
             cb.condition(
                 and::expr([
                     not::expr(is_last_byte_expr.clone()),
@@ -200,9 +331,15 @@ impl<F: Field> LEB128Chip<F> {
             is_first_byte,
             is_last_byte,
             is_byte_has_cb,
+            is_minimal,
+            last_byte_high_bits_mul,
+            byte_index,
+            max_bytes: max_bytes_col,
             byte_mul,
             sn,
             sn_recovered,
+            last_byte_inv,
+            max_byte_index_diff_inv,
             _marker: PhantomData,
         };
 
@@ -279,6 +416,83 @@ impl<F: Field> LEB128Chip<F> {
             )
             .map_err(remap_error_to_assign_at(assign_offset))?;
 
+        region
+            .assign_fixed(
+                || {
+                    format!(
+                        "assign 'is_minimal' to {} at {}",
+                        p.is_minimal, assign_offset
+                    )
+                },
+                self.config.is_minimal,
+                assign_offset,
+                || Value::known(F::from(p.is_minimal as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        let last_byte_high_bits_mul = p.last_byte_high_bits_mul();
+        region
+            .assign_fixed(
+                || {
+                    format!(
+                        "assign 'last_byte_high_bits_mul' to {} at {}",
+                        last_byte_high_bits_mul, assign_offset
+                    )
+                },
+                self.config.last_byte_high_bits_mul,
+                assign_offset,
+                || Value::known(F::from(last_byte_high_bits_mul)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        let last_byte_inv = if p.is_last_byte() {
+            F::from(p.byte_val as u64).invert().unwrap_or(F::zero())
+        } else {
+            F::zero()
+        };
+        region
+            .assign_advice(
+                || format!("assign 'last_byte_inv' at {}", assign_offset),
+                self.config.last_byte_inv,
+                assign_offset,
+                || Value::known(last_byte_inv),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        region
+            .assign_fixed(
+                || {
+                    format!(
+                        "assign 'byte_index' to {} at {}",
+                        p.byte_rel_offset, assign_offset
+                    )
+                },
+                self.config.byte_index,
+                assign_offset,
+                || Value::known(F::from(p.byte_rel_offset as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        region
+            .assign_fixed(
+                || format!("assign 'max_bytes' to {} at {}", p.max_bytes, assign_offset),
+                self.config.max_bytes,
+                assign_offset,
+                || Value::known(F::from(p.max_bytes as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
+        let max_byte_index_diff =
+            F::from(p.max_bytes as u64) - F::one() - F::from(p.byte_rel_offset as u64);
+        region
+            .assign_advice(
+                || format!("assign 'max_byte_index_diff_inv' at {}", assign_offset),
+                self.config.max_byte_index_diff_inv,
+                assign_offset,
+                || Value::known(max_byte_index_diff.invert().unwrap_or(F::zero())),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+
         let leb_byte_mul = if p.is_byte_has_cb() || p.is_last_byte() {
             pow(0b10000000, p.byte_rel_offset)
         } else {
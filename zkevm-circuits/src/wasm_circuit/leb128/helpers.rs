@@ -1,8 +1,16 @@
+//! Witness-side LEB128 decoding and encoding, the values computed here are the ones the
+//! `LEB128Chip` expects to be assigned (e.g. by witness generators of custom sections).
+//!
+//! Offsets are bytecode offsets. With `is_signed` the bytes are expected to encode a negative
+//! number and its absolute value is returned (this is how the chip tracks signed values).
+
 use num_traits::pow;
 
 use crate::wasm_circuit::error::{Error, remap_error};
 use crate::wasm_circuit::leb128::consts::{EIGHT_MS_BIT_MASK, LEB128_MAX_BYTES_COUNT};
 
+/// returns the value recovered from the bytes up to (and including) the byte at `byte_rel_offset`
+/// given the value recovered at the previous byte. Bytes past `last_byte_rel_offset` keep it zero
 pub fn leb128_compute_sn_recovered_at_position(
     sn_recovered_at_prev_pos: u64,
     is_signed: bool,
@@ -26,23 +34,28 @@ pub fn leb128_compute_sn_recovered_at_position(
     sn_recovered_at_pos
 }
 
+/// returns the offset of the last byte (the one without the continuation bit) of the number
+/// starting at `first_byte_offset`.
+///
+/// fails with `UnexpectedEndOfBytes` (offset of the missing byte) if the bytes end before the
+/// last byte and with `Leb128MaxBytes` if the number takes more than `LEB128_MAX_BYTES_COUNT` bytes
 pub fn leb128_compute_last_byte_offset(
     bytes: &[u8],
     first_byte_offset: usize,
 ) -> Result<usize, Error> {
     let mut offset = first_byte_offset;
     loop {
-        let byte = bytes.get(offset).ok_or(Error::IndexOutOfBoundsSimple)?;
+        let byte = bytes.get(offset).ok_or(Error::UnexpectedEndOfBytes(offset))?;
         if byte & EIGHT_MS_BIT_MASK == 0 { break }
         offset += 1;
         let byte_offset = offset - first_byte_offset;
-        if byte_offset >= LEB128_MAX_BYTES_COUNT { return Err(Error::IndexOutOfBoundsSimple) }
+        if byte_offset >= LEB128_MAX_BYTES_COUNT { return Err(Error::Leb128MaxBytes) }
     }
 
     Ok(offset)
 }
 
-/// returns SN and last byte offset
+/// returns SN and last byte offset, fails as `leb128_compute_last_byte_offset` does
 pub fn leb128_compute_sn(
     bytes: &[u8],
     is_signed: bool,
@@ -62,6 +75,7 @@ pub fn leb128_compute_sn(
     Ok((sn, last_byte_offset))
 }
 
+/// encodes the `value`, negative values can be encoded as signed only
 pub fn leb128_encode(
     is_signed: bool,
     value: i128,
@@ -10,8 +10,10 @@ use log::debug;
 use eth_types::Field;
 
 use crate::wasm_circuit::leb128::circuit::{LEB128Chip, LEB128Config};
+use crate::wasm_circuit::leb128::consts::LEB128_MAX_BYTES_COUNT;
 use crate::wasm_circuit::leb128::helpers::leb128_compute_sn_recovered_at_position;
 use crate::wasm_circuit::sections::consts::LebParams;
+use crate::wasm_circuit::tables::fixed_range::config::RangeTableConfig;
 
 #[derive(Default)]
 struct TestCircuit<'a, F, const IS_SIGNED: bool> {
@@ -20,6 +22,9 @@ struct TestCircuit<'a, F, const IS_SIGNED: bool> {
     is_signed: bool,
     sn: u64,
     offset_shift: usize,
+    is_minimal: bool,
+    bit_width: usize,
+    max_bytes: usize,
     _marker: PhantomData<F>,
 }
 
@@ -27,6 +32,7 @@ struct TestCircuit<'a, F, const IS_SIGNED: bool> {
 struct TestCircuitConfig<F, const IS_SIGNED: bool> {
     leb_bytes: Column<Advice>,
     leb128_config: LEB128Config<F>,
+    range_table_config_0_256: RangeTableConfig<F, 0, 256>,
     _marker: PhantomData<F>,
 }
 
@@ -40,13 +46,17 @@ impl<'a, F: Field, const IS_SIGNED: bool> Circuit<F> for TestCircuit<'a, F, IS_S
         cs: &mut ConstraintSystem<F>,
     ) -> Self::Config {
         let leb_bytes = cs.advice_column();
+        let range_table_config_0_256 = RangeTableConfig::configure(cs);
         let leb128_config = LEB128Chip::<F>::configure(
             cs,
             &leb_bytes,
+            LEB128_MAX_BYTES_COUNT,
+            Some(&range_table_config_0_256),
         );
         let test_circuit_config = TestCircuitConfig {
             leb_bytes,
             leb128_config,
+            range_table_config_0_256,
             _marker: Default::default(),
         };
 
@@ -59,6 +69,7 @@ impl<'a, F: Field, const IS_SIGNED: bool> Circuit<F> for TestCircuit<'a, F, IS_S
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         let leb128_chip = LEB128Chip::construct(config.leb128_config);
+        config.range_table_config_0_256.load(&mut layouter)?;
 
         layouter.assign_region(
             || "leb128 region",
@@ -85,6 +96,10 @@ impl<'a, F: Field, const IS_SIGNED: bool> Circuit<F> for TestCircuit<'a, F, IS_S
                         last_byte_rel_offset: self.leb_bytes_last_byte_index as usize,
                         sn: self.sn,
                         sn_recovered_at_pos,
+                        byte_val: leb_byte,
+                        is_minimal: self.is_minimal,
+                        bit_width: self.bit_width,
+                        max_bytes: self.max_bytes,
                     };
                     debug!(
                         "offset {} is_signed '{}' leb_byte_offset '{}' sn_recovered_at_pos '{}' is_last_leb_byte '{}'",
@@ -122,7 +137,7 @@ mod leb128_circuit_tests {
     use eth_types::Field;
 
     use crate::wasm_circuit::error::Error;
-    use crate::wasm_circuit::leb128::consts::{EIGHT_LS_BITS_MASK, EIGHT_MS_BIT_MASK, SEVEN_LS_BITS_MASK};
+    use crate::wasm_circuit::leb128::consts::{EIGHT_LS_BITS_MASK, EIGHT_MS_BIT_MASK, LEB128_MAX_BYTES_COUNT, LEB128_MAX_BYTES_COUNT_32_BITS, SEVEN_LS_BITS_MASK};
     use crate::wasm_circuit::leb128::tests::TestCircuit;
     use crate::wasm_circuit::tests_helpers::break_bit_by_mask;
 
@@ -246,7 +261,7 @@ mod leb128_circuit_tests {
         test_circuit: TestCircuit<'_, F, IS_SIGNED>,
         is_ok: bool,
     ) {
-        let k = 5;
+        let k = 9;
         let prover = MockProver::run(k, &test_circuit, vec![]).unwrap();
         if is_ok {
             prover.assert_satisfied();
@@ -273,6 +288,9 @@ mod leb128_circuit_tests {
             is_signed: IS_SIGNED,
             sn: solid_number,
             offset_shift,
+            is_minimal: false,
+            bit_width: 64,
+            max_bytes: LEB128_MAX_BYTES_COUNT,
             _marker: PhantomData
         };
         self::test(circuit, true);
@@ -340,6 +358,9 @@ mod leb128_circuit_tests {
                 is_signed: IS_SIGNED,
                 sn: solid_number,
                 offset_shift: 0,
+                is_minimal: false,
+                bit_width: 64,
+                max_bytes: LEB128_MAX_BYTES_COUNT,
                 _marker: PhantomData
             };
             self::test(circuit, true);
@@ -410,6 +431,9 @@ mod leb128_circuit_tests {
                 is_signed: IS_SIGNED,
                 sn: solid_number,
                 offset_shift: 0,
+                is_minimal: false,
+                bit_width: 64,
+                max_bytes: LEB128_MAX_BYTES_COUNT,
                 _marker: PhantomData
             };
             self::test(circuit, false);
@@ -483,6 +507,9 @@ mod leb128_circuit_tests {
                 is_signed: IS_SIGNED,
                 sn: solid_number,
                 offset_shift: 0,
+                is_minimal: false,
+                bit_width: 64,
+                max_bytes: LEB128_MAX_BYTES_COUNT,
                 _marker: PhantomData
             };
             self::test(circuit, false);
@@ -519,4 +546,131 @@ mod leb128_circuit_tests {
         leb_broken_random_bit::<8, IS_SIGNED>();
         leb_broken_random_bit::<9, IS_SIGNED>();
     }
-}
\ No newline at end of file
+
+    fn minimal_encoding(leb_bytes: &[u8], sn: u64, bit_width: usize, is_ok: bool) {
+        let circuit = TestCircuit::<Fr, false> {
+            leb_bytes,
+            leb_bytes_last_byte_index: leb_bytes.len() as u64 - 1,
+            is_signed: false,
+            sn,
+            offset_shift: 0,
+            is_minimal: true,
+            bit_width,
+            max_bytes: LEB128_MAX_BYTES_COUNT,
+            _marker: PhantomData
+        };
+        self::test(circuit, is_ok);
+    }
+
+    #[test]
+    pub fn test_minimal_encoding_ok() {
+        minimal_encoding(&[0x00], 0, 32, true);
+        minimal_encoding(&[0x7f], 127, 32, true);
+        minimal_encoding(&[0xac, 0x02], 300, 32, true);
+        minimal_encoding(&[0xff, 0xff, 0xff, 0xff, 0x0f], u32::MAX as u64, 32, true);
+        minimal_encoding(&[0x80, 0x80, 0x80, 0x80, 0x10], 1 << 32, 64, true);
+    }
+
+    #[test]
+    pub fn test_minimal_encoding_trailing_zero_byte_fails() {
+        minimal_encoding(&[0x80, 0x00], 0, 32, false);
+        minimal_encoding(&[0x81, 0x80, 0x00], 1, 32, false);
+    }
+
+    #[test]
+    pub fn test_non_minimal_encoding_ok_when_not_required() {
+        let circuit = TestCircuit::<Fr, false> {
+            leb_bytes: &[0x80, 0x00],
+            leb_bytes_last_byte_index: 1,
+            is_signed: false,
+            sn: 0,
+            offset_shift: 0,
+            is_minimal: false,
+            bit_width: 32,
+            max_bytes: LEB128_MAX_BYTES_COUNT,
+            _marker: PhantomData
+        };
+        self::test(circuit, true);
+    }
+
+    #[test]
+    pub fn test_minimal_encoding_unused_high_bits_fails() {
+        minimal_encoding(&[0x80, 0x80, 0x80, 0x80, 0x10], 1 << 32, 32, false);
+        minimal_encoding(&[0xff, 0xff, 0xff, 0xff, 0x7f], (1 << 35) - 1, 32, false);
+    }
+    fn leb_with_max_bytes(leb_bytes: &[u8], sn: u64, max_bytes: usize, is_ok: bool) {
+        let circuit = TestCircuit::<Fr, false> {
+            leb_bytes,
+            leb_bytes_last_byte_index: leb_bytes.len() as u64 - 1,
+            is_signed: false,
+            sn,
+            offset_shift: 0,
+            is_minimal: false,
+            bit_width: 64,
+            max_bytes,
+            _marker: PhantomData
+        };
+        self::test(circuit, is_ok);
+    }
+
+    #[test]
+    pub fn test_max_bytes_ok() {
+        leb_with_max_bytes(&[0x01], 1, LEB128_MAX_BYTES_COUNT_32_BITS, true);
+        leb_with_max_bytes(&[0xff, 0xff, 0xff, 0xff, 0x0f], u32::MAX as u64, LEB128_MAX_BYTES_COUNT_32_BITS, true);
+        leb_with_max_bytes(&[0x81, 0x80, 0x80, 0x80, 0x00], 1, LEB128_MAX_BYTES_COUNT_32_BITS, true);
+        leb_with_max_bytes(&[0x81, 0x80, 0x80, 0x80, 0x80, 0x00], 1, LEB128_MAX_BYTES_COUNT, true);
+    }
+
+    #[test]
+    pub fn test_max_bytes_over_long_encoding_fails() {
+        leb_with_max_bytes(&[0x81, 0x80, 0x80, 0x80, 0x80, 0x00], 1, LEB128_MAX_BYTES_COUNT_32_BITS, false);
+        leb_with_max_bytes(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00], 0, LEB128_MAX_BYTES_COUNT_32_BITS, false);
+    }
+}
+
+#[cfg(test)]
+mod leb128_helpers_tests {
+    use crate::wasm_circuit::{
+        error::Error,
+        leb128::{
+            consts::LEB128_MAX_BYTES_COUNT,
+            helpers::{leb128_compute_last_byte_offset, leb128_compute_sn, leb128_encode},
+        },
+    };
+
+    #[test]
+    pub fn max_u64_is_decoded() {
+        let bytes = leb128_encode(false, u64::MAX as i128).unwrap();
+        assert_eq!(bytes.len(), LEB128_MAX_BYTES_COUNT);
+        assert_eq!(
+            leb128_compute_sn(&bytes, false, 0).unwrap(),
+            (u64::MAX, LEB128_MAX_BYTES_COUNT - 1)
+        );
+    }
+
+    #[test]
+    pub fn single_byte_zero_is_decoded() {
+        assert_eq!(leb128_compute_sn(&[0x00], false, 0).unwrap(), (0, 0));
+        assert_eq!(leb128_compute_sn(&[0x80, 0x00, 0x00], false, 1).unwrap(), (0, 1));
+    }
+
+    #[test]
+    pub fn negative_number_is_decoded_as_absolute_value() {
+        let bytes = leb128_encode(true, -129).unwrap();
+        assert_eq!(leb128_compute_sn(&bytes, true, 0).unwrap(), (129, bytes.len() - 1));
+    }
+
+    #[test]
+    pub fn missing_last_byte_fails() {
+        assert_eq!(
+            leb128_compute_last_byte_offset(&[0x00, 0x80, 0x80], 1),
+            Err(Error::UnexpectedEndOfBytes(3))
+        );
+    }
+
+    #[test]
+    pub fn too_many_bytes_fail() {
+        let bytes = [0x80; LEB128_MAX_BYTES_COUNT + 1];
+        assert_eq!(leb128_compute_sn(&bytes, false, 0), Err(Error::Leb128MaxBytes));
+    }
+}
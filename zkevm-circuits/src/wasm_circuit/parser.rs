@@ -0,0 +1,212 @@
+use std::ops::Range;
+
+use crate::wasm_circuit::{
+    common::wasm_compute_section_len,
+    consts::{WASM_SECTIONS_START_INDEX, WASM_VERSION_PREFIX_LEN, WASM_VERSION_PREFIX_START_INDEX},
+    error::Error,
+    leb128::helpers::leb128_compute_sn,
+    types::{SectionParseSummary, WasmSection},
+};
+
+/// Location of a section inside the module bytecode, offsets are relative to the bytecode start.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SectionSpan {
+    /// raw section id byte, it is not checked against the known sections
+    pub section_id: u8,
+    pub id_offset: usize,
+    pub len_offset: usize,
+    pub body_offset: usize,
+    pub body_len: usize,
+}
+
+impl SectionSpan {
+    /// offset right after the last byte of the section body
+    pub fn end_offset(&self) -> usize {
+        self.body_offset + self.body_len
+    }
+}
+
+/// Section reported by `WasmChip::decoded_sections` once it got assigned.
+#[derive(Clone, Debug)]
+pub struct DecodedSection {
+    pub section: WasmSection,
+    /// bytes of the whole section (id and len included), relative to the bytecode start
+    pub range: Range<usize>,
+    /// leading item count of the section body (funcs, exports, etc.), `None` for the sections
+    /// which have no such count (custom, start and data count)
+    pub items_count: Option<u64>,
+}
+
+impl DecodedSection {
+    /// builds the report out of what the section body chip parsed, without decoding the section
+    /// body again
+    pub fn from_summary(
+        section: WasmSection,
+        section_span: &SectionSpan,
+        summary: &SectionParseSummary,
+    ) -> Self {
+        Self {
+            section,
+            range: section_span.id_offset..section_span.end_offset(),
+            items_count: Some(summary.items_count),
+        }
+    }
+
+    pub fn decode(
+        bytes: &[u8],
+        section: WasmSection,
+        section_span: &SectionSpan,
+    ) -> Result<Self, Error> {
+        let items_count = match section {
+            WasmSection::Custom | WasmSection::Start | WasmSection::DataCount => None,
+            // an empty body holds no items
+            _ if section_span.body_len == 0 => Some(0),
+            _ => Some(leb128_compute_sn(bytes, false, section_span.body_offset)?.0),
+        };
+        Ok(Self {
+            section,
+            range: section_span.id_offset..section_span.end_offset(),
+            items_count,
+        })
+    }
+}
+
+/// returns the little-endian version word following the magic prefix, fails with
+/// `IndexOutOfBoundsAt` (offset of the first missing byte) if the bytecode is too short
+pub fn parse_version(bytes: &[u8]) -> Result<u32, Error> {
+    let mut version_bytes = [0u8; 4];
+    for (idx, version_byte) in version_bytes
+        .iter_mut()
+        .enumerate()
+        .take(WASM_VERSION_PREFIX_LEN)
+    {
+        let offset = WASM_VERSION_PREFIX_START_INDEX + idx;
+        *version_byte = *bytes.get(offset).ok_or(Error::IndexOutOfBoundsAt(offset))?;
+    }
+    Ok(u32::from_le_bytes(version_bytes))
+}
+
+/// splits the module bytecode (magic prefix and version included) into sections without assigning
+/// anything.
+///
+/// fails with `ComputeValueAt` if a section len can't be decoded and with `SectionLenOverflow` if
+/// a section body goes over the end of the bytecode (offsets point at the section len)
+pub fn parse_sections(bytes: &[u8]) -> Result<Vec<SectionSpan>, Error> {
+    let mut spans = vec![];
+    let mut offset = WASM_SECTIONS_START_INDEX;
+    while offset < bytes.len() {
+        let id_offset = offset;
+        let len_offset = id_offset + 1;
+        let (body_len, body_len_leb_len) =
+            wasm_compute_section_len(bytes, len_offset).map_err(|e| match e {
+                Error::SectionLenOverflow(_) => e,
+                _ => Error::ComputeValueAt(len_offset),
+            })?;
+        let span = SectionSpan {
+            section_id: bytes[id_offset],
+            id_offset,
+            len_offset,
+            body_offset: len_offset + body_len_leb_len as usize,
+            body_len,
+        };
+        offset = span.end_offset();
+        spans.push(span);
+    }
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use wabt::wat2wasm;
+
+    use crate::wasm_circuit::{
+        common::wasm_compute_section_len,
+        consts::WASM_SECTIONS_START_INDEX,
+        error::Error,
+        parser::{parse_sections, parse_version, SectionSpan},
+        types::WasmSection,
+    };
+
+    #[test]
+    pub fn sections_of_file1_are_contiguous() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let spans = parse_sections(&bytes).unwrap();
+        assert!(!spans.is_empty());
+        assert_eq!(spans[0].id_offset, WASM_SECTIONS_START_INDEX);
+        for (prev, next) in spans.iter().zip(spans.iter().skip(1)) {
+            assert_eq!(prev.end_offset(), next.id_offset);
+        }
+        assert_eq!(spans.last().unwrap().end_offset(), bytes.len());
+    }
+
+    #[test]
+    pub fn multibyte_section_len() {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Custom as u8, 0x81, 0x01]);
+        bytes.extend([0x00; 129]);
+        bytes.extend([WasmSection::Start as u8, 0x01, 0x00]);
+        let spans = parse_sections(&bytes).unwrap();
+        assert_eq!(
+            spans,
+            vec![
+                SectionSpan {
+                    section_id: WasmSection::Custom as u8,
+                    id_offset: 8,
+                    len_offset: 9,
+                    body_offset: 11,
+                    body_len: 129,
+                },
+                SectionSpan {
+                    section_id: WasmSection::Start as u8,
+                    id_offset: 140,
+                    len_offset: 141,
+                    body_offset: 142,
+                    body_len: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn truncated_section_body_fails() {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Start as u8, 0x02, 0x00]);
+        assert_eq!(parse_sections(&bytes), Err(Error::SectionLenOverflow(9)));
+    }
+
+    #[test]
+    pub fn version_is_little_endian_word() {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(parse_version(&bytes), Ok(1));
+        bytes[4..8].copy_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(parse_version(&bytes), Ok(1 << 24));
+        assert_eq!(
+            parse_version(&bytes[..6]),
+            Err(Error::IndexOutOfBoundsAt(6))
+        );
+    }
+
+    #[test]
+    pub fn truncated_section_len_fails() {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Start as u8, 0x80]);
+        assert_eq!(parse_sections(&bytes), Err(Error::ComputeValueAt(9)));
+    }
+
+    #[test]
+    pub fn huge_section_len_overflows() {
+        let mut bytes = vec![WasmSection::Custom as u8, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        bytes.extend([0x00; 4]);
+        assert_eq!(bytes.len(), 10);
+        assert_eq!(
+            wasm_compute_section_len(&bytes, 1),
+            Err(Error::SectionLenOverflow(1))
+        );
+    }
+}
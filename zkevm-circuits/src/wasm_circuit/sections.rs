@@ -5,8 +5,10 @@ pub mod memory;
 pub mod export;
 pub mod code;
 pub mod data;
+pub mod data_count;
 pub mod global;
 pub mod consts;
+pub mod custom;
 pub mod start;
 pub mod table;
 pub mod element;
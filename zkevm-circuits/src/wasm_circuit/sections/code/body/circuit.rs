@@ -1,8 +1,8 @@
-use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use std::{cell::RefCell, marker::PhantomData, ops::Range, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Chip, Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -11,36 +11,48 @@ use log::debug;
 use eth_types::Field;
 use gadgets::{
     binary_number::BinaryNumberChip,
+    is_zero::{IsZeroChip, IsZeroInstruction},
     less_than::{LtChip, LtInstruction},
     util::{and, not, or, Expr},
 };
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmBlockLevelAwareChip, WasmCountPrefixedItemsAwareChip,
-            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
-            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmBlockLevelAwareChip,
+            WasmColumnAnnotationAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
+            WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
         },
         consts::{WASM_BLOCKTYPE_DELIMITER, WASM_BLOCK_END},
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
-        leb128::circuit::LEB128Chip,
+        leb128::{circuit::LEB128Chip, helpers::leb128_compute_sn},
         sections::{code::body::types::AssignType, consts::LebParams},
         tables::{
             code_blocks, code_blocks::circuit::CodeBlocksChip,
-            dynamic_indexes::circuit::DynamicIndexesChip,
+            dynamic_indexes::{
+                circuit::DynamicIndexesChip,
+                types::{LookupArgsParams, Tag},
+            },
         },
         types::{
             AssignDeltaType, AssignValueType, ControlInstruction, NumericInstruction,
-            ParametricInstruction, SharedState, VariableInstruction, CONTROL_INSTRUCTION_BLOCK,
-            CONTROL_INSTRUCTION_WITHOUT_ARGS, CONTROL_INSTRUCTION_WITH_LEB_ARG,
-            NUMERIC_INSTRUCTIONS_WITHOUT_ARGS, NUMERIC_INSTRUCTION_WITH_LEB_ARG,
-            PARAMETRIC_INSTRUCTIONS_WITHOUT_ARGS, VARIABLE_INSTRUCTION_WITH_LEB_ARG,
+            NumType, ParametricInstruction, RefType, SectionParseSummary, SharedState,
+            VariableInstruction,
+            CONTROL_INSTRUCTION_BLOCK, CONTROL_INSTRUCTION_WITHOUT_ARGS,
+            CONTROL_INSTRUCTION_WITH_LEB_ARG, CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG,
+            NUMERIC_INSTRUCTIONS_WITHOUT_ARGS,
+            NUMERIC_INSTRUCTION_PURE_BINARY, NUMERIC_INSTRUCTION_PURE_UNARY,
+            NUMERIC_INSTRUCTION_WITH_LEB_ARG, NUM_TYPE_VALUES, PARAMETRIC_INSTRUCTIONS_WITHOUT_ARGS,
+            REF_TYPE_VALUES, VARIABLE_INSTRUCTION_WITH_LEB_ARG,
         },
     },
 };
@@ -63,9 +75,18 @@ pub struct WasmCodeSectionBodyConfig<F: Field> {
     pub is_control_instruction: Column<Fixed>,
     pub is_control_instruction_leb_arg: Column<Fixed>,
     pub is_parametric_instruction: Column<Fixed>,
+    /// empty blocktype (`WASM_BLOCKTYPE_DELIMITER`)
     pub is_blocktype_delimiter: Column<Fixed>,
+    /// single result blocktype, a valtype byte
+    pub is_blocktype_valtype: Column<Fixed>,
+    /// blocktype referring a func type, a non-negative s33 type index
+    pub is_blocktype_type_index: Column<Fixed>,
+    /// type index (leb128) and table index (the reserved `0x00` byte) of a `call_indirect`
+    pub is_call_indirect_type_index: Column<Fixed>,
+    pub is_call_indirect_table_index: Column<Fixed>,
     pub is_block_end: Column<Fixed>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
     pub numeric_instructions_chip: Rc<BinaryNumberChip<F, NumericInstruction, 8>>,
     pub variable_instruction_chip: Rc<BinaryNumberChip<F, VariableInstruction, 8>>,
@@ -74,15 +95,46 @@ pub struct WasmCodeSectionBodyConfig<F: Field> {
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
 
     pub code_blocks_chip: Rc<CodeBlocksChip<F>>,
-    block_opcode_number: Column<Advice>,
+    pub block_opcode_number: Column<Advice>,
 
     pub func_count: Column<Advice>,
     pub block_level: Column<Advice>,
     pub block_level_lt_chip: Rc<LtChip<F, 2>>,
-    body_byte_rev_index: Column<Advice>,
-    body_item_rev_count: Column<Advice>,
-
-    error_code: Column<Advice>,
+    /// `0 < prev.block_level` on `end`, so an `end` can't close more blocks than were opened
+    pub block_end_level_lt_chip: Rc<LtChip<F, 2>>,
+    /// `0 < block_level` on every `end` but the last one of a func body, so the func base level
+    /// is reached exactly once
+    pub inner_block_end_level_lt_chip: Rc<LtChip<F, 2>>,
+    pub locals_count: Column<Advice>,
+    pub local_index_lt_chip: Rc<LtChip<F, 4>>,
+    /// typeidx of the func body and the params count of the type, bound to the function and type
+    /// sections by `WasmChip`
+    pub func_type_index: Column<Advice>,
+    pub params_count: Column<Advice>,
+    /// params count + locals declared up to the row, equals `locals_count` at the end of the func
+    /// body
+    pub declared_locals_count: Column<Advice>,
+    /// instructions of the section up to the row, the row included. See `extract_branch_targets`
+    /// for what an instruction is, the program counter of an instruction is the count before it
+    pub instructions_count: Column<Advice>,
+    /// label of a `br`/`br_if` leaving a block: program counter the branch jumps to and code
+    /// blocks indexes of the `block`/`loop`/`if` it leaves and of its `end`
+    pub branch_target: Column<Advice>,
+    pub branch_block_index: Column<Advice>,
+    pub branch_block_end_index: Column<Advice>,
+    /// `branch_block_index < block_opcode_number + 1` and `block_opcode_number <
+    /// branch_block_end_index` on a branch label, the branch is inside the block it leaves
+    pub branch_block_index_lt_chip: Rc<LtChip<F, 4>>,
+    pub branch_block_end_index_lt_chip: Rc<LtChip<F, 4>>,
+    /// `block_level - 1 - label = 0` on a branch label, the branch leaves the func body (returns)
+    pub branch_leaves_func_body_chip: Rc<IsZeroChip<F>>,
+    pub body_byte_rev_index: Column<Advice>,
+    /// `body_byte_rev_index=0` is allowed at the last byte of a func body only, so the declared
+    /// func body len can't be less or greater than the bytes count of the body
+    pub body_byte_rev_index_is_zero_chip: Rc<IsZeroChip<F>>,
+    pub body_item_rev_count: Column<Advice>,
+
+    pub error_code: Column<Advice>,
 
     pub shared_state: Rc<RefCell<SharedState>>,
 
@@ -91,6 +143,17 @@ pub struct WasmCodeSectionBodyConfig<F: Field> {
 
 impl<'a, F: Field> WasmCodeSectionBodyConfig<F> {}
 
+/// `block`/`loop`/`if` whose `end` is not assigned yet
+struct OpenCodeBlock {
+    /// code blocks index of the block
+    index: u64,
+    is_loop: bool,
+    /// instructions count at the block, a branch leaving a `loop` jumps there
+    instructions_count: u64,
+    /// (assign offset, block_opcode_number) of the labels of the branches leaving the block
+    branches: Vec<(usize, u64)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct WasmCodeSectionBodyChip<F: Field> {
     pub config: WasmCodeSectionBodyConfig<F>,
@@ -141,10 +204,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -156,6 +220,8 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
             .map_err(remap_error_to_assign_at(assign_offset))?;
         self.assign_func_count(region, assign_offset)?;
         self.assign_block_level(region, assign_offset)?;
+        self.assign_func_locals(region, assign_offset)?;
+        self.assign_instructions_count(region, assign_offset)?;
 
         for assign_type in assign_types {
             if [
@@ -166,10 +232,12 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                 AssignType::IsNumericInstructionLebArg,
                 AssignType::IsVariableInstructionLebArg,
                 AssignType::IsControlInstructionLebArg,
+                AssignType::IsBlocktypeTypeIndex,
+                AssignType::IsCallIndirectTypeIndex,
             ]
             .contains(&assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -295,7 +363,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                     if assign_value == 1 {
-                        let opcode: NumericInstruction = wb.bytes[wb_offset]
+                        let opcode: NumericInstruction = wb.byte_at(wb_offset)?
                             .try_into()
                             .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
                         self.config
@@ -334,7 +402,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                     if assign_value == 1 {
-                        let opcode = wb.bytes[wb_offset]
+                        let opcode = wb.byte_at(wb_offset)?
                             .try_into()
                             .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
                         self.config
@@ -373,7 +441,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                     if assign_value == 1 {
-                        let opcode = wb.bytes[wb_offset]
+                        let opcode = wb.byte_at(wb_offset)?
                             .try_into()
                             .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
                         self.config
@@ -412,7 +480,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                     if assign_value == 1 {
-                        let opcode = wb.bytes[wb_offset]
+                        let opcode = wb.byte_at(wb_offset)?
                             .try_into()
                             .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
                         self.config
@@ -436,6 +504,66 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::IsBlocktypeValtype => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_blocktype_valtype' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_blocktype_valtype,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsBlocktypeTypeIndex => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_blocktype_type_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_blocktype_type_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsCallIndirectTypeIndex => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_call_indirect_type_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_call_indirect_type_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsCallIndirectTableIndex => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_call_indirect_table_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_call_indirect_table_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::IsBlockEnd => {
                     region
                         .assign_fixed(
@@ -465,6 +593,10 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
                             || Value::known(F::from(assign_value)),
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
+                    self.config
+                        .body_byte_rev_index_is_zero_chip
+                        .assign(region, assign_offset, Value::known(F::from(assign_value)))
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
                 AssignType::BodyItemRevCount => {
                     region
@@ -505,6 +637,69 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmCodeSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmCodeSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "code_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_funcs_count", self.config.is_funcs_count.into()),
+                ("is_func_body_len", self.config.is_func_body_len.into()),
+                (
+                    "is_local_type_transitions_count",
+                    self.config.is_local_type_transitions_count.into(),
+                ),
+                ("is_local_repetition_count", self.config.is_local_repetition_count.into()),
+                ("is_local_type", self.config.is_local_type.into()),
+                ("is_numeric_instruction", self.config.is_numeric_instruction.into()),
+                (
+                    "is_numeric_instruction_leb_arg",
+                    self.config.is_numeric_instruction_leb_arg.into(),
+                ),
+                ("is_variable_instruction", self.config.is_variable_instruction.into()),
+                (
+                    "is_variable_instruction_leb_arg",
+                    self.config.is_variable_instruction_leb_arg.into(),
+                ),
+                ("is_control_instruction", self.config.is_control_instruction.into()),
+                (
+                    "is_control_instruction_leb_arg",
+                    self.config.is_control_instruction_leb_arg.into(),
+                ),
+                ("is_parametric_instruction", self.config.is_parametric_instruction.into()),
+                ("is_blocktype_delimiter", self.config.is_blocktype_delimiter.into()),
+                ("is_blocktype_valtype", self.config.is_blocktype_valtype.into()),
+                ("is_blocktype_type_index", self.config.is_blocktype_type_index.into()),
+                (
+                    "is_call_indirect_type_index",
+                    self.config.is_call_indirect_type_index.into(),
+                ),
+                (
+                    "is_call_indirect_table_index",
+                    self.config.is_call_indirect_table_index.into(),
+                ),
+                ("is_block_end", self.config.is_block_end.into()),
+                ("block_opcode_number", self.config.block_opcode_number.into()),
+                ("block_level", self.config.block_level.into()),
+                ("locals_count", self.config.locals_count.into()),
+                ("func_type_index", self.config.func_type_index.into()),
+                ("params_count", self.config.params_count.into()),
+                ("declared_locals_count", self.config.declared_locals_count.into()),
+                ("instructions_count", self.config.instructions_count.into()),
+                ("branch_target", self.config.branch_target.into()),
+                ("branch_block_index", self.config.branch_block_index.into()),
+                (
+                    "branch_block_end_index",
+                    self.config.branch_block_end_index.into(),
+                ),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmCodeSectionBodyChip<F> {
     pub fn construct(config: WasmCodeSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -514,6 +709,76 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         instance
     }
 
+    /// first byte of the label of a `br`/`br_if`
+    fn is_branch_label_expr(
+        vc: &mut VirtualCells<'_, F>,
+        q_first: Column<Fixed>,
+        control_instruction_chip: &BinaryNumberChip<F, ControlInstruction, 8>,
+    ) -> Expression<F> {
+        and::expr([
+            not::expr(vc.query_fixed(q_first, Rotation::cur())),
+            or::expr([
+                control_instruction_chip
+                    .config
+                    .value_equals(ControlInstruction::Br, Rotation::prev())(vc),
+                control_instruction_chip
+                    .config
+                    .value_equals(ControlInstruction::BrIf, Rotation::prev())(vc),
+            ]),
+        ])
+    }
+
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every `br`/`br_if` leaving a block has a `BranchTarget` row (first
+    /// byte of its label), every `call` a `CallFuncIndex` one (first byte of its function index)
+    /// and every `call_indirect` a `CallIndirectTypeIndex` one (first byte of its type index)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_branch_row_expr = q_enable_expr.clone()
+            * Self::is_branch_label_expr(
+                vc,
+                self.config.q_first,
+                &self.config.control_instruction_chip,
+            )
+            * not::expr(self.config.branch_leaves_func_body_chip.config().expr());
+        let is_call_row_expr = and::expr([
+            q_enable_expr.clone(),
+            not::expr(vc.query_fixed(self.config.q_first, Rotation::cur())),
+            self.config
+                .control_instruction_chip
+                .config
+                .value_equals(ControlInstruction::Call, Rotation::prev())(vc),
+        ]);
+        let is_call_indirect_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_call_indirect_type_index, Rotation::cur()),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+        ]);
+        let is_row_expr = is_branch_row_expr.clone()
+            + is_call_row_expr.clone()
+            + is_call_indirect_row_expr.clone();
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_branch_row_expr.clone() * WasmMetadataTag::BranchTarget.expr()
+                + is_call_row_expr.clone() * WasmMetadataTag::CallFuncIndex.expr()
+                + is_call_indirect_row_expr.clone() * WasmMetadataTag::CallIndirectTypeIndex.expr(),
+            is_row_expr
+                * (vc.query_advice(self.config.instructions_count, Rotation::cur()) - 1.expr()),
+            is_branch_row_expr * vc.query_advice(self.config.branch_target, Rotation::cur())
+                + (is_call_row_expr + is_call_indirect_row_expr)
+                    * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
@@ -537,6 +802,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
 
         let block_level = cs.advice_column();
         let block_opcode_number = cs.advice_column();
+        let locals_count = cs.advice_column();
+        let func_type_index = cs.advice_column();
+        let params_count = cs.advice_column();
+        let declared_locals_count = cs.advice_column();
 
         let is_numeric_instruction = cs.fixed_column();
         let is_numeric_instruction_leb_arg = cs.fixed_column();
@@ -546,6 +815,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         let is_control_instruction_leb_arg = cs.fixed_column();
         let is_parametric_instruction = cs.fixed_column();
         let is_blocktype_delimiter = cs.fixed_column();
+        let is_blocktype_valtype = cs.fixed_column();
+        let is_blocktype_type_index = cs.fixed_column();
+        let is_call_indirect_type_index = cs.fixed_column();
+        let is_call_indirect_table_index = cs.fixed_column();
         let is_block_end = cs.fixed_column();
 
         let config = CodeBlocksChip::configure(cs, shared_state.clone());
@@ -600,28 +873,173 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         );
         let block_level_lt_chip = Rc::new(LtChip::construct(config));
 
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
+                let not_q_first_expr = not::expr(q_first_expr.clone());
+                let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
+
+                and::expr([q_enable_expr.clone(), not_q_first_expr, is_block_end_expr])
+            },
+            |_vc| 0.expr(),
+            |vc| vc.query_advice(block_level, Rotation::prev()),
+        );
+        let block_end_level_lt_chip = Rc::new(LtChip::construct(config));
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let not_q_last_expr = not::expr(vc.query_fixed(q_last, Rotation::cur()));
+                let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
+                let not_is_func_body_len_next_expr =
+                    not::expr(vc.query_fixed(is_func_body_len, Rotation::next()));
+
+                and::expr([
+                    q_enable_expr.clone(),
+                    not_q_last_expr,
+                    is_block_end_expr,
+                    not_is_func_body_len_next_expr,
+                ])
+            },
+            |_vc| 0.expr(),
+            |vc| vc.query_advice(block_level, Rotation::cur()),
+        );
+        let inner_block_end_level_lt_chip = Rc::new(LtChip::construct(config));
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
+                let not_q_first_expr = not::expr(q_first_expr.clone());
+                let is_variable_instruction_prev_expr =
+                    vc.query_fixed(is_variable_instruction, Rotation::prev());
+                // variable instruction which is not global.get/global.set is a local one
+                let is_local_instruction_prev_expr = not::expr(
+                    variable_instruction_chip
+                        .config
+                        .value_equals(VariableInstruction::GlobalGet, Rotation::prev())(
+                        vc
+                    ) + variable_instruction_chip
+                        .config
+                        .value_equals(VariableInstruction::GlobalSet, Rotation::prev())(
+                        vc
+                    ),
+                );
+
+                and::expr([
+                    q_enable_expr.clone(),
+                    not_q_first_expr,
+                    is_variable_instruction_prev_expr,
+                    is_local_instruction_prev_expr,
+                ])
+            },
+            |vc| vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+            |vc| vc.query_advice(locals_count, Rotation::cur()),
+        );
+        let local_index_lt_chip = Rc::new(LtChip::construct(config));
+
+        let instructions_count = cs.advice_column();
+        let branch_target = cs.advice_column();
+        let branch_block_index = cs.advice_column();
+        let branch_block_end_index = cs.advice_column();
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                ) * Self::is_branch_label_expr(vc, q_first, &control_instruction_chip)
+            },
+            |vc| vc.query_advice(branch_block_index, Rotation::cur()),
+            |vc| vc.query_advice(block_opcode_number, Rotation::cur()) + 1.expr(),
+        );
+        let branch_block_index_lt_chip = Rc::new(LtChip::construct(config));
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                ) * Self::is_branch_label_expr(vc, q_first, &control_instruction_chip)
+            },
+            |vc| vc.query_advice(block_opcode_number, Rotation::cur()),
+            |vc| vc.query_advice(branch_block_end_index, Rotation::cur()),
+        );
+        let branch_block_end_index_lt_chip = Rc::new(LtChip::construct(config));
+
+        let value_inv = cs.advice_column();
+        let config = IsZeroChip::configure(
+            cs,
+            |vc| {
+                Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                ) * Self::is_branch_label_expr(vc, q_first, &control_instruction_chip)
+            },
+            |vc| {
+                vc.query_advice(block_level, Rotation::cur())
+                    - 1.expr()
+                    - vc.query_advice(leb128_chip.config.sn, Rotation::cur())
+            },
+            value_inv,
+        );
+        let branch_leaves_func_body_chip = Rc::new(IsZeroChip::construct(config));
+
+        // every byte of a func body after its len
+        let func_body_byte_columns = [
+            is_local_type_transitions_count,
+            is_local_repetition_count,
+            is_local_type,
+            is_numeric_instruction,
+            is_numeric_instruction_leb_arg,
+            is_variable_instruction,
+            is_variable_instruction_leb_arg,
+            is_control_instruction,
+            is_control_instruction_leb_arg,
+            is_parametric_instruction,
+            is_blocktype_delimiter,
+            is_blocktype_valtype,
+            is_blocktype_type_index,
+            is_call_indirect_type_index,
+            is_call_indirect_table_index,
+            is_block_end,
+        ];
         Self::configure_len_prefixed_bytes_span_checks(
             cs,
             leb128_chip.as_ref(),
             |vc| {
                 or::expr(
-                    [
-                        is_local_type_transitions_count,
-                        is_local_repetition_count,
-                        is_local_type,
-                        is_numeric_instruction,
-                        is_numeric_instruction_leb_arg,
-                        is_variable_instruction,
-                        is_variable_instruction_leb_arg,
-                        is_control_instruction,
-                        is_control_instruction_leb_arg,
-                        is_parametric_instruction,
-                        is_blocktype_delimiter,
-                        is_block_end,
-                    ]
-                    .map(|c| vc.query_fixed(c, Rotation::cur()))
-                    .iter()
-                    .collect_vec(),
+                    func_body_byte_columns
+                        .map(|c| vc.query_fixed(c, Rotation::cur()))
+                        .iter()
+                        .collect_vec(),
                 )
             },
             body_byte_rev_index,
@@ -690,6 +1108,28 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             },
         );
 
+        dynamic_indexes_chip.lookup_args(
+            "code section has valid setup for func body indexes",
+            cs,
+            |vc| {
+                let cond = vc.query_fixed(is_funcs_count, Rotation::cur());
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::FuncBodyIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+
         cs.lookup_any("code_blocks_chip lines are valid", |vc| {
             let control_opcode_is_block_expr =
                 control_instruction_chip
@@ -708,6 +1148,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     .config
                     .value_equals(ControlInstruction::Else, Rotation::cur())(vc);
 
+            let is_control_instruction_expr = vc.query_fixed(is_control_instruction, Rotation::cur());
             let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
 
             let bytecode_number_expr = vc.query_advice(bytecode_number, Rotation::cur());
@@ -715,10 +1156,11 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             let block_opcode_number_expr = vc.query_advice(block_opcode_number, Rotation::cur());
             let byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
 
-            let block_opcode_number_increased_expr = control_opcode_is_block_expr.clone()
-                + control_opcode_is_loop_expr.clone()
-                + control_opcode_is_if_expr.clone()
-                + control_opcode_is_else_expr.clone()
+            let block_opcode_number_increased_expr = is_control_instruction_expr.clone()
+                * (control_opcode_is_block_expr.clone()
+                    + control_opcode_is_loop_expr.clone()
+                    + control_opcode_is_if_expr.clone()
+                    + control_opcode_is_else_expr.clone())
                 + is_block_end_expr.clone();
 
             let c = &code_blocks_chip.config;
@@ -739,9 +1181,117 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     block_opcode_number_increased_expr.clone() * q_last_expr.clone(),
                     vc.query_fixed(c.q_last, Rotation::cur()),
                 ),
+                (
+                    block_opcode_number_increased_expr.clone()
+                        * vc.query_advice(block_level, Rotation::cur()),
+                    vc.query_advice(c.level, Rotation::cur()),
+                ),
+                (
+                    block_opcode_number_increased_expr.clone()
+                        * vc.query_advice(instructions_count, Rotation::cur()),
+                    vc.query_advice(c.instructions_count, Rotation::cur()),
+                ),
             ]
         });
 
+        // the block a branch leaves is the one of the code blocks table at `block_level - label`
+        // holding the branch, its target is the one of the block
+        cs.lookup_any(
+            "br/br_if leaving a block jumps to the target of the block",
+            |vc| {
+                let cond = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                ) * Self::is_branch_label_expr(vc, q_first, &control_instruction_chip)
+                    * not::expr(branch_leaves_func_body_chip.config().expr());
+
+                let c = &code_blocks_chip.config;
+                vec![
+                    (
+                        cond.clone() * vc.query_advice(bytecode_number, Rotation::cur()),
+                        vc.query_advice(c.bytecode_number, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * vc.query_advice(branch_block_index, Rotation::cur()),
+                        vc.query_advice(c.index, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone()
+                            * (vc.query_advice(block_level, Rotation::cur())
+                                - vc.query_advice(leb128_chip.config.sn, Rotation::cur())),
+                        vc.query_advice(c.level, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * vc.query_advice(branch_block_end_index, Rotation::cur()),
+                        vc.query_advice(c.end_index, Rotation::cur()),
+                    ),
+                    (
+                        cond * vc.query_advice(branch_target, Rotation::cur()),
+                        vc.query_advice(c.branch_target, Rotation::cur()),
+                    ),
+                ]
+            },
+        );
+
+        let value_inv = cs.advice_column();
+        let body_byte_rev_index_is_zero_config = IsZeroChip::configure(
+            cs,
+            |vc| {
+                Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                )
+            },
+            |vc| vc.query_advice(body_byte_rev_index, Rotation::cur()),
+            value_inv,
+        );
+        let body_byte_rev_index_is_zero_chip =
+            Rc::new(IsZeroChip::construct(body_byte_rev_index_is_zero_config));
+
+        cs.create_gate("WasmCodeSectionBody func body len gate", |vc| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                vc,
+                q_enable,
+                &shared_state.borrow(),
+                error_code,
+            );
+            let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+            let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
+            let is_func_body_len_next_expr = vc.query_fixed(is_func_body_len, Rotation::next());
+            let is_func_body_byte_expr = or::expr(
+                func_body_byte_columns
+                    .map(|c| vc.query_fixed(c, Rotation::cur()))
+                    .iter()
+                    .collect_vec(),
+            );
+            let is_func_body_end_expr = and::expr([
+                is_block_end_expr,
+                or::expr([q_last_expr, is_func_body_len_next_expr]),
+            ]);
+
+            cb.condition(
+                and::expr([
+                    is_func_body_byte_expr,
+                    body_byte_rev_index_is_zero_chip.config().expr(),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "body_byte_rev_index=0 => last byte of the func body",
+                        is_func_body_end_expr,
+                        1.expr(),
+                    );
+                },
+            );
+
+            cb.gate(q_enable_expr)
+        });
+
         cs.create_gate("WasmCodeSectionBody gate", |vc| {
             let mut cb = BaseConstraintBuilder::default();
 
@@ -763,6 +1313,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             let is_control_instruction_leb_arg_expr = vc.query_fixed(is_control_instruction_leb_arg, Rotation::cur());
             let is_parametric_instruction_expr = vc.query_fixed(is_parametric_instruction, Rotation::cur());
             let is_blocktype_delimiter_expr = vc.query_fixed(is_blocktype_delimiter, Rotation::cur());
+            let is_blocktype_valtype_expr = vc.query_fixed(is_blocktype_valtype, Rotation::cur());
+            let is_blocktype_type_index_expr = vc.query_fixed(is_blocktype_type_index, Rotation::cur());
+            let is_call_indirect_type_index_expr = vc.query_fixed(is_call_indirect_type_index, Rotation::cur());
+            let is_call_indirect_table_index_expr = vc.query_fixed(is_call_indirect_table_index, Rotation::cur());
             let is_block_end_prev_expr = vc.query_fixed(is_block_end, Rotation::prev());
             let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
 
@@ -776,6 +1330,21 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             let not_q_first_expr = not::expr(q_first_expr.clone());
             let is_br_prev_expr = control_instruction_chip.config.value_equals(ControlInstruction::Br, Rotation::prev())(vc);
             let is_br_if_prev_expr = control_instruction_chip.config.value_equals(ControlInstruction::BrIf, Rotation::prev())(vc);
+            let is_variable_instruction_prev_expr = vc.query_fixed(is_variable_instruction, Rotation::prev());
+            let is_local_instruction_prev_expr = not::expr(
+                variable_instruction_chip.config.value_equals(VariableInstruction::GlobalGet, Rotation::prev())(vc)
+                + variable_instruction_chip.config.value_equals(VariableInstruction::GlobalSet, Rotation::prev())(vc)
+            );
+
+            let locals_count_prev_expr = vc.query_advice(locals_count, Rotation::prev());
+            let locals_count_expr = vc.query_advice(locals_count, Rotation::cur());
+            let func_type_index_prev_expr = vc.query_advice(func_type_index, Rotation::prev());
+            let func_type_index_expr = vc.query_advice(func_type_index, Rotation::cur());
+            let params_count_prev_expr = vc.query_advice(params_count, Rotation::prev());
+            let params_count_expr = vc.query_advice(params_count, Rotation::cur());
+            let declared_locals_count_prev_expr = vc.query_advice(declared_locals_count, Rotation::prev());
+            let declared_locals_count_expr = vc.query_advice(declared_locals_count, Rotation::cur());
+            let leb128_sn_expr = vc.query_advice(leb128_chip.config.sn, Rotation::cur());
 
             let block_opcode_number_prev_expr = vc.query_advice(block_opcode_number, Rotation::prev());
             let block_opcode_number_expr = vc.query_advice(block_opcode_number, Rotation::cur());
@@ -793,6 +1362,11 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             cb.require_boolean("is_control_instruction is boolean", is_control_instruction_expr.clone());
             cb.require_boolean("is_control_instruction_leb_arg is boolean", is_control_instruction_leb_arg_expr.clone());
             cb.require_boolean("is_parametric_instruction is boolean", is_parametric_instruction_expr.clone());
+            cb.require_boolean("is_blocktype_delimiter is boolean", is_blocktype_delimiter_expr.clone());
+            cb.require_boolean("is_blocktype_valtype is boolean", is_blocktype_valtype_expr.clone());
+            cb.require_boolean("is_blocktype_type_index is boolean", is_blocktype_type_index_expr.clone());
+            cb.require_boolean("is_call_indirect_type_index is boolean", is_call_indirect_type_index_expr.clone());
+            cb.require_boolean("is_call_indirect_table_index is boolean", is_call_indirect_table_index_expr.clone());
 
             configure_constraints_for_q_first_and_q_last(
                 &mut cb,
@@ -809,11 +1383,18 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             let control_opcode_is_if_expr = control_instruction_chip.config.value_equals(ControlInstruction::If, Rotation::cur())(vc);
             let control_opcode_is_else_expr = control_instruction_chip.config.value_equals(ControlInstruction::Else, Rotation::cur())(vc);
 
-            let block_opcode_number_increased_expr = control_opcode_is_block_expr.clone()
-                + control_opcode_is_loop_expr.clone()
-                + control_opcode_is_if_expr.clone()
-                + control_opcode_is_else_expr.clone()
+            // only real control opcodes (not a leb arg or a numeric opcode sharing the byte value)
+            // open a new block, so the number grows by exactly 1 at each block boundary
+            let block_opcode_number_increased_expr = is_control_instruction_expr.clone()
+                * (control_opcode_is_block_expr.clone()
+                    + control_opcode_is_loop_expr.clone()
+                    + control_opcode_is_if_expr.clone()
+                    + control_opcode_is_else_expr.clone())
                 + is_block_end_expr.clone();
+            cb.require_boolean(
+                "block_opcode_number_increased is boolean",
+                block_opcode_number_increased_expr.clone(),
+            );
 
             cb.condition(
                 and::expr([
@@ -896,6 +1477,12 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                         control_instruction_chip.config.value_equals(*v, Rotation::cur())(vc)
                     }).collect_vec()
             );
+            let is_control_opcode_with_type_index_param_expr = or::expr(
+                CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG.iter()
+                    .map(|v| {
+                        control_instruction_chip.config.value_equals(*v, Rotation::cur())(vc)
+                    }).collect_vec()
+            );
             let is_control_opcode_block_expr = or::expr(
                 CONTROL_INSTRUCTION_BLOCK.iter()
                     .map(|v| {
@@ -969,13 +1556,37 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                         block_level_prev_expr - 1.expr(),
                         block_level_expr.clone(),
                     );
+                    cb.require_equal(
+                        "is_block_end => prev.block_level>=1",
+                        block_end_level_lt_chip.config().is_lt(vc, None),
+                        1.expr(),
+                    );
                 }
             );
+            // the last `end` of a func body brings block_level back to the func base level and
+            // it is the only one doing so
             cb.condition(
                 and::expr([
-                    not::expr(q_first_expr.clone()),
-                    not::expr(q_last_expr.clone()),
-                    not::expr(and::expr([
+                    is_block_end_expr.clone(),
+                    not_q_last_expr.clone(),
+                ]),
+                |cb| {
+                    let is_func_body_len_next_expr = vc.query_fixed(is_func_body_len, Rotation::next());
+                    cb.require_zero(
+                        "is_block_end && next.is_func_body_len => block_level=0",
+                        is_func_body_len_next_expr.clone() * block_level_expr.clone(),
+                    );
+                    cb.require_zero(
+                        "is_block_end && !next.is_func_body_len => block_level>=1",
+                        (1.expr() - is_func_body_len_next_expr) * (inner_block_end_level_lt_chip.config().is_lt(vc, None) - 1.expr()),
+                    );
+                }
+            );
+            cb.condition(
+                and::expr([
+                    not::expr(q_first_expr.clone()),
+                    not::expr(q_last_expr.clone()),
+                    not::expr(and::expr([
                         is_func_body_len_expr.clone(),
                         or::expr([
                             is_funcs_count_prev_expr.clone(),
@@ -1009,6 +1620,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     + is_control_instruction_leb_arg_expr.clone()
                     + is_parametric_instruction_expr.clone()
                     + is_blocktype_delimiter_expr.clone()
+                    + is_blocktype_valtype_expr.clone()
+                    + is_blocktype_type_index_expr.clone()
+                    + is_call_indirect_type_index_expr.clone()
+                    + is_call_indirect_table_index_expr.clone()
                     + is_block_end_expr.clone(),
                 1.expr(),
             );
@@ -1098,6 +1713,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                         or::expr([
                             is_control_opcode_without_params_expr.clone(),
                             is_control_opcode_with_leb_param_expr.clone(),
+                            is_control_opcode_with_type_index_param_expr.clone(),
                             is_control_opcode_block_expr.clone(),
                         ]),
                         1.expr(),
@@ -1125,6 +1741,8 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     is_local_type_transitions_count_expr.clone(),
                     is_local_repetition_count_expr.clone(),
                     is_instruction_leb_arg_expr.clone(),
+                    is_blocktype_type_index_expr.clone(),
+                    is_call_indirect_type_index_expr.clone(),
                 ]),
                 |cb| {
                     cb.require_equal(
@@ -1145,6 +1763,29 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     );
                 }
             );
+            // is_blocktype_valtype{1} => NUM_TYPE_VALUES || REF_TYPE_VALUES
+            cb.condition(
+                is_blocktype_valtype_expr.clone(),
+                |cb| {
+                    cb.require_in_set(
+                        "is_blocktype_valtype(1) => byte_val is valtype",
+                        byte_val_expr.clone(),
+                        NUM_TYPE_VALUES.iter().map(|&v| v.expr())
+                            .chain(REF_TYPE_VALUES.iter().map(|&v| v.expr()))
+                            .collect_vec(),
+                    );
+                }
+            );
+            // is_call_indirect_table_index{1} => table 0
+            cb.condition(
+                is_call_indirect_table_index_expr.clone(),
+                |cb| {
+                    cb.require_zero(
+                        "is_call_indirect_table_index(1) => byte_val=0",
+                        byte_val_expr.clone(),
+                    );
+                }
+            );
             // is_block_end{1} => WASM_BLOCK_END
             cb.condition(
                 is_block_end_expr.clone(),
@@ -1197,16 +1838,79 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     );
                 }
             );
-            // is_control_opcode_block{1} -> is_blocktype_delimiter{1}
+            // is_control_opcode_with_type_index_param{1} -> is_call_indirect_type_index+ -> is_call_indirect_table_index{1} -> is_instruction || is_block_end
             configure_transition_check(
                 &mut cb,
                 vc,
-                "is_control_opcode_block(1) -> is_blocktype_delimiter(1)",
+                "is_control_opcode_with_type_index_param(1) -> is_call_indirect_type_index+",
+                is_control_opcode_with_type_index_param_expr.clone(),
+                true,
+                &[is_call_indirect_type_index],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_call_indirect_type_index+ (not last leb byte) -> is_call_indirect_type_index",
+                and::expr([
+                    is_call_indirect_type_index_expr.clone(),
+                    not::expr(leb128_is_last_byte_expr.clone()),
+                ]),
+                true,
+                &[is_call_indirect_type_index],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_call_indirect_type_index+ (last leb byte) -> is_call_indirect_table_index(1)",
+                and::expr([
+                    is_call_indirect_type_index_expr.clone(),
+                    leb128_is_last_byte_expr.clone(),
+                ]),
+                true,
+                &[is_call_indirect_table_index],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_call_indirect_table_index(1) -> is_instruction || is_block_end",
+                is_call_indirect_table_index_expr.clone(),
+                true,
+                &[is_numeric_instruction, is_variable_instruction, is_control_instruction, is_parametric_instruction, is_block_end],
+            );
+            // is_control_opcode_block{1} -> is_blocktype_delimiter{1} || is_blocktype_valtype{1} || is_blocktype_type_index+
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_control_opcode_block(1) -> is_blocktype_delimiter(1) || is_blocktype_valtype(1) || is_blocktype_type_index+",
                 and::expr([
                     is_control_opcode_block_expr.clone(),
                 ]),
                 true,
-                &[is_blocktype_delimiter],
+                &[is_blocktype_delimiter, is_blocktype_valtype, is_blocktype_type_index],
+            );
+            // is_blocktype_delimiter{1} || is_blocktype_valtype{1} -> is_instruction || is_block_end
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_blocktype_delimiter(1) || is_blocktype_valtype(1) -> is_instruction || is_block_end",
+                or::expr([
+                    is_blocktype_delimiter_expr.clone(),
+                    is_blocktype_valtype_expr.clone(),
+                ]),
+                true,
+                &[is_numeric_instruction, is_variable_instruction, is_control_instruction, is_parametric_instruction, is_block_end],
+            );
+            // is_blocktype_type_index+ -> is_instruction || is_block_end
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "is_blocktype_type_index+ -> is_instruction || is_block_end",
+                and::expr([
+                    is_blocktype_type_index_expr.clone(),
+                    leb128_is_last_byte_expr.clone(),
+                ]),
+                true,
+                &[is_numeric_instruction, is_variable_instruction, is_control_instruction, is_parametric_instruction, is_block_end],
             );
 
             // COMPLEX RELATIONS CONSTRAINTS:
@@ -1269,11 +1973,12 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 }
             );
 
-            // is_control_instruction{1} && not(is_control_opcode_block) -> is_instruction_leb_arg || is_instruction || is_block_end
+            // is_control_instruction{1} && not(is_control_opcode_block) && not(is_control_opcode_with_type_index_param) -> is_instruction_leb_arg || is_instruction || is_block_end
             cb.condition(
                 and::expr([
                     is_control_instruction_expr.clone(),
                     not::expr(is_control_opcode_block_expr.clone()),
+                    not::expr(is_control_opcode_with_type_index_param_expr.clone()),
                 ]),
                 |cb| {
                     let is_control_instruction_leb_arg_next_expr = vc.query_fixed(is_control_instruction_leb_arg, Rotation::next());
@@ -1440,7 +2145,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             cb.condition(
                 and::expr([
                     q_enable_expr.clone(),
-                    not_q_first_expr,
+                    not_q_first_expr.clone(),
                     or::expr([
                         is_br_prev_expr,
                         is_br_if_prev_expr,
@@ -1454,6 +2159,136 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 }
             );
 
+            // locals count is set per func body and can't change inside it
+            cb.condition(
+                and::expr([
+                    q_enable_expr.clone(),
+                    not_q_first_expr.clone(),
+                    not::expr(is_func_body_len_expr.clone()),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "locals_count is the same inside a func body",
+                        locals_count_expr.clone(),
+                        locals_count_prev_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "func_type_index is the same inside a func body",
+                        func_type_index_expr.clone(),
+                        func_type_index_prev_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "params_count is the same inside a func body",
+                        params_count_expr.clone(),
+                        params_count_prev_expr.clone(),
+                    );
+                    cb.require_equal(
+                        "declared_locals_count=prev.declared_locals_count+(is_local_repetition_count && leb128.is_last_byte ? leb128.sn : 0)",
+                        declared_locals_count_expr.clone(),
+                        declared_locals_count_prev_expr.clone()
+                            + is_local_repetition_count_expr.clone() * leb128_is_last_byte_expr.clone() * leb128_sn_expr.clone(),
+                    );
+                }
+            );
+            // the params of the func are its first locals
+            cb.condition(
+                and::expr([
+                    q_enable_expr.clone(),
+                    is_func_body_len_expr.clone(),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "is_func_body_len => declared_locals_count=params_count",
+                        declared_locals_count_expr.clone(),
+                        params_count_expr.clone(),
+                    );
+                }
+            );
+            // locals_count is constant inside the body, so pinning it at the last `end` pins it
+            // everywhere
+            cb.condition(
+                and::expr([
+                    q_enable_expr.clone(),
+                    is_block_end_expr.clone(),
+                    or::expr([
+                        q_last_expr.clone(),
+                        vc.query_fixed(is_func_body_len, Rotation::next()),
+                    ]),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "last is_block_end of a func body => locals_count=declared_locals_count",
+                        locals_count_expr.clone(),
+                        declared_locals_count_expr.clone(),
+                    );
+                }
+            );
+            // `block`, `loop` and the `end` of a block are no instructions
+            let instructions_count_expr = vc.query_advice(instructions_count, Rotation::cur());
+            let is_instruction_expr = is_numeric_instruction_expr.clone()
+                + is_variable_instruction_expr.clone()
+                + is_parametric_instruction_expr.clone()
+                + is_control_instruction_expr.clone()
+                    * (1.expr()
+                        - control_opcode_is_block_expr.clone()
+                        - control_opcode_is_loop_expr.clone())
+                + is_block_end_expr.clone()
+                    * (q_last_expr.clone() + vc.query_fixed(is_func_body_len, Rotation::next()));
+            cb.condition(
+                q_first_expr.clone(),
+                |cb| {
+                    cb.require_zero(
+                        "q_first => instructions_count=0",
+                        instructions_count_expr.clone(),
+                    );
+                }
+            );
+            cb.condition(
+                not_q_first_expr.clone(),
+                |cb| {
+                    cb.require_equal(
+                        "instructions_count=prev.instructions_count+is_instruction",
+                        instructions_count_expr.clone(),
+                        vc.query_advice(instructions_count, Rotation::prev())
+                            + is_instruction_expr.clone(),
+                    );
+                }
+            );
+            cb.condition(
+                and::expr([
+                    q_enable_expr.clone(),
+                    Self::is_branch_label_expr(vc, q_first, &control_instruction_chip),
+                    not::expr(branch_leaves_func_body_chip.config().expr()),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "br/br_if leaving a block => branch_block_index<=block_opcode_number",
+                        branch_block_index_lt_chip.config().is_lt(vc, None),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "br/br_if leaving a block => block_opcode_number<branch_block_end_index",
+                        branch_block_end_index_lt_chip.config().is_lt(vc, None),
+                        1.expr(),
+                    );
+                }
+            );
+
+            cb.condition(
+                and::expr([
+                    q_enable_expr.clone(),
+                    not_q_first_expr.clone(),
+                    is_variable_instruction_prev_expr.clone(),
+                    is_local_instruction_prev_expr.clone(),
+                ]),
+                |cb| {
+                    cb.require_zero(
+                        "local.get/local.set/local.tee arg is valid",
+                        local_index_lt_chip.config().is_lt(vc, None).expr() - 1.expr(),
+                    );
+                }
+            );
+
             cb.gate(q_enable_expr.clone())
         });
 
@@ -1476,7 +2311,12 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             is_control_instruction_leb_arg,
             is_parametric_instruction,
             is_blocktype_delimiter,
+            is_blocktype_valtype,
+            is_blocktype_type_index,
+            is_call_indirect_type_index,
+            is_call_indirect_table_index,
             is_block_end,
+            wb_table,
             leb128_chip,
             numeric_instructions_chip,
             variable_instruction_chip,
@@ -1488,7 +2328,22 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             func_count,
             block_level,
             block_level_lt_chip,
+            block_end_level_lt_chip,
+            inner_block_end_level_lt_chip,
+            locals_count,
+            local_index_lt_chip,
+            func_type_index,
+            params_count,
+            declared_locals_count,
+            instructions_count,
+            branch_target,
+            branch_block_index,
+            branch_block_end_index,
+            branch_block_index_lt_chip,
+            branch_block_end_index_lt_chip,
+            branch_leaves_func_body_chip,
             body_byte_rev_index,
+            body_byte_rev_index_is_zero_chip,
             body_item_rev_count,
             error_code,
             shared_state,
@@ -1505,36 +2360,37 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         wb_offset: usize,
         assign_delta: AssignDeltaType,
         block_opcode_number: &mut u64,
+        open_blocks: &mut Vec<OpenCodeBlock>,
     ) -> Result<usize, Error> {
         let mut offset = wb_offset;
 
-        let opcode = wb.bytes[offset];
-
-        let mut assign_type = AssignType::Unknown;
-        let mut assign_type_argument = AssignType::Unknown;
+        let opcode = wb.byte_at(offset)?;
 
-        if let Ok(opcode) = <u8 as TryInto<NumericInstruction>>::try_into(opcode) {
-            assign_type = AssignType::IsNumericInstruction;
-            if NUMERIC_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
-                assign_type_argument = AssignType::IsNumericInstructionLebArg;
-            }
-        }
+        let (assign_type, assign_type_argument) = Self::instruction_assign_types(opcode);
 
-        if let Ok(opcode) = <u8 as TryInto<VariableInstruction>>::try_into(opcode) {
-            assign_type = AssignType::IsVariableInstruction;
-            if VARIABLE_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
-                assign_type_argument = AssignType::IsVariableInstructionLebArg;
-            }
+        // `block`, `loop` and the `end` of a block are no instructions
+        let is_instruction = if opcode == WASM_BLOCK_END {
+            open_blocks.is_empty()
+        } else {
+            ![
+                ControlInstruction::Block as u8,
+                ControlInstruction::Loop as u8,
+            ]
+            .contains(&opcode)
+        };
+        if is_instruction {
+            self.shared_state().borrow_mut().instructions_count += 1;
         }
 
         if let Ok(opcode) = <u8 as TryInto<ControlInstruction>>::try_into(opcode) {
-            assign_type = AssignType::IsControlInstruction;
             if CONTROL_INSTRUCTION_BLOCK.contains(&opcode) {
-                assign_type_argument = AssignType::IsBlocktypeDelimiter;
                 self.shared_state().borrow_mut().block_level_inc();
-            }
-            if CONTROL_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
-                assign_type_argument = AssignType::IsControlInstructionLebArg
+                open_blocks.push(OpenCodeBlock {
+                    index: *block_opcode_number + 1,
+                    is_loop: opcode == ControlInstruction::Loop,
+                    instructions_count: self.shared_state().borrow().instructions_count as u64,
+                    branches: vec![],
+                });
             }
 
             match opcode {
@@ -1590,13 +2446,28 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             }
         }
 
-        if let Ok(_opcode) = <u8 as TryInto<ParametricInstruction>>::try_into(opcode) {
-            assign_type = AssignType::IsParametricInstruction;
-        }
-
-        if opcode == WASM_BLOCK_END {
-            assign_type = AssignType::IsBlockEnd;
-            self.shared_state().borrow_mut().block_level_dec();
+        if assign_type == AssignType::IsBlockEnd {
+            let block_level_prev = self.shared_state().borrow().block_level;
+            self.config
+                .block_end_level_lt_chip
+                .assign(
+                    region,
+                    checked_assign_offset(offset, assign_delta)?,
+                    F::zero(),
+                    F::from(block_level_prev as u64),
+                )
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+            self.shared_state().borrow_mut().block_level_dec()?;
+            let block_level = self.shared_state().borrow().block_level;
+            self.config
+                .inner_block_end_level_lt_chip
+                .assign(
+                    region,
+                    checked_assign_offset(offset, assign_delta)?,
+                    F::zero(),
+                    F::from(block_level as u64),
+                )
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
 
             *block_opcode_number += 1;
             self.markup_code_blocks(
@@ -1608,6 +2479,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 *block_opcode_number,
                 Some(code_blocks::types::Opcode::End),
             )?;
+            // the `end` of a func body closes no block
+            if let Some(open_block) = open_blocks.pop() {
+                self.markup_branch_targets(region, assign_delta, open_block, *block_opcode_number)?;
+            }
         };
 
         if [
@@ -1633,12 +2508,67 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         }
 
         if assign_type_argument == AssignType::IsBlocktypeDelimiter {
+            let assign_offset = checked_assign_offset(offset, assign_delta)?;
+            let blocktype_end_offset = Self::blocktype_end_offset(wb, offset)
+                .map_err(|_| Error::InvalidByteValueAt(assign_offset))?;
+            let blocktype_len = blocktype_end_offset - offset;
+            match Self::blocktype_assign_type(wb.byte_at(offset)?) {
+                AssignType::IsBlocktypeTypeIndex => {
+                    self.markup_leb_section(
+                        region,
+                        wb,
+                        offset,
+                        assign_delta,
+                        &[AssignType::IsBlocktypeTypeIndex],
+                    )?;
+                }
+                assign_type => {
+                    self.assign(region, wb, offset, assign_delta, &[assign_type], 1, None)?;
+                }
+            }
+            self.markup_code_blocks(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                blocktype_len,
+                *block_opcode_number,
+                None,
+            )?;
+            offset += blocktype_len;
+        }
+
+        if assign_type_argument == AssignType::IsCallIndirectTypeIndex {
+            let (_, type_index_leb_len) = self.markup_leb_section(
+                region,
+                wb,
+                offset,
+                assign_delta,
+                &[AssignType::IsCallIndirectTypeIndex],
+            )?;
+            self.markup_code_blocks(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                type_index_leb_len,
+                *block_opcode_number,
+                None,
+            )?;
+            offset += type_index_leb_len;
+            // only table 0 is supported
+            if wb.byte_at(offset)? != 0 {
+                return Err(Error::InvalidByteValueAt(checked_assign_offset(
+                    offset,
+                    assign_delta,
+                )?));
+            }
             self.assign(
                 region,
                 wb,
                 offset,
                 assign_delta,
-                &[assign_type_argument],
+                &[AssignType::IsCallIndirectTableIndex],
                 1,
                 None,
             )?;
@@ -1675,7 +2605,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             let block_level = self.config.shared_state.borrow().block_level;
             debug!(
                 "assign at {} block_level_lt_chip instr_arg_val {} block_level {}",
-                offset + assign_delta,
+                checked_assign_offset(offset, assign_delta)?,
                 instr_arg_val,
                 block_level,
             );
@@ -1683,11 +2613,70 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 .block_level_lt_chip
                 .assign(
                     region,
-                    offset + assign_delta,
+                    checked_assign_offset(offset, assign_delta)?,
                     F::from(instr_arg_val),
                     F::from(block_level as u64),
                 )
                 .map_err(remap_error(Error::FatalAssignExternalChip))?;
+            if [ControlInstruction::Br as u8, ControlInstruction::BrIf as u8].contains(&opcode) {
+                let assign_offset = checked_assign_offset(offset, assign_delta)?;
+                // "br/br_if arg is valid" requires is_lt to hold
+                debug!(
+                    "br/br_if at {} opcode {:#x} instr_arg_val {} block_level {} is_lt {}",
+                    assign_offset,
+                    opcode,
+                    instr_arg_val,
+                    block_level,
+                    instr_arg_val < block_level as u64,
+                );
+                self.config
+                    .branch_leaves_func_body_chip
+                    .assign(
+                        region,
+                        assign_offset,
+                        Value::known(
+                            F::from(block_level as u64) - F::one() - F::from(instr_arg_val),
+                        ),
+                    )
+                    .map_err(remap_error_to_assign_at(assign_offset))?;
+                // the target of a branch leaving a block is known at the `end` of the block
+                match usize::try_from(instr_arg_val)
+                    .ok()
+                    .filter(|label| *label < open_blocks.len())
+                {
+                    Some(label) => {
+                        let open_blocks_count = open_blocks.len();
+                        open_blocks[open_blocks_count - 1 - label]
+                            .branches
+                            .push((assign_offset, *block_opcode_number));
+                    }
+                    None => self.assign_branch_block_index_lt_chips(
+                        region,
+                        assign_offset,
+                        *block_opcode_number,
+                        0,
+                        0,
+                    )?,
+                }
+            }
+            if assign_type_argument == AssignType::IsVariableInstructionLebArg {
+                let locals_count = self.config.shared_state.borrow().locals_count;
+                debug!(
+                    "assign at {} local_index_lt_chip instr_arg_val {} locals_count {}",
+                    checked_assign_offset(offset, assign_delta)?,
+                    instr_arg_val,
+                    locals_count,
+                );
+                self.config
+                    .local_index_lt_chip
+                    .assign(
+                        region,
+                        checked_assign_offset(offset, assign_delta)?,
+                        F::from(instr_arg_val),
+                        F::from(locals_count as u64),
+                    )
+                    .map_err(remap_error(Error::FatalAssignExternalChip))?;
+            }
             offset += inst_arg_leb_len;
         }
 
@@ -1749,23 +2738,414 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 &[code_blocks::types::AssignType::Opcode],
                 assign_value as u64,
             )?;
+            let (block_level, instructions_count) = {
+                let shared_state = self.config.shared_state.borrow();
+                (shared_state.block_level, shared_state.instructions_count)
+            };
+            self.config.code_blocks_chip.assign(
+                region,
+                offset,
+                assign_delta,
+                &[code_blocks::types::AssignType::Level],
+                block_level as u64,
+            )?;
+            self.config.code_blocks_chip.assign(
+                region,
+                offset,
+                assign_delta,
+                &[code_blocks::types::AssignType::InstructionsCount],
+                instructions_count as u64,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// assigns the per func body locals state of `shared_state`
+    fn assign_func_locals(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+    ) -> Result<(), Error> {
+        let shared_state = self.config.shared_state.borrow();
+        for (name, column, value) in [
+            (
+                "locals_count",
+                self.config.locals_count,
+                shared_state.locals_count,
+            ),
+            (
+                "func_type_index",
+                self.config.func_type_index,
+                shared_state.func_type_index,
+            ),
+            (
+                "params_count",
+                self.config.params_count,
+                shared_state.params_count,
+            ),
+            (
+                "declared_locals_count",
+                self.config.declared_locals_count,
+                shared_state.declared_locals_count,
+            ),
+        ] {
+            region
+                .assign_advice(
+                    || format!("assign '{}' val {} at {}", name, value, assign_offset),
+                    column,
+                    assign_offset,
+                    || Value::known(F::from(value as u64)),
+                )
+                .map_err(remap_error_to_assign_at(assign_offset))?;
+        }
+        Ok(())
+    }
+
+    /// assigns `shared_state.instructions_count`
+    fn assign_instructions_count(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+    ) -> Result<(), Error> {
+        let instructions_count = self.config.shared_state.borrow().instructions_count;
+        region
+            .assign_advice(
+                || {
+                    format!(
+                        "assign 'instructions_count' val {} at {}",
+                        instructions_count, assign_offset
+                    )
+                },
+                self.config.instructions_count,
+                assign_offset,
+                || Value::known(F::from(instructions_count as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+        Ok(())
+    }
+
+    /// assigns the end of `open_block` (index `end_index`) and its branch target to the code blocks
+    /// table and to the labels of the branches leaving it
+    fn markup_branch_targets(
+        &self,
+        region: &mut Region<F>,
+        assign_delta: AssignDeltaType,
+        open_block: OpenCodeBlock,
+        end_index: u64,
+    ) -> Result<(), Error> {
+        let branch_target = if open_block.is_loop {
+            open_block.instructions_count
+        } else {
+            self.config.shared_state.borrow().instructions_count as u64
+        };
+        let code_blocks_chip = &self.config.code_blocks_chip;
+        code_blocks_chip.assign(
+            region,
+            open_block.index as usize - 1,
+            assign_delta,
+            &[code_blocks::types::AssignType::EndIndex],
+            end_index,
+        )?;
+        code_blocks_chip.assign(
+            region,
+            open_block.index as usize - 1,
+            assign_delta,
+            &[code_blocks::types::AssignType::BranchTarget],
+            branch_target,
+        )?;
+        code_blocks_chip.assign(
+            region,
+            end_index as usize - 1,
+            assign_delta,
+            &[code_blocks::types::AssignType::OpenIndex],
+            open_block.index,
+        )?;
+        for (assign_offset, block_opcode_number) in open_block.branches {
+            for (name, column, value) in [
+                ("branch_target", self.config.branch_target, branch_target),
+                (
+                    "branch_block_index",
+                    self.config.branch_block_index,
+                    open_block.index,
+                ),
+                (
+                    "branch_block_end_index",
+                    self.config.branch_block_end_index,
+                    end_index,
+                ),
+            ] {
+                region
+                    .assign_advice(
+                        || format!("assign '{}' val {} at {}", name, value, assign_offset),
+                        column,
+                        assign_offset,
+                        || Value::known(F::from(value)),
+                    )
+                    .map_err(remap_error_to_assign_at(assign_offset))?;
+            }
+            self.assign_branch_block_index_lt_chips(
+                region,
+                assign_offset,
+                block_opcode_number,
+                open_block.index,
+                end_index,
+            )?;
         }
+        Ok(())
+    }
 
+    fn assign_branch_block_index_lt_chips(
+        &self,
+        region: &mut Region<F>,
+        assign_offset: usize,
+        block_opcode_number: u64,
+        branch_block_index: u64,
+        branch_block_end_index: u64,
+    ) -> Result<(), Error> {
+        self.config
+            .branch_block_index_lt_chip
+            .assign(
+                region,
+                assign_offset,
+                F::from(branch_block_index),
+                F::from(block_opcode_number + 1),
+            )
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        self.config
+            .branch_block_end_index_lt_chip
+            .assign(
+                region,
+                assign_offset,
+                F::from(block_opcode_number),
+                F::from(branch_block_end_index),
+            )
+            .map_err(remap_error(Error::FatalAssignExternalChip))?;
         Ok(())
     }
 
-    /// updates `shared_state.dynamic_indexes_offset` to a new offset
+    /// returns typeidx, params count (taken from the func type) and params + locals count of the
+    /// func body whose len starts at `wb_offset`
+    fn compute_locals_count(
+        &self,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        func_index: usize,
+    ) -> Result<(usize, usize, usize), Error> {
+        let (func_type_index, params_count) = {
+            let shared_state = self.config.shared_state.borrow();
+            let func_type_index = *shared_state
+                .func_type_indexes
+                .get(func_index)
+                .ok_or(Error::IndexOutOfBoundsAt(wb_offset))?;
+            let params_count = *shared_state
+                .type_params_counts
+                .get(func_type_index)
+                .ok_or(Error::IndexOutOfBoundsAt(wb_offset))?;
+            (func_type_index, params_count)
+        };
+        let mut locals_count = params_count;
+        let (_, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, wb_offset)?;
+        let mut offset = last_byte_offset + 1;
+        let (local_type_transitions_count, last_byte_offset) =
+            leb128_compute_sn(&wb.bytes, false, offset)?;
+        offset = last_byte_offset + 1;
+        for _ in 0..local_type_transitions_count {
+            let (local_repetition_count, last_byte_offset) =
+                leb128_compute_sn(&wb.bytes, false, offset)?;
+            locals_count += local_repetition_count as usize;
+            // skip the local type byte
+            offset = last_byte_offset + 2;
+        }
+
+        Ok((func_type_index, params_count, locals_count))
+    }
+
+    /// assign type of the blocktype starting with `byte`: the empty blocktype
+    /// (`WASM_BLOCKTYPE_DELIMITER`), a single result valtype or an s33 type index
+    fn blocktype_assign_type(byte: u8) -> AssignType {
+        if byte == WASM_BLOCKTYPE_DELIMITER {
+            AssignType::IsBlocktypeDelimiter
+        } else if <u8 as TryInto<NumType>>::try_into(byte).is_ok()
+            || <u8 as TryInto<RefType>>::try_into(byte).is_ok()
+        {
+            AssignType::IsBlocktypeValtype
+        } else {
+            AssignType::IsBlocktypeTypeIndex
+        }
+    }
+
+    /// offset right after the blocktype at `wb_offset`. Type indexes are s33 encoded and must be
+    /// non-negative
+    fn blocktype_end_offset(wb: &WasmBytecode, wb_offset: usize) -> Result<usize, Error> {
+        match Self::blocktype_assign_type(wb.byte_at(wb_offset)?) {
+            AssignType::IsBlocktypeTypeIndex => {
+                let (_, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, wb_offset)?;
+                if wb.byte_at(last_byte_offset)? & 0x40 != 0 {
+                    return Err(Error::InvalidByteValueAt(wb_offset));
+                }
+                Ok(last_byte_offset + 1)
+            }
+            _ => Ok(wb_offset + 1),
+        }
+    }
+
+    /// assign types of the opcode byte and of the argument (`AssignType::Unknown` if there is
+    /// none) of the instruction starting with `opcode`. The opcode assign type is
+    /// `AssignType::Unknown` if `opcode` is not supported
+    fn instruction_assign_types(opcode: u8) -> (AssignType, AssignType) {
+        let mut assign_type = AssignType::Unknown;
+        let mut assign_type_argument = AssignType::Unknown;
+
+        if let Ok(opcode) = <u8 as TryInto<NumericInstruction>>::try_into(opcode) {
+            assign_type = AssignType::IsNumericInstruction;
+            if NUMERIC_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
+                assign_type_argument = AssignType::IsNumericInstructionLebArg;
+            }
+        }
+
+        if let Ok(opcode) = <u8 as TryInto<VariableInstruction>>::try_into(opcode) {
+            assign_type = AssignType::IsVariableInstruction;
+            if VARIABLE_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
+                assign_type_argument = AssignType::IsVariableInstructionLebArg;
+            }
+        }
+
+        if let Ok(opcode) = <u8 as TryInto<ControlInstruction>>::try_into(opcode) {
+            assign_type = AssignType::IsControlInstruction;
+            if CONTROL_INSTRUCTION_BLOCK.contains(&opcode) {
+                assign_type_argument = AssignType::IsBlocktypeDelimiter;
+            }
+            if CONTROL_INSTRUCTION_WITH_LEB_ARG.contains(&opcode) {
+                assign_type_argument = AssignType::IsControlInstructionLebArg
+            }
+            if CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG.contains(&opcode) {
+                assign_type_argument = AssignType::IsCallIndirectTypeIndex
+            }
+        }
+
+        if let Ok(_opcode) = <u8 as TryInto<ParametricInstruction>>::try_into(opcode) {
+            assign_type = AssignType::IsParametricInstruction;
+        }
+
+        if opcode == WASM_BLOCK_END {
+            assign_type = AssignType::IsBlockEnd;
+        }
+
+        (assign_type, assign_type_argument)
+    }
+
+    /// offset right after the instruction (opcode and its argument) at `wb_offset`, the layout
+    /// is the one `markup_instruction_section` assigns
+    fn instruction_end_offset(wb: &WasmBytecode, wb_offset: usize) -> Result<usize, Error> {
+        let (assign_type, assign_type_argument) =
+            Self::instruction_assign_types(wb.byte_at(wb_offset)?);
+        if assign_type == AssignType::Unknown {
+            return Err(Error::ParseOpcodeFailedAt(wb_offset));
+        }
+        let offset = wb_offset + 1;
+        match assign_type_argument {
+            AssignType::IsBlocktypeDelimiter => Self::blocktype_end_offset(wb, offset),
+            AssignType::IsNumericInstructionLebArg
+            | AssignType::IsVariableInstructionLebArg
+            | AssignType::IsControlInstructionLebArg => {
+                let is_signed = assign_type_argument == AssignType::IsNumericInstructionLebArg;
+                let (_, last_byte_offset) = leb128_compute_sn(&wb.bytes, is_signed, offset)?;
+                Ok(last_byte_offset + 1)
+            }
+            // the type index is followed by the table index byte
+            AssignType::IsCallIndirectTypeIndex => {
+                let (_, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, offset)?;
+                Ok(last_byte_offset + 2)
+            }
+            _ => Ok(offset),
+        }
+    }
+
+    /// returns byte ranges of the func bodies of the code section body at `wb_offset` which hold
+    /// constant expressions foldable at compile time: maximal runs of `*.const` and non-trapping
+    /// integer numeric instructions (`NUMERIC_INSTRUCTION_PURE_UNARY`,
+    /// `NUMERIC_INSTRUCTION_PURE_BINARY`) where every such instruction takes its operands from
+    /// the run itself. Runs without arithmetic instructions are skipped.
     ///
-    /// returns new offset
-    pub fn assign_auto(
+    /// it is an analysis of the bytecode only, nothing gets assigned or constrained
+    pub fn collect_const_expr_ranges(
+        wb: &WasmBytecode,
+        wb_offset: usize,
+    ) -> Result<Vec<Range<usize>>, Error> {
+        let mut ranges = vec![];
+        let (funcs_count, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, wb_offset)?;
+        let mut offset = last_byte_offset + 1;
+        for _ in 0..funcs_count {
+            let (func_body_len, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, offset)?;
+            let func_body_end_offset = last_byte_offset + 1 + func_body_len as usize;
+            let (local_type_transitions_count, last_byte_offset) =
+                leb128_compute_sn(&wb.bytes, false, last_byte_offset + 1)?;
+            offset = last_byte_offset + 1;
+            for _ in 0..local_type_transitions_count {
+                let (_, last_byte_offset) = leb128_compute_sn(&wb.bytes, false, offset)?;
+                // skip the local type byte
+                offset = last_byte_offset + 2;
+            }
+
+            // (run start offset, values the run left on the stack, run has arithmetic)
+            let mut run: Option<(usize, usize, bool)> = None;
+            while offset < func_body_end_offset {
+                let opcode =
+                    <u8 as TryInto<NumericInstruction>>::try_into(wb.byte_at(offset)?).ok();
+                run = match (opcode, run) {
+                    (Some(NumericInstruction::I32Const | NumericInstruction::I64Const), run) => {
+                        let (start, stack_len, has_arithmetic) = run.unwrap_or((offset, 0, false));
+                        Some((start, stack_len + 1, has_arithmetic))
+                    }
+                    // ops fold only if all their operands come from the run
+                    (Some(opcode), Some((start, stack_len, _)))
+                        if NUMERIC_INSTRUCTION_PURE_UNARY.contains(&opcode) && stack_len >= 1 =>
+                    {
+                        Some((start, stack_len, true))
+                    }
+                    (Some(opcode), Some((start, stack_len, _)))
+                        if NUMERIC_INSTRUCTION_PURE_BINARY.contains(&opcode) && stack_len >= 2 =>
+                    {
+                        Some((start, stack_len - 1, true))
+                    }
+                    (_, run) => {
+                        if let Some((start, _, true)) = run {
+                            ranges.push(start..offset);
+                        }
+                        None
+                    }
+                };
+                offset = Self::instruction_end_offset(wb, offset)?;
+            }
+            if let Some((start, _, true)) = run {
+                ranges.push(start..offset);
+            }
+        }
+
+        Ok(ranges)
+    }
+}
+
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmCodeSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<usize, Error> {
+    ) -> Result<(usize, SectionParseSummary), Error> {
         let mut offset = wb_offset;
         let mut block_opcode_number: u64 = 0;
+        {
+            let mut shared_state = self.config.shared_state.borrow_mut();
+            shared_state.locals_count = 0;
+            shared_state.func_type_index = 0;
+            shared_state.params_count = 0;
+            shared_state.declared_locals_count = 0;
+            shared_state.instructions_count = 0;
+        }
+        let mut open_blocks = vec![];
 
         // is_funcs_count+
         let (funcs_count, funcs_count_leb_len) = self.markup_leb_section(
@@ -1796,6 +3176,15 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             None,
         )?;
         self.config.shared_state.borrow_mut().func_count += funcs_count as usize;
+        // one index per func body (is_func_body_len), checked against the function section
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            funcs_count as usize,
+            Tag::FuncBodyIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
         self.assign(
             region,
             &wb,
@@ -1807,8 +3196,17 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
         )?;
         offset += funcs_count_leb_len;
 
-        for _func_index in 0..funcs_count {
+        for func_index in 0..funcs_count {
             body_item_rev_count -= 1;
+            let (func_type_index, params_count, locals_count) =
+                self.compute_locals_count(wb, offset, func_index as usize)?;
+            {
+                let mut shared_state = self.config.shared_state.borrow_mut();
+                shared_state.locals_count = locals_count;
+                shared_state.func_type_index = func_type_index;
+                shared_state.params_count = params_count;
+                shared_state.declared_locals_count = params_count;
+            }
             // is_func_body_len+
             self.config.shared_state.borrow_mut().block_level_inc();
             let (func_body_len, func_body_len_leb_len) = self.markup_leb_section(
@@ -1827,8 +3225,12 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                 block_opcode_number,
                 None,
             )?;
-            let func_body_end_offset =
-                offset + func_body_len_leb_len + (func_body_len as usize) - 1;
+            // the declared len is not bound by the code blocks markup, so it may go past the end
+            // of the bytecode
+            let func_body_end_offset = (func_body_len as usize)
+                .checked_add(offset + func_body_len_leb_len - 1)
+                .filter(|func_body_end_offset| *func_body_end_offset < wb.bytes.len())
+                .ok_or(Error::FuncBodyLenExceedsModule(offset))?;
             for offset in offset..=func_body_end_offset {
                 self.assign(
                     region,
@@ -1876,7 +3278,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
 
             for _is_valtype_transition_index in 0..is_local_type_transitions_count {
                 // -> local_var_descriptor+(is_local_repetition_count+ ...
-                let (_is_local_repetition_count, is_local_repetition_count_leb_len) = self
+                let (is_local_repetition_count, is_local_repetition_count_leb_len) = self
                     .markup_leb_section(
                         region,
                         wb,
@@ -1894,6 +3296,10 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     None,
                 )?;
                 offset += is_local_repetition_count_leb_len;
+                // the declared locals are counted in at the last byte of the repetition count
+                self.config.shared_state.borrow_mut().declared_locals_count +=
+                    is_local_repetition_count as usize;
+                self.assign_func_locals(region, checked_assign_offset(offset - 1, assign_delta)?)?;
 
                 // is_local_type{1}
                 self.assign(
@@ -1924,6 +3330,7 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
                     offset,
                     assign_delta,
                     &mut block_opcode_number,
+                    &mut open_blocks,
                 )?;
             }
         }
@@ -1948,6 +3355,12 @@ impl<F: Field> WasmCodeSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count: funcs_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -1,16 +1,24 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
-    plonk::{Circuit, ConstraintSystem, Error},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Any, Circuit, Column, ConstraintSystem, Error},
 };
 
 use eth_types::{Field, Hash, ToWord};
+use gadgets::{is_zero::IsZeroInstruction, less_than::LtInstruction};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
-    sections::code::body::circuit::WasmCodeSectionBodyChip,
+    common::{WasmAssignAwareChip, WasmSectionSummaryAwareChip},
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
+    sections::{
+        code::body::{
+            circuit::{WasmCodeSectionBodyChip, WasmCodeSectionBodyConfig},
+            types::AssignType,
+        },
+        consts::LebParams,
+    },
     tables::dynamic_indexes::circuit::DynamicIndexesChip,
     types::SharedState,
 };
@@ -20,6 +28,15 @@ struct TestCircuit<'a, F> {
     code_hash: Hash,
     bytecode: &'a [u8],
     offset_start: usize,
+    type_params_counts: Vec<usize>,
+    func_type_indexes: Vec<usize>,
+    /// overwrites `locals_count` (and the local index comparisons, taking the byte as the local
+    /// index) after the assignment
+    forged_locals_count: Option<u64>,
+    /// (offset of a single byte func body len, forged len): the bytecode table gets the forged
+    /// len, the leb128 cells and `body_byte_rev_index` are overwritten to count down from it
+    /// after the assignment of the actual bytecode
+    forged_func_body_len: Option<(usize, u8)>,
     _marker: PhantomData<F>,
 }
 
@@ -51,7 +68,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let dynamic_indexes_config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(dynamic_indexes_config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_code_section_body_config = WasmCodeSectionBodyChip::configure(
@@ -83,12 +101,19 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         let wb = WasmBytecode::new(self.bytecode.to_vec().clone());
+        let mut wb_table_bytes = self.bytecode.to_vec();
+        if let Some((len_offset, len)) = self.forged_func_body_len {
+            wb_table_bytes[len_offset] = len;
+        }
+        let wb_table_wb = WasmBytecode::new(wb_table_bytes);
         let assign_delta = 0;
         layouter
             .assign_region(
                 || format!("wasm bytecode table at {}", assign_delta),
                 |mut region| {
-                    config.wb_table.load(&mut region, &wb, assign_delta)?;
+                    config
+                        .wb_table
+                        .load(&mut region, &wb_table_wb, assign_delta)?;
                     Ok(())
                 },
             )
@@ -96,6 +121,12 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         layouter.assign_region(
             || "wasm_code_section_body region",
             |mut region| {
+                {
+                    let shared_state = config.body_chip.config.shared_state.clone();
+                    let mut shared_state = shared_state.borrow_mut();
+                    shared_state.type_params_counts = self.type_params_counts.clone();
+                    shared_state.func_type_indexes = self.func_type_indexes.clone();
+                }
                 let mut offset_start = self.offset_start;
                 while offset_start < wb.bytes.len() {
                     offset_start = config
@@ -103,6 +134,52 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                         .assign_auto(&mut region, &wb, offset_start, assign_delta)
                         .unwrap();
                 }
+                if let Some((len_offset, len)) = self.forged_func_body_len {
+                    let leb128_config = &config.body_chip.config.leb128_chip.config;
+                    let len_f = F::from(len as u64);
+                    for (column, value) in [
+                        (leb128_config.sn, len_f),
+                        (leb128_config.sn_recovered, len_f),
+                        (leb128_config.last_byte_inv, len_f.invert().unwrap()),
+                    ] {
+                        region.assign_advice(
+                            || format!("forged func body len at {}", len_offset),
+                            column,
+                            len_offset,
+                            || Value::known(value),
+                        )?;
+                    }
+                    for offset in len_offset..wb.bytes.len() {
+                        let rev_index = len_f - F::from((offset - len_offset) as u64);
+                        region.assign_advice(
+                            || format!("forged 'body_byte_rev_index' at {}", offset),
+                            config.body_chip.config.body_byte_rev_index,
+                            offset,
+                            || Value::known(rev_index),
+                        )?;
+                        config
+                            .body_chip
+                            .config
+                            .body_byte_rev_index_is_zero_chip
+                            .assign(&mut region, offset, Value::known(rev_index))?;
+                    }
+                }
+                if let Some(locals_count) = self.forged_locals_count {
+                    for (offset, &byte_val) in wb.bytes.iter().enumerate() {
+                        region.assign_advice(
+                            || format!("forged 'locals_count' at {}", offset),
+                            config.body_chip.config.locals_count,
+                            offset,
+                            || Value::known(F::from(locals_count)),
+                        )?;
+                        config.body_chip.config.local_index_lt_chip.assign(
+                            &mut region,
+                            offset,
+                            F::from(byte_val as u64),
+                            F::from(locals_count),
+                        )?;
+                    }
+                }
 
                 Ok(())
             },
@@ -112,17 +189,124 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+/// Column each `AssignType` is assigned to. The match has no catch-all arm, so a new variant
+/// doesn't compile until it is mapped here (and handled by `assign_internal`).
+fn assign_type_column<F: Field>(
+    config: &WasmCodeSectionBodyConfig<F>,
+    assign_type: AssignType,
+) -> Option<Column<Any>> {
+    let column: Column<Any> = match assign_type {
+        AssignType::Unknown => return None,
+        AssignType::QFirst => config.q_first.into(),
+        AssignType::QLast => config.q_last.into(),
+        AssignType::IsFuncsCount => config.is_funcs_count.into(),
+        AssignType::IsFuncBodyLen => config.is_func_body_len.into(),
+        AssignType::IsLocalTypeTransitionsCount => config.is_local_type_transitions_count.into(),
+        AssignType::IsLocalRepetitionCount => config.is_local_repetition_count.into(),
+        AssignType::IsLocalType => config.is_local_type.into(),
+        AssignType::IsNumericInstruction => config.is_numeric_instruction.into(),
+        AssignType::IsNumericInstructionLebArg => config.is_numeric_instruction_leb_arg.into(),
+        AssignType::IsVariableInstruction => config.is_variable_instruction.into(),
+        AssignType::IsVariableInstructionLebArg => config.is_variable_instruction_leb_arg.into(),
+        AssignType::IsControlInstruction => config.is_control_instruction.into(),
+        AssignType::IsControlInstructionLebArg => config.is_control_instruction_leb_arg.into(),
+        AssignType::IsParametricInstruction => config.is_parametric_instruction.into(),
+        AssignType::IsBlocktypeDelimiter => config.is_blocktype_delimiter.into(),
+        AssignType::IsBlocktypeValtype => config.is_blocktype_valtype.into(),
+        AssignType::IsBlocktypeTypeIndex => config.is_blocktype_type_index.into(),
+        AssignType::IsBlockEnd => config.is_block_end.into(),
+        AssignType::BodyByteRevIndex => config.body_byte_rev_index.into(),
+        AssignType::BodyItemRevCount => config.body_item_rev_count.into(),
+        AssignType::BlockOpcodeIndex => config.block_opcode_number.into(),
+        AssignType::ErrorCode => config.error_code.into(),
+    };
+    Some(column)
+}
+
+/// Assigns each of `assign_types` with `ASSIGN_TYPES_TEST_VALUE` at its own row.
+#[derive(Default)]
+struct AssignTypesTestCircuit<F> {
+    assign_types: Vec<AssignType>,
+    _marker: PhantomData<F>,
+}
+
+/// Not 1, so instruction flags don't get decoded by their opcode chips.
+const ASSIGN_TYPES_TEST_VALUE: u64 = 2;
+
+impl<F: Field> Circuit<F> for AssignTypesTestCircuit<F> {
+    type Config = TestCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        TestCircuit::<F>::configure(cs)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let wb = WasmBytecode::new(vec![0; self.assign_types.len()]);
+        layouter.assign_region(
+            || "wasm_code_section_body assign types region",
+            |mut region| {
+                config.body_chip.config.shared_state.borrow_mut().error_code =
+                    ASSIGN_TYPES_TEST_VALUE;
+                for (offset, assign_type) in self.assign_types.iter().enumerate() {
+                    config
+                        .body_chip
+                        .assign(
+                            &mut region,
+                            &wb,
+                            offset,
+                            0,
+                            &[*assign_type],
+                            ASSIGN_TYPES_TEST_VALUE,
+                            Some(LebParams::default()),
+                        )
+                        .unwrap();
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod wasm_code_section_body_tests {
-    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use std::ops::RangeInclusive;
+
+    use halo2_proofs::{
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Any, Column},
+    };
     use log::debug;
+    use strum::IntoEnumIterator;
     use wasmbin::sections::Kind;
 
     use bus_mapping::state_db::CodeDB;
     use eth_types::Field;
 
     use crate::wasm_circuit::{
-        common::wat_extract_section_body_bytecode, sections::code::body::tests::TestCircuit,
+        bytecode::bytecode::WasmBytecode,
+        common::wat_extract_section_body_bytecode,
+        leb128::helpers::leb128_compute_sn,
+        sections::code::body::{
+            circuit::{WasmCodeSectionBodyChip, WasmCodeSectionBodyConfig},
+            tests::{
+                assign_type_column, AssignTypesTestCircuit, TestCircuit, ASSIGN_TYPES_TEST_VALUE,
+            },
+            types::AssignType,
+        },
+        tables::code_blocks::types::Opcode as CodeBlocksOpcode,
+        test_helpers::{assigned_cell_value, test_circuit_config},
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
@@ -135,60 +319,491 @@ mod wasm_code_section_body_tests {
         }
     }
 
-    #[test]
-    pub fn file1_ok() {
-        let bytecode = wat_extract_section_body_bytecode("./test_files/cc1.wat", Kind::Code);
+    /// returns (params count of each func type, typeidx of each func) declared by the module
+    fn extract_func_types(path_to_file: &str) -> (Vec<usize>, Vec<usize>) {
+        let type_body = wat_extract_section_body_bytecode(path_to_file, Kind::Type);
+        let mut type_params_counts = vec![];
+        if !type_body.is_empty() {
+            let (types_count, last_byte_offset) = leb128_compute_sn(&type_body, false, 0).unwrap();
+            let mut offset = last_byte_offset + 1;
+            for _ in 0..types_count {
+                // skip func type prefix
+                offset += 1;
+                let (params_count, last_byte_offset) =
+                    leb128_compute_sn(&type_body, false, offset).unwrap();
+                type_params_counts.push(params_count as usize);
+                offset = last_byte_offset + 1 + params_count as usize;
+                let (results_count, last_byte_offset) =
+                    leb128_compute_sn(&type_body, false, offset).unwrap();
+                offset = last_byte_offset + 1 + results_count as usize;
+            }
+        }
+        let function_body = wat_extract_section_body_bytecode(path_to_file, Kind::Function);
+        let mut func_type_indexes = vec![];
+        if !function_body.is_empty() {
+            let (funcs_count, last_byte_offset) =
+                leb128_compute_sn(&function_body, false, 0).unwrap();
+            let mut offset = last_byte_offset + 1;
+            for _ in 0..funcs_count {
+                let (typeidx, last_byte_offset) =
+                    leb128_compute_sn(&function_body, false, offset).unwrap();
+                func_type_indexes.push(typeidx as usize);
+                offset = last_byte_offset + 1;
+            }
+        }
+        (type_params_counts, func_type_indexes)
+    }
+
+    fn test_file(path_to_file: &str, is_ok: bool) {
+        let bytecode = wat_extract_section_body_bytecode(path_to_file, Kind::Code);
         debug!(
             "bytecode (len {}) hex {:x?} bin {:?}",
             bytecode.len(),
             bytecode,
             bytecode
         );
+        let (type_params_counts, func_type_indexes) = extract_func_types(path_to_file);
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            type_params_counts,
+            func_type_indexes,
+            forged_locals_count: None,
+            forged_func_body_len: None,
             _marker: Default::default(),
         };
-        test(test_circuit, true);
+        test(test_circuit, is_ok);
+    }
+
+    /// single func body: `local.get {local_index} drop end` with `locals` i32 locals declared
+    fn test_local_index(params_count: usize, locals: u8, local_index: u8, is_ok: bool) {
+        let mut func_body = vec![];
+        if locals > 0 {
+            func_body.extend_from_slice(&[0x01, locals, 0x7f]);
+        } else {
+            func_body.push(0x00);
+        }
+        func_body.extend_from_slice(&[0x20, local_index, 0x1a, 0x0b]);
+        let mut bytecode = vec![0x01, func_body.len() as u8];
+        bytecode.extend_from_slice(&func_body);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            type_params_counts: vec![params_count],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: None,
+            _marker: Default::default(),
+        };
+        test(test_circuit, is_ok);
+    }
+
+    /// single func body without params and locals, `instructions` must include the final `end`
+    fn test_func_body(instructions: &[u8], is_ok: bool) {
+        let mut bytecode = vec![0x01, instructions.len() as u8 + 1, 0x00];
+        bytecode.extend_from_slice(instructions);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: None,
+            _marker: Default::default(),
+        };
+        test(test_circuit, is_ok);
     }
 
     #[test]
-    pub fn file2_ok() {
-        let bytecode = wat_extract_section_body_bytecode("./test_files/cc2.wat", Kind::Code);
-        debug!(
-            "bytecode (len {}) hex {:x?} bin {:?}",
-            bytecode.len(),
-            bytecode,
-            bytecode
-        );
+    pub fn forged_locals_count_fails() {
+        // `local.get 1 drop end` of a func with a single param and no locals, the forged locals
+        // count gets the local index through the comparison but doesn't match the declarations
+        let bytecode = [0x01, 0x05, 0x00, 0x20, 0x01, 0x1a, 0x0b];
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash: CodeDB::hash(&bytecode),
+            bytecode: &bytecode,
+            offset_start: 0,
+            type_params_counts: vec![1],
+            func_type_indexes: vec![0],
+            forged_locals_count: Some(2),
+            forged_func_body_len: None,
+            _marker: Default::default(),
+        };
+        test(test_circuit, false);
+    }
+
+    #[test]
+    pub fn local_index_refers_param_ok() {
+        test_local_index(1, 0, 0, true);
+    }
+
+    #[test]
+    pub fn local_index_refers_local_ok() {
+        test_local_index(1, 2, 2, true);
+    }
+
+    #[test]
+    pub fn local_index_out_of_range_fails() {
+        test_local_index(1, 2, 3, false);
+    }
+
+    #[test]
+    pub fn local_index_without_params_and_locals_fails() {
+        test_local_index(0, 0, 0, false);
+    }
+
+    #[test]
+    #[should_panic(expected = "BlockLevelUnderflow")]
+    pub fn extra_trailing_block_end_fails() {
+        // the second `end` has no block to close
+        test_func_body(&[0x0b, 0x0b], false);
+    }
+
+    #[test]
+    pub fn func_base_level_reached_before_last_end_fails() {
+        // the first `end` already closes the func
+        test_func_body(&[0x0b, 0x02, 0x40, 0x0b], false);
+    }
+
+    #[test]
+    pub fn block_with_valtype_result_ok() {
+        // block (result i32) i32.const 1 end drop
+        test_func_body(&[0x02, 0x7f, 0x41, 0x01, 0x0b, 0x1a, 0x0b], true);
+    }
+
+    #[test]
+    pub fn block_with_type_index_ok() {
+        // block (type 0) end
+        test_func_body(&[0x02, 0x00, 0x0b, 0x0b], true);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidByteValueAt(4)")]
+    pub fn block_with_negative_type_index_fails() {
+        // 0x41 is neither the empty blocktype nor a valtype and reads as a negative s33
+        test_func_body(&[0x02, 0x41, 0x0b, 0x0b], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "UnexpectedEndOfBytes(4)")]
+    pub fn truncated_func_body_fails() {
+        // func body declares 4 bytes but the bytecode ends right after `drop`
+        let bytecode = vec![0x01, 0x04, 0x00, 0x1a];
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: None,
             _marker: Default::default(),
         };
-        test(test_circuit, true);
+        test(test_circuit, false);
     }
 
     #[test]
-    pub fn file3_ok() {
-        let bytecode = wat_extract_section_body_bytecode("./test_files/cc3.wat", Kind::Code);
-        debug!(
-            "bytecode (len {}) hex {:x?} bin {:?}",
-            bytecode.len(),
-            bytecode,
-            bytecode
-        );
+    #[should_panic(expected = "FuncBodyLenExceedsModule(1)")]
+    pub fn func_body_len_exceeding_module_fails() {
+        // func body declares 16 bytes but holds just the locals count and `end`
+        let bytecode = vec![0x01, 0x10, 0x00, 0x0b];
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: None,
             _marker: Default::default(),
         };
-        test(test_circuit, true);
+        test(test_circuit, false);
+    }
+
+    /// single func body without params and locals declaring `func_body_len` bytes
+    fn test_func_body_with_len(func_body_len: u8, instructions: &[u8], is_ok: bool) {
+        let mut bytecode = vec![0x01, func_body_len, 0x00];
+        bytecode.extend_from_slice(instructions);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: None,
+            _marker: Default::default(),
+        };
+        test(test_circuit, is_ok);
+    }
+
+    #[test]
+    pub fn func_body_len_equal_to_body_bytes_count_ok() {
+        // i32.const 1 drop end
+        test_func_body_with_len(5, &[0x41, 0x01, 0x1a, 0x0b], true);
+    }
+
+    #[test]
+    pub fn func_body_len_less_than_body_bytes_count_fails() {
+        // the declared body ends right after `i32.const 1`, the parser rejects such a body, so
+        // the actual one is assigned with the declared len forged afterwards
+        let bytecode = [0x01, 0x05, 0x00, 0x41, 0x01, 0x1a, 0x0b];
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash: CodeDB::hash(&bytecode),
+            bytecode: &bytecode,
+            offset_start: 0,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0],
+            forged_locals_count: None,
+            forged_func_body_len: Some((1, 3)),
+            _marker: Default::default(),
+        };
+        let prover = MockProver::run(8, &test_circuit, vec![]).unwrap();
+        let failures = format!("{:?}", prover.verify().unwrap_err());
+        assert!(failures.contains("body_byte_rev_index=0 => last byte of the func body"));
+    }
+
+    #[test]
+    pub fn func_body_len_greater_than_body_bytes_count_fails() {
+        // the declared body takes 2 more bytes after the final `end`
+        test_func_body_with_len(4, &[0x0b, 0x1a, 0x1a], false);
+    }
+
+    #[test]
+    pub fn nested_blocks_ok() {
+        test_func_body(&[0x02, 0x40, 0x02, 0x40, 0x0b, 0x0b, 0x0b], true);
+    }
+
+    #[test]
+    pub fn sign_extension_opcodes_ok() {
+        // i32.const -1 i32.extend8_s i32.extend16_s drop
+        // i64.const -1 i64.extend8_s i64.extend16_s i64.extend32_s drop end
+        test_func_body(
+            &[
+                0x41, 0x7f, 0xc0, 0xc1, 0x1a, 0x42, 0x7f, 0xc2, 0xc3, 0xc4, 0x1a, 0x0b,
+            ],
+            true,
+        );
+    }
+
+    /// runs the satisfied body of `funcs_count` funcs of the type 0 (without params) and returns
+    /// the values of the column picked by `column` at `offsets`
+    fn assigned_column_values(
+        bytecode: &[u8],
+        funcs_count: usize,
+        column: impl Fn(&WasmCodeSectionBodyConfig<Fr>) -> Column<Any>,
+        offsets: impl Iterator<Item = usize>,
+    ) -> Vec<u64> {
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash: CodeDB::hash(bytecode),
+            bytecode,
+            type_params_counts: vec![0],
+            func_type_indexes: vec![0; funcs_count],
+            ..Default::default()
+        };
+        let prover = MockProver::run(8, &test_circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let config = test_circuit_config::<TestCircuit<Fr>>();
+        let column = column(&config.body_chip.config);
+        offsets
+            .map(|offset| {
+                assigned_cell_value(&prover, column, offset)
+                    .unwrap_or_else(|| panic!("{:?} is not assigned at {}", column, offset))
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn body_byte_rev_index_counts_down_to_func_body_end() {
+        // func 0: i32.const 1 drop end
+        // func 1: end
+        let bytecode = vec![0x02, 0x05, 0x00, 0x41, 0x01, 0x1a, 0x0b, 0x02, 0x00, 0x0b];
+        let rev_indexes = |offsets: RangeInclusive<usize>| {
+            assigned_column_values(
+                &bytecode,
+                2,
+                |config| config.body_byte_rev_index.into(),
+                offsets,
+            )
+        };
+        // the last byte of the func body len gets the body len, the last body byte gets 0
+        assert_eq!(rev_indexes(1..=6), vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(rev_indexes(7..=9), vec![2, 1, 0]);
+    }
+
+    /// runs a single func body and checks the opcodes of the `code_blocks` rows
+    fn test_func_body_code_blocks(instructions: &[u8], expected: &[CodeBlocksOpcode]) {
+        let mut bytecode = vec![0x01, instructions.len() as u8 + 1, 0x00];
+        bytecode.extend_from_slice(instructions);
+        let opcodes = assigned_column_values(
+            &bytecode,
+            1,
+            |config| config.code_blocks_chip.config.opcode.into(),
+            0..expected.len(),
+        );
+        assert_eq!(
+            opcodes,
+            expected.iter().map(|&v| v as u64).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    pub fn if_without_else_ok() {
+        // i32.const 1 if end end
+        test_func_body_code_blocks(
+            &[0x41, 0x01, 0x04, 0x40, 0x0b, 0x0b],
+            &[
+                CodeBlocksOpcode::If,
+                CodeBlocksOpcode::End,
+                CodeBlocksOpcode::End,
+            ],
+        );
+    }
+
+    #[test]
+    pub fn if_with_else_ok() {
+        // i32.const 1 if else end end
+        test_func_body_code_blocks(
+            &[0x41, 0x01, 0x04, 0x40, 0x05, 0x0b, 0x0b],
+            &[
+                CodeBlocksOpcode::If,
+                CodeBlocksOpcode::Else,
+                CodeBlocksOpcode::End,
+                CodeBlocksOpcode::End,
+            ],
+        );
+    }
+
+    #[test]
+    pub fn if_with_and_without_else_ok() {
+        // i32.const 1 if end i32.const 0 if else block end end end
+        test_func_body_code_blocks(
+            &[
+                0x41, 0x01, 0x04, 0x40, 0x0b, 0x41, 0x00, 0x04, 0x40, 0x05, 0x02, 0x40, 0x0b, 0x0b,
+                0x0b,
+            ],
+            &[
+                CodeBlocksOpcode::If,
+                CodeBlocksOpcode::End,
+                CodeBlocksOpcode::If,
+                CodeBlocksOpcode::Else,
+                CodeBlocksOpcode::Block,
+                CodeBlocksOpcode::End,
+                CodeBlocksOpcode::End,
+            ],
+        );
+    }
+
+    #[test]
+    pub fn block_opcode_number_is_contiguous_on_nested_blocks() {
+        // block block end end end
+        let bytecode = vec![0x01, 0x08, 0x00, 0x02, 0x40, 0x02, 0x40, 0x0b, 0x0b, 0x0b];
+        let numbers = assigned_column_values(
+            &bytecode,
+            1,
+            |config| config.block_opcode_number.into(),
+            0..bytecode.len(),
+        );
+        // no gaps: every step is either 0 or exactly 1
+        assert!(numbers.windows(2).all(|w| w[1] == w[0] || w[1] == w[0] + 1));
+        // each block/end opcode takes the next number
+        assert_eq!(
+            [3, 5, 7, 8, 9]
+                .iter()
+                .map(|&offset| numbers[offset])
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    pub fn collect_const_expr_ranges_finds_folded_arithmetic() {
+        // func 0: i32.const 1 i32.const 2 i32.add drop end
+        // func 1: local.get 0 i32.const 1 i32.add drop i64.const 3 drop end
+        let bytecode = vec![
+            0x02, 0x08, 0x00, 0x41, 0x01, 0x41, 0x02, 0x6a, 0x1a, 0x0b, 0x0d, 0x01, 0x01, 0x7f,
+            0x20, 0x00, 0x41, 0x01, 0x6a, 0x1a, 0x42, 0x03, 0x1a, 0x0b,
+        ];
+        let wb = WasmBytecode::new(bytecode);
+        let ranges = WasmCodeSectionBodyChip::<Fr>::collect_const_expr_ranges(&wb, 0).unwrap();
+        assert_eq!(ranges, vec![3..8]);
+    }
+
+    #[test]
+    pub fn collect_const_expr_ranges_skips_trapping_arithmetic() {
+        // i32.const 6 i32.const 7 i32.mul i32.eqz drop i32.const 1 i32.const 0 i32.div_u drop end
+        let bytecode = vec![
+            0x01, 0x0f, 0x00, 0x41, 0x06, 0x41, 0x07, 0x6c, 0x45, 0x1a, 0x41, 0x01, 0x41, 0x00,
+            0x6e, 0x1a, 0x0b,
+        ];
+        let wb = WasmBytecode::new(bytecode);
+        let ranges = WasmCodeSectionBodyChip::<Fr>::collect_const_expr_ranges(&wb, 0).unwrap();
+        assert_eq!(ranges, vec![3..9]);
+    }
+
+    #[test]
+    pub fn file1_ok() {
+        test_file("./test_files/cc1.wat", true);
+    }
+
+    #[test]
+    pub fn file2_ok() {
+        test_file("./test_files/cc2.wat", true);
+    }
+
+    #[test]
+    pub fn file3_ok() {
+        test_file("./test_files/cc3.wat", true);
+    }
+
+    #[test]
+    #[should_panic(expected = "FatalUnknownAssignTypeUsed")]
+    pub fn unknown_assign_type_fails() {
+        let test_circuit = AssignTypesTestCircuit::<Fr> {
+            assign_types: vec![AssignType::Unknown],
+            _marker: Default::default(),
+        };
+        MockProver::run(8, &test_circuit, vec![]).unwrap();
+    }
+
+    #[test]
+    pub fn every_assign_type_assigns_its_column() {
+        let assign_types = AssignType::iter()
+            .filter(|t| *t != AssignType::Unknown)
+            .collect::<Vec<_>>();
+        let test_circuit = AssignTypesTestCircuit::<Fr> {
+            assign_types: assign_types.clone(),
+            _marker: Default::default(),
+        };
+        let prover = MockProver::run(8, &test_circuit, vec![]).unwrap();
+
+        let config = test_circuit_config::<AssignTypesTestCircuit<Fr>>();
+        let value_at = |assign_type: AssignType, offset: usize| {
+            let column = assign_type_column(&config.body_chip.config, assign_type).unwrap();
+            assigned_cell_value(&prover, column, offset)
+        };
+        for (offset, assign_type) in assign_types.iter().enumerate() {
+            for other in assign_types.iter() {
+                let is_assigned = value_at(*other, offset) == Some(ASSIGN_TYPES_TEST_VALUE);
+                assert_eq!(
+                    is_assigned,
+                    other == assign_type,
+                    "assign type {:?} at {} (checking column of {:?})",
+                    assign_type,
+                    offset,
+                    other,
+                );
+            }
+        }
     }
 }
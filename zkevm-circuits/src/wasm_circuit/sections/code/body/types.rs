@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+use strum_macros::EnumIter;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, EnumIter)]
 pub enum AssignType {
     QFirst,
     QLast,
@@ -17,6 +19,10 @@ pub enum AssignType {
     IsControlInstructionLebArg,
     IsParametricInstruction,
     IsBlocktypeDelimiter,
+    IsBlocktypeValtype,
+    IsBlocktypeTypeIndex,
+    IsCallIndirectTypeIndex,
+    IsCallIndirectTableIndex,
     IsBlockEnd,
 
     BodyByteRevIndex,
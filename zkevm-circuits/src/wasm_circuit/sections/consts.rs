@@ -1,3 +1,5 @@
+use crate::wasm_circuit::leb128::consts::{BITS_IN_BYTE, LEB128_BITS_CHUNK_SIZE};
+
 #[derive(Copy, Clone, Default, Debug)]
 pub struct LebParams {
     pub is_signed: bool,
@@ -5,6 +7,14 @@ pub struct LebParams {
     pub last_byte_rel_offset: usize,
     pub sn: u64,
     pub sn_recovered_at_pos: u64,
+    pub byte_val: u8,
+    /// enables minimal encoding checks (only takes effect if the chip is configured with
+    /// `require_minimal`)
+    pub is_minimal: bool,
+    /// declared width of the value in bits (32 or 64)
+    pub bit_width: usize,
+    /// max number of bytes the leb128 may occupy (5 for 32-bit, 10 for 64-bit values)
+    pub max_bytes: usize,
 }
 
 impl LebParams {
@@ -19,4 +29,25 @@ impl LebParams {
     pub fn is_byte_has_cb(&self) -> bool {
         self.byte_rel_offset < self.last_byte_rel_offset
     }
+
+    /// multiplier which moves the bits of the last byte unused by `bit_width` beyond the 8 bit
+    /// range, so `last_byte * mul < 256` iff those bits are zero
+    pub fn last_byte_high_bits_mul(&self) -> u64 {
+        if !self.is_minimal || !self.is_last_byte() {
+            return 0;
+        }
+        let bits_used = LEB128_BITS_CHUNK_SIZE * self.byte_rel_offset;
+        if bits_used >= self.bit_width {
+            return 1 << BITS_IN_BYTE;
+        }
+        let bits_allowed = self.bit_width - bits_used;
+        if bits_allowed >= BITS_IN_BYTE {
+            return 1;
+        }
+        1 << (BITS_IN_BYTE - bits_allowed)
+    }
 }
+
+/// number of trailing export name bytes packed into the key the export names are told apart by
+/// (keeps the key below 2^248)
+pub const EXPORT_NAME_KEY_MAX_LEN: usize = 30;
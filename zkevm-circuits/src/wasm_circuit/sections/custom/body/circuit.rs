@@ -0,0 +1,481 @@
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+    circuit::{Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    poly::Rotation,
+};
+use log::debug;
+
+use eth_types::Field;
+use gadgets::util::{and, not, or, Expr};
+
+use crate::{
+    evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    wasm_circuit::{
+        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        common::{
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+        },
+        error::{
+            checked_assign_offset, error_index_out_of_bounds, remap_error_to_assign_at,
+            require_leb_params_at, Error,
+        },
+        leb128::circuit::LEB128Chip,
+        sections::{consts::LebParams, custom::body::types::AssignType},
+        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
+        utf8::circuit::UTF8Chip,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct WasmCustomSectionBodyConfig<F: Field> {
+    pub q_enable: Column<Fixed>,
+    pub q_first: Column<Fixed>,
+    pub q_last: Column<Fixed>,
+    pub is_name_len: Column<Fixed>,
+    pub is_name: Column<Fixed>,
+    pub is_custom_bytes: Column<Fixed>,
+
+    pub wb_table: Rc<WasmBytecodeTable>,
+    pub leb128_chip: Rc<LEB128Chip<F>>,
+    pub utf8_chip: Rc<UTF8Chip<F>>,
+
+    pub func_count: Column<Advice>,
+    body_byte_rev_index: Column<Advice>,
+
+    pub error_code: Column<Advice>,
+
+    shared_state: Rc<RefCell<SharedState>>,
+
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field> WasmCustomSectionBodyConfig<F> {}
+
+/// custom section body is `name_len+ name* custom_bytes*`, where `custom_bytes` are opaque and
+/// never interpreted (name section, producers section, etc.)
+#[derive(Debug, Clone)]
+pub struct WasmCustomSectionBodyChip<F: Field> {
+    pub config: WasmCustomSectionBodyConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmCustomSectionBodyChip<F> {}
+
+impl<F: Field> WasmLenPrefixedBytesSpanAwareChip<F> for WasmCustomSectionBodyChip<F> {}
+
+impl<F: Field> WasmErrorAwareChip<F> for WasmCustomSectionBodyChip<F> {
+    fn error_code_col(&self) -> Column<Advice> {
+        self.config.error_code
+    }
+}
+
+impl<F: Field> WasmSharedStateAwareChip<F> for WasmCustomSectionBodyChip<F> {
+    fn shared_state(&self) -> Rc<RefCell<SharedState>> {
+        self.config.shared_state.clone()
+    }
+}
+
+impl<F: Field> WasmFuncCountAwareChip<F> for WasmCustomSectionBodyChip<F> {
+    fn func_count_col(&self) -> Column<Advice> {
+        self.config.func_count
+    }
+}
+
+impl<F: Field> WasmAssignAwareChip<F> for WasmCustomSectionBodyChip<F> {
+    type AssignType = AssignType;
+
+    fn assign_internal(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+        assign_types: &[Self::AssignType],
+        assign_value: AssignValueType,
+        leb_params: Option<LebParams>,
+    ) -> Result<(), Error> {
+        let q_enable = true;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
+        debug!(
+            "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
+        );
+        region
+            .assign_fixed(
+                || format!("assign 'q_enable' val {} at {}", q_enable, assign_offset),
+                self.config.q_enable,
+                assign_offset,
+                || Value::known(F::from(q_enable as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+        self.assign_func_count(region, assign_offset)?;
+
+        for assign_type in assign_types {
+            if *assign_type == AssignType::IsNameLen {
+                let p = require_leb_params_at(leb_params, assign_offset)?;
+                self.config
+                    .leb128_chip
+                    .assign(region, assign_offset, q_enable, p)?;
+            }
+            if *assign_type == AssignType::IsName {
+                self.config
+                    .utf8_chip
+                    .assign(region, assign_offset, q_enable, byte_val)?;
+            }
+            match assign_type {
+                AssignType::QFirst => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'q_first' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.q_first,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::QLast => {
+                    region
+                        .assign_fixed(
+                            || format!("assign 'q_last' val {} at {}", assign_value, assign_offset),
+                            self.config.q_last,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsNameLen => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_name_len' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_name_len,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsName => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_name' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_name,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsCustomBytes => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_custom_bytes' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_custom_bytes,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::BodyByteRevIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'body_byte_rev_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.body_byte_rev_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::ErrorCode => {
+                    self.assign_error_code(region, assign_offset, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmCustomSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "custom_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_name_len", self.config.is_name_len.into()),
+                ("is_name", self.config.is_name.into()),
+                ("is_custom_bytes", self.config.is_custom_bytes.into()),
+            ],
+        );
+    }
+}
+
+impl<F: Field> WasmCustomSectionBodyChip<F> {
+    pub fn construct(config: WasmCustomSectionBodyConfig<F>) -> Self {
+        let instance = Self {
+            config,
+            _marker: PhantomData,
+        };
+        instance
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        wb_table: Rc<WasmBytecodeTable>,
+        leb128_chip: Rc<LEB128Chip<F>>,
+        utf8_chip: Rc<UTF8Chip<F>>,
+        func_count: Column<Advice>,
+        shared_state: Rc<RefCell<SharedState>>,
+        body_byte_rev_index: Column<Advice>,
+        error_code: Column<Advice>,
+    ) -> WasmCustomSectionBodyConfig<F> {
+        let q_enable = cs.fixed_column();
+        let q_first = cs.fixed_column();
+        let q_last = cs.fixed_column();
+        let is_name_len = cs.fixed_column();
+        let is_name = cs.fixed_column();
+        let is_custom_bytes = cs.fixed_column();
+
+        Self::configure_len_prefixed_bytes_span_checks(
+            cs,
+            leb128_chip.as_ref(),
+            |vc| vc.query_fixed(is_name, Rotation::cur()),
+            body_byte_rev_index,
+            |vc| {
+                and::expr([
+                    vc.query_fixed(is_name_len, Rotation::cur()),
+                    vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur()),
+                ])
+            },
+            |vc| {
+                let is_name_expr = vc.query_fixed(is_name, Rotation::cur());
+                let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+                let is_custom_bytes_next_expr = vc.query_fixed(is_custom_bytes, Rotation::next());
+
+                and::expr([
+                    is_name_expr,
+                    or::expr([q_last_expr, is_custom_bytes_next_expr]),
+                ])
+            },
+        );
+
+        cs.create_gate("WasmCustomSectionBody gate", |vc| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                vc,
+                q_enable,
+                &shared_state.borrow(),
+                error_code,
+            );
+            let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+            let not_q_last_expr = not::expr(q_last_expr.clone());
+            let is_name_len_expr = vc.query_fixed(is_name_len, Rotation::cur());
+            let is_name_expr = vc.query_fixed(is_name, Rotation::cur());
+            let is_custom_bytes_expr = vc.query_fixed(is_custom_bytes, Rotation::cur());
+
+            let leb128_q_enable_expr = vc.query_fixed(leb128_chip.config.q_enable, Rotation::cur());
+            let utf8_q_enable_expr = vc.query_fixed(utf8_chip.config.q_enable, Rotation::cur());
+
+            cb.require_boolean("q_enable is boolean", q_enable_expr.clone());
+            cb.require_boolean("is_name_len is boolean", is_name_len_expr.clone());
+            cb.require_boolean("is_name is boolean", is_name_expr.clone());
+            cb.require_boolean("is_custom_bytes is boolean", is_custom_bytes_expr.clone());
+
+            configure_constraints_for_q_first_and_q_last(
+                &mut cb,
+                vc,
+                &q_enable,
+                &q_first,
+                &[is_name_len],
+                &q_last,
+                &[is_name_len, is_name, is_custom_bytes],
+            );
+
+            cb.require_equal(
+                "exactly one mark flag active at the same time",
+                is_name_len_expr.clone() + is_name_expr.clone() + is_custom_bytes_expr.clone(),
+                1.expr(),
+            );
+
+            cb.condition(is_name_len_expr.clone(), |cb| {
+                cb.require_equal(
+                    "is_name_len => leb128",
+                    leb128_q_enable_expr.clone(),
+                    1.expr(),
+                )
+            });
+            cb.require_equal(
+                "is_name -> utf8",
+                is_name_expr.clone(),
+                utf8_q_enable_expr.clone(),
+            );
+
+            // is_name_len+ -> is_name* -> is_custom_bytes*
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_name_len+ -> is_name* -> is_custom_bytes*",
+                and::expr([not_q_last_expr.clone(), is_name_len_expr.clone()]),
+                true,
+                &[is_name_len, is_name, is_custom_bytes],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_name* -> is_custom_bytes*",
+                and::expr([not_q_last_expr.clone(), is_name_expr.clone()]),
+                true,
+                &[is_name, is_custom_bytes],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_custom_bytes*",
+                and::expr([not_q_last_expr.clone(), is_custom_bytes_expr.clone()]),
+                true,
+                &[is_custom_bytes],
+            );
+
+            cb.gate(q_enable_expr.clone())
+        });
+
+        let config = WasmCustomSectionBodyConfig::<F> {
+            _marker: PhantomData,
+
+            q_enable,
+            q_first,
+            q_last,
+            is_name_len,
+            is_name,
+            is_custom_bytes,
+            wb_table,
+            leb128_chip,
+            utf8_chip,
+            func_count,
+            body_byte_rev_index,
+            error_code,
+            shared_state,
+        };
+
+        config
+    }
+
+    /// `body_len` is the section len, custom bytes occupy everything after the name
+    pub fn assign_auto(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+        body_len: usize,
+    ) -> Result<NewWbOffsetType, Error> {
+        let mut offset = wb_offset;
+        let body_end_offset = wb_offset + body_len;
+        if body_end_offset > wb.bytes.len() {
+            return Err(error_index_out_of_bounds(wb_offset));
+        }
+
+        // is_name_len+
+        let (name_len, name_len_leb_len) =
+            self.markup_leb_section(region, wb, offset, assign_delta, &[AssignType::IsNameLen])?;
+        self.assign(
+            region,
+            &wb,
+            offset,
+            assign_delta,
+            &[AssignType::QFirst],
+            1,
+            None,
+        )?;
+        let name_len_last_byte_offset = offset + name_len_leb_len - 1;
+        let name_last_byte_offset = name_len_last_byte_offset + name_len as usize;
+        if name_last_byte_offset >= body_end_offset {
+            return Err(error_index_out_of_bounds(name_len_last_byte_offset));
+        }
+        for offset in name_len_last_byte_offset..=name_last_byte_offset {
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::BodyByteRevIndex],
+                (name_last_byte_offset - offset) as u64,
+                None,
+            )?;
+        }
+        offset += name_len_leb_len;
+
+        // is_name*
+        for offset in offset..offset + name_len as usize {
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::IsName],
+                1,
+                None,
+            )?;
+        }
+        offset += name_len as usize;
+
+        // is_custom_bytes*
+        for offset in offset..body_end_offset {
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::IsCustomBytes],
+                1,
+                None,
+            )?;
+        }
+        offset = body_end_offset;
+
+        if offset != wb_offset {
+            self.assign(
+                region,
+                &wb,
+                offset - 1,
+                assign_delta,
+                &[AssignType::QLast],
+                1,
+                None,
+            )?;
+        }
+
+        Ok(offset)
+    }
+}
@@ -0,0 +1,199 @@
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use eth_types::{Field, Hash};
+
+use crate::wasm_circuit::{
+    bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
+    sections::custom::body::circuit::WasmCustomSectionBodyChip,
+    tables::fixed_range::config::RangeTableConfig,
+    types::SharedState,
+    utf8::circuit::UTF8Chip,
+};
+
+#[derive(Default)]
+struct TestCircuit<'a, F> {
+    code_hash: Hash,
+    bytecode: &'a [u8],
+    offset_start: usize,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone)]
+struct TestCircuitConfig<F: Field> {
+    body_chip: Rc<WasmCustomSectionBodyChip<F>>,
+    wb_table: Rc<WasmBytecodeTable>,
+    range_table_config_0_128: Rc<RangeTableConfig<F, 0, 128>>,
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
+    type Config = TestCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        let wb_table = Rc::new(WasmBytecodeTable::construct(cs, false));
+        let func_count = cs.advice_column();
+        let error_code = cs.advice_column();
+        let body_byte_rev_index = cs.advice_column();
+
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+
+        let range_table_config_0_128 = Rc::new(RangeTableConfig::configure(cs));
+
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
+        let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
+
+        let utf8_config =
+            UTF8Chip::configure(cs, range_table_config_0_128.clone(), &wb_table.value);
+        let utf8_chip = Rc::new(UTF8Chip::construct(utf8_config));
+
+        let config = WasmCustomSectionBodyChip::configure(
+            cs,
+            wb_table.clone(),
+            leb128_chip.clone(),
+            utf8_chip.clone(),
+            func_count,
+            shared_state.clone(),
+            body_byte_rev_index,
+            error_code,
+        );
+        let body_chip = Rc::new(WasmCustomSectionBodyChip::construct(config));
+
+        let test_circuit_config = TestCircuitConfig {
+            body_chip,
+            wb_table,
+            range_table_config_0_128,
+            _marker: Default::default(),
+        };
+
+        test_circuit_config
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let wb = WasmBytecode::new(self.bytecode.to_vec().clone());
+        let assign_delta = 0;
+        config.range_table_config_0_128.load(&mut layouter)?;
+        layouter
+            .assign_region(
+                || format!("wasm bytecode table at {}", assign_delta),
+                |mut region| {
+                    config.wb_table.load(&mut region, &wb, assign_delta)?;
+                    Ok(())
+                },
+            )
+            .unwrap();
+        layouter.assign_region(
+            || "wasm_custom_section_body region",
+            |mut region| {
+                config
+                    .body_chip
+                    .assign_auto(
+                        &mut region,
+                        &wb,
+                        self.offset_start,
+                        assign_delta,
+                        wb.bytes.len() - self.offset_start,
+                    )
+                    .unwrap();
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod wasm_custom_section_body_tests {
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    use bus_mapping::state_db::CodeDB;
+    use eth_types::Field;
+
+    use crate::wasm_circuit::sections::custom::body::tests::TestCircuit;
+
+    fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
+        let k = 8;
+        let prover = MockProver::run(k, &test_circuit, vec![]).unwrap();
+        if is_ok {
+            prover.assert_satisfied();
+        } else {
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    fn custom_section_body(name: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut bytecode = vec![name.len() as u8];
+        bytecode.extend(name);
+        bytecode.extend(payload);
+        bytecode
+    }
+
+    #[test]
+    pub fn name_and_payload_ok() {
+        let bytecode = custom_section_body(b"name", &[0x01, 0x06, 0x01, 0x00, 0xff, 0x80]);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            _marker: Default::default(),
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn empty_payload_ok() {
+        let bytecode = custom_section_body(b"producers", &[]);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            _marker: Default::default(),
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn empty_name_ok() {
+        let bytecode = custom_section_body(b"", &[0xaa, 0xbb]);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            _marker: Default::default(),
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn invalid_utf8_name_fails() {
+        let bytecode = custom_section_body(&[b'n', 0xff, b'm', b'e'], &[0x01]);
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            _marker: Default::default(),
+        };
+        test(test_circuit, false);
+    }
+}
@@ -0,0 +1,13 @@
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AssignType {
+    QFirst,
+    QLast,
+
+    IsNameLen,
+    IsName,
+    IsCustomBytes,
+
+    BodyByteRevIndex,
+
+    ErrorCode,
+}
@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -11,22 +11,25 @@ use log::debug;
 use eth_types::Field;
 use gadgets::{
     binary_number::BinaryNumberChip,
-    util::{and, not, or, Expr},
+    util::{and, not, or, select, Expr},
 };
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
-            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmLenPrefixedBytesSpanAwareChip, WasmMarkupLeb128SectionAwareChip,
+            WasmSectionSummaryAwareChip, WasmSharedStateAwareChip,
         },
         consts::WASM_BLOCK_END,
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
         leb128::circuit::LEB128Chip,
         sections::{consts::LebParams, data::body::types::AssignType},
@@ -36,7 +39,7 @@ use crate::{
         },
         types::{
             AssignDeltaType, AssignValueType, MemSegmentType, NewWbOffsetType, NumericInstruction,
-            SharedState,
+            SectionParseSummary, SharedState,
         },
     },
 };
@@ -61,6 +64,12 @@ pub struct WasmDataSectionBodyConfig<F: Field> {
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
     pub mem_segment_type: Column<Advice>,
     pub mem_segment_type_chip: Rc<BinaryNumberChip<F, MemSegmentType, 8>>,
+    /// the memory the segments get copied into is a 64-bit one
+    pub is_memory64: Column<Advice>,
+    /// data index of the segment the row belongs to
+    pub item_index: Column<Advice>,
+
+    pub wb_table: Rc<WasmBytecodeTable>,
 
     func_count: Column<Advice>,
     body_byte_rev_index: Column<Advice>,
@@ -119,14 +128,15 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmDataSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?} leb_params {:?}",
             assign_offset,
             q_enable,
             assign_types,
             assign_value,
-            wb.bytes[wb_offset],
+            byte_val,
             leb_params,
         );
         region
@@ -147,7 +157,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmDataSectionBodyChip<F> {
             ]
             .contains(&assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -320,6 +330,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmDataSectionBodyChip<F> {
                         .assign(region, assign_offset, &mem_segment_type)
                         .map_err(remap_error(Error::FatalAssignExternalChip))?;
                 }
+                AssignType::IsMemory64 => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'is_memory64' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_memory64,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::IsMemIndex => {
                     region
                         .assign_fixed(
@@ -365,6 +390,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmDataSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::ItemIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'item_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.item_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::ErrorCode => {
                     self.assign_error_code(region, assign_offset, None)?;
                 }
@@ -374,6 +414,47 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmDataSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmDataSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "data_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_memidx", self.config.is_memidx.into()),
+                (
+                    "is_mem_segment_type",
+                    self.config.is_mem_segment_type.into(),
+                ),
+                (
+                    "is_mem_segment_size_opcode",
+                    self.config.is_mem_segment_size_opcode.into(),
+                ),
+                (
+                    "is_mem_segment_size",
+                    self.config.is_mem_segment_size.into(),
+                ),
+                ("is_block_end", self.config.is_block_end.into()),
+                ("is_mem_segment_len", self.config.is_mem_segment_len.into()),
+                (
+                    "is_mem_segment_bytes",
+                    self.config.is_mem_segment_bytes.into(),
+                ),
+                (
+                    "is_mem_segment_type_ctx",
+                    self.config.is_mem_segment_type_ctx.into(),
+                ),
+                ("mem_segment_type", self.config.mem_segment_type.into()),
+                ("is_memory64", self.config.is_memory64.into()),
+                ("item_index", self.config.item_index.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmDataSectionBodyChip<F> {
     pub fn construct(config: WasmDataSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -383,6 +464,49 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every segment has a `DataSegmentLen` row (first byte of its len)
+    /// and the non-empty ones a `DataSegmentOffset` row (first byte of the segment bytes)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_len_row_expr = and::expr([
+            q_enable_expr.clone(),
+            vc.query_fixed(self.config.is_mem_segment_len, Rotation::cur()),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+        ]);
+        let is_offset_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_mem_segment_bytes, Rotation::cur()),
+            vc.query_fixed(self.config.is_mem_segment_len, Rotation::prev()),
+        ]);
+        let is_row_expr = is_len_row_expr.clone() + is_offset_row_expr.clone();
+        // active segments are dropped once the module is instantiated
+        let is_passive_expr =
+            self.config
+                .mem_segment_type_chip
+                .config
+                .value_equals(MemSegmentType::Passive, Rotation::cur())(vc);
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_len_row_expr.clone() * WasmMetadataTag::DataSegmentLen.expr()
+                + is_offset_row_expr.clone() * WasmMetadataTag::DataSegmentOffset.expr(),
+            is_row_expr * vc.query_advice(self.config.item_index, Rotation::cur()),
+            is_len_row_expr
+                * is_passive_expr
+                * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur())
+                + is_offset_row_expr * vc.query_advice(self.config.wb_table.index, Rotation::cur()),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
@@ -409,6 +533,8 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
 
         let is_mem_segment_type_ctx = cs.fixed_column();
         let mem_segment_type = cs.advice_column();
+        let is_memory64 = cs.advice_column();
+        let item_index = cs.advice_column();
 
         let config =
             BinaryNumberChip::configure(cs, is_mem_segment_type_ctx, Some(mem_segment_type.into()));
@@ -506,6 +632,7 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
 
             let byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
             let mem_segment_type_expr = vc.query_advice(mem_segment_type, Rotation::cur());
+            let is_memory64_expr = vc.query_advice(is_memory64, Rotation::cur());
 
             let leb128_is_last_byte_expr = vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur());
 
@@ -519,6 +646,22 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
             cb.require_boolean("is_mem_segment_len is boolean", is_mem_segment_len_expr.clone());
             cb.require_boolean("is_mem_segment_bytes is boolean", is_mem_segment_bytes_expr.clone());
 
+            // segments are numbered from 0 in declaration order, every segment starts with its type
+            let item_index_expr = vc.query_advice(item_index, Rotation::cur());
+            let item_index_prev_expr = vc.query_advice(item_index, Rotation::prev());
+            let is_items_count_prev_expr = vc.query_fixed(is_items_count, Rotation::prev());
+            cb.condition(is_items_count_expr.clone(), |cb| {
+                cb.require_zero("is_items_count => item_index=0", item_index_expr.clone());
+            });
+            cb.condition(not::expr(is_items_count_expr.clone()), |cb| {
+                cb.require_equal(
+                    "!is_items_count => item_index=prev.item_index+(is_mem_segment_type && !prev.is_items_count)",
+                    item_index_expr.clone(),
+                    item_index_prev_expr.clone()
+                        + is_mem_segment_type_expr.clone() * not::expr(is_items_count_prev_expr.clone()),
+                );
+            });
+
             cb.require_equal(
                 "exactly one mark flag active at the same time",
                 is_items_count_expr.clone()
@@ -1006,8 +1149,20 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
                         byte_val_expr.clone(),
                         vec![
                             NumericInstruction::I32Const.expr(),
+                            NumericInstruction::I64Const.expr(),
                         ],
-                    )
+                    );
+                    // is_memory64 is looked up in the memory 0 limit type by `WasmChip`
+                    cb.require_boolean("is_mem_segment_size_opcode -> is_memory64 is boolean", is_memory64_expr.clone());
+                    cb.require_equal(
+                        "is_mem_segment_size_opcode -> opcode matches the memory type",
+                        byte_val_expr.clone(),
+                        select::expr(
+                            is_memory64_expr.clone(),
+                            NumericInstruction::I64Const.expr(),
+                            NumericInstruction::I32Const.expr(),
+                        ),
+                    );
                 }
             );
 
@@ -1033,6 +1188,9 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
             dynamic_indexes_chip,
             mem_segment_type,
             mem_segment_type_chip,
+            is_memory64,
+            item_index,
+            wb_table,
             func_count,
             body_byte_rev_index,
             body_item_rev_count,
@@ -1043,13 +1201,49 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
         config
     }
 
-    pub fn assign_auto(
+    /// checks the offset expression opcode at `wb_offset` against the type of the memory
+    /// declared by the memory section: `i64.const` for a 64-bit memory, `i32.const` otherwise
+    fn assign_mem_segment_size_opcode_memory_type(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(), Error> {
+        let is_memory64 = self.config.shared_state.borrow().is_memory64;
+        let expected_opcode = if is_memory64 {
+            NumericInstruction::I64Const
+        } else {
+            NumericInstruction::I32Const
+        };
+        if wb.byte_at(wb_offset)? != expected_opcode as u8 {
+            return Err(Error::InvalidByteValueAt(checked_assign_offset(
+                wb_offset,
+                assign_delta,
+            )?));
+        }
+        self.assign(
+            region,
+            wb,
+            wb_offset,
+            assign_delta,
+            &[AssignType::IsMemory64],
+            is_memory64 as u64,
+            None,
+        )?;
+
+        Ok(())
+    }
+}
+
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmDataSectionBodyChip<F> {
+    fn assign_auto_with_summary(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         // items_count+
@@ -1080,6 +1274,15 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::ItemIndex],
+                0,
+                None,
+            )?;
         }
         let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
             region,
@@ -1091,12 +1294,12 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
         self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
         offset += items_count_leb_len;
 
-        for _item_index in 0..items_count {
+        for item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
 
             // is_mem_segment_type{1}
-            let mem_segment_type_val = wb.bytes[offset];
+            let mem_segment_type_val = wb.byte_at(offset)?;
             let mem_segment_type: MemSegmentType = mem_segment_type_val
                 .try_into()
                 .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -1147,6 +1350,12 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
                         mem_segment_type_val as u64,
                         None,
                     )?;
+                    self.assign_mem_segment_size_opcode_memory_type(
+                        region,
+                        wb,
+                        offset,
+                        assign_delta,
+                    )?;
                     offset += 1;
 
                     // is_mem_segment_size+
@@ -1363,6 +1572,12 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
                         mem_segment_type_val as u64,
                         None,
                     )?;
+                    self.assign_mem_segment_size_opcode_memory_type(
+                        region,
+                        wb,
+                        offset,
+                        assign_delta,
+                    )?;
                     offset += 1;
 
                     // is_mem_segment_size+
@@ -1471,6 +1686,15 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
                     body_item_rev_count,
                     None,
                 )?;
+                self.assign(
+                    region,
+                    &wb,
+                    offset,
+                    assign_delta,
+                    &[AssignType::ItemIndex],
+                    item_index,
+                    None,
+                )?;
             }
         }
 
@@ -1486,6 +1710,21 @@ impl<F: Field> WasmDataSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            items_count as usize,
+            Tag::DataSegmentIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -9,7 +9,8 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::data::body::circuit::WasmDataSectionBodyChip,
     tables::dynamic_indexes::circuit::DynamicIndexesChip,
     types::SharedState,
@@ -51,7 +52,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_data_section_body_config = WasmDataSectionBodyChip::configure(
@@ -15,9 +15,11 @@ pub enum AssignType {
     IsMemSegmentTypeCtx,
 
     MemSegmentType,
+    IsMemory64,
 
     BodyByteRevIndex,
     BodyItemRevCount,
+    ItemIndex,
 
     ErrorCode,
 }
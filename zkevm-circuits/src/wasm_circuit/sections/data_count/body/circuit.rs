@@ -0,0 +1,369 @@
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+    circuit::{Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    poly::Rotation,
+};
+use log::debug;
+
+use eth_types::Field;
+use gadgets::util::{and, not, Expr};
+
+use crate::{
+    evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    wasm_circuit::{
+        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        common::{
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip,
+            WasmSharedStateAwareChip,
+        },
+        error::{checked_assign_offset, remap_error_to_assign_at, require_leb_params_at, Error},
+        leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT_32_BITS},
+        sections::{consts::LebParams, data_count::body::types::AssignType},
+        tables::dynamic_indexes::{
+            circuit::DynamicIndexesChip,
+            types::{LookupArgsParams, Tag},
+        },
+        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct WasmDataCountSectionBodyConfig<F: Field> {
+    pub q_enable: Column<Fixed>,
+    pub q_first: Column<Fixed>,
+    pub q_last: Column<Fixed>,
+    pub is_data_count: Column<Fixed>,
+
+    pub wb_table: Rc<WasmBytecodeTable>,
+    pub leb128_chip: Rc<LEB128Chip<F>>,
+    pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
+
+    pub func_count: Column<Advice>,
+
+    pub error_code: Column<Advice>,
+
+    shared_state: Rc<RefCell<SharedState>>,
+
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field> WasmDataCountSectionBodyConfig<F> {}
+
+#[derive(Debug, Clone)]
+pub struct WasmDataCountSectionBodyChip<F: Field> {
+    pub config: WasmDataCountSectionBodyConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT_32_BITS
+    }
+}
+
+impl<F: Field> WasmErrorAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    fn error_code_col(&self) -> Column<Advice> {
+        self.config.error_code
+    }
+}
+
+impl<F: Field> WasmSharedStateAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    fn shared_state(&self) -> Rc<RefCell<SharedState>> {
+        self.config.shared_state.clone()
+    }
+}
+
+impl<F: Field> WasmFuncCountAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    fn func_count_col(&self) -> Column<Advice> {
+        self.config.func_count
+    }
+}
+
+impl<F: Field> WasmAssignAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    type AssignType = AssignType;
+
+    fn assign_internal(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+        assign_types: &[Self::AssignType],
+        assign_value: AssignValueType,
+        leb_params: Option<LebParams>,
+    ) -> Result<(), Error> {
+        let q_enable = true;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
+        debug!(
+            "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
+        );
+        region
+            .assign_fixed(
+                || format!("assign 'q_enable' val {} at {}", q_enable, assign_offset),
+                self.config.q_enable,
+                assign_offset,
+                || Value::known(F::from(q_enable as u64)),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+        self.assign_func_count(region, assign_offset)?;
+
+        for assign_type in assign_types {
+            if *assign_type == AssignType::IsDataCount {
+                let p = require_leb_params_at(leb_params, assign_offset)?;
+                self.config
+                    .leb128_chip
+                    .assign(region, assign_offset, q_enable, p)?;
+            }
+            match assign_type {
+                AssignType::QFirst => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'q_first' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.q_first,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::QLast => {
+                    region
+                        .assign_fixed(
+                            || format!("assign 'q_last' val {} at {}", assign_value, assign_offset),
+                            self.config.q_last,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::IsDataCount => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_data_count' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_data_count,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::ErrorCode => {
+                    self.assign_error_code(region, assign_offset, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmDataCountSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "data_count_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_data_count", self.config.is_data_count.into()),
+            ],
+        );
+    }
+}
+
+impl<F: Field> WasmDataCountSectionBodyChip<F> {
+    pub fn construct(config: WasmDataCountSectionBodyConfig<F>) -> Self {
+        let instance = Self {
+            config,
+            _marker: PhantomData,
+        };
+        instance
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        wb_table: Rc<WasmBytecodeTable>,
+        leb128_chip: Rc<LEB128Chip<F>>,
+        dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
+        func_count: Column<Advice>,
+        shared_state: Rc<RefCell<SharedState>>,
+        error_code: Column<Advice>,
+        bytecode_number: Column<Advice>,
+    ) -> WasmDataCountSectionBodyConfig<F> {
+        let q_enable = cs.fixed_column();
+        let q_first = cs.fixed_column();
+        let q_last = cs.fixed_column();
+        let is_data_count = cs.fixed_column();
+
+        dynamic_indexes_chip.lookup_args(
+            "data count section value equals to the number of data segments",
+            cs,
+            |vc| {
+                let cond = vc.query_fixed(is_data_count, Rotation::cur());
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::DataIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+
+        cs.create_gate("WasmDataCountSectionBody gate", |vc| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                vc,
+                q_enable,
+                &shared_state.borrow(),
+                error_code,
+            );
+            // let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
+            let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
+            let not_q_last_expr = not::expr(q_last_expr.clone());
+            let is_data_count_expr = vc.query_fixed(is_data_count, Rotation::cur());
+            let is_data_count_prev_expr = vc.query_fixed(is_data_count, Rotation::prev());
+
+            let _byte_val_expr = vc.query_advice(wb_table.value, Rotation::cur());
+
+            let leb128_q_enable_expr = vc.query_fixed(leb128_chip.config.q_enable, Rotation::cur());
+            let leb128_is_first_byte_expr =
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur());
+
+            cb.require_boolean("q_enable is boolean", q_enable_expr.clone());
+            cb.require_boolean("is_data_count is boolean", is_data_count_expr.clone());
+
+            configure_constraints_for_q_first_and_q_last(
+                &mut cb,
+                vc,
+                &q_enable,
+                &q_first,
+                &[is_data_count],
+                &q_last,
+                &[is_data_count],
+            );
+
+            cb.require_equal(
+                "exactly one mark flag active at the same time",
+                is_data_count_expr.clone(),
+                1.expr(),
+            );
+
+            cb.condition(is_data_count_expr.clone(), |cb| {
+                cb.require_equal(
+                    "is_data_count => leb128",
+                    leb128_q_enable_expr.clone(),
+                    1.expr(),
+                )
+            });
+
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check prev: is_data_count+",
+                and::expr([not_q_last_expr.clone(), is_data_count_expr.clone()]),
+                false,
+                &[is_data_count],
+            );
+            cb.condition(
+                and::expr([
+                    is_data_count_expr.clone(),
+                    leb128_is_first_byte_expr.clone(),
+                    is_data_count_prev_expr.clone(),
+                ]),
+                |cb| {
+                    let leb128_q_enable_prev_expr =
+                        vc.query_fixed(leb128_chip.config.q_enable, Rotation::prev());
+                    cb.require_equal(
+                        "exactly one leb arg in a row",
+                        leb128_q_enable_prev_expr,
+                        0.expr(),
+                    )
+                },
+            );
+
+            cb.gate(q_enable_expr.clone())
+        });
+
+        let config = WasmDataCountSectionBodyConfig::<F> {
+            _marker: PhantomData,
+
+            q_enable,
+            q_first,
+            q_last,
+            is_data_count,
+            wb_table,
+            leb128_chip,
+            dynamic_indexes_chip,
+            func_count,
+            error_code,
+            shared_state,
+        };
+
+        config
+    }
+
+    pub fn assign_auto(
+        &self,
+        region: &mut Region<F>,
+        wb: &WasmBytecode,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+    ) -> Result<NewWbOffsetType, Error> {
+        let mut offset = wb_offset;
+
+        let (_data_count, data_count_leb_len) = self.markup_leb_section(
+            region,
+            &wb,
+            offset,
+            assign_delta,
+            &[AssignType::IsDataCount],
+        )?;
+        self.assign(
+            region,
+            &wb,
+            offset,
+            assign_delta,
+            &[AssignType::QFirst],
+            1,
+            None,
+        )?;
+        offset += data_count_leb_len;
+
+        if offset != wb_offset {
+            self.assign(
+                region,
+                &wb,
+                offset - 1,
+                assign_delta,
+                &[AssignType::QLast],
+                1,
+                None,
+            )?;
+        }
+
+        Ok(offset)
+    }
+}
@@ -0,0 +1,173 @@
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use eth_types::{Field, Hash};
+
+use crate::wasm_circuit::{
+    bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
+    sections::data_count::body::circuit::WasmDataCountSectionBodyChip,
+    tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
+    types::SharedState,
+};
+
+#[derive(Default)]
+struct TestCircuit<'a, F> {
+    code_hash: Hash,
+    bytecode: &'a [u8],
+    offset_start: usize,
+    data_segments_count: usize,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone)]
+struct TestCircuitConfig<F: Field> {
+    body_chip: Rc<WasmDataCountSectionBodyChip<F>>,
+    dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
+    wb_table: Rc<WasmBytecodeTable>,
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
+    type Config = TestCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        let wb_table = Rc::new(WasmBytecodeTable::construct(cs, false));
+        let func_count = cs.advice_column();
+        let error_code = cs.advice_column();
+        let bytecode_number = cs.advice_column();
+
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+
+        let config = DynamicIndexesChip::configure(cs, shared_state.clone());
+        let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
+
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
+        let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
+
+        let config = WasmDataCountSectionBodyChip::configure(
+            cs,
+            wb_table.clone(),
+            leb128_chip.clone(),
+            dynamic_indexes_chip.clone(),
+            func_count,
+            shared_state.clone(),
+            error_code,
+            bytecode_number,
+        );
+        let body_chip = Rc::new(WasmDataCountSectionBodyChip::construct(config));
+
+        let test_circuit_config = TestCircuitConfig {
+            body_chip,
+            dynamic_indexes_chip,
+            wb_table,
+            _marker: Default::default(),
+        };
+
+        test_circuit_config
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let wb = WasmBytecode::new(self.bytecode.to_vec().clone());
+        let assign_delta = 0;
+        layouter
+            .assign_region(
+                || format!("wasm bytecode table at {}", assign_delta),
+                |mut region| {
+                    config.wb_table.load(&mut region, &wb, assign_delta)?;
+                    Ok(())
+                },
+            )
+            .unwrap();
+        layouter.assign_region(
+            || "wasm_data_count_section_body region",
+            |mut region| {
+                // data indexes are normally registered by the data section
+                config
+                    .dynamic_indexes_chip
+                    .assign_auto(
+                        &mut region,
+                        0,
+                        assign_delta,
+                        self.data_segments_count,
+                        Tag::DataIndex,
+                    )
+                    .unwrap();
+
+                let mut offset_start = self.offset_start;
+                while offset_start < wb.bytes.len() {
+                    offset_start = config
+                        .body_chip
+                        .assign_auto(&mut region, &wb, offset_start, assign_delta)
+                        .unwrap();
+                }
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod wasm_data_count_section_body_tests {
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    use bus_mapping::state_db::CodeDB;
+    use eth_types::Field;
+
+    use crate::wasm_circuit::sections::data_count::body::tests::TestCircuit;
+
+    fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
+        let k = 8;
+        let prover = MockProver::run(k, &test_circuit, vec![]).unwrap();
+        if is_ok {
+            prover.assert_satisfied();
+        } else {
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    pub fn data_count_matches_segments_ok() {
+        let bytecode = vec![2];
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            data_segments_count: 2,
+            _marker: Default::default(),
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn data_count_mismatches_segments_fails() {
+        let bytecode = vec![3];
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            data_segments_count: 2,
+            _marker: Default::default(),
+        };
+        test(test_circuit, false);
+    }
+}
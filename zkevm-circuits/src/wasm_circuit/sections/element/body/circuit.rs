@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
@@ -15,20 +15,31 @@ use gadgets::{
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
+        },
+        consts::WASM_BLOCK_END,
+        error::{
+            checked_assign_offset, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at,
+            require_leb_params_at, Error,
         },
-        error::{remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error},
         leb128::circuit::LEB128Chip,
         sections::{
             consts::LebParams,
             element::body::{consts::ElementType, types::AssignType},
         },
-        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
+        tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
+        types::{
+            AssignDeltaType, AssignValueType, NewWbOffsetType, NumericInstruction,
+            SectionParseSummary, SharedState,
+        },
     },
 };
 
@@ -40,6 +51,7 @@ pub struct WasmElementSectionBodyConfig<F: Field> {
     pub is_items_count: Column<Fixed>,
     pub is_elem_type: Column<Fixed>,
     pub is_elem_type_ctx: Column<Fixed>,
+    pub is_table_idx: Column<Fixed>,
     pub is_numeric_instruction: Column<Fixed>,
     pub is_numeric_instruction_leb_arg: Column<Fixed>,
     pub is_block_end: Column<Fixed>,
@@ -48,10 +60,15 @@ pub struct WasmElementSectionBodyConfig<F: Field> {
     pub is_elem_kind: Column<Fixed>,
 
     pub elem_type: Column<Advice>,
+    /// index of the func idx in the element segments concatenated in declaration order (what
+    /// `Tag::TableElemFuncIndex` indexes), on the other rows the count of the func idxs before
+    pub elem_func_index: Column<Advice>,
 
     pub elem_type_chip: Rc<BinaryNumberChip<F, ElementType, 8>>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
+    pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
 
     pub func_count: Column<Advice>,
     body_item_rev_count: Column<Advice>,
@@ -107,8 +124,8 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmElementSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
-        let byte_val = wb.bytes[wb_offset];
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
             assign_offset, q_enable, assign_types, assign_value, byte_val,
@@ -126,13 +143,14 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmElementSectionBodyChip<F> {
         for assign_type in assign_types {
             if [
                 AssignType::IsItemsCount,
+                AssignType::IsTableIdx,
                 AssignType::IsNumericInstructionLebArg,
                 AssignType::IsFuncsIdxCount,
                 AssignType::IsFuncIdx,
             ]
             .contains(&assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -203,6 +221,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmElementSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::IsTableIdx => {
+                    region
+                        .assign_fixed(
+                            || {
+                                format!(
+                                    "assign 'is_table_idx' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.is_table_idx,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::IsNumericInstruction => {
                     region
                         .assign_fixed(
@@ -330,13 +363,62 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmElementSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
-                AssignType::ErrorCode => {}
+                AssignType::ElemFuncIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'elem_func_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.elem_func_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::ErrorCode => {
+                    self.assign_error_code(region, assign_offset, None)?;
+                }
             }
         }
         Ok(())
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmElementSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "element_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_elem_type", self.config.is_elem_type.into()),
+                ("is_elem_type_ctx", self.config.is_elem_type_ctx.into()),
+                ("is_table_idx", self.config.is_table_idx.into()),
+                (
+                    "is_numeric_instruction",
+                    self.config.is_numeric_instruction.into(),
+                ),
+                (
+                    "is_numeric_instruction_leb_arg",
+                    self.config.is_numeric_instruction_leb_arg.into(),
+                ),
+                ("is_block_end", self.config.is_block_end.into()),
+                ("is_funcs_idx_count", self.config.is_funcs_idx_count.into()),
+                ("is_func_idx", self.config.is_func_idx.into()),
+                ("is_elem_kind", self.config.is_elem_kind.into()),
+                ("elem_type", self.config.elem_type.into()),
+                ("elem_func_index", self.config.elem_func_index.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmElementSectionBodyChip<F> {
     pub fn construct(config: WasmElementSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -346,10 +428,49 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every func idx has a `TableElemFunc` row (first byte of the func
+    /// idx) and the last byte of the section holds the `TableElemFuncsCount` one
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_func_idx_expr = vc.query_fixed(self.config.is_func_idx, Rotation::cur());
+        let elem_func_index_expr = vc.query_advice(self.config.elem_func_index, Rotation::cur());
+        let leb128_sn_expr = vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur());
+
+        let is_func_expr = and::expr([
+            q_enable_expr.clone(),
+            is_func_idx_expr.clone(),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+        ]);
+        let is_funcs_count_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.q_last, Rotation::cur()),
+        ]);
+        let is_row_expr = is_func_expr.clone() + is_funcs_count_expr.clone();
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_func_expr.clone() * WasmMetadataTag::TableElemFunc.expr()
+                + is_funcs_count_expr.clone() * WasmMetadataTag::TableElemFuncsCount.expr(),
+            is_func_expr.clone() * elem_func_index_expr.clone(),
+            is_func_expr * leb128_sn_expr
+                + is_funcs_count_expr * (elem_func_index_expr + is_func_idx_expr),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
         leb128_chip: Rc<LEB128Chip<F>>,
+        dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
         func_count: Column<Advice>,
         shared_state: Rc<RefCell<SharedState>>,
         body_item_rev_count: Column<Advice>,
@@ -361,6 +482,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
         let is_items_count = cs.fixed_column();
         let is_elem_type = cs.fixed_column();
         let is_elem_type_ctx = cs.fixed_column();
+        let is_table_idx = cs.fixed_column();
         let is_numeric_instruction = cs.fixed_column();
         let is_numeric_instruction_leb_arg = cs.fixed_column();
         let is_block_end = cs.fixed_column();
@@ -369,6 +491,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
         let is_elem_kind = cs.fixed_column();
 
         let elem_type = cs.advice_column();
+        let elem_func_index = cs.advice_column();
         let config = BinaryNumberChip::configure(cs, is_elem_type_ctx, Some(elem_type.into()));
         let elem_type_chip = Rc::new(BinaryNumberChip::construct(config));
 
@@ -401,6 +524,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             let is_items_count_expr = vc.query_fixed(is_items_count, Rotation::cur());
             let is_elem_type_expr = vc.query_fixed(is_elem_type, Rotation::cur());
             let is_elem_type_ctx_expr = vc.query_fixed(is_elem_type_ctx, Rotation::cur());
+            let is_table_idx_expr = vc.query_fixed(is_table_idx, Rotation::cur());
             let is_numeric_instruction_expr = vc.query_fixed(is_numeric_instruction, Rotation::cur());
             let is_numeric_instruction_leb_arg_expr = vc.query_fixed(is_numeric_instruction_leb_arg, Rotation::cur());
             let is_block_end_expr = vc.query_fixed(is_block_end, Rotation::cur());
@@ -416,6 +540,11 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             let elem_type_is_1_expr = elem_type_chip.config.value_equals(ElementType::_1, Rotation::cur())(vc);
             // let elem_type_is_0_next_expr = elem_type_chip.config.value_equals(ElementType::_0, Rotation::next())(vc);
             let elem_type_is_1_next_expr = elem_type_chip.config.value_equals(ElementType::_1, Rotation::next())(vc);
+            let elem_type_is_2_expr = elem_type_chip.config.value_equals(ElementType::_2, Rotation::cur())(vc);
+            // kinds 0 and 2 are active segments with an offset expr and a func idx vector
+            let elem_type_is_active_expr = elem_type_is_0_expr.clone() + elem_type_is_2_expr.clone();
+            // kinds 1 and 2 declare an elem kind before the func idx vector
+            let elem_type_has_elem_kind_expr = elem_type_is_1_expr.clone() + elem_type_is_2_expr.clone();
 
             let leb128_sn_expr = vc.query_advice(leb128_chip.config.sn, Rotation::cur());
             let leb128_is_last_byte_expr = vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur());
@@ -424,6 +553,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             cb.require_boolean("is_items_count is boolean", is_items_count_expr.clone());
             cb.require_boolean("is_elem_type is boolean", is_elem_type_expr.clone());
             cb.require_boolean("is_elem_type_ctx is boolean", is_elem_type_ctx_expr.clone());
+            cb.require_boolean("is_table_idx is boolean", is_table_idx_expr.clone());
             cb.require_boolean("is_numeric_instruction is boolean", is_numeric_instruction_expr.clone());
             cb.require_boolean("is_numeric_instruction_leb_arg is boolean", is_numeric_instruction_leb_arg_expr.clone());
             cb.require_boolean("is_block_end is boolean", is_block_end_expr.clone());
@@ -431,6 +561,23 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             cb.require_boolean("is_func_idx is boolean", is_func_idx_expr.clone());
             cb.require_boolean("is_elem_kind is boolean", is_elem_kind_expr.clone());
 
+            // func idxs are numbered from 0 across the segments in declaration order
+            let elem_func_index_expr = vc.query_advice(elem_func_index, Rotation::cur());
+            let elem_func_index_prev_expr = vc.query_advice(elem_func_index, Rotation::prev());
+            let is_func_idx_prev_expr = vc.query_fixed(is_func_idx, Rotation::prev());
+            let leb128_is_last_byte_prev_expr = vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::prev());
+            cb.condition(is_items_count_expr.clone(), |cb| {
+                cb.require_zero("is_items_count => elem_func_index=0", elem_func_index_expr.clone());
+            });
+            cb.condition(not::expr(is_items_count_expr.clone()), |cb| {
+                cb.require_equal(
+                    "!is_items_count => elem_func_index=prev.elem_func_index+(prev.is_func_idx && prev.leb128.is_last_byte)",
+                    elem_func_index_expr.clone(),
+                    elem_func_index_prev_expr.clone()
+                        + is_func_idx_prev_expr.clone() * leb128_is_last_byte_prev_expr.clone(),
+                );
+            });
+
             configure_constraints_for_q_first_and_q_last(
                 &mut cb,
                 vc,
@@ -445,6 +592,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 "exactly one mark flag active at the same time",
                 is_items_count_expr.clone() +
                     is_elem_type_expr.clone() +
+                    is_table_idx_expr.clone() +
                     is_numeric_instruction_expr.clone() +
                     is_numeric_instruction_leb_arg_expr.clone() +
                     is_block_end_expr.clone() +
@@ -457,13 +605,14 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             cb.condition(
                 or::expr([
                     is_items_count_expr.clone(),
+                    is_table_idx_expr.clone(),
                     is_funcs_idx_count_expr.clone(),
                     is_func_idx_expr.clone(),
                     is_numeric_instruction_leb_arg_expr.clone(),
                 ]),
                 |cb| {
                     cb.require_equal(
-                        "is_items_count || is_funcs_idx_count || is_func_idx || is_numeric_instruction_leb_arg => leb128",
+                        "is_items_count || is_table_idx || is_funcs_idx_count || is_func_idx || is_numeric_instruction_leb_arg => leb128",
                         vc.query_fixed(leb128_chip.config.q_enable, Rotation::cur()),
                         1.expr(),
                     )
@@ -479,8 +628,8 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                         vec![
                             ElementType::_0.expr(),
                             ElementType::_1.expr(),
+                            ElementType::_2.expr(),
                             // TODO
-                            // ElementType::_2.expr(),
                             // ElementType::_3.expr(),
                             // ElementType::_4.expr(),
                             // ElementType::_5.expr(),
@@ -494,6 +643,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             cb.require_equal(
                 "check relation of is_elem_type_ctx with other flags",
                 is_elem_type_expr.clone()
+                    + is_table_idx_expr.clone()
                     + is_numeric_instruction_expr.clone()
                     + is_numeric_instruction_leb_arg_expr.clone()
                     + is_block_end_expr.clone()
@@ -525,9 +675,42 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 }
             );
 
+            cb.condition(
+                is_elem_kind_expr.clone(),
+                |cb| {
+                    cb.require_zero(
+                        "is_elem_kind -> byte_val is funcref elem kind",
+                        byte_val_expr.clone(),
+                    );
+                }
+            );
+
+            // the offset expr of an active segment is a single `i32.const` terminated by `end`
+            cb.condition(
+                is_numeric_instruction_expr.clone(),
+                |cb| {
+                    cb.require_equal(
+                        "is_numeric_instruction -> byte_val is i32.const",
+                        byte_val_expr.clone(),
+                        NumericInstruction::I32Const.expr(),
+                    );
+                }
+            );
+            cb.condition(
+                is_block_end_expr.clone(),
+                |cb| {
+                    cb.require_equal(
+                        "is_block_end -> byte_val = WASM_BLOCK_END",
+                        byte_val_expr.clone(),
+                        WASM_BLOCK_END.expr(),
+                    );
+                }
+            );
+
             // is_items_count+ -> elem+(is_elem_type{1} -> elem_body+)
             // elem_body+(is_elem_type{1}=0 -> is_numeric_instruction{1} -> is_numeric_instruction_leb_arg+ -> is_block_end{1} -> is_funcs_idx_count+ -> is_func_idx*)
             // elem_body+(is_elem_type{1}=1 -> is_elem_kind{1} -> is_funcs_idx_count+ -> is_func_idx*)
+            // elem_body+(is_elem_type{1}=2 -> is_table_idx+ -> is_numeric_instruction{1} -> is_numeric_instruction_leb_arg+ -> is_block_end{1} -> is_elem_kind{1} -> is_funcs_idx_count+ -> is_func_idx*)
             configure_transition_check(
                 &mut cb,
                 vc,
@@ -571,7 +754,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 and::expr([
                     not_q_last_expr.clone(),
                     is_numeric_instruction_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]),
                 true,
                 &[is_numeric_instruction_leb_arg, ],
@@ -583,7 +766,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 and::expr([
                     not_q_last_expr.clone(),
                     is_numeric_instruction_leb_arg_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]),
                 true,
                 &[is_numeric_instruction_leb_arg, is_block_end, ],
@@ -596,7 +779,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_numeric_instruction_leb_arg_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]),
                 true,
                 &[is_block_end],
@@ -620,7 +803,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 and::expr([
                     not_q_last_expr.clone(),
                     is_funcs_idx_count_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]) * leb128_sn_expr.clone(),
                 true,
                 &[is_funcs_idx_count, is_func_idx, ],
@@ -633,7 +816,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_funcs_idx_count_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]) * leb128_sn_expr.clone(),
                 true,
                 &[is_func_idx],
@@ -645,7 +828,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 and::expr([
                     not_q_last_expr.clone(),
                     is_func_idx_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]),
                 true,
                 &[is_func_idx, is_elem_type],
@@ -658,7 +841,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_func_idx_expr.clone(),
-                    elem_type_is_0_expr.clone(),
+                    elem_type_is_active_expr.clone(),
                 ]),
                 true,
                 &[is_elem_type],
@@ -683,7 +866,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 and::expr([
                     not_q_last_expr.clone(),
                     is_elem_kind_expr.clone(),
-                    elem_type_is_1_expr.clone(),
+                    elem_type_has_elem_kind_expr.clone(),
                 ]),
                 true,
                 &[is_funcs_idx_count, ],
@@ -738,6 +921,56 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 true,
                 &[is_func_idx, is_elem_type],
             );
+            // elem_body+(is_elem_type{1}=2 -> is_table_idx+ -> is_numeric_instruction{1} -> is_numeric_instruction_leb_arg+ -> is_block_end{1} -> is_elem_kind{1} -> is_funcs_idx_count+ -> is_func_idx*)
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_elem_type{1}=2 -> is_table_idx+",
+                and::expr([
+                    not_q_last_expr.clone(),
+                    is_elem_type_expr.clone(),
+                    elem_type_is_2_expr.clone(),
+                ]),
+                true,
+                &[is_table_idx, ],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_table_idx+ -> is_numeric_instruction{1}",
+                and::expr([
+                    not_q_last_expr.clone(),
+                    is_table_idx_expr.clone(),
+                    elem_type_is_2_expr.clone(),
+                ]),
+                true,
+                &[is_table_idx, is_numeric_instruction, ],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next (last leb byte): is_table_idx+ -> is_numeric_instruction{1}",
+                and::expr([
+                    not_q_last_expr.clone(),
+                    leb128_is_last_byte_expr.clone(),
+                    is_table_idx_expr.clone(),
+                    elem_type_is_2_expr.clone(),
+                ]),
+                true,
+                &[is_numeric_instruction],
+            );
+            configure_transition_check(
+                &mut cb,
+                vc,
+                "check next: is_block_end{1} -> is_elem_kind{1}",
+                and::expr([
+                    not_q_last_expr.clone(),
+                    is_block_end_expr.clone(),
+                    elem_type_is_2_expr.clone(),
+                ]),
+                true,
+                &[is_elem_kind, ],
+            );
 
             cb.gate(q_enable_expr.clone())
         });
@@ -751,6 +984,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             is_items_count,
             is_elem_type,
             is_elem_type_ctx,
+            is_table_idx,
             is_numeric_instruction,
             is_numeric_instruction_leb_arg,
             is_block_end,
@@ -758,8 +992,11 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             is_func_idx,
             is_elem_kind,
             elem_type,
+            elem_func_index,
             elem_type_chip,
+            wb_table,
             leb128_chip,
+            dynamic_indexes_chip,
             func_count,
             body_item_rev_count,
             error_code,
@@ -768,14 +1005,16 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmElementSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         // items_count+
@@ -787,6 +1026,7 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             &[AssignType::IsItemsCount],
         )?;
         let mut body_item_rev_count = items_count;
+        let mut table_elem_funcs_count = 0;
         for offset in offset..offset + items_count_leb_len {
             self.assign(
                 region,
@@ -797,6 +1037,15 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::ElemFuncIndex],
+                0,
+                None,
+            )?;
         }
         self.assign(
             region,
@@ -814,10 +1063,14 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             let item_start_offset = offset;
 
             // elem_type{1}
-            let elem_type_val = wb.bytes[offset];
-            let elem_type: ElementType = elem_type_val
-                .try_into()
-                .map_err(remap_error_to_invalid_enum_value_at(offset + assign_delta))?;
+            let elem_type_val = wb.byte_at(offset)?;
+            let elem_type: ElementType =
+                elem_type_val
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(checked_assign_offset(
+                        offset,
+                        assign_delta,
+                    )?))?;
             let elem_type_val = elem_type_val as u64;
             self.assign(
                 region,
@@ -840,7 +1093,30 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             offset += 1;
 
             match elem_type {
-                ElementType::_0 => {
+                ElementType::_0 | ElementType::_2 => {
+                    if elem_type == ElementType::_2 {
+                        // table_idx+
+                        let (_table_idx, table_idx_leb_len) = self.markup_leb_section(
+                            region,
+                            wb,
+                            offset,
+                            assign_delta,
+                            &[AssignType::IsTableIdx, AssignType::IsElemTypeCtx],
+                        )?;
+                        for offset in offset..offset + table_idx_leb_len {
+                            self.assign(
+                                region,
+                                wb,
+                                offset,
+                                assign_delta,
+                                &[AssignType::ElemType],
+                                elem_type_val,
+                                None,
+                            )?;
+                        }
+                        offset += table_idx_leb_len;
+                    }
+
                     // numeric_instruction{1}
                     self.assign(
                         region,
@@ -908,6 +1184,29 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                     )?;
                     offset += 1;
 
+                    if elem_type == ElementType::_2 {
+                        // elem_kind{1}
+                        self.assign(
+                            region,
+                            wb,
+                            offset,
+                            assign_delta,
+                            &[AssignType::IsElemKind, AssignType::IsElemTypeCtx],
+                            1,
+                            None,
+                        )?;
+                        self.assign(
+                            region,
+                            wb,
+                            offset,
+                            assign_delta,
+                            &[AssignType::ElemType],
+                            elem_type_val,
+                            None,
+                        )?;
+                        offset += 1;
+                    }
+
                     // funcs_idx_count+
                     let (funcs_idx_count, funcs_idx_count_leb_len) = self.markup_leb_section(
                         region,
@@ -928,6 +1227,17 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                         )?;
                     }
                     offset += funcs_idx_count_leb_len;
+                    for offset in item_start_offset..offset {
+                        self.assign(
+                            region,
+                            wb,
+                            offset,
+                            assign_delta,
+                            &[AssignType::ElemFuncIndex],
+                            table_elem_funcs_count,
+                            None,
+                        )?;
+                    }
 
                     for _funcs_idx_index in 0..funcs_idx_count {
                         // func_idx+
@@ -949,6 +1259,18 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                                 None,
                             )?;
                         }
+                        for offset in offset..offset + func_idx_leb_len {
+                            self.assign(
+                                region,
+                                wb,
+                                offset,
+                                assign_delta,
+                                &[AssignType::ElemFuncIndex],
+                                table_elem_funcs_count,
+                                None,
+                            )?;
+                        }
+                        table_elem_funcs_count += 1;
                         offset += func_idx_leb_len;
                     }
                 }
@@ -994,6 +1316,17 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                         )?;
                     }
                     offset += funcs_idx_count_leb_len;
+                    for offset in item_start_offset..offset {
+                        self.assign(
+                            region,
+                            wb,
+                            offset,
+                            assign_delta,
+                            &[AssignType::ElemFuncIndex],
+                            table_elem_funcs_count,
+                            None,
+                        )?;
+                    }
 
                     for _funcs_idx_index in 0..funcs_idx_count {
                         // func_idxs+
@@ -1015,12 +1348,24 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
                                 None,
                             )?;
                         }
+                        for offset in offset..offset + func_idxs_leb_len {
+                            self.assign(
+                                region,
+                                wb,
+                                offset,
+                                assign_delta,
+                                &[AssignType::ElemFuncIndex],
+                                table_elem_funcs_count,
+                                None,
+                            )?;
+                        }
+                        table_elem_funcs_count += 1;
                         offset += func_idxs_leb_len;
                     }
                 }
                 _ => {
                     return Err(Error::FatalUnsupportedTypeValue(format!(
-                        "unsupported element type '{:?}'",
+                        "unsupported element segment kind '{:?}' (kinds 0, 1, 2 are supported)",
                         elem_type
                     )))
                 }
@@ -1051,6 +1396,21 @@ impl<F: Field> WasmElementSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
+            region,
+            self.config.shared_state.borrow().dynamic_indexes_offset,
+            assign_delta,
+            table_elem_funcs_count as usize,
+            Tag::TableElemFuncIndex,
+        )?;
+        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
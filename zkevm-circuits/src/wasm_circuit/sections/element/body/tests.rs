@@ -9,8 +9,11 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::element::body::circuit::WasmElementSectionBodyChip,
+    tables::dynamic_indexes::circuit::DynamicIndexesChip,
+    test_helpers::section_body_test_circuit,
     types::SharedState,
 };
 
@@ -45,13 +48,18 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let config = DynamicIndexesChip::configure(cs, shared_state.clone());
+        let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
+
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_element_section_body_config = WasmElementSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
             body_item_rev_count,
@@ -103,6 +111,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+section_body_test_circuit!(TestCircuit);
+
 #[cfg(test)]
 mod wasm_element_section_body_tests {
     use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
@@ -115,6 +125,7 @@ mod wasm_element_section_body_tests {
     use crate::wasm_circuit::{
         common::{wat_extract_section_body_bytecode, wat_extract_section_bytecode},
         sections::element::body::tests::TestCircuit,
+        test_helpers::SectionBodyTestCircuit,
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
@@ -168,4 +179,62 @@ mod wasm_element_section_body_tests {
         };
         test(test_circuit, true);
     }
+
+    #[test]
+    pub fn kind_2_ok() {
+        // (elem (table 0) (i32.const 0) func 0 1)
+        TestCircuit::<Fr>::test_body(
+            8,
+            &[0x01, 0x02, 0x00, 0x41, 0x00, 0x0b, 0x00, 0x02, 0x00, 0x01],
+            true,
+        );
+    }
+
+    #[test]
+    pub fn kind_2_multibyte_table_idx_ok() {
+        // (elem (table 200) (i32.const 7) func 3)
+        TestCircuit::<Fr>::test_body(
+            8,
+            &[0x01, 0x02, 0xc8, 0x01, 0x41, 0x07, 0x0b, 0x00, 0x01, 0x03],
+            true,
+        );
+    }
+
+    #[test]
+    pub fn kinds_0_1_2_mixed_ok() {
+        // (elem (i32.const 0) func 0) (elem func 1) (elem (table 1) (i32.const 2) func 0 1)
+        TestCircuit::<Fr>::test_body(
+            8,
+            &[
+                0x03, 0x00, 0x41, 0x00, 0x0b, 0x01, 0x00, 0x01, 0x00, 0x01, 0x01, 0x02, 0x01, 0x41,
+                0x02, 0x0b, 0x00, 0x02, 0x00, 0x01,
+            ],
+            true,
+        );
+    }
+
+    #[test]
+    pub fn kind_0_i32_const_0_offset_ok() {
+        // (elem (i32.const 0) func 0)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x00, 0x41, 0x00, 0x0b, 0x01, 0x00], true);
+    }
+
+    #[test]
+    pub fn kind_0_i64_const_offset_fails() {
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x00, 0x42, 0x00, 0x0b, 0x01, 0x00], false);
+    }
+
+    #[test]
+    pub fn kind_0_offset_without_block_end_fails() {
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x00, 0x41, 0x00, 0x01, 0x01, 0x00], false);
+    }
+
+    #[test]
+    pub fn kind_2_invalid_elem_kind_fails() {
+        TestCircuit::<Fr>::test_body(
+            8,
+            &[0x01, 0x02, 0x00, 0x41, 0x00, 0x0b, 0x01, 0x02, 0x00, 0x01],
+            false,
+        );
+    }
 }
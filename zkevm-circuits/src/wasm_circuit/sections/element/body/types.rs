@@ -8,6 +8,7 @@ pub enum AssignType {
     IsItemsCount,
     IsElemType,
     IsElemTypeCtx,
+    IsTableIdx,
     IsNumericInstruction,
     IsNumericInstructionLebArg,
     IsBlockEnd,
@@ -16,6 +17,7 @@ pub enum AssignType {
     IsElemKind,
 
     BodyItemRevCount,
+    ElemFuncIndex,
 
     ErrorCode,
 }
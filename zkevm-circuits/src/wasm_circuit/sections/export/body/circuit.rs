@@ -1,8 +1,8 @@
-use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    circuit::{Chip, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
@@ -10,28 +10,65 @@ use log::debug;
 use eth_types::Field;
 use gadgets::{
     binary_number::BinaryNumberChip,
+    less_than::{LtChip, LtInstruction},
     util::{and, not, or, Expr},
 };
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
-            WasmMarkupLeb128SectionAwareChip, WasmNameAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check_with_id, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmLenPrefixedBytesSpanAwareChip, WasmMarkupLeb128SectionAwareChip, WasmNameAwareChip,
+            WasmSectionSummaryAwareChip, WasmSharedStateAwareChip,
         },
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
-        leb128::circuit::LEB128Chip,
-        sections::{consts::LebParams, export::body::types::AssignType},
-        types::{AssignDeltaType, AssignValueType, ExportDescType, NewWbOffsetType, SharedState},
+        leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT_32_BITS},
+        sections::{
+            consts::{LebParams, EXPORT_NAME_KEY_MAX_LEN},
+            export::body::types::AssignType,
+        },
+        tables::fixed_range::config::RangeTableConfig,
+        types::{
+            AssignDeltaType, AssignValueType, ExportDescType, NewWbOffsetType, SectionParseSummary,
+            SharedState,
+        },
+        utf8::circuit::UTF8Chip,
     },
 };
 
+/// bytes of an export name packed into its key, names ending with the same bytes share the key
+fn export_name_key_bytes(name: &[u8]) -> &[u8] {
+    &name[name.len().saturating_sub(EXPORT_NAME_KEY_MAX_LEN)..]
+}
+
+/// value of `name_key` on the exportdesc_type row of an export named `name`
+pub fn export_name_key<F: Field>(name: &[u8]) -> F {
+    export_name_key_bytes(name)
+        .iter()
+        .fold(F::zero(), |key, &byte_val| {
+            key * F::from(257) + F::from(byte_val as u64 + 1)
+        })
+        + F::one()
+}
+
+/// `export_name_key` as a u64, `None` if the name is too long for its key to fit
+pub fn export_name_key_u64(name: &[u8]) -> Option<u64> {
+    export_name_key_bytes(name)
+        .iter()
+        .try_fold(0u64, |key, &byte_val| {
+            key.checked_mul(257)?.checked_add(byte_val as u64 + 1)
+        })?
+        .checked_add(1)
+}
+
 #[derive(Debug, Clone)]
 pub struct WasmExportSectionBodyConfig<F: Field> {
     pub q_enable: Column<Fixed>,
@@ -44,10 +81,21 @@ pub struct WasmExportSectionBodyConfig<F: Field> {
     pub is_exportdesc_type_ctx: Column<Fixed>,
     pub is_exportdesc_val: Column<Fixed>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
+    pub utf8_chip: Rc<UTF8Chip<F>>,
     pub exportdesc_type: Column<Advice>,
     pub exportdesc_type_chip: Rc<BinaryNumberChip<F, ExportDescType, 8>>,
 
+    /// packs the last `EXPORT_NAME_KEY_MAX_LEN` name bytes, final value is on the
+    /// exportdesc_type row of the item
+    pub name_key: Column<Advice>,
+    /// name keys of the items in strictly increasing order, each placed on an exportdesc_type row
+    /// and carried over the rows until the next one
+    pub sorted_name_key: Column<Advice>,
+    pub name_key_byte_lt_chip: Rc<LtChip<F, 4>>,
+    pub sorted_name_key_lt_chip: Rc<LtChip<F, 31>>,
+
     pub func_count: Column<Advice>,
     body_byte_rev_index: Column<Advice>,
     body_item_rev_count: Column<Advice>,
@@ -67,7 +115,11 @@ pub struct WasmExportSectionBodyChip<F: Field> {
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmExportSectionBodyChip<F> {}
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmExportSectionBodyChip<F> {
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT_32_BITS
+    }
+}
 
 impl<F: Field> WasmCountPrefixedItemsAwareChip<F> for WasmExportSectionBodyChip<F> {}
 
@@ -107,10 +159,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmExportSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -130,11 +183,16 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmExportSectionBodyChip<F> {
             ]
             .contains(&assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
             }
+            if *assign_type == AssignType::IsExportName {
+                self.config
+                    .utf8_chip
+                    .assign(region, assign_offset, q_enable, byte_val)?;
+            }
             match assign_type {
                 AssignType::QFirst => {
                     region
@@ -305,6 +363,32 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmExportSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmExportSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "export_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_export_name_len", self.config.is_export_name_len.into()),
+                ("is_export_name", self.config.is_export_name.into()),
+                ("is_exportdesc_type", self.config.is_exportdesc_type.into()),
+                (
+                    "is_exportdesc_type_ctx",
+                    self.config.is_exportdesc_type_ctx.into(),
+                ),
+                ("is_exportdesc_val", self.config.is_exportdesc_val.into()),
+                ("exportdesc_type", self.config.exportdesc_type.into()),
+                ("name_key", self.config.name_key.into()),
+                ("sorted_name_key", self.config.sorted_name_key.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmExportSectionBodyChip<F> {
     pub fn construct(config: WasmExportSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -314,10 +398,63 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
         instance
     }
 
+    fn assign_key(
+        &self,
+        region: &mut Region<F>,
+        column: Column<Advice>,
+        wb_offset: usize,
+        assign_delta: AssignDeltaType,
+        key: F,
+    ) -> Result<(), Error> {
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        region
+            .assign_advice(
+                || format!("assign key {:?} at {}", key, assign_offset),
+                column,
+                assign_offset,
+                || Value::known(key),
+            )
+            .map_err(remap_error_to_assign_at(assign_offset))?;
+        Ok(())
+    }
+
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every function export has an `ExportFuncIndex` row (first byte of
+    /// its funcidx, the name key is on the exportdesc_type row right before it)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_exportdesc_val, Rotation::cur()),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+            self.config
+                .exportdesc_type_chip
+                .config
+                .value_equals(ExportDescType::Funcidx, Rotation::cur())(vc),
+        ]);
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_row_expr.clone() * WasmMetadataTag::ExportFuncIndex.expr(),
+            is_row_expr.clone() * vc.query_advice(self.config.name_key, Rotation::prev()),
+            is_row_expr * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
         leb128_chip: Rc<LEB128Chip<F>>,
+        utf8_chip: Rc<UTF8Chip<F>>,
+        range_table_config_0_256: &RangeTableConfig<F, 0, 256>,
         func_count: Column<Advice>,
         shared_state: Rc<RefCell<SharedState>>,
         body_byte_rev_index: Column<Advice>,
@@ -341,6 +478,92 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             BinaryNumberChip::configure(cs, is_exportdesc_type_ctx, Some(exportdesc_type.into()));
         let exportdesc_type_chip = Rc::new(BinaryNumberChip::construct(config));
 
+        let name_key = cs.advice_column();
+        let sorted_name_key = cs.advice_column();
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let is_export_name_expr = vc.query_fixed(is_export_name, Rotation::cur());
+
+                and::expr([q_enable_expr, is_export_name_expr])
+            },
+            |vc| vc.query_advice(body_byte_rev_index, Rotation::cur()),
+            |_vc| EXPORT_NAME_KEY_MAX_LEN.expr(),
+        );
+        let name_key_byte_lt_chip = Rc::new(LtChip::construct(config));
+
+        let config = LtChip::configure(
+            cs,
+            |vc| {
+                let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                    vc,
+                    q_enable,
+                    &shared_state.borrow(),
+                    error_code,
+                );
+                let is_exportdesc_type_expr = vc.query_fixed(is_exportdesc_type, Rotation::cur());
+
+                and::expr([q_enable_expr, is_exportdesc_type_expr])
+            },
+            |vc| vc.query_advice(sorted_name_key, Rotation::prev()),
+            |vc| vc.query_advice(sorted_name_key, Rotation::cur()),
+        );
+        let sorted_name_key_lt_chip = Rc::new(LtChip::construct(config));
+
+        // the lt chips leave their diff bytes unchecked
+        for (name, diff) in name_key_byte_lt_chip
+            .config()
+            .diff
+            .iter()
+            .map(|diff| ("export name key byte lt diff is a byte", diff))
+            .chain(
+                sorted_name_key_lt_chip
+                    .config()
+                    .diff
+                    .iter()
+                    .map(|diff| ("export sorted name key lt diff is a byte", diff)),
+            )
+        {
+            range_table_config_0_256.lookup(name, cs, |vc| {
+                (
+                    vc.query_fixed(q_enable, Rotation::cur()),
+                    vc.query_advice(*diff, Rotation::cur()),
+                )
+            });
+        }
+
+        // every sorted key is the name key of some item of the same bytecode, as there are as
+        // many sorted keys as items and they strictly increase, the name keys are all distinct
+        cs.lookup_any("export sorted name key is a name key", |vc| {
+            let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+                vc,
+                q_enable,
+                &shared_state.borrow(),
+                error_code,
+            );
+            let is_exportdesc_type_expr = vc.query_fixed(is_exportdesc_type, Rotation::cur());
+            let cond = and::expr([q_enable_expr, is_exportdesc_type_expr.clone()]);
+            let code_hash_expr = vc.query_advice(wb_table.code_hash, Rotation::cur());
+
+            vec![
+                (
+                    cond.clone() * code_hash_expr.clone(),
+                    is_exportdesc_type_expr.clone() * code_hash_expr,
+                ),
+                (
+                    cond * vc.query_advice(sorted_name_key, Rotation::cur()),
+                    is_exportdesc_type_expr * vc.query_advice(name_key, Rotation::cur()),
+                ),
+            ]
+        });
+
         Self::configure_len_prefixed_bytes_span_checks(
             cs,
             leb128_chip.as_ref(),
@@ -402,7 +625,7 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             let mut cb = BaseConstraintBuilder::default();
 
             let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(vc, q_enable, &shared_state.borrow(), error_code);
-            // let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
+            let q_first_expr = vc.query_fixed(q_first, Rotation::cur());
             let q_last_expr = vc.query_fixed(q_last, Rotation::cur());
             let not_q_last_expr = not::expr(q_last_expr.clone());
             let is_items_count_expr = vc.query_fixed(is_items_count, Rotation::cur());
@@ -419,7 +642,15 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             let exportdesc_type_prev_expr = vc.query_advice(exportdesc_type, Rotation::prev());
             let exportdesc_type_expr = vc.query_advice(exportdesc_type, Rotation::cur());
 
+            let is_export_name_prev_expr = vc.query_fixed(is_export_name, Rotation::prev());
+            let name_key_prev_expr = vc.query_advice(name_key, Rotation::prev());
+            let name_key_expr = vc.query_advice(name_key, Rotation::cur());
+            let sorted_name_key_prev_expr = vc.query_advice(sorted_name_key, Rotation::prev());
+            let sorted_name_key_expr = vc.query_advice(sorted_name_key, Rotation::cur());
+            let is_name_key_byte_expr = name_key_byte_lt_chip.config().is_lt(vc, None);
+
             let leb128_is_last_byte_expr = vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur());
+            let utf8_q_enable_expr = vc.query_fixed(utf8_chip.config.q_enable, Rotation::cur());
 
             cb.require_boolean("q_enable is boolean", q_enable_expr.clone());
             cb.require_boolean("is_items_count is boolean", is_items_count_expr.clone());
@@ -463,6 +694,13 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
                 }
             );
 
+            // exactly the `name_len` bytes counted by body_byte_rev_index are validated as utf8
+            cb.require_equal(
+                "is_export_name -> utf8",
+                is_export_name_expr.clone(),
+                utf8_q_enable_expr.clone(),
+            );
+
             cb.condition(
                 or::expr([
                     is_exportdesc_type_expr.clone(),
@@ -491,96 +729,148 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             );
 
             // is_items_count+ -> item+(is_export_name_len+ -> is_export_name+ -> is_exportdesc_type{1} -> is_exportdesc_val+)
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next: is_items_count+ -> item+(is_export_name_len+ ...",
+                ExportItemsCount,
                 and::expr([
                     not_q_last_expr.clone(),
                     is_items_count_expr.clone(),
                 ]),
-                true,
-                &[is_items_count, is_export_name_len],
+                next,
+                [is_items_count, is_export_name_len],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next (last leb byte): is_items_count+ -> item+(is_export_name_len+ ...",
+                ExportItemsCountLastLebByte,
                 and::expr([
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_items_count_expr.clone(),
                 ]),
-                true,
-                &[is_export_name_len],
+                next,
+                [is_export_name_len],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next: is_export_name_len+ -> is_export_name+",
+                ExportNameLen,
                 and::expr([
                     not_q_last_expr.clone(),
                     is_export_name_len_expr.clone(),
                 ]),
-                true,
-                &[is_export_name_len, is_export_name],
+                next,
+                [is_export_name_len, is_export_name],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next (last leb byte): is_export_name_len+ -> is_export_name+",
+                ExportNameLenLastLebByte,
                 and::expr([
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_export_name_len_expr.clone(),
                 ]),
-                true,
-                &[is_export_name],
+                next,
+                [is_export_name],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next: is_export_name+ -> is_exportdesc_type{1}",
+                ExportName,
                 and::expr([
                     not_q_last_expr.clone(),
                     is_export_name_expr.clone(),
                 ]),
-                true,
-                &[is_export_name, is_exportdesc_type],
+                next,
+                [is_export_name, is_exportdesc_type],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next: is_exportdesc_type{1} -> is_exportdesc_val+",
+                ExportDescType,
                 and::expr([
                     not_q_last_expr.clone(),
                     is_exportdesc_type_expr.clone(),
                 ]),
-                true,
-                &[is_exportdesc_val],
+                next,
+                [is_exportdesc_val],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next: is_exportdesc_val+",
+                ExportDescVal,
                 and::expr([
                     not_q_last_expr.clone(),
                     is_exportdesc_val_expr.clone(),
                 ]),
-                true,
-                &[is_exportdesc_val, is_export_name_len],
+                next,
+                [is_exportdesc_val, is_export_name_len],
             );
-            configure_transition_check(
+            configure_transition_check_with_id!(
                 &mut cb,
                 vc,
-                "check next (last leb byte): is_exportdesc_val+",
+                ExportDescValLastLebByte,
                 and::expr([
                     not_q_last_expr.clone(),
                     leb128_is_last_byte_expr.clone(),
                     is_exportdesc_val_expr.clone(),
                 ]),
-                true,
-                &[is_export_name_len],
+                next,
+                [is_export_name_len],
+            );
+
+            // name bytes with body_byte_rev_index < EXPORT_NAME_KEY_MAX_LEN are packed as base 257
+            // digits (byte+1), the rest is skipped
+            cb.condition(
+                is_export_name_expr.clone(),
+                |cb| {
+                    cb.require_equal(
+                        "is_export_name => name_key=prev.name_key*(is_name_key_byte ? 257 : 1)+is_name_key_byte*(byte_val+1)",
+                        name_key_expr.clone(),
+                        is_export_name_prev_expr.clone() * name_key_prev_expr.clone() * (1.expr() + is_name_key_byte_expr.clone() * 256.expr())
+                            + is_name_key_byte_expr.clone() * (byte_val_expr.clone() + 1.expr()),
+                    );
+                }
+            );
+            // +1 keeps the key of an empty name above the initial sorted_name_key
+            cb.condition(
+                is_exportdesc_type_expr.clone(),
+                |cb| {
+                    cb.require_equal(
+                        "is_exportdesc_type => name_key=prev.name_key+1",
+                        name_key_expr.clone(),
+                        is_export_name_prev_expr.clone() * name_key_prev_expr.clone() + 1.expr(),
+                    );
+                    cb.require_equal(
+                        "is_exportdesc_type => prev.sorted_name_key<sorted_name_key",
+                        sorted_name_key_lt_chip.config().is_lt(vc, None),
+                        1.expr(),
+                    );
+                }
+            );
+            cb.condition(
+                q_first_expr.clone(),
+                |cb| {
+                    cb.require_zero(
+                        "q_first => sorted_name_key=0",
+                        sorted_name_key_expr.clone(),
+                    );
+                }
+            );
+            cb.condition(
+                and::expr([
+                    not::expr(q_first_expr.clone()),
+                    not::expr(is_exportdesc_type_expr.clone()),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "!q_first && !is_exportdesc_type => sorted_name_key=prev.sorted_name_key",
+                        sorted_name_key_expr.clone(),
+                        sorted_name_key_prev_expr.clone(),
+                    );
+                }
             );
 
             cb.condition(
@@ -614,9 +904,15 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             is_exportdesc_type,
             is_exportdesc_type_ctx,
             is_exportdesc_val,
+            wb_table,
             leb128_chip,
+            utf8_chip,
             exportdesc_type,
             exportdesc_type_chip,
+            name_key,
+            sorted_name_key,
+            name_key_byte_lt_chip,
+            sorted_name_key_lt_chip,
             func_count,
             body_byte_rev_index,
             body_item_rev_count,
@@ -626,14 +922,16 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmExportSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         let (items_count, items_count_leb_len) = self.markup_leb_section(
@@ -666,6 +964,10 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
         )?;
         offset += items_count_leb_len;
 
+        // export names must be unique inside the module, checked on their keys the same way the
+        // circuit does
+        let mut export_names = HashSet::new();
+        let mut exportdesc_type_items = Vec::new();
         for _item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
@@ -693,6 +995,13 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             }
             offset += export_name_len_leb_len;
 
+            let export_name = wb
+                .bytes
+                .get(offset..offset + export_name_len as usize)
+                .ok_or(Error::UnexpectedEndOfBytes(wb.bytes.len()))?;
+            if !export_names.insert(export_name_key_bytes(export_name)) {
+                return Err(Error::DuplicateExportNameAt(offset));
+            }
             let export_name_new_offset = self.markup_name_section(
                 region,
                 wb,
@@ -702,10 +1011,27 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
                 export_name_len as usize,
                 1,
             )?;
+            let mut name_key = F::zero();
+            for (byte_index, offset) in (offset..export_name_new_offset).enumerate() {
+                let rev_index = export_name_len as usize - 1 - byte_index;
+                if rev_index < EXPORT_NAME_KEY_MAX_LEN {
+                    name_key = name_key * F::from(257) + F::from(wb.byte_at(offset)? as u64 + 1);
+                }
+                self.config
+                    .name_key_byte_lt_chip
+                    .assign(
+                        region,
+                        checked_assign_offset(offset, assign_delta)?,
+                        F::from(rev_index as u64),
+                        F::from(EXPORT_NAME_KEY_MAX_LEN as u64),
+                    )
+                    .map_err(remap_error(Error::FatalAssignExternalChip))?;
+                self.assign_key(region, self.config.name_key, offset, assign_delta, name_key)?;
+            }
             offset = export_name_new_offset;
 
-            let exportdesc_type_val = wb.bytes.as_slice()[offset];
-            let exportdesc_type: ExportDescType = wb.bytes.as_slice()[offset]
+            let exportdesc_type_val = wb.byte_at(offset)?;
+            let exportdesc_type: ExportDescType = exportdesc_type_val
                 .try_into()
                 .map_err(remap_error_to_invalid_enum_value_at(offset))?;
             self.assign(
@@ -731,8 +1057,20 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             )?;
             self.config
                 .exportdesc_type_chip
-                .assign(region, offset + assign_delta, &exportdesc_type)
+                .assign(
+                    region,
+                    checked_assign_offset(offset, assign_delta)?,
+                    &exportdesc_type,
+                )
                 .map_err(remap_error(Error::FatalAssignExternalChip))?;
+            self.assign_key(
+                region,
+                self.config.name_key,
+                offset,
+                assign_delta,
+                export_name_key(export_name),
+            )?;
+            exportdesc_type_items.push((offset, export_name));
             offset += 1;
 
             match exportdesc_type {
@@ -759,7 +1097,11 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
                         )?;
                         self.config
                             .exportdesc_type_chip
-                            .assign(region, offset + assign_delta, &exportdesc_type)
+                            .assign(
+                                region,
+                                checked_assign_offset(offset, assign_delta)?,
+                                &exportdesc_type,
+                            )
                             .map_err(remap_error(Error::FatalAssignExternalChip))?;
                     }
                     offset += exportdesc_val_leb_len;
@@ -779,6 +1121,43 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             }
         }
 
+        // keys of equal length order as their bytes, keys of shorter names are smaller
+        let mut sorted_name_key_bytes = exportdesc_type_items
+            .iter()
+            .map(|(_, export_name)| export_name_key_bytes(export_name))
+            .collect::<Vec<_>>();
+        sorted_name_key_bytes.sort_by_key(|key_bytes| (key_bytes.len(), *key_bytes));
+        let mut sorted_name_keys = exportdesc_type_items
+            .iter()
+            .map(|(offset, _)| *offset)
+            .zip(sorted_name_key_bytes)
+            .peekable();
+        let mut sorted_name_key = F::zero();
+        for offset in wb_offset..offset {
+            if let Some((_, key_bytes)) = sorted_name_keys
+                .next_if(|(exportdesc_type_offset, _)| *exportdesc_type_offset == offset)
+            {
+                let prev_sorted_name_key = sorted_name_key;
+                sorted_name_key = export_name_key(key_bytes);
+                self.config
+                    .sorted_name_key_lt_chip
+                    .assign(
+                        region,
+                        checked_assign_offset(offset, assign_delta)?,
+                        prev_sorted_name_key,
+                        sorted_name_key,
+                    )
+                    .map_err(remap_error(Error::FatalAssignExternalChip))?;
+            }
+            self.assign_key(
+                region,
+                self.config.sorted_name_key,
+                offset,
+                assign_delta,
+                sorted_name_key,
+            )?;
+        }
+
         if offset != wb_offset {
             self.assign(
                 region,
@@ -791,6 +1170,12 @@ impl<F: Field> WasmExportSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
     plonk::{Circuit, ConstraintSystem, Error},
 };
 
@@ -9,9 +9,12 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
-    sections::export::body::circuit::WasmExportSectionBodyChip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
+    sections::export::body::circuit::{export_name_key, WasmExportSectionBodyChip},
+    tables::fixed_range::config::RangeTableConfig,
     types::SharedState,
+    utf8::circuit::UTF8Chip,
 };
 
 #[derive(Default)]
@@ -19,6 +22,8 @@ struct TestCircuit<'a, F> {
     code_hash: Hash,
     bytecode: &'a [u8],
     offset_start: usize,
+    /// (offset, name) pairs overwriting `sorted_name_key` with the key of the name
+    forged_sorted_name_keys: &'a [(usize, &'a [u8])],
     _marker: PhantomData<F>,
 }
 
@@ -26,6 +31,8 @@ struct TestCircuit<'a, F> {
 struct TestCircuitConfig<F: Field> {
     body_chip: Rc<WasmExportSectionBodyChip<F>>,
     wb_table: Rc<WasmBytecodeTable>,
+    range_table_config_0_128: Rc<RangeTableConfig<F, 0, 128>>,
+    range_table_config_0_256: Rc<RangeTableConfig<F, 0, 256>>,
     _marker: PhantomData<F>,
 }
 
@@ -46,13 +53,22 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
+        let range_table_config_0_128 = Rc::new(RangeTableConfig::configure(cs));
+        let utf8_config =
+            UTF8Chip::<F>::configure(cs, range_table_config_0_128.clone(), &wb_table.value);
+        let utf8_chip = Rc::new(UTF8Chip::construct(utf8_config));
+        let range_table_config_0_256 = Rc::new(RangeTableConfig::configure(cs));
+
         let wasm_export_section_body_config = WasmExportSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            utf8_chip.clone(),
+            range_table_config_0_256.as_ref(),
             func_count,
             shared_state,
             body_byte_rev_index,
@@ -64,6 +80,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let test_circuit_config = TestCircuitConfig {
             body_chip: Rc::new(wasm_export_section_body_chip),
             wb_table: wb_table.clone(),
+            range_table_config_0_128: range_table_config_0_128.clone(),
+            range_table_config_0_256: range_table_config_0_256.clone(),
             _marker: Default::default(),
         };
 
@@ -86,6 +104,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                 },
             )
             .unwrap();
+        config.range_table_config_0_128.load(&mut layouter)?;
+        config.range_table_config_0_256.load(&mut layouter)?;
         layouter.assign_region(
             || "wasm_export_section_body region",
             |mut region| {
@@ -96,6 +116,14 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                         .assign_auto(&mut region, &wb, offset_start, assign_delta)
                         .unwrap();
                 }
+                for (offset, name) in self.forged_sorted_name_keys {
+                    region.assign_advice(
+                        || format!("forged 'sorted_name_key' at {}", offset),
+                        config.body_chip.config.sorted_name_key,
+                        *offset,
+                        || Value::known(export_name_key::<F>(name)),
+                    )?;
+                }
 
                 Ok(())
             },
@@ -119,7 +147,7 @@ mod wasm_export_section_body_tests {
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
-        let k = 8;
+        let k = 9;
         let prover = MockProver::run(k, &test_circuit, vec![]).unwrap();
         if is_ok {
             prover.assert_satisfied();
@@ -142,11 +170,108 @@ mod wasm_export_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            forged_sorted_name_keys: &[],
             _marker: Default::default(),
         };
         test(test_circuit, true);
     }
 
+    fn test_bytecode(bytecode: &[u8], is_ok: bool) {
+        let code_hash = CodeDB::hash(bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode,
+            offset_start: 0,
+            forged_sorted_name_keys: &[],
+            _marker: Default::default(),
+        };
+        test(test_circuit, is_ok);
+    }
+
+    #[test]
+    pub fn distinct_export_names_ok() {
+        // 2 exports of func 0 named "foo" and "bar"
+        test_bytecode(
+            &[
+                0x02, 0x03, b'f', b'o', b'o', 0x00, 0x00, 0x03, b'b', b'a', b'r', 0x00, 0x00,
+            ],
+            true,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "DuplicateExportNameAt(8)")]
+    pub fn duplicate_export_names_fail() {
+        // 2 exports of func 0 both named "foo"
+        test_bytecode(
+            &[
+                0x02, 0x03, b'f', b'o', b'o', 0x00, 0x00, 0x03, b'f', b'o', b'o', 0x00, 0x00,
+            ],
+            false,
+        );
+    }
+
+    #[test]
+    pub fn forged_sorted_name_keys_fail() {
+        // 2 exports of func 0 named "foo" and "bar", the sorted keys claim "bar" twice
+        let bytecode = [
+            0x02, 0x03, b'f', b'o', b'o', 0x00, 0x00, 0x03, b'b', b'a', b'r', 0x00, 0x00,
+        ];
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash: CodeDB::hash(&bytecode),
+            bytecode: &bytecode,
+            offset_start: 0,
+            forged_sorted_name_keys: &[(11, b"bar"), (12, b"bar")],
+            _marker: Default::default(),
+        };
+        test(test_circuit, false);
+    }
+
+    #[test]
+    pub fn export_names_differing_in_last_byte_ok() {
+        // 2 exports of func 0 named by 32 bytes differing in the last one
+        let mut bytecode = vec![0x02];
+        for last_byte in [b'0', b'1'] {
+            bytecode.push(32);
+            bytecode.extend_from_slice(&[b'_'; 31]);
+            bytecode.extend_from_slice(&[last_byte, 0x00, 0x00]);
+        }
+        test_bytecode(&bytecode, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "DuplicateExportNameAt(37)")]
+    pub fn export_names_sharing_key_bytes_fail() {
+        // 2 exports of func 0 named by 32 bytes differing in the first one only, the key covers
+        // the last 30 bytes
+        let mut bytecode = vec![0x02];
+        for first_byte in [b'a', b'b'] {
+            bytecode.extend_from_slice(&[32, first_byte]);
+            bytecode.extend_from_slice(&[b'_'; 31]);
+            bytecode.extend_from_slice(&[0x00, 0x00]);
+        }
+        test_bytecode(&bytecode, false);
+    }
+
+    #[test]
+    pub fn non_ascii_export_name_ok() {
+        // export of func 0 named "é"
+        test_bytecode(&[0x01, 0x02, 0xc3, 0xa9, 0x00, 0x00], true);
+    }
+
+    #[test]
+    pub fn invalid_utf8_export_name_fails() {
+        // export of func 0 named by a lone continuation byte
+        test_bytecode(&[0x01, 0x01, 0x80, 0x00, 0x00], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidEnumValueAt(4)")]
+    pub fn export_name_len_less_than_name_fails() {
+        // export "foo" declared 2 bytes long, its last byte is taken as the exportdesc type
+        test_bytecode(&[0x01, 0x02, b'f', b'o', b'o', 0x00, 0x00], false);
+    }
+
     #[test]
     pub fn file2_ok() {
         let bytecode = wat_extract_section_body_bytecode("./test_files/cc2.wat", Kind::Export);
@@ -161,6 +286,7 @@ mod wasm_export_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            forged_sorted_name_keys: &[],
             _marker: Default::default(),
         };
         test(test_circuit, true);
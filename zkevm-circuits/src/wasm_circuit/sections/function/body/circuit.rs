@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
@@ -12,17 +12,26 @@ use gadgets::util::{and, not, or, Expr};
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
         },
-        error::{remap_error_to_assign_at, Error},
-        leb128::circuit::LEB128Chip,
+        error::{checked_assign_offset, remap_error_to_assign_at, require_leb_params_at, Error},
+        leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT_32_BITS},
         sections::{consts::LebParams, function::body::types::AssignType},
-        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
+        tables::dynamic_indexes::{
+            circuit::DynamicIndexesChip,
+            types::{LookupArgsParams, Tag},
+        },
+        types::{
+            AssignDeltaType, AssignValueType, NewWbOffsetType, SectionParseSummary, SharedState,
+        },
     },
 };
 
@@ -34,6 +43,9 @@ pub struct WasmFunctionSectionBodyConfig<F: Field> {
     pub is_items_count: Column<Fixed>,
     pub is_typeidx: Column<Fixed>,
 
+    pub item_index: Column<Advice>,
+
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
 
     func_count: Column<Advice>,
@@ -54,7 +66,11 @@ pub struct WasmFunctionSectionBodyChip<F: Field> {
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmFunctionSectionBodyChip<F> {}
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmFunctionSectionBodyChip<F> {
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT_32_BITS
+    }
+}
 
 impl<F: Field> WasmCountPrefixedItemsAwareChip<F> for WasmFunctionSectionBodyChip<F> {}
 
@@ -90,10 +106,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmFunctionSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -107,7 +124,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmFunctionSectionBodyChip<F> {
 
         for assign_type in assign_types {
             if [AssignType::IsItemsCount, AssignType::IsTypeidx].contains(&assign_type) {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -183,6 +200,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmFunctionSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::ItemIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'item_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.item_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::ErrorCode => {
                     self.assign_error_code(region, assign_offset, None)?;
                 }
@@ -192,6 +224,23 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmFunctionSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmFunctionSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "function_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_typeidx", self.config.is_typeidx.into()),
+                ("item_index", self.config.item_index.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmFunctionSectionBodyChip<F> {
     pub fn construct(config: WasmFunctionSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -201,14 +250,81 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every function has a `FuncTypeIndex` row (first byte of its
+    /// typeidx), the imported functions come first
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_typeidx, Rotation::cur()),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+        ]);
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_row_expr.clone() * WasmMetadataTag::FuncTypeIndex.expr(),
+            // `func_count` holds the imported functions count here
+            is_row_expr.clone()
+                * (vc.query_advice(self.config.func_count, Rotation::cur())
+                    + vc.query_advice(self.config.item_index, Rotation::cur())),
+            is_row_expr * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+        ]
+    }
+
+    /// looks up `(code_hash, body_item_rev_count, type_index)` (enabled by `cond`) returned by `p`
+    /// in the typeidx of the items, an item is identified by its `body_item_rev_count` as the
+    /// code section has the same items count
+    pub fn lookup_type_index(
+        &self,
+        name: &'static str,
+        cs: &mut ConstraintSystem<F>,
+        p: impl FnOnce(
+            &mut VirtualCells<'_, F>,
+        ) -> (Expression<F>, Expression<F>, Expression<F>, Expression<F>),
+    ) {
+        cs.lookup_any(name, |vc| {
+            let (cond, code_hash, body_item_rev_count, type_index) = p(vc);
+            let is_typeidx_expr = vc.query_fixed(self.config.is_typeidx, Rotation::cur());
+
+            vec![
+                (
+                    cond.clone() * code_hash,
+                    is_typeidx_expr.clone()
+                        * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+                ),
+                (
+                    cond.clone() * body_item_rev_count,
+                    is_typeidx_expr.clone()
+                        * vc.query_advice(self.config.body_item_rev_count, Rotation::cur()),
+                ),
+                (
+                    cond * type_index,
+                    is_typeidx_expr
+                        * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+                ),
+            ]
+        });
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
-        _wb_table: Rc<WasmBytecodeTable>,
+        wb_table: Rc<WasmBytecodeTable>,
         leb128_chip: Rc<LEB128Chip<F>>,
+        dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
         func_count: Column<Advice>,
         shared_state: Rc<RefCell<SharedState>>,
         body_item_rev_count: Column<Advice>,
         error_code: Column<Advice>,
+        bytecode_number: Column<Advice>,
     ) -> WasmFunctionSectionBodyConfig<F> {
         let q_enable = cs.fixed_column();
         let q_first = cs.fixed_column();
@@ -216,6 +332,30 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
         let is_items_count = cs.fixed_column();
         let is_typeidx = cs.fixed_column();
 
+        let item_index = cs.advice_column();
+
+        dynamic_indexes_chip.lookup_args(
+            "function section items count equals to the number of code section func bodies",
+            cs,
+            |vc| {
+                let cond = vc.query_fixed(is_items_count, Rotation::cur());
+                let cond = cond
+                    * Self::get_selector_expr_enriched_with_error_processing(
+                        vc,
+                        q_enable,
+                        &shared_state.borrow(),
+                        error_code,
+                    );
+                LookupArgsParams {
+                    cond,
+                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
+                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
+                    tag: Tag::FuncBodyIndex.expr(),
+                    is_terminator: true.expr(),
+                }
+            },
+        );
+
         Self::configure_count_prefixed_items_checks(
             cs,
             leb128_chip.as_ref(),
@@ -263,6 +403,25 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
             cb.require_boolean("is_items_count is boolean", is_items_count_expr.clone());
             cb.require_boolean("is_typeidx is boolean", is_typeidx_expr.clone());
 
+            // functions are numbered from 0 in declaration order
+            let item_index_expr = vc.query_advice(item_index, Rotation::cur());
+            let item_index_prev_expr = vc.query_advice(item_index, Rotation::prev());
+            let is_items_count_prev_expr = vc.query_fixed(is_items_count, Rotation::prev());
+            let leb128_is_first_byte_expr =
+                vc.query_fixed(leb128_chip.config.is_first_byte, Rotation::cur());
+            cb.condition(is_items_count_expr.clone(), |cb| {
+                cb.require_zero("is_items_count => item_index=0", item_index_expr.clone());
+            });
+            cb.condition(not::expr(is_items_count_expr.clone()), |cb| {
+                cb.require_equal(
+                    "!is_items_count => item_index=prev.item_index+(leb128.is_first_byte && !prev.is_items_count)",
+                    item_index_expr.clone(),
+                    item_index_prev_expr.clone()
+                        + leb128_is_first_byte_expr.clone()
+                            * not::expr(is_items_count_prev_expr.clone()),
+                );
+            });
+
             configure_constraints_for_q_first_and_q_last(
                 &mut cb,
                 vc,
@@ -343,6 +502,8 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
             q_last,
             is_items_count,
             is_typeidx,
+            item_index,
+            wb_table,
             leb128_chip,
             func_count,
             body_item_rev_count,
@@ -352,14 +513,16 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmFunctionSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         let (items_count, items_count_leb_len) = self.markup_leb_section(
@@ -380,6 +543,15 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::ItemIndex],
+                0,
+                None,
+            )?;
         }
         self.assign(
             region,
@@ -392,11 +564,11 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
         )?;
         offset += items_count_leb_len;
 
-        for _item_index in 0..items_count {
+        for item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
 
-            let (_typeidx_val, typeidx_val_leb_len) = self.markup_leb_section(
+            let (typeidx_val, typeidx_val_leb_len) = self.markup_leb_section(
                 region,
                 wb,
                 offset,
@@ -404,6 +576,11 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
                 &[AssignType::IsTypeidx],
             )?;
             offset += typeidx_val_leb_len;
+            self.config
+                .shared_state
+                .borrow_mut()
+                .func_type_indexes
+                .push(typeidx_val as usize);
 
             for offset in item_start_offset..offset {
                 self.assign(
@@ -415,6 +592,15 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
                     body_item_rev_count,
                     None,
                 )?;
+                self.assign(
+                    region,
+                    &wb,
+                    offset,
+                    assign_delta,
+                    &[AssignType::ItemIndex],
+                    item_index,
+                    None,
+                )?;
             }
         }
 
@@ -430,6 +616,12 @@ impl<F: Field> WasmFunctionSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -9,8 +9,10 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::function::body::circuit::WasmFunctionSectionBodyChip,
+    tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
     types::SharedState,
 };
 
@@ -19,12 +21,14 @@ struct TestCircuit<'a, F> {
     code_hash: Hash,
     bytecode: &'a [u8],
     offset_start: usize,
+    func_bodies_count: usize,
     _marker: PhantomData<F>,
 }
 
 #[derive(Clone)]
 struct TestCircuitConfig<F: Field> {
     body_chip: Rc<WasmFunctionSectionBodyChip<F>>,
+    dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
     wb_table: Rc<WasmBytecodeTable>,
     _marker: PhantomData<F>,
 }
@@ -42,25 +46,33 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let func_count = cs.advice_column();
         let error_code = cs.advice_column();
         let body_item_rev_count = cs.advice_column();
+        let bytecode_number = cs.advice_column();
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let config = DynamicIndexesChip::configure(cs, shared_state.clone());
+        let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
+
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_function_section_body_config = WasmFunctionSectionBodyChip::configure(
             cs,
             wb_table.clone(),
             leb128_chip.clone(),
+            dynamic_indexes_chip.clone(),
             func_count,
             shared_state.clone(),
             body_item_rev_count,
             error_code,
+            bytecode_number,
         );
         let wasm_function_section_body_chip =
             WasmFunctionSectionBodyChip::construct(wasm_function_section_body_config);
         let test_circuit_config = TestCircuitConfig {
             body_chip: Rc::new(wasm_function_section_body_chip),
+            dynamic_indexes_chip,
             wb_table: wb_table.clone(),
             _marker: Default::default(),
         };
@@ -87,6 +99,18 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         layouter.assign_region(
             || "wasm_function_section_body region",
             |mut region| {
+                // func body indexes are normally registered by the code section
+                config
+                    .dynamic_indexes_chip
+                    .assign_auto(
+                        &mut region,
+                        0,
+                        assign_delta,
+                        self.func_bodies_count,
+                        Tag::FuncBodyIndex,
+                    )
+                    .unwrap();
+
                 let mut offset_start = self.offset_start;
                 while offset_start < wb.bytes.len() {
                     offset_start = config
@@ -113,7 +137,8 @@ mod wasm_function_section_body_tests {
     use eth_types::Field;
 
     use crate::wasm_circuit::{
-        common::wat_extract_section_body_bytecode, sections::function::body::tests::TestCircuit,
+        common::wat_extract_section_body_bytecode, leb128::helpers::leb128_compute_sn,
+        sections::function::body::tests::TestCircuit,
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
@@ -126,9 +151,13 @@ mod wasm_function_section_body_tests {
         }
     }
 
-    #[test]
-    pub fn file1_ok() {
-        let bytecode = wat_extract_section_body_bytecode("./test_files/cc1.wat", Kind::Function);
+    fn code_section_func_bodies_count(path: &str) -> usize {
+        let bytecode = wat_extract_section_body_bytecode(path, Kind::Code);
+        leb128_compute_sn(&bytecode, false, 0).unwrap().0 as usize
+    }
+
+    fn file_test(path: &str, func_bodies_count_delta: usize, is_ok: bool) {
+        let bytecode = wat_extract_section_body_bytecode(path, Kind::Function);
         debug!(
             "bytecode (len {}) hex {:x?} bin {:?}",
             bytecode.len(),
@@ -140,27 +169,39 @@ mod wasm_function_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            func_bodies_count: code_section_func_bodies_count(path) + func_bodies_count_delta,
             _marker: Default::default(),
         };
-        test(test_circuit, true);
+        test(test_circuit, is_ok);
+    }
+
+    #[test]
+    pub fn file1_ok() {
+        file_test("./test_files/cc1.wat", 0, true);
     }
 
     #[test]
     pub fn file2_ok() {
-        let bytecode = wat_extract_section_body_bytecode("./test_files/cc2.wat", Kind::Function);
-        debug!(
-            "bytecode (len {}) hex {:x?} bin {:?}",
-            bytecode.len(),
-            bytecode,
-            bytecode
-        );
+        file_test("./test_files/cc2.wat", 0, true);
+    }
+
+    #[test]
+    pub fn file1_func_bodies_count_mismatch_fails() {
+        file_test("./test_files/cc1.wat", 1, false);
+    }
+
+    #[test]
+    pub fn over_long_type_index_fails() {
+        // 1 function with type index 0 encoded with 6 bytes (max is 5 for 32-bit values)
+        let bytecode: Vec<u8> = vec![0x01, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            func_bodies_count: 1,
             _marker: Default::default(),
         };
-        test(test_circuit, true);
+        test(test_circuit, false);
     }
 }
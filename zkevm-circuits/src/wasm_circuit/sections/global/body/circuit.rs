@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -16,24 +16,28 @@ use gadgets::{
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
         },
         consts::WASM_BLOCK_END,
-        error::{remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error},
+        error::{
+            checked_assign_offset, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at,
+            require_leb_params_at, Error,
+        },
         leb128::circuit::LEB128Chip,
         sections::{consts::LebParams, global::body::types::AssignType},
-        tables::dynamic_indexes::{
-            circuit::DynamicIndexesChip,
-            types::{LookupArgsParams, Tag},
-        },
+        tables::dynamic_indexes::circuit::DynamicIndexesChip,
         types::{
-            AssignDeltaType, AssignValueType, NewWbOffsetType, NumType, NumericInstruction,
-            SharedState, NUM_TYPE_VALUES,
+            AssignDeltaType, AssignValueType, Mutability, NewWbOffsetType, NumType,
+            NumericInstruction, SectionParseSummary, SharedState, MUTABILITY_VALUES,
+            NUM_TYPE_VALUES,
         },
     },
 };
@@ -52,7 +56,11 @@ pub struct WasmGlobalSectionBodyConfig<F: Field> {
     pub is_expr_delimiter: Column<Fixed>,
 
     pub global_type: Column<Advice>,
+    /// index of the global the row belongs to (imported globals come first), the global count
+    /// before the section on the items count rows
+    pub global_index: Column<Advice>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
     pub global_type_chip: Rc<BinaryNumberChip<F, NumType, 8>>,
@@ -111,10 +119,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmGlobalSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_values {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -128,7 +137,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmGlobalSectionBodyChip<F> {
 
         for assign_type in assign_types {
             if [AssignType::IsItemsCount, AssignType::IsInitVal].contains(&assign_type) {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -301,6 +310,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmGlobalSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::GlobalIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'global_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.global_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::ErrorCode => {
                     self.assign_error_code(region, assign_offset, None)?;
                 }
@@ -310,6 +334,29 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmGlobalSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmGlobalSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "global_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_global_type", self.config.is_global_type.into()),
+                ("is_global_type_ctx", self.config.is_global_type_ctx.into()),
+                ("is_mut_prop", self.config.is_mut_prop.into()),
+                ("is_init_opcode", self.config.is_init_opcode.into()),
+                ("is_init_val", self.config.is_init_val.into()),
+                ("is_expr_delimiter", self.config.is_expr_delimiter.into()),
+                ("global_type", self.config.global_type.into()),
+                ("global_index", self.config.global_index.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmGlobalSectionBodyChip<F> {
     pub fn construct(config: WasmGlobalSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -319,6 +366,28 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every global has a `GlobalMutability` row (its mutability byte)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_mut_prop, Rotation::cur()),
+        ]);
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_row_expr.clone() * WasmMetadataTag::GlobalMutability.expr(),
+            is_row_expr.clone() * vc.query_advice(self.config.global_index, Rotation::cur()),
+            is_row_expr * vc.query_advice(self.config.wb_table.value, Rotation::cur()),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
@@ -328,7 +397,6 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
         shared_state: Rc<RefCell<SharedState>>,
         body_item_rev_count: Column<Advice>,
         error_code: Column<Advice>,
-        bytecode_number: Column<Advice>,
     ) -> WasmGlobalSectionBodyConfig<F> {
         let q_enable = cs.fixed_column();
         let q_first = cs.fixed_column();
@@ -342,31 +410,10 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
         let is_expr_delimiter = cs.fixed_column();
 
         let global_type = cs.advice_column();
+        let global_index = cs.advice_column();
         let config = BinaryNumberChip::configure(cs, is_global_type_ctx, Some(global_type.into()));
         let global_type_chip = Rc::new(BinaryNumberChip::construct(config));
 
-        dynamic_indexes_chip.lookup_args(
-            "global section has valid setup for mem indexes",
-            cs,
-            |vc| {
-                let cond = vc.query_fixed(is_items_count, Rotation::cur());
-                let cond = cond
-                    * Self::get_selector_expr_enriched_with_error_processing(
-                        vc,
-                        q_enable,
-                        &shared_state.borrow(),
-                        error_code,
-                    );
-                LookupArgsParams {
-                    cond,
-                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
-                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
-                    tag: Tag::GlobalIndex.expr(),
-                    is_terminator: true.expr(),
-                }
-            },
-        );
-
         Self::configure_count_prefixed_items_checks(
             cs,
             leb128_chip.as_ref(),
@@ -416,6 +463,19 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             cb.require_boolean("is_init_val is boolean", is_init_val_expr.clone());
             cb.require_boolean("is_expr_delimiter is boolean", is_expr_delimiter_expr.clone());
 
+            // every global starts with its type, the first one's index is set by `WasmChip`
+            let global_index_expr = vc.query_advice(global_index, Rotation::cur());
+            let global_index_prev_expr = vc.query_advice(global_index, Rotation::prev());
+            let is_items_count_prev_expr = vc.query_fixed(is_items_count, Rotation::prev());
+            cb.condition(not::expr(is_items_count_expr.clone()), |cb| {
+                cb.require_equal(
+                    "!is_items_count => global_index=prev.global_index+(is_global_type && !prev.is_items_count)",
+                    global_index_expr.clone(),
+                    global_index_prev_expr.clone()
+                        + is_global_type_expr.clone() * not::expr(is_items_count_prev_expr.clone()),
+                );
+            });
+
             configure_constraints_for_q_first_and_q_last(
                 &mut cb,
                 vc,
@@ -590,9 +650,10 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             cb.condition(
                 is_mut_prop_expr.clone(),
                 |cb| {
-                    cb.require_boolean(
-                        "is_mut_prop -> bool",
+                    cb.require_in_set(
+                        "is_mut_prop has eligible byte value",
                         byte_val_expr.clone(),
+                        MUTABILITY_VALUES.iter().map(|&v| v.expr()).collect_vec(),
                     )
                 }
             );
@@ -652,6 +713,8 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             is_init_val,
             is_expr_delimiter,
             global_type,
+            global_index,
+            wb_table,
             leb128_chip,
             dynamic_indexes_chip,
             global_type_chip,
@@ -663,14 +726,16 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmGlobalSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         let (items_count, items_count_leb_len) = self.markup_leb_section(
@@ -681,6 +746,8 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             &[AssignType::IsItemsCount],
         )?;
         let mut body_item_rev_count = items_count;
+        // the globals of the section are numbered after the imported ones
+        let first_global_index = self.config.shared_state.borrow().global_count as u64;
         for offset in offset..offset + items_count_leb_len {
             self.assign(
                 region,
@@ -691,15 +758,25 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::GlobalIndex],
+                first_global_index,
+                None,
+            )?;
+        }
+        // global indexes are registered once all the globals (imported ones included) are known
+        {
+            let mut shared_state = self.config.shared_state.borrow_mut();
+            shared_state.global_count += items_count as usize;
+            let global_count = shared_state.global_count;
+            shared_state
+                .global_count_changes
+                .insert(offset, global_count);
         }
-        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
-            region,
-            self.config.shared_state.borrow().dynamic_indexes_offset,
-            assign_delta,
-            items_count as usize,
-            Tag::GlobalIndex,
-        )?;
-        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
         self.assign(
             region,
             &wb,
@@ -711,12 +788,12 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
         )?;
         offset += items_count_leb_len;
 
-        for _item_index in 0..items_count {
+        for item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
 
             // is_global_type{1}
-            let global_type_val = wb.bytes[offset];
+            let global_type_val = wb.byte_at(offset)?;
             // let global_type: NumType =
             // global_type_val.try_into().map_err(remap_error_to_invalid_enum_value_at(offset))?;
             let global_type_val = global_type_val as u64;
@@ -741,6 +818,13 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             offset += 1;
 
             // is_mut_prop{1}
+            let _mutability: Mutability =
+                wb.byte_at(offset)?
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(checked_assign_offset(
+                        offset,
+                        assign_delta,
+                    )?))?;
             self.assign(
                 region,
                 wb,
@@ -762,6 +846,17 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             offset += 1;
 
             // is_init_opcode{1}
+            let init_opcode = if global_type_val == NumType::I64 as u64 {
+                NumericInstruction::I64Const
+            } else {
+                NumericInstruction::I32Const
+            };
+            if wb.byte_at(offset)? != init_opcode as u8 {
+                return Err(Error::InvalidByteValueAt(checked_assign_offset(
+                    offset,
+                    assign_delta,
+                )?));
+            }
             self.assign(
                 region,
                 wb,
@@ -803,7 +898,13 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             }
             offset += init_val_leb_len;
 
-            // is_expr_delimiter{1}
+            // is_expr_delimiter{1}: the init expr is a single const instruction
+            if wb.byte_at(offset)? != WASM_BLOCK_END {
+                return Err(Error::InvalidByteValueAt(checked_assign_offset(
+                    offset,
+                    assign_delta,
+                )?));
+            }
             self.assign(
                 region,
                 wb,
@@ -825,6 +926,15 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
                     body_item_rev_count,
                     None,
                 )?;
+                self.assign(
+                    region,
+                    &wb,
+                    offset,
+                    assign_delta,
+                    &[AssignType::GlobalIndex],
+                    first_global_index + item_index,
+                    None,
+                )?;
             }
         }
 
@@ -840,6 +950,12 @@ impl<F: Field> WasmGlobalSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
     plonk::{Circuit, ConstraintSystem, Error},
 };
 
@@ -9,9 +9,11 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::global::body::circuit::WasmGlobalSectionBodyChip,
     tables::dynamic_indexes::circuit::DynamicIndexesChip,
+    test_helpers::section_body_test_circuit,
     types::SharedState,
 };
 
@@ -20,6 +22,9 @@ struct TestCircuit<'a, F> {
     code_hash: Hash,
     bytecode: &'a [u8],
     offset_start: usize,
+    /// (offset, byte): the bytecode table value at the offset gets overwritten with the byte
+    /// after assignment, the chip keeps the witness of `bytecode`
+    forged_bytes: Vec<(usize, u8)>,
     _marker: PhantomData<F>,
 }
 
@@ -42,7 +47,6 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let wb_table = Rc::new(WasmBytecodeTable::construct(cs, false));
         let func_count = cs.advice_column();
         let error_code = cs.advice_column();
-        let bytecode_number = cs.advice_column();
         let body_item_rev_count = cs.advice_column();
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
@@ -50,7 +54,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_global_section_body_config = WasmGlobalSectionBodyChip::configure(
@@ -62,7 +67,6 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
             shared_state.clone(),
             body_item_rev_count,
             error_code,
-            bytecode_number,
         );
         let wasm_global_section_body_chip =
             WasmGlobalSectionBodyChip::construct(wasm_global_section_body_config);
@@ -87,6 +91,14 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                 || format!("wasm bytecode table at {}", assign_delta),
                 |mut region| {
                     config.wb_table.load(&mut region, &wb, assign_delta)?;
+                    for &(offset, byte) in &self.forged_bytes {
+                        region.assign_advice(
+                            || format!("forge bytecode table value at {}", offset),
+                            config.wb_table.value,
+                            assign_delta + offset,
+                            || Value::known(F::from(byte as u64)),
+                        )?;
+                    }
                     Ok(())
                 },
             )
@@ -110,6 +122,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+section_body_test_circuit!(TestCircuit);
+
 #[cfg(test)]
 mod wasm_global_section_body_tests {
     use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
@@ -121,8 +135,12 @@ mod wasm_global_section_body_tests {
 
     use crate::wasm_circuit::{
         common::wat_extract_section_body_bytecode, sections::global::body::tests::TestCircuit,
+        test_helpers::SectionBodyTestCircuit,
     };
 
+    /// (global i32 (i32.const 1))
+    const I32_GLOBAL_BODY: [u8; 6] = [0x01, 0x7f, 0x00, 0x41, 0x01, 0x0b];
+
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
         let k = 8;
         let prover = MockProver::run(k, &test_circuit, vec![]).unwrap();
@@ -133,6 +151,27 @@ mod wasm_global_section_body_tests {
         }
     }
 
+    /// assigns the valid `bytecode`, forges its byte at `offset` in the bytecode table and checks
+    /// that the `constraint` rejects it
+    fn test_forged_byte(bytecode: &[u8], offset: usize, byte: u8, constraint: &str) {
+        let test_circuit = TestCircuit::<Fr> {
+            forged_bytes: vec![(offset, byte)],
+            ..TestCircuit::from_body_bytecode(bytecode)
+        };
+        let failures = MockProver::run(8, &test_circuit, vec![])
+            .unwrap()
+            .verify()
+            .expect_err("the forged byte must be rejected");
+        assert!(
+            failures
+                .iter()
+                .any(|failure| format!("{:?}", failure).contains(constraint)),
+            "'{}' not in {:?}",
+            constraint,
+            failures
+        );
+    }
+
     #[test]
     pub fn file1_ok() {
         let bytecode = wat_extract_section_body_bytecode("./test_files/cc1.wat", Kind::Global);
@@ -147,6 +186,7 @@ mod wasm_global_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            forged_bytes: vec![],
             _marker: Default::default(),
         };
         test(test_circuit, true);
@@ -166,8 +206,91 @@ mod wasm_global_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            forged_bytes: vec![],
             _marker: Default::default(),
         };
         test(test_circuit, true);
     }
+
+    #[test]
+    pub fn const_and_var_globals_ok() {
+        // (global i32 (i32.const 1)) (global (mut i64) (i64.const 2))
+        TestCircuit::<Fr>::test_body(
+            8,
+            &[
+                0x02, 0x7f, 0x00, 0x41, 0x01, 0x0b, 0x7e, 0x01, 0x42, 0x02, 0x0b,
+            ],
+            true,
+        );
+    }
+
+    #[test]
+    pub fn forged_mutability_fails() {
+        test_forged_byte(
+            &I32_GLOBAL_BODY,
+            2,
+            0x02,
+            "is_mut_prop has eligible byte value",
+        );
+    }
+
+    #[test]
+    pub fn forged_init_opcode_fails() {
+        // nop
+        test_forged_byte(
+            &I32_GLOBAL_BODY,
+            3,
+            0x01,
+            "is_init_opcode has eligible byte value",
+        );
+    }
+
+    #[test]
+    pub fn forged_init_opcode_not_matching_global_type_fails() {
+        // i64.const
+        test_forged_byte(
+            &I32_GLOBAL_BODY,
+            3,
+            0x42,
+            "global_type_is_i32 => global type corresponds to init opcode",
+        );
+    }
+
+    #[test]
+    pub fn forged_init_expr_end_fails() {
+        // nop
+        test_forged_byte(
+            &I32_GLOBAL_BODY,
+            5,
+            0x01,
+            "is_expr_delimiter -> byte value = WASM_BLOCK_END",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidEnumValueAt(2)")]
+    pub fn invalid_mutability_fails() {
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x7f, 0x02, 0x41, 0x01, 0x0b], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidByteValueAt(5)")]
+    pub fn init_expr_not_terminated_by_end_fails() {
+        // (i32.const 1) followed by nop instead of end
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x7f, 0x00, 0x41, 0x01, 0x01], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidByteValueAt(5)")]
+    pub fn multi_instruction_init_expr_fails() {
+        // (i32.const 1) (i32.const 2) end
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x7f, 0x00, 0x41, 0x01, 0x41, 0x02, 0x0b], false);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidByteValueAt(3)")]
+    pub fn init_opcode_not_matching_global_type_fails() {
+        // (global i32 (i64.const 1))
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x7f, 0x00, 0x42, 0x01, 0x0b], false);
+    }
 }
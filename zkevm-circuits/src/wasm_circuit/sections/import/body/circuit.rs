@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -17,24 +17,28 @@ use gadgets::{
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            LimitTypeFields, WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip,
-            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip,
-            WasmLimitTypeAwareChip, WasmMarkupLeb128SectionAwareChip, WasmNameAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, LimitTypeFields, WasmAssignAwareChip,
+            WasmColumnAnnotationAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
+            WasmFuncCountAwareChip, WasmLenPrefixedBytesSpanAwareChip, WasmLimitTypeAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmNameAwareChip, WasmSectionSummaryAwareChip,
             WasmSharedStateAwareChip,
         },
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
-        leb128::circuit::LEB128Chip,
+        leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT_32_BITS},
         sections::{consts::LebParams, import::body::types::AssignType},
         tables::dynamic_indexes::circuit::DynamicIndexesChip,
         types::{
             AssignDeltaType, AssignValueType, ImportDescType, LimitType, NewWbOffsetType, RefType,
-            SharedState, IMPORT_DESC_TYPE_VALUES, MUTABILITY_VALUES, REF_TYPE_VALUES,
+            SectionParseSummary, SharedState, IMPORT_DESC_TYPE_VALUES, MUTABILITY_VALUES,
+            REF_TYPE_VALUES,
         },
         utf8::circuit::UTF8Chip,
     },
@@ -59,6 +63,7 @@ pub struct WasmImportSectionBodyConfig<F: Field> {
 
     pub is_ref_type: Column<Fixed>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
     pub utf8_chip: Rc<UTF8Chip<F>>,
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
@@ -66,6 +71,7 @@ pub struct WasmImportSectionBodyConfig<F: Field> {
     pub importdesc_type_chip: Rc<BinaryNumberChip<F, ImportDescType, 8>>,
 
     func_count: Column<Advice>,
+    global_count: Column<Advice>,
     body_byte_rev_index: Column<Advice>,
     body_item_rev_count: Column<Advice>,
 
@@ -84,7 +90,11 @@ pub struct WasmImportSectionBodyChip<F: Field> {
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmImportSectionBodyChip<F> {}
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmImportSectionBodyChip<F> {
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT_32_BITS
+    }
+}
 
 impl<F: Field> WasmCountPrefixedItemsAwareChip<F> for WasmImportSectionBodyChip<F> {}
 
@@ -126,10 +136,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmImportSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -151,13 +162,12 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmImportSectionBodyChip<F> {
             ]
             .contains(assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, true, p)?;
             }
             if [AssignType::IsModName, AssignType::IsImportName].contains(assign_type) {
-                let byte_val = wb.bytes[wb_offset];
                 self.config
                     .utf8_chip
                     .assign(region, assign_offset, true, byte_val)?;
@@ -478,6 +488,37 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmImportSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmImportSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "import_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_mod_name_len", self.config.is_mod_name_len.into()),
+                ("is_mod_name", self.config.is_mod_name.into()),
+                ("is_import_name_len", self.config.is_import_name_len.into()),
+                ("is_import_name", self.config.is_import_name.into()),
+                ("is_importdesc_type", self.config.is_importdesc_type.into()),
+                (
+                    "is_importdesc_type_ctx",
+                    self.config.is_importdesc_type_ctx.into(),
+                ),
+                ("is_importdesc_val", self.config.is_importdesc_val.into()),
+                ("is_mut_prop", self.config.is_mut_prop.into()),
+                ("is_ref_type", self.config.is_ref_type.into()),
+                ("importdesc_type", self.config.importdesc_type.into()),
+            ],
+        );
+        self.config
+            .limit_type_fields
+            .annotate_columns(region, "import_section");
+    }
+}
+
 impl<F: Field> WasmImportSectionBodyChip<F> {
     pub fn construct(config: WasmImportSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -487,6 +528,48 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every imported function has a `FuncTypeIndex` row (first byte of
+    /// its typeidx) and every imported global a `GlobalMutability` row (its mutability byte)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_global_row_expr = and::expr([
+            q_enable_expr.clone(),
+            vc.query_fixed(self.config.is_mut_prop, Rotation::cur()),
+        ]);
+        let is_row_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(self.config.is_importdesc_val, Rotation::cur()),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+            self.config
+                .importdesc_type_chip
+                .config
+                .value_equals(ImportDescType::Typeidx, Rotation::cur())(vc),
+        ]);
+
+        [
+            (is_row_expr.clone() + is_global_row_expr.clone())
+                * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_row_expr.clone() * WasmMetadataTag::FuncTypeIndex.expr()
+                + is_global_row_expr.clone() * WasmMetadataTag::GlobalMutability.expr(),
+            // `func_count` and `global_count` already count the import the row belongs to
+            is_row_expr.clone()
+                * (vc.query_advice(self.config.func_count, Rotation::cur()) - 1.expr())
+                + is_global_row_expr.clone()
+                    * (vc.query_advice(self.config.global_count, Rotation::cur()) - 1.expr()),
+            is_row_expr * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur())
+                + is_global_row_expr * vc.query_advice(self.config.wb_table.value, Rotation::cur()),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
@@ -494,6 +577,7 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
         utf8_chip: Rc<UTF8Chip<F>>,
         dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
         func_count: Column<Advice>,
+        global_count: Column<Advice>,
         shared_state: Rc<RefCell<SharedState>>,
         body_byte_rev_index: Column<Advice>,
         body_item_rev_count: Column<Advice>,
@@ -570,16 +654,25 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                 ])
             },
             |vc| {
+                let is_mod_name_len_expr = vc.query_fixed(is_mod_name_len, Rotation::cur());
                 let is_mod_name_expr = vc.query_fixed(is_mod_name, Rotation::cur());
+                let is_import_name_len_expr = vc.query_fixed(is_import_name_len, Rotation::cur());
                 let is_import_name_len_next_expr =
                     vc.query_fixed(is_import_name_len, Rotation::next());
                 let is_import_name_expr = vc.query_fixed(is_import_name, Rotation::cur());
                 let is_importdesc_type_next_expr =
                     vc.query_fixed(is_importdesc_type, Rotation::next());
 
+                // an empty name ends right at its len prefix, so the prefix must encode 0
                 or::expr([
-                    and::expr([is_mod_name_expr, is_import_name_len_next_expr]),
-                    and::expr([is_import_name_expr, is_importdesc_type_next_expr]),
+                    and::expr([
+                        or::expr([is_mod_name_len_expr, is_mod_name_expr]),
+                        is_import_name_len_next_expr,
+                    ]),
+                    and::expr([
+                        or::expr([is_import_name_len_expr, is_import_name_expr]),
+                        is_importdesc_type_next_expr,
+                    ]),
                 ])
             },
         );
@@ -1530,12 +1623,14 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
             is_mut_prop,
             limit_type_fields,
             is_ref_type,
+            wb_table,
             leb128_chip,
             utf8_chip,
             dynamic_indexes_chip,
             importdesc_type,
             importdesc_type_chip,
             func_count,
+            global_count,
             body_byte_rev_index,
             body_item_rev_count,
             error_code,
@@ -1544,14 +1639,16 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmImportSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         self.assign(
@@ -1661,7 +1758,7 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
             offset += import_name_len as usize;
 
             // is_importdesc_type{1}
-            let importdesc_type_val = wb.bytes[offset];
+            let importdesc_type_val = wb.byte_at(offset)?;
             let importdesc_type: ImportDescType = importdesc_type_val
                 .try_into()
                 .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -1669,6 +1766,28 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
             if importdesc_type == ImportDescType::Typeidx {
                 self.config.shared_state.borrow_mut().func_count += 1;
             }
+            if importdesc_type == ImportDescType::GlobalType {
+                let mut shared_state = self.config.shared_state.borrow_mut();
+                shared_state.global_count += 1;
+                let global_count = shared_state.global_count;
+                shared_state
+                    .global_count_changes
+                    .insert(offset, global_count);
+            }
+            if importdesc_type == ImportDescType::MemType {
+                let mut shared_state = self.config.shared_state.borrow_mut();
+                shared_state.memory_count += 1;
+                let memory_count = shared_state.memory_count;
+                shared_state
+                    .memory_count_changes
+                    .insert(offset, memory_count);
+            }
+            if importdesc_type == ImportDescType::TableType {
+                let mut shared_state = self.config.shared_state.borrow_mut();
+                shared_state.table_count += 1;
+                let table_count = shared_state.table_count;
+                shared_state.table_count_changes.insert(offset, table_count);
+            }
             self.assign(
                 region,
                 wb,
@@ -1693,7 +1812,11 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
             )?;
             self.config
                 .importdesc_type_chip
-                .assign(region, offset + assign_delta, &importdesc_type)
+                .assign(
+                    region,
+                    checked_assign_offset(offset, assign_delta)?,
+                    &importdesc_type,
+                )
                 .map_err(remap_error(Error::FatalAssignExternalChip))?;
             offset += 1;
 
@@ -1723,7 +1846,11 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                         )?;
                         self.config
                             .importdesc_type_chip
-                            .assign(region, offset + assign_delta, &importdesc_type)
+                            .assign(
+                                region,
+                                checked_assign_offset(offset, assign_delta)?,
+                                &importdesc_type,
+                            )
                             .map_err(remap_error(Error::FatalAssignExternalChip))?;
                     }
                     offset += importdesc_val_leb_len;
@@ -1752,7 +1879,11 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                         )?;
                         self.config
                             .importdesc_type_chip
-                            .assign(region, offset + assign_delta, &importdesc_type)
+                            .assign(
+                                region,
+                                checked_assign_offset(offset, assign_delta)?,
+                                &importdesc_type,
+                            )
                             .map_err(remap_error(Error::FatalAssignExternalChip))?;
                     }
                     offset += importdesc_val_leb_len;
@@ -1782,14 +1913,18 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                         )?;
                         self.config
                             .importdesc_type_chip
-                            .assign(region, offset + assign_delta, &importdesc_type)
+                            .assign(
+                                region,
+                                checked_assign_offset(offset, assign_delta)?,
+                                &importdesc_type,
+                            )
                             .map_err(remap_error(Error::FatalAssignExternalChip))?;
                     }
                     offset += 1;
                 }
                 ImportDescType::MemType => {
                     // limit_type{1}
-                    let limit_type_val = wb.bytes[offset];
+                    let limit_type_val = wb.byte_at(offset)?;
                     let limit_type: LimitType = limit_type_val
                         .try_into()
                         .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -1872,7 +2007,7 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                 }
                 ImportDescType::TableType => {
                     // ref_type{1}
-                    let ref_type_val = wb.bytes[offset];
+                    let ref_type_val = wb.byte_at(offset)?;
                     let ref_type: RefType = ref_type_val
                         .try_into()
                         .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -1889,7 +2024,7 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                     offset += 1;
 
                     // limit_type{1}
-                    let limit_type_val = wb.bytes[offset];
+                    let limit_type_val = wb.byte_at(offset)?;
                     let limit_type: LimitType = limit_type_val
                         .try_into()
                         .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -1972,7 +2107,7 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
                             .limit_type_params_lt_chip
                             .assign(
                                 region,
-                                offset + assign_delta,
+                                checked_assign_offset(offset, assign_delta)?,
                                 F::from(limit_min),
                                 F::from(limit_max),
                             )
@@ -2007,6 +2142,12 @@ impl<F: Field> WasmImportSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -1,7 +1,7 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
     plonk::{Circuit, ConstraintSystem, Error},
 };
 
@@ -9,8 +9,8 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    common::WasmSharedStateAwareChip,
-    leb128::circuit::LEB128Chip,
+    common::{WasmSectionSummaryAwareChip, WasmSharedStateAwareChip},
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::import::body::circuit::WasmImportSectionBodyChip,
     tables::{dynamic_indexes::circuit::DynamicIndexesChip, fixed_range::config::RangeTableConfig},
     types::SharedState,
@@ -22,6 +22,9 @@ struct TestCircuit<'a, F> {
     code_hash: Hash,
     bytecode: &'a [u8],
     offset_start: usize,
+    /// (offset, byte): the bytecode table value at the offset gets overwritten with the byte
+    /// after assignment, the chip keeps the witness of `bytecode`
+    forged_bytes: Vec<(usize, u8)>,
     _marker: PhantomData<F>,
 }
 
@@ -44,6 +47,7 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
         let wb_table = Rc::new(WasmBytecodeTable::construct(cs, false));
         let func_count = cs.advice_column();
+        let global_count = cs.advice_column();
         let error_code = cs.advice_column();
         let body_byte_rev_index = cs.advice_column();
         let body_item_rev_count = cs.advice_column();
@@ -55,7 +59,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let utf8_config =
@@ -69,6 +74,7 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
             utf8_chip.clone(),
             dynamic_indexes_chip.clone(),
             func_count,
+            global_count,
             shared_state.clone(),
             body_byte_rev_index,
             body_item_rev_count,
@@ -98,6 +104,14 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                 || format!("wasm bytecode table at {}", assign_delta),
                 |mut region| {
                     config.wb_table.load(&mut region, &wb, assign_delta)?;
+                    for &(offset, byte) in &self.forged_bytes {
+                        region.assign_advice(
+                            || format!("forge bytecode table value at {}", offset),
+                            config.wb_table.value,
+                            assign_delta + offset,
+                            || Value::known(F::from(byte as u64)),
+                        )?;
+                    }
                     Ok(())
                 },
             )
@@ -146,6 +160,29 @@ mod wasm_import_section_body_tests {
         }
     }
 
+    /// assigns the valid `bytecode`, forges its byte at `offset` in the bytecode table and checks
+    /// that the `constraint` rejects it
+    fn test_forged_byte(bytecode: &[u8], offset: usize, byte: u8, constraint: &str) {
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash: CodeDB::hash(bytecode),
+            bytecode,
+            forged_bytes: vec![(offset, byte)],
+            ..Default::default()
+        };
+        let failures = MockProver::run(8, &test_circuit, vec![])
+            .unwrap()
+            .verify()
+            .expect_err("the forged byte must be rejected");
+        assert!(
+            failures
+                .iter()
+                .any(|failure| format!("{:?}", failure).contains(constraint)),
+            "'{}' not in {:?}",
+            constraint,
+            failures
+        );
+    }
+
     #[test]
     pub fn file1_ok() {
         let bytecode = wat_extract_section_body_bytecode("./test_files/cc1.wat", Kind::Import);
@@ -165,6 +202,62 @@ mod wasm_import_section_body_tests {
         test(test_circuit, true);
     }
 
+    #[test]
+    #[should_panic(expected = "InvalidEnumValueAt(5)")]
+    pub fn invalid_importdesc_type_fails() {
+        // import "m" "f" with descriptor type 4 (only 0..=3 are valid)
+        let bytecode = [0x01, 0x01, b'm', 0x01, b'f', 0x04, 0x00];
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            ..Default::default()
+        };
+        test(test_circuit, false);
+    }
+
+    #[test]
+    pub fn forged_importdesc_type_fails() {
+        // import "m" "f" of a func with type 0, the descriptor type is forged to 4
+        test_forged_byte(
+            &[0x01, 0x01, b'm', 0x01, b'f', 0x00, 0x00],
+            5,
+            0x04,
+            "is_importdesc_type => value is valid",
+        );
+    }
+
+    #[test]
+    pub fn empty_names_ok() {
+        // import "" "" of a func with type 0
+        let bytecode = [0x01, 0x00, 0x00, 0x00, 0x00];
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            ..Default::default()
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidEnumValueAt(5)")]
+    pub fn import_name_len_less_than_name_fails() {
+        // import "m" "fg" with the name declared 1 byte long, so 'g' is taken as the
+        // importdesc type
+        let bytecode = [0x01, 0x01, b'm', 0x01, b'f', b'g', 0x00, 0x00];
+        let code_hash = CodeDB::hash(&bytecode);
+        let test_circuit = TestCircuit::<Fr> {
+            code_hash,
+            bytecode: &bytecode,
+            offset_start: 0,
+            ..Default::default()
+        };
+        test(test_circuit, false);
+    }
+
     #[test]
     pub fn file2_ok() {
         let bytecode = wat_extract_section_body_bytecode("./test_files/cc2.wat", Kind::Import);
@@ -179,6 +272,7 @@ mod wasm_import_section_body_tests {
             code_hash,
             bytecode: &bytecode,
             offset_start: 0,
+            forged_bytes: vec![],
             _marker: Default::default(),
         };
         test(test_circuit, true);
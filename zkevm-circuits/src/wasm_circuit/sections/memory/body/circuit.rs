@@ -2,7 +2,7 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -16,26 +16,27 @@ use gadgets::{
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            LimitTypeFields, WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip,
-            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmLimitTypeAwareChip,
-            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, LimitTypeFields, WasmAssignAwareChip,
+            WasmColumnAnnotationAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
+            WasmFuncCountAwareChip, WasmLimitTypeAwareChip, WasmMarkupLeb128SectionAwareChip,
+            WasmSectionSummaryAwareChip, WasmSharedStateAwareChip,
         },
+        consts::WASM_MEMORY_MAX_PAGES,
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
         leb128::circuit::LEB128Chip,
         sections::{consts::LebParams, memory::body::types::AssignType},
-        tables::dynamic_indexes::{
-            circuit::DynamicIndexesChip,
-            types::{LookupArgsParams, Tag},
-        },
+        tables::dynamic_indexes::circuit::DynamicIndexesChip,
         types::{
-            AssignDeltaType, AssignValueType, LimitType, NewWbOffsetType, SharedState,
-            LIMIT_TYPE_VALUES,
+            AssignDeltaType, AssignValueType, LimitType, NewWbOffsetType, SectionParseSummary,
+            SharedState, MEMORY_LIMIT_TYPE_VALUES,
         },
     },
 };
@@ -52,6 +53,10 @@ pub struct WasmMemorySectionBodyConfig<F: Field> {
     pub leb128_chip: Rc<LEB128Chip<F>>,
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
 
+    /// index of the memory in the section, memories are numbered from 0 in declaration order
+    pub item_index: Column<Advice>,
+    pub wb_table: Rc<WasmBytecodeTable>,
+
     func_count: Column<Advice>,
     body_item_rev_count: Column<Advice>,
 
@@ -108,10 +113,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmMemorySectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -131,7 +137,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmMemorySectionBodyChip<F> {
             ]
             .contains(&assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -251,8 +257,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmMemorySectionBodyChip<F> {
                             || Value::known(F::from(assign_value)),
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
-                    let limit_type: LimitType = (assign_value as u8)
-                        .try_into()
+                    let limit_type = LimitType::try_from_memory(assign_value as u8)
                         .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
                     self.config
                         .limit_type_fields
@@ -275,6 +280,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmMemorySectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::ItemIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'item_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.item_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::ErrorCode => {
                     self.assign_error_code(region, assign_offset, None)?;
                 }
@@ -284,6 +304,25 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmMemorySectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmMemorySectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "memory_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("item_index", self.config.item_index.into()),
+            ],
+        );
+        self.config
+            .limit_type_fields
+            .annotate_columns(region, "memory_section");
+    }
+}
+
 impl<F: Field> WasmMemorySectionBodyChip<F> {
     pub fn construct(config: WasmMemorySectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -293,6 +332,66 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every memory has a `MemoryMinPages` row (first byte of its min)
+    /// and a `MemoryMaxPages` one (first byte of its max or, if it declares none, its limit type)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let LimitTypeFields {
+            is_limit_type,
+            is_limit_min,
+            is_limit_max,
+            limit_type_chip,
+            ..
+        } = &self.config.limit_type_fields;
+        let leb128_is_first_byte_expr = vc.query_fixed(
+            self.config.leb128_chip.config.is_first_byte,
+            Rotation::cur(),
+        );
+        let leb128_sn_expr = vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur());
+        let limit_type_is_min_only_expr = or::expr([
+            limit_type_chip
+                .config
+                .value_equals(LimitType::MinOnly, Rotation::cur())(vc),
+            limit_type_chip
+                .config
+                .value_equals(LimitType::MinOnly64, Rotation::cur())(vc),
+        ]);
+
+        let is_min_expr = and::expr([
+            q_enable_expr.clone(),
+            vc.query_fixed(*is_limit_min, Rotation::cur()),
+            leb128_is_first_byte_expr.clone(),
+        ]);
+        let is_max_expr = and::expr([
+            q_enable_expr.clone(),
+            vc.query_fixed(*is_limit_max, Rotation::cur()),
+            leb128_is_first_byte_expr,
+        ]);
+        let is_max_undeclared_expr = and::expr([
+            q_enable_expr,
+            vc.query_fixed(*is_limit_type, Rotation::cur()),
+            limit_type_is_min_only_expr,
+        ]);
+        let is_row_expr =
+            is_min_expr.clone() + is_max_expr.clone() + is_max_undeclared_expr.clone();
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_min_expr.clone() * WasmMetadataTag::MemoryMinPages.expr()
+                + (is_max_expr.clone() + is_max_undeclared_expr.clone())
+                    * WasmMetadataTag::MemoryMaxPages.expr(),
+            is_row_expr * vc.query_advice(self.config.item_index, Rotation::cur()),
+            (is_min_expr + is_max_expr) * leb128_sn_expr
+                + is_max_undeclared_expr * WASM_MEMORY_MAX_PAGES.expr(),
+        ]
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         wb_table: Rc<WasmBytecodeTable>,
@@ -302,7 +401,6 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
         shared_state: Rc<RefCell<SharedState>>,
         body_item_rev_count: Column<Advice>,
         error_code: Column<Advice>,
-        bytecode_number: Column<Advice>,
     ) -> WasmMemorySectionBodyConfig<F> {
         let q_enable = cs.fixed_column();
         let q_first = cs.fixed_column();
@@ -310,27 +408,7 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
 
         let is_items_count = cs.fixed_column();
 
-        dynamic_indexes_chip.lookup_args(
-            "memory section has valid setup for mem indexes",
-            cs,
-            |vc| {
-                let cond = vc.query_fixed(is_items_count, Rotation::cur());
-                let cond = cond
-                    * Self::get_selector_expr_enriched_with_error_processing(
-                        vc,
-                        q_enable,
-                        &shared_state.borrow(),
-                        error_code,
-                    );
-                LookupArgsParams {
-                    cond,
-                    bytecode_number: vc.query_advice(bytecode_number, Rotation::cur()),
-                    index: vc.query_advice(leb128_chip.config.sn, Rotation::cur()),
-                    tag: Tag::MemIndex.expr(),
-                    is_terminator: true.expr(),
-                }
-            },
-        );
+        let item_index = cs.advice_column();
 
         let limit_type_fields =
             Self::construct_limit_type_fields(cs, q_enable, leb128_chip.as_ref());
@@ -386,6 +464,9 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             let is_limit_type_expr = vc.query_fixed(is_limit_type, Rotation::cur());
             let is_limit_min_expr = vc.query_fixed(is_limit_min, Rotation::cur());
             let is_limit_max_expr = vc.query_fixed(is_limit_max, Rotation::cur());
+            let is_items_count_prev_expr = vc.query_fixed(is_items_count, Rotation::prev());
+            let item_index_expr = vc.query_advice(item_index, Rotation::cur());
+            let item_index_prev_expr = vc.query_advice(item_index, Rotation::prev());
 
             // let is_limit_type_ctx_expr = vc.query_fixed(is_limit_type_ctx, Rotation::cur());
 
@@ -393,14 +474,22 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             // let limit_type_prev_expr = vc.query_advice(limit_type, Rotation::prev());
             // let limit_type_expr = vc.query_advice(limit_type, Rotation::cur());
 
-            let limit_type_is_min_only_expr =
+            let limit_type_is_min_only_expr = or::expr([
                 limit_type_chip
                     .config
-                    .value_equals(LimitType::MinOnly, Rotation::cur())(vc);
-            let limit_type_is_min_max_expr =
+                    .value_equals(LimitType::MinOnly, Rotation::cur())(vc),
                 limit_type_chip
                     .config
-                    .value_equals(LimitType::MinMax, Rotation::cur())(vc);
+                    .value_equals(LimitType::MinOnly64, Rotation::cur())(vc),
+            ]);
+            let limit_type_is_min_max_expr = or::expr([
+                limit_type_chip
+                    .config
+                    .value_equals(LimitType::MinMax, Rotation::cur())(vc),
+                limit_type_chip
+                    .config
+                    .value_equals(LimitType::MinMax64, Rotation::cur())(vc),
+            ]);
 
             let leb128_is_last_byte_expr =
                 vc.query_fixed(leb128_chip.config.is_last_byte, Rotation::cur());
@@ -408,6 +497,19 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             cb.require_boolean("q_enable is boolean", q_enable_expr.clone());
             cb.require_boolean("is_items_count is boolean", is_items_count_expr.clone());
 
+            // memories are numbered from 0 in declaration order
+            cb.condition(is_items_count_expr.clone(), |cb| {
+                cb.require_zero("is_items_count => item_index=0", item_index_expr.clone());
+            });
+            cb.condition(not::expr(is_items_count_expr.clone()), |cb| {
+                cb.require_equal(
+                    "!is_items_count => item_index=prev.item_index+(is_limit_type && !prev.is_items_count)",
+                    item_index_expr.clone(),
+                    item_index_prev_expr.clone()
+                        + is_limit_type_expr.clone() * not::expr(is_items_count_prev_expr.clone()),
+                );
+            });
+
             configure_constraints_for_q_first_and_q_last(
                 &mut cb,
                 vc,
@@ -561,7 +663,10 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
                 cb.require_in_set(
                     "is_limit_type -> byte_val is valid",
                     byte_val_expr.clone(),
-                    LIMIT_TYPE_VALUES.iter().map(|&v| v.expr()).collect_vec(),
+                    MEMORY_LIMIT_TYPE_VALUES
+                        .iter()
+                        .map(|&v| v.expr())
+                        .collect_vec(),
                 );
             });
 
@@ -575,6 +680,8 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             q_first,
             q_last,
             is_items_count,
+            item_index,
+            wb_table,
             limit_type_fields,
             leb128_chip,
             dynamic_indexes_chip,
@@ -586,14 +693,16 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmMemorySectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         let (items_count, items_count_leb_len) = self.markup_leb_section(
@@ -614,15 +723,25 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::ItemIndex],
+                0,
+                None,
+            )?;
+        }
+        // mem indexes are registered once all the memories (imported ones included) are known
+        {
+            let mut shared_state = self.config.shared_state.borrow_mut();
+            shared_state.memory_count += items_count as usize;
+            let memory_count = shared_state.memory_count;
+            shared_state
+                .memory_count_changes
+                .insert(offset, memory_count);
         }
-        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
-            region,
-            self.config.shared_state.borrow().dynamic_indexes_offset,
-            assign_delta,
-            items_count as usize,
-            Tag::MemIndex,
-        )?;
-        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
         self.assign(
             region,
             &wb,
@@ -634,16 +753,16 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
         )?;
         offset += items_count_leb_len;
 
-        for _item_index in 0..items_count {
+        for item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
 
             // limit_type{1}
-            let limit_type_val = wb.bytes[offset];
-            let limit_type: LimitType = limit_type_val
-                .try_into()
+            let limit_type_val = wb.byte_at(offset)?;
+            let limit_type = LimitType::try_from_memory(limit_type_val)
                 .map_err(remap_error_to_invalid_enum_value_at(offset))?;
             let limit_type_val = limit_type_val as u64;
+            self.config.shared_state.borrow_mut().is_memory64 = limit_type.is_memory64();
             self.assign(
                 region,
                 wb,
@@ -686,7 +805,7 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             offset += limit_min_leb_len;
 
             // limit_max*
-            if limit_type == LimitType::MinMax {
+            if limit_type.has_max() {
                 let (limit_max, limit_max_leb_len) = self.markup_leb_section(
                     region,
                     wb,
@@ -710,7 +829,7 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
                     .limit_type_params_lt_chip
                     .assign(
                         region,
-                        offset + assign_delta,
+                        checked_assign_offset(offset, assign_delta)?,
                         F::from(limit_min),
                         F::from(limit_max),
                     )
@@ -728,6 +847,15 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
                     body_item_rev_count,
                     None,
                 )?;
+                self.assign(
+                    region,
+                    &wb,
+                    offset,
+                    assign_delta,
+                    &[AssignType::ItemIndex],
+                    item_index,
+                    None,
+                )?;
             }
         }
 
@@ -743,6 +871,12 @@ impl<F: Field> WasmMemorySectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -9,9 +9,11 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::{WasmSectionSummaryAwareChip, WasmSharedStateAwareChip},
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::memory::body::circuit::WasmMemorySectionBodyChip,
-    tables::dynamic_indexes::circuit::DynamicIndexesChip,
+    tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
+    test_helpers::section_body_test_circuit,
     types::SharedState,
 };
 
@@ -42,7 +44,6 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let wb_table = Rc::new(WasmBytecodeTable::construct(cs, false));
         let func_count = cs.advice_column();
         let error_code = cs.advice_column();
-        let bytecode_number = cs.advice_column();
         let body_item_rev_count = cs.advice_column();
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
@@ -50,7 +51,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_memory_section_body_config = WasmMemorySectionBodyChip::configure(
@@ -62,7 +64,6 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
             shared_state.clone(),
             body_item_rev_count,
             error_code,
-            bytecode_number,
         );
         let wasm_memory_section_body_chip =
             WasmMemorySectionBodyChip::construct(wasm_memory_section_body_config);
@@ -101,6 +102,22 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
                         .assign_auto(&mut region, &wb, offset_start, assign_delta)
                         .unwrap();
                 }
+                // `WasmChip` registers the mem indexes once the whole bytecode is assigned
+                let shared_state = config.body_chip.shared_state();
+                let memory_count = shared_state.borrow().memory_count;
+                let dynamic_indexes_offset = shared_state.borrow().dynamic_indexes_offset;
+                config
+                    .body_chip
+                    .config
+                    .dynamic_indexes_chip
+                    .assign_auto(
+                        &mut region,
+                        dynamic_indexes_offset,
+                        assign_delta,
+                        memory_count,
+                        Tag::MemIndex,
+                    )
+                    .unwrap();
 
                 Ok(())
             },
@@ -110,6 +127,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+section_body_test_circuit!(TestCircuit);
+
 #[cfg(test)]
 mod wasm_memory_section_body_tests {
     use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
@@ -121,6 +140,7 @@ mod wasm_memory_section_body_tests {
 
     use crate::wasm_circuit::{
         common::wat_extract_section_body_bytecode, sections::memory::body::tests::TestCircuit,
+        test_helpers::SectionBodyTestCircuit,
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
@@ -201,4 +221,34 @@ mod wasm_memory_section_body_tests {
         };
         test(test_circuit, true);
     }
+
+    #[test]
+    pub fn limits_min_only_ok() {
+        // (memory 5)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x00, 0x05], true);
+    }
+
+    #[test]
+    pub fn limits_min_max_ok() {
+        // (memory 3 5)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0x03, 0x05], true);
+        // (memory 1 200), max takes 2 leb bytes
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0x01, 0xc8, 0x01], true);
+    }
+
+    #[test]
+    pub fn limits_min_equals_max_ok() {
+        // (memory 5 5)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0x05, 0x05], true);
+        // (memory 200 200)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0xc8, 0x01, 0xc8, 0x01], true);
+    }
+
+    #[test]
+    pub fn limits_min_greater_than_max_fails() {
+        // (memory 5 3)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0x05, 0x03], false);
+        // (memory 200 199)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x01, 0xc8, 0x01, 0xc7, 0x01], false);
+    }
 }
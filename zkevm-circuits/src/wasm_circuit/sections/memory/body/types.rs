@@ -12,6 +12,7 @@ pub enum AssignType {
 
     IsLimitTypeCtx,
     BodyItemRevCount,
+    ItemIndex,
 
     ErrorCode,
 }
@@ -15,12 +15,13 @@ use crate::{
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
-            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmErrorAwareChip, WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip,
+            WasmSharedStateAwareChip,
         },
-        error::{remap_error_to_assign_at, Error},
-        leb128::circuit::LEB128Chip,
+        error::{checked_assign_offset, remap_error_to_assign_at, require_leb_params_at, Error},
+        leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT_32_BITS},
         sections::{consts::LebParams, start::body::types::AssignType},
         types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
     },
@@ -53,7 +54,11 @@ pub struct WasmStartSectionBodyChip<F: Field> {
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmStartSectionBodyChip<F> {}
+impl<F: Field> WasmMarkupLeb128SectionAwareChip<F> for WasmStartSectionBodyChip<F> {
+    fn leb128_max_bytes(&self) -> usize {
+        LEB128_MAX_BYTES_COUNT_32_BITS
+    }
+}
 
 impl<F: Field> WasmErrorAwareChip<F> for WasmStartSectionBodyChip<F> {
     fn error_code_col(&self) -> Column<Advice> {
@@ -87,10 +92,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmStartSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -104,7 +110,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmStartSectionBodyChip<F> {
 
         for assign_type in assign_types {
             if *assign_type == AssignType::IsFuncsIndex {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -159,6 +165,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmStartSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmStartSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "start_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_func_index", self.config.is_func_index.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmStartSectionBodyChip<F> {
     pub fn construct(config: WasmStartSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -9,7 +9,7 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::start::body::circuit::WasmStartSectionBodyChip,
     types::SharedState,
 };
@@ -44,7 +44,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
 
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let config = WasmStartSectionBodyChip::configure(
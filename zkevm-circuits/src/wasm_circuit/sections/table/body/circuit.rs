@@ -19,19 +19,22 @@ use crate::{
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            LimitTypeFields, WasmAssignAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
-            WasmLimitTypeAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, LimitTypeFields, WasmAssignAwareChip,
+            WasmColumnAnnotationAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmLimitTypeAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
         },
         error::{
-            remap_error, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error,
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, require_leb_params_at, Error,
         },
         leb128::circuit::LEB128Chip,
         sections::{consts::LebParams, table::body::types::AssignType},
-        tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
+        tables::dynamic_indexes::circuit::DynamicIndexesChip,
         types::{
-            AssignDeltaType, AssignValueType, LimitType, NewWbOffsetType, SharedState,
-            REF_TYPE_VALUES,
+            AssignDeltaType, AssignValueType, LimitType, NewWbOffsetType, RefType,
+            SectionParseSummary, SharedState, REF_TYPE_VALUES,
         },
     },
 };
@@ -100,10 +103,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTableSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -123,7 +127,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTableSectionBodyChip<F> {
             ]
             .contains(assign_type)
             {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, q_enable, p)?;
@@ -276,6 +280,28 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTableSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmTableSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "table_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                (
+                    "is_reference_type_count",
+                    self.config.is_reference_type_count.into(),
+                ),
+                ("is_reference_type", self.config.is_reference_type.into()),
+            ],
+        );
+        self.config
+            .limit_type_fields
+            .annotate_columns(region, "table_section");
+    }
+}
+
 impl<F: Field> WasmTableSectionBodyChip<F> {
     pub fn construct(config: WasmTableSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -582,18 +608,20 @@ impl<F: Field> WasmTableSectionBodyChip<F> {
 
         config
     }
+}
 
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmTableSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
 
         // reference_type_count+
-        let (_reference_type_count, reference_type_count_leb_len) = self.markup_leb_section(
+        let (reference_type_count, reference_type_count_leb_len) = self.markup_leb_section(
             region,
             wb,
             offset,
@@ -612,6 +640,10 @@ impl<F: Field> WasmTableSectionBodyChip<F> {
         offset += reference_type_count_leb_len;
 
         // reference_type{1}
+        let _reference_type: RefType = wb
+            .byte_at(offset)?
+            .try_into()
+            .map_err(remap_error_to_invalid_enum_value_at(offset))?;
         self.assign(
             region,
             wb,
@@ -621,18 +653,17 @@ impl<F: Field> WasmTableSectionBodyChip<F> {
             1,
             None,
         )?;
-        let dynamic_indexes_offset = self.config.dynamic_indexes_chip.assign_auto(
-            region,
-            self.config.shared_state.borrow().dynamic_indexes_offset,
-            assign_delta,
-            1,
-            Tag::TableIndex,
-        )?;
-        self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
+        // table indexes are registered once all the tables (imported ones included) are known
+        {
+            let mut shared_state = self.config.shared_state.borrow_mut();
+            shared_state.table_count += 1;
+            let table_count = shared_state.table_count;
+            shared_state.table_count_changes.insert(offset, table_count);
+        }
         offset += 1;
 
         // limit_type{1}
-        let limit_type_val = wb.bytes[offset];
+        let limit_type_val = wb.byte_at(offset)?;
         let limit_type: LimitType = limit_type_val
             .try_into()
             .map_err(remap_error_to_invalid_enum_value_at(offset))?;
@@ -703,7 +734,7 @@ impl<F: Field> WasmTableSectionBodyChip<F> {
                 .limit_type_params_lt_chip
                 .assign(
                     region,
-                    offset + assign_delta,
+                    checked_assign_offset(offset, assign_delta)?,
                     F::from(limit_min),
                     F::from(limit_max),
                 )
@@ -723,6 +754,12 @@ impl<F: Field> WasmTableSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count: reference_type_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -9,9 +9,11 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::table::body::circuit::WasmTableSectionBodyChip,
     tables::dynamic_indexes::circuit::DynamicIndexesChip,
+    test_helpers::section_body_test_circuit,
     types::SharedState,
 };
 
@@ -48,7 +50,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
 
         let wasm_table_section_body_config = WasmTableSectionBodyChip::configure(
@@ -108,6 +111,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+section_body_test_circuit!(TestCircuit);
+
 #[cfg(test)]
 mod wasm_table_section_body_tests {
     use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
@@ -119,6 +124,7 @@ mod wasm_table_section_body_tests {
 
     use crate::wasm_circuit::{
         common::wat_extract_section_body_bytecode, sections::table::body::tests::TestCircuit,
+        test_helpers::SectionBodyTestCircuit,
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool) {
@@ -172,4 +178,28 @@ mod wasm_table_section_body_tests {
         };
         test(test_circuit, true);
     }
+
+    #[test]
+    pub fn funcref_min_only_ok() {
+        // (table 2 funcref)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x70, 0x00, 0x02], true);
+    }
+
+    #[test]
+    pub fn externref_min_max_ok() {
+        // (table 2 10 externref)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x6f, 0x01, 0x02, 0x0a], true);
+    }
+
+    #[test]
+    pub fn limits_min_equals_max_ok() {
+        // (table 3 3 funcref)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x70, 0x01, 0x03, 0x03], true);
+    }
+
+    #[test]
+    pub fn limits_min_greater_than_max_fails() {
+        // (table 5 3 funcref)
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x70, 0x01, 0x05, 0x03], false);
+    }
 }
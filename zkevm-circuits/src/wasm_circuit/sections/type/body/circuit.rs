@@ -2,32 +2,37 @@ use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
     circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
 use log::debug;
 
 use eth_types::Field;
-use gadgets::util::{and, not};
+use gadgets::util::{and, not, or, Expr};
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
+    table::WasmMetadataTag,
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSectionSummaryAwareChip,
+            WasmSharedStateAwareChip,
         },
-        error::{remap_error_to_assign_at, Error},
-        leb128::circuit::LEB128Chip,
+        error::{checked_assign_offset, remap_error_to_assign_at, require_leb_params_at, Error},
+        leb128::{circuit::LEB128Chip, helpers::leb128_compute_sn},
         sections::{
             consts::LebParams,
             r#type::{body::types::AssignType, item::circuit::WasmTypeSectionItemChip},
         },
         tables::dynamic_indexes::{circuit::DynamicIndexesChip, types::Tag},
-        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
+        types::{
+            AssignDeltaType, AssignValueType, NewWbOffsetType, SectionParseSummary, SharedState,
+        },
     },
 };
 
@@ -42,7 +47,10 @@ pub struct WasmTypeSectionBodyConfig<F> {
     func_count: Column<Advice>,
     error_code: Column<Advice>,
     body_item_rev_count: Column<Advice>,
+    /// type index of the item the row belongs to
+    pub item_index: Column<Advice>,
 
+    pub wb_table: Rc<WasmBytecodeTable>,
     pub section_item_chip: Rc<WasmTypeSectionItemChip<F>>,
     pub leb128_chip: Rc<LEB128Chip<F>>,
     pub dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
@@ -96,10 +104,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionBodyChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -113,7 +122,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionBodyChip<F> {
 
         for assign_type in assign_types {
             if [AssignType::IsBodyItemsCount].contains(&assign_type) {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, true, p)?;
@@ -190,6 +199,21 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionBodyChip<F> {
                         )
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::ItemIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'item_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.item_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
                 AssignType::ErrorCode => {
                     self.assign_error_code(region, assign_offset, None)?;
                 }
@@ -199,6 +223,24 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionBodyChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmTypeSectionBodyChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "type_section",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_items_count", self.config.is_items_count.into()),
+                ("is_body", self.config.is_body.into()),
+                ("item_index", self.config.item_index.into()),
+            ],
+        );
+        self.config.section_item_chip.annotate_columns(region);
+    }
+}
+
 impl<F: Field> WasmTypeSectionBodyChip<F> {
     pub fn construct(config: WasmTypeSectionBodyConfig<F>) -> Self {
         let instance = Self {
@@ -208,9 +250,83 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
         instance
     }
 
+    /// (code_hash, tag, index, value) of the wasm metadata table rows the section describes,
+    /// zero on the other rows: every type has a `TypeParamsCount` and a `TypeResultsCount` row
+    /// (first byte of the counts)
+    pub fn metadata_table_exprs(&self, vc: &mut VirtualCells<'_, F>) -> [Expression<F>; 4] {
+        let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
+            vc,
+            self.config.q_enable,
+            &self.config.shared_state.borrow(),
+            self.config.error_code,
+        );
+        let is_input_count_expr = vc.query_fixed(
+            self.config.section_item_chip.config.is_input_count,
+            Rotation::cur(),
+        );
+        let is_output_count_expr = vc.query_fixed(
+            self.config.section_item_chip.config.is_output_count,
+            Rotation::cur(),
+        );
+        let is_row_expr = and::expr([
+            q_enable_expr,
+            or::expr([is_input_count_expr.clone(), is_output_count_expr.clone()]),
+            vc.query_fixed(
+                self.config.leb128_chip.config.is_first_byte,
+                Rotation::cur(),
+            ),
+        ]);
+
+        [
+            is_row_expr.clone() * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+            is_row_expr.clone()
+                * (is_input_count_expr * WasmMetadataTag::TypeParamsCount.expr()
+                    + is_output_count_expr * WasmMetadataTag::TypeResultsCount.expr()),
+            is_row_expr.clone() * vc.query_advice(self.config.item_index, Rotation::cur()),
+            is_row_expr * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+        ]
+    }
+
+    /// looks up `(code_hash, type_index, params_count)` (enabled by `cond`) returned by `p` in the
+    /// params counts of the items
+    pub fn lookup_params_count(
+        &self,
+        name: &'static str,
+        cs: &mut ConstraintSystem<F>,
+        p: impl FnOnce(
+            &mut VirtualCells<'_, F>,
+        ) -> (Expression<F>, Expression<F>, Expression<F>, Expression<F>),
+    ) {
+        cs.lookup_any(name, |vc| {
+            let (cond, code_hash, type_index, params_count) = p(vc);
+            let is_input_count_expr = vc.query_fixed(
+                self.config.section_item_chip.config.is_input_count,
+                Rotation::cur(),
+            );
+
+            vec![
+                (
+                    cond.clone() * code_hash,
+                    is_input_count_expr.clone()
+                        * vc.query_advice(self.config.wb_table.code_hash, Rotation::cur()),
+                ),
+                (
+                    cond.clone() * type_index,
+                    is_input_count_expr.clone()
+                        * vc.query_advice(self.config.item_index, Rotation::cur()),
+                ),
+                (
+                    cond * params_count,
+                    is_input_count_expr
+                        * vc.query_advice(self.config.leb128_chip.config.sn, Rotation::cur()),
+                ),
+            ]
+        });
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
-        _wb_table: Rc<WasmBytecodeTable>,
+        wb_table: Rc<WasmBytecodeTable>,
         leb128_chip: Rc<LEB128Chip<F>>,
         section_item_chip: Rc<WasmTypeSectionItemChip<F>>,
         dynamic_indexes_chip: Rc<DynamicIndexesChip<F>>,
@@ -225,6 +341,8 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
         let is_items_count = cs.fixed_column();
         let is_body = cs.fixed_column();
 
+        let item_index = cs.advice_column();
+
         Self::configure_count_prefixed_items_checks(
             cs,
             leb128_chip.as_ref(),
@@ -259,6 +377,12 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
             let not_q_last_expr = not::expr(q_last_expr.clone());
             let is_items_count_expr = vc.query_fixed(is_items_count, Rotation::cur());
             let is_body_expr = vc.query_fixed(is_body, Rotation::cur());
+            let is_items_count_prev_expr = vc.query_fixed(is_items_count, Rotation::prev());
+            let is_item_start_expr =
+                vc.query_fixed(section_item_chip.config.q_first, Rotation::cur());
+
+            let item_index_prev_expr = vc.query_advice(item_index, Rotation::prev());
+            let item_index_expr = vc.query_advice(item_index, Rotation::cur());
 
             // let byte_value_expr = vc.query_advice(bytecode_table.value, Rotation::cur());
 
@@ -291,6 +415,19 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
                 vc.query_fixed(section_item_chip.config.q_enable, Rotation::cur()),
             );
 
+            // items are numbered from 0 in declaration order
+            cb.condition(is_items_count_expr.clone(), |cb| {
+                cb.require_zero("is_items_count => item_index=0", item_index_expr.clone());
+            });
+            cb.condition(is_body_expr.clone(), |cb| {
+                cb.require_equal(
+                    "is_body => item_index=prev.item_index+(is_item_start && !prev.is_items_count)",
+                    item_index_expr.clone(),
+                    item_index_prev_expr.clone()
+                        + is_item_start_expr.clone() * not::expr(is_items_count_prev_expr.clone()),
+                );
+            });
+
             configure_transition_check(
                 &mut cb,
                 vc,
@@ -331,6 +468,8 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
             q_last,
             is_items_count,
             is_body,
+            item_index,
+            wb_table,
             leb128_chip,
             section_item_chip,
             dynamic_indexes_chip,
@@ -342,15 +481,16 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
 
         config
     }
+}
 
-    /// updates `shared_state.dynamic_indexes_offset` to a new offset
-    pub fn assign_auto(
+impl<F: Field> WasmSectionSummaryAwareChip<F> for WasmTypeSectionBodyChip<F> {
+    fn assign_auto_with_summary(
         &self,
         region: &mut Region<F>,
         wb: &WasmBytecode,
         wb_offset: usize,
         assign_delta: AssignDeltaType,
-    ) -> Result<NewWbOffsetType, Error> {
+    ) -> Result<(NewWbOffsetType, SectionParseSummary), Error> {
         let mut offset = wb_offset;
         self.assign(
             region,
@@ -379,6 +519,15 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
                 body_item_rev_count,
                 None,
             )?;
+            self.assign(
+                region,
+                &wb,
+                offset,
+                assign_delta,
+                &[AssignType::ItemIndex],
+                0,
+                None,
+            )?;
         }
         offset += items_count_leb_len;
 
@@ -391,7 +540,7 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
         )?;
         self.config.shared_state.borrow_mut().dynamic_indexes_offset = dynamic_indexes_offset;
 
-        for _body_item_index in 0..items_count {
+        for body_item_index in 0..items_count {
             body_item_rev_count -= 1;
             let item_start_offset = offset;
 
@@ -401,6 +550,13 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
                 item_start_offset,
                 assign_delta,
             )?;
+            // item starts with the func type prefix followed by the params count
+            let (params_count, _) = leb128_compute_sn(&wb.bytes, false, item_start_offset + 1)?;
+            self.config
+                .shared_state
+                .borrow_mut()
+                .type_params_counts
+                .push(params_count as usize);
             for offset in item_start_offset..next_body_item_offset {
                 self.assign(
                     region,
@@ -411,6 +567,15 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
                     1,
                     None,
                 )?;
+                self.assign(
+                    region,
+                    wb,
+                    offset,
+                    assign_delta,
+                    &[AssignType::ItemIndex],
+                    body_item_index,
+                    None,
+                )?;
             }
 
             for offset in item_start_offset..next_body_item_offset {
@@ -439,6 +604,12 @@ impl<F: Field> WasmTypeSectionBodyChip<F> {
             )?;
         }
 
-        Ok(offset)
+        Ok((
+            offset,
+            SectionParseSummary {
+                items_count,
+                byte_len: offset - wb_offset,
+            },
+        ))
     }
 }
@@ -9,18 +9,20 @@ use eth_types::{Field, Hash, ToWord};
 
 use crate::wasm_circuit::{
     bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    leb128::circuit::LEB128Chip,
+    common::WasmSectionSummaryAwareChip,
+    leb128::{circuit::LEB128Chip, consts::LEB128_MAX_BYTES_COUNT},
     sections::r#type::{
         body::circuit::WasmTypeSectionBodyChip, item::circuit::WasmTypeSectionItemChip,
     },
     tables::dynamic_indexes::circuit::DynamicIndexesChip,
+    test_helpers::section_body_test_circuit,
     types::SharedState,
 };
 
 #[derive(Default)]
 struct TestCircuit<'a, F> {
     code_hash: Hash,
-    bytecode_bytes: &'a [u8],
+    bytecode: &'a [u8],
     assign_delta_base: usize,
     _marker: PhantomData<F>,
 }
@@ -53,7 +55,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let dynamic_indexes_chip = Rc::new(DynamicIndexesChip::construct(config));
 
-        let leb128_config = LEB128Chip::<F>::configure(cs, &wb_table.value);
+        let leb128_config =
+            LEB128Chip::<F>::configure(cs, &wb_table.value, LEB128_MAX_BYTES_COUNT, None);
         let leb128_chip = Rc::new(LEB128Chip::construct(leb128_config));
         let config = WasmTypeSectionItemChip::configure(
             cs,
@@ -93,7 +96,7 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         let assign_delta = self.assign_delta_base;
-        let wb = WasmBytecode::new(self.bytecode_bytes.to_vec().clone());
+        let wb = WasmBytecode::new(self.bytecode.to_vec().clone());
         layouter
             .assign_region(
                 || format!("wasm bytecode table at {}", assign_delta),
@@ -124,6 +127,8 @@ impl<'a, F: Field> Circuit<F> for TestCircuit<'a, F> {
     }
 }
 
+section_body_test_circuit!(TestCircuit);
+
 #[cfg(test)]
 mod wasm_type_section_body_tests {
     use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
@@ -136,6 +141,7 @@ mod wasm_type_section_body_tests {
 
     use crate::wasm_circuit::{
         common::wat_extract_section_body_bytecode, sections::r#type::body::tests::TestCircuit,
+        test_helpers::SectionBodyTestCircuit,
     };
 
     fn test<'a, F: Field>(test_circuit: TestCircuit<'_, F>, is_ok: bool, k: u32) {
@@ -158,7 +164,7 @@ mod wasm_type_section_body_tests {
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
-            bytecode_bytes: &bytecode,
+            bytecode: &bytecode,
             ..Default::default()
         };
         test(test_circuit, true, 8);
@@ -171,7 +177,7 @@ mod wasm_type_section_body_tests {
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
-            bytecode_bytes: &bytecode,
+            bytecode: &bytecode,
             assign_delta_base: thread_rng().gen_range(5..300),
             ..Default::default()
         };
@@ -185,7 +191,7 @@ mod wasm_type_section_body_tests {
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
-            bytecode_bytes: &bytecode,
+            bytecode: &bytecode,
             ..Default::default()
         };
         test(test_circuit, true, 8);
@@ -198,10 +204,34 @@ mod wasm_type_section_body_tests {
         let code_hash = CodeDB::hash(&bytecode);
         let test_circuit = TestCircuit::<Fr> {
             code_hash,
-            bytecode_bytes: &bytecode,
+            bytecode: &bytecode,
             assign_delta_base: thread_rng().gen_range(5..300),
             ..Default::default()
         };
         test(test_circuit, true, 9);
     }
+
+    #[test]
+    pub fn multi_value_results_ok() {
+        // (type (func (result i32 i32)))
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x60, 0x00, 0x02, 0x7f, 0x7f], true);
+        // (type (func (param i64) (result i32 i64 i32)))
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x60, 0x01, 0x7e, 0x03, 0x7f, 0x7e, 0x7f], true);
+    }
+
+    #[test]
+    pub fn float_val_types_ok() {
+        // (type (func (param f32 f64) (result f32)))
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x60, 0x02, 0x7d, 0x7c, 0x01, 0x7d], true);
+    }
+
+    #[test]
+    pub fn invalid_param_val_type_fails() {
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x60, 0x01, 0x00, 0x00], false);
+    }
+
+    #[test]
+    pub fn invalid_result_val_type_fails() {
+        TestCircuit::<Fr>::test_body(8, &[0x01, 0x60, 0x00, 0x01, 0x00], false);
+    }
 }
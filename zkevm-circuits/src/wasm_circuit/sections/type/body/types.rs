@@ -7,6 +7,7 @@ pub enum AssignType {
     IsBody,
 
     BodyItemRevCount,
+    ItemIndex,
 
     ErrorCode,
 }
@@ -16,17 +16,21 @@ use crate::{
     wasm_circuit::{
         bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
         common::{
-            configure_constraints_for_q_first_and_q_last, configure_transition_check,
-            WasmAssignAwareChip, WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip,
-            WasmFuncCountAwareChip, WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
+            annotate_region_columns, configure_constraints_for_q_first_and_q_last,
+            configure_transition_check, WasmAssignAwareChip, WasmColumnAnnotationAwareChip,
+            WasmCountPrefixedItemsAwareChip, WasmErrorAwareChip, WasmFuncCountAwareChip,
+            WasmMarkupLeb128SectionAwareChip, WasmSharedStateAwareChip,
         },
-        error::{remap_error_to_assign_at, Error},
+        error::{checked_assign_offset, remap_error_to_assign_at, require_leb_params_at, Error},
         leb128::circuit::LEB128Chip,
         sections::{
             consts::LebParams,
-            r#type::item::{consts::Type::FuncType, types::AssignType},
+            r#type::item::{
+                consts::{Type::FuncType, VAL_TYPE_VALUES},
+                types::AssignType,
+            },
         },
-        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, NumType, SharedState},
+        types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
     },
 };
 
@@ -96,10 +100,11 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionItemChip<F> {
         leb_params: Option<LebParams>,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = wb_offset + assign_delta;
+        let assign_offset = checked_assign_offset(wb_offset, assign_delta)?;
+        let byte_val = wb.byte_at(wb_offset)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {} byte_val {:x?}",
-            assign_offset, q_enable, assign_types, assign_value, wb.bytes[wb_offset],
+            assign_offset, q_enable, assign_types, assign_value, byte_val,
         );
         region
             .assign_fixed(
@@ -113,7 +118,7 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionItemChip<F> {
 
         for assign_type in assign_types {
             if [AssignType::IsInputCount, AssignType::IsOutputCount].contains(&assign_type) {
-                let p = leb_params.unwrap();
+                let p = require_leb_params_at(leb_params, assign_offset)?;
                 self.config
                     .leb128_chip
                     .assign(region, assign_offset, true, p)?;
@@ -244,6 +249,25 @@ impl<F: Field> WasmAssignAwareChip<F> for WasmTypeSectionItemChip<F> {
     }
 }
 
+impl<F: Field> WasmColumnAnnotationAwareChip<F> for WasmTypeSectionItemChip<F> {
+    fn annotate_columns(&self, region: &mut Region<F>) {
+        annotate_region_columns(
+            region,
+            "type_section_item",
+            &[
+                ("q_enable", self.config.q_enable.into()),
+                ("q_first", self.config.q_first.into()),
+                ("q_last", self.config.q_last.into()),
+                ("is_type", self.config.is_type.into()),
+                ("is_input_count", self.config.is_input_count.into()),
+                ("is_input_type", self.config.is_input_type.into()),
+                ("is_output_count", self.config.is_output_count.into()),
+                ("is_output_type", self.config.is_output_type.into()),
+            ],
+        );
+    }
+}
+
 impl<F: Field> WasmTypeSectionItemChip<F> {
     pub fn construct(config: WasmTypeSectionItemConfig<F>) -> Self {
         let instance = Self {
@@ -275,7 +299,12 @@ impl<F: Field> WasmTypeSectionItemChip<F> {
             cs,
             leb128_chip.as_ref(),
             body_item_rev_count,
-            |vc| vc.query_fixed(is_input_count, Rotation::cur()),
+            |vc| {
+                let is_input_count_expr = vc.query_fixed(is_input_count, Rotation::cur());
+                let is_output_count_expr = vc.query_fixed(is_output_count, Rotation::cur());
+
+                or::expr([is_input_count_expr, is_output_count_expr])
+            },
             |vc| {
                 let q_enable_expr = Self::get_selector_expr_enriched_with_error_processing(
                     vc,
@@ -370,7 +399,7 @@ impl<F: Field> WasmTypeSectionItemChip<F> {
                     cb.require_in_set(
                         "type_section_item input/output type has valid value",
                         byte_value_expr.clone(),
-                        vec![NumType::I32.expr(), NumType::I64.expr()],
+                        VAL_TYPE_VALUES.iter().map(|&v| v.expr()).collect_vec(),
                     )
                 },
             );
@@ -459,7 +488,7 @@ impl<F: Field> WasmTypeSectionItemChip<F> {
                 &mut cb,
                 vc,
                 "check next: is_output_type*",
-                and::expr([not_q_last_expr.clone(), is_output_count_expr.clone()]),
+                and::expr([not_q_last_expr.clone(), is_output_type_expr.clone()]),
                 true,
                 &[is_output_type],
             );
@@ -12,3 +12,18 @@ impl<F: FieldExt> Expr<F> for Type {
         Expression::Constant(F::from(*self as u64))
     }
 }
+
+/// https://webassembly.github.io/spec/core/binary/types.html#value-types
+#[derive(Copy, Clone, Debug)]
+pub enum ValType {
+    I32 = 0x7F,
+    I64 = 0x7E,
+    F32 = 0x7D,
+    F64 = 0x7C,
+}
+pub const VAL_TYPE_VALUES: &[ValType] = &[ValType::I32, ValType::I64, ValType::F32, ValType::F64];
+impl<F: FieldExt> Expr<F> for ValType {
+    fn expr(&self) -> Expression<F> {
+        Expression::Constant(F::from(*self as u64))
+    }
+}
@@ -0,0 +1,75 @@
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+use eth_types::Field;
+
+use crate::wasm_circuit::{
+    bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+    circuit::{WasmChip, WasmConfig},
+    types::SharedState,
+};
+
+/// Standalone WASM module circuit.
+///
+/// Configures only the bytecode table and the `WasmChip` (with its own range/poseidon tables),
+/// none of the EVM execution tables (copy, keccak, exp, etc.) are involved. Suitable for
+/// module-only verification.
+#[derive(Clone, Debug, Default)]
+pub struct WasmCircuit<F> {
+    pub wbs: Vec<WasmBytecode>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> WasmCircuit<F> {
+    pub fn new(wbs: Vec<WasmBytecode>) -> Self {
+        Self {
+            wbs,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: Field> Circuit<F> for WasmCircuit<F> {
+    type Config = WasmConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+        let wb_table = Rc::new(WasmBytecodeTable::construct(cs, true));
+
+        WasmChip::<F>::configure(cs, wb_table, shared_state)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let mut wasm_chip = WasmChip::construct(config);
+
+        wasm_chip
+            .load_once(&mut layouter)
+            .map_err(|_| Error::Synthesis)?;
+        layouter.assign_region(
+            || "wasm_chip region",
+            |mut region| {
+                wasm_chip.config.shared_state.borrow_mut().reset();
+                wasm_chip
+                    .assign_auto_bytecodes(&mut region, &self.wbs, 0)
+                    .map_err(|_| Error::Synthesis)?;
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
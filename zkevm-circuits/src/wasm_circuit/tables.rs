@@ -1,3 +1,4 @@
 pub mod fixed_range;
 pub mod dynamic_indexes;
 pub mod code_blocks;
+pub mod section_rank;
@@ -1,8 +1,8 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Region, Value},
-    plonk::{Advice, Column, ConstraintSystem, Fixed},
+    circuit::{Chip, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use log::debug;
@@ -10,6 +10,8 @@ use log::debug;
 use eth_types::Field;
 use gadgets::{
     binary_number::BinaryNumberChip,
+    is_zero::{IsZeroChip, IsZeroInstruction},
+    less_than::{LtChip, LtInstruction},
     util::{and, not, Expr},
 };
 
@@ -20,7 +22,10 @@ use crate::{
             configure_constraints_for_q_first_and_q_last, WasmBytecodeNumberAwareChip,
             WasmSharedStateAwareChip,
         },
-        error::{remap_error_to_assign_at, remap_error_to_invalid_enum_value_at, Error},
+        error::{
+            checked_assign_offset, remap_error, remap_error_to_assign_at,
+            remap_error_to_invalid_enum_value_at, Error,
+        },
         tables::code_blocks::types::{AssignType, Opcode, OPCODE_VALUES},
         types::{AssignDeltaType, AssignValueType, OffsetType, SharedState},
     },
@@ -34,8 +39,24 @@ pub struct CodeBlocksConfig<F> {
     pub bytecode_number: Column<Advice>,
     pub index: Column<Advice>,
     pub opcode: Column<Advice>,
+    /// block level of the code section body row: the level inside the block on `block`, `loop`
+    /// and `if`, the level after it on `end` (zero for the `end` of a func body)
+    pub level: Column<Advice>,
+    /// instructions count of the code section body row, see `extract_branch_targets` for what
+    /// an instruction is
+    pub instructions_count: Column<Advice>,
+    /// `block`/`loop`/`if`: index of the `end` closing the block and program counter a branch
+    /// leaving the block jumps to, zero on the other rows
+    pub end_index: Column<Advice>,
+    pub branch_target: Column<Advice>,
+    /// `end` of a block: index of the `block`/`loop`/`if` it closes
+    pub open_index: Column<Advice>,
 
     pub opcode_chip: Rc<BinaryNumberChip<F, Opcode, 8>>,
+    /// `index < end_index` on `block`/`loop`/`if`, a block is closed after it is opened
+    pub end_index_lt_chip: Rc<LtChip<F, 4>>,
+    /// `end` of a func body, it closes no block
+    pub level_is_zero_chip: Rc<IsZeroChip<F>>,
 
     pub shared_state: Rc<RefCell<SharedState>>,
 
@@ -71,6 +92,20 @@ impl<F: Field> CodeBlocksChip<F> {
         instance
     }
 
+    /// `block`, `loop` or `if` at the row, the opcodes opening a block
+    fn is_open_expr(
+        opcode_chip: &BinaryNumberChip<F, Opcode, 8>,
+        vc: &mut VirtualCells<'_, F>,
+    ) -> Expression<F> {
+        opcode_chip
+            .config
+            .value_equals(Opcode::Block, Rotation::cur())(vc)
+            + opcode_chip
+                .config
+                .value_equals(Opcode::Loop, Rotation::cur())(vc)
+            + opcode_chip.config.value_equals(Opcode::If, Rotation::cur())(vc)
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         shared_state: Rc<RefCell<SharedState>>,
@@ -82,12 +117,120 @@ impl<F: Field> CodeBlocksChip<F> {
         let opcode = cs.advice_column();
 
         let index = cs.advice_column();
+        let level = cs.advice_column();
+        let instructions_count = cs.advice_column();
+        let end_index = cs.advice_column();
+        let branch_target = cs.advice_column();
+        let open_index = cs.advice_column();
 
         let config = BinaryNumberChip::configure(cs, q_enable, Some(opcode.into()));
         let opcode_chip = Rc::new(BinaryNumberChip::construct(config));
 
+        let config = LtChip::configure(
+            cs,
+            |vc| vc.query_fixed(q_enable, Rotation::cur()) * Self::is_open_expr(&opcode_chip, vc),
+            |vc| vc.query_advice(index, Rotation::cur()),
+            |vc| vc.query_advice(end_index, Rotation::cur()),
+        );
+        let end_index_lt_chip = Rc::new(LtChip::construct(config));
+
+        let value_inv = cs.advice_column();
+        let config = IsZeroChip::configure(
+            cs,
+            |vc| vc.query_fixed(q_enable, Rotation::cur()),
+            |vc| vc.query_advice(level, Rotation::cur()),
+            value_inv,
+        );
+        let level_is_zero_chip = Rc::new(IsZeroChip::construct(config));
+
         Self::configure_bytecode_number(cs, q_enable, q_first, q_last, bytecode_number);
 
+        // a block is closed by the first `end` after it going a level down. Levels move by one,
+        // so the `block`/`loop`/`if` of a level and the `end`s going down from it alternate, and
+        // an `end` can only close a block opened before it: the two lookups below, both ways,
+        // leave the matching blocks as the only choice
+        cs.lookup_any(
+            "code blocks: block/loop/if is closed by an end a level down",
+            |vc| {
+                let cond = vc.query_fixed(q_enable, Rotation::cur())
+                    * Self::is_open_expr(&opcode_chip, vc);
+
+                vec![
+                    (
+                        cond.clone() * vc.query_advice(bytecode_number, Rotation::cur()),
+                        vc.query_advice(bytecode_number, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * vc.query_advice(end_index, Rotation::cur()),
+                        vc.query_advice(index, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * Opcode::End.expr(),
+                        vc.query_advice(opcode, Rotation::cur()),
+                    ),
+                    (
+                        cond * (vc.query_advice(level, Rotation::cur()) - 1.expr()),
+                        vc.query_advice(level, Rotation::cur()),
+                    ),
+                ]
+            },
+        );
+        cs.lookup_any(
+            "code blocks: end of a block closes a block/loop/if a level up",
+            |vc| {
+                let cond = vc.query_fixed(q_enable, Rotation::cur())
+                    * opcode_chip
+                        .config
+                        .value_equals(Opcode::End, Rotation::cur())(vc)
+                    * not::expr(level_is_zero_chip.config().expr());
+
+                vec![
+                    (
+                        cond.clone() * vc.query_advice(bytecode_number, Rotation::cur()),
+                        vc.query_advice(bytecode_number, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * vc.query_advice(open_index, Rotation::cur()),
+                        vc.query_advice(index, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * (vc.query_advice(level, Rotation::cur()) + 1.expr()),
+                        vc.query_advice(level, Rotation::cur()),
+                    ),
+                    (
+                        cond * vc.query_advice(index, Rotation::cur()),
+                        vc.query_advice(end_index, Rotation::cur()),
+                    ),
+                ]
+            },
+        );
+        // a branch leaving a `block` or an `if` jumps right after its `end`
+        cs.lookup_any(
+            "code blocks: block/if branch target follows its end",
+            |vc| {
+                let cond = vc.query_fixed(q_enable, Rotation::cur())
+                    * (opcode_chip
+                        .config
+                        .value_equals(Opcode::Block, Rotation::cur())(vc)
+                        + opcode_chip.config.value_equals(Opcode::If, Rotation::cur())(vc));
+
+                vec![
+                    (
+                        cond.clone() * vc.query_advice(bytecode_number, Rotation::cur()),
+                        vc.query_advice(bytecode_number, Rotation::cur()),
+                    ),
+                    (
+                        cond.clone() * vc.query_advice(end_index, Rotation::cur()),
+                        vc.query_advice(index, Rotation::cur()),
+                    ),
+                    (
+                        cond * vc.query_advice(branch_target, Rotation::cur()),
+                        vc.query_advice(instructions_count, Rotation::cur()),
+                    ),
+                ]
+            },
+        );
+
         cs.create_gate("CodeBlocks gate", |vc| {
             let mut cb = BaseConstraintBuilder::default();
 
@@ -213,10 +356,19 @@ impl<F: Field> CodeBlocksChip<F> {
                 },
             );
 
+            // the else branch may hold nested blocks as well, the `else` itself is optional since
+            // `if -> end` is allowed above
             cb.condition(
                 and::expr([not_q_last_expr.clone(), opcode_is_else_expr.clone()]),
                 |cb| {
-                    cb.require_equal("else -> end", opcode_is_end_next_expr.clone(), 1.expr());
+                    cb.require_equal(
+                        "else -> block | loop | if | end",
+                        opcode_is_block_next_expr.clone()
+                            + opcode_is_loop_next_expr.clone()
+                            + opcode_is_if_next_expr.clone()
+                            + opcode_is_end_next_expr.clone(),
+                        1.expr(),
+                    );
                 },
             );
 
@@ -242,6 +394,32 @@ impl<F: Field> CodeBlocksChip<F> {
                 );
             });
 
+            let is_open_expr = Self::is_open_expr(&opcode_chip, vc);
+            let end_index_expr = vc.query_advice(end_index, Rotation::cur());
+            let branch_target_expr = vc.query_advice(branch_target, Rotation::cur());
+            cb.condition(is_open_expr.clone(), |cb| {
+                cb.require_equal(
+                    "block/loop/if => index<end_index",
+                    end_index_lt_chip.config().is_lt(vc, None),
+                    1.expr(),
+                );
+            });
+            cb.condition(not::expr(is_open_expr), |cb| {
+                cb.require_zero("!block/loop/if => end_index=0", end_index_expr);
+                cb.require_zero(
+                    "!block/loop/if => branch_target=0",
+                    branch_target_expr.clone(),
+                );
+            });
+            // a branch leaving a `loop` jumps to its first instruction
+            cb.condition(opcode_is_loop_expr.clone(), |cb| {
+                cb.require_equal(
+                    "loop => branch_target=instructions_count",
+                    branch_target_expr,
+                    vc.query_advice(instructions_count, Rotation::cur()),
+                );
+            });
+
             cb.gate(q_enable_expr.clone())
         });
 
@@ -253,7 +431,14 @@ impl<F: Field> CodeBlocksChip<F> {
             q_last,
             opcode,
             index,
+            level,
+            instructions_count,
+            end_index,
+            branch_target,
+            open_index,
             opcode_chip,
+            end_index_lt_chip,
+            level_is_zero_chip,
             bytecode_number,
             shared_state,
         };
@@ -270,7 +455,7 @@ impl<F: Field> CodeBlocksChip<F> {
         assign_value: AssignValueType,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = offset + assign_delta;
+        let assign_offset = checked_assign_offset(offset, assign_delta)?;
         debug!(
             "assign at {} q_enable {} assign_types {:?} assign_value {:?}",
             assign_offset, q_enable, assign_types, assign_value,
@@ -282,7 +467,7 @@ impl<F: Field> CodeBlocksChip<F> {
                 assign_offset,
                 || Value::known(F::from(q_enable as u64)),
             )
-            .unwrap();
+            .map_err(remap_error_to_assign_at(assign_offset))?;
         self.assign_bytecode_number(region, assign_offset, None)?;
         for assign_type in assign_types {
             match assign_type {
@@ -338,6 +523,90 @@ impl<F: Field> CodeBlocksChip<F> {
                         .assign(region, assign_offset, &opcode)
                         .map_err(remap_error_to_assign_at(assign_offset))?;
                 }
+                AssignType::Level => {
+                    region
+                        .assign_advice(
+                            || format!("assign 'level' val {} at {}", assign_value, assign_offset),
+                            self.config.level,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                    self.config
+                        .level_is_zero_chip
+                        .assign(region, assign_offset, Value::known(F::from(assign_value)))
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::InstructionsCount => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'instructions_count' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.instructions_count,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::EndIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'end_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.end_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                    // the row at `offset` has index `offset + 1`
+                    self.config
+                        .end_index_lt_chip
+                        .assign(
+                            region,
+                            assign_offset,
+                            F::from(offset as u64 + 1),
+                            F::from(assign_value),
+                        )
+                        .map_err(remap_error(Error::FatalAssignExternalChip))?;
+                }
+                AssignType::BranchTarget => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'branch_target' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.branch_target,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
+                AssignType::OpenIndex => {
+                    region
+                        .assign_advice(
+                            || {
+                                format!(
+                                    "assign 'open_index' val {} at {}",
+                                    assign_value, assign_offset
+                                )
+                            },
+                            self.config.open_index,
+                            assign_offset,
+                            || Value::known(F::from(assign_value)),
+                        )
+                        .map_err(remap_error_to_assign_at(assign_offset))?;
+                }
             }
         }
 
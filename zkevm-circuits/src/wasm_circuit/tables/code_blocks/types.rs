@@ -11,6 +11,11 @@ pub enum AssignType {
     QLast,
     Index,
     Opcode,
+    Level,
+    InstructionsCount,
+    EndIndex,
+    BranchTarget,
+    OpenIndex,
 }
 
 #[derive(Default, Copy, Clone, Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
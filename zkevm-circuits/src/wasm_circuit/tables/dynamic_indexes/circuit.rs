@@ -8,13 +8,19 @@ use halo2_proofs::{
 use log::debug;
 
 use eth_types::Field;
-use gadgets::util::{and, not, or, Expr};
+use gadgets::{
+    binary_number::BinaryNumberChip,
+    util::{and, not, or, Expr},
+};
 
 use crate::{
     evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon},
     wasm_circuit::{
         common::{WasmBytecodeNumberAwareChip, WasmSharedStateAwareChip},
-        error::{remap_error_to_assign_at, Error},
+        error::{
+            checked_assign_offset, remap_error_to_assign_at, remap_error_to_invalid_enum_value_at,
+            Error,
+        },
         tables::dynamic_indexes::types::{AssignType, LookupArgsParams, Tag, TAG_VALUES},
         types::{AssignDeltaType, AssignValueType, NewWbOffsetType, SharedState},
     },
@@ -27,6 +33,11 @@ pub struct DynamicIndexesConfig<F> {
     pub index: Column<Advice>,
     pub is_terminator: Column<Fixed>,
     pub tag: Column<Fixed>,
+    /// one flag per `TAG_VALUES` item: the terminator of the tag is at or above the row (inside
+    /// the run of rows assigned for the bytecode)
+    pub is_tag_terminated: Vec<Column<Advice>>,
+
+    pub tag_chip: Rc<BinaryNumberChip<F, Tag, 4>>,
 
     pub shared_state: Rc<RefCell<SharedState>>,
 
@@ -63,13 +74,20 @@ impl<F: Field> DynamicIndexesChip<F> {
 
         let bytecode_number = cs.advice_column();
         let index = cs.advice_column();
+        let is_tag_terminated: Vec<Column<Advice>> =
+            TAG_VALUES.iter().map(|_| cs.advice_column()).collect();
+
+        let config = BinaryNumberChip::configure(cs, q_enable, Some(tag.into()));
+        let tag_chip = Rc::new(BinaryNumberChip::construct(config));
 
         cs.create_gate("DynamicIndexes gate", |vc| {
             let mut cb = BaseConstraintBuilder::default();
 
+            let q_enable_prev_expr = vc.query_fixed(q_enable, Rotation::prev());
             let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
             let q_enable_next_expr = vc.query_fixed(q_enable, Rotation::next());
 
+            let is_terminator_prev_expr = vc.query_fixed(is_terminator, Rotation::prev());
             let is_terminator_expr = vc.query_fixed(is_terminator, Rotation::cur());
             let is_terminator_next_expr = vc.query_fixed(is_terminator, Rotation::next());
 
@@ -91,8 +109,6 @@ impl<F: Field> DynamicIndexesChip<F> {
             );
 
             cb.condition(is_terminator_expr.clone(), |cb| {
-                let is_terminator_prev_expr = vc.query_fixed(is_terminator, Rotation::prev());
-                let is_terminator_next_expr = vc.query_fixed(is_terminator, Rotation::next());
                 cb.require_equal(
                     "is_terminator -> prev.is_terminator=0",
                     is_terminator_prev_expr.clone(),
@@ -153,6 +169,40 @@ impl<F: Field> DynamicIndexesChip<F> {
                     );
                 },
             );
+            // together with the 1 by 1 growth this fixes the indexes of a tag-block to 0..=count
+            cb.condition(
+                or::expr([
+                    not::expr(q_enable_prev_expr.clone()),
+                    is_terminator_prev_expr.clone(),
+                ]),
+                |cb| {
+                    cb.require_zero("tag-block starts from index 0", index_expr.clone());
+                },
+            );
+
+            // the flags are carried over the run of rows of the bytecode (each bytecode gets a
+            // separate run) and a tag-block terminator sets the flag of its tag, so there can be
+            // at most one terminator per tag
+            for (tag_value, is_tag_terminated) in TAG_VALUES.iter().zip(is_tag_terminated.iter()) {
+                let is_tag_terminated_expr = vc.query_advice(*is_tag_terminated, Rotation::cur());
+                let is_tag_terminated_prev_expr = q_enable_prev_expr.clone()
+                    * vc.query_advice(*is_tag_terminated, Rotation::prev());
+                let is_tag_terminator_expr = is_terminator_expr.clone()
+                    * tag_chip.config.value_equals(*tag_value, Rotation::cur())(vc);
+                cb.require_boolean(
+                    "is_tag_terminated is boolean",
+                    is_tag_terminated_expr.clone(),
+                );
+                cb.require_zero(
+                    "single terminator per tag",
+                    is_tag_terminated_prev_expr.clone() * is_tag_terminator_expr.clone(),
+                );
+                cb.require_equal(
+                    "is_tag_terminated=prev.is_tag_terminated+is_tag_terminator",
+                    is_tag_terminated_expr,
+                    is_tag_terminated_prev_expr + is_tag_terminator_expr,
+                );
+            }
 
             cb.gate(q_enable_expr.clone())
         });
@@ -165,6 +215,8 @@ impl<F: Field> DynamicIndexesChip<F> {
             tag,
             index,
             bytecode_number,
+            is_tag_terminated,
+            tag_chip,
             shared_state,
         };
 
@@ -240,7 +292,7 @@ impl<F: Field> DynamicIndexesChip<F> {
         assign_value: AssignValueType,
     ) -> Result<(), Error> {
         let q_enable = true;
-        let assign_offset = offset + assign_delta;
+        let assign_offset = checked_assign_offset(offset, assign_delta)?;
         debug!(
             "assign at {} q_enable {} assign_type {:?} assign_value {:?}",
             assign_offset, q_enable, assign_type, assign_value,
@@ -273,6 +325,13 @@ impl<F: Field> DynamicIndexesChip<F> {
                         || Value::known(F::from(assign_value)),
                     )
                     .map_err(remap_error_to_assign_at(assign_offset))?;
+                let tag: Tag = (assign_value as u8)
+                    .try_into()
+                    .map_err(remap_error_to_invalid_enum_value_at(assign_offset))?;
+                self.config
+                    .tag_chip
+                    .assign(region, assign_offset, &tag)
+                    .map_err(remap_error_to_assign_at(assign_offset))?;
             }
             AssignType::IsTerminator => {
                 region
@@ -294,6 +353,49 @@ impl<F: Field> DynamicIndexesChip<F> {
         Ok(())
     }
 
+    /// assigns the `is_tag_terminated` flags of the row, `tag` is added to the terminated tags
+    /// of the bytecode if the row is a terminator
+    pub fn assign_terminated_tags(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        assign_delta: AssignDeltaType,
+        tag: Tag,
+        is_terminator: bool,
+    ) -> Result<(), Error> {
+        let assign_offset = checked_assign_offset(offset, assign_delta)?;
+        if is_terminator {
+            self.config
+                .shared_state
+                .borrow_mut()
+                .dynamic_indexes_terminated_tags
+                .insert(tag);
+        }
+        let shared_state = self.config.shared_state.borrow();
+        for (tag_value, is_tag_terminated) in
+            TAG_VALUES.iter().zip(self.config.is_tag_terminated.iter())
+        {
+            let is_terminated = shared_state
+                .dynamic_indexes_terminated_tags
+                .contains(tag_value);
+            region
+                .assign_advice(
+                    || {
+                        format!(
+                            "assign 'is_tag_terminated' {:?} val {} at {}",
+                            tag_value, is_terminated, assign_offset
+                        )
+                    },
+                    *is_tag_terminated,
+                    assign_offset,
+                    || Value::known(F::from(is_terminated as u64)),
+                )
+                .map_err(remap_error_to_assign_at(assign_offset))?;
+        }
+
+        Ok(())
+    }
+
     pub fn assign_auto(
         &self,
         region: &mut Region<F>,
@@ -313,7 +415,11 @@ impl<F: Field> DynamicIndexesChip<F> {
             //     AssignType::BytecodeNumber,
             //     bytecode_number,
             // )?;
-            self.assign_bytecode_number(region, offset + assign_delta, None)?;
+            self.assign_bytecode_number(
+                region,
+                checked_assign_offset(offset, assign_delta)?,
+                None,
+            )?;
             self.assign(
                 region,
                 offset,
@@ -325,6 +431,13 @@ impl<F: Field> DynamicIndexesChip<F> {
             if rel_offset == indexes_count {
                 self.assign(region, offset, assign_delta, AssignType::IsTerminator, 1)?;
             }
+            self.assign_terminated_tags(
+                region,
+                offset,
+                assign_delta,
+                tag,
+                rel_offset == indexes_count,
+            )?;
         }
 
         Ok(offset)
@@ -1,28 +1,40 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
-    plonk::{Circuit, ConstraintSystem, Error},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Fixed},
+    poly::Rotation,
 };
 
 use eth_types::{Field, ToWord};
 use gadgets::util::Expr;
 
-use crate::wasm_circuit::tables::dynamic_indexes::{
-    circuit::DynamicIndexesChip,
-    types::{LookupArgsParams, Tag},
+use crate::wasm_circuit::{
+    common::WasmBytecodeNumberAwareChip,
+    tables::dynamic_indexes::{
+        circuit::DynamicIndexesChip,
+        types::{AssignType, LookupArgsParams, Tag},
+    },
 };
 
 #[derive(Default)]
 struct TestCircuit<F> {
     len: usize,
     tag: Tag,
+    /// indexes assigned row by row (the last one is the terminator), `assign_auto` is used if
+    /// empty
+    indexes: Vec<u64>,
+    /// (tag, indexes count) of the tag-blocks assigned after the first one
+    extra_blocks: Vec<(Tag, usize)>,
+    /// enables the lookup of one more func index terminator (with index 3)
+    is_second_terminator_looked_up: bool,
     _marker: PhantomData<F>,
 }
 
 #[derive(Clone)]
 struct TestCircuitConfig<F: Field> {
     chip: Rc<DynamicIndexesChip<F>>,
+    q_second_terminator_lookup: Column<Fixed>,
     _marker: PhantomData<F>,
 }
 
@@ -38,9 +50,11 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         let shared_state = Rc::new(RefCell::new(Default::default()));
         let config = DynamicIndexesChip::configure(cs, shared_state.clone());
         let chip = DynamicIndexesChip::construct(config);
+        let q_second_terminator_lookup = cs.fixed_column();
 
         let test_circuit_config = TestCircuitConfig {
             chip: Rc::new(chip),
+            q_second_terminator_lookup,
             _marker: Default::default(),
         };
 
@@ -66,6 +80,17 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
                 is_terminator: true.expr(),
             },
         );
+        test_circuit_config.chip.lookup_args(
+            "second func index terminator lookup test",
+            cs,
+            |vc| LookupArgsParams {
+                cond: vc.query_fixed(q_second_terminator_lookup, Rotation::cur()),
+                bytecode_number: 1.expr(),
+                index: 3.expr(),
+                tag: Tag::FuncIndex.expr(),
+                is_terminator: true.expr(),
+            },
+        );
 
         test_circuit_config
     }
@@ -80,10 +105,41 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             |mut region| {
                 config.chip.config.shared_state.borrow_mut().reset();
                 let mut offset = 0;
-                offset = config
-                    .chip
-                    .assign_auto(&mut region, offset, 0, self.len, self.tag)
-                    .unwrap();
+                if self.indexes.is_empty() {
+                    offset = config
+                        .chip
+                        .assign_auto(&mut region, offset, 0, self.len, self.tag)
+                        .unwrap();
+                }
+                for (i, index) in self.indexes.iter().enumerate() {
+                    offset += 1;
+                    let chip = &config.chip;
+                    chip.assign_bytecode_number(&mut region, offset, None)
+                        .unwrap();
+                    chip.assign(&mut region, offset, 0, AssignType::Index, *index)
+                        .unwrap();
+                    chip.assign(&mut region, offset, 0, AssignType::Tag, self.tag as u64)
+                        .unwrap();
+                    let is_terminator = i == self.indexes.len() - 1;
+                    if is_terminator {
+                        chip.assign(&mut region, offset, 0, AssignType::IsTerminator, 1)
+                            .unwrap();
+                    }
+                    chip.assign_terminated_tags(&mut region, offset, 0, self.tag, is_terminator)
+                        .unwrap();
+                }
+                for (tag, len) in self.extra_blocks.iter() {
+                    offset = config
+                        .chip
+                        .assign_auto(&mut region, offset, 0, *len, *tag)
+                        .unwrap();
+                }
+                region.assign_fixed(
+                    || "q_second_terminator_lookup",
+                    config.q_second_terminator_lookup,
+                    0,
+                    || Value::known(F::from(self.is_second_terminator_looked_up as u64)),
+                )?;
 
                 Ok(())
             },
@@ -117,7 +173,61 @@ mod dynamic_indexes_tests {
             len: 5,
             tag: Tag::FuncIndex,
             _marker: Default::default(),
+            ..Default::default()
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn explicit_increasing_indexes_ok() {
+        let test_circuit = TestCircuit::<Fr> {
+            tag: Tag::FuncIndex,
+            indexes: vec![0, 1, 2, 3, 4, 5],
+            ..Default::default()
+        };
+        test(test_circuit, true);
+    }
+
+    #[test]
+    pub fn func_indexes_out_of_order_fail() {
+        let test_circuit = TestCircuit::<Fr> {
+            tag: Tag::FuncIndex,
+            indexes: vec![0, 2, 1, 3, 4, 5],
+            ..Default::default()
+        };
+        test(test_circuit, false);
+    }
+
+    #[test]
+    pub fn func_indexes_not_starting_from_zero_fail() {
+        let test_circuit = TestCircuit::<Fr> {
+            tag: Tag::FuncIndex,
+            indexes: vec![1, 2, 3, 4, 5],
+            ..Default::default()
+        };
+        test(test_circuit, false);
+    }
+
+    #[test]
+    pub fn terminators_of_different_tags_ok() {
+        let test_circuit = TestCircuit::<Fr> {
+            len: 5,
+            tag: Tag::FuncIndex,
+            extra_blocks: vec![(Tag::TypeIndex, 3), (Tag::GlobalIndex, 0)],
+            ..Default::default()
         };
         test(test_circuit, true);
     }
+
+    #[test]
+    pub fn two_func_index_terminators_fail() {
+        let test_circuit = TestCircuit::<Fr> {
+            len: 5,
+            tag: Tag::FuncIndex,
+            extra_blocks: vec![(Tag::FuncIndex, 3)],
+            is_second_terminator_looked_up: true,
+            ..Default::default()
+        };
+        test(test_circuit, false);
+    }
 }
@@ -4,6 +4,8 @@ use strum_macros::EnumIter;
 use eth_types::Field;
 use gadgets::util::Expr;
 
+use crate::wasm_circuit::error::Error;
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum AssignType {
     Index,
@@ -21,6 +23,12 @@ pub enum Tag {
     MemIndex,
     GlobalIndex,
     DataIndex,
+    FuncBodyIndex,
+    /// function indexes referenced by the element segments, numbered in declaration order
+    TableElemFuncIndex,
+    /// data segments referenced by `memory.init` and `data.drop`, numbered in declaration order
+    /// as the data indexes (the active segments count too)
+    DataSegmentIndex,
     // TODO
     // ElemIndex,
     // LocalIndex,
@@ -32,7 +40,29 @@ pub const TAG_VALUES: &[Tag] = &[
     Tag::MemIndex,
     Tag::GlobalIndex,
     Tag::DataIndex,
+    Tag::FuncBodyIndex,
+    Tag::TableElemFuncIndex,
+    Tag::DataSegmentIndex,
 ];
+impl TryFrom<u8> for Tag {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        for tag in TAG_VALUES {
+            if v == *tag as u8 {
+                return Ok(*tag);
+            }
+        }
+        Err(Error::InvalidEnumValue)
+    }
+}
+
+impl From<Tag> for usize {
+    fn from(t: Tag) -> Self {
+        t as usize
+    }
+}
+
 impl<F: FieldExt> Expr<F> for Tag {
     fn expr(&self) -> Expression<F> {
         Expression::Constant(F::from(*self as u64))
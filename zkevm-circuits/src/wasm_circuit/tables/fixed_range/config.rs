@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use halo2_proofs::{
     circuit::{Layouter, Value},
-    plonk::{ConstraintSystem, Error, TableColumn},
+    plonk::{ConstraintSystem, Error, Expression, TableColumn, VirtualCells},
 };
 use eth_types::Field;
 
@@ -22,6 +22,20 @@ impl<F: Field, const RANGE_START: usize, const COUNT: usize> RangeTableConfig<F,
         }
     }
 
+    /// looks up `value` (enabled by `cond`) returned by `p` in the range
+    pub fn lookup(
+        &self,
+        name: &'static str,
+        cs: &mut ConstraintSystem<F>,
+        p: impl FnOnce(&mut VirtualCells<'_, F>) -> (Expression<F>, Expression<F>),
+    ) {
+        cs.lookup(name, |vc| {
+            let (cond, value) = p(vc);
+
+            vec![(cond * value, self.value)]
+        });
+    }
+
     pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         layouter.assign_table(
             || "load range-check table",
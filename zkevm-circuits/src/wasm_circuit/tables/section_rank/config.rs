@@ -0,0 +1,72 @@
+use std::marker::PhantomData;
+
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error, Expression, TableColumn, VirtualCells},
+};
+
+use crate::wasm_circuit::types::WASM_SECTION_VALUES;
+
+/// `(section_id, rank)` pairs: the position of every section id in the order the sections must
+/// appear in a module. The custom section maps to 0 (it can appear anywhere)
+#[derive(Debug, Clone)]
+pub struct SectionRankTableConfig<F: Field> {
+    pub section_id: TableColumn,
+    pub rank: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> SectionRankTableConfig<F> {
+    pub fn configure(cs: &mut ConstraintSystem<F>) -> Self {
+        let section_id = cs.lookup_table_column();
+        let rank = cs.lookup_table_column();
+
+        Self {
+            section_id,
+            rank,
+            _marker: PhantomData,
+        }
+    }
+
+    /// looks up `(section_id, rank)` (enabled by `cond`) returned by `p` in the table
+    pub fn lookup(
+        &self,
+        name: &'static str,
+        cs: &mut ConstraintSystem<F>,
+        p: impl FnOnce(&mut VirtualCells<'_, F>) -> (Expression<F>, Expression<F>, Expression<F>),
+    ) {
+        cs.lookup(name, |vc| {
+            let (cond, section_id, rank) = p(vc);
+
+            vec![
+                (cond.clone() * section_id, self.section_id),
+                (cond * rank, self.rank),
+            ]
+        });
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "load section rank table",
+            |mut table| {
+                for (offset, section) in WASM_SECTION_VALUES.iter().enumerate() {
+                    table.assign_cell(
+                        || "section_id",
+                        self.section_id,
+                        offset,
+                        || Value::known(F::from(*section as u64)),
+                    )?;
+                    table.assign_cell(
+                        || "rank",
+                        self.rank,
+                        offset,
+                        || Value::known(F::from(section.rank())),
+                    )?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
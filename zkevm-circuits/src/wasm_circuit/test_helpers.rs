@@ -0,0 +1,135 @@
+use bus_mapping::{circuit_input_builder::CircuitsParams, mock::BlockData};
+use eth_types::{bytecode::UncheckedWasmBinary, geth_types::GethData, Field};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{CellValue, MockProver},
+    halo2curves::bn256::Fr,
+    plonk::{Any, Circuit, Column, ConstraintSystem},
+};
+use mock::TestContext;
+
+use crate::witness::{block_convert, Block};
+
+/// Builds a witness block for a single tx calling into the raw WASM `bytes`.
+///
+/// Runs bus-mapping over the traces of the tx, so the returned block carries the
+/// `ExecStep`s of every executed WASM opcode together with the filled rw table.
+pub fn block_from_wasm<F: Field>(bytes: &[u8]) -> Block<F> {
+    block_from_wasm_with_params(bytes, CircuitsParams::default())
+}
+
+/// Same as [`block_from_wasm`] but with non-default circuit params.
+pub fn block_from_wasm_with_params<F: Field>(bytes: &[u8], params: CircuitsParams) -> Block<F> {
+    let test_ctx =
+        TestContext::<2, 1>::simple_ctx_with_bytecode(UncheckedWasmBinary::from(bytes.to_vec()))
+            .unwrap();
+    let geth_data: GethData = test_ctx.into();
+    let mut builder = BlockData::new_from_geth_data_with_params(geth_data.clone(), params)
+        .new_circuit_input_builder();
+    builder
+        .handle_block(&geth_data.eth_block, &geth_data.geth_traces)
+        .unwrap();
+    block_convert(&builder.block, &builder.code_db).unwrap()
+}
+
+/// A section body test circuit assigning a whole section body, implemented with
+/// [`section_body_test_circuit`].
+pub trait SectionBodyTestCircuit<'a>: Circuit<Fr> + Sized {
+    /// Builds the circuit for the section body `bytecode`.
+    fn from_body_bytecode(bytecode: &'a [u8]) -> Self;
+
+    /// Checks that the circuit of `2^k` rows built for `bytecode` verifies iff `is_ok`.
+    fn test_body(k: u32, bytecode: &'a [u8], is_ok: bool) {
+        let prover = MockProver::run(k, &Self::from_body_bytecode(bytecode), vec![]).unwrap();
+        if is_ok {
+            prover.assert_satisfied();
+        } else {
+            assert!(prover.verify().is_err());
+        }
+    }
+}
+
+/// Implements [`SectionBodyTestCircuit`] for the `TestCircuit<'a, F>` of a section body, built
+/// from its `code_hash` and `bytecode` fields, the others being defaulted.
+macro_rules! section_body_test_circuit {
+    ($circuit:ident) => {
+        impl<'a> $crate::wasm_circuit::test_helpers::SectionBodyTestCircuit<'a>
+            for $circuit<'a, ::halo2_proofs::halo2curves::bn256::Fr>
+        {
+            fn from_body_bytecode(bytecode: &'a [u8]) -> Self {
+                Self {
+                    code_hash: ::bus_mapping::state_db::CodeDB::hash(bytecode),
+                    bytecode,
+                    ..Default::default()
+                }
+            }
+        }
+    };
+}
+pub(crate) use section_body_test_circuit;
+
+/// Config of the circuit `C`, to pick the columns to inspect in a `MockProver` run.
+pub fn test_circuit_config<C: Circuit<Fr>>() -> C::Config {
+    let mut cs = ConstraintSystem::<Fr>::default();
+    C::configure(&mut cs)
+}
+
+/// Value assigned to the `column` cell at `offset` in the `prover` run, `None` if unassigned.
+pub fn assigned_cell_value(
+    prover: &MockProver<Fr>,
+    column: Column<Any>,
+    offset: usize,
+) -> Option<u64> {
+    let cells = match column.column_type() {
+        Any::Fixed => &prover.fixed()[column.index()],
+        Any::Advice(_) => &prover.advice()[column.index()],
+        Any::Instance => unreachable!("instance cells are not assigned in the region"),
+    };
+    match cells[offset] {
+        CellValue::Assigned(v) => Some(v.get_lower_128() as u64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_helpers_tests {
+    use eth_types::{
+        bytecode::WasmBinaryBytecode, bytecode_internal, evm_types::OpcodeId, Bytecode,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use crate::{evm_circuit::step::ExecutionState, test_util::CircuitTestBuilder};
+
+    use super::block_from_wasm;
+
+    fn load_bytecode() -> Bytecode {
+        let mut code = Bytecode::default();
+        let data_offset = code.fill_default_global_data(vec![0x01, 0x02, 0x03, 0x04]);
+        bytecode_internal! {code,
+            I32Const[data_offset]
+            .write_memarg(OpcodeId::I32Load, 0, 0, 0)
+            Drop
+        }
+        code
+    }
+
+    #[test]
+    fn block_from_wasm_has_wasm_steps_and_rws() {
+        let block = block_from_wasm::<Fr>(&load_bytecode().wasm_binary());
+
+        let execution_states = block.txs[0]
+            .steps
+            .iter()
+            .map(|step| step.execution_state)
+            .collect::<Vec<_>>();
+        assert!(execution_states.contains(&ExecutionState::WASM_LOAD));
+        assert!(execution_states.contains(&ExecutionState::WASM_DROP));
+        assert!(!block.rws.0.is_empty());
+    }
+
+    #[test]
+    fn block_from_wasm_satisfies_circuits() {
+        let block = block_from_wasm::<Fr>(&load_bytecode().wasm_binary());
+        CircuitTestBuilder::<2, 1>::new_from_block(block).run();
+    }
+}
@@ -1,24 +1,50 @@
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner},
+    circuit::{Layouter, Region, SimpleFloorPlanner, Value},
     plonk::{Circuit, ConstraintSystem, Error},
 };
 use log::debug;
 
-use eth_types::{Field, ToWord};
+use eth_types::{Field, ToScalar, ToWord};
 
-use crate::wasm_circuit::{
-    bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
-    circuit::{WasmChip, WasmConfig},
-    types::SharedState,
+use crate::{
+    table::{WasmMetadataTable, WasmMetadataTag},
+    wasm_circuit::{
+        bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+        circuit::{WasmChip, WasmConfig},
+        error::Error as WasmError,
+        instrumentation::WasmInstrument,
+        parser::DecodedSection,
+        types::{AssignDeltaType, SharedState},
+    },
 };
 
+/// assigns `wb` step by step (`assign_prefix`, `assign_section` for each section,
+/// `assign_dynamic_indexes`), `after_section` gets the chip right after every section
+fn assign_by_sections<F: Field>(
+    wasm_chip: &mut WasmChip<F>,
+    region: &mut Region<F>,
+    wb: &WasmBytecode,
+    wb_offset: usize,
+    assign_delta: AssignDeltaType,
+    mut after_section: impl FnMut(&WasmChip<F>),
+) -> Result<AssignDeltaType, WasmError> {
+    wasm_chip.assign_prefix(region, wb, wb_offset, assign_delta)?;
+    for section_span in wasm_chip.section_spans(region, wb, assign_delta)? {
+        wasm_chip.assign_section(region, wb, &section_span, assign_delta)?;
+        after_section(wasm_chip);
+    }
+    wasm_chip.assign_dynamic_indexes(region, wb, assign_delta)
+}
+
 #[derive(Default)]
 struct TestCircuitWithErrorProcessing<F> {
     wbs: Vec<WasmBytecode>,
     wb_offset: usize,
     assign_delta_base: usize,
+    /// assign every bytecode section by section (`assign_section`) instead of `assign_auto`
+    assign_by_sections: bool,
     _marker: PhantomData<F>,
 }
 
@@ -55,9 +81,21 @@ impl<F: Field> Circuit<F> for TestCircuitWithErrorProcessing<F> {
                 let mut assign_delta = self.assign_delta_base;
                 for wb in &self.wbs {
                     wasm_chip.load(&mut region, wb, assign_delta).unwrap();
-                    assign_delta = wasm_chip
-                        .assign_auto(&mut region, wb, self.wb_offset, assign_delta)
-                        .unwrap();
+                    assign_delta = if self.assign_by_sections {
+                        assign_by_sections(
+                            &mut wasm_chip,
+                            &mut region,
+                            wb,
+                            self.wb_offset,
+                            assign_delta,
+                            |_| {},
+                        )
+                        .unwrap()
+                    } else {
+                        wasm_chip
+                            .assign_auto(&mut region, wb, self.wb_offset, assign_delta)
+                            .unwrap()
+                    };
                     // debug!(
                     //     "RESULT error_code {}",
                     //     wasm_chip.config.shared_state.borrow().error_code
@@ -72,15 +110,48 @@ impl<F: Field> Circuit<F> for TestCircuitWithErrorProcessing<F> {
     }
 }
 
+/// `PRODUCTION_POSEIDON_TABLE` switches the chip to the production poseidon table layout,
+/// `RANGE_TABLE_0_65536` makes the chip load the 0..65536 range table (needs `k >= 17`),
+/// `STRUCTURE_ONLY` configures the chip without the code and data body chips
 #[derive(Default)]
-struct TestCircuit<F> {
+struct TestCircuit<
+    F,
+    const PRODUCTION_POSEIDON_TABLE: bool = false,
+    const RANGE_TABLE_0_65536: bool = false,
+    const STRUCTURE_ONLY: bool = false,
+> {
     wbs: Vec<WasmBytecode>,
     wb_offset: usize,
     assign_delta_base: usize,
+    /// offsets (relative to `assign_delta_base`) where leb128 `is_first_byte` gets overwritten
+    /// with 0 after assignment, used to desync markers from leb128 values
+    leb128_first_byte_unset_at: Vec<usize>,
+    /// (bytecode offset, value): `global_count` of the first bytecode gets overwritten with
+    /// `value` from the offset up to the last byte after assignment
+    global_count_forged_from: Option<(usize, usize)>,
+    /// `func_count` of the shared state right after every bytecode assignment
+    func_counts: RefCell<Vec<usize>>,
+    /// `dynamic_indexes_offset` of the shared state right after every bytecode assignment
+    dynamic_indexes_offsets: RefCell<Vec<usize>>,
+    /// instrument of the chip right after every bytecode assignment
+    instruments: RefCell<Vec<WasmInstrument>>,
+    /// decoded sections of the chip right after every bytecode assignment
+    decoded_sections: RefCell<Vec<Vec<DecodedSection>>>,
+    /// assign every bytecode section by section (`assign_section`) instead of `assign_auto`
+    assign_by_sections: bool,
+    /// decoded sections count of the chip right after every `assign_section`
+    decoded_sections_counts: RefCell<Vec<usize>>,
     _marker: PhantomData<F>,
 }
 
-impl<F: Field> Circuit<F> for TestCircuit<F> {
+impl<
+        F: Field,
+        const PRODUCTION_POSEIDON_TABLE: bool,
+        const RANGE_TABLE_0_65536: bool,
+        const STRUCTURE_ONLY: bool,
+    > Circuit<F>
+    for TestCircuit<F, PRODUCTION_POSEIDON_TABLE, RANGE_TABLE_0_65536, STRUCTURE_ONLY>
+{
     type Config = WasmConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -90,6 +161,9 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
 
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
         let shared_state = Rc::new(RefCell::new(SharedState::default()));
+        shared_state.borrow_mut().production_poseidon_table_enabled = PRODUCTION_POSEIDON_TABLE;
+        shared_state.borrow_mut().range_table_0_65536_enabled = RANGE_TABLE_0_65536;
+        shared_state.borrow_mut().structure_only_enabled = STRUCTURE_ONLY;
         let wb_table = Rc::new(WasmBytecodeTable::construct(cs, true));
         let config = WasmChip::<F>::configure(cs, wb_table, shared_state);
 
@@ -111,13 +185,193 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
                 let mut assign_delta = self.assign_delta_base;
                 for wb in &self.wbs {
                     wasm_chip.load(&mut region, wb, assign_delta).unwrap();
-                    assign_delta = wasm_chip
-                        .assign_auto(&mut region, wb, self.wb_offset, assign_delta)
-                        .unwrap();
+                    assign_delta = if self.assign_by_sections {
+                        assign_by_sections(
+                            &mut wasm_chip,
+                            &mut region,
+                            wb,
+                            self.wb_offset,
+                            assign_delta,
+                            |wasm_chip| {
+                                self.decoded_sections_counts
+                                    .borrow_mut()
+                                    .push(wasm_chip.decoded_sections().len())
+                            },
+                        )
+                        .unwrap()
+                    } else {
+                        wasm_chip
+                            .assign_auto(&mut region, wb, self.wb_offset, assign_delta)
+                            .unwrap()
+                    };
                     debug!(
                         "RESULT error_code {}",
                         wasm_chip.config.shared_state.borrow().error_code
                     );
+                    self.func_counts
+                        .borrow_mut()
+                        .push(wasm_chip.config.shared_state.borrow().func_count);
+                    self.dynamic_indexes_offsets.borrow_mut().push(
+                        wasm_chip
+                            .config
+                            .shared_state
+                            .borrow()
+                            .dynamic_indexes_offset,
+                    );
+                    self.instruments
+                        .borrow_mut()
+                        .push(wasm_chip.instrument().clone());
+                    self.decoded_sections
+                        .borrow_mut()
+                        .push(wasm_chip.decoded_sections().to_vec());
+                }
+                for offset in &self.leb128_first_byte_unset_at {
+                    region.assign_fixed(
+                        || format!("unset leb128 'is_first_byte' at {}", offset),
+                        wasm_chip.config.leb128_chip.config.is_first_byte,
+                        self.assign_delta_base + offset,
+                        || Value::known(F::zero()),
+                    )?;
+                }
+                if let Some((wb_offset, global_count)) = self.global_count_forged_from {
+                    // the bytecode table has the zero row
+                    for offset in wb_offset..self.wbs[0].bytes.len() {
+                        region.assign_advice(
+                            || format!("forge 'global_count' at {}", offset),
+                            wasm_chip.config.global_count,
+                            self.assign_delta_base + 1 + offset,
+                            || Value::known(F::from(global_count as u64)),
+                        )?;
+                    }
+                }
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Assigns the bytecode with `assign_auto` shifted by `assign_delta` and expects
+/// `OffsetOverflow` instead of an arithmetic overflow
+#[derive(Default)]
+struct TestCircuitOffsetOverflow<F> {
+    bytes: Vec<u8>,
+    assign_delta: AssignDeltaType,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for TestCircuitOffsetOverflow<F> {
+    type Config = WasmConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+        let wb_table = Rc::new(WasmBytecodeTable::construct(cs, true));
+        WasmChip::<F>::configure(cs, wb_table, shared_state)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let mut wasm_chip = WasmChip::construct(config);
+        let wb = WasmBytecode::new(self.bytes.clone());
+
+        layouter.assign_region(
+            || "wasm_chip region",
+            |mut region| {
+                let result = wasm_chip.assign_auto(&mut region, &wb, 0, self.assign_delta);
+                // the bytecode table has the zero row
+                assert_eq!(result, Err(WasmError::OffsetOverflow(wb.bytes.len() + 1)));
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Assigns the bytecodes with `assign_auto_bytecodes` next to a wasm metadata table bound to them
+/// by `WasmChip::configure_metadata_table`, the table gets the rows of every module plus
+/// `extra_rows`
+#[derive(Default)]
+struct TestCircuitWithMetadataTable<F> {
+    wbs: Vec<WasmBytecode>,
+    /// (bytecode index, tag, index, value) rows added to the table
+    extra_rows: Vec<(usize, WasmMetadataTag, u64, u64)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> Circuit<F> for TestCircuitWithMetadataTable<F> {
+    type Config = (WasmConfig<F>, WasmMetadataTable);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared_state = Rc::new(RefCell::new(SharedState::default()));
+        let wb_table = Rc::new(WasmBytecodeTable::construct(cs, true));
+        let config = WasmChip::<F>::configure(cs, wb_table, shared_state);
+        let metadata_table = WasmMetadataTable::construct(cs);
+        WasmChip::configure_metadata_table(cs, &config, &metadata_table);
+
+        (config, metadata_table)
+    }
+
+    fn synthesize(
+        &self,
+        (config, metadata_table): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let mut wasm_chip = WasmChip::construct(config);
+
+        wasm_chip.load_once(&mut layouter).unwrap();
+        layouter.assign_region(
+            || "wasm_chip region",
+            |mut region| {
+                wasm_chip.config.shared_state.borrow_mut().reset();
+                wasm_chip
+                    .assign_auto_bytecodes(&mut region, &self.wbs, 0)
+                    .unwrap();
+
+                Ok(())
+            },
+        )?;
+        layouter.assign_region(
+            || "wasm metadata table",
+            |mut region| {
+                let rows = self
+                    .wbs
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(wb_index, wb)| {
+                        WasmMetadataTable::module_rows(&wb.bytes)
+                            .into_iter()
+                            .map(move |(tag, index, value)| (wb_index, tag, index, value))
+                    })
+                    .chain(self.extra_rows.iter().copied());
+                metadata_table.assign(&mut region, 0, [Value::known(F::zero()); 4])?;
+                for (offset, (wb_index, tag, index, value)) in rows.enumerate() {
+                    metadata_table.assign(
+                        &mut region,
+                        offset + 1,
+                        [
+                            Value::known(self.wbs[wb_index].code_hash.to_scalar().unwrap()),
+                            Value::known(F::from(tag as u64)),
+                            Value::known(F::from(index)),
+                            Value::known(F::from(value)),
+                        ],
+                    )?;
                 }
 
                 Ok(())
@@ -131,26 +385,61 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
 #[cfg(test)]
 mod wasm_circuit_tests {
     use ethers_core::k256::pkcs8::der::Encode;
-    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use halo2_proofs::{
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem},
+    };
     use log::debug;
     use rand::{random, thread_rng, Rng};
     use wabt::wat2wasm;
 
-    use eth_types::Field;
+    use bus_mapping::state_db::CodeDB;
+    use eth_types::{Field, ToScalar, ToWord};
 
-    use crate::wasm_circuit::{
-        bytecode::bytecode::WasmBytecode,
-        consts::{
-            WASM_MAGIC_PREFIX_END_INDEX, WASM_MAGIC_PREFIX_LEN, WASM_MAGIC_PREFIX_START_INDEX,
-            WASM_VERSION_PREFIX_END_INDEX, WASM_VERSION_PREFIX_LEN,
-            WASM_VERSION_PREFIX_START_INDEX,
+    use crate::{
+        table::{PoseidonTable, WasmMetadataTag},
+        wasm_circuit::{
+            bytecode::{bytecode::WasmBytecode, bytecode_table::WasmBytecodeTable},
+            circuit::WasmChip,
+            common::wasm_compute_section_len,
+            consts::{
+                WASM_BLOCK_END, WASM_MAGIC_PREFIX_END_INDEX, WASM_MAGIC_PREFIX_LEN,
+                WASM_MAGIC_PREFIX_START_INDEX, WASM_SECTIONS_START_INDEX,
+                WASM_VERSION_PREFIX_END_INDEX, WASM_VERSION_PREFIX_LEN,
+                WASM_VERSION_PREFIX_START_INDEX,
+            },
+            error::{checked_assign_offset, Error, ValidateError},
+            parser::parse_sections,
+            standalone::WasmCircuit,
+            tests::{
+                TestCircuit, TestCircuitOffsetOverflow, TestCircuitWithErrorProcessing,
+                TestCircuitWithMetadataTable,
+            },
+            tests_helpers::mutate_byte,
+            types::{
+                ColumnCounts, ExportDescType, ImportDescType, LimitType, MemSegmentType,
+                NumericInstruction, VariableInstruction, WasmSection,
+            },
         },
-        tests::{TestCircuit, TestCircuitWithErrorProcessing},
-        tests_helpers::mutate_byte,
-        types::WasmSection,
     };
 
-    fn test<'a, F: Field>(test_circuit: &TestCircuit<F>, is_ok: bool, k: u32) {
+    fn test<
+        'a,
+        F: Field,
+        const PRODUCTION_POSEIDON_TABLE: bool,
+        const RANGE_TABLE_0_65536: bool,
+        const STRUCTURE_ONLY: bool,
+    >(
+        test_circuit: &TestCircuit<
+            F,
+            PRODUCTION_POSEIDON_TABLE,
+            RANGE_TABLE_0_65536,
+            STRUCTURE_ONLY,
+        >,
+        is_ok: bool,
+        k: u32,
+    ) {
         let prover = MockProver::run(k, test_circuit, vec![]).unwrap();
         if is_ok {
             prover.assert_satisfied();
@@ -194,6 +483,119 @@ mod wasm_circuit_tests {
         test(&circuit, true, 9);
     }
 
+    #[test]
+    pub fn file1_production_poseidon_table_ok() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let wb = WasmBytecode::new(bytes);
+        let circuit = TestCircuit::<Fr, true> {
+            wbs: vec![wb],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn file1_range_table_0_65536_ok() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let wb = WasmBytecode::new(bytes);
+        let circuit = TestCircuit::<Fr, false, true> {
+            wbs: vec![wb],
+            ..Default::default()
+        };
+        test(&circuit, true, 17);
+    }
+
+    #[test]
+    pub fn file1_structure_only_ok() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let wb = WasmBytecode::new(bytes);
+        let circuit = TestCircuit::<Fr, false, false, true> {
+            wbs: vec![wb],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn structure_only_skips_code_and_data_body_columns() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let full_stats = TestCircuit::<Fr>::configure(&mut cs).column_stats();
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let structure_only_stats =
+            TestCircuit::<Fr, false, false, true>::configure(&mut cs).column_stats();
+
+        for name in ["code section", "data section"] {
+            assert_eq!(
+                structure_only_stats.get(name),
+                Some(ColumnCounts::default())
+            );
+            assert_ne!(full_stats.get(name), Some(ColumnCounts::default()));
+        }
+        assert!(
+            structure_only_stats.total().advice + structure_only_stats.total().fixed
+                < full_stats.total().advice + full_stats.total().fixed
+        );
+    }
+
+    #[test]
+    pub fn dev_and_production_poseidon_tables_have_same_hash_id() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        // dev_load2 uses the code hash of the bytecode as hash_id of every row
+        let dev_hash_id: Fr = CodeDB::hash(&bytes).to_word().to_scalar().unwrap();
+        let rows = PoseidonTable::code_hash_rows::<Fr>(&bytes);
+        assert!(!rows.is_empty());
+        assert!(rows.iter().all(|row| row[0] == dev_hash_id));
+        // both layouts satisfy the code hash lookups for the same bytecode
+        let dev_circuit = TestCircuit::<Fr, false> {
+            wbs: vec![WasmBytecode::new(bytes.clone())],
+            ..Default::default()
+        };
+        test(&dev_circuit, true, 9);
+        let production_circuit = TestCircuit::<Fr, true> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&production_circuit, true, 9);
+    }
+
+    #[test]
+    pub fn forged_code_hash_fails() {
+        let bytes = wat2wasm("(module (func (result i32) i32.const 5))").unwrap();
+        let other_bytes = wat2wasm("(module (func (result i32) i32.const 6))").unwrap();
+        let mut wb = WasmBytecode::new(bytes);
+        wb.code_hash = CodeDB::hash(&other_bytes).to_word();
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![wb],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn code_hash_of_other_bytes_fails() {
+        let bytes = wat2wasm("(module (func (result i32) i32.const 5))").unwrap();
+        let other_bytes = wat2wasm("(module (func (result i32) i32.const 6))").unwrap();
+        let wb = WasmBytecode::new(bytes);
+        // the poseidon table has the rows of the code hash, but for the bytes of `wb`
+        let forged_wb = WasmBytecode {
+            bytes: other_bytes,
+            code_hash: wb.code_hash,
+        };
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![wb, forged_wb],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
     #[test]
     pub fn file1_with_random_assign_delta_base_ok() {
         let path = "./test_files/cc1.wat";
@@ -260,6 +662,221 @@ mod wasm_circuit_tests {
         test(&circuit, true, 13);
     }
 
+    #[test]
+    pub fn same_bytecode_assigned_twice_has_same_func_count() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let wb = WasmBytecode::new(bytes);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![wb.clone(), wb],
+            ..Default::default()
+        };
+        test(&circuit, true, 10);
+        // the region closure may be run more than once by the floor planner
+        let func_counts = circuit.func_counts.borrow();
+        assert!(func_counts.len() >= 2);
+        assert!(func_counts[0] > 0);
+        assert!(func_counts
+            .iter()
+            .all(|func_count| *func_count == func_counts[0]));
+    }
+
+    #[test]
+    pub fn dry_run_rows_match_assign_auto() {
+        let paths = [
+            "./test_files/cc1.wat",
+            "./test_files/cc2.wat",
+            "./test_files/cc3.wat",
+        ];
+        for path in paths {
+            let data: Vec<u8> = std::fs::read(path).unwrap();
+            let bytes = wat2wasm(data).unwrap();
+            let rows = WasmChip::<Fr>::assign_auto_dry_run(&bytes).unwrap();
+            let bytes_len = bytes.len();
+            let circuit = TestCircuit::<Fr> {
+                wbs: vec![WasmBytecode::new(bytes)],
+                ..Default::default()
+            };
+            test(&circuit, true, 9);
+            // the test circuit has the zero row enabled. The dry run measures every row of the
+            // region, the bytecode and dynamic indexes rows are the lower bound
+            let dynamic_indexes_offset = circuit.dynamic_indexes_offsets.borrow()[0];
+            assert!(rows + 1 >= (bytes_len + 1).max(dynamic_indexes_offset + 2));
+            assert!(rows + 1 <= 1 << 9);
+        }
+    }
+
+    #[test]
+    pub fn instrument_records_section_body_rows() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let section_spans = parse_sections(&bytes).unwrap();
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+        let instrument = &circuit.instruments.borrow()[0];
+        assert_eq!(instrument.sections().len(), section_spans.len());
+        for ((section, rows), section_span) in instrument.sections().iter().zip(&section_spans) {
+            assert_eq!(*section as u8, section_span.section_id);
+            assert_eq!(*rows, section_span.body_len);
+        }
+        assert_eq!(
+            instrument.total_rows(),
+            section_spans.iter().map(|s| s.body_len).sum::<usize>()
+        );
+        let code_span = section_spans
+            .iter()
+            .find(|s| s.section_id == WasmSection::Code as u8)
+            .unwrap();
+        assert_eq!(instrument.rows_of(WasmSection::Code), code_span.body_len);
+    }
+
+    #[test]
+    pub fn decoded_sections_report_ranges_and_items_count() {
+        let bytes = wat2wasm(r#"(module (func (export "main")) (func))"#).unwrap();
+        let section_spans = parse_sections(&bytes).unwrap();
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+        let decoded_sections = &circuit.decoded_sections.borrow()[0];
+        assert_eq!(
+            decoded_sections
+                .iter()
+                .map(|s| (s.section as u8, s.items_count))
+                .collect::<Vec<_>>(),
+            vec![
+                (WasmSection::Type as u8, Some(1)),
+                (WasmSection::Function as u8, Some(2)),
+                (WasmSection::Export as u8, Some(1)),
+                (WasmSection::Code as u8, Some(2)),
+            ]
+        );
+        for (decoded_section, section_span) in decoded_sections.iter().zip(&section_spans) {
+            assert_eq!(
+                decoded_section.range,
+                section_span.id_offset..section_span.end_offset()
+            );
+        }
+    }
+
+    #[test]
+    pub fn decoded_sections_report_data_items_count() {
+        let bytes = wat2wasm(
+            r#"(module (memory 1) (data (i32.const 0) "ab") (data (i32.const 2) "cd") (data "ef"))"#,
+        )
+        .unwrap();
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes.clone())],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+        let structure_only_circuit = TestCircuit::<Fr, false, false, true> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&structure_only_circuit, true, 9);
+        for decoded_sections in [
+            &circuit.decoded_sections.borrow()[0],
+            &structure_only_circuit.decoded_sections.borrow()[0],
+        ] {
+            let data_section = decoded_sections
+                .iter()
+                .find(|s| s.section as u8 == WasmSection::Data as u8)
+                .unwrap();
+            assert_eq!(data_section.items_count, Some(3));
+        }
+    }
+
+    #[test]
+    pub fn assign_by_sections_ok() {
+        let paths = [
+            "./test_files/cc1.wat",
+            "./test_files/cc2.wat",
+            "./test_files/cc3.wat",
+        ];
+        let mut wbs = vec![];
+        for path in paths {
+            let data: Vec<u8> = std::fs::read(path).unwrap();
+            wbs.push(WasmBytecode::new(wat2wasm(data).unwrap()));
+        }
+        let circuit = TestCircuit::<Fr> {
+            wbs: wbs.clone(),
+            assign_by_sections: true,
+            ..Default::default()
+        };
+        test(&circuit, true, 13);
+        // the same layout as the one `assign_auto` produces
+        let auto_circuit = TestCircuit::<Fr> {
+            wbs,
+            ..Default::default()
+        };
+        test(&auto_circuit, true, 13);
+        assert_eq!(
+            *circuit.dynamic_indexes_offsets.borrow(),
+            *auto_circuit.dynamic_indexes_offsets.borrow()
+        );
+        assert_eq!(
+            *circuit.func_counts.borrow(),
+            *auto_circuit.func_counts.borrow()
+        );
+        // every section is decoded by the time its `assign_section` returns
+        let expected_decoded_sections_counts = auto_circuit
+            .decoded_sections
+            .borrow()
+            .iter()
+            .flat_map(|decoded_sections| 1..=decoded_sections.len())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            *circuit.decoded_sections_counts.borrow(),
+            expected_decoded_sections_counts
+        );
+    }
+
+    #[test]
+    pub fn dry_run_unsupported_version_fails() {
+        let mut bytes = wat2wasm("(module (func))").unwrap();
+        bytes[WASM_VERSION_PREFIX_START_INDEX..=WASM_VERSION_PREFIX_END_INDEX]
+            .copy_from_slice(&[0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes),
+            Err(Error::UnsupportedWasmVersion(2))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "UnsupportedWasmVersion(2)")]
+    pub fn unsupported_version_fails() {
+        let mut bytes = wat2wasm("(module (func))").unwrap();
+        bytes[WASM_VERSION_PREFIX_START_INDEX..=WASM_VERSION_PREFIX_END_INDEX]
+            .copy_from_slice(&[0x02, 0x00, 0x00, 0x00]);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn dry_run_bad_prefix_fails() {
+        let bytes = wat2wasm("(module)").unwrap();
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes[..3]),
+            Err(Error::IndexOutOfBoundsAt(3))
+        );
+        let mut bytes = bytes;
+        bytes[1] = b'b';
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes),
+            Err(Error::InvalidByteValueAt(WASM_MAGIC_PREFIX_START_INDEX))
+        );
+    }
+
     #[test]
     pub fn invalid_bytecode_parse_error_ok() {
         let paths = [
@@ -397,4 +1014,838 @@ mod wasm_circuit_tests {
         };
         test_with_error_processing(&circuit, true, 9);
     }
+
+    /// returns offset of the body of the first `section` of the bytecode
+    fn section_body_start(bytes: &[u8], section: WasmSection) -> usize {
+        let mut offset = WASM_SECTIONS_START_INDEX;
+        loop {
+            let (section_len, section_len_leb_len) =
+                wasm_compute_section_len(bytes, offset + 1).unwrap();
+            let body_start = offset + 1 + section_len_leb_len as usize;
+            if bytes[offset] == section as u8 {
+                return body_start;
+            }
+            offset = body_start + section_len;
+        }
+    }
+
+    #[test]
+    pub fn file2_invalid_segment_kind_parse_error_ok() {
+        let path = "./test_files/cc2.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        for section in [WasmSection::Element, WasmSection::Data] {
+            let mut wb = WasmBytecode::new(bytes.clone());
+            // the first segment kind follows the single byte items count
+            let segment_kind_offset = section_body_start(&wb.bytes, section) + 1;
+            wb.bytes[segment_kind_offset] = 0xff;
+            debug!("wb (modified): {:x?}", wb.bytes);
+            let circuit = TestCircuitWithErrorProcessing::<Fr> {
+                wbs: vec![wb],
+                ..Default::default()
+            };
+            test_with_error_processing(&circuit, true, 9);
+        }
+    }
+
+    #[test]
+    pub fn file1_index_marker_not_followed_by_leb128_first_byte_fails() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let wb = WasmBytecode::new(bytes);
+        // (export "main" (func 0)): exportdesc type byte follows the name, funcidx leb follows it
+        let name = "main".as_bytes();
+        let name_offset = wb
+            .bytes
+            .windows(name.len())
+            .position(|w| w == name)
+            .unwrap();
+        let exportdesc_type_offset = name_offset + name.len();
+        let funcidx_offset = exportdesc_type_offset + 1;
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![wb],
+            leb128_first_byte_unset_at: vec![funcidx_offset],
+            ..Default::default()
+        };
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert!(format!("{:?}", failures).contains("next.leb128_is_first_byte=1"));
+    }
+
+    #[test]
+    pub fn standalone_circuit_with_two_modules_ok() {
+        let wats = [
+            r#"(module (func (export "main")))"#,
+            r#"(module (memory 1) (func (result i32) (i32.const 7)))"#,
+        ];
+        let wbs = wats
+            .iter()
+            .map(|wat| WasmBytecode::new(wat2wasm(wat).unwrap()))
+            .collect::<Vec<_>>();
+        let circuit = WasmCircuit::<Fr>::new(wbs);
+        let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    pub fn standalone_circuit_without_evm_tables_ok() {
+        let paths = [
+            "./test_files/cc1.wat",
+            "./test_files/cc2.wat",
+            "./test_files/cc3.wat",
+        ];
+        for path in paths {
+            let data: Vec<u8> = std::fs::read(path).unwrap();
+            let bytes = wat2wasm(data).unwrap();
+            let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+            let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    pub fn standalone_circuit_invalid_section_id_returns_error() {
+        let path = "./test_files/cc1.wat";
+        let data: Vec<u8> = std::fs::read(path).unwrap();
+        let bytes = wat2wasm(data).unwrap();
+        let mut wb = WasmBytecode::new(bytes);
+        wb.bytes[8] = WasmSection::DataCount as u8 + 1;
+
+        let circuit = WasmCircuit::<Fr>::new(vec![wb]);
+        assert!(MockProver::run(12, &circuit, vec![]).is_err());
+    }
+
+    #[test]
+    pub fn standalone_circuit_section_len_mismatch_returns_error() {
+        // start section declares 2 body bytes but its funcidx leb128 takes only 1
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Start as u8, 0x02, 0x00, 0x00]);
+
+        let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+        assert!(MockProver::run(12, &circuit, vec![]).is_err());
+    }
+
+    #[test]
+    pub fn standalone_circuit_function_and_code_count_mismatch_fails() {
+        // function section declares 2 functions but code section has 1 func body
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Type as u8, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        bytes.extend([WasmSection::Function as u8, 0x03, 0x02, 0x00, 0x00]);
+        bytes.extend([WasmSection::Code as u8, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+
+        let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    pub fn bytecode_from_wat_matches_raw_bytes() {
+        let wb = WasmBytecode::from_wat(GLOBALS_WAT).unwrap();
+        let wb_raw = WasmBytecode::new(wat2wasm(GLOBALS_WAT).unwrap());
+        assert_eq!(wb.bytes, wb_raw.bytes);
+        assert_eq!(wb.code_hash, wb_raw.code_hash);
+    }
+
+    #[test]
+    pub fn bytecode_from_invalid_wat_returns_error() {
+        assert!(WasmBytecode::from_wat("(module (func (i32.unknown)))").is_err());
+    }
+
+    #[test]
+    pub fn standalone_circuit_extra_block_end_returns_error() {
+        let mut bytes = wat2wasm("(module (func nop))").unwrap();
+        // func body: locals count, `nop`, `end` -> `nop` becomes a second `end`
+        let nop_offset = section_body_start(&bytes, WasmSection::Code) + 3;
+        assert_eq!(bytes[nop_offset], 0x01);
+        bytes[nop_offset] = 0x0b;
+
+        let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+        assert!(MockProver::run(12, &circuit, vec![]).is_err());
+    }
+
+    /// returns `bytes` with `section` (id, len and body) inserted right after the `after` section
+    fn insert_section_after(bytes: &[u8], after: WasmSection, section: &[u8]) -> Vec<u8> {
+        let span = parse_sections(bytes)
+            .unwrap()
+            .into_iter()
+            .find(|span| span.section_id == after as u8)
+            .unwrap();
+        let mut result = bytes[..span.end_offset()].to_vec();
+        result.extend_from_slice(section);
+        result.extend_from_slice(&bytes[span.end_offset()..]);
+        result
+    }
+
+    const ORDERED_SECTIONS_WAT: &str = r#"(module (func (export "main")))"#;
+
+    #[test]
+    pub fn custom_section_between_type_and_function_sections_ok() {
+        let bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        // custom section named "abc" with empty content
+        let bytes = insert_section_after(
+            &bytes,
+            WasmSection::Type,
+            &[0x00, 0x04, 0x03, b'a', b'b', b'c'],
+        );
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn empty_element_section_ok() {
+        let bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        let bytes = insert_section_after(
+            &bytes,
+            WasmSection::Export,
+            &[WasmSection::Element as u8, 0x00],
+        );
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes).unwrap(),
+            bytes.len()
+        );
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+        let decoded_sections = &circuit.decoded_sections.borrow()[0];
+        let element_section = decoded_sections
+            .iter()
+            .find(|s| s.section as u8 == WasmSection::Element as u8)
+            .unwrap();
+        assert_eq!(element_section.range.len(), 2);
+        assert_eq!(element_section.items_count, Some(0));
+    }
+
+    const DATA_SEGMENT_WAT: &str = r#"(module (memory 1) (func) (data (i32.const 0) "ab"))"#;
+
+    #[test]
+    pub fn data_count_section_before_code_section_ok() {
+        let bytes = wat2wasm(DATA_SEGMENT_WAT).unwrap();
+        // data count section (id 12) goes between the memory (id 5) and the code (id 10) sections
+        let bytes = insert_section_after(
+            &bytes,
+            WasmSection::Memory,
+            &[WasmSection::DataCount as u8, 0x01, 0x01],
+        );
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn data_count_section_after_code_section_fails() {
+        let bytes = wat2wasm(DATA_SEGMENT_WAT).unwrap();
+        let bytes = insert_section_after(
+            &bytes,
+            WasmSection::Code,
+            &[WasmSection::DataCount as u8, 0x01, 0x01],
+        );
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn empty_last_section_ok() {
+        let mut bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        bytes.extend([WasmSection::Data as u8, 0x00]);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn section_after_greater_section_id_fails() {
+        let bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        // memory section (min 1 page) after the code section
+        let bytes =
+            insert_section_after(&bytes, WasmSection::Code, &[0x05, 0x03, 0x01, 0x00, 0x01]);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    /// module with two memory sections (min 1 page each)
+    fn two_memory_sections_bytecode() -> Vec<u8> {
+        let bytes = wat2wasm("(module (memory 1))").unwrap();
+        insert_section_after(&bytes, WasmSection::Memory, &[0x05, 0x03, 0x01, 0x00, 0x01])
+    }
+
+    #[test]
+    pub fn dry_run_duplicate_section_fails() {
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&two_memory_sections_bytecode()),
+            Err(Error::DuplicateSection(WasmSection::Memory as u8))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "DuplicateSection(5)")]
+    pub fn duplicate_section_fails() {
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(two_memory_sections_bytecode())],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn assign_by_sections_duplicate_section_error_processing_ok() {
+        let circuit = TestCircuitWithErrorProcessing::<Fr> {
+            wbs: vec![WasmBytecode::new(two_memory_sections_bytecode())],
+            assign_by_sections: true,
+            ..Default::default()
+        };
+        test_with_error_processing(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn validate_ok() {
+        let bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        WasmChip::<Fr>::validate(&bytes).unwrap();
+    }
+
+    #[test]
+    pub fn validate_reports_assign_failure() {
+        assert!(matches!(
+            WasmChip::<Fr>::validate(&two_memory_sections_bytecode()),
+            Err(ValidateError::Synthesis(Error::DuplicateSection(5)))
+        ));
+    }
+
+    #[test]
+    pub fn validate_reports_verify_failures() {
+        // function section declares 2 functions but code section has 1 func body
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Type as u8, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        bytes.extend([WasmSection::Function as u8, 0x03, 0x02, 0x00, 0x00]);
+        bytes.extend([WasmSection::Code as u8, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        match WasmChip::<Fr>::validate(&bytes) {
+            Err(ValidateError::Unsatisfied(failures)) => assert!(!failures.is_empty()),
+            result => panic!("unexpected validation result {:?}", result),
+        }
+    }
+
+    /// `(func)` module whose function section is left out
+    fn code_section_without_function_section_bytecode() -> Vec<u8> {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Type as u8, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        bytes.extend([WasmSection::Code as u8, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        bytes
+    }
+
+    #[test]
+    pub fn padded_leb128_func_count_ok() {
+        // the spec allows padded (non-minimal) leb128 as long as the max byte count is kept
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Type as u8, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        bytes.extend([WasmSection::Function as u8, 0x03, 0x81, 0x00, 0x00]);
+        bytes.extend([WasmSection::Code as u8, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn dry_run_reports_the_offset_of_a_broken_section_len() {
+        let mut bytes = b"\0asm".to_vec();
+        bytes.extend([0x01, 0x00, 0x00, 0x00]);
+        bytes.extend([WasmSection::Type as u8, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        // unterminated section len at offset 15
+        bytes.extend([WasmSection::Function as u8, 0xff]);
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes),
+            Err(Error::ComputeValueAt(15))
+        );
+    }
+
+    #[test]
+    pub fn dry_run_code_section_without_function_section_fails() {
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&code_section_without_function_section_bytecode()),
+            Err(Error::MissingRequiredSection(WasmSection::Function as u8))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "MissingRequiredSection(3)")]
+    pub fn code_section_without_function_section_fails() {
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(
+                code_section_without_function_section_bytecode(),
+            )],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn checked_assign_offset_overflow_fails() {
+        assert_eq!(checked_assign_offset(1, 2), Ok(3));
+        assert_eq!(checked_assign_offset(0, usize::MAX), Ok(usize::MAX));
+        // it is `u32::MAX + 1` on 32-bit targets
+        assert_eq!(
+            checked_assign_offset(1, usize::MAX),
+            Err(Error::OffsetOverflow(1))
+        );
+    }
+
+    #[test]
+    pub fn assign_auto_with_overflowing_assign_delta_fails() {
+        let circuit = TestCircuitOffsetOverflow::<Fr> {
+            bytes: wat2wasm("(module)").unwrap(),
+            assign_delta: usize::MAX - WASM_MAGIC_PREFIX_LEN,
+            ..Default::default()
+        };
+        MockProver::run(9, &circuit, vec![]).unwrap();
+    }
+
+    /// `(memory i64 1) (data (<offset_opcode> 0) "ab")`, wabt has no memory64 support
+    fn memory64_data_bytecode(offset_opcode: NumericInstruction) -> Vec<u8> {
+        let mut bytes = wat2wasm("(module)").unwrap();
+        bytes.extend([
+            WasmSection::Memory as u8,
+            0x03,
+            0x01,
+            LimitType::MinOnly64 as u8,
+            0x01,
+        ]);
+        bytes.extend([
+            WasmSection::Data as u8,
+            0x08,
+            0x01,
+            MemSegmentType::Active as u8,
+            offset_opcode as u8,
+            0x00,
+            WASM_BLOCK_END,
+            0x02,
+            b'a',
+            b'b',
+        ]);
+        bytes
+    }
+
+    #[test]
+    pub fn memory64_data_segment_i64_offset_ok() {
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(memory64_data_bytecode(
+                NumericInstruction::I64Const,
+            ))],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "AssignAt")]
+    pub fn memory64_data_segment_i32_offset_fails() {
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(memory64_data_bytecode(
+                NumericInstruction::I32Const,
+            ))],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "AssignAt")]
+    pub fn memory32_data_segment_i64_offset_fails() {
+        let mut bytes = wat2wasm(r#"(module (memory 1) (data (i32.const 0) "ab"))"#).unwrap();
+        let offset_opcode_index = bytes
+            .iter()
+            .rposition(|&b| b == NumericInstruction::I32Const as u8)
+            .unwrap();
+        bytes[offset_opcode_index] = NumericInstruction::I64Const as u8;
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn active_data_segment_imported_memory_ok() {
+        let bytes =
+            wat2wasm(r#"(module (import "env" "memory" (memory 1)) (data (i32.const 0) "ab"))"#)
+                .unwrap();
+        assert_eq!(
+            WasmChip::<Fr>::assign_auto_dry_run(&bytes).unwrap(),
+            bytes.len()
+        );
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn active_data_segment_without_memory_fails() {
+        // `(data (i32.const 0) "a")` with neither a memory section nor a memory import, wabt
+        // refuses to compile it
+        let mut bytes = wat2wasm("(module)").unwrap();
+        bytes.extend([
+            WasmSection::Data as u8,
+            0x07,
+            0x01,
+            MemSegmentType::Active as u8,
+            NumericInstruction::I32Const as u8,
+            0x00,
+            WASM_BLOCK_END,
+            0x01,
+            b'a',
+        ]);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    /// `(import "env" "m" (memory 1)) (memory 1) (export "m" (memory <memidx>))`, wabt refuses to
+    /// compile a second memory
+    fn imported_and_local_memory_exported_as(memidx: u8) -> Vec<u8> {
+        let mut bytes = wat2wasm("(module)").unwrap();
+        bytes.extend([
+            WasmSection::Import as u8,
+            0x0a,
+            0x01,
+            0x03,
+            b'e',
+            b'n',
+            b'v',
+            0x01,
+            b'm',
+            ImportDescType::MemType as u8,
+            LimitType::MinOnly as u8,
+            0x01,
+        ]);
+        bytes.extend([
+            WasmSection::Memory as u8,
+            0x03,
+            0x01,
+            LimitType::MinOnly as u8,
+            0x01,
+        ]);
+        bytes.extend([
+            WasmSection::Export as u8,
+            0x05,
+            0x01,
+            0x01,
+            b'm',
+            ExportDescType::Memidx as u8,
+            memidx,
+        ]);
+        bytes
+    }
+
+    #[test]
+    pub fn local_memory_follows_imported_memory_ok() {
+        // the imported memory is memory 0, the one of the memory section is memory 1
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(imported_and_local_memory_exported_as(1))],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn memory_index_past_local_memory_fails() {
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(imported_and_local_memory_exported_as(2))],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    #[test]
+    pub fn repeated_custom_sections_ok() {
+        let bytes = wat2wasm(ORDERED_SECTIONS_WAT).unwrap();
+        let custom_section = [0x00, 0x04, 0x03, b'a', b'b', b'c'];
+        let bytes = insert_section_after(&bytes, WasmSection::Type, &custom_section);
+        let bytes = insert_section_after(&bytes, WasmSection::Custom, &custom_section);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    const START_IMPORTED_FUNC_WAT: &str = r#"
+        (module
+            (import "env" "f" (func $f))
+            (func)
+            (start $f)
+        )
+    "#;
+
+    #[test]
+    pub fn start_refers_imported_func_ok() {
+        let bytes = wat2wasm(START_IMPORTED_FUNC_WAT).unwrap();
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, true, 9);
+    }
+
+    #[test]
+    pub fn start_refers_func_past_imports_and_funcs_fails() {
+        let mut bytes = wat2wasm(START_IMPORTED_FUNC_WAT).unwrap();
+        // the imported func is 0, the local one is 1
+        let start_func_index_offset = section_body_start(&bytes, WasmSection::Start);
+        assert_eq!(bytes[start_func_index_offset], 0);
+        bytes[start_func_index_offset] = 2;
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![WasmBytecode::new(bytes)],
+            ..Default::default()
+        };
+        test(&circuit, false, 9);
+    }
+
+    const GLOBALS_WAT: &str = r#"
+        (module
+            (import "env" "g" (global i32))
+            (global i32 (i32.const 1))
+            (global (mut i64) (i64.const 2))
+            (func
+                global.get 2
+                global.set 2
+                global.get 0
+                drop
+            )
+        )
+    "#;
+
+    #[test]
+    pub fn standalone_circuit_global_index_refs_ok() {
+        let wb = WasmBytecode::from_wat(GLOBALS_WAT).unwrap();
+        let circuit = WasmCircuit::<Fr>::new(vec![wb]);
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    pub fn standalone_circuit_global_index_out_of_range_fails() {
+        let mut bytes = wat2wasm(GLOBALS_WAT).unwrap();
+        // `global.get 2` -> `global.get 99`
+        let global_get_offset = bytes
+            .windows(2)
+            .position(|w| w == [VariableInstruction::GlobalGet as u8, 0x02])
+            .unwrap();
+        bytes[global_get_offset + 1] = 99;
+
+        let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    pub fn metadata_table_memory_limits_ok() {
+        let wbs = [
+            "(module (memory 2))",
+            "(module (memory 1 3))",
+            "(module (memory 200 300))",
+        ]
+        .iter()
+        .map(|wat| WasmBytecode::from_wat(wat).unwrap())
+        .collect::<Vec<_>>();
+        let circuit = TestCircuitWithMetadataTable::<Fr> {
+            wbs,
+            ..Default::default()
+        };
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    pub fn metadata_table_forged_memory_limits_fail() {
+        let forged_rows = [
+            (WasmMetadataTag::MemoryMinPages, 0, 2),
+            // the memory declares no max
+            (WasmMetadataTag::MemoryMaxPages, 0, 3),
+            // there is a single memory
+            (WasmMetadataTag::MemoryMinPages, 1, 1),
+        ];
+        for (tag, index, value) in forged_rows {
+            let circuit = TestCircuitWithMetadataTable::<Fr> {
+                wbs: vec![WasmBytecode::from_wat("(module (memory 1))").unwrap()],
+                extra_rows: vec![(0, tag, index, value)],
+                ..Default::default()
+            };
+            let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    const BRANCHES_WAT: &str = r#"
+        (module
+            (func (param i32)
+                block
+                    local.get 0
+                    br_if 0
+                    loop
+                        local.get 0
+                        br_if 1
+                        br 0
+                    end
+                end
+                local.get 0
+                if
+                    br 0
+                end
+                br 0
+            )
+        )
+    "#;
+
+    #[test]
+    pub fn metadata_table_branch_targets_ok() {
+        let wb = WasmBytecode::from_wat(BRANCHES_WAT).unwrap();
+        assert_eq!(
+            WasmMetadataTable::module_rows(&wb.bytes),
+            [(1, 5), (3, 5), (4, 2), (7, 8)]
+                .map(|(pc, target)| (WasmMetadataTag::BranchTarget, pc, target))
+                .to_vec()
+        );
+        let circuit = TestCircuitWithMetadataTable::<Fr> {
+            wbs: vec![wb],
+            ..Default::default()
+        };
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    pub fn metadata_table_forged_branch_targets_fail() {
+        let forged_rows = [
+            // the `br_if 0` leaving the block falls through
+            (1, 2),
+            // the `br 0` of the loop jumps past it
+            (4, 5),
+            // the `br 0` of the if jumps back to it
+            (7, 6),
+            // the last `br 0` leaves the func body
+            (8, 9),
+        ];
+        for (pc, target) in forged_rows {
+            let circuit = TestCircuitWithMetadataTable::<Fr> {
+                wbs: vec![WasmBytecode::from_wat(BRANCHES_WAT).unwrap()],
+                extra_rows: vec![(0, WasmMetadataTag::BranchTarget, pc, target)],
+                ..Default::default()
+            };
+            let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    pub fn standalone_circuit_forged_global_count_fails() {
+        let wb = WasmBytecode::from_wat(GLOBALS_WAT).unwrap();
+        // 1 imported + 2 declared globals, claim a 4th one from the global section on so that
+        // `global.get 3` would pass
+        let items_count_offset = section_body_start(&wb.bytes, WasmSection::Global);
+        let circuit = TestCircuit::<Fr> {
+            wbs: vec![wb],
+            global_count_forged_from: Some((items_count_offset, 4)),
+            ..Default::default()
+        };
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert!(format!("{:?}", failures).contains("global_count grew by items count"));
+    }
+
+    const LOCALS_WAT: &str = r#"
+        (module
+            (func (param i32 i64)
+                (local i32 i32)
+                local.get 0
+                local.set 3
+                local.get 1
+                local.tee 1
+                drop
+            )
+        )
+    "#;
+
+    #[test]
+    pub fn standalone_circuit_local_index_refs_ok() {
+        let wb = WasmBytecode::from_wat(LOCALS_WAT).unwrap();
+        let circuit = WasmCircuit::<Fr>::new(vec![wb]);
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    pub fn standalone_circuit_local_index_out_of_range_fails() {
+        let mut bytes = wat2wasm(LOCALS_WAT).unwrap();
+        // `local.set 3` -> `local.set 4`
+        let local_set_offset = bytes
+            .windows(2)
+            .position(|w| w == [VariableInstruction::LocalSet as u8, 0x03])
+            .unwrap();
+        bytes[local_set_offset + 1] = 4;
+
+        let circuit = WasmCircuit::<Fr>::new(vec![WasmBytecode::new(bytes)]);
+        let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    pub fn column_stats_cover_all_wasm_chip_columns() {
+        let mut cs = ConstraintSystem::<Fr>::default();
+        let config = TestCircuit::<Fr>::configure(&mut cs);
+        let stats = config.column_stats();
+
+        let mut wb_table_cs = ConstraintSystem::<Fr>::default();
+        WasmBytecodeTable::construct(&mut wb_table_cs, true);
+        let wb_table_columns = ColumnCounts::of(&wb_table_cs);
+
+        let total = stats.total();
+        assert_eq!(
+            ColumnCounts {
+                fixed: total.fixed + wb_table_columns.fixed,
+                advice: total.advice + wb_table_columns.advice,
+            },
+            ColumnCounts::of(&cs)
+        );
+        for name in [
+            "wasm chip",
+            "range tables",
+            "poseidon table",
+            "leb128",
+            "utf8",
+            "type section",
+            "code section",
+            "custom section",
+        ] {
+            let counts = stats.get(name).unwrap();
+            assert!(
+                counts.fixed + counts.advice > 0,
+                "'{}' has no columns",
+                name
+            );
+        }
+    }
 }
@@ -1,9 +1,16 @@
-use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
+use std::collections::{BTreeMap, BTreeSet};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{ConstraintSystem, Expression},
+};
 use strum_macros::EnumIter;
 
 use gadgets::util::Expr;
 
-use crate::wasm_circuit::error::Error;
+use crate::wasm_circuit::{
+    consts::SECTION_ID_DEFAULT, error::Error, tables::dynamic_indexes::types::Tag,
+};
 
 pub type AssignOffsetType = usize;
 pub type AssignDeltaType = usize;
@@ -17,6 +24,15 @@ pub type Leb128LengthType = usize;
 pub type Leb128BytesCountType = u8;
 pub type SectionLengthType = usize;
 
+/// What a section body chip parsed out of the section body it assigned
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SectionParseSummary {
+    /// leading item count of the section body (funcs, exports, etc.)
+    pub items_count: u64,
+    /// bytes of the section body taken by the items (item count included)
+    pub byte_len: usize,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum AssignType {
     Unknown,
@@ -25,6 +41,9 @@ pub enum AssignType {
     IsSectionId,
     IsSectionLen,
     IsSectionBody,
+    IsOpaqueSectionBody,
+    IsOpaqueFuncsCount,
+    IsOpaqueDataItemsCount,
 
     BodyByteRevIndexL1,
 
@@ -83,6 +102,21 @@ impl TryFrom<i32> for WasmSection {
     }
 }
 
+impl WasmSection {
+    /// position of the section in the order sections must appear in a module, it differs from
+    /// the id since the data count section (id 12) goes right before the code section (id 10).
+    /// Custom sections can appear anywhere, their rank is 0
+    pub fn rank(&self) -> u64 {
+        match self {
+            WasmSection::Custom => 0,
+            WasmSection::DataCount => WasmSection::Code as u64,
+            WasmSection::Code => WasmSection::Code as u64 + 1,
+            WasmSection::Data => WasmSection::Code as u64 + 2,
+            section => *section as u64,
+        }
+    }
+}
+
 impl<F: FieldExt> Expr<F> for WasmSection {
     #[inline]
     fn expr(&self) -> Expression<F> {
@@ -137,7 +171,7 @@ impl<F: FieldExt> Expr<F> for NumType {
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum RefType {
     FuncRef = 0x70,
-    ExternRef = 0x71,
+    ExternRef = 0x6F,
 }
 
 pub const REF_TYPE_VALUES: &[RefType] = &[RefType::FuncRef, RefType::ExternRef];
@@ -169,13 +203,45 @@ impl<F: FieldExt> Expr<F> for RefType {
 }
 
 /// https://webassembly.github.io/spec/core/binary/types.html#limits
+/// Bit 2 marks the limits of a 64-bit memory (memory64 proposal), it is only valid for memories.
 #[derive(Copy, Clone, Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LimitType {
     MinOnly = 0x0,
     MinMax = 0x1,
+    MinOnly64 = 0x4,
+    MinMax64 = 0x5,
 }
 
 pub const LIMIT_TYPE_VALUES: &[LimitType] = &[LimitType::MinOnly, LimitType::MinMax];
+/// limit types a memory type can have
+pub const MEMORY_LIMIT_TYPE_VALUES: &[LimitType] = &[
+    LimitType::MinOnly,
+    LimitType::MinMax,
+    LimitType::MinOnly64,
+    LimitType::MinMax64,
+];
+
+impl LimitType {
+    /// whether the limits are followed by a max value
+    pub fn has_max(&self) -> bool {
+        matches!(self, LimitType::MinMax | LimitType::MinMax64)
+    }
+
+    /// whether the limits belong to a 64-bit memory
+    pub fn is_memory64(&self) -> bool {
+        matches!(self, LimitType::MinOnly64 | LimitType::MinMax64)
+    }
+
+    /// same as `try_from` but also accepts the limit types only memories can have
+    pub fn try_from_memory(v: u8) -> Result<Self, Error> {
+        for instr in MEMORY_LIMIT_TYPE_VALUES {
+            if v == *instr as u8 {
+                return Ok(*instr);
+            }
+        }
+        Err(Error::InvalidEnumValue)
+    }
+}
 
 impl TryFrom<u8> for LimitType {
     type Error = Error;
@@ -338,6 +404,19 @@ pub enum Mutability {
 
 pub const MUTABILITY_VALUES: &[Mutability] = &[Mutability::Const, Mutability::Var];
 
+impl TryFrom<u8> for Mutability {
+    type Error = Error;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        for instr in MUTABILITY_VALUES {
+            if v == *instr as u8 {
+                return Ok(*instr);
+            }
+        }
+        Err(Error::InvalidEnumValue)
+    }
+}
+
 impl<F: FieldExt> Expr<F> for Mutability {
     #[inline]
     fn expr(&self) -> Expression<F> {
@@ -490,10 +569,82 @@ pub enum NumericInstruction {
     I64extend32S = 0xc4,
 }
 
-pub const NUMERIC_INSTRUCTIONS_WITHOUT_ARGS: &[NumericInstruction] =
-    &[NumericInstruction::I32Add, NumericInstruction::I64Add];
+pub const NUMERIC_INSTRUCTIONS_WITHOUT_ARGS: &[NumericInstruction] = &[
+    NumericInstruction::I32Add,
+    NumericInstruction::I64Add,
+    NumericInstruction::I32extend8S,
+    NumericInstruction::I32extend16S,
+    NumericInstruction::I64extend8S,
+    NumericInstruction::I64extend16S,
+    NumericInstruction::I64extend32S,
+];
 pub const NUMERIC_INSTRUCTION_WITH_LEB_ARG: &[NumericInstruction] =
     &[NumericInstruction::I32Const, NumericInstruction::I64Const];
+/// integer instructions popping one value and pushing one, none of them traps
+pub const NUMERIC_INSTRUCTION_PURE_UNARY: &[NumericInstruction] = &[
+    NumericInstruction::I32Eqz,
+    NumericInstruction::I64Eqz,
+    NumericInstruction::I32Clz,
+    NumericInstruction::I32Ctz,
+    NumericInstruction::I32Popcnt,
+    NumericInstruction::I64Clz,
+    NumericInstruction::I64Ctz,
+    NumericInstruction::I64Popcnt,
+    NumericInstruction::I32WrapI64,
+    NumericInstruction::I64ExtendSI32,
+    NumericInstruction::I64ExtendUI32,
+    NumericInstruction::I32extend8S,
+    NumericInstruction::I32extend16S,
+    NumericInstruction::I64extend8S,
+    NumericInstruction::I64extend16S,
+    NumericInstruction::I64extend32S,
+];
+/// integer instructions popping two values and pushing one, none of them traps (divisions and
+/// remainders are left out)
+pub const NUMERIC_INSTRUCTION_PURE_BINARY: &[NumericInstruction] = &[
+    NumericInstruction::I32Eq,
+    NumericInstruction::I32Ne,
+    NumericInstruction::I32LtS,
+    NumericInstruction::I32LtU,
+    NumericInstruction::I32GtS,
+    NumericInstruction::I32GtU,
+    NumericInstruction::I32LeS,
+    NumericInstruction::I32LeU,
+    NumericInstruction::I32GeS,
+    NumericInstruction::I32GeU,
+    NumericInstruction::I32Add,
+    NumericInstruction::I32Sub,
+    NumericInstruction::I32Mul,
+    NumericInstruction::I32And,
+    NumericInstruction::I32Or,
+    NumericInstruction::I32Xor,
+    NumericInstruction::I32Shl,
+    NumericInstruction::I32ShrS,
+    NumericInstruction::I32ShrU,
+    NumericInstruction::I32Rotl,
+    NumericInstruction::I32Rotr,
+    NumericInstruction::I64Eq,
+    NumericInstruction::I64Ne,
+    NumericInstruction::I64LtS,
+    NumericInstruction::I64LtU,
+    NumericInstruction::I64GtS,
+    NumericInstruction::I64GtU,
+    NumericInstruction::I64LeS,
+    NumericInstruction::I64LeU,
+    NumericInstruction::I64GeS,
+    NumericInstruction::I64GeU,
+    NumericInstruction::I64Add,
+    NumericInstruction::I64Sub,
+    NumericInstruction::I64Mul,
+    NumericInstruction::I64And,
+    NumericInstruction::I64Or,
+    NumericInstruction::I64Xor,
+    NumericInstruction::I64Shl,
+    NumericInstruction::I64ShrS,
+    NumericInstruction::I64ShrU,
+    NumericInstruction::I64Rotl,
+    NumericInstruction::I64Rotr,
+];
 
 impl TryFrom<u8> for NumericInstruction {
     type Error = Error;
@@ -592,6 +743,9 @@ pub const CONTROL_INSTRUCTION_WITH_LEB_ARG: &[ControlInstruction] = &[
     ControlInstruction::BrIf,
     ControlInstruction::Call,
 ];
+/// `call_indirect` takes a type index (leb128) and a table index (the reserved `0x00` byte)
+pub const CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG: &[ControlInstruction] =
+    &[ControlInstruction::CallIndirect];
 pub const CONTROL_INSTRUCTION_BLOCK: &[ControlInstruction] = &[
     ControlInstruction::Block,
     ControlInstruction::Loop,
@@ -612,6 +766,11 @@ impl TryFrom<u8> for ControlInstruction {
                 return Ok(*instr);
             }
         }
+        for instr in CONTROL_INSTRUCTION_WITH_TYPE_INDEX_ARG {
+            if v == *instr as u8 {
+                return Ok(*instr);
+            }
+        }
         for instr in CONTROL_INSTRUCTION_BLOCK {
             if v == *instr as u8 {
                 return Ok(*instr);
@@ -670,28 +829,101 @@ impl<F: FieldExt> Expr<F> for ParametricInstruction {
     }
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+/// (section, section it requires) pairs, checked once all the sections of a bytecode are seen
+pub const SECTION_DEPENDENCIES: [(WasmSection, WasmSection); 1] =
+    [(WasmSection::Code, WasmSection::Function)];
+
+#[derive(Clone, Debug, Default)]
 pub struct SharedState {
     pub bytecode_number: u64,
     pub dynamic_indexes_offset: usize,
     pub func_count: usize,
+    pub global_count: usize,
+    /// bytecode offset => `global_count` right after it, for the rows where it grows
+    pub global_count_changes: BTreeMap<usize, usize>,
+    /// memories of the memory index space (imported ones included)
+    pub memory_count: usize,
+    /// bytecode offset => `memory_count` right after it, for the rows where it grows
+    pub memory_count_changes: BTreeMap<usize, usize>,
+    /// tables of the table index space (imported ones included)
+    pub table_count: usize,
+    /// bytecode offset => `table_count` right after it, for the rows where it grows
+    pub table_count_changes: BTreeMap<usize, usize>,
+    /// tags whose dynamic indexes terminator is already assigned for the current bytecode
+    pub dynamic_indexes_terminated_tags: BTreeSet<Tag>,
     pub block_level: usize,
+    /// params count of each func type declared by the type section
+    pub type_params_counts: Vec<usize>,
+    /// typeidx of each func declared by the function section
+    pub func_type_indexes: Vec<usize>,
+    /// params + locals count of the func body being processed by the code section
+    pub locals_count: usize,
+    /// typeidx and params count of the func body being processed by the code section
+    pub func_type_index: usize,
+    pub params_count: usize,
+    /// params count + locals declared so far by the func body being processed by the code section
+    pub declared_locals_count: usize,
+    /// instructions of the code section assigned so far, see `extract_branch_targets`
+    pub instructions_count: usize,
+    /// id of the last non-custom section assigned for the current bytecode
+    pub section_id_prev: u64,
+    pub is_code_section_present: bool,
+    /// bit `id` is set for every section `id` seen so far in the current bytecode
+    pub sections_present: u32,
+    /// memory declared by the memory section is a 64-bit one (memory64 proposal), active data
+    /// segment offsets are `i64.const` expressions then
+    pub is_memory64: bool,
 
     pub error_processing_enabled: bool,
     pub error_code: u64,
+
+    /// makes `WasmChip::configure` build the poseidon table with `PoseidonTable::construct`
+    /// (second phase `hash_id`, the layout of the poseidon circuit) instead of `dev_construct`
+    pub production_poseidon_table_enabled: bool,
+    /// makes `WasmChip::load_once` load the 0..65536 range table, it takes 2^16 rows so the
+    /// circuit needs `k >= 17`
+    pub range_table_0_65536_enabled: bool,
+    /// makes `WasmChip::configure` skip the code and data body chips, their section bodies are
+    /// assigned as opaque bytes and only the section structure (magic prefix, section ordering,
+    /// len-prefixed spans, code hash) is proven
+    pub structure_only_enabled: bool,
 }
 
 impl SharedState {
     pub fn reset(&mut self) {
         self.bytecode_number = 1;
         self.dynamic_indexes_offset = 0;
-        self.func_count = 0;
-        self.block_level = 0;
+        self.bytecode_state_reset();
 
         // self.error_processing_enabled = true;
         self.error_code = 0;
     }
 
+    /// resets the state accumulated while assigning a single bytecode, the state shared by all
+    /// the bytecodes of the region (`bytecode_number`, `dynamic_indexes_offset`) is kept
+    pub fn bytecode_state_reset(&mut self) {
+        self.func_count = 0;
+        self.global_count = 0;
+        self.global_count_changes.clear();
+        self.memory_count = 0;
+        self.memory_count_changes.clear();
+        self.table_count = 0;
+        self.table_count_changes.clear();
+        self.dynamic_indexes_terminated_tags.clear();
+        self.block_level = 0;
+        self.type_params_counts.clear();
+        self.func_type_indexes.clear();
+        self.locals_count = 0;
+        self.func_type_index = 0;
+        self.params_count = 0;
+        self.declared_locals_count = 0;
+        self.instructions_count = 0;
+        self.section_id_prev = SECTION_ID_DEFAULT as u64;
+        self.is_code_section_present = false;
+        self.sections_present = 0;
+        self.is_memory64 = false;
+    }
+
     pub fn bytecode_number_inc(&mut self) {
         self.bytecode_number += 1;
     }
@@ -713,7 +945,94 @@ impl SharedState {
     pub fn block_level_reset(&mut self) {
         self.block_level = 0;
     }
-    pub fn block_level_dec(&mut self) {
-        self.block_level -= 1;
+    /// fails if the block level is already at the base (there is no block to close)
+    pub fn block_level_dec(&mut self) -> Result<(), Error> {
+        self.block_level = self
+            .block_level
+            .checked_sub(1)
+            .ok_or(Error::BlockLevelUnderflow)?;
+        Ok(())
+    }
+    pub fn section_present_set(&mut self, id: u8) {
+        self.sections_present |= 1 << id;
+    }
+    pub fn has_section(&self, id: u8) -> bool {
+        self.sections_present & (1 << id) != 0
+    }
+    /// fails with the id of the first section required by a present section but missing itself
+    pub fn check_section_dependencies(&self) -> Result<(), Error> {
+        for (section, required_section) in SECTION_DEPENDENCIES {
+            if self.has_section(section as u8) && !self.has_section(required_section as u8) {
+                return Err(Error::MissingRequiredSection(required_section as u8));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fixed/advice columns taken by a part of the circuit
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ColumnCounts {
+    pub fixed: usize,
+    pub advice: usize,
+}
+
+impl ColumnCounts {
+    /// columns allocated in `cs` so far
+    pub fn of<F: FieldExt>(cs: &ConstraintSystem<F>) -> Self {
+        Self {
+            fixed: cs.num_fixed_columns,
+            advice: cs.num_advice_columns,
+        }
+    }
+}
+
+/// Columns each subchip of the `WasmChip` adds to the constraint system, in configure order
+#[derive(Clone, Debug, Default)]
+pub struct ColumnStats {
+    /// columns the constraint system had before the first record
+    base: ColumnCounts,
+    pub entries: Vec<(&'static str, ColumnCounts)>,
+}
+
+impl ColumnStats {
+    pub fn new<F: FieldExt>(cs: &ConstraintSystem<F>) -> Self {
+        Self {
+            base: ColumnCounts::of(cs),
+            entries: vec![],
+        }
+    }
+
+    /// accounts the columns allocated in `cs` since the previous record to `name`
+    pub fn record<F: FieldExt>(&mut self, name: &'static str, cs: &ConstraintSystem<F>) {
+        let now = ColumnCounts::of(cs);
+        let total = self.total();
+        let added = ColumnCounts {
+            fixed: now.fixed - self.base.fixed - total.fixed,
+            advice: now.advice - self.base.advice - total.advice,
+        };
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, counts)) => {
+                counts.fixed += added.fixed;
+                counts.advice += added.advice;
+            }
+            None => self.entries.push((name, added)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<ColumnCounts> {
+        self.entries
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, counts)| *counts)
+    }
+
+    pub fn total(&self) -> ColumnCounts {
+        self.entries
+            .iter()
+            .fold(ColumnCounts::default(), |acc, (_, counts)| ColumnCounts {
+                fixed: acc.fixed + counts.fixed,
+                advice: acc.advice + counts.advice,
+            })
     }
 }
@@ -1,8 +1,9 @@
+use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::rc::Rc;
 
 use halo2_proofs::{
-    plonk::{Advice, Column, ConstraintSystem},
+    plonk::{Advice, Column, ConstraintSystem, Expression, VirtualCells},
 };
 use halo2_proofs::circuit::{Chip, Region, Value};
 use halo2_proofs::plonk::Fixed;
@@ -10,27 +11,42 @@ use halo2_proofs::poly::Rotation;
 
 use eth_types::Field;
 use gadgets::is_zero::{IsZeroChip, IsZeroInstruction};
-use gadgets::util::Expr;
+use gadgets::util::{and, not, Expr};
 
 use crate::evm_circuit::util::constraint_builder::{BaseConstraintBuilder, ConstrainBuilderCommon};
 use crate::wasm_circuit::bytecode::bytecode::WasmBytecode;
 use crate::wasm_circuit::error::{Error, remap_error, remap_error_to_assign_at};
 use crate::wasm_circuit::tables::fixed_range::config::RangeTableConfig;
+use crate::wasm_circuit::utf8::consts::{
+    UTF8_2BYTE_LEADING_BYTE_MAX, UTF8_2BYTE_LEADING_BYTE_MIN, UTF8_3BYTE_LEADING_BYTE_MAX,
+    UTF8_3BYTE_LEADING_BYTE_MIN, UTF8_4BYTE_LEADING_BYTE_MAX, UTF8_4BYTE_LEADING_BYTE_MIN,
+    UTF8_CODEPOINT_1BYTE_MAX, UTF8_CONTINUATION_BYTE_MAX, UTF8_CONTINUATION_BYTE_MIN,
+    UTF8_LEADING_BYTE_E0, UTF8_LEADING_BYTE_ED, UTF8_LEADING_BYTE_F0, UTF8_LEADING_BYTE_F4,
+};
+
+/// byte ranges are checked against the 0..128 range table, so every range must be at most 128 wide
+const RANGE_TABLE_LEN: u64 = 128;
 
 #[derive(Debug, Clone)]
 pub struct UTF8Config<F: Field> {
     pub q_enable: Column<Fixed>,
-    // pub is_first_byte: Column<Fixed>,
-    // pub is_last_byte: Column<Fixed>,
-    // pub is_bytes_count_1: Column<Fixed>,
-    // pub is_bytes_count_2: Column<Fixed>,
-    // pub is_bytes_count_3: Column<Fixed>,
-    // pub is_bytes_count_4: Column<Fixed>,
-
-    // pub codepoint: Column<Advice>,
-    // pub codepoint_recovered: Column<Advice>,
-    // pub byte_mul: Column<Advice>,
+    /// position of the byte inside the encoding of its codepoint
+    pub is_first_byte: Column<Fixed>,
+    pub is_second_byte: Column<Fixed>,
+    pub is_third_byte: Column<Fixed>,
+    pub is_fourth_byte: Column<Fixed>,
+    /// number of bytes encoding the codepoint, the same for all its bytes
+    pub is_bytes_count_1: Column<Fixed>,
+    pub is_bytes_count_2: Column<Fixed>,
+    pub is_bytes_count_3: Column<Fixed>,
+    pub is_bytes_count_4: Column<Fixed>,
+
     pub(crate) byte_val_is_zero_chip: IsZeroChip<F>,
+    /// leading bytes narrowing the range of the second byte
+    pub(crate) leading_byte_is_e0_chip: IsZeroChip<F>,
+    pub(crate) leading_byte_is_ed_chip: IsZeroChip<F>,
+    pub(crate) leading_byte_is_f0_chip: IsZeroChip<F>,
+    pub(crate) leading_byte_is_f4_chip: IsZeroChip<F>,
     pub(crate) eligible_byte_vals_range_table_config: Rc<RangeTableConfig<F, 0, 128>>,
 
     _marker: PhantomData<F>,
@@ -39,9 +55,16 @@ pub struct UTF8Config<F: Field> {
 impl<F: Field> UTF8Config<F>
 {}
 
+/// (bytes count, byte position) of the last assigned byte
+#[derive(Debug, Clone, Default)]
+struct UTF8AssignState {
+    char_markup: Option<(u8, u8)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct UTF8Chip<F: Field> {
     pub config: UTF8Config<F>,
+    assign_state: RefCell<UTF8AssignState>,
     _marker: PhantomData<F>,
 }
 
@@ -50,27 +73,50 @@ impl<F: Field> UTF8Chip<F>
     pub fn construct(config: UTF8Config<F>) -> Self {
         let instance = Self {
             config,
+            assign_state: RefCell::new(UTF8AssignState::default()),
             _marker: PhantomData,
         };
         instance
     }
 
+    /// returns (min, len) exprs of the byte range `min..=max`
+    fn byte_range_exprs(min: u64, max: u64) -> (Expression<F>, Expression<F>) {
+        (min.expr(), (max - min + 1).expr())
+    }
+
+    /// adds lookups checking that `cond=1 -> byte_val in min..min+len`
+    fn configure_byte_range_lookups(
+        cs: &mut ConstraintSystem<F>,
+        name: &'static str,
+        eligible_byte_vals_range_table_config: &RangeTableConfig<F, 0, 128>,
+        range_args: impl Fn(&mut VirtualCells<'_, F>) -> (Expression<F>, Expression<F>, Expression<F>, Expression<F>),
+    ) {
+        cs.lookup(name, |vc| {
+            let (cond, byte_val, min, _) = range_args(vc);
+
+            vec![(cond * (byte_val - min), eligible_byte_vals_range_table_config.value)]
+        });
+        cs.lookup(name, |vc| {
+            let (cond, byte_val, min, len) = range_args(vc);
+
+            vec![(cond * (byte_val - min + RANGE_TABLE_LEN.expr() - len), eligible_byte_vals_range_table_config.value)]
+        });
+    }
+
     pub fn configure(
         cs: &mut ConstraintSystem<F>,
         eligible_byte_vals_range_table_config: Rc<RangeTableConfig<F, 0, 128>>,
         bytes: &Column<Advice>,
     ) -> UTF8Config<F> {
         let q_enable = cs.fixed_column();
-        // let is_first_byte = cs.fixed_column();
-        // let is_last_byte = cs.fixed_column();
-        // let is_bytes_count_1 = cs.fixed_column();
-        // let is_bytes_count_2 = cs.fixed_column();
-        // let is_bytes_count_3 = cs.fixed_column();
-        // let is_bytes_count_4 = cs.fixed_column();
-
-        // let codepoint = cs.advice_column();
-        // let codepoint_recovered = cs.advice_column();
-        // let byte_mul = cs.advice_column();
+        let is_first_byte = cs.fixed_column();
+        let is_second_byte = cs.fixed_column();
+        let is_third_byte = cs.fixed_column();
+        let is_fourth_byte = cs.fixed_column();
+        let is_bytes_count_1 = cs.fixed_column();
+        let is_bytes_count_2 = cs.fixed_column();
+        let is_bytes_count_3 = cs.fixed_column();
+        let is_bytes_count_4 = cs.fixed_column();
 
         let value_inv = cs.advice_column();
         let byte_val_is_zero_config = IsZeroChip::configure(
@@ -81,296 +127,199 @@ impl<F: Field> UTF8Chip<F>
         );
         let byte_val_is_zero_chip = IsZeroChip::construct(byte_val_is_zero_config);
 
+        let [
+            leading_byte_is_e0_chip,
+            leading_byte_is_ed_chip,
+            leading_byte_is_f0_chip,
+            leading_byte_is_f4_chip,
+        ] = [
+            UTF8_LEADING_BYTE_E0,
+            UTF8_LEADING_BYTE_ED,
+            UTF8_LEADING_BYTE_F0,
+            UTF8_LEADING_BYTE_F4,
+        ].map(|leading_byte| {
+            let value_inv = cs.advice_column();
+            let config = IsZeroChip::configure(
+                cs,
+                |vc| and::expr([
+                    vc.query_fixed(q_enable, Rotation::cur()),
+                    vc.query_fixed(is_first_byte, Rotation::cur()),
+                ]),
+                |vc| vc.query_advice(*bytes, Rotation::cur()) - leading_byte.expr(),
+                value_inv,
+            );
+            IsZeroChip::construct(config)
+        });
+
         cs.create_gate("UTF8 gate: q_enable=1", |vc| {
             let mut cb = BaseConstraintBuilder::default();
 
             let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
-            // let is_first_byte_expr = vc.query_fixed(is_first_byte, Rotation::cur());
-            // let is_last_byte_expr = vc.query_fixed(is_last_byte, Rotation::cur());
-            // let is_bytes_count_1_expr = vc.query_fixed(is_bytes_count_1, Rotation::cur());
-            // let is_bytes_count_2_expr = vc.query_fixed(is_bytes_count_2, Rotation::cur());
-            // let is_bytes_count_3_expr = vc.query_fixed(is_bytes_count_3, Rotation::cur());
-            // let is_bytes_count_4_expr = vc.query_fixed(is_bytes_count_4, Rotation::cur());
-
-            // let codepoint_expr = vc.query_advice(codepoint, Rotation::cur());
-            // let byte_mul_expr = vc.query_advice(byte_mul, Rotation::cur());
-            // let codepoint_recovered_expr = vc.query_advice(codepoint_recovered, Rotation::cur());
-
-            // let byte_val_expr = vc.query_advice(*bytes, Rotation::cur());
+            let is_first_byte_expr = vc.query_fixed(is_first_byte, Rotation::cur());
+            let is_second_byte_expr = vc.query_fixed(is_second_byte, Rotation::cur());
+            let is_third_byte_expr = vc.query_fixed(is_third_byte, Rotation::cur());
+            let is_fourth_byte_expr = vc.query_fixed(is_fourth_byte, Rotation::cur());
+            let is_bytes_count_1_expr = vc.query_fixed(is_bytes_count_1, Rotation::cur());
+            let is_bytes_count_2_expr = vc.query_fixed(is_bytes_count_2, Rotation::cur());
+            let is_bytes_count_3_expr = vc.query_fixed(is_bytes_count_3, Rotation::cur());
+            let is_bytes_count_4_expr = vc.query_fixed(is_bytes_count_4, Rotation::cur());
 
             cb.require_boolean("q_enable is bool", q_enable_expr.clone());
-            // cb.require_boolean("is_first_byte is bool", is_first_byte_expr.clone());
-            // cb.require_boolean("is_last_byte is bool", is_last_byte_expr.clone());
-            // cb.require_boolean("is_bytes_count_1 is bool", is_bytes_count_1_expr.clone());
-            // cb.require_boolean("is_bytes_count_2 is bool", is_bytes_count_2_expr.clone());
-            // cb.require_boolean("is_bytes_count_3 is bool", is_bytes_count_3_expr.clone());
-            // cb.require_boolean("is_bytes_count_4 is bool", is_bytes_count_4_expr.clone());
+            cb.require_boolean("is_first_byte is bool", is_first_byte_expr.clone());
+            cb.require_boolean("is_second_byte is bool", is_second_byte_expr.clone());
+            cb.require_boolean("is_third_byte is bool", is_third_byte_expr.clone());
+            cb.require_boolean("is_fourth_byte is bool", is_fourth_byte_expr.clone());
+            cb.require_boolean("is_bytes_count_1 is bool", is_bytes_count_1_expr.clone());
+            cb.require_boolean("is_bytes_count_2 is bool", is_bytes_count_2_expr.clone());
+            cb.require_boolean("is_bytes_count_3 is bool", is_bytes_count_3_expr.clone());
+            cb.require_boolean("is_bytes_count_4 is bool", is_bytes_count_4_expr.clone());
 
             cb.require_zero("q_enable=1 -> byte_val!=0", byte_val_is_zero_chip.config().expr());
 
-            // TODO test
-            // cb.condition(
-            //     q_enable_expr.clone(),
-            //     |cb| {
-            //         cb.require_zero(
-            //             "test",
-            //             (1..pow(2, 7) - 1)
-            //                 .fold(
-            //                     1.expr(),
-            //                     |acc, x| { acc.clone() * (x.expr() - byte_val_expr.clone()) },
-            //                 )
-            //         );
-            //     }
-            // );
-
-            // cb.require_equal(
-            //     "exactly one is_bytes_count_X must be active at the same time",
-            //     is_bytes_count_1_expr.clone() + is_bytes_count_2_expr.clone() + is_bytes_count_3_expr.clone() + is_bytes_count_4_expr.clone(),
-            //     1.expr(),
-            // );
-            // cb.condition(
-            //     not::expr(is_first_byte_expr.clone()),
-            //     |cb| {
-            //         let is_bytes_count_1_prev_expr = vc.query_fixed(is_bytes_count_1, Rotation::prev());
-            //         cb.require_equal("is_first_byte=0 -> prev.is_byte_count_1 = cur.is_byte_count_1", is_bytes_count_1_prev_expr, is_bytes_count_1_expr.clone());
-            //         let is_bytes_count_2_prev_expr = vc.query_fixed(is_bytes_count_2, Rotation::prev());
-            //         cb.require_equal("is_first_byte=0 -> prev.is_byte_count_2 = cur.is_byte_count_2", is_bytes_count_2_prev_expr, is_bytes_count_2_expr.clone());
-            //         let is_bytes_count_3_prev_expr = vc.query_fixed(is_bytes_count_3, Rotation::prev());
-            //         cb.require_equal("is_first_byte=0 -> prev.is_byte_count_3 = cur.is_byte_count_3", is_bytes_count_3_prev_expr, is_bytes_count_3_expr.clone());
-            //         let is_bytes_count_4_prev_expr = vc.query_fixed(is_bytes_count_4, Rotation::prev());
-            //         cb.require_equal("is_first_byte=0 -> prev.is_byte_count_4 = cur.is_byte_count_4", is_bytes_count_4_prev_expr, is_bytes_count_4_expr.clone());
-            //     }
-            // );
-            //
-            // // transition checks: is_first_byte{1} -> q_enable* -> is_last_byte{1}
-            // configure_check_for_transition(
-            //     &mut cb,
-            //     vc,
-            //     "check next: is_first_byte{1} -> q_enable* -> is_last_byte{1}",
-            //     and::expr([
-            //         is_first_byte_expr.clone(),
-            //         not::expr(is_last_byte_expr.clone()),
-            //     ]),
-            //     true,
-            //     &[q_enable, is_last_byte],
-            // );
-            // configure_check_for_transition(
-            //     &mut cb,
-            //     vc,
-            //     "check prev: is_first_byte{1} -> q_enable*",
-            //     and::expr([
-            //         q_enable_expr.clone(),
-            //         not::expr(is_first_byte_expr.clone()),
-            //     ]),
-            //     false,
-            //     &[is_first_byte, q_enable],
-            // );
-            // configure_check_for_transition(
-            //     &mut cb,
-            //     vc,
-            //     "check next: q_enable* -> is_last_byte{1}",
-            //     and::expr([
-            //         q_enable_expr.clone(),
-            //         not::expr(is_last_byte_expr.clone()),
-            //     ]),
-            //     true,
-            //     &[q_enable, is_last_byte],
-            // );
-            // configure_check_for_transition(
-            //     &mut cb,
-            //     vc,
-            //     "check prev: is_first_byte{1} -> q_enable* -> is_last_byte{1}",
-            //     and::expr([
-            //         not::expr(is_first_byte_expr.clone()),
-            //         is_last_byte_expr.clone(),
-            //     ]),
-            //     false,
-            //     &[is_first_byte, q_enable],
-            // );
-            //
-            // cb.condition(
-            //     is_last_byte_expr.clone(),
-            //     |cbc| {
-            //         cbc.require_equal(
-            //             "is_last_byte=1 -> byte_mul=1",
-            //             byte_mul_expr.clone(),
-            //             1.expr(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     not::expr(is_last_byte_expr.clone()),
-            //     |cbc| {
-            //         let byte_mul_next = vc.query_advice(byte_mul, Rotation::next());
-            //         cbc.require_equal(
-            //             "is_last_byte=0 -> cur.byte_mul=next.byte_mul * 0b1000000",
-            //             byte_mul_expr.clone(),
-            //             byte_mul_next * 0b1000000.expr(),
-            //         )
-            //     }
-            // );
-            //
-            // cb.condition(
-            //     not::expr(is_last_byte_expr.clone()),
-            //     |cb| {
-            //         let codepoint_recovered_prev_expr = select::expr(
-            //             not::expr(is_first_byte_expr.clone()),
-            //             vc.query_advice(codepoint_recovered, Rotation::prev()),
-            //             0.expr(),
-            //         );
-            //         let bit_mask_expr = select::expr(
-            //             is_first_byte_expr.clone(),
-            //             is_bytes_count_1_expr.clone() * 0b0.expr()
-            //                 + is_bytes_count_2_expr.clone() * 0b11000000.expr()
-            //                 + is_bytes_count_3_expr.clone() * 0b11100000.expr()
-            //                 + is_bytes_count_4_expr.clone() * 0b11110000.expr(),
-            //             0b10000000.expr(),
-            //         );
-            //         cb.require_equal(
-            //             "is_last_byte=0 -> codepoint_recovered = codepoint_recovered_prev + (byte_val - byte_mask) * byte_mul",
-            //             codepoint_recovered_expr.clone(),
-            //             codepoint_recovered_prev_expr.clone() + (byte_val_expr.clone() - bit_mask_expr.clone()) * byte_mul_expr.clone(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     is_last_byte_expr.clone(),
-            //     |cb| {
-            //         cb.require_equal(
-            //             "is_last_byte=1 -> codepoint_recovered=codepoint",
-            //             codepoint_recovered_expr.clone(),
-            //             codepoint_expr.clone(),
-            //         )
-            //     }
-            // );
-
-            // to do: is_first_byte=1 -> remove bit mask and check that value greater 0 (checks for 'overlong encoding' -> not a valid utf8 according to specs)
-
-            // cb.condition(
-            //     not::expr(is_first_byte_expr.clone()),
-            //     |cb| {
-            //         let codepoint_prev_expr = vc.query_advice(codepoint, Rotation::prev());
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> prev.codepoint = cur.codepoint",
-            //             codepoint_prev_expr,
-            //             codepoint_expr.clone(),
-            //         )
-            //     }
-            // );
-            //
-            // // block of checks below is for: is_first_byte=1 -> bit mask is valid according to number of bytes for encoding
-            // cb.condition(
-            //     and::expr([
-            //         is_first_byte_expr.clone(),
-            //         is_bytes_count_1_expr.clone(),
-            //     ]),
-            //     |cb| {
-            //         let bit_mask_expr = 0b0.expr();
-            //         let byte_val_without_mask_expr = byte_val_expr.clone() - bit_mask_expr.clone();
-            //         // TODO replace with lookup
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> byte_val-0b00000000 must belong to [1..2^7-1]",
-            //             (1..pow(2, 7)-1).fold(1.expr(), |acc, x| { acc.clone() * (x.expr() - byte_val_without_mask_expr.clone()) }),
-            //             0.expr(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     and::expr([
-            //         is_first_byte_expr.clone(),
-            //         is_bytes_count_2_expr.clone(),
-            //     ]),
-            //     |cb| {
-            //         let bit_mask_expr = 0b0.expr();
-            //         let byte_val_without_mask_expr = byte_val_expr.clone() - bit_mask_expr.clone();
-            //         // TODO replace with lookup
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> byte_val-0b11000000 must belong to [1..2^5-1]",
-            //             (1..pow(2, 5)-1).fold(1.expr(), |acc, x| { acc.clone() * (x.expr() - byte_val_without_mask_expr.clone()) }),
-            //             0.expr(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     and::expr([
-            //         is_first_byte_expr.clone(),
-            //         is_bytes_count_3_expr.clone(),
-            //     ]),
-            //     |cb| {
-            //         let bit_mask_expr = 0b0.expr();
-            //         let byte_val_without_mask_expr = byte_val_expr.clone() - bit_mask_expr.clone();
-            //         // TODO replace with lookup
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> byte_val-0b11000000 must belong to [1..2^4-1]",
-            //             (1..pow(2, 4)-1).fold(1.expr(), |acc, x| { acc.clone() * (x.expr() - byte_val_without_mask_expr.clone()) }),
-            //             0.expr(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     and::expr([
-            //         is_first_byte_expr.clone(),
-            //         is_bytes_count_4_expr.clone(),
-            //     ]),
-            //     |cb| {
-            //         let bit_mask_expr = 0b0.expr();
-            //         let byte_val_without_mask_expr = byte_val_expr.clone() - bit_mask_expr.clone();
-            //         // TODO replace with lookup
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> byte_val-0b11000000 must belong to [1..2^3-1]",
-            //             (1..pow(2, 3)-1).fold(1.expr(), |acc, x| { acc.clone() * (x.expr() - byte_val_without_mask_expr.clone()) }),
-            //             0.expr(),
-            //         )
-            //     }
-            // );
-            // cb.condition(
-            //     not::expr(is_first_byte_expr.clone()),
-            //     |cb| {
-            //         let bit_mask_expr = 0b10000000.expr();
-            //         let byte_val_without_mask_expr = byte_val_expr.clone() - bit_mask_expr;
-            //         // TODO replace with lookup
-            //         cb.require_equal(
-            //             "is_first_byte=0 -> byte_val-0b10000000 must belong to [1..2^6-1]",
-            //             (1..pow(2, 6)-1).fold(1.expr(), |acc, x| { acc.clone() * (x.expr() - byte_val_without_mask_expr.clone()) }),
-            //             0.expr(),
-            //         )
-            //     }
-            // );
+            cb.require_equal(
+                "exactly one byte position is active at the same time",
+                is_first_byte_expr.clone() + is_second_byte_expr.clone() + is_third_byte_expr.clone() + is_fourth_byte_expr.clone(),
+                1.expr(),
+            );
+            cb.require_equal(
+                "exactly one is_bytes_count_X is active at the same time",
+                is_bytes_count_1_expr.clone() + is_bytes_count_2_expr.clone() + is_bytes_count_3_expr.clone() + is_bytes_count_4_expr.clone(),
+                1.expr(),
+            );
+
+            // bytes of the same codepoint share the bytes count and go one after another
+            cb.condition(
+                not::expr(is_first_byte_expr.clone()),
+                |cb| {
+                    let q_enable_prev_expr = vc.query_fixed(q_enable, Rotation::prev());
+                    cb.require_equal("is_first_byte=0 -> prev.q_enable=1", q_enable_prev_expr, 1.expr());
+                    for (name, is_bytes_count) in [
+                        ("is_first_byte=0 -> prev.is_bytes_count_1=cur.is_bytes_count_1", is_bytes_count_1),
+                        ("is_first_byte=0 -> prev.is_bytes_count_2=cur.is_bytes_count_2", is_bytes_count_2),
+                        ("is_first_byte=0 -> prev.is_bytes_count_3=cur.is_bytes_count_3", is_bytes_count_3),
+                        ("is_first_byte=0 -> prev.is_bytes_count_4=cur.is_bytes_count_4", is_bytes_count_4),
+                    ] {
+                        cb.require_equal(
+                            name,
+                            vc.query_fixed(is_bytes_count, Rotation::prev()),
+                            vc.query_fixed(is_bytes_count, Rotation::cur()),
+                        );
+                    }
+                }
+            );
+            for (name, is_byte_expr, is_prev_byte) in [
+                ("is_second_byte=1 -> prev.is_first_byte=1", is_second_byte_expr.clone(), is_first_byte),
+                ("is_third_byte=1 -> prev.is_second_byte=1", is_third_byte_expr.clone(), is_second_byte),
+                ("is_fourth_byte=1 -> prev.is_third_byte=1", is_fourth_byte_expr.clone(), is_third_byte),
+            ] {
+                cb.condition(
+                    is_byte_expr,
+                    |cb| {
+                        cb.require_equal(name, vc.query_fixed(is_prev_byte, Rotation::prev()), 1.expr());
+                    }
+                );
+            }
+
+            // the codepoint ends exactly after its bytes count
+            let is_last_byte_expr = is_first_byte_expr.clone() * is_bytes_count_1_expr.clone()
+                + is_second_byte_expr.clone() * is_bytes_count_2_expr.clone()
+                + is_third_byte_expr.clone() * is_bytes_count_3_expr.clone()
+                + is_fourth_byte_expr.clone() * is_bytes_count_4_expr.clone();
+            let q_enable_next_expr = vc.query_fixed(q_enable, Rotation::next());
+            let is_first_byte_next_expr = vc.query_fixed(is_first_byte, Rotation::next());
+            cb.condition(
+                is_last_byte_expr.clone(),
+                |cb| {
+                    cb.require_zero(
+                        "is_last_byte=1 -> next is not a continuation byte",
+                        q_enable_next_expr.clone() * not::expr(is_first_byte_next_expr.clone()),
+                    );
+                }
+            );
+            cb.condition(
+                not::expr(is_last_byte_expr.clone()),
+                |cb| {
+                    cb.require_equal("is_last_byte=0 -> next.q_enable=1", q_enable_next_expr.clone(), 1.expr());
+                    cb.require_zero("is_last_byte=0 -> next.is_first_byte=0", is_first_byte_next_expr.clone());
+                }
+            );
 
             cb.gate(q_enable_expr.clone())
         });
 
-        cs.lookup("byte values are UTF8 ASCII compatible", |vc| {
-            let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
-            let byte_val_expr = vc.query_advice(*bytes, Rotation::cur());
-
-            vec![(q_enable_expr * byte_val_expr, eligible_byte_vals_range_table_config.value)]
-        });
+        // leading bytes are bound to the bytes count, continuation bytes are 0b10xxxxxx
+        Self::configure_byte_range_lookups(
+            cs,
+            "byte values are valid UTF8 leading or continuation bytes",
+            &eligible_byte_vals_range_table_config,
+            |vc| {
+                let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
+                let is_first_byte_expr = vc.query_fixed(is_first_byte, Rotation::cur());
+                let byte_val_expr = vc.query_advice(*bytes, Rotation::cur());
+
+                let mut min_expr = not::expr(is_first_byte_expr.clone()) * UTF8_CONTINUATION_BYTE_MIN.expr();
+                let mut len_expr = not::expr(is_first_byte_expr.clone()) * (UTF8_CONTINUATION_BYTE_MAX - UTF8_CONTINUATION_BYTE_MIN + 1).expr();
+                for (is_bytes_count, (min, max)) in [
+                    (is_bytes_count_1, (1, UTF8_CODEPOINT_1BYTE_MAX)),
+                    (is_bytes_count_2, (UTF8_2BYTE_LEADING_BYTE_MIN, UTF8_2BYTE_LEADING_BYTE_MAX)),
+                    (is_bytes_count_3, (UTF8_3BYTE_LEADING_BYTE_MIN, UTF8_3BYTE_LEADING_BYTE_MAX)),
+                    (is_bytes_count_4, (UTF8_4BYTE_LEADING_BYTE_MIN, UTF8_4BYTE_LEADING_BYTE_MAX)),
+                ] {
+                    let is_leading_byte_expr = is_first_byte_expr.clone() * vc.query_fixed(is_bytes_count, Rotation::cur());
+                    let (leading_byte_min_expr, leading_byte_len_expr) = Self::byte_range_exprs(min, max);
+                    min_expr = min_expr + is_leading_byte_expr.clone() * leading_byte_min_expr;
+                    len_expr = len_expr + is_leading_byte_expr * leading_byte_len_expr;
+                }
+
+                (q_enable_expr, byte_val_expr, min_expr, len_expr)
+            },
+        );
 
-        // cs.create_gate("UTF8 gate: q_enable=0", |vc| {
-        //     let mut cb = BaseConstraintBuilder::default();
-        //
-        //     let q_enable_expr = vc.query_fixed(q_enable, Rotation::cur());
-        //     let is_first_byte_expr = vc.query_fixed(is_first_byte, Rotation::cur());
-        //
-        //     cb.require_zero("is_first_byte=0", is_first_byte_expr.clone());
-        //     cb.require_zero("is_last_byte=0", is_first_byte_expr.clone());
-        //
-        //     cb.gate(not::expr(q_enable_expr.clone()))
-        // });
+        // second bytes after 0xE0/0xED/0xF0/0xF4 exclude overlong encodings, surrogates and
+        // codepoints above UTF8_CODEPOINT_MAX
+        Self::configure_byte_range_lookups(
+            cs,
+            "second byte values are valid for the leading byte",
+            &eligible_byte_vals_range_table_config,
+            |vc| {
+                let cond_expr = vc.query_fixed(q_enable, Rotation::cur())
+                    * vc.query_fixed(is_first_byte, Rotation::cur())
+                    * (vc.query_fixed(is_bytes_count_3, Rotation::cur()) + vc.query_fixed(is_bytes_count_4, Rotation::cur()));
+                let byte_val_next_expr = vc.query_advice(*bytes, Rotation::next());
+
+                let leading_byte_is_e0_expr = leading_byte_is_e0_chip.config().expr();
+                let leading_byte_is_ed_expr = leading_byte_is_ed_chip.config().expr();
+                let leading_byte_is_f0_expr = leading_byte_is_f0_chip.config().expr();
+                let leading_byte_is_f4_expr = leading_byte_is_f4_chip.config().expr();
+                // 0xE0: 0xA0..=0xBF, 0xED: 0x80..=0x9F, 0xF0: 0x90..=0xBF, 0xF4: 0x80..=0x8F
+                let min_expr = UTF8_CONTINUATION_BYTE_MIN.expr()
+                    + leading_byte_is_e0_expr.clone() * 0x20.expr()
+                    + leading_byte_is_f0_expr.clone() * 0x10.expr();
+                let len_expr = (UTF8_CONTINUATION_BYTE_MAX - UTF8_CONTINUATION_BYTE_MIN + 1).expr()
+                    - leading_byte_is_e0_expr * 0x20.expr()
+                    - leading_byte_is_ed_expr * 0x20.expr()
+                    - leading_byte_is_f0_expr * 0x10.expr()
+                    - leading_byte_is_f4_expr * 0x30.expr();
+
+                (cond_expr, byte_val_next_expr, min_expr, len_expr)
+            },
+        );
 
         let config = UTF8Config {
             q_enable,
-            // is_first_byte,
-            // is_last_byte,
-            // is_bytes_count_1,
-            // is_bytes_count_2,
-            // is_bytes_count_3,
-            // is_bytes_count_4,
-            // codepoint,
-            // codepoint_recovered,
-            // byte_mul,
+            is_first_byte,
+            is_second_byte,
+            is_third_byte,
+            is_fourth_byte,
+            is_bytes_count_1,
+            is_bytes_count_2,
+            is_bytes_count_3,
+            is_bytes_count_4,
             byte_val_is_zero_chip,
+            leading_byte_is_e0_chip,
+            leading_byte_is_ed_chip,
+            leading_byte_is_f0_chip,
+            leading_byte_is_f4_chip,
             eligible_byte_vals_range_table_config,
             _marker: PhantomData,
         };
@@ -378,21 +327,49 @@ impl<F: Field> UTF8Chip<F>
         config
     }
 
+    /// returns (bytes count, byte position) of `byte_val` following the previously assigned byte.
+    ///
+    /// continuation bytes which don't continue a codepoint are marked up as 1 byte codepoints, so
+    /// they fail the leading byte range check
+    fn compute_char_markup(&self, byte_val: u8) -> (u8, u8) {
+        let is_continuation_byte = byte_val as u64 >= UTF8_CONTINUATION_BYTE_MIN
+            && byte_val as u64 <= UTF8_CONTINUATION_BYTE_MAX;
+        match self.assign_state.borrow().char_markup {
+            Some((bytes_count, byte_pos)) if is_continuation_byte && byte_pos + 1 < bytes_count => {
+                return (bytes_count, byte_pos + 1);
+            }
+            _ => {}
+        }
+        let bytes_count = if byte_val as u64 >= UTF8_4BYTE_LEADING_BYTE_MIN {
+            4
+        } else if byte_val as u64 >= UTF8_3BYTE_LEADING_BYTE_MIN {
+            3
+        } else if byte_val as u64 > UTF8_CONTINUATION_BYTE_MAX {
+            2
+        } else {
+            1
+        };
+        (bytes_count, 0)
+    }
+
     pub fn assign(
         &self,
         region: &mut Region<F>,
         offset: usize,
         q_enable: bool,
         byte_val: u8,
-        // is_first_byte: bool,
-        // is_last_byte: bool,
-        // codepoint: u64,
-        // codepoint_recovered: u64,
-        // byte_mul: u64,
-        // bytes_count: u8,
     ) -> Result<(), Error> {
         self.config.byte_val_is_zero_chip.assign(region, offset, Value::known(F::from(byte_val as u64)))
             .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        for (chip, leading_byte) in [
+            (&self.config.leading_byte_is_e0_chip, UTF8_LEADING_BYTE_E0),
+            (&self.config.leading_byte_is_ed_chip, UTF8_LEADING_BYTE_ED),
+            (&self.config.leading_byte_is_f0_chip, UTF8_LEADING_BYTE_F0),
+            (&self.config.leading_byte_is_f4_chip, UTF8_LEADING_BYTE_F4),
+        ] {
+            chip.assign(region, offset, Value::known(F::from(byte_val as u64) - F::from(leading_byte)))
+                .map_err(remap_error(Error::FatalAssignExternalChip))?;
+        }
 
         region.assign_fixed(
             || format!("assign 'q_enable' to {} at {}", q_enable, offset),
@@ -401,65 +378,30 @@ impl<F: Field> UTF8Chip<F>
             || Value::known(F::from(q_enable as u64)),
         ).map_err(remap_error_to_assign_at(offset))?;
 
-        // region.assign_fixed(
-        //     || format!("assign 'is_first_byte' to {} at {}", is_first_byte, offset),
-        //     self.config.is_first_byte,
-        //     offset,
-        //     || Value::known(F::from(is_first_byte as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        //
-        // region.assign_fixed(
-        //     || format!("assign 'is_last_byte' to {} at {}", is_last_byte, offset),
-        //     self.config.is_last_byte,
-        //     offset,
-        //     || Value::known(F::from(is_last_byte as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        //
-        // region.assign_fixed(
-        //     || format!("assign 'is_bytes_count_1' to {} at {}", bytes_count=1, offset),
-        //     self.config.is_bytes_count_1,
-        //     offset,
-        //     || Value::known(F::from((bytes_count=1) as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        // region.assign_fixed(
-        //     || format!("assign 'is_bytes_count_2' to {} at {}", bytes_count=2, offset),
-        //     self.config.is_bytes_count_2,
-        //     offset,
-        //     || Value::known(F::from((bytes_count=2) as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        // region.assign_fixed(
-        //     || format!("assign 'is_bytes_count_3' to {} at {}", bytes_count=3, offset),
-        //     self.config.is_bytes_count_3,
-        //     offset,
-        //     || Value::known(F::from((bytes_count=3) as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        // region.assign_fixed(
-        //     || format!("assign 'is_bytes_count_4' to {} at {}", bytes_count=4, offset),
-        //     self.config.is_bytes_count_4,
-        //     offset,
-        //     || Value::known(F::from((bytes_count=4) as u64)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        //
-        // region.assign_advice(
-        //     || format!("assign 'codepoint' to {} at {}", codepoint, offset),
-        //     self.config.codepoint,
-        //     offset,
-        //     || Value::known(F::from(codepoint)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        //
-        // region.assign_advice(
-        //     || format!("assign 'codepoint_recovered' to {} at {}", codepoint_recovered, offset),
-        //     self.config.codepoint_recovered,
-        //     offset,
-        //     || Value::known(F::from(codepoint_recovered)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
-        //
-        // region.assign_advice(
-        //     || format!("assign 'byte_mul' to {} at {}", byte_mul, offset),
-        //     self.config.byte_mul,
-        //     offset,
-        //     || Value::known(F::from(byte_mul)),
-        // ).map_err(remap_error_to_assign_at_offset(offset))?;
+        if !q_enable {
+            self.assign_state.borrow_mut().char_markup = None;
+            return Ok(());
+        }
+        let (bytes_count, byte_pos) = self.compute_char_markup(byte_val);
+        self.assign_state.borrow_mut().char_markup = Some((bytes_count, byte_pos));
+
+        for (name, column, value) in [
+            ("is_first_byte", self.config.is_first_byte, byte_pos == 0),
+            ("is_second_byte", self.config.is_second_byte, byte_pos == 1),
+            ("is_third_byte", self.config.is_third_byte, byte_pos == 2),
+            ("is_fourth_byte", self.config.is_fourth_byte, byte_pos == 3),
+            ("is_bytes_count_1", self.config.is_bytes_count_1, bytes_count == 1),
+            ("is_bytes_count_2", self.config.is_bytes_count_2, bytes_count == 2),
+            ("is_bytes_count_3", self.config.is_bytes_count_3, bytes_count == 3),
+            ("is_bytes_count_4", self.config.is_bytes_count_4, bytes_count == 4),
+        ] {
+            region.assign_fixed(
+                || format!("assign '{}' to {} at {}", name, value, offset),
+                column,
+                offset,
+                || Value::known(F::from(value as u64)),
+            ).map_err(remap_error_to_assign_at(offset))?;
+        }
 
         Ok(())
     }
@@ -472,15 +414,16 @@ impl<F: Field> UTF8Chip<F>
         bytecode_offset_start: usize,
         region_offset_start: usize,
     ) -> Result<(), Error> {
+        self.assign_state.borrow_mut().char_markup = None;
         for (offset, bytecode_offset) in (bytecode_offset_start..bytecode_offset_start + bytecode_chunk_len).enumerate() {
             self.assign(
                 region,
                 region_offset_start + offset,
                 true,
-                wb.bytes[bytecode_offset],
+                wb.byte_at(bytecode_offset)?,
             )?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -12,3 +12,19 @@ pub const UTF8_1BYTE_ENCODING_LEADING_BYTE_BIT_MASK: u64 = 0b01111111;
 pub const UTF8_2BYTE_ENCODING_LEADING_BYTE_BIT_MASK: u64 = 0b11011111;
 pub const UTF8_3BYTE_ENCODING_LEADING_BYTE_BIT_MASK: u64 = 0b11101111;
 pub const UTF8_4BYTE_ENCODING_LEADING_BYTE_BIT_MASK: u64 = 0b11110111;
+pub const UTF8_CONTINUATION_BYTE_MIN: u64 = 0x80;
+pub const UTF8_CONTINUATION_BYTE_MAX: u64 = 0xBF;
+/// leading bytes 0xC0 and 0xC1 can only encode overlong 2 byte sequences
+pub const UTF8_2BYTE_LEADING_BYTE_MIN: u64 = 0xC2;
+pub const UTF8_2BYTE_LEADING_BYTE_MAX: u64 = 0xDF;
+pub const UTF8_3BYTE_LEADING_BYTE_MIN: u64 = 0xE0;
+pub const UTF8_3BYTE_LEADING_BYTE_MAX: u64 = 0xEF;
+pub const UTF8_4BYTE_LEADING_BYTE_MIN: u64 = 0xF0;
+/// leading bytes above 0xF4 encode codepoints above `UTF8_CODEPOINT_MAX`
+pub const UTF8_4BYTE_LEADING_BYTE_MAX: u64 = 0xF4;
+/// leading bytes whose second byte has a narrower range than `UTF8_CONTINUATION_BYTE_MIN..=UTF8_CONTINUATION_BYTE_MAX`
+/// (overlong encodings, surrogates and codepoints above `UTF8_CODEPOINT_MAX`)
+pub const UTF8_LEADING_BYTE_E0: u64 = 0xE0;
+pub const UTF8_LEADING_BYTE_ED: u64 = 0xED;
+pub const UTF8_LEADING_BYTE_F0: u64 = 0xF0;
+pub const UTF8_LEADING_BYTE_F4: u64 = 0xF4;
@@ -138,4 +138,37 @@ mod utf8_circuit_tests {
     pub fn test_zero() {
         exact_utf8bytecode(vec![0].as_slice(), 0, false);
     }
+
+    #[test]
+    pub fn test_valid_3byte_char() {
+        assert_eq!("a€b".as_bytes(), &[0x61, 0xE2, 0x82, 0xAC, 0x62]);
+        exact_utf8bytecode("a€b".as_bytes(), 0, true);
+    }
+
+    #[test]
+    pub fn test_valid_multibyte_chars() {
+        exact_utf8bytecode("é€𐍈".as_bytes(), 1, true);
+    }
+
+    #[test]
+    pub fn test_lone_continuation_byte() {
+        exact_utf8bytecode(vec![0x80].as_slice(), 0, false);
+    }
+
+    #[test]
+    pub fn test_truncated_3byte_char() {
+        exact_utf8bytecode(vec![0xE2, 0x82, 0x61].as_slice(), 0, false);
+    }
+
+    #[test]
+    pub fn test_overlong_encodings() {
+        // '/' encoded with 2 and 3 bytes
+        exact_utf8bytecode(vec![0xC0, 0xAF].as_slice(), 0, false);
+        exact_utf8bytecode(vec![0xE0, 0x80, 0xAF].as_slice(), 0, false);
+    }
+
+    #[test]
+    pub fn test_surrogate() {
+        exact_utf8bytecode(vec![0xED, 0xA0, 0x80].as_slice(), 0, false);
+    }
 }
@@ -118,7 +118,7 @@ impl<F: Field> Block<F> {
         let num_rows_required_for_rw_table: usize = self.circuits_params.max_rws;
         let num_rows_required_for_fixed_table: usize = detect_fixed_table_tags(self)
             .iter()
-            .map(|tag| tag.build::<F>().count())
+            .map(|tag| tag.build::<F>(self.circuits_params.wasm_gas_model).count())
             .sum();
         let num_rows_required_for_bytecode_table: usize = self
             .bytecodes
@@ -14,14 +14,9 @@ pub struct Bytecode {
 }
 
 impl Bytecode {
-    /// Assignments for bytecode table
-    pub fn table_assignments<F: Field>(
-        &self,
-        challenges: &Challenges<Value<F>>,
-    ) -> Vec<[Value<F>; 5]> {
-        let n = 1 + self.bytes.len();
-        let mut rows = Vec::with_capacity(n);
-        let hash = if cfg!(feature = "poseidon-codehash") {
+    /// Code hash as it's assigned to the tables keyed on it
+    pub fn hash_value<F: Field>(&self, challenges: &Challenges<Value<F>>) -> Value<F> {
+        if cfg!(feature = "poseidon-codehash") {
             challenges
                 .evm_word()
                 .map(|_challenge| rlc::value(&self.hash.to_le_bytes(), F::from(256u64)))
@@ -30,7 +25,17 @@ impl Bytecode {
             challenges
                 .evm_word()
                 .map(|challenge| rlc::value(&self.hash.to_le_bytes(), challenge))
-        };
+        }
+    }
+
+    /// Assignments for bytecode table
+    pub fn table_assignments<F: Field>(
+        &self,
+        challenges: &Challenges<Value<F>>,
+    ) -> Vec<[Value<F>; 5]> {
+        let n = 1 + self.bytes.len();
+        let mut rows = Vec::with_capacity(n);
+        let hash = self.hash_value(challenges);
 
         rows.push([
             hash,
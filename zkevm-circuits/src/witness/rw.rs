@@ -441,6 +441,13 @@ impl Rw {
         }
     }
 
+    pub fn memory_address(&self) -> u64 {
+        match self {
+            Self::Memory { memory_address, .. } => *memory_address,
+            _ => unreachable!("{:?}", self),
+        }
+    }
+
     // At this moment is a helper for the EVM circuit until EVM challange API is
     // applied
     pub(crate) fn table_assignment_aux<F: Field>(&self, randomness: F) -> RwRow<F> {
@@ -14,11 +14,11 @@ use bus_mapping::{
 use crate::{
     evm_circuit::{
         param::STACK_CAPACITY,
-        step::ExecutionState,
+        step::{wasm_opcode_id_to_execution_state, ExecutionState},
     },
     table::RwTableTag,
 };
-use crate::evm_circuit::param::{N_BYTES_U64, PAGE_SIZE};
+use crate::evm_circuit::param::{N_BYTES_U64, PAGE_SIZE, WASM_PUSH_OPCODES};
 
 /// Step executed in a transaction
 #[derive(Clone, Default, PartialEq, Eq)]
@@ -59,6 +59,8 @@ pub struct ExecStep {
     pub max_stack_height: u32,
     /// Num locals
     pub num_locals: u32,
+    /// Immediate params of the opcode
+    pub params: Vec<u64>,
 }
 
 impl fmt::Debug for ExecStep {
@@ -120,6 +122,11 @@ impl From<&ExecError> for ExecutionState {
                 ExecutionState::ErrorCodeStore
             }
             ExecError::PrecompileFailed => ExecutionState::ErrorPrecompileFailed,
+            ExecError::MemoryOutOfBounds => ExecutionState::WASM_ErrorMemoryOutOfBounds,
+            ExecError::DivisionByZero | ExecError::IntegerOverflow => {
+                ExecutionState::WASM_ErrorDivByZero
+            }
+            ExecError::CallIndirect => ExecutionState::WASM_ErrorCallIndirect,
             ExecError::OutOfGas(oog_error) => match oog_error {
                 OogError::Constant => ExecutionState::ErrorOutOfGasConstant,
                 OogError::StaticMemoryExpansion => {
@@ -147,6 +154,47 @@ impl From<&circuit_input_builder::ExecStep> for ExecutionState {
     fn from(step: &circuit_input_builder::ExecStep) -> Self {
         if let Some(error) = step.error.as_ref() {
             log::debug!("step err {:?}", error);
+            // wasm drop has no invalid stack pointers in the responsible opcode table, so its
+            // underflow can't be proven by `ErrorStack`
+            if let (ExecError::StackUnderflow, circuit_input_builder::ExecState::Op(OpcodeId::Drop)) =
+                (error, &step.exec_state)
+            {
+                return ExecutionState::WASM_ErrorStack;
+            }
+            // wasm pushes are bounded by the configured max stack depth, not the evm stack limit
+            if let (ExecError::StackOverflow, circuit_input_builder::ExecState::Op(op)) =
+                (error, &step.exec_state)
+            {
+                if WASM_PUSH_OPCODES.contains(op) {
+                    return ExecutionState::WASM_ErrorStack;
+                }
+            }
+            // bulk memory opcodes pop three operands and check two ranges
+            if let (
+                ExecError::MemoryOutOfBounds,
+                circuit_input_builder::ExecState::Op(OpcodeId::MemoryCopy | OpcodeId::MemoryFill),
+            ) = (error, &step.exec_state)
+            {
+                return ExecutionState::WASM_ErrorMemoryBulkOutOfBounds;
+            }
+            // `memory.init` also checks the source range against its data segment
+            if let (
+                ExecError::MemoryOutOfBounds,
+                circuit_input_builder::ExecState::Op(OpcodeId::MemoryInit),
+            ) = (error, &step.exec_state)
+            {
+                return ExecutionState::WASM_ErrorMemoryInitOutOfBounds;
+            }
+            // wasm opcodes are charged by the wasm step cost model, not the evm constant gas
+            if let (
+                ExecError::OutOfGas(OogError::Constant),
+                circuit_input_builder::ExecState::Op(op),
+            ) = (error, &step.exec_state)
+            {
+                if !op.is_evm_call() {
+                    return ExecutionState::WASM_ErrorOutOfGas;
+                }
+            }
             return error.into();
         }
         match step.exec_state {
@@ -162,67 +210,11 @@ impl From<&circuit_input_builder::ExecStep> for ExecutionState {
                     }};
                 }
 
-                match op {
-                    // WASM opcodes
-                    OpcodeId::I32Add |
-                    OpcodeId::I64Add |
-                    OpcodeId::I32Sub |
-                    OpcodeId::I64Sub |
-                    OpcodeId::I32Mul |
-                    OpcodeId::I64Mul |
-                    OpcodeId::I32DivS |
-                    OpcodeId::I64DivS |
-                    OpcodeId::I32DivU |
-                    OpcodeId::I64DivU |
-                    OpcodeId::I32RemS |
-                    OpcodeId::I64RemS |
-                    OpcodeId::I32RemU |
-                    OpcodeId::I64RemU => ExecutionState::WASM_BIN,
-
-                    OpcodeId::I32Const |
-                    OpcodeId::I64Const => ExecutionState::WASM_CONST,
-
-                    OpcodeId::Drop => ExecutionState::WASM_DROP,
-
-                    OpcodeId::I32Ctz |
-                    OpcodeId::I64Ctz |
-                    OpcodeId::I32Clz |
-                    OpcodeId::I64Clz |
-                    OpcodeId::I32Popcnt |
-                    OpcodeId::I64Popcnt => ExecutionState::WASM_UNARY,
-
-                    OpcodeId::I32Eqz |
-                    OpcodeId::I64Eqz => ExecutionState::WASM_TEST,
-
-                    OpcodeId::I32WrapI64 |
-                    OpcodeId::I64ExtendSI32 |
-                    OpcodeId::I64ExtendUI32 => ExecutionState::WASM_CONVERSION,
-
-                    OpcodeId::GetGlobal |
-                    OpcodeId::SetGlobal => ExecutionState::WASM_GLOBAL,
-
-                    OpcodeId::GetLocal |
-                    OpcodeId::SetLocal |
-                    OpcodeId::TeeLocal => ExecutionState::WASM_LOCAL,
-
-                    OpcodeId::Call |
-                    OpcodeId::CallIndirect => ExecutionState::WASM_CALL,
-
-                    OpcodeId::Return |
-                    OpcodeId::Br |
-                    OpcodeId::BrIf |
-                    OpcodeId::BrTable => ExecutionState::WASM_BREAK,
-
-                    OpcodeId::End => ExecutionState::WASM_END,
-
-                    OpcodeId::Select => ExecutionState::WASM_SELECT,
-
-                    OpcodeId::I32GtU | OpcodeId::I32GeU | OpcodeId::I32LtU | OpcodeId::I32LeU |
-                    OpcodeId::I32Eq | OpcodeId::I32Ne | OpcodeId::I32GtS | OpcodeId::I32GeS | OpcodeId::I32LtS |
-                    OpcodeId::I32LeS | OpcodeId::I64GtU | OpcodeId::I64GeU | OpcodeId::I64LtU | OpcodeId::I64LeU |
-                    OpcodeId::I64Eq | OpcodeId::I64Ne | OpcodeId::I64GtS | OpcodeId::I64GeS | OpcodeId::I64LtS |
-                    OpcodeId::I64LeS => ExecutionState::WASM_REL,
+                if let Some(state) = wasm_opcode_id_to_execution_state(op) {
+                    return state;
+                }
 
+                match op {
                     // EVM opcodes
                     OpcodeId::ADDMOD => ExecutionState::ADDMOD,
                     OpcodeId::ADDRESS => ExecutionState::ADDRESS,
@@ -353,5 +345,6 @@ pub(super) fn step_convert(step: &circuit_input_builder::ExecStep, block_num: u6
         function_index: step.function_index,
         max_stack_height: step.function_index,
         num_locals: step.num_locals,
+        params: step.params.clone(),
     }
 }